@@ -0,0 +1,695 @@
+# English UI strings, which are also used for any message a translation leaves out.
+# To translate Avio, copy this file to the `locales` folder next to the settings as
+# `<language code>.ftl` (e.g. `de.ftl`) and translate the values; `{ $name }` placeholders
+# are filled in by the player.
+
+language-name = English
+
+## Start screen and playback
+osd-integrated-loudness = Integrated loudness: { $value }
+track-number = Track { $track }
+osd-speed-interpolated = Speed { $speed }x (interpolated)
+osd-speed = Speed { $speed }x
+osd-pitch = Pitch { $semitones } semitones
+osd-loop-start = Loop from { $time }, press B again at the end
+osd-loop = Looping { $start } – { $end }
+osd-loop-pass = Pass { $pass } of { $repeats } ({ $speed }x)
+osd-loop-finished = Loop finished
+osd-loop-cleared = Loop cleared
+osd-loop-too-short = Loop too short, move on before setting B
+osd-mark-in = In: { $time }
+osd-mark-out = Out: { $time }
+osd-marker-added = Added { $name }
+osd-stop-after-file-on = Stopping after this file
+osd-stop-after-file-off = Playing on after this file
+osd-stopped-after-file = Stopped after the file
+osd-sleep-timer = Sleep timer: { $time }
+osd-sleep-timer-off = Sleep timer off
+osd-sleep-timer-paused = Paused by the sleep timer
+osd-sleep-countdown = { $action } in { $seconds } s
+osd-muted = Muted
+osd-volume = Volume { $percent }%
+osd-zoom = Zoom { $percent }%
+osd-window-size = Window size { $percent }%
+osd-screenshot-saved = Screenshot: { $file }
+osd-screenshot-failed = Screenshot failed: { $error }
+osd-recording-started = Recording
+osd-recording-saved = Recording saved: { $file }
+osd-live-no-timeshift = Turn on timeshift to rewind live streams
+osd-interpolation-on = Slow motion interpolation on
+osd-interpolation-off = Slow motion interpolation off
+osd-night-mode-on = Night mode on
+osd-output-device = Playing on { $device }
+osd-secondary-audio = Mixing in audio stream { $index }
+osd-secondary-audio-off = Secondary audio off
+osd-night-mode-off = Night mode off
+osd-no-frame-to-save = No video frame to save
+osd-captions-on = Closed captions on
+osd-captions-off = Closed captions off
+osd-subtitle-position = Subtitle position: { $percent }%
+osd-report-saved = Report saved
+osd-ocr-loaded = Recognized subtitles loaded
+osd-ocr-saved = Subtitles saved
+osd-ocr-save-failed = Failed to save subtitles: { $error }
+osd-report-failed = Failed to save report: { $error }
+osd-sponsorblock-segments = SponsorBlock: { $count } segments
+osd-skipped-segment = Skipped { $name }
+osd-cache-cleared = Cache cleared, { $size } freed
+osd-audio-resynced = Audio resynced
+osd-audio-delay = Audio delay: { $ms } ms
+osd-preset = Preset: { $name }
+osd-preset-off = Preset off
+skip-segment-button = Skip { $name } ⏭
+start-hint = Select a video or audio file to start playing
+start-open-file = Open File
+start-live-buffering = Buffering live stream… { $seconds } s
+start-web-resolving = Opening { $url }…
+start-cancel = Cancel
+start-recovery = Avio did not close properly while playing { $file } at { $time }.
+start-recovery-continue = Continue
+start-recovery-dismiss = Dismiss
+start-open-folder = Open Folder
+start-join-files = Join Files
+start-join-files-hint = Play several files back to back as one, e.g. a recording split by the camera
+start-video-wall = Video Wall
+up-next = Up next
+up-next-audio = { $count } audio
+up-next-subtitles = { $count } subtitles
+start-open-url = Open URL
+video-window-placeholder = The video is playing in its own window
+video-window-return = Show video here
+start-register-file-types = Open media files with Avio
+
+## Menus
+stream-label = Stream { $index }: { $description }
+stream-kind-video = Video
+stream-kind-audio = Audio
+menu-video-stream = Video stream
+menu-image-subtitles = Image subtitles
+menu-ocr-subtitles = Convert to text...
+menu-off = Off
+menu-closed-captions = Closed captions (CC1)
+menu-no-closed-captions = No CEA-608 captions found in this video
+menu-filmstrip = Filmstrip
+menu-pixel-inspector = Pixel inspector
+menu-loudness-meter = Loudness meter
+menu-level-meters = Channel levels
+menu-spherical-view = 360° view
+menu-field-of-view = Field of view
+menu-reset-view = Reset view
+menu-video-window = Separate video window
+menu-video-window-hint = Show the picture in a window of its own, e.g. on a second monitor
+menu-window-size = Window size:
+menu-window-size-hint = Resize the window so the video shows at this share of its pixel size; 100% shows every pixel as one screen pixel
+menu-raw-stream = Stream format…
+menu-dvd-titles = DVD titles
+menu-dvd-title = Title { $number } ({ $duration }, { $chapters } chapters)
+menu-screenshot = Screenshot
+menu-screenshot-frame = Video frame
+menu-screenshot-displayed = As displayed
+menu-screenshot-displayed-hint = Includes subtitles, shaders and on-screen messages
+menu-screenshot-options = Screenshot options
+menu-screenshot-folder = Folder:
+menu-screenshot-choose-folder = Choose folder...
+menu-screenshot-name = File name:
+menu-screenshot-name-hint = %f is the file name, %t the position, %n a counter and %% a percent sign
+menu-screenshot-format = Format:
+menu-screenshot-quality = JPEG quality:
+menu-filters = Filters
+menu-denoise = Denoise (hqdn3d)
+menu-deband = Deband
+menu-scaling = Scaling
+menu-color-conversion = Color conversion:
+menu-color-conversion-hint = swscale filter used for the chroma planes
+menu-display-sampling = Display:
+menu-display-sampling-hint = GPU sampling when the video is resized to the window
+menu-display-mode = Shape:
+menu-display-mode-hint = Fit shows the whole picture with black bars, Fill crops it to cover the window, Stretch distorts it to cover the window
+menu-speed = Speed
+menu-interpolate-slow-motion = Interpolate slow motion
+menu-interpolate-slow-motion-hint = Synthesizes in-between frames below 1x; needs a fast CPU
+menu-shader = Shader
+menu-none = None
+menu-load-shader = Load shader...
+menu-compare = Compare
+menu-compare-with-file = With { $file }
+menu-wipe = Wipe
+menu-close-comparison = Close comparison
+menu-video-wall = Video wall
+menu-open-video-wall = Open several files…
+menu-open-video-wall-hint = Play several files at once in a grid with shared controls, e.g. recordings from several cameras
+menu-close-video-wall = Close video wall
+video-wall-play-sound = Play the sound of this video
+menu-compare-with = Compare with...
+menu-appearance = Appearance
+menu-presets = Presets
+menu-preset-patterns = Applied to { $patterns }
+menu-edit-presets = Edit presets...
+menu-frame-queue = Frames decoded ahead:
+menu-frame-queue-hint = Frames kept ready to show while reading stalls, e.g. on a network share. Each takes a full uncompressed frame of memory. Applies to files opened afterwards.
+menu-read-ahead = Read-ahead:
+menu-read-ahead-hint = How much of the file is read ahead of decoding in the background. Applies to files opened afterwards.
+menu-no-chapters = No chapters
+menu-load-chapters = Load chapters file...
+menu-snap-to-chapters = Snap seek bar to chapters
+menu-snap-to-chapters-hint = Clicking the seek bar within a few pixels of where a chapter starts or ends seeks exactly there
+menu-detect-scenes = Detect scenes...
+menu-skip-segments = Skip segments...
+menu-cutlist = Marks and markers...
+menu-annotations = Notes...
+menu-channels = Channels
+menu-downmix-stereo = Stereo
+menu-downmix-mono = Mono
+menu-downmix-source = Source layout
+menu-audio-output = Output
+menu-audio-output-cpal = cpal (low latency)
+menu-ab-loop = A-B loop
+menu-ab-loop-set-a = Set A
+menu-ab-loop-set-b = Set B
+menu-ab-loop-clear = Clear
+menu-ab-loop-repeats = Repeats:
+menu-ab-loop-repeats-hint = Passes to play before playback continues past B
+menu-ab-loop-forever = ∞
+menu-ab-loop-ramp = Speed up with each pass
+menu-ab-loop-ramp-hint = Practice mode: the first pass plays slowly and the last one at the normal speed
+menu-ab-loop-ramp-start = Starting speed:
+menu-stop-after-file = Stop after this file
+menu-sleep-timer = Sleep timer
+menu-sleep-timer-remaining = { $time } ({ $remaining } left)
+menu-sleep-action = When the timer runs out:
+sleep-timer-minutes = { $minutes } min
+sleep-timer-end-of-file = End of this file
+sleep-action-pause = Pause
+sleep-action-quit = Quit
+menu-end-of-playlist = At the end of the playlist:
+end-of-playlist-stay = Stay open
+end-of-playlist-close = Close the file
+end-of-playlist-quit = Quit
+end-of-playlist-suspend = Put the computer to sleep
+menu-audio-delay = Audio delay
+menu-audio-delay-ms = { $ms } ms
+menu-measure-sync = Measure sync...
+menu-pitch = Pitch
+menu-pitch-semitones = { $semitones } semitones
+menu-keep-pitch = Keep pitch when changing speed
+menu-keep-pitch-hint = Faster or slower playback sounds at its original pitch instead of higher or lower
+menu-night-mode = Night mode
+menu-output-device = Output device
+menu-output-device-default = System default
+menu-output-device-default-named = System default ({ $device })
+menu-audio-profiles = Remember per device and monitor
+menu-audio-profiles-hint = Restores the volume, channels and output used last on each device, and the device picked on each monitor
+menu-mix-in = Mix in
+menu-mix-main = Main
+menu-mix-secondary = Mixed in
+menu-mix-balance-hint = Both play at full level in the middle. Double-click to center.
+menu-night-mode-hint = Turns loud scenes down and quiet dialog up, for watching at low volume
+menu-night-mode-strength = Strength:
+menu-audible-scrubbing = Audible scrubbing
+menu-audible-scrubbing-hint = Dragging the seek bar plays short snippets of the sound under it
+menu-audio-memory = Memory for decoded audio:
+menu-audio-memory-hint = Audio that would take more is decoded while it plays instead of up front. Applies to files opened afterwards.
+menu-theme = Theme
+menu-accent-color = Accent color
+stream-kind-subtitles = Subtitles
+scope-histogram = Luma histogram
+scope-parade = RGB parade
+scope-vectorscope = Vectorscope
+theme-dark = Dark
+theme-light = Light
+theme-system = System
+sampler-nearest = Nearest
+sampler-linear = Linear
+sampler-mipmapped = Linear + mipmaps
+display-mode-fit = Fit
+display-mode-fill = Fill
+display-mode-stretch = Stretch
+menu-language = Language
+menu-fullscreen-this-display = Fullscreen on this display
+menu-fullscreen-this-display-hint = Fullscreen always uses the display the window is on now
+menu-click-to-pause = Click the picture to play or pause
+menu-live-streams = Live streams
+menu-live-latency = Delay behind live:
+menu-live-catch-up = Catch up:
+menu-timeshift = Timeshift buffer on disk
+menu-timeshift-hint = Lets live streams be paused and rewound. Applies to streams opened afterwards.
+menu-tray-icon = Show icon in the system tray
+menu-tray-icon-unsupported = The tray icon is not available on this system
+menu-minimize-to-tray = Minimize to tray
+menu-security = Untrusted files
+menu-restrict-protocols = Restrict protocols
+menu-restrict-protocols-hint = Local files may only read local files, and URLs only network streams, so a downloaded playlist can't fetch URLs or files outside its folder and a remote one can't read your files.
+menu-block-expanding-formats = Refuse concat lists and FFmpeg metadata files
+menu-block-expanding-formats-hint = These formats play other files or URLs listed inside them.
+menu-autoload-sidecar-files = Load chapter files and linked segments automatically
+menu-autoload-sidecar-files-hint = CUE sheets and FFmpeg metadata files next to the file, and Matroska segments in the same folder that an ordered edition links to.
+menu-storage = Storage…
+menu-stats-log = Log playback statistics
+menu-stats-log-hint = Writes late frames, decode times, audio underruns and sync corrections to a file in the cache folder, to attach to performance reports
+menu-stats-log-path = Logging to { $path }
+menu-crash-recovery = Offer to continue after a crash
+menu-crash-recovery-hint = Notes the file, position and playlist every few seconds, so that the next start can pick up where playback stopped
+menu-diagnostics = Diagnostics…
+
+## Live streams
+live-position = { $time } · LIVE −{ $latency } s
+catch-up-off = Off
+catch-up-speed-up = Speed up
+catch-up-skip = Skip ahead
+
+## Tray icon menu
+
+tray-play-pause = Play / Pause
+tray-next = Next
+tray-show = Show Avio
+tray-quit = Quit
+
+## Buttons
+button-reset = Reset
+button-search = Search
+button-subtitle-style = Style…
+button-close = Close
+button-download = Download
+button-start = Start
+button-go = Go
+button-cancel = Cancel
+button-ok = OK
+button-paste = Paste
+button-open = Open
+button-choose = Choose...
+button-export = Export
+button-resume = Resume
+button-pause = Pause
+button-import = Import...
+button-export-file = Export...
+button-save = Save
+
+## Control names read by screen readers
+control-play = Play
+control-pause = Pause
+control-back = Back 10 seconds
+control-forward = Forward 10 seconds
+control-open = Open file
+control-media-info = Media information
+control-search-subtitles = Search subtitles
+control-chapters = Chapters
+control-video = Video settings
+control-audio = Audio settings
+control-shuffle = Shuffle
+control-time-display = Click to switch between elapsed, remaining, timecode and frame number
+time-frame = Frame { $frame } / { $total }
+control-sleep-timer = Sleep timer
+control-record = Record stream
+control-stop-recording = Stop recording ({ $time })
+control-fullscreen = Fullscreen
+control-mute = Mute
+control-unmute = Unmute
+control-volume = Volume
+control-seek = Seek
+control-speed-down = Slower
+control-speed-up = Faster
+control-pitch-down = Lower pitch
+control-pitch-up = Higher pitch
+control-audio-delay-down = Play audio earlier
+control-audio-delay-up = Play audio later
+control-av-offset = A/V: { $offset } ms
+control-av-resync = Audio and video are out of sync. Click to resync (Y).
+playback-state-playing = Playing, { $position } of { $duration }
+playback-state-paused = Paused, { $position } of { $duration }
+
+## File dialogs
+filter-chapters = Chapters
+filter-all-files = All files
+filter-media-files = Media files
+filter-playlists = Playlists
+filter-dvd-images = DVD images
+filter-m3u8-playlist = M3U8 playlist
+filter-shaders = Shaders
+filter-fonts = Fonts
+filter-text = Text
+filter-edl = Edit decision list
+filter-skip-segments = Skip segments
+filter-cookies = Cookies
+
+## Errors
+error-cannot-open = Cannot open { $file }
+error-join-files = Cannot join the files
+error-open-linked-segment = Cannot open linked segment
+error-load-chapters = Cannot load chapters
+error-file-associations = Cannot update file associations
+error-open-comparison = Cannot open comparison video
+error-switch-audio-output = Cannot switch audio output
+error-reload-audio = Cannot reload audio
+error-secondary-audio = Cannot mix in the audio stream
+error-load-subtitle-font = Cannot load subtitle font
+error-invalid-time = Invalid time format
+error-end-before-start = The end time must be after the start time
+error-cannot-import = Cannot import: { $error }
+error-cannot-export = Cannot export: { $error }
+error-recording = Recording failed
+
+## Media information
+info-path = Path:
+info-format = Format:
+info-duration = Duration:
+info-overall-bitrate = Overall Bitrate:
+info-resolution = Resolution:
+info-codec = Codec:
+info-codec-id = Codec ID:
+info-frame-rate = Frame Rate:
+info-pixel-format = Pixel Format:
+info-bitrate = Bitrate:
+info-frame-count = Frame Count:
+info-aspect-ratio = Aspect Ratio:
+info-color-space = Color Space:
+info-projection = Projection:
+info-time-base = Time Base:
+info-disposition = Disposition:
+info-codec-capabilities = Codec Capabilities:
+info-codec-profiles = Codec Profiles:
+info-video-stream-metadata = Video Stream Metadata
+info-sample-rate = Sample Rate:
+info-channels = Channels:
+info-sample-format = Sample Format:
+info-channel-layout = Channel Layout:
+info-profile = Profile:
+info-audio-stream-metadata = Audio Stream Metadata
+info-language = Language:
+info-subtitle-stream-metadata = Subtitle Stream Metadata
+info-type = Type:
+info-other-stream-metadata = Other Stream Metadata
+info-start = Start:
+info-end = End:
+info-chapter-metadata = Chapter Metadata
+info-unknown = Unknown
+info-measured = measured { $bitrate }
+info-file-information = File Information
+info-measure-bitrates = Measure bitrates
+info-measure-bitrates-hint = Reads the whole file to compute the average bitrates
+info-local-only = Only available for local files
+info-measuring-bitrates = Measuring bitrates...
+info-measuring-failed = Measuring failed: { $error }
+info-tags = Tags
+info-tag-title = Title:
+info-tag-artist = Artist:
+info-tag-album = Album:
+info-tag-track = Track:
+info-tag-year = Year:
+info-video-streams = Video Streams
+info-stream-heading = Stream { $number } (Index: { $index })
+info-equirectangular = Equirectangular (360°)
+info-audio-streams = Audio Streams
+info-subtitle-streams = Subtitle Streams
+info-other-streams = Other Streams
+info-chapters = Chapters
+info-global-metadata = Global Metadata
+info-unavailable = No media information available
+info-chapter-heading = Chapter { $index }: { $title }
+
+## Subtitles
+subtitle-search-searching = Searching...
+subtitle-search-downloading = Downloading { $file }...
+subtitle-search-no-results = No subtitles found
+subtitle-search-loaded = Loaded { $file }
+subtitle-search-other-file = Saved { $file }, not loaded as another file is open now
+subtitle-search-load-failed = Error loading subtitles: { $error }
+subtitle-search-error = Error: { $error }
+subtitle-search-api-key = OpenSubtitles API key:
+subtitle-search-language = Language:
+subtitle-search-downloads = { $count } downloads
+subtitle-style-font = Font:
+subtitle-style-font-default = Default
+subtitle-style-font-monospace = Monospace
+subtitle-style-font-file = Font file…
+subtitle-style-size = Size:
+subtitle-style-color = Color:
+subtitle-style-outline = Outline:
+subtitle-style-position = Position:
+subtitle-style-position-value = { $percent }% from bottom
+subtitle-style-background = Background:
+subtitle-style-opacity = opacity
+subtitle-style-fallback-fonts = Fallback fonts:
+subtitle-style-add-font = Add font…
+subtitle-style-system-fonts = Use system fonts for CJK, Hebrew and Arabic
+subtitle-style-system-fonts-hint = Fills in characters the fonts above lack with fonts found on this computer
+subtitle-style-attached-fonts = { $count } fonts attached to this file
+
+## Analysis
+analysis-no-file = Open a file to analyze it.
+analysis-bitrate = Bitrate
+analysis-bitrate-description = Reads all packets to plot how the bitrate changes over time.
+analysis-scan-bitrate = Scan bitrate
+analysis-show-under-seek-bar = Show under seek bar
+analysis-scan-again = Scan again
+analysis-gop = GOP structure
+analysis-gop-no-video = Open a video to analyze its GOP structure.
+analysis-analyze-gop = Analyze GOP structure
+analysis-analyze-again = Analyze again
+analysis-gop-description = Decodes the video stream to find its keyframes and frame types. Long gaps between keyframes make seeking slow.
+gop-decoding = Decoding the video stream...
+gop-failed = Analysis failed: { $error }
+gop-no-frames = No frames were decoded
+gop-frames = Frames:
+gop-frames-of-type = { $kind } frames:
+gop-keyframes = Keyframes:
+gop-length = GOP length:
+gop-length-value = { $average } frames on average ({ $min } to { $max })
+gop-longest-gap = Longest keyframe gap:
+gop-frame-types-per-second = Frame types per second:
+bitrate-reading = Reading packets...
+bitrate-failed = Scan failed: { $error }
+bitrate-no-packets = The stream has no packets
+bitrate-axis-seconds = Seconds
+
+## Verify file
+verify-no-file = Open a file to verify it.
+verify-local-only = Only local files can be verified.
+verify-start = Start check
+verify-save-report = Save report...
+verify-check-again = Check again
+verify-description = Decodes every packet of every stream and lists the decode errors with their timestamps. This takes about as long as transcoding.
+verify-decoding = Decoding all streams...
+verify-failed = Check failed: { $error }
+verify-no-errors = No errors found
+verify-errors-found = { $count } errors found
+verify-column-stream = Stream
+verify-column-packets = Packets
+verify-column-frames = Frames
+verify-column-errors = Errors
+verify-not-decoded = Not decoded: { $reason }
+verify-click-to-jump = Click an error to jump to it:
+
+## Scene detection
+scenes-no-video = Open a video to detect its scenes.
+scenes-local-only = Scenes can only be detected in single local files.
+scenes-threshold = Threshold
+scenes-threshold-hint = Mean luma difference between frames; lower finds more cuts
+scenes-detect-again = Detect again
+scenes-use-as-chapters = Use as chapters
+scenes-description = Decodes the whole video and marks a scene wherever consecutive frames differ a lot. Files without chapters get the scenes as chapters.
+scenes-use-as-chapters-hint = Replaces the { $count } chapters of this file
+scenes-chapter-title = Scene { $number }
+scenes-decoding = Decoding the video...
+scenes-failed = Scene detection failed: { $error }
+scenes-found = { $count } scenes found
+ocr-no-streams = This file has no PGS or VobSub subtitles.
+ocr-description = Reads every image of the stream with Tesseract, which has to be installed with the data of the language. The text can then be styled like any subtitle file or saved as SRT.
+ocr-stream = Stream
+ocr-language = Language
+ocr-language-hint = Tesseract language codes, joined with + for several: eng, deu, fra, eng+jpn
+ocr-load = Use as subtitles
+ocr-load-hint = Shows the text instead of the images
+ocr-save = Save as SRT...
+ocr-running = Recognizing text... { $count } lines so far
+ocr-failed = Text recognition failed: { $error }
+ocr-finished = { $count } lines recognized
+sync-no-streams = Open a file with both video and audio to measure its sync.
+sync-local-only = Sync can only be measured in single local files.
+sync-description = Lines up flashes and cuts in the first minutes of the picture with claps and other sudden sounds, and suggests an audio delay that makes them meet.
+sync-measure-again = Measure again
+sync-apply = Apply delay of { $ms } ms
+sync-analyzing = Analyzing picture and sound...
+sync-failed = Measuring sync failed: { $error }
+sync-too-short = The file is too short to measure
+sync-in-sync = Sound and picture are in sync
+sync-audio-early = The sound comes { $ms } ms early
+sync-audio-late = The sound comes { $ms } ms late
+sync-confidence = Confidence: { $score }
+presets-description = A preset sets the ticked values for the file playing; the next file gets the usual settings back, or the preset its name matches.
+presets-name = Name
+presets-patterns = File names
+presets-patterns-hint = One pattern per line, * for any text and ? for one character, ignoring case
+presets-apply = Apply
+presets-update = Take current values
+presets-update-hint = Replaces the ticked values with the ones in use now
+presets-delete = Delete
+presets-new = New preset from current settings
+presets-new-name = New preset
+presets-on = On
+sync-unreliable = Few clear events to line up, so the estimate may be off. Check it by ear before keeping it.
+
+## Go to time and Open URL
+go-to-time-hint = Enter a time as hh:mm:ss.mmm, mm:ss or seconds
+open-url-hint = Enter the address of a stream or remote file
+open-url-empty = Enter a URL
+open-url-spaces = URLs cannot contain spaces
+open-url-invalid = Not a URL, expected something like https://host/path
+open-url-no-host = The URL has no host
+open-url-http-options = HTTP options
+
+## Raw streams
+raw-stream-hint = This file is a raw stream without a container. Choose how to read it:
+raw-stream-format = Format
+raw-stream-framerate = Frame rate
+http-user-agent = User agent
+http-headers = Headers
+http-cookies-file = Cookies file
+http-username = User name
+http-password = Password
+go-to-time-past-end = Time is past the end of the file ({ $duration })
+
+## Export frames
+export-invalid-frame-rate = Invalid frame rate
+export-choose-folder = Choose an output folder
+export-frame-rate = Frame rate:
+export-every-frame = every frame
+export-format = Format:
+export-output-folder = Output folder:
+export-progress = { $count } frames exported
+
+## Transcode
+label-start = Start:
+label-end = End:
+transcode-invalid-bitrate = Invalid video bitrate
+transcode-choose-file = Choose an output file
+transcode-video-codec = Video codec:
+transcode-quality = Quality:
+transcode-bitrate = Bitrate
+transcode-crf-hint = lower is better
+transcode-video-bitrate = Video bitrate:
+transcode-resolution = Resolution:
+transcode-original-resolution = Original
+transcode-audio-codec = Audio codec:
+transcode-audio-bitrate = Audio bitrate:
+transcode-output-file = Output file:
+transcode-done = Done
+transcode-paused = Paused
+
+## Remux
+remux-no-streams = Select at least one stream
+remux-same-file = Choose a different file than the source
+remux-description = Copies the streams into a new container without re-encoding.
+remux-container = Container:
+remux-saved = Saved to { $file }
+remux-start = Remux...
+
+## Skip segments and SponsorBlock
+skip-segment-default-name = segment
+skip-segment-invalid-start = Invalid start time for { $name }
+skip-segment-invalid-end = Invalid end time for { $name }
+skip-segment-ends-before-start = { $name } ends before it starts
+skip-segments-description = Parts of this file that playback skips automatically or offers to skip.
+skip-segments-name = Name
+skip-segments-start = Start
+skip-segments-end = End
+skip-segments-skip = Skip
+skip-segments-now = Now
+skip-segments-now-hint = Use the current position
+skip-segments-automatically = Automatically
+skip-segments-ask = Ask
+skip-segments-remove = Remove
+skip-segments-add = Add segment
+skip-segments-new-name = Intro
+sponsor-category-sponsor = Sponsor
+sponsor-category-selfpromo = Self-promotion
+sponsor-category-interaction = Interaction reminder
+sponsor-category-intro = Intro
+sponsor-category-outro = Outro
+sponsor-category-preview = Preview / recap
+sponsor-category-music-offtopic = Non-music section
+sponsor-category-filler = Filler
+sponsor-action-ignore = Ignore
+sponsor-action-mark = Mark
+sponsor-action-skip = Skip
+
+## Loudness meter
+loudness-coverage = { $percent }% of the file measured
+
+## Storage
+storage-config-folder = Settings folder
+storage-cache-folder = Cache folder
+storage-no-folder = Not available on this system
+storage-total = Total
+storage-cache-limit = Cache limit:
+storage-cache-limit-hint = The oldest cached files are deleted when the cache grows past this size. 0 means no limit.
+storage-clear-cache = Clear cache
+
+## Diagnostics
+diagnostics-copy = Copy to clipboard
+diagnostics-collecting = Probing devices…
+diagnostics-version = Version
+diagnostics-os = System
+diagnostics-license = License
+diagnostics-configuration = Build configuration
+diagnostics-hwaccel = Hardware acceleration
+diagnostics-available = Available
+diagnostics-unavailable = Not available
+diagnostics-none = None
+diagnostics-decoders = Decoders
+diagnostics-decoders-video = Video
+diagnostics-decoders-audio = Audio
+diagnostics-decoders-subtitle = Subtitles
+diagnostics-decoders-hardware = Hardware
+diagnostics-codec-test = Codec support
+codec-test-missing = Not in this build
+codec-test-found = found; opens with the file's headers
+diagnostics-audio = Audio
+diagnostics-audio-output = Output in use
+diagnostics-audio-host = { $host } devices
+diagnostics-default-device = { $device } (default)
+diagnostics-gpu = GPU
+diagnostics-gpu-vendor = Vendor
+diagnostics-gpu-renderer = Renderer
+diagnostics-gpu-version = OpenGL version
+diagnostics-gpu-glsl = GLSL version
+cache-subtitles = Downloaded subtitles
+cache-timeshift = Timeshift buffers
+cache-stats-logs = Playback statistics
+
+## Windows
+window-subtitle-search = Subtitle Search
+window-subtitle-style = Subtitle Style
+window-analysis = Analysis
+window-verify-file = Verify File
+window-scene-detection = Scene Detection
+window-subtitle-ocr = Subtitle Text Recognition
+window-sync-analysis = Measure Sync
+window-presets = Presets
+window-go-to-time = Go to Time
+window-open-url = Open URL
+window-raw-stream = Open Raw Stream
+window-export-frames = Export Frames
+window-transcode = Transcode
+window-remux = Remux
+window-skip-segments = Skip Segments
+window-cutlist = Marks and Markers
+window-annotations = Notes
+annotations-hint = Write a note and press Enter
+annotations-add = Add
+annotations-empty = No notes on this file yet.
+annotations-remove = Remove note
+annotations-export = Export:
+cutlist-no-file = Open a file to mark it.
+cutlist-description = The part between In and Out is exported, split at the markers inside it.
+cutlist-in = In
+cutlist-out = Out
+cutlist-now = Now
+cutlist-marker-name = Marker { $number }
+cutlist-add-marker = Add marker
+cutlist-remove-marker = Remove marker
+cutlist-export-edl = Export EDL...
+cutlist-export-ffmpeg = Export FFmpeg commands...
+cutlist-empty-range = Out is before In, so there is nothing to export.
+window-media-information = Media Information
+window-storage = Storage
+window-diagnostics = Diagnostics
@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+
+/// Shorter loops are refused, as they would go back to A on every frame.
+pub const MIN_LOOP_MS: i64 = 100;
+
+/// How an A-B loop repeats.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AbLoopSettings {
+    /// Passes to play before playback goes on past B, or 0 to loop until cleared.
+    pub repeats: u32,
+    /// Start slow and speed up with each pass, reaching the normal speed on the last one.
+    pub ramp_speed: bool,
+    /// Speed of the first pass when ramping, relative to the normal speed.
+    pub ramp_start: f64,
+}
+
+impl Default for AbLoopSettings {
+    fn default() -> Self {
+        Self {
+            repeats: 0,
+            ramp_speed: false,
+            ramp_start: 0.5,
+        }
+    }
+}
+
+pub enum LoopEvent {
+    /// Playback passed B and goes back to A for another pass.
+    Repeat,
+    /// The last pass ended, so playback goes on.
+    Finished,
+}
+
+/// A part of the file played over and over, from the point A to the point B.
+#[derive(Debug, Clone)]
+pub struct AbLoop {
+    pub start_ms: i64,
+    /// B, or `None` while only A is set.
+    pub end_ms: Option<i64>,
+    /// Passes finished so far.
+    played: u32,
+    /// Whether playback has been seen between A and B since the last jump back, so that a
+    /// position that hasn't caught up with the seek yet doesn't count as another pass.
+    armed: bool,
+}
+
+impl AbLoop {
+    pub fn new(start_ms: i64) -> Self {
+        Self {
+            start_ms,
+            end_ms: None,
+            played: 0,
+            armed: false,
+        }
+    }
+
+    /// Sets B, swapping the points when it lies before A. Returns false and leaves B unset when
+    /// the loop would be shorter than `MIN_LOOP_MS`.
+    pub fn set_end(&mut self, end_ms: i64) -> bool {
+        if (end_ms - self.start_ms).abs() < MIN_LOOP_MS {
+            return false;
+        }
+        if end_ms < self.start_ms {
+            self.end_ms = Some(self.start_ms);
+            self.start_ms = end_ms;
+        } else {
+            self.end_ms = Some(end_ms);
+        }
+        self.armed = false;
+        true
+    }
+
+    /// The pass playing now, counted from 1.
+    pub fn pass(&self) -> u32 {
+        self.played + 1
+    }
+
+    /// The speed of the current pass relative to the normal speed.
+    pub fn speed_factor(&self, settings: &AbLoopSettings) -> f64 {
+        if !settings.ramp_speed || settings.repeats < 2 || self.end_ms.is_none() {
+            return 1.0;
+        }
+        let progress = self.played.min(settings.repeats - 1) as f64 / (settings.repeats - 1) as f64;
+        settings.ramp_start + (1.0 - settings.ramp_start) * progress
+    }
+
+    /// Counts a pass when playback at `position_ms` has reached B.
+    pub fn update(&mut self, position_ms: i64, settings: &AbLoopSettings) -> Option<LoopEvent> {
+        let end_ms = self.end_ms?;
+        if position_ms > self.start_ms && position_ms < end_ms {
+            self.armed = true;
+        }
+        if !self.armed || position_ms < end_ms {
+            return None;
+        }
+        self.armed = false;
+        self.played += 1;
+        if settings.repeats > 0 && self.played >= settings.repeats {
+            Some(LoopEvent::Finished)
+        } else {
+            Some(LoopEvent::Repeat)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn end_before_start_swaps_the_points() {
+        let mut ab_loop = AbLoop::new(5_000);
+        assert!(ab_loop.set_end(2_000));
+        assert_eq!(ab_loop.start_ms, 2_000);
+        assert_eq!(ab_loop.end_ms, Some(5_000));
+    }
+
+    #[test]
+    fn refuses_loops_shorter_than_the_minimum() {
+        let mut ab_loop = AbLoop::new(5_000);
+        assert!(!ab_loop.set_end(5_000 + MIN_LOOP_MS - 1));
+        assert!(!ab_loop.set_end(5_000 - MIN_LOOP_MS + 1));
+        assert_eq!(ab_loop.end_ms, None);
+        assert!(ab_loop.set_end(5_000 + MIN_LOOP_MS));
+        assert_eq!(ab_loop.end_ms, Some(5_000 + MIN_LOOP_MS));
+    }
+}
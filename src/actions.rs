@@ -0,0 +1,357 @@
+use crate::audio::{AudioBackend, Downmix};
+use crate::scopes::Scope;
+use crate::shaders::{DisplayMode, Sampler};
+use crate::video::ScalingAlgorithm;
+use eframe::egui::{self, Key, Modifiers};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    TogglePause,
+    Play,
+    Pause,
+    ToggleFullscreen,
+    ExitFullscreen,
+    ToggleMute,
+    /// Moves the audio to the position of the frame on screen.
+    ResyncAudio,
+    /// Plays the sound later (positive) or earlier by this many milliseconds.
+    ChangeAudioDelay(i64),
+    ResetAudioDelay,
+    /// Shows the window that measures how far the sound is off from the picture.
+    MeasureSync,
+    VolumeUp,
+    VolumeDown,
+    SpeedUp,
+    SpeedDown,
+    ResetSpeed,
+    /// Shifts the pitch a semitone without changing the speed.
+    PitchUp,
+    PitchDown,
+    ResetPitch,
+    /// Sets the start of an A-B loop, then its end, then clears it.
+    CycleAbLoop,
+    /// Toggles motion-interpolated frames while playing slower than 1x.
+    ToggleSlowMotionInterpolation,
+    SeekRelative(i64),
+    SeekPercent(u8),
+    SeekTo(i64),
+    SeekStart,
+    SeekEnd,
+    PlaylistNext,
+    PlaylistPrevious,
+    OpenFile,
+    OpenFolder,
+    /// Opens several files to play back to back as one.
+    JoinFiles,
+    OpenUrl,
+    ExportPlaylist,
+    ToggleShuffle,
+    /// Pauses at the end of the current file instead of playing the next one.
+    ToggleStopAfterCurrent,
+    /// Steps the sleep timer through its presets and off.
+    CycleSleepTimer,
+    ToggleMediaInfo,
+    /// Marks the current position as the start of the part to export.
+    MarkIn,
+    MarkOut,
+    /// Adds a named marker at the current position.
+    AddMarker,
+    ToggleCutlist,
+    ToggleAnnotations,
+    /// Opens the notes with a new one at the current position.
+    AddAnnotation,
+    ToggleAnalysis,
+    /// Shows the window that decodes the whole file looking for damage.
+    VerifyFile,
+    GoToTime,
+    SearchSubtitles,
+    ToggleCaptions,
+    NextChapter,
+    PreviousChapter,
+    LoadChapters,
+    /// Shows the window that finds scene cuts and turns them into chapters.
+    DetectScenes,
+    /// Shows the window that converts image subtitles to text.
+    RecognizeSubtitles,
+    /// Jumps to the end of the skip segment playback is in.
+    SkipSegment,
+    EditSkipSegments,
+    /// Moves subtitles up (positive) or down by this many percent of the video height.
+    MoveSubtitles(i8),
+    SelectVideoStream(usize),
+    /// A PGS/VobSub stream index, or `None` to hide image subtitles.
+    SelectSubtitleStream(Option<usize>),
+    ToggleDenoise,
+    ToggleDeband,
+    ExportFrames,
+    /// Opens the dialog that re-encodes the file or a part of it.
+    Transcode,
+    /// Opens the dialog that copies the streams into an MP4 or MKV file.
+    Remux,
+    /// Starts or stops copying the network stream being played to a file.
+    ToggleRecording,
+    /// Saves the decoded frame as it came out of the decoder.
+    Screenshot,
+    /// Saves the video area of the window, with subtitles, shaders and the OSD.
+    ScreenshotAsDisplayed,
+    ToggleSphericalView,
+    /// Moves the picture into a window of its own, or back into the main window.
+    ToggleVideoWindow,
+    /// Resizes the window so the picture shows at this percentage of its pixel size.
+    ScaleWindow(u16),
+    ToggleFilmstrip,
+    /// Shows the values of the pixel under the cursor.
+    TogglePixelInspector,
+    ToggleScope(Scope),
+    ToggleLoudnessMeter,
+    ToggleLevelMeters,
+    SetDownmix(Downmix),
+    /// An audio stream to mix into the main one, such as a commentary track, or `None`.
+    SelectSecondaryAudio(Option<usize>),
+    SetScaling(ScalingAlgorithm),
+    SetSampler(Sampler),
+    SetDisplayMode(DisplayMode),
+    /// Switches between fit, fill and stretch.
+    CycleDisplayMode,
+    SetAudioBackend(AudioBackend),
+    /// Turns the dynamic range compressor on or off.
+    ToggleNightMode,
+    OpenComparison,
+    CloseComparison,
+    /// Opens several files to play at once in a grid.
+    OpenVideoWall,
+    CloseVideoWall,
+    ToggleWipe,
+    LoadShader,
+    SelectShader(Option<usize>),
+    /// A preset by index, or `None` to undo the one applied.
+    ApplyPreset(Option<usize>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shortcut {
+    Key(Modifiers, Key),
+    Text(char),
+}
+
+pub struct KeyBindings {
+    bindings: Vec<(Shortcut, Action)>,
+}
+
+impl KeyBindings {
+    pub fn new() -> Self {
+        let mut bindings = Self {
+            bindings: Vec::new(),
+        };
+
+        bindings.bind_key(Key::Space, Action::TogglePause);
+        bindings.bind_key(Key::F, Action::ToggleFullscreen);
+        bindings.bind_key(Key::Escape, Action::ExitFullscreen);
+        bindings.bind_key(Key::M, Action::ToggleMute);
+        bindings.bind_key(Key::Y, Action::ResyncAudio);
+        bindings.bind(Shortcut::Text('+'), Action::ChangeAudioDelay(50));
+        bindings.bind(Shortcut::Text('-'), Action::ChangeAudioDelay(-50));
+        bindings.bind(
+            Shortcut::Key(Modifiers::SHIFT, Key::Y),
+            Action::ResetAudioDelay,
+        );
+        bindings.bind_key(Key::ArrowUp, Action::VolumeUp);
+        bindings.bind_key(Key::ArrowDown, Action::VolumeDown);
+        bindings.bind_key(Key::ArrowLeft, Action::SeekRelative(-5000));
+        bindings.bind_key(Key::ArrowRight, Action::SeekRelative(5000));
+        bindings.bind_key(Key::Home, Action::SeekStart);
+        bindings.bind_key(Key::End, Action::SeekEnd);
+        bindings.bind_key(Key::I, Action::ToggleMediaInfo);
+        bindings.bind(Shortcut::Key(Modifiers::ALT, Key::I), Action::MarkIn);
+        bindings.bind(Shortcut::Key(Modifiers::ALT, Key::O), Action::MarkOut);
+        bindings.bind(Shortcut::Key(Modifiers::ALT, Key::M), Action::AddMarker);
+        bindings.bind(
+            Shortcut::Key(Modifiers::SHIFT, Key::C),
+            Action::ToggleCutlist,
+        );
+        bindings.bind(
+            Shortcut::Key(Modifiers::SHIFT, Key::A),
+            Action::AddAnnotation,
+        );
+        bindings.bind_key(Key::A, Action::ToggleAnalysis);
+        bindings.bind_key(Key::R, Action::ToggleShuffle);
+        bindings.bind(
+            Shortcut::Key(Modifiers::SHIFT, Key::Q),
+            Action::ToggleStopAfterCurrent,
+        );
+        bindings.bind(
+            Shortcut::Key(Modifiers::SHIFT, Key::Z),
+            Action::CycleSleepTimer,
+        );
+        bindings.bind_key(Key::W, Action::ToggleWipe);
+        bindings.bind_key(Key::V, Action::ToggleSphericalView);
+        bindings.bind_key(Key::T, Action::ToggleFilmstrip);
+        bindings.bind_key(Key::P, Action::TogglePixelInspector);
+        bindings.bind_key(Key::Z, Action::CycleDisplayMode);
+        bindings.bind_key(Key::H, Action::ToggleScope(Scope::Histogram));
+        bindings.bind(
+            Shortcut::Key(Modifiers::SHIFT, Key::H),
+            Action::ToggleScope(Scope::Parade),
+        );
+        bindings.bind_key(Key::U, Action::ToggleScope(Scope::Vectorscope));
+        bindings.bind_key(Key::L, Action::ToggleLoudnessMeter);
+        bindings.bind(
+            Shortcut::Key(Modifiers::SHIFT, Key::L),
+            Action::ToggleLevelMeters,
+        );
+        bindings.bind_key(Key::Enter, Action::SkipSegment);
+        bindings.bind_key(Key::C, Action::ToggleCaptions);
+        bindings.bind_key(Key::S, Action::Screenshot);
+        bindings.bind(
+            Shortcut::Key(Modifiers::SHIFT, Key::S),
+            Action::ScreenshotAsDisplayed,
+        );
+        bindings.bind_key(Key::PageDown, Action::NextChapter);
+        bindings.bind_key(Key::PageUp, Action::PreviousChapter);
+        bindings.bind(
+            Shortcut::Key(Modifiers::COMMAND, Key::ArrowUp),
+            Action::MoveSubtitles(1),
+        );
+        bindings.bind(
+            Shortcut::Key(Modifiers::COMMAND, Key::ArrowDown),
+            Action::MoveSubtitles(-1),
+        );
+        bindings.bind(Shortcut::Key(Modifiers::COMMAND, Key::O), Action::OpenFile);
+        bindings.bind(
+            Shortcut::Key(Modifiers::COMMAND | Modifiers::SHIFT, Key::O),
+            Action::OpenFolder,
+        );
+        bindings.bind(Shortcut::Key(Modifiers::COMMAND, Key::J), Action::JoinFiles);
+        bindings.bind(Shortcut::Key(Modifiers::COMMAND, Key::L), Action::OpenUrl);
+        bindings.bind(Shortcut::Key(Modifiers::COMMAND, Key::G), Action::GoToTime);
+        bindings.bind(
+            Shortcut::Key(Modifiers::COMMAND, Key::D),
+            Action::SearchSubtitles,
+        );
+        bindings.bind(
+            Shortcut::Key(Modifiers::COMMAND, Key::E),
+            Action::ExportFrames,
+        );
+        bindings.bind(
+            Shortcut::Key(Modifiers::COMMAND | Modifiers::SHIFT, Key::E),
+            Action::Transcode,
+        );
+        bindings.bind(Shortcut::Key(Modifiers::COMMAND, Key::R), Action::Remux);
+        bindings.bind(
+            Shortcut::Key(Modifiers::COMMAND | Modifiers::SHIFT, Key::R),
+            Action::ToggleRecording,
+        );
+        bindings.bind(
+            Shortcut::Key(Modifiers::COMMAND, Key::I),
+            Action::VerifyFile,
+        );
+        bindings.bind(
+            Shortcut::Key(Modifiers::COMMAND, Key::K),
+            Action::OpenComparison,
+        );
+        bindings.bind(
+            Shortcut::Key(Modifiers::COMMAND, Key::M),
+            Action::OpenVideoWall,
+        );
+        bindings.bind(
+            Shortcut::Key(Modifiers::COMMAND, Key::S),
+            Action::ExportPlaylist,
+        );
+        bindings.bind(
+            Shortcut::Key(Modifiers::ALT, Key::Num0),
+            Action::ScaleWindow(50),
+        );
+        bindings.bind(
+            Shortcut::Key(Modifiers::ALT, Key::Num1),
+            Action::ScaleWindow(100),
+        );
+        bindings.bind(
+            Shortcut::Key(Modifiers::ALT, Key::Num2),
+            Action::ScaleWindow(200),
+        );
+        bindings.bind(Shortcut::Text(']'), Action::SpeedUp);
+        bindings.bind(Shortcut::Text('['), Action::SpeedDown);
+        bindings.bind_key(Key::Backspace, Action::ResetSpeed);
+        bindings.bind(Shortcut::Text('}'), Action::PitchUp);
+        bindings.bind(Shortcut::Text('{'), Action::PitchDown);
+        bindings.bind(
+            Shortcut::Key(Modifiers::SHIFT, Key::Backspace),
+            Action::ResetPitch,
+        );
+        bindings.bind_key(Key::N, Action::ToggleSlowMotionInterpolation);
+        bindings.bind_key(Key::B, Action::CycleAbLoop);
+        bindings.bind(
+            Shortcut::Key(Modifiers::SHIFT, Key::N),
+            Action::ToggleNightMode,
+        );
+        bindings.bind(Shortcut::Text('>'), Action::PlaylistNext);
+        bindings.bind(Shortcut::Text('<'), Action::PlaylistPrevious);
+
+        let digits = [
+            Key::Num0,
+            Key::Num1,
+            Key::Num2,
+            Key::Num3,
+            Key::Num4,
+            Key::Num5,
+            Key::Num6,
+            Key::Num7,
+            Key::Num8,
+            Key::Num9,
+        ];
+        for (i, key) in digits.into_iter().enumerate() {
+            bindings.bind_key(key, Action::SeekPercent(i as u8 * 10));
+        }
+
+        bindings
+    }
+
+    pub fn bind(&mut self, shortcut: Shortcut, action: Action) {
+        self.unbind(shortcut);
+        self.bindings.push((shortcut, action));
+    }
+
+    pub fn unbind(&mut self, shortcut: Shortcut) {
+        self.bindings.retain(|(s, _)| *s != shortcut);
+    }
+
+    pub fn actions(&self, input: &egui::InputState) -> Vec<Action> {
+        let mut actions = Vec::new();
+
+        for event in &input.events {
+            let action = match event {
+                egui::Event::Key {
+                    key,
+                    pressed: true,
+                    modifiers,
+                    ..
+                } => self.find(|shortcut| match shortcut {
+                    Shortcut::Key(m, k) => k == key && modifiers.matches_exact(*m),
+                    Shortcut::Text(_) => false,
+                }),
+                egui::Event::Text(text) => self.find(|shortcut| match shortcut {
+                    Shortcut::Text(c) => text.chars().eq(std::iter::once(*c)),
+                    Shortcut::Key(..) => false,
+                }),
+                _ => None,
+            };
+
+            if let Some(action) = action {
+                actions.push(action);
+            }
+        }
+
+        actions
+    }
+
+    fn bind_key(&mut self, key: Key, action: Action) {
+        self.bind(Shortcut::Key(Modifiers::NONE, key), action);
+    }
+
+    fn find(&self, predicate: impl Fn(&Shortcut) -> bool) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(shortcut, _)| predicate(shortcut))
+            .map(|(_, action)| *action)
+    }
+}
@@ -0,0 +1,169 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// How long a note stays up in the SRT export when the next one comes later.
+const SRT_DURATION_MS: i64 = 4000;
+
+/// A note on a moment of a file, such as a review comment.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Annotation {
+    pub time_ms: i64,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Srt,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 3] = [ExportFormat::Csv, ExportFormat::Json, ExportFormat::Srt];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Json => "JSON",
+            ExportFormat::Srt => "SRT",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Srt => "srt",
+        }
+    }
+}
+
+/// Notes of every file, keyed by path and saved next to the settings.
+#[derive(Default)]
+pub struct AnnotationStore {
+    files: HashMap<String, Vec<Annotation>>,
+}
+
+impl AnnotationStore {
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(content) => match serde_json::from_str(&content) {
+                Ok(files) => Self { files },
+                Err(e) => {
+                    eprintln!("Error parsing annotations {}: {}", path.display(), e);
+                    Self::default()
+                }
+            },
+            Err(_) => Self::default(),
+        }
+    }
+
+    fn save(&self) {
+        let Some(path) = Self::path() else {
+            return;
+        };
+
+        let result = path
+            .parent()
+            .map(fs::create_dir_all)
+            .unwrap_or(Ok(()))
+            .and_then(|_| {
+                let content = serde_json::to_string_pretty(&self.files)?;
+                fs::write(&path, content)
+            });
+
+        if let Err(e) = result {
+            eprintln!("Error saving annotations {}: {}", path.display(), e);
+        }
+    }
+
+    fn path() -> Option<PathBuf> {
+        crate::storage::config_dir().map(|dir| dir.join("annotations.json"))
+    }
+
+    pub fn get(&self, media_path: &str) -> &[Annotation] {
+        self.files
+            .get(media_path)
+            .map(|annotations| annotations.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Replaces the notes of a file, sorted by time, and saves the store.
+    pub fn set(&mut self, media_path: &str, mut annotations: Vec<Annotation>) {
+        annotations.sort_by_key(|annotation| annotation.time_ms);
+        if annotations.is_empty() {
+            self.files.remove(media_path);
+        } else {
+            self.files.insert(media_path.to_string(), annotations);
+        }
+        self.save();
+    }
+}
+
+/// HH:MM:SS.mmm, or with a comma before the milliseconds as SRT has it.
+fn timestamp(ms: i64, separator: char) -> String {
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        separator,
+        ms % 1000
+    )
+}
+
+fn to_csv(annotations: &[Annotation]) -> String {
+    let mut csv = String::from("time,time_ms,text\n");
+    for annotation in annotations {
+        let _ = writeln!(
+            csv,
+            "{},{},\"{}\"",
+            timestamp(annotation.time_ms, '.'),
+            annotation.time_ms,
+            annotation.text.replace('"', "\"\"")
+        );
+    }
+    csv
+}
+
+/// Each note as a subtitle lasting until the next one, so they can be reviewed over the video.
+fn to_srt(annotations: &[Annotation]) -> String {
+    let mut srt = String::new();
+    for (index, annotation) in annotations.iter().enumerate() {
+        let end_ms = annotations
+            .get(index + 1)
+            .map_or(i64::MAX, |next| next.time_ms)
+            .min(annotation.time_ms + SRT_DURATION_MS)
+            .max(annotation.time_ms + 1);
+        let _ = write!(
+            srt,
+            "{}\n{} --> {}\n{}\n\n",
+            index + 1,
+            timestamp(annotation.time_ms, ','),
+            timestamp(end_ms, ','),
+            annotation.text
+        );
+    }
+    srt
+}
+
+pub fn export(
+    path: &Path,
+    format: ExportFormat,
+    annotations: &[Annotation],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let content = match format {
+        ExportFormat::Csv => to_csv(annotations),
+        ExportFormat::Json => serde_json::to_string_pretty(annotations)?,
+        ExportFormat::Srt => to_srt(annotations),
+    };
+    fs::write(path, content)?;
+    Ok(())
+}
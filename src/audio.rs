@@ -1,47 +1,255 @@
-use ffmpeg::{codec, format, frame, media};
+use crate::error::MediaError;
+use crate::http;
+use crate::levels::LevelMeter;
+use crate::loudness::LoudnessMeter;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use ffmpeg::ffi::AVChannelOrder;
+use ffmpeg::software::resampling;
+use ffmpeg::{codec, format, frame, media, rescale, ChannelLayout, Dictionary};
 use ffmpeg_next as ffmpeg;
 use ffmpeg_next::{Rational, Rescale};
 use rodio::{OutputStream, Sink, Source};
-use std::sync::{Arc, Mutex};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU32, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex, OnceLock, RwLock, Weak};
+use std::thread;
 use std::time::Duration;
 
 const MS_TIME_BASE: Rational = Rational(1, 1000);
+const OUTPUT_FORMAT: format::Sample = format::Sample::F32(format::sample::Type::Planar);
+/// Default budget for decoded audio, about 90 minutes of 48 kHz stereo.
+pub const DEFAULT_MEMORY_MB: u32 = 2048;
+/// How long to wait for the first samples of a live stream.
+const LIVE_START_TIMEOUT: Duration = Duration::from_secs(20);
+/// How long the sound fades out before pausing or jumping, and in afterwards. Stopping
+/// mid-wave would click.
+const FADE_MS: f32 = 50.0;
+/// `SourceControl::seek_to` when no jump is waiting.
+const NO_SEEK: usize = usize::MAX;
+/// Length of the snippet played for each position while scrubbing, before it fades out.
+const SCRUB_MS: u32 = 80;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Downmix {
+    /// Keep the source channel layout and let the output device map it.
+    Passthrough,
+    /// Fold surround layouts down to stereo; mono and stereo sources are kept as they are.
+    #[default]
+    Stereo,
+    Mono,
+}
+
+impl Downmix {
+    fn output_layout(&self, source: ChannelLayout) -> ChannelLayout {
+        match self {
+            Downmix::Passthrough => source,
+            Downmix::Stereo if source.channels() <= 2 => source,
+            Downmix::Stereo => ChannelLayout::STEREO,
+            Downmix::Mono => ChannelLayout::MONO,
+        }
+    }
+
+    fn output_channels(&self, source_channels: u16) -> u16 {
+        match self {
+            Downmix::Passthrough => source_channels,
+            Downmix::Stereo => source_channels.min(2),
+            Downmix::Mono => 1,
+        }
+    }
+}
+
+/// The channels and rate decoded samples are converted to.
+#[derive(Debug, Clone, Copy)]
+enum Conversion {
+    /// The stream's own rate, with its channels downmixed.
+    Downmix(Downmix),
+    /// Those of the main stream, for a stream mixed into it.
+    Match { channels: u16, sample_rate: u32 },
+}
+
+impl Conversion {
+    fn output_channels(&self, source_channels: u16) -> u16 {
+        match self {
+            Conversion::Downmix(downmix) => downmix.output_channels(source_channels),
+            Conversion::Match { channels, .. } => *channels,
+        }
+    }
+}
 
 fn timestamp_to_ms(timestamp: i64, time_base: Rational) -> i64 {
     timestamp.rescale(time_base, MS_TIME_BASE)
 }
 
+/// Decoded samples, or for audio decoded while it plays, the part of them around the
+/// playback position.
+pub struct SampleWindow {
+    /// Index in the whole stream of the first value held.
+    start: usize,
+    /// Interleaved samples with `channels` values per sample frame.
+    values: VecDeque<f32>,
+}
+
+impl SampleWindow {
+    fn new(values: Vec<f32>) -> Self {
+        Self {
+            start: 0,
+            values: values.into(),
+        }
+    }
+
+    /// Index just past the last value held.
+    pub fn end(&self) -> usize {
+        self.start + self.values.len()
+    }
+
+    fn get(&self, index: usize) -> Option<f32> {
+        let offset = index.checked_sub(self.start)?;
+        self.values.get(offset).copied()
+    }
+
+    /// The values from `start` to `end` in the whole stream, if all of them are held.
+    pub fn range(&self, start: usize, end: usize) -> Option<impl Iterator<Item = &f32>> {
+        if start < self.start || end > self.end() || start > end {
+            return None;
+        }
+        Some(self.values.range(start - self.start..end - self.start))
+    }
+
+    /// Drops the values before `index`.
+    fn discard_before(&mut self, index: usize) {
+        let count = index.saturating_sub(self.start).min(self.values.len());
+        self.values.drain(..count);
+        self.start += count;
+    }
+}
+
+/// How the samples get into memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Buffering {
+    /// All decoded up front.
+    Whole,
+    /// A live stream decoded as it arrives. Past the memory budget the oldest samples are
+    /// dropped.
+    Live,
+    /// A file too long for the memory budget, decoded a little ahead of the playback position
+    /// and decoded anew from where a seek lands.
+    Streamed,
+}
+
 struct DecodedAudio {
-    samples: Vec<f32>,
+    samples: RwLock<SampleWindow>,
+    stream_index: usize,
+    channels: u16,
     sample_rate: u32,
-    duration_ms: i64,
+    duration_ms: AtomicI64,
+    buffering: Buffering,
+    /// Set while a thread is still adding samples past the end of the window.
+    decoding: AtomicBool,
+    /// Set while the input is a file still being written, whose end is waited out.
+    following: AtomicBool,
+    /// How many values the window may hold.
+    capacity: usize,
+    /// Where the output reads, as an index into the samples.
+    read_pos: AtomicUsize,
+    /// Index to decode from after a seek away from the window, or -1.
+    seek_request: AtomicI64,
+    /// How often the output ran out of decoded samples and waited in silence.
+    underruns: AtomicUsize,
+    /// Timestamp of the stream's first packet, which is sample 0.
+    start_ms: i64,
 }
 
-impl DecodedAudio {
-    fn new(filename: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let mut input = format::input(&filename)?;
-        let audio_stream = input
-            .streams()
-            .best(media::Type::Audio)
-            .ok_or("No audio stream found")?;
-        let time_base = audio_stream.time_base();
-        let context = codec::Context::from_parameters(audio_stream.parameters())?;
-        let stream_index = audio_stream.index();
+/// An input with a decoder for its best audio stream, the stream's index and time base.
+type OpenedStream = (
+    format::context::Input,
+    codec::decoder::Audio,
+    usize,
+    Rational,
+);
 
-        let mut decoder = context.decoder().audio()?;
+/// Opens an audio stream of a file with a decoder for it, by default the best one.
+fn open_audio_stream(
+    filename: &str,
+    stream_index: Option<usize>,
+) -> Result<OpenedStream, MediaError> {
+    let input = http::open_input(&filename).map_err(MediaError::Open)?;
+    let audio_stream = match stream_index {
+        Some(index) => input
+            .stream(index)
+            .filter(|stream| stream.parameters().medium() == media::Type::Audio),
+        None => input.streams().best(media::Type::Audio),
+    }
+    .ok_or(MediaError::NoAudioStream)?;
+    let time_base = audio_stream.time_base();
+    let codec_id = audio_stream.parameters().id();
+    let context = codec::Context::from_parameters(audio_stream.parameters())?;
+    let stream_index = audio_stream.index();
 
-        let sample_rate = decoder.rate() / decoder.channels() as u32;
-        let channels = decoder.channels();
+    let decoder = context
+        .decoder()
+        .audio()
+        .map_err(|e| MediaError::decoder(e, codec_id))?;
 
-        println!(
-            "Decoding audio: sample rate={}Hz, channels={}",
-            decoder.rate(),
-            channels
-        );
+    println!(
+        "Decoding audio: sample rate={}Hz, channels={}",
+        decoder.rate(),
+        decoder.channels()
+    );
+
+    Ok((input, decoder, stream_index, time_base))
+}
+
+/// RMS level of the best audio stream mixed down to mono, in consecutive windows of
+/// `window_ms` from its first sample, up to `limit_ms`.
+pub fn level_envelope(
+    filename: &str,
+    window_ms: i64,
+    limit_ms: i64,
+    cancel: &AtomicBool,
+) -> Result<Vec<f32>, MediaError> {
+    let (mut input, mut decoder, stream_index, _) = open_audio_stream(filename, None)?;
+    let mut converter = SampleConverter::new(Conversion::Downmix(Downmix::Mono));
+    let mut levels = Vec::new();
+    let mut window = Vec::new();
+
+    for (stream, packet) in input.packets() {
+        if cancel.load(Ordering::Relaxed) || levels.len() as i64 * window_ms >= limit_ms {
+            break;
+        }
+        if stream.index() != stream_index {
+            continue;
+        }
+        if let Err(e) = decoder.send_packet(&packet) {
+            eprintln!("Error sending packet: {}", e);
+            continue;
+        }
+        converter.receive_frames(&mut decoder);
 
+        let window_length = (converter.sample_rate as i64 * window_ms / 1000).max(1) as usize;
+        for sample in converter.samples.drain(..) {
+            window.push(sample);
+            if window.len() == window_length {
+                let power = window.iter().map(|value| value * value).sum::<f32>();
+                levels.push((power / window_length as f32).sqrt());
+                window.clear();
+            }
+        }
+    }
+
+    Ok(levels)
+}
+
+impl DecodedAudio {
+    /// Decodes the whole stream up front.
+    fn decode_all(
+        (mut input, mut decoder, stream_index, time_base): OpenedStream,
+        conversion: Conversion,
+    ) -> Result<Self, MediaError> {
         let decoding_start = std::time::Instant::now();
-        let mut samples = Vec::new();
+        let mut converter = SampleConverter::new(conversion);
         let mut duration_ms = 0;
+        let mut start_ms = None;
 
         for (stream, packet) in input.packets() {
             if stream.index() != stream_index {
@@ -50,6 +258,7 @@ impl DecodedAudio {
 
             if let Some(pts) = packet.pts() {
                 let ts_ms = timestamp_to_ms(pts, time_base);
+                start_ms.get_or_insert(ts_ms);
                 if ts_ms > duration_ms {
                     duration_ms = ts_ms;
                 }
@@ -60,82 +269,720 @@ impl DecodedAudio {
                 continue;
             }
 
-            let mut decoded = frame::Audio::empty();
-            while decoder.receive_frame(&mut decoded).is_ok() {
-                match decoded.format() {
-                    format::Sample::F32(format::sample::Type::Planar) => {
-                        let frame_samples = decoded.plane::<f32>(0);
-
-                        if channels == 1 {
-                            for &sample in frame_samples {
-                                samples.push(sample);
-                                samples.push(sample);
-                            }
-                        } else {
-                            samples.extend_from_slice(frame_samples);
-                        }
-                    }
-                    other_format => {
-                        let mut converted = frame::Audio::empty();
-                        if let Ok(_) = ffmpeg::software::resampling::context::Context::get(
-                            decoded.format(),
-                            decoded.channel_layout(),
-                            decoded.rate(),
-                            format::Sample::F32(format::sample::Type::Planar),
-                            decoded.channel_layout(),
-                            decoded.rate(),
-                        )
-                        .and_then(|mut converter| converter.run(&decoded, &mut converted))
-                        {
-                            let frame_samples = converted.plane::<f32>(0);
-
-                            if channels == 1 {
-                                for &sample in frame_samples {
-                                    samples.push(sample);
-                                    samples.push(sample);
-                                }
-                            } else {
-                                samples.extend_from_slice(frame_samples);
-                            }
-                        } else {
-                            println!("Failed to convert audio format {:?}", other_format);
-                        }
-                    }
-                }
-            }
+            converter.receive_frames(&mut decoder);
         }
 
+        // Drain the frames the decoder still holds back
+        decoder.send_eof()?;
+        converter.receive_frames(&mut decoder);
+        converter.flush()?;
+
+        // Nothing decodable in the whole stream
+        let channels = converter
+            .channels()
+            .ok_or(MediaError::Decode(ffmpeg::Error::InvalidData))?;
+        let sample_rate = converter.sample_rate;
+        let samples = converter.samples;
+
+        // The last packet timestamp marks the start of the last frame, so prefer the decoded length
+        let decoded_ms = (samples.len() / channels as usize) as i64 * 1000 / sample_rate as i64;
+        let duration_ms = duration_ms.max(decoded_ms);
+
         println!(
-            "Finished decoding {} audio samples, duration: {}ms, took {}ms",
+            "Finished decoding {} audio samples ({} channels at {}Hz), duration: {}ms, took {}ms",
             samples.len(),
+            channels,
+            sample_rate,
             duration_ms,
             decoding_start.elapsed().as_millis()
         );
 
         Ok(DecodedAudio {
-            samples,
+            samples: RwLock::new(SampleWindow::new(samples)),
+            stream_index,
+            channels,
             sample_rate,
-            duration_ms,
+            duration_ms: AtomicI64::new(duration_ms),
+            buffering: Buffering::Whole,
+            decoding: AtomicBool::new(false),
+            following: AtomicBool::new(false),
+            capacity: usize::MAX,
+            read_pos: AtomicUsize::new(0),
+            seek_request: AtomicI64::new(-1),
+            underruns: AtomicUsize::new(0),
+            start_ms: start_ms.unwrap_or(0),
         })
     }
 
+    /// Decodes on a thread of its own, returning once the first samples are in and fix the
+    /// channel count and rate. The thread ends with a live stream, or for streamed audio once
+    /// the audio is dropped. With `following`, the end of the input is a file still being
+    /// written and decoding waits there for more.
+    fn start_decoding(
+        filename: &str,
+        stream_index: Option<usize>,
+        conversion: Conversion,
+        buffering: Buffering,
+        following: bool,
+        capacity: usize,
+        duration_ms: i64,
+    ) -> Result<Arc<Self>, MediaError> {
+        let (sender, receiver) = mpsc::channel();
+        let filename = filename.to_string();
+
+        thread::spawn(move || {
+            let (mut input, mut decoder, stream_index, time_base) =
+                match open_audio_stream(&filename, stream_index) {
+                    Ok(opened) => opened,
+                    Err(e) => {
+                        let _ = sender.send(Err(e));
+                        return;
+                    }
+                };
+            let mut converter = SampleConverter::new(conversion);
+            let mut shared: Option<Weak<DecodedAudio>> = None;
+            // Timestamp of the stream's first packet, which is sample 0
+            let mut first_ms = None;
+            // Where the next decoded values go; unknown right after a seek
+            let mut next_index = Some(0);
+            let mut at_end = false;
+
+            loop {
+                let audio = match &shared {
+                    Some(weak) => match weak.upgrade() {
+                        Some(audio) => Some(audio),
+                        // The audio was closed
+                        None => return,
+                    },
+                    None => None,
+                };
+
+                if let Some(audio) = &audio {
+                    let request = audio.seek_request.swap(-1, Ordering::SeqCst);
+                    if request >= 0 {
+                        let target_ms =
+                            audio.sample_pos_to_ms(request as usize) + first_ms.unwrap_or(0);
+                        let timestamp = target_ms.rescale(MS_TIME_BASE, rescale::TIME_BASE);
+                        if let Err(e) = input.seek(timestamp, ..timestamp) {
+                            eprintln!("Error seeking audio: {}", e);
+                        }
+                        decoder.flush();
+                        converter.samples.clear();
+                        next_index = None;
+                        at_end = false;
+                    }
+
+                    if at_end || audio.is_ahead_enough() {
+                        audio.discard_played();
+                        thread::sleep(Duration::from_millis(20));
+                        continue;
+                    }
+                }
+
+                let next = input
+                    .packets()
+                    .next()
+                    .map(|(stream, packet)| (stream.index(), packet));
+                let Some((index, packet)) = next else {
+                    let still_following = match &audio {
+                        Some(audio) => audio.following.load(Ordering::Relaxed),
+                        None => following,
+                    };
+                    if still_following {
+                        // The end of a file that is still being written; read on once it grows
+                        unsafe {
+                            let pb = (*input.as_mut_ptr()).pb;
+                            if !pb.is_null() {
+                                (*pb).eof_reached = 0;
+                            }
+                        }
+                        thread::sleep(Duration::from_millis(50));
+                        continue;
+                    }
+
+                    let _ = decoder.send_eof();
+                    converter.receive_frames(&mut decoder);
+                    let _ = converter.flush();
+                    let Some(audio) = audio else {
+                        let _ = sender.send(Err(MediaError::Decode(ffmpeg::Error::InvalidData)));
+                        return;
+                    };
+                    audio.append(&mut converter.samples, &mut next_index);
+                    audio.finish();
+                    if buffering == Buffering::Live {
+                        println!("Live audio ended after {}ms", audio.duration_ms());
+                        return;
+                    }
+                    // Streamed audio stays around for seeks back
+                    decoder.flush();
+                    at_end = true;
+                    continue;
+                };
+                if index != stream_index {
+                    continue;
+                }
+
+                if let Some(pts) = packet.pts() {
+                    let packet_ms = timestamp_to_ms(pts, time_base);
+                    let first_ms = *first_ms.get_or_insert(packet_ms);
+                    if next_index.is_none() {
+                        next_index = audio
+                            .as_ref()
+                            .map(|audio| audio.ms_to_sample_pos((packet_ms - first_ms).max(0)));
+                    }
+                }
+                if next_index.is_none() {
+                    continue;
+                }
+
+                if let Err(e) = decoder.send_packet(&packet) {
+                    eprintln!("Error sending packet: {}", e);
+                    continue;
+                }
+                converter.receive_frames(&mut decoder);
+
+                let Some(channels) = converter.channels() else {
+                    continue;
+                };
+                match audio {
+                    Some(audio) => audio.append(&mut converter.samples, &mut next_index),
+                    None => {
+                        let audio = Arc::new(DecodedAudio {
+                            samples: RwLock::new(SampleWindow::new(Vec::new())),
+                            stream_index,
+                            channels,
+                            sample_rate: converter.sample_rate,
+                            duration_ms: AtomicI64::new(duration_ms),
+                            buffering,
+                            decoding: AtomicBool::new(true),
+                            following: AtomicBool::new(following),
+                            capacity,
+                            read_pos: AtomicUsize::new(0),
+                            seek_request: AtomicI64::new(-1),
+                            underruns: AtomicUsize::new(0),
+                            start_ms: first_ms.unwrap_or(0),
+                        });
+                        audio.append(&mut converter.samples, &mut next_index);
+                        shared = Some(Arc::downgrade(&audio));
+                        if sender.send(Ok(audio)).is_err() {
+                            return;
+                        }
+                    }
+                }
+            }
+        });
+
+        receiver
+            .recv_timeout(LIVE_START_TIMEOUT)
+            .map_err(|_| MediaError::Decode(ffmpeg::Error::InvalidData))?
+    }
+
+    /// Adds decoded values that belong at `next_index` in the whole stream, unless a seek
+    /// has been requested since they were decoded.
+    fn append(&self, values: &mut Vec<f32>, next_index: &mut Option<usize>) {
+        let mut window = self.samples.write().unwrap();
+        let Some(index) = *next_index else {
+            values.clear();
+            return;
+        };
+        if self.seek_request.load(Ordering::SeqCst) >= 0 {
+            values.clear();
+            return;
+        }
+        *next_index = Some(index + values.len());
+
+        // After a seek, decoding starts at a packet near the requested position rather than
+        // on it; what comes before it is dropped and a short gap is filled with silence
+        let end = window.end();
+        let max_gap = self.sample_rate as usize * self.channels as usize;
+        if index > end && index - end <= max_gap {
+            window.values.extend(std::iter::repeat_n(0.0, index - end));
+        }
+        let skip = end.saturating_sub(index).min(values.len());
+        window.values.extend(values.drain(skip..));
+        values.clear();
+
+        if window.values.len() > self.capacity {
+            // Only live streams get here, having run past the budget
+            let excess = window.values.len() - self.capacity;
+            let excess = excess.next_multiple_of(self.channels as usize);
+            let start = window.start;
+            window.discard_before(start + excess);
+        }
+
+        let decoded_ms = self.sample_pos_to_ms(window.end());
+        self.duration_ms.fetch_max(decoded_ms, Ordering::Relaxed);
+    }
+
+    /// Marks the stream as decoded to its end, unless a seek has asked for more since.
+    fn finish(&self) {
+        let _window = self.samples.write().unwrap();
+        if self.seek_request.load(Ordering::SeqCst) < 0 {
+            self.decoding.store(false, Ordering::Relaxed);
+        }
+    }
+
+    /// Whether streamed audio holds enough beyond the playback position for now. A quarter
+    /// of the budget is kept behind it for short seeks back.
+    fn is_ahead_enough(&self) -> bool {
+        if self.buffering != Buffering::Streamed {
+            return false;
+        }
+        let ahead = self.capacity - self.capacity / 4;
+        let end = self.samples.read().unwrap().end();
+        end.saturating_sub(self.read_pos.load(Ordering::Relaxed)) >= ahead
+    }
+
+    /// Drops streamed samples that lie further behind the playback position than the quarter
+    /// of the budget kept for seeks back.
+    fn discard_played(&self) {
+        if self.buffering != Buffering::Streamed {
+            return;
+        }
+        let behind = self.capacity / 4;
+        let keep_from = self
+            .read_pos
+            .load(Ordering::Relaxed)
+            .saturating_sub(behind)
+            .next_multiple_of(self.channels as usize);
+        let mut window = self.samples.write().unwrap();
+        // In chunks rather than a little on every pass
+        if keep_from > window.start + behind / 8 {
+            window.discard_before(keep_from);
+        }
+    }
+
+    /// Moves the read position for a seek to `ms` and returns where playback goes. Streamed
+    /// audio outside the window is decoded anew from there; a live stream can't go back past
+    /// the samples it still holds.
+    fn prepare_seek(&self, ms: i64) -> i64 {
+        let mut window = self.samples.write().unwrap();
+        let ms = match self.buffering {
+            Buffering::Live => ms.max(self.sample_pos_to_ms(window.start)),
+            _ => ms,
+        };
+        let index = self.ms_to_sample_pos(ms);
+        self.read_pos.store(index, Ordering::Relaxed);
+
+        let in_window = window.start <= index && index <= window.end();
+        if self.buffering == Buffering::Streamed && !in_window {
+            window.values.clear();
+            window.start = index;
+            self.seek_request.store(index as i64, Ordering::SeqCst);
+            self.decoding.store(true, Ordering::Relaxed);
+        }
+        ms
+    }
+
+    fn duration_ms(&self) -> i64 {
+        self.duration_ms.load(Ordering::Relaxed)
+    }
+
+    /// Whether more samples are coming past the end of the window.
+    fn is_decoding(&self) -> bool {
+        self.decoding.load(Ordering::Relaxed)
+    }
+
+    fn is_live(&self) -> bool {
+        self.buffering == Buffering::Live && self.is_decoding()
+    }
+
     fn ms_to_sample_pos(&self, ms: i64) -> usize {
         let samples_per_ms = self.sample_rate as f64 / 1000.0;
         let sample_pos = (ms as f64 * samples_per_ms) as usize;
-        sample_pos * 2
+        sample_pos * self.channels as usize
     }
 
     fn sample_pos_to_ms(&self, pos: usize) -> i64 {
-        let sample_idx = pos / 2;
+        let sample_idx = pos / self.channels as usize;
         let ms_per_sample = 1000.0 / self.sample_rate as f64;
         (sample_idx as f64 * ms_per_sample) as i64
     }
 }
 
+/// Converts decoded frames of any sample format, layout and rate into interleaved f32
+/// samples. The output layout and rate are fixed by the first frame.
+struct SampleConverter {
+    downmix: Downmix,
+    resampler: Option<resampling::Context>,
+    output_layout: Option<ChannelLayout>,
+    sample_rate: u32,
+    samples: Vec<f32>,
+}
+
+impl SampleConverter {
+    fn new(conversion: Conversion) -> Self {
+        let (downmix, output_layout, sample_rate) = match conversion {
+            Conversion::Downmix(downmix) => (downmix, None, 0),
+            Conversion::Match {
+                channels,
+                sample_rate,
+            } => (
+                Downmix::Passthrough,
+                Some(ChannelLayout::default(channels as i32)),
+                sample_rate,
+            ),
+        };
+        Self {
+            downmix,
+            resampler: None,
+            output_layout,
+            sample_rate,
+            samples: Vec::new(),
+        }
+    }
+
+    fn channels(&self) -> Option<u16> {
+        self.output_layout.map(|layout| layout.channels() as u16)
+    }
+
+    fn receive_frames(&mut self, decoder: &mut codec::decoder::Audio) {
+        let mut decoded = frame::Audio::empty();
+
+        while decoder.receive_frame(&mut decoded).is_ok() {
+            if let Err(e) = self.push(&mut decoded) {
+                eprintln!("Failed to convert audio frame: {}", e);
+            }
+        }
+    }
+
+    fn push(&mut self, decoded: &mut frame::Audio) -> Result<(), ffmpeg::Error> {
+        // Some demuxers only report a channel count and custom orders reference memory owned by
+        // the frame, so both are replaced by the default layout for the channel count
+        if decoded.channel_layout().0.order != AVChannelOrder::AV_CHANNEL_ORDER_NATIVE {
+            decoded.set_channel_layout(ChannelLayout::default(decoded.channels() as i32));
+        }
+
+        let input_changed = self.resampler.as_ref().is_some_and(|resampler| {
+            let input = resampler.input();
+            input.format != decoded.format()
+                || input.channel_layout != decoded.channel_layout()
+                || input.rate != decoded.rate()
+        });
+        if input_changed {
+            self.flush()?;
+            self.resampler = None;
+        }
+
+        if self.resampler.is_none() {
+            let output_layout = match self.output_layout {
+                Some(output_layout) => output_layout,
+                None => {
+                    let output_layout = self.downmix.output_layout(decoded.channel_layout());
+                    self.output_layout = Some(output_layout);
+                    self.sample_rate = decoded.rate();
+                    output_layout
+                }
+            };
+
+            let mut options = Dictionary::new();
+            // Float output is not normalized by default, so a 5.1 fold-down could clip
+            options.set("rematrix_maxval", "1.0");
+
+            self.resampler = Some(resampling::Context::get_with(
+                decoded.format(),
+                decoded.channel_layout(),
+                decoded.rate(),
+                OUTPUT_FORMAT,
+                output_layout,
+                self.sample_rate,
+                options,
+            )?);
+        }
+
+        let mut converted = frame::Audio::empty();
+        if let Some(resampler) = &mut self.resampler {
+            resampler.run(decoded, &mut converted)?;
+            self.interleave(&converted);
+        }
+
+        Ok(())
+    }
+
+    /// Collects the samples the resampler buffers internally, e.g. when changing rates.
+    fn flush(&mut self) -> Result<(), ffmpeg::Error> {
+        if let Some(resampler) = &mut self.resampler {
+            if resampler.delay().is_some() {
+                let mut converted =
+                    frame::Audio::new(OUTPUT_FORMAT, 4096, resampler.output().channel_layout);
+                resampler.flush(&mut converted)?;
+                self.interleave(&converted);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn interleave(&mut self, planar: &frame::Audio) {
+        if planar.samples() == 0 {
+            return;
+        }
+
+        let planes: Vec<&[f32]> = (0..planar.planes())
+            .map(|plane| planar.plane::<f32>(plane))
+            .collect();
+
+        self.samples.reserve(planar.samples() * planes.len());
+        for i in 0..planar.samples() {
+            self.samples.extend(planes.iter().map(|plane| plane[i]));
+        }
+    }
+}
+
+/// Levels up to the knee pass unchanged; louder peaks are bent smoothly towards full scale.
+const SOFT_CLIP_KNEE: f32 = 0.8;
+
+/// Limits amplified samples to [-1, 1] without the harsh distortion of hard clipping.
+fn soft_clip(sample: f32) -> f32 {
+    let magnitude = sample.abs();
+    if magnitude <= SOFT_CLIP_KNEE {
+        return sample;
+    }
+
+    let headroom = 1.0 - SOFT_CLIP_KNEE;
+    (SOFT_CLIP_KNEE + headroom * ((magnitude - SOFT_CLIP_KNEE) / headroom).tanh()).copysign(sample)
+}
+
+/// Applies the volume, soft clipping when it amplifies above 100%.
+fn apply_gain(sample: f32, gain: f32) -> f32 {
+    if gain > 1.0 {
+        soft_clip(sample * gain)
+    } else {
+        sample * gain
+    }
+}
+
+/// A second stream of the file played along with the main one, such as a commentary track.
+#[derive(Default)]
+struct Mix {
+    secondary: RwLock<Option<Arc<DecodedAudio>>>,
+    /// Share of the secondary stream as f32 bits, from 0 (main only) to 1 (secondary only).
+    balance: AtomicU32,
+    /// How much later the secondary stream starts than the main one, in milliseconds and in
+    /// values, as both index their samples from their own first packet.
+    offset_ms: AtomicI64,
+    offset: AtomicI64,
+}
+
+impl Mix {
+    /// Both streams play at full level in the middle; towards either end the other one
+    /// fades out.
+    fn gains(&self) -> (f32, f32) {
+        let balance = f32::from_bits(self.balance.load(Ordering::Relaxed));
+        ((2.0 - 2.0 * balance).min(1.0), (2.0 * balance).min(1.0))
+    }
+
+    /// Mixes the value at `index` of the secondary stream into `value`, and moves its read
+    /// position along with the main one.
+    fn apply(&self, value: f32, index: usize, secondary: Option<&SampleWindow>) -> f32 {
+        let Some(samples) = secondary else {
+            return value;
+        };
+        let (main_gain, secondary_gain) = self.gains();
+        let other = self
+            .secondary_index(index)
+            .and_then(|index| samples.get(index))
+            .unwrap_or(0.0);
+        soft_clip(value * main_gain + other * secondary_gain)
+    }
+
+    /// The index in the secondary stream that plays along with `index` of the main one, or
+    /// `None` before the secondary stream starts.
+    fn secondary_index(&self, index: usize) -> Option<usize> {
+        usize::try_from(index as i64 - self.offset.load(Ordering::Relaxed)).ok()
+    }
+
+    fn prepare_seek(&self, ms: i64) {
+        if let Some(audio) = self.secondary.read().unwrap().as_ref() {
+            audio.prepare_seek((ms - self.offset_ms.load(Ordering::Relaxed)).max(0));
+        }
+    }
+
+    /// Swaps in a secondary stream, lined up with `main` by the timestamps of their first
+    /// packets, and moves it to where `main` plays at `position_ms`.
+    fn set_secondary(
+        &self,
+        secondary: Option<Arc<DecodedAudio>>,
+        main: &DecodedAudio,
+        position_ms: i64,
+    ) {
+        if let Some(audio) = &secondary {
+            let offset_ms = audio.start_ms - main.start_ms;
+            let offset = offset_ms * main.sample_rate as i64 / 1000 * main.channels as i64;
+            self.offset_ms.store(offset_ms, Ordering::Relaxed);
+            self.offset.store(offset, Ordering::Relaxed);
+            audio.prepare_seek((position_ms - offset_ms).max(0));
+        }
+        *self.secondary.write().unwrap() = secondary;
+    }
+}
+
+/// How fast the night mode compressor reacts to a loud sound, and lets go after it.
+const COMPRESSOR_ATTACK_MS: f32 = 10.0;
+const COMPRESSOR_RELEASE_MS: f32 = 250.0;
+
+/// Night mode: turns loud passages down and, with its makeup gain, quiet ones up. The soft
+/// clipper after it limits what still peaks above full scale.
+#[derive(Clone)]
+struct Compressor {
+    envelope: f32,
+    attack: f32,
+    release: f32,
+}
+
+impl Compressor {
+    /// `rate` is how often `process` is called per second.
+    fn new(rate: u32) -> Self {
+        let coefficient = |ms: f32| (-1000.0 / (ms * rate as f32)).exp();
+        Self {
+            envelope: 0.0,
+            attack: coefficient(COMPRESSOR_ATTACK_MS),
+            release: coefficient(COMPRESSOR_RELEASE_MS),
+        }
+    }
+
+    /// Compresses one sample, with `strength` from 0 (off) to 1. The level is followed
+    /// across the channels together, so that the stereo image stays in place.
+    fn process(&mut self, sample: f32, strength: f32) -> f32 {
+        let level = sample.abs();
+        let coefficient = if level > self.envelope {
+            self.attack
+        } else {
+            self.release
+        };
+        self.envelope = level + coefficient * (self.envelope - level);
+        if strength <= 0.0 {
+            return sample;
+        }
+
+        let threshold_db = -10.0 - 20.0 * strength;
+        let slope = 1.0 - 1.0 / (1.0 + 7.0 * strength);
+        let makeup_db = -threshold_db * slope * 0.5;
+        let envelope_db = 20.0 * self.envelope.max(1e-6).log10();
+        let gain_db = makeup_db - (envelope_db - threshold_db).max(0.0) * slope;
+        soft_clip(sample * 10f32.powf(gain_db / 20.0))
+    }
+}
+
+/// Length of the pitch shifter's grains. Longer sounds smoother on sustained notes but
+/// smears attacks.
+const PITCH_WINDOW_MS: u32 = 60;
+
+/// Shifts the pitch without changing the speed: two read heads slide through a short delay
+/// line at the pitch ratio and are crossfaded so that neither is heard when it jumps back.
+#[derive(Clone)]
+struct PitchShifter {
+    /// A delay line per channel.
+    lines: Vec<Vec<f32>>,
+    write: usize,
+    /// Position of the first read head within the window, from 0 to 1.
+    phase: f32,
+    window: usize,
+}
+
+impl PitchShifter {
+    fn new(channels: u16, sample_rate: u32) -> Self {
+        let window = (sample_rate * PITCH_WINDOW_MS / 1000).max(2) as usize;
+        Self {
+            lines: vec![vec![0.0; window + 2]; channels.max(1) as usize],
+            write: 0,
+            phase: 0.0,
+            window,
+        }
+    }
+
+    /// Shifts one sample of `channel` by `ratio`, 2 being an octave up. Samples come in
+    /// frame by frame; the heads move on after the last channel.
+    fn process(&mut self, channel: usize, sample: f32, ratio: f32) -> f32 {
+        let length = self.window + 2;
+        self.lines[channel][self.write] = sample;
+
+        let output = if ratio == 1.0 {
+            sample
+        } else {
+            let line = &self.lines[channel];
+            let read = |phase: f32| {
+                let delay = phase * self.window as f32 + 1.0;
+                let position = (self.write as f32 - delay).rem_euclid(length as f32);
+                let index = position as usize;
+                let fraction = position - index as f32;
+                line[index] + (line[(index + 1) % length] - line[index]) * fraction
+            };
+            // Triangular gains add up to one and are zero where a head jumps back
+            let second = (self.phase + 0.5) % 1.0;
+            let gain = |phase: f32| 1.0 - (2.0 * phase - 1.0).abs();
+            read(self.phase) * gain(self.phase) + read(second) * gain(second)
+        };
+
+        if channel + 1 == self.lines.len() {
+            self.write = (self.write + 1) % length;
+            self.phase = (self.phase + (1.0 - ratio) / self.window as f32).rem_euclid(1.0);
+        }
+        output
+    }
+}
+
+/// Gain that ramps between silence and full volume over `FADE_MS`.
+#[derive(Clone)]
+struct Fade {
+    gain: f32,
+    step: f32,
+}
+
+impl Fade {
+    /// Starts silent. `rate` is how often `next` is called per second.
+    fn new(rate: u32) -> Self {
+        Self {
+            gain: 0.0,
+            step: 1000.0 / (FADE_MS * rate as f32),
+        }
+    }
+
+    /// Moves one step towards full volume, or towards silence when `audible` is false.
+    fn next(&mut self, audible: bool) -> f32 {
+        self.gain = if audible {
+            (self.gain + self.step).min(1.0)
+        } else {
+            (self.gain - self.step).max(0.0)
+        };
+        self.gain
+    }
+
+    fn is_silent(&self) -> bool {
+        self.gain <= 0.0
+    }
+}
+
+/// Pausing and seeking for the rodio source, which carries them out once it has faded out,
+/// and the night mode strength.
+struct SourceControl {
+    paused: AtomicBool,
+    /// Sample position to jump to, or `NO_SEEK`.
+    seek_to: AtomicUsize,
+    /// Samples of the scrub snippet still to play, even while paused.
+    scrub: AtomicUsize,
+    /// Compressor strength as f32 bits; 0 turns it off.
+    compression: AtomicU32,
+    /// Pitch shift ratio as f32 bits.
+    pitch: AtomicU32,
+}
+
 struct MemoryAudioSource {
     decoded_audio: Arc<DecodedAudio>,
     position: usize,
     current_time_ms: Arc<Mutex<i64>>,
+    /// Gain above 100%, stored as f32 bits; the sink handles attenuation.
+    boost: Arc<AtomicU32>,
+    control: Arc<SourceControl>,
+    mix: Arc<Mix>,
+    fade: Fade,
+    compressor: Compressor,
+    pitch_shifter: PitchShifter,
+    /// Silent values still to hand out for the sample frame being padded while a live
+    /// stream hasn't caught up or playback is paused, so that the channels stay in step.
+    padding: usize,
+    /// Set while waiting for the decoder, so that each wait counts as one underrun.
+    starving: bool,
 }
 
 impl MemoryAudioSource {
@@ -143,14 +990,27 @@ impl MemoryAudioSource {
         decoded_audio: Arc<DecodedAudio>,
         start_pos: usize,
         current_time_ms: Arc<Mutex<i64>>,
+        boost: Arc<AtomicU32>,
+        control: Arc<SourceControl>,
+        mix: Arc<Mix>,
     ) -> Self {
         let ms = decoded_audio.sample_pos_to_ms(start_pos);
         *current_time_ms.lock().unwrap() = ms;
+        let rate = decoded_audio.sample_rate * decoded_audio.channels as u32;
+        let pitch_shifter = PitchShifter::new(decoded_audio.channels, decoded_audio.sample_rate);
 
         Self {
             decoded_audio,
             position: start_pos,
             current_time_ms,
+            boost,
+            control,
+            mix,
+            fade: Fade::new(rate),
+            compressor: Compressor::new(rate),
+            pitch_shifter,
+            padding: 0,
+            starving: false,
         }
     }
 }
@@ -159,16 +1019,79 @@ impl Iterator for MemoryAudioSource {
     type Item = f32;
 
     fn next(&mut self) -> Option<f32> {
-        if self.position < self.decoded_audio.samples.len() {
-            let sample = self.decoded_audio.samples[self.position];
+        if self.padding > 0 {
+            self.padding -= 1;
+            return Some(0.0);
+        }
+
+        let audio = &self.decoded_audio;
+        let channels = audio.channels as usize;
+        let scrub = self.control.scrub.load(Ordering::Relaxed);
+        let holding = self.control.paused.load(Ordering::Relaxed) && scrub == 0;
+        let seek_to = self.control.seek_to.load(Ordering::Relaxed);
+        let fading_out = holding || seek_to != NO_SEEK;
+        // Pausing and jumping wait for silence at the start of a sample frame
+        if fading_out && self.fade.is_silent() && self.position % channels == 0 {
+            if seek_to != NO_SEEK {
+                // A newer seek that came in meanwhile is taken on the next sample
+                let _ = self.control.seek_to.compare_exchange(
+                    seek_to,
+                    NO_SEEK,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                );
+                self.position = seek_to;
+            }
+            if holding {
+                self.padding = channels - 1;
+                return Some(0.0);
+            }
+        }
+        let fade = self.fade.next(!fading_out);
+        if scrub > 0 && seek_to == NO_SEEK {
+            self.control.scrub.fetch_sub(1, Ordering::Relaxed);
+        }
 
-            if self.position % 4000 == 0 {
-                let ms = self.decoded_audio.sample_pos_to_ms(self.position);
+        let samples = audio.samples.read().unwrap();
+        if let Some(sample) = samples.get(self.position) {
+            self.starving = false;
+            let secondary = self.mix.secondary.read().unwrap();
+            // While a jump waits, the position it goes to is the one reported
+            if self.position % 4000 == 0 && seek_to == NO_SEEK {
+                let ms = audio.sample_pos_to_ms(self.position);
                 *self.current_time_ms.lock().unwrap() = ms;
+                audio.read_pos.store(self.position, Ordering::Relaxed);
+                if let Some(secondary) = secondary.as_ref() {
+                    let index = self.mix.secondary_index(self.position).unwrap_or(0);
+                    secondary.read_pos.store(index, Ordering::Relaxed);
+                }
             }
 
+            let secondary_samples = secondary
+                .as_ref()
+                .map(|audio| audio.samples.read().unwrap());
+            let sample = self
+                .mix
+                .apply(sample, self.position, secondary_samples.as_deref());
+            let channel = self.position % channels;
             self.position += 1;
-            Some(sample)
+            let pitch = f32::from_bits(self.control.pitch.load(Ordering::Relaxed));
+            let sample = self.pitch_shifter.process(channel, sample, pitch);
+            let strength = f32::from_bits(self.control.compression.load(Ordering::Relaxed));
+            let sample = self.compressor.process(sample, strength);
+            Some(apply_gain(sample, f32::from_bits(self.boost.load(Ordering::Relaxed))) * fade)
+        } else if audio.is_decoding() {
+            // A live stream that dropped what wasn't played in time goes on from what is left
+            if audio.buffering == Buffering::Live && self.position < samples.start {
+                self.position = samples.start;
+            }
+            // Waiting for the decoder: play a frame of silence without moving on
+            if !self.starving {
+                self.starving = true;
+                audio.underruns.fetch_add(1, Ordering::Relaxed);
+            }
+            self.padding = audio.channels as usize - 1;
+            Some(0.0)
         } else {
             None
         }
@@ -177,7 +1100,7 @@ impl Iterator for MemoryAudioSource {
 
 impl Source for MemoryAudioSource {
     fn channels(&self) -> u16 {
-        2
+        self.decoded_audio.channels
     }
     fn sample_rate(&self) -> u32 {
         self.decoded_audio.sample_rate
@@ -186,7 +1109,10 @@ impl Source for MemoryAudioSource {
         None
     }
     fn total_duration(&self) -> Option<Duration> {
-        let total_seconds = (self.decoded_audio.duration_ms / 1000) as u64;
+        if self.decoded_audio.is_live() {
+            return None;
+        }
+        let total_seconds = (self.decoded_audio.duration_ms() / 1000) as u64;
         Some(Duration::from_secs(total_seconds))
     }
 }
@@ -197,98 +1123,894 @@ impl Clone for MemoryAudioSource {
             decoded_audio: self.decoded_audio.clone(),
             position: self.position,
             current_time_ms: self.current_time_ms.clone(),
+            boost: self.boost.clone(),
+            control: self.control.clone(),
+            mix: self.mix.clone(),
+            fade: self.fade.clone(),
+            compressor: self.compressor.clone(),
+            pitch_shifter: self.pitch_shifter.clone(),
+            padding: self.padding,
+            starving: self.starving,
         }
     }
 }
 
-pub struct Audio {
-    pub current_time_ms: Arc<Mutex<i64>>,
-    decoded_audio: Arc<DecodedAudio>,
-    sink: Sink,
-    _stream: OutputStream,
-    was_playing: Arc<Mutex<bool>>,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AudioBackend {
+    /// rodio's `Sink` on the default output device.
+    #[default]
+    Rodio,
+    /// A cpal stream fed straight from our own callback, with lower latency and
+    /// positions reported by the device.
+    Cpal,
 }
 
-impl Audio {
-    pub fn new(filename: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        println!("Loading audio file: {}", filename);
+/// The output device that outputs open from now on, by name, or `None` for the system
+/// default.
+fn selected_device() -> &'static RwLock<Option<String>> {
+    static DEVICE: OnceLock<RwLock<Option<String>>> = OnceLock::new();
+    DEVICE.get_or_init(|| RwLock::new(None))
+}
 
-        let decoded_audio = Arc::new(DecodedAudio::new(filename)?);
+/// Picks the device for outputs created after this; playing audio moves over with
+/// `Audio::set_backend`.
+pub fn select_output_device(name: Option<&str>) {
+    *selected_device().write().unwrap() = name.map(str::to_string);
+}
 
-        let (stream, stream_handle) = OutputStream::try_default()?;
-        let sink = Sink::try_new(&stream_handle)?;
+pub fn output_device_names() -> Vec<String> {
+    cpal::default_host()
+        .output_devices()
+        .map(|devices| devices.filter_map(|device| device.name().ok()).collect())
+        .unwrap_or_default()
+}
 
-        let current_time_ms = Arc::new(Mutex::new(0i64));
-        let was_playing = Arc::new(Mutex::new(true));
+pub fn default_output_device_name() -> Option<String> {
+    cpal::default_host()
+        .default_output_device()
+        .and_then(|device| device.name().ok())
+}
+
+/// The selected device, or the default one when it is gone.
+fn output_device() -> Option<cpal::Device> {
+    let host = cpal::default_host();
+    if let Some(name) = selected_device().read().unwrap().as_deref() {
+        let device = host.output_devices().ok().and_then(|mut devices| {
+            devices.find(|device| device.name().is_ok_and(|device_name| device_name == name))
+        });
+        match device {
+            Some(device) => return Some(device),
+            None => eprintln!("Output device {} not found, using the default", name),
+        }
+    }
+    host.default_output_device()
+}
 
-        let source = MemoryAudioSource::new(decoded_audio.clone(), 0, current_time_ms.clone());
+/// Plays the decoded samples on an output device.
+trait AudioOutput {
+    fn seek(&self, target_ms: i64);
+    /// Plays a short snippet from `target_ms`, also while paused.
+    fn scrub(&self, target_ms: i64);
+    /// Whether the last scrub snippet is still playing.
+    fn is_scrubbing(&self) -> bool;
+    fn current_time_ms(&self) -> i64;
+    fn is_finished(&self) -> bool;
+    fn play(&self);
+    fn pause(&self);
+    fn set_volume(&self, volume: f32);
+    /// Night mode compressor strength from 0 (off) to 1.
+    fn set_compression(&self, strength: f32);
+    /// Pitch shift on top of the speed, as a frequency ratio.
+    fn set_pitch(&self, ratio: f32);
+    /// Playback rate; the pitch changes along with it.
+    fn set_speed(&self, speed: f32);
+}
 
-        let source = source.repeat_infinite();
+/// The sink itself keeps playing; pausing is left to the source so that it can fade out.
+struct RodioOutput {
+    decoded_audio: Arc<DecodedAudio>,
+    current_time_ms: Arc<Mutex<i64>>,
+    boost: Arc<AtomicU32>,
+    control: Arc<SourceControl>,
+    mix: Arc<Mix>,
+    sink: Sink,
+    _stream: OutputStream,
+}
+
+impl RodioOutput {
+    fn new(
+        decoded_audio: Arc<DecodedAudio>,
+        mix: Arc<Mix>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let device = output_device().ok_or("No audio output device found")?;
+        let (stream, stream_handle) = OutputStream::try_from_device(&device)?;
+        let sink = Sink::try_new(&stream_handle)?;
 
+        let current_time_ms = Arc::new(Mutex::new(0i64));
+        let boost = Arc::new(AtomicU32::new(1.0f32.to_bits()));
+        let control = Arc::new(SourceControl {
+            paused: AtomicBool::new(true),
+            seek_to: AtomicUsize::new(NO_SEEK),
+            scrub: AtomicUsize::new(0),
+            compression: AtomicU32::new(0.0f32.to_bits()),
+            pitch: AtomicU32::new(1.0f32.to_bits()),
+        });
+        let source = MemoryAudioSource::new(
+            decoded_audio.clone(),
+            0,
+            current_time_ms.clone(),
+            boost.clone(),
+            control.clone(),
+            mix.clone(),
+        );
         sink.append(source);
-        sink.set_volume(0.1);
-        sink.play();
 
-        Ok(Audio {
-            current_time_ms,
+        Ok(Self {
             decoded_audio,
+            current_time_ms,
+            boost,
+            control,
+            mix,
             sink,
             _stream: stream,
-            was_playing,
         })
     }
+}
 
-    pub fn seek(&self, target_ms: i64) {
-        let was_playing = !self.sink.is_paused();
-        *self.was_playing.lock().unwrap() = was_playing;
+impl AudioOutput for RodioOutput {
+    fn seek(&self, target_ms: i64) {
+        let sample_pos = self.decoded_audio.ms_to_sample_pos(target_ms);
+        *self.current_time_ms.lock().unwrap() = target_ms;
 
-        let target_ms = target_ms.max(0).min(self.decoded_audio.duration_ms);
+        if self.sink.empty() {
+            // The source ended with the audio, so playing on needs a new one
+            let source = MemoryAudioSource::new(
+                self.decoded_audio.clone(),
+                sample_pos,
+                self.current_time_ms.clone(),
+                self.boost.clone(),
+                self.control.clone(),
+                self.mix.clone(),
+            );
+            self.sink.append(source);
+        } else {
+            self.control.seek_to.store(sample_pos, Ordering::Relaxed);
+        }
+    }
 
-        let sample_pos = self.decoded_audio.ms_to_sample_pos(target_ms);
+    fn scrub(&self, target_ms: i64) {
+        self.seek(target_ms);
+        let audio = &self.decoded_audio;
+        let length = audio.sample_rate * audio.channels as u32 * SCRUB_MS / 1000;
+        self.control.scrub.store(length as usize, Ordering::Relaxed);
+    }
 
-        *self.current_time_ms.lock().unwrap() = target_ms;
+    fn is_scrubbing(&self) -> bool {
+        self.control.scrub.load(Ordering::Relaxed) > 0
+    }
+
+    fn current_time_ms(&self) -> i64 {
+        *self.current_time_ms.lock().unwrap()
+    }
+
+    fn is_finished(&self) -> bool {
+        self.sink.empty()
+    }
+
+    fn play(&self) {
+        self.control.paused.store(false, Ordering::Relaxed);
+    }
+
+    fn pause(&self) {
+        self.control.paused.store(true, Ordering::Relaxed);
+    }
+
+    fn set_volume(&self, volume: f32) {
+        self.sink.set_volume(volume.min(1.0));
+        self.boost
+            .store(volume.max(1.0).to_bits(), Ordering::Relaxed);
+    }
+
+    fn set_compression(&self, strength: f32) {
+        self.control
+            .compression
+            .store(strength.to_bits(), Ordering::Relaxed);
+    }
+
+    fn set_pitch(&self, ratio: f32) {
+        self.control.pitch.store(ratio.to_bits(), Ordering::Relaxed);
+    }
+
+    fn set_speed(&self, speed: f32) {
+        self.sink.set_speed(speed);
+    }
+}
 
+impl Drop for RodioOutput {
+    fn drop(&mut self) {
         self.sink.stop();
-        self.sink.clear();
+    }
+}
 
-        let source = MemoryAudioSource::new(
-            self.decoded_audio.clone(),
-            sample_pos,
-            self.current_time_ms.clone(),
+struct CpalState {
+    /// Read position in sample frames; fractional when the device runs at another rate.
+    position: f64,
+    /// Position to jump to once the sound has faded out.
+    seek_to: Option<f64>,
+    /// Frames of the scrub snippet still to play, even while paused.
+    scrub_frames: usize,
+    /// Output frames in a scrub snippet.
+    scrub_length: usize,
+    /// Position of the audio reaching the speakers, after the device latency.
+    audible_ms: i64,
+    fade: Fade,
+    compressor: Compressor,
+    pitch_shifter: PitchShifter,
+    /// The source channels of the sample frame being filled.
+    frame: Vec<f32>,
+    /// Set while waiting for the decoder, so that each wait counts as one underrun.
+    starving: bool,
+}
+
+struct CpalShared {
+    decoded_audio: Arc<DecodedAudio>,
+    mix: Arc<Mix>,
+    state: Mutex<CpalState>,
+    paused: AtomicBool,
+    volume: AtomicU32,
+    /// Night mode compressor strength as f32 bits; 0 turns it off.
+    compression: AtomicU32,
+    /// Pitch shift ratio as f32 bits.
+    pitch: AtomicU32,
+    speed: AtomicU32,
+}
+
+impl CpalShared {
+    fn fill<T>(
+        &self,
+        data: &mut [T],
+        output_channels: usize,
+        output_rate: u32,
+        info: &cpal::OutputCallbackInfo,
+    ) where
+        T: cpal::Sample + cpal::FromSample<f32>,
+    {
+        let audio = &self.decoded_audio;
+        let samples = audio.samples.read().unwrap();
+        let secondary = self.mix.secondary.read().unwrap();
+        let secondary_samples = secondary
+            .as_ref()
+            .map(|audio| audio.samples.read().unwrap());
+        let channels = audio.channels as usize;
+        let speed = f32::from_bits(self.speed.load(Ordering::Relaxed)) as f64;
+        let step = audio.sample_rate as f64 / output_rate as f64 * speed;
+        let volume = f32::from_bits(self.volume.load(Ordering::Relaxed));
+        let compression = f32::from_bits(self.compression.load(Ordering::Relaxed));
+        let pitch = f32::from_bits(self.pitch.load(Ordering::Relaxed));
+        let paused = self.paused.load(Ordering::Relaxed);
+
+        let mut state = self.state.lock().unwrap();
+        if audio.buffering == Buffering::Live
+            && (state.position as usize) * channels < samples.start
+        {
+            state.position = (samples.start / channels) as f64;
+        }
+
+        let position = state.seek_to.unwrap_or(state.position);
+        let position_ms = (position * 1000.0 / audio.sample_rate as f64) as i64;
+        let timestamp = info.timestamp();
+        let latency_ms = timestamp
+            .playback
+            .duration_since(&timestamp.callback)
+            .map(|latency| latency.as_millis() as i64)
+            .unwrap_or(0);
+        state.audible_ms = if paused || state.seek_to.is_some() {
+            position_ms
+        } else {
+            (position_ms - (latency_ms as f64 * speed) as i64).max(0)
+        };
+
+        for frame in data.chunks_mut(output_channels) {
+            // Pausing and jumping wait until the sound has faded out
+            let holding = paused && state.scrub_frames == 0;
+            let fading_out = holding || state.seek_to.is_some();
+            if fading_out && state.fade.is_silent() {
+                if let Some(position) = state.seek_to.take() {
+                    state.position = position;
+                }
+                if holding {
+                    frame.fill(T::EQUILIBRIUM);
+                    continue;
+                }
+            }
+            let fade = state.fade.next(!fading_out);
+            if state.seek_to.is_none() {
+                state.scrub_frames = state.scrub_frames.saturating_sub(1);
+            }
+
+            let index = state.position as usize;
+            if samples.get(index * channels).is_none() {
+                if audio.is_decoding() && !state.starving {
+                    state.starving = true;
+                    audio.underruns.fetch_add(1, Ordering::Relaxed);
+                }
+                frame.fill(T::EQUILIBRIUM);
+                continue;
+            }
+            state.starving = false;
+
+            // Linear interpolation is enough for the occasional 44.1/48 kHz mismatch
+            let fraction = (state.position - index as f64) as f32;
+            let next = match samples.get((index + 1) * channels) {
+                Some(_) => index + 1,
+                None => index,
+            };
+
+            let value_at = |index: usize| {
+                let value = samples.get(index)?;
+                Some(self.mix.apply(value, index, secondary_samples.as_deref()))
+            };
+            let state = &mut *state;
+            for channel in 0..channels {
+                let current = value_at(index * channels + channel).unwrap_or(0.0);
+                let following = value_at(next * channels + channel).unwrap_or(current);
+                let value = current + (following - current) * fraction;
+                let value = state.pitch_shifter.process(channel, value, pitch);
+                let value = state.compressor.process(value, compression);
+                state.frame[channel] = apply_gain(value, volume) * fade;
+            }
+
+            for (channel, sample) in frame.iter_mut().enumerate() {
+                *sample = match channels {
+                    1 => T::from_sample(state.frame[0]),
+                    _ if channel < channels => T::from_sample(state.frame[channel]),
+                    _ => T::EQUILIBRIUM,
+                };
+            }
+
+            state.position += step;
+        }
+
+        let read_pos = state.position as usize * channels;
+        audio.read_pos.store(read_pos, Ordering::Relaxed);
+        if let Some(secondary) = secondary.as_ref() {
+            let index = self.mix.secondary_index(read_pos).unwrap_or(0);
+            secondary.read_pos.store(index, Ordering::Relaxed);
+        }
+    }
+}
+
+struct CpalOutput {
+    shared: Arc<CpalShared>,
+    _stream: cpal::Stream,
+}
+
+impl CpalOutput {
+    fn new(
+        decoded_audio: Arc<DecodedAudio>,
+        mix: Arc<Mix>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let device = output_device().ok_or("No audio output device found")?;
+        let config = Self::choose_config(&device, decoded_audio.sample_rate)?;
+        let output_rate = config.sample_rate().0;
+        let channels = decoded_audio.channels;
+
+        let shared = Arc::new(CpalShared {
+            decoded_audio,
+            mix,
+            state: Mutex::new(CpalState {
+                position: 0.0,
+                seek_to: None,
+                scrub_frames: 0,
+                scrub_length: (output_rate * SCRUB_MS / 1000) as usize,
+                audible_ms: 0,
+                fade: Fade::new(output_rate),
+                compressor: Compressor::new(output_rate * channels as u32),
+                pitch_shifter: PitchShifter::new(channels, output_rate),
+                frame: vec![0.0; channels as usize],
+                starving: false,
+            }),
+            paused: AtomicBool::new(true),
+            volume: AtomicU32::new(1.0f32.to_bits()),
+            compression: AtomicU32::new(0.0f32.to_bits()),
+            pitch: AtomicU32::new(1.0f32.to_bits()),
+            speed: AtomicU32::new(1.0f32.to_bits()),
+        });
+
+        let stream = match config.sample_format() {
+            cpal::SampleFormat::F32 => Self::build_stream::<f32>(&device, &config, &shared),
+            cpal::SampleFormat::I16 => Self::build_stream::<i16>(&device, &config, &shared),
+            cpal::SampleFormat::U16 => Self::build_stream::<u16>(&device, &config, &shared),
+            other => return Err(format!("Unsupported output sample format {:?}", other).into()),
+        }?;
+        stream.play()?;
+
+        println!(
+            "Opened cpal output: {} channels at {}Hz",
+            config.channels(),
+            output_rate
         );
 
-        let source = source.repeat_infinite();
+        Ok(Self {
+            shared,
+            _stream: stream,
+        })
+    }
+
+    /// Prefers running the device at the source rate so that no resampling is needed.
+    fn choose_config(
+        device: &cpal::Device,
+        sample_rate: u32,
+    ) -> Result<cpal::SupportedStreamConfig, Box<dyn std::error::Error>> {
+        let default_config = device.default_output_config()?;
+
+        let matching = device.supported_output_configs()?.find(|range| {
+            range.channels() == default_config.channels()
+                && range.sample_format() == default_config.sample_format()
+                && range.min_sample_rate().0 <= sample_rate
+                && sample_rate <= range.max_sample_rate().0
+        });
 
-        self.sink.append(source);
+        Ok(match matching {
+            Some(range) => range.with_sample_rate(cpal::SampleRate(sample_rate)),
+            None => default_config,
+        })
+    }
 
-        if was_playing {
-            self.sink.play();
+    fn build_stream<T>(
+        device: &cpal::Device,
+        config: &cpal::SupportedStreamConfig,
+        shared: &Arc<CpalShared>,
+    ) -> Result<cpal::Stream, cpal::BuildStreamError>
+    where
+        T: cpal::SizedSample + cpal::FromSample<f32>,
+    {
+        let shared = shared.clone();
+        let output_channels = config.channels() as usize;
+        let output_rate = config.sample_rate().0;
+
+        device.build_output_stream(
+            &config.config(),
+            move |data: &mut [T], info: &cpal::OutputCallbackInfo| {
+                shared.fill(data, output_channels, output_rate, info)
+            },
+            |e| eprintln!("Audio output error: {}", e),
+            None,
+        )
+    }
+}
+
+impl AudioOutput for CpalOutput {
+    fn seek(&self, target_ms: i64) {
+        let audio = &self.shared.decoded_audio;
+        let mut state = self.shared.state.lock().unwrap();
+        state.seek_to = Some(target_ms as f64 * audio.sample_rate as f64 / 1000.0);
+        state.audible_ms = target_ms;
+    }
+
+    fn scrub(&self, target_ms: i64) {
+        let audio = &self.shared.decoded_audio;
+        let mut state = self.shared.state.lock().unwrap();
+        state.seek_to = Some(target_ms as f64 * audio.sample_rate as f64 / 1000.0);
+        state.audible_ms = target_ms;
+        state.scrub_frames = state.scrub_length;
+    }
+
+    fn is_scrubbing(&self) -> bool {
+        self.shared.state.lock().unwrap().scrub_frames > 0
+    }
+
+    fn current_time_ms(&self) -> i64 {
+        self.shared.state.lock().unwrap().audible_ms
+    }
+
+    fn is_finished(&self) -> bool {
+        let audio = &self.shared.decoded_audio;
+        if audio.is_decoding() {
+            return false;
+        }
+        let end = audio.samples.read().unwrap().end();
+        let state = self.shared.state.lock().unwrap();
+        state.seek_to.unwrap_or(state.position) as usize * audio.channels as usize >= end
+    }
+
+    fn play(&self) {
+        self.shared.paused.store(false, Ordering::Relaxed);
+    }
+
+    fn pause(&self) {
+        self.shared.paused.store(true, Ordering::Relaxed);
+    }
+
+    fn set_volume(&self, volume: f32) {
+        self.shared
+            .volume
+            .store(volume.to_bits(), Ordering::Relaxed);
+    }
+
+    fn set_compression(&self, strength: f32) {
+        self.shared
+            .compression
+            .store(strength.to_bits(), Ordering::Relaxed);
+    }
+
+    fn set_pitch(&self, ratio: f32) {
+        self.shared.pitch.store(ratio.to_bits(), Ordering::Relaxed);
+    }
+
+    fn set_speed(&self, speed: f32) {
+        self.shared.speed.store(speed.to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// Audio decoded, or being decoded, without an output yet. Decoding a long file takes a
+/// while, so it can be done ahead of time on another thread.
+pub struct PreparedAudio {
+    decoded_audio: Arc<DecodedAudio>,
+}
+
+/// The index of a secondary stream being decoded on another thread, and where it arrives.
+type SecondaryLoading = (usize, mpsc::Receiver<Result<Arc<DecodedAudio>, MediaError>>);
+
+pub struct Audio {
+    decoded_audio: Arc<DecodedAudio>,
+    mix: Arc<Mix>,
+    secondary_loading: Option<SecondaryLoading>,
+    output: Box<dyn AudioOutput>,
+    /// How much later the sound plays than the positions asked for.
+    delay_ms: i64,
+    loudness: LoudnessMeter,
+    levels: LevelMeter,
+}
+
+impl Audio {
+    /// Decodes the audio up front when it fits in `memory_mb`, and otherwise while it plays.
+    pub fn new(
+        filename: &str,
+        downmix: Downmix,
+        backend: AudioBackend,
+        memory_mb: u32,
+    ) -> Result<Self, MediaError> {
+        Self::from_prepared(Self::prepare(filename, downmix, memory_mb)?, backend)
+    }
+
+    /// Plays audio decoded by `prepare`.
+    pub fn from_prepared(
+        prepared: PreparedAudio,
+        backend: AudioBackend,
+    ) -> Result<Self, MediaError> {
+        Self::with_decoded(prepared.decoded_audio, backend)
+    }
+
+    /// The decoding half of `new`.
+    pub fn prepare(
+        filename: &str,
+        downmix: Downmix,
+        memory_mb: u32,
+    ) -> Result<PreparedAudio, MediaError> {
+        println!("Loading audio file: {}", filename);
+
+        let decoded_audio = Self::decode(filename, None, Conversion::Downmix(downmix), memory_mb)?;
+        Ok(PreparedAudio { decoded_audio })
+    }
+
+    /// Decodes a stream up front when it fits in `memory_mb`, and otherwise starts decoding
+    /// it while it plays.
+    fn decode(
+        filename: &str,
+        stream_index: Option<usize>,
+        conversion: Conversion,
+        memory_mb: u32,
+    ) -> Result<Arc<DecodedAudio>, MediaError> {
+        let opened = open_audio_stream(filename, stream_index)?;
+        let (input, decoder, _, _) = &opened;
+        let duration_ms = (input.duration() / 1000).max(0);
+        let channels = conversion.output_channels(decoder.channels());
+        let decoded_values = duration_ms as u64 * decoder.rate() as u64 / 1000 * channels as u64;
+        let capacity = Self::capacity(memory_mb);
+
+        if decoded_values <= capacity as u64 {
+            Ok(Arc::new(DecodedAudio::decode_all(opened, conversion)?))
         } else {
-            self.sink.pause();
+            println!(
+                "Decoded audio would take {} MB, over the {} MB budget; decoding while playing",
+                decoded_values * 4 / 1024 / 1024,
+                memory_mb
+            );
+            drop(opened);
+            DecodedAudio::start_decoding(
+                filename,
+                stream_index,
+                conversion,
+                Buffering::Streamed,
+                false,
+                capacity,
+                duration_ms,
+            )
         }
     }
 
+    /// Plays a live stream while it is being decoded. `following` is for a file that is still
+    /// being written, such as the timeshift buffer.
+    pub fn new_live(
+        filename: &str,
+        downmix: Downmix,
+        backend: AudioBackend,
+        following: bool,
+        memory_mb: u32,
+    ) -> Result<Self, MediaError> {
+        println!("Opening live audio: {}", filename);
+
+        Self::with_decoded(
+            DecodedAudio::start_decoding(
+                filename,
+                None,
+                Conversion::Downmix(downmix),
+                Buffering::Live,
+                following,
+                Self::capacity(memory_mb),
+                0,
+            )?,
+            backend,
+        )
+    }
+
+    /// How many samples fit in `memory_mb`.
+    fn capacity(memory_mb: u32) -> usize {
+        (memory_mb as usize * 1024 * 1024 / std::mem::size_of::<f32>()).max(1024 * 1024)
+    }
+
+    fn with_decoded(
+        decoded_audio: Arc<DecodedAudio>,
+        backend: AudioBackend,
+    ) -> Result<Self, MediaError> {
+        let mix = Arc::new(Mix::default());
+        let output = Self::create_output(decoded_audio.clone(), mix.clone(), backend)?;
+
+        output.set_volume(0.1);
+        output.play();
+
+        let sample_count = match decoded_audio.buffering {
+            Buffering::Streamed => decoded_audio.ms_to_sample_pos(decoded_audio.duration_ms()),
+            _ => decoded_audio.samples.read().unwrap().end(),
+        };
+        let loudness = LoudnessMeter::new(
+            decoded_audio.channels,
+            decoded_audio.sample_rate,
+            sample_count,
+        );
+
+        let levels = LevelMeter::new(decoded_audio.channels, decoded_audio.sample_rate);
+
+        Ok(Audio {
+            decoded_audio,
+            mix,
+            secondary_loading: None,
+            output,
+            delay_ms: 0,
+            loudness,
+            levels,
+        })
+    }
+
+    fn create_output(
+        decoded_audio: Arc<DecodedAudio>,
+        mix: Arc<Mix>,
+        backend: AudioBackend,
+    ) -> Result<Box<dyn AudioOutput>, MediaError> {
+        let output_error = |e: Box<dyn std::error::Error>| MediaError::AudioOutput(e.to_string());
+
+        Ok(match backend {
+            AudioBackend::Rodio => {
+                Box::new(RodioOutput::new(decoded_audio, mix).map_err(output_error)?)
+            }
+            AudioBackend::Cpal => {
+                Box::new(CpalOutput::new(decoded_audio, mix).map_err(output_error)?)
+            }
+        })
+    }
+
+    /// Moves playback to another output backend at the current position. The new
+    /// output starts paused at full volume and normal speed; the caller restores them.
+    pub fn set_backend(&mut self, backend: AudioBackend) -> Result<(), MediaError> {
+        let position_ms = self.output.current_time_ms();
+        let output = Self::create_output(self.decoded_audio.clone(), self.mix.clone(), backend)?;
+        let position_ms = self.decoded_audio.prepare_seek(position_ms);
+        self.mix.prepare_seek(position_ms);
+        output.seek(position_ms);
+
+        self.output = output;
+        Ok(())
+    }
+
+    pub fn seek(&self, target_ms: i64) {
+        let target_ms = (target_ms - self.delay_ms)
+            .max(0)
+            .min(self.decoded_audio.duration_ms());
+        let target_ms = self.decoded_audio.prepare_seek(target_ms);
+        self.mix.prepare_seek(target_ms);
+        self.output.seek(target_ms);
+    }
+
+    /// Plays a moment of sound at `target_ms` for a seek bar being dragged, moving there
+    /// unless the last snippet is still playing. Playback goes on from there afterwards if
+    /// it was not paused.
+    pub fn scrub(&self, target_ms: i64) {
+        if self.output.is_scrubbing() {
+            return;
+        }
+        let target_ms = (target_ms - self.delay_ms)
+            .max(0)
+            .min(self.decoded_audio.duration_ms());
+        let target_ms = self.decoded_audio.prepare_seek(target_ms);
+        self.mix.prepare_seek(target_ms);
+        self.output.scrub(target_ms);
+    }
+
+    /// Index of the stream that plays, the best audio stream of the file.
+    pub fn stream_index(&self) -> usize {
+        self.decoded_audio.stream_index
+    }
+
+    /// Plays another audio stream of the file along with the main one, such as a commentary
+    /// track, or stops with `None`. It is converted to the channels and rate of the main one.
+    /// Decoding it can take a while, so it happens on another thread and `update_secondary`
+    /// mixes it in once it is ready.
+    pub fn set_secondary_stream(
+        &mut self,
+        filename: &str,
+        stream_index: Option<usize>,
+        memory_mb: u32,
+    ) {
+        self.secondary_loading = None;
+        let Some(index) = stream_index else {
+            self.mix.set_secondary(None, &self.decoded_audio, 0);
+            return;
+        };
+
+        println!("Mixing in audio stream {}", index);
+        let conversion = Conversion::Match {
+            channels: self.decoded_audio.channels,
+            sample_rate: self.decoded_audio.sample_rate,
+        };
+        let (sender, receiver) = mpsc::channel();
+        let filename = filename.to_string();
+        thread::spawn(move || {
+            let _ = sender.send(Self::decode(&filename, Some(index), conversion, memory_mb));
+        });
+        self.secondary_loading = Some((index, receiver));
+    }
+
+    /// Mixes in the secondary stream once it has been decoded far enough, returning its index
+    /// and whether that worked.
+    pub fn update_secondary(&mut self) -> Option<(usize, Result<(), MediaError>)> {
+        let (index, receiver) = self.secondary_loading.as_ref()?;
+        let result = match receiver.try_recv() {
+            Ok(result) => result,
+            Err(mpsc::TryRecvError::Empty) => return None,
+            Err(mpsc::TryRecvError::Disconnected) => {
+                Err(MediaError::Decode(ffmpeg::Error::InvalidData))
+            }
+        };
+        let index = *index;
+        self.secondary_loading = None;
+
+        Some((
+            index,
+            result.map(|secondary| {
+                let position_ms = self.output.current_time_ms();
+                self.mix
+                    .set_secondary(Some(secondary), &self.decoded_audio, position_ms);
+            }),
+        ))
+    }
+
+    /// The secondary stream mixed in, or the one being decoded to be.
+    pub fn secondary_stream(&self) -> Option<usize> {
+        if let Some((index, _)) = &self.secondary_loading {
+            return Some(*index);
+        }
+        self.mix
+            .secondary
+            .read()
+            .unwrap()
+            .as_ref()
+            .map(|audio| audio.stream_index)
+    }
+
+    /// Sets the share of the secondary stream from 0 (main only) to 1 (secondary only).
+    pub fn set_mix_balance(&self, balance: f32) {
+        self.mix
+            .balance
+            .store(balance.clamp(0.0, 1.0).to_bits(), Ordering::Relaxed);
+    }
+
     pub fn get_current_time(&self) -> i64 {
-        *self.current_time_ms.lock().unwrap()
+        self.output.current_time_ms() + self.delay_ms
+    }
+
+    /// Plays the sound `delay_ms` later than the picture, or earlier when negative, to make
+    /// up for a file whose streams are out of sync.
+    pub fn set_delay(&mut self, delay_ms: i64) {
+        let position_ms = self.get_current_time();
+        self.delay_ms = delay_ms;
+        self.seek(position_ms);
+    }
+
+    /// How often playback has had to wait for the decoder.
+    pub fn underruns(&self) -> usize {
+        self.decoded_audio.underruns.load(Ordering::Relaxed)
+    }
+
+    /// For a live stream, how much has been decoded so far, i.e. the live edge.
+    pub fn get_duration_ms(&self) -> i64 {
+        self.decoded_audio.duration_ms()
+    }
+
+    /// Whether a live stream is still coming in.
+    pub fn is_live(&self) -> bool {
+        self.decoded_audio.is_live()
+    }
+
+    /// Stops waiting for a followed file to grow, so that its end is the end of the stream.
+    pub fn stop_following(&self) {
+        self.decoded_audio.following.store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.output.is_finished()
     }
 
     pub fn pause(&self) {
-        *self.was_playing.lock().unwrap() = false;
-        self.sink.pause();
+        self.output.pause();
     }
 
     pub fn play(&self) {
-        *self.was_playing.lock().unwrap() = true;
-        self.sink.play();
+        self.output.play();
     }
 
+    /// Sets the gain; values above 1.0 amplify with soft clipping.
     pub fn set_volume(&self, volume: f32) {
-        self.sink.set_volume(volume);
+        self.output.set_volume(volume);
     }
-}
 
-impl Drop for Audio {
-    fn drop(&mut self) {
-        self.sink.stop();
+    /// Turns the night mode compressor on with `strength` from 0 to 1, or off with 0.
+    pub fn set_compression(&self, strength: f32) {
+        self.output.set_compression(strength.clamp(0.0, 1.0));
+    }
+
+    pub fn set_speed(&self, speed: f32) {
+        self.output.set_speed(speed);
+    }
+
+    /// Shifts the pitch by `ratio` without changing the speed, e.g. to undo the pitch change
+    /// of playing faster or slower.
+    pub fn set_pitch(&self, ratio: f32) {
+        self.output.set_pitch(ratio.clamp(0.25, 4.0));
+    }
+
+    /// Measures the loudness of what has played since the last call. The meter works on
+    /// the decoded samples, so it is not affected by the volume.
+    pub fn update_loudness(&mut self) -> &LoudnessMeter {
+        let position_ms = self.output.current_time_ms();
+        self.loudness
+            .update(&self.decoded_audio.samples.read().unwrap(), position_ms);
+        &self.loudness
+    }
+
+    pub fn loudness(&self) -> &LoudnessMeter {
+        &self.loudness
+    }
+
+    /// Measures the level of each channel at the playback position, before the volume.
+    pub fn update_levels(&mut self) -> &LevelMeter {
+        let position_ms = self.output.current_time_ms();
+        self.levels
+            .update(&self.decoded_audio.samples.read().unwrap(), position_ms);
+        &self.levels
+    }
+
+    pub fn levels(&self) -> &LevelMeter {
+        &self.levels
     }
 }
@@ -0,0 +1,98 @@
+use crate::audio::{self, AudioBackend, Downmix};
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// How often the output devices are listed again.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Audio settings remembered for one output device.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioProfile {
+    pub volume: f32,
+    pub downmix: Downmix,
+    pub backend: AudioBackend,
+}
+
+impl Default for AudioProfile {
+    fn default() -> Self {
+        Self {
+            volume: 0.7,
+            downmix: Downmix::default(),
+            backend: AudioBackend::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioProfiles {
+    /// Switch to the settings of a device when playback moves to it.
+    pub enabled: bool,
+    /// Settings last used with each output device, by name.
+    pub devices: HashMap<String, AudioProfile>,
+    /// Output device picked while the window was on each monitor, by the monitor's size;
+    /// egui doesn't tell monitors apart otherwise.
+    pub monitors: HashMap<String, String>,
+}
+
+impl Default for AudioProfiles {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            devices: HashMap::new(),
+            monitors: HashMap::new(),
+        }
+    }
+}
+
+pub fn monitor_key(size: egui::Vec2) -> String {
+    format!("{}x{}", size.x.round(), size.y.round())
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OutputDevices {
+    /// The system's default device, which changes e.g. when headphones are plugged in.
+    pub default: Option<String>,
+    pub names: Vec<String>,
+}
+
+/// Lists the output devices on a thread of its own, as that can take a moment, and reports
+/// whenever they change.
+pub struct DeviceWatcher {
+    receiver: mpsc::Receiver<OutputDevices>,
+}
+
+impl DeviceWatcher {
+    pub fn start() -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            let mut last = None;
+            loop {
+                let devices = OutputDevices {
+                    default: audio::default_output_device_name(),
+                    names: audio::output_device_names(),
+                };
+                if last.as_ref() != Some(&devices) {
+                    if sender.send(devices.clone()).is_err() {
+                        return;
+                    }
+                    last = Some(devices);
+                }
+                thread::sleep(POLL_INTERVAL);
+            }
+        });
+
+        Self { receiver }
+    }
+
+    /// The devices, if they changed since the last call.
+    pub fn poll(&self) -> Option<OutputDevices> {
+        self.receiver.try_iter().last()
+    }
+}
@@ -0,0 +1,44 @@
+/// Offset between the clocks past which audio is considered out of sync.
+pub const THRESHOLD_MS: i64 = 200;
+/// A change in the offset this large is a seek rather than drift, and starts over.
+const JUMP_MS: f64 = 1000.0;
+/// Samples taken after a start over before the offset is reported.
+const SETTLE_SAMPLES: u32 = 15;
+/// Weight of each new sample in the smoothed offset.
+const SMOOTHING: f64 = 0.1;
+
+/// Tracks how far the audio clock is from the video clock, smoothed over the frames shown.
+/// Positive means the audio is behind.
+#[derive(Default)]
+pub struct AvSync {
+    offset_ms: f64,
+    samples: u32,
+}
+
+impl AvSync {
+    pub fn update(&mut self, video_ms: i64, audio_ms: i64) {
+        let offset_ms = (video_ms - audio_ms) as f64;
+        if self.samples == 0 || (offset_ms - self.offset_ms).abs() > JUMP_MS {
+            self.offset_ms = offset_ms;
+            self.samples = 1;
+            return;
+        }
+
+        self.offset_ms += (offset_ms - self.offset_ms) * SMOOTHING;
+        self.samples = self.samples.saturating_add(1);
+    }
+
+    pub fn reset(&mut self) {
+        self.samples = 0;
+    }
+
+    /// The smoothed offset, once enough frames were shown to tell.
+    pub fn offset_ms(&self) -> Option<i64> {
+        (self.samples >= SETTLE_SAMPLES).then_some(self.offset_ms.round() as i64)
+    }
+
+    pub fn is_out_of_sync(&self) -> bool {
+        self.offset_ms()
+            .is_some_and(|offset_ms| offset_ms.abs() > THRESHOLD_MS)
+    }
+}
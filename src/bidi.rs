@@ -0,0 +1,280 @@
+//! Just enough of the Unicode bidirectional algorithm and Arabic shaping for subtitle lines.
+//! egui lays text out left to right one character at a time, so right-to-left text is put
+//! into display order, and Arabic letters into their joined forms, before it is drawn.
+
+#[derive(Clone, Copy, PartialEq)]
+enum Class {
+    Left,
+    Right,
+    Neutral,
+}
+
+fn class(c: char) -> Class {
+    match c {
+        // Arabic-Indic digits read left to right like any other number
+        '\u{0660}'..='\u{0669}' | '\u{06F0}'..='\u{06F9}' => Class::Left,
+        '\u{0590}'..='\u{08FF}' | '\u{FB1D}'..='\u{FDFF}' | '\u{FE70}'..='\u{FEFF}' => Class::Right,
+        _ if c.is_alphanumeric() => Class::Left,
+        _ => Class::Neutral,
+    }
+}
+
+/// Puts a line into the order it is displayed in, with Arabic letters shaped.
+/// Lines without right-to-left letters are returned as they are.
+pub fn visual_line(line: &str) -> String {
+    let chars = shape_arabic(line);
+
+    // A letter and the vowel marks on it move as one
+    let mut clusters: Vec<(usize, usize)> = Vec::new();
+    for (index, c) in chars.iter().enumerate() {
+        match clusters.last_mut() {
+            Some(cluster) if is_transparent(*c) => cluster.1 = index + 1,
+            _ => clusters.push((index, index + 1)),
+        }
+    }
+    let classes: Vec<Class> = clusters
+        .iter()
+        .map(|(start, _)| class(chars[*start]))
+        .collect();
+    if !classes.contains(&Class::Right) {
+        return line.to_string();
+    }
+
+    // The first strong letter sets the direction of the line
+    let rtl = classes
+        .iter()
+        .find(|class| **class != Class::Neutral)
+        .is_some_and(|class| *class == Class::Right);
+
+    // Neutrals between letters of one direction take it, others take the line's
+    let mut resolved = classes.clone();
+    let mut index = 0;
+    while index < classes.len() {
+        if classes[index] != Class::Neutral {
+            index += 1;
+            continue;
+        }
+        let start = index;
+        while index < classes.len() && classes[index] == Class::Neutral {
+            index += 1;
+        }
+        let before = start.checked_sub(1).map(|i| classes[i]);
+        let after = classes.get(index).copied();
+        let direction = match (before, after) {
+            (Some(before), Some(after)) if before == after => before,
+            _ if rtl => Class::Right,
+            _ => Class::Left,
+        };
+        resolved[start..index].fill(direction);
+    }
+
+    let levels: Vec<u8> = resolved
+        .iter()
+        .map(|class| match class {
+            Class::Right => 1,
+            _ if rtl => 2,
+            _ => 0,
+        })
+        .collect();
+
+    // Reverse every run at each level or above, from the highest level down to 1
+    let mut order: Vec<usize> = (0..clusters.len()).collect();
+    let highest = levels.iter().copied().max().unwrap_or(0);
+    for level in (1..=highest).rev() {
+        let mut start = 0;
+        while start < order.len() {
+            if levels[order[start]] < level {
+                start += 1;
+                continue;
+            }
+            let mut end = start;
+            while end < order.len() && levels[order[end]] >= level {
+                end += 1;
+            }
+            order[start..end].reverse();
+            start = end;
+        }
+    }
+
+    let mut visual = String::with_capacity(line.len());
+    for index in order {
+        let (start, end) = clusters[index];
+        for c in &chars[start..end] {
+            visual.push(if levels[index] % 2 == 1 {
+                mirror(*c)
+            } else {
+                *c
+            });
+        }
+    }
+    visual
+}
+
+/// Brackets point the other way in right-to-left text.
+fn mirror(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        '«' => '»',
+        '»' => '«',
+        _ => c,
+    }
+}
+
+/// Hebrew points and Arabic vowel marks, which sit on a letter without affecting how it joins.
+fn is_transparent(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0591}'..='\u{05BD}'
+            | '\u{05BF}'
+            | '\u{05C1}'..='\u{05C2}'
+            | '\u{05C4}'..='\u{05C5}'
+            | '\u{05C7}'
+            | '\u{0610}'..='\u{061A}'
+            | '\u{064B}'..='\u{065F}'
+            | '\u{0670}'
+    )
+}
+
+/// Presentation forms of a letter: isolated, final, and for letters that also join the
+/// next one, initial and medial.
+struct Forms {
+    isolated: u32,
+    dual: bool,
+}
+
+fn forms(c: char) -> Option<Forms> {
+    let right = |isolated| {
+        Some(Forms {
+            isolated,
+            dual: false,
+        })
+    };
+    let dual = |isolated| {
+        Some(Forms {
+            isolated,
+            dual: true,
+        })
+    };
+    match c {
+        '\u{0622}' => right(0xFE81),
+        '\u{0623}' => right(0xFE83),
+        '\u{0624}' => right(0xFE85),
+        '\u{0625}' => right(0xFE87),
+        '\u{0626}' => dual(0xFE89),
+        '\u{0627}' => right(0xFE8D),
+        '\u{0628}' => dual(0xFE8F),
+        '\u{0629}' => right(0xFE93),
+        '\u{062A}' => dual(0xFE95),
+        '\u{062B}' => dual(0xFE99),
+        '\u{062C}' => dual(0xFE9D),
+        '\u{062D}' => dual(0xFEA1),
+        '\u{062E}' => dual(0xFEA5),
+        '\u{062F}' => right(0xFEA9),
+        '\u{0630}' => right(0xFEAB),
+        '\u{0631}' => right(0xFEAD),
+        '\u{0632}' => right(0xFEAF),
+        '\u{0633}' => dual(0xFEB1),
+        '\u{0634}' => dual(0xFEB5),
+        '\u{0635}' => dual(0xFEB9),
+        '\u{0636}' => dual(0xFEBD),
+        '\u{0637}' => dual(0xFEC1),
+        '\u{0638}' => dual(0xFEC5),
+        '\u{0639}' => dual(0xFEC9),
+        '\u{063A}' => dual(0xFECD),
+        '\u{0641}' => dual(0xFED1),
+        '\u{0642}' => dual(0xFED5),
+        '\u{0643}' => dual(0xFED9),
+        '\u{0644}' => dual(0xFEDD),
+        '\u{0645}' => dual(0xFEE1),
+        '\u{0646}' => dual(0xFEE5),
+        '\u{0647}' => dual(0xFEE9),
+        '\u{0648}' => right(0xFEED),
+        '\u{0649}' => right(0xFEEF),
+        '\u{064A}' => dual(0xFEF1),
+        // Persian and Urdu letters
+        '\u{067E}' => dual(0xFB56),
+        '\u{0686}' => dual(0xFB7A),
+        '\u{0698}' => right(0xFB8A),
+        '\u{06A9}' => dual(0xFB8E),
+        '\u{06AF}' => dual(0xFB92),
+        '\u{06CC}' => dual(0xFBFC),
+        _ => None,
+    }
+}
+
+/// Whether the letter connects to the one after it.
+fn joins_next(c: char) -> bool {
+    c == '\u{0640}' || forms(c).is_some_and(|forms| forms.dual)
+}
+
+/// Whether the letter connects to the one before it.
+fn joins_previous(c: char) -> bool {
+    c == '\u{0640}' || forms(c).is_some()
+}
+
+/// Isolated and final forms of lam followed by the alef.
+fn lam_alef(alef: char) -> Option<u32> {
+    match alef {
+        '\u{0622}' => Some(0xFEF5),
+        '\u{0623}' => Some(0xFEF7),
+        '\u{0625}' => Some(0xFEF9),
+        '\u{0627}' => Some(0xFEFB),
+        _ => None,
+    }
+}
+
+/// Replaces Arabic letters with the presentation form for their position in the word.
+fn shape_arabic(line: &str) -> Vec<char> {
+    let chars: Vec<char> = line.chars().collect();
+    let neighbour = |index: usize, step: isize| {
+        let mut index = index as isize + step;
+        while index >= 0 && (index as usize) < chars.len() {
+            let c = chars[index as usize];
+            if !is_transparent(c) {
+                return Some(c);
+            }
+            index += step;
+        }
+        None
+    };
+
+    let mut shaped = Vec::with_capacity(chars.len());
+    let mut index = 0;
+    while index < chars.len() {
+        let c = chars[index];
+        let Some(letter) = forms(c) else {
+            shaped.push(c);
+            index += 1;
+            continue;
+        };
+
+        let joined_to_previous = neighbour(index, -1).is_some_and(joins_next);
+
+        if c == '\u{0644}' {
+            if let Some(ligature) = chars.get(index + 1).and_then(|alef| lam_alef(*alef)) {
+                let form = ligature + joined_to_previous as u32;
+                shaped.push(char::from_u32(form).unwrap_or(c));
+                index += 2;
+                continue;
+            }
+        }
+
+        let joined_to_next = letter.dual && neighbour(index, 1).is_some_and(joins_previous);
+        let offset = match (joined_to_previous, joined_to_next) {
+            (false, false) => 0,
+            (true, false) => 1,
+            (false, true) => 2,
+            (true, true) => 3,
+        };
+        shaped.push(char::from_u32(letter.isolated + offset).unwrap_or(c));
+        index += 1;
+    }
+    shaped
+}
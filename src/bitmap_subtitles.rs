@@ -0,0 +1,265 @@
+extern crate ffmpeg_next as ffmpeg;
+
+use crate::error::MediaError;
+use eframe::egui;
+use ffmpeg::{codec, format, subtitle, Packet, Rational, Rescale};
+use std::collections::VecDeque;
+
+const MS_TIME_BASE: Rational = Rational(1, 1000);
+
+/// Image-based subtitle codecs: Blu-ray PGS and DVD VobSub.
+pub fn is_bitmap_codec(id: codec::Id) -> bool {
+    matches!(id, codec::Id::HDMV_PGS_SUBTITLE | codec::Id::DVD_SUBTITLE)
+}
+
+/// One subtitle image in canvas coordinates, as straight-alpha RGBA.
+pub struct BitmapRect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+pub struct BitmapCue {
+    /// Changes whenever a new cue is decoded, so cached textures know when to update.
+    pub id: u64,
+    pub start_ms: i64,
+    /// PGS cues (and some DVD ones) have no end and last until the next cue.
+    pub end_ms: Option<i64>,
+    pub rects: Vec<BitmapRect>,
+}
+
+/// Decodes a PGS or VobSub stream from the packets the video demuxer reads anyway,
+/// keeping the cues that haven't been shown yet.
+pub struct BitmapSubtitleTrack {
+    stream_index: usize,
+    decoder: ffmpeg::decoder::Subtitle,
+    time_base: Rational,
+    canvas_width: usize,
+    canvas_height: usize,
+    cues: VecDeque<BitmapCue>,
+    next_id: u64,
+}
+
+impl BitmapSubtitleTrack {
+    /// The video size is used as the canvas when the stream doesn't declare one.
+    pub fn new(
+        stream: &format::stream::Stream,
+        video_width: usize,
+        video_height: usize,
+    ) -> Result<Self, MediaError> {
+        Self::from_parameters(
+            stream.index(),
+            stream.parameters(),
+            stream.time_base(),
+            video_width,
+            video_height,
+        )
+    }
+
+    /// Like `new`, for a stream whose parameters were copied from the input.
+    pub fn from_parameters(
+        stream_index: usize,
+        parameters: codec::Parameters,
+        time_base: Rational,
+        video_width: usize,
+        video_height: usize,
+    ) -> Result<Self, MediaError> {
+        let codec_id = parameters.id();
+        if !is_bitmap_codec(codec_id) {
+            return Err(MediaError::UnsupportedCodec(format!("{:?}", codec_id)));
+        }
+
+        let (width, height) = unsafe {
+            let ptr = parameters.as_ptr();
+            ((*ptr).width.max(0) as usize, (*ptr).height.max(0) as usize)
+        };
+
+        let decoder = codec::Context::from_parameters(parameters)
+            .and_then(|context| context.decoder().subtitle())
+            .map_err(|e| MediaError::decoder(e, codec_id))?;
+
+        Ok(Self {
+            stream_index,
+            decoder,
+            time_base,
+            canvas_width: if width > 0 { width } else { video_width },
+            canvas_height: if height > 0 { height } else { video_height },
+            cues: VecDeque::new(),
+            next_id: 0,
+        })
+    }
+
+    pub fn stream_index(&self) -> usize {
+        self.stream_index
+    }
+
+    /// Size of the frame the cue positions refer to.
+    pub fn canvas_size(&self) -> (usize, usize) {
+        (self.canvas_width, self.canvas_height)
+    }
+
+    pub fn decode(&mut self, packet: &Packet) -> Result<(), ffmpeg::Error> {
+        let mut decoded = ffmpeg::Subtitle::new();
+        if !self.decoder.decode(packet, &mut decoded)? {
+            return Ok(());
+        }
+
+        let packet_ms = packet
+            .pts()
+            .map(|pts| pts.rescale(self.time_base, MS_TIME_BASE))
+            .unwrap_or(0);
+        let start_ms = packet_ms + decoded.start() as i64;
+        let end_ms = match decoded.end() {
+            0 | u32::MAX => None,
+            end => Some(packet_ms + end as i64),
+        };
+        let rects = decoded
+            .rects()
+            .filter_map(|rect| match rect {
+                subtitle::Rect::Bitmap(bitmap) => to_rgba(&bitmap),
+                _ => None,
+            })
+            .collect();
+
+        // The Rust wrapper doesn't free the decoded images
+        unsafe {
+            ffmpeg::ffi::avsubtitle_free(decoded.as_mut_ptr());
+        }
+
+        self.cues.push_back(BitmapCue {
+            id: self.next_id,
+            start_ms,
+            end_ms,
+            rects,
+        });
+        self.next_id += 1;
+
+        Ok(())
+    }
+
+    /// Drops pending cues after a seek; the decoder picks up again at the next cue.
+    pub fn flush(&mut self) {
+        self.decoder.flush();
+        self.cues.clear();
+    }
+
+    /// Takes the decoded cues, for reading the whole stream rather than playing it.
+    pub fn take_cues(&mut self) -> impl Iterator<Item = BitmapCue> + '_ {
+        self.cues.drain(..)
+    }
+
+    pub fn cue_at(&mut self, time_ms: i64) -> Option<&BitmapCue> {
+        // A cue is replaced as soon as the next one starts
+        while self.cues.len() > 1 && self.cues[1].start_ms <= time_ms {
+            self.cues.pop_front();
+        }
+
+        self.cues.front().filter(|cue| {
+            cue.start_ms <= time_ms
+                && cue.end_ms.map_or(true, |end_ms| time_ms < end_ms)
+                && !cue.rects.is_empty()
+        })
+    }
+}
+
+/// Expands the 8-bit palette indices. The palette is ARGB with straight alpha.
+fn to_rgba(bitmap: &subtitle::Bitmap) -> Option<BitmapRect> {
+    let width = bitmap.width() as usize;
+    let height = bitmap.height() as usize;
+    if width == 0 || height == 0 {
+        return None;
+    }
+
+    let (indices, stride, palette) = unsafe {
+        let rect = &*bitmap.as_ptr();
+        if rect.data[0].is_null() || rect.data[1].is_null() {
+            return None;
+        }
+        let stride = rect.linesize[0] as usize;
+        if stride < width {
+            return None;
+        }
+        (
+            std::slice::from_raw_parts(rect.data[0], stride * height),
+            stride,
+            std::slice::from_raw_parts(rect.data[1] as *const u32, bitmap.colors().min(256)),
+        )
+    };
+
+    let mut pixels = Vec::with_capacity(width * height * 4);
+    for row in indices.chunks_exact(stride) {
+        for &index in &row[..width] {
+            let argb = palette.get(index as usize).copied().unwrap_or(0);
+            pixels.extend_from_slice(&[
+                (argb >> 16) as u8,
+                (argb >> 8) as u8,
+                argb as u8,
+                (argb >> 24) as u8,
+            ]);
+        }
+    }
+
+    Some(BitmapRect {
+        x: bitmap.x(),
+        y: bitmap.y(),
+        width,
+        height,
+        pixels,
+    })
+}
+
+/// Keeps the textures of the cue on screen so they are only uploaded once.
+#[derive(Default)]
+pub struct BitmapSubtitleTextures {
+    cue_id: Option<u64>,
+    textures: Vec<(egui::Rect, egui::TextureHandle)>,
+}
+
+impl BitmapSubtitleTextures {
+    /// Scales the cue from its canvas onto the video rectangle.
+    pub fn draw(
+        &mut self,
+        ctx: &egui::Context,
+        painter: &egui::Painter,
+        video_rect: egui::Rect,
+        cue: &BitmapCue,
+        canvas_size: (usize, usize),
+    ) {
+        if self.cue_id != Some(cue.id) {
+            self.cue_id = Some(cue.id);
+            self.textures = cue
+                .rects
+                .iter()
+                .map(|rect| {
+                    let image = egui::ColorImage::from_rgba_unmultiplied(
+                        [rect.width, rect.height],
+                        &rect.pixels,
+                    );
+                    let texture =
+                        ctx.load_texture("bitmap_subtitle", image, egui::TextureOptions::LINEAR);
+                    let area = egui::Rect::from_min_size(
+                        egui::pos2(rect.x as f32, rect.y as f32),
+                        egui::vec2(rect.width as f32, rect.height as f32),
+                    );
+                    (area, texture)
+                })
+                .collect();
+        }
+
+        let scale = egui::vec2(
+            video_rect.width() / canvas_size.0.max(1) as f32,
+            video_rect.height() / canvas_size.1.max(1) as f32,
+        );
+        let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+
+        for (area, texture) in &self.textures {
+            let screen_rect = egui::Rect::from_min_max(
+                video_rect.min + area.min.to_vec2() * scale,
+                video_rect.min + area.max.to_vec2() * scale,
+            );
+            painter.image(texture.id(), screen_rect, uv, egui::Color32::WHITE);
+        }
+    }
+}
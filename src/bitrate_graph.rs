@@ -0,0 +1,235 @@
+extern crate ffmpeg_next as ffmpeg;
+
+use crate::http;
+use crate::i18n::tr;
+use eframe::egui;
+use egui_plot::{Line, Plot, PlotPoints, VLine};
+use ffmpeg::{Rational, Rescale};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const GRAPH_COLOR: egui::Color32 = egui::Color32::from_rgb(100, 150, 255);
+const PLOT_HEIGHT: f32 = 160.0;
+
+/// Bits of every stream, summed per second of its packet timestamps.
+#[derive(Default)]
+pub struct BitrateHistory {
+    pub streams: BTreeMap<usize, Vec<u64>>,
+}
+
+impl BitrateHistory {
+    fn add(&mut self, stream_index: usize, time_ms: i64, bytes: usize) {
+        let second = (time_ms.max(0) / 1000) as usize;
+        let seconds = self.streams.entry(stream_index).or_default();
+        if seconds.len() <= second {
+            seconds.resize(second + 1, 0);
+        }
+        seconds[second] += bytes as u64 * 8;
+    }
+
+    pub fn stream(&self, stream_index: usize) -> Option<&[u64]> {
+        self.streams.get(&stream_index).map(Vec::as_slice)
+    }
+}
+
+enum ScanEvent {
+    Progress(i64),
+    Finished(Result<BitrateHistory, String>),
+}
+
+/// Reads every packet of the file on a worker thread. Dropping it stops the scan.
+pub struct BitrateScan {
+    receiver: mpsc::Receiver<ScanEvent>,
+    cancel: Arc<AtomicBool>,
+    duration_ms: i64,
+    position_ms: i64,
+    result: Option<Result<BitrateHistory, String>>,
+}
+
+impl BitrateScan {
+    pub fn start(filename: &str, duration_ms: i64) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancel_flag = cancel.clone();
+        let filename = filename.to_string();
+
+        thread::spawn(move || {
+            let result = scan(&filename, &sender, &cancel_flag);
+            let _ = sender.send(ScanEvent::Finished(result.map_err(|e| e.to_string())));
+        });
+
+        Self {
+            receiver,
+            cancel,
+            duration_ms,
+            position_ms: 0,
+            result: None,
+        }
+    }
+
+    /// Returns true while the scan is still running.
+    pub fn update(&mut self) -> bool {
+        for event in self.receiver.try_iter() {
+            match event {
+                ScanEvent::Progress(position_ms) => self.position_ms = position_ms,
+                ScanEvent::Finished(result) => self.result = Some(result),
+            }
+        }
+        self.result.is_none()
+    }
+
+    pub fn history(&self) -> Option<&BitrateHistory> {
+        self.result.as_ref()?.as_ref().ok()
+    }
+
+    /// Draws progress or the graph of one stream. Returns the time to seek to when
+    /// the graph is clicked.
+    pub fn show(&self, ui: &mut egui::Ui, stream_index: usize, current_ms: i64) -> Option<i64> {
+        match &self.result {
+            None => {
+                let progress = if self.duration_ms > 0 {
+                    self.position_ms as f32 / self.duration_ms as f32
+                } else {
+                    0.0
+                };
+                ui.label(tr!("bitrate-reading"));
+                ui.add(egui::ProgressBar::new(progress.clamp(0.0, 1.0)).show_percentage());
+                None
+            }
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::LIGHT_RED, tr!("bitrate-failed", error = e));
+                None
+            }
+            Some(Ok(history)) => match history.stream(stream_index) {
+                Some(seconds) => show_plot(ui, seconds, current_ms),
+                None => {
+                    ui.label(tr!("bitrate-no-packets"));
+                    None
+                }
+            },
+        }
+    }
+}
+
+impl Drop for BitrateScan {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+fn scan(
+    filename: &str,
+    sender: &mpsc::Sender<ScanEvent>,
+    cancel: &AtomicBool,
+) -> Result<BitrateHistory, Box<dyn std::error::Error>> {
+    let mut input = http::open_input(&filename)?;
+    let mut history = BitrateHistory::default();
+    let mut last_times_ms = vec![0; input.streams().count()];
+    let mut last_progress = Instant::now();
+
+    for (stream, packet) in input.packets() {
+        if cancel.load(Ordering::Relaxed) {
+            return Err("Cancelled".into());
+        }
+
+        // Packets without timestamps belong to the second of the one before
+        let index = stream.index();
+        if let Some(ts) = packet.pts().or(packet.dts()) {
+            last_times_ms[index] = ts.rescale(stream.time_base(), Rational(1, 1000));
+        }
+        let time_ms = last_times_ms[index];
+        history.add(index, time_ms, packet.size());
+
+        if last_progress.elapsed() > Duration::from_millis(100) {
+            last_progress = Instant::now();
+            let _ = sender.send(ScanEvent::Progress(time_ms));
+        }
+    }
+
+    Ok(history)
+}
+
+fn format_bitrate(bits_per_second: f64) -> String {
+    if bits_per_second >= 1_000_000.0 {
+        format!("{:.2} Mbps", bits_per_second / 1_000_000.0)
+    } else {
+        format!("{:.0} kbps", bits_per_second / 1000.0)
+    }
+}
+
+fn show_plot(ui: &mut egui::Ui, seconds: &[u64], current_ms: i64) -> Option<i64> {
+    let average = seconds.iter().sum::<u64>() as f64 / seconds.len().max(1) as f64;
+    let peak = seconds.iter().copied().max().unwrap_or(0);
+    ui.label(format!(
+        "Average {}, peak {}",
+        format_bitrate(average),
+        format_bitrate(peak as f64)
+    ));
+
+    // Steps rather than a slope, since each value covers a whole second
+    let points: Vec<[f64; 2]> = seconds
+        .iter()
+        .enumerate()
+        .flat_map(|(second, &bits)| {
+            let kbps = bits as f64 / 1000.0;
+            [[second as f64, kbps], [second as f64 + 1.0, kbps]]
+        })
+        .collect();
+
+    let response = Plot::new("bitrate_plot")
+        .height(PLOT_HEIGHT)
+        .allow_scroll(false)
+        .allow_zoom(false)
+        .allow_drag(false)
+        .allow_boxed_zoom(false)
+        .include_y(0.0)
+        .x_axis_label(tr!("bitrate-axis-seconds"))
+        .y_axis_label("kbps")
+        .label_formatter(|_, point| format!("{:.0} s\n{:.0} kbps", point.x.floor(), point.y))
+        .show(ui, |plot_ui| {
+            plot_ui.line(
+                Line::new(PlotPoints::new(points))
+                    .color(GRAPH_COLOR)
+                    .fill(0.0),
+            );
+            plot_ui.vline(VLine::new(current_ms as f64 / 1000.0).color(egui::Color32::WHITE));
+            plot_ui.pointer_coordinate()
+        });
+
+    if response.response.clicked() {
+        let point = response.inner?;
+        return Some((point.x.max(0.0) * 1000.0) as i64);
+    }
+
+    None
+}
+
+/// Draws the bitrate as a faint area graph whose bottom edge is `rect.bottom()`,
+/// spread over `duration_ms`.
+pub fn draw_overlay(painter: &egui::Painter, rect: egui::Rect, seconds: &[u64], duration_ms: i64) {
+    let peak = seconds.iter().copied().max().unwrap_or(0);
+    if peak == 0 || duration_ms <= 0 {
+        return;
+    }
+
+    let second_width = rect.width() * 1000.0 / duration_ms as f32;
+    let color = GRAPH_COLOR.gamma_multiply(0.5);
+    for (second, &bits) in seconds.iter().enumerate() {
+        let left = rect.left() + second as f32 * second_width;
+        if left >= rect.right() {
+            break;
+        }
+        let height = rect.height() * bits as f32 / peak as f32;
+        painter.rect_filled(
+            egui::Rect::from_min_max(
+                egui::pos2(left, rect.bottom() - height),
+                egui::pos2((left + second_width).min(rect.right()), rect.bottom()),
+            ),
+            egui::Rounding::ZERO,
+            color,
+        );
+    }
+}
@@ -0,0 +1,253 @@
+const ROWS: usize = 15;
+const COLUMNS: usize = 32;
+
+/// PAC row (1-based) for the first byte of a preamble address code, indexed by its low 3 bits.
+const PAC_ROWS: [usize; 8] = [11, 1, 3, 12, 14, 5, 7, 9];
+
+const SPECIAL_CHARS: [char; 16] = [
+    '®', '°', '½', '¿', '™', '¢', '£', '♪', 'à', ' ', 'è', 'â', 'ê', 'î', 'ô', 'û',
+];
+const EXTENDED_CHARS_1: &str = "ÁÉÓÚÜü‘¡*'—©℠•“”ÀÂÇÈÊËëÎÏïÔÙùÛ«»";
+const EXTENDED_CHARS_2: &str = "ÃãÍÌìÒòÕõ{}\\^_|~ÄäÖöß¥¤¦ÅåØø┌┐└┘";
+
+type Screen = [[char; COLUMNS]; ROWS];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    PopOn,
+    RollUp(usize),
+    PaintOn,
+    /// Text mode data is not caption text and is ignored.
+    Text,
+}
+
+/// Decodes the CC1 service of CEA-608 closed captions from A/53 caption data
+/// (the `cc_data` triplets FFmpeg attaches to decoded frames). CEA-708 service
+/// blocks are skipped; broadcasts carry the 608 captions alongside them.
+pub struct Cea608Decoder {
+    displayed: Screen,
+    non_displayed: Screen,
+    mode: Mode,
+    row: usize,
+    column: usize,
+    /// Data channel of the last control code; characters for CC2 are skipped.
+    channel: u8,
+    last_control: Option<(u8, u8)>,
+    found: bool,
+}
+
+impl Cea608Decoder {
+    pub fn new() -> Self {
+        Self {
+            displayed: [[' '; COLUMNS]; ROWS],
+            non_displayed: [[' '; COLUMNS]; ROWS],
+            mode: Mode::PopOn,
+            row: ROWS - 1,
+            column: 0,
+            channel: 1,
+            last_control: None,
+            found: false,
+        }
+    }
+
+    /// Clears the screen after a seek so that stale captions are not shown.
+    pub fn reset(&mut self) {
+        let found = self.found;
+        *self = Self::new();
+        self.found = found;
+    }
+
+    /// Whether the stream carried any 608 caption data so far.
+    pub fn found(&self) -> bool {
+        self.found
+    }
+
+    pub fn feed(&mut self, cc_data: &[u8]) {
+        for triplet in cc_data.chunks_exact(3) {
+            let cc_valid = triplet[0] & 0x04 != 0;
+            let cc_type = triplet[0] & 0x03;
+
+            // Type 0 is field 1 (CC1/CC2); field 2 and DTVCC (708) packets are skipped
+            if cc_valid && cc_type == 0 {
+                self.decode_pair(triplet[1] & 0x7F, triplet[2] & 0x7F);
+            }
+        }
+    }
+
+    /// Displayed caption rows, top to bottom.
+    pub fn text(&self) -> Option<String> {
+        let lines: Vec<String> = self
+            .displayed
+            .iter()
+            .map(|row| row.iter().collect::<String>().trim().to_string())
+            .filter(|line| !line.is_empty())
+            .collect();
+
+        if lines.is_empty() {
+            None
+        } else {
+            Some(lines.join("\n"))
+        }
+    }
+
+    fn decode_pair(&mut self, b1: u8, b2: u8) {
+        if b1 == 0 && b2 == 0 {
+            return;
+        }
+        self.found = true;
+
+        if (0x10..=0x1F).contains(&b1) {
+            // Control codes are usually sent twice for redundancy
+            if self.last_control == Some((b1, b2)) {
+                self.last_control = None;
+                return;
+            }
+            self.last_control = Some((b1, b2));
+            self.channel = if b1 & 0x08 != 0 { 2 } else { 1 };
+
+            if self.channel == 1 {
+                self.decode_control(b1 & 0x17, b2);
+            }
+            return;
+        }
+
+        self.last_control = None;
+        // 0x01-0x0F start extended data services packets, which carry no caption text
+        if b1 < 0x20 {
+            return;
+        }
+        if self.channel == 1 && self.mode != Mode::Text {
+            self.write_char(basic_char(b1));
+            if b2 >= 0x20 {
+                self.write_char(basic_char(b2));
+            }
+        }
+    }
+
+    fn decode_control(&mut self, b1: u8, b2: u8) {
+        match (b1, b2) {
+            (0x14, 0x20) => self.mode = Mode::PopOn,
+            (0x14, 0x21) if self.column > 0 => {
+                self.column -= 1;
+                let (row, column) = (self.row, self.column);
+                self.target()[row][column] = ' ';
+            }
+            (0x14, 0x24) => {
+                let (row, column) = (self.row, self.column);
+                self.target()[row][column..].fill(' ');
+            }
+            (0x14, 0x25..=0x27) => {
+                let rows = (b2 - 0x25 + 2) as usize;
+                if !matches!(self.mode, Mode::RollUp(_)) {
+                    self.displayed = [[' '; COLUMNS]; ROWS];
+                    self.row = ROWS - 1;
+                }
+                self.mode = Mode::RollUp(rows);
+                self.column = 0;
+            }
+            (0x14, 0x29) => self.mode = Mode::PaintOn,
+            (0x14, 0x2A) | (0x14, 0x2B) => self.mode = Mode::Text,
+            (0x14, 0x2C) => self.displayed = [[' '; COLUMNS]; ROWS],
+            (0x14, 0x2D) => self.carriage_return(),
+            (0x14, 0x2E) => self.non_displayed = [[' '; COLUMNS]; ROWS],
+            (0x14, 0x2F) => {
+                std::mem::swap(&mut self.displayed, &mut self.non_displayed);
+                self.mode = Mode::PopOn;
+            }
+            // Tab offsets
+            (0x17, 0x21..=0x23) => {
+                self.column = (self.column + (b2 - 0x20) as usize).min(COLUMNS - 1);
+            }
+            // Mid-row style codes take up a space
+            (0x11, 0x20..=0x2F) => self.write_char(' '),
+            (0x11, 0x30..=0x3F) => self.write_char(SPECIAL_CHARS[(b2 - 0x30) as usize]),
+            // Extended characters replace the fallback character sent before them
+            (0x12, 0x20..=0x3F) | (0x13, 0x20..=0x3F) => {
+                let table = if b1 == 0x12 {
+                    EXTENDED_CHARS_1
+                } else {
+                    EXTENDED_CHARS_2
+                };
+                if let Some(c) = table.chars().nth((b2 - 0x20) as usize) {
+                    self.column = self.column.saturating_sub(1);
+                    self.write_char(c);
+                }
+            }
+            (_, 0x40..=0x7F) => self.preamble_address(b1, b2),
+            _ => {}
+        }
+    }
+
+    /// Moves the cursor to the start of a row, optionally indented.
+    fn preamble_address(&mut self, b1: u8, b2: u8) {
+        let row = PAC_ROWS[(b1 & 0x07) as usize] + usize::from(b2 & 0x20 != 0);
+        let row = row.clamp(1, ROWS) - 1;
+
+        if let Mode::RollUp(rows) = self.mode {
+            // Roll-up captions keep their lines and move the whole window to the new base row
+            if row != self.row {
+                let mut moved = [[' '; COLUMNS]; ROWS];
+                for offset in 0..rows.min(row + 1).min(self.row + 1) {
+                    moved[row - offset] = self.displayed[self.row - offset];
+                }
+                self.displayed = moved;
+            }
+        }
+
+        self.row = row;
+        self.column = if b2 & 0x10 != 0 {
+            (((b2 & 0x0E) >> 1) as usize * 4).min(COLUMNS - 1)
+        } else {
+            0
+        };
+    }
+
+    fn carriage_return(&mut self) {
+        match self.mode {
+            Mode::RollUp(rows) => {
+                let top = (self.row + 1).saturating_sub(rows);
+                for row in top..self.row {
+                    self.displayed[row] = self.displayed[row + 1];
+                }
+                self.displayed[self.row] = [' '; COLUMNS];
+                // Lines that scrolled out of the window disappear
+                for row in self.displayed.iter_mut().take(top) {
+                    *row = [' '; COLUMNS];
+                }
+            }
+            _ => self.row = (self.row + 1).min(ROWS - 1),
+        }
+        self.column = 0;
+    }
+
+    fn write_char(&mut self, c: char) {
+        let (row, column) = (self.row, self.column);
+        self.target()[row][column] = c;
+        self.column = (column + 1).min(COLUMNS - 1);
+    }
+
+    /// Pop-on captions are built off screen; the other modes write to the screen directly.
+    fn target(&mut self) -> &mut Screen {
+        match self.mode {
+            Mode::PopOn => &mut self.non_displayed,
+            _ => &mut self.displayed,
+        }
+    }
+}
+
+/// The 608 basic character set is ASCII with a few accented letters swapped in.
+fn basic_char(byte: u8) -> char {
+    match byte {
+        0x2A => 'á',
+        0x5C => 'é',
+        0x5E => 'í',
+        0x5F => 'ó',
+        0x60 => 'ú',
+        0x7B => 'ç',
+        0x7C => '÷',
+        0x7D => 'Ñ',
+        0x7E => 'ñ',
+        0x7F => '█',
+        _ => byte as char,
+    }
+}
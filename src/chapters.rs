@@ -0,0 +1,285 @@
+use crate::media_info::ChapterInfo;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// CUE sheet timestamps count frames of 1/75 s.
+const CUE_FRAMES_PER_SECOND: i64 = 75;
+
+struct ParsedChapter {
+    title: String,
+    start_ms: i64,
+    end_ms: Option<i64>,
+    metadata: HashMap<String, String>,
+}
+
+/// Loads chapters from a CUE sheet or an FFmpeg metadata file (`;FFMETADATA1`).
+/// Chapters without an end time run until the next one, the last until `duration_ms`.
+pub fn load(path: &str, duration_ms: i64) -> Result<Vec<ChapterInfo>, Box<dyn std::error::Error>> {
+    let bytes = fs::read(path)?;
+    let content = String::from_utf8_lossy(&bytes);
+    let content = content.trim_start_matches('\u{feff}');
+
+    let mut parsed = if content.starts_with(";FFMETADATA") {
+        parse_ffmetadata(content)
+    } else {
+        parse_cue(content)
+    };
+
+    if parsed.is_empty() {
+        return Err(format!("No chapters found in {}", path).into());
+    }
+
+    parsed.sort_by_key(|chapter| chapter.start_ms);
+    let starts: Vec<i64> = parsed
+        .iter()
+        .skip(1)
+        .map(|chapter| chapter.start_ms)
+        .collect();
+
+    Ok(parsed
+        .into_iter()
+        .enumerate()
+        .map(|(index, chapter)| ChapterInfo {
+            index,
+            title: chapter.title,
+            start_time_ms: chapter.start_ms,
+            end_time_ms: chapter
+                .end_ms
+                .or_else(|| starts.get(index).copied())
+                .unwrap_or(duration_ms),
+            metadata: chapter.metadata,
+        })
+        .collect())
+}
+
+/// Finds a chapter file for the media file: `<name>.cue`, `<name>.<ext>.cue` or
+/// `<name>.ffmetadata`, or else a CUE sheet in the same folder that refers to the file.
+pub fn find_for(media_path: &str) -> Option<String> {
+    let path = Path::new(media_path);
+    let mut candidates = vec![
+        path.with_extension("cue"),
+        path.with_extension("ffmetadata"),
+    ];
+    if let Some(ext) = path.extension() {
+        candidates.push(path.with_extension(format!("{}.cue", ext.to_string_lossy())));
+    }
+    if let Some(found) = candidates.iter().find(|candidate| candidate.is_file()) {
+        return Some(found.to_string_lossy().into_owned());
+    }
+
+    let file_name = path.file_name()?.to_string_lossy().to_lowercase();
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|candidate| {
+            candidate
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("cue"))
+        })
+        .find(|candidate| {
+            fs::read(candidate).is_ok_and(|bytes| {
+                cue_files(&String::from_utf8_lossy(&bytes))
+                    .iter()
+                    .any(|name| name.to_lowercase() == file_name)
+            })
+        })
+        .map(|candidate| candidate.to_string_lossy().into_owned())
+}
+
+/// Every `TRACK` with an `INDEX 01` becomes a chapter. Sheets for several files restart
+/// their times per file, so only the tracks of the first file are used.
+fn parse_cue(content: &str) -> Vec<ParsedChapter> {
+    let mut chapters = Vec::new();
+    let mut files = 0;
+    let mut album_performer = None;
+    let mut current: Option<(String, Option<String>, Option<i64>)> = None;
+
+    let mut finish = |track: Option<(String, Option<String>, Option<i64>)>,
+                      album_performer: &Option<String>| {
+        if let Some((title, performer, Some(start_ms))) = track {
+            let mut metadata = HashMap::new();
+            if let Some(artist) = performer.or_else(|| album_performer.clone()) {
+                metadata.insert("artist".to_string(), artist);
+            }
+            chapters.push(ParsedChapter {
+                title,
+                start_ms,
+                end_ms: None,
+                metadata,
+            });
+        }
+    };
+
+    for line in content.lines() {
+        let line = line.trim();
+        let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+
+        match command.to_uppercase().as_str() {
+            "FILE" => {
+                files += 1;
+                if files > 1 {
+                    break;
+                }
+            }
+            "TRACK" => {
+                finish(current.take(), &album_performer);
+                let number = rest.split_whitespace().next().unwrap_or_default();
+                current = Some((format!("Track {}", number), None, None));
+            }
+            "TITLE" => {
+                if let Some((title, _, _)) = &mut current {
+                    *title = unquote(rest);
+                }
+            }
+            "PERFORMER" => match &mut current {
+                Some((_, performer, _)) => *performer = Some(unquote(rest)),
+                None => album_performer = Some(unquote(rest)),
+            },
+            "INDEX" => {
+                let mut fields = rest.split_whitespace();
+                if let (Some("01"), Some(time), Some((_, _, start_ms))) =
+                    (fields.next(), fields.next(), &mut current)
+                {
+                    *start_ms = parse_cue_time(time);
+                }
+            }
+            _ => {}
+        }
+    }
+    finish(current, &album_performer);
+
+    chapters
+}
+
+/// File names referenced by `FILE "name" TYPE` lines.
+fn cue_files(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let (command, rest) = line.split_once(' ')?;
+            if !command.eq_ignore_ascii_case("FILE") {
+                return None;
+            }
+            // The file type follows the name, which may itself contain spaces
+            let name = match rest.strip_prefix('"') {
+                Some(quoted) => quoted.split('"').next()?,
+                None => rest.rsplit_once(' ').map_or(rest, |(name, _)| name),
+            };
+            Path::new(name)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .collect()
+}
+
+/// `mm:ss:ff`, where minutes may exceed 59.
+fn parse_cue_time(time: &str) -> Option<i64> {
+    let mut parts = time.split(':').map(|part| part.parse::<i64>().ok());
+    let minutes = parts.next()??;
+    let seconds = parts.next()??;
+    let frames = parts.next()??;
+
+    Some((minutes * 60 + seconds) * 1000 + frames * 1000 / CUE_FRAMES_PER_SECOND)
+}
+
+fn unquote(value: &str) -> String {
+    value.trim().trim_matches('"').to_string()
+}
+
+fn parse_ffmetadata(content: &str) -> Vec<ParsedChapter> {
+    let mut chapters = Vec::new();
+    let mut section: Option<HashMap<String, String>> = None;
+
+    let mut finish = |section: Option<HashMap<String, String>>| {
+        if let Some(mut values) = section {
+            let (num, den) = values
+                .remove("TIMEBASE")
+                .and_then(|time_base| {
+                    let (num, den) = time_base.split_once('/')?;
+                    Some((
+                        num.trim().parse::<i64>().ok()?,
+                        den.trim().parse::<i64>().ok()?,
+                    ))
+                })
+                .filter(|(num, den)| *num > 0 && *den > 0)
+                .unwrap_or((1, 1_000_000_000));
+            let to_ms = |value: Option<String>| {
+                value
+                    .and_then(|value| value.trim().parse::<i64>().ok())
+                    .map(|value| value * num * 1000 / den)
+            };
+
+            let start_ms = to_ms(values.remove("START"));
+            let end_ms = to_ms(values.remove("END"));
+            if let Some(start_ms) = start_ms {
+                chapters.push(ParsedChapter {
+                    title: values
+                        .remove("title")
+                        .unwrap_or_else(|| format!("Chapter {}", chapters.len() + 1)),
+                    start_ms,
+                    end_ms,
+                    metadata: values,
+                });
+            }
+        }
+    };
+
+    let mut lines = content.lines();
+    while let Some(line) = lines.next() {
+        if line.starts_with(';') || line.starts_with('#') {
+            continue;
+        }
+
+        if line.starts_with('[') {
+            finish(section.take());
+            if line.trim() == "[CHAPTER]" {
+                section = Some(HashMap::new());
+            }
+            continue;
+        }
+
+        // A trailing backslash continues the value on the next line
+        let mut line = line.to_string();
+        while line.ends_with('\\') && !line.ends_with("\\\\") {
+            line.pop();
+            line.push('\n');
+            match lines.next() {
+                Some(next) => line.push_str(next),
+                None => break,
+            }
+        }
+
+        if let (Some(values), Some((key, value))) = (&mut section, split_key_value(&line)) {
+            values.insert(key, value);
+        }
+    }
+    finish(section);
+
+    chapters
+}
+
+/// Splits at the first unescaped `=` and removes the escaping backslashes.
+fn split_key_value(line: &str) -> Option<(String, String)> {
+    let mut key = String::new();
+    let mut value = String::new();
+    let mut in_value = false;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        let target = if in_value { &mut value } else { &mut key };
+        match c {
+            '\\' => target.extend(chars.next()),
+            '=' if !in_value => in_value = true,
+            c => target.push(c),
+        }
+    }
+
+    in_value.then_some((key, value))
+}
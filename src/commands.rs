@@ -0,0 +1,79 @@
+use crate::actions::Action;
+use eframe::egui;
+use std::sync::mpsc;
+
+/// What the tray, the system media controls and anything else outside the UI asks the player
+/// to do. Shortcuts and buttons perform the same actions directly.
+#[derive(Debug, Clone, Copy)]
+pub enum PlayerCommand {
+    Action(Action),
+    /// Brings the window back, also after it was minimized to the tray.
+    ShowWindow,
+    Quit,
+}
+
+/// A change in playback that the parts mirroring it, like the system media controls, follow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayerEvent {
+    /// Another file was opened, so the title, tags and duration changed.
+    TrackChanged,
+    /// Playback paused, resumed or jumped.
+    PlaybackChanged,
+}
+
+/// Hands commands to the player and wakes it up; cloned into the threads they come from.
+#[derive(Clone)]
+pub struct CommandSender {
+    sender: mpsc::Sender<PlayerCommand>,
+    ctx: egui::Context,
+}
+
+impl CommandSender {
+    pub fn send(&self, command: PlayerCommand) {
+        let _ = self.sender.send(command);
+        // Commands also arrive while the window is idle or in the background
+        self.ctx.request_repaint();
+    }
+}
+
+/// Collects the commands sent to the player and the events it raises, which are both handled
+/// once per frame in the order they came in.
+pub struct CommandBus {
+    sender: mpsc::Sender<PlayerCommand>,
+    receiver: mpsc::Receiver<PlayerCommand>,
+    events: Vec<PlayerEvent>,
+}
+
+impl CommandBus {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            sender,
+            receiver,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn sender(&self, ctx: &egui::Context) -> CommandSender {
+        CommandSender {
+            sender: self.sender.clone(),
+            ctx: ctx.clone(),
+        }
+    }
+
+    pub fn commands(&self) -> Vec<PlayerCommand> {
+        self.receiver.try_iter().collect()
+    }
+
+    /// Raises an event. One that is already waiting is not raised twice, so a burst of seeks
+    /// updates the listeners once.
+    pub fn emit(&mut self, event: PlayerEvent) {
+        if !self.events.contains(&event) {
+            self.events.push(event);
+        }
+    }
+
+    pub fn take_events(&mut self) -> Vec<PlayerEvent> {
+        std::mem::take(&mut self.events)
+    }
+}
@@ -0,0 +1,73 @@
+extern crate ffmpeg_next as ffmpeg;
+
+use crate::error::MediaError;
+use crate::http;
+use crate::matroska::{OrderedTimeline, TimelineChapter, TimelinePart};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{OnceLock, RwLock};
+
+/// Builds a timeline that plays the files back to back, with a chapter for each, as for a
+/// recording the camera split into several files.
+pub fn timeline(paths: &[String]) -> Result<OrderedTimeline, MediaError> {
+    ffmpeg::init().map_err(MediaError::Open)?;
+
+    let mut parts = Vec::new();
+    let mut chapters = Vec::new();
+    let mut position_ms = 0;
+
+    for path in paths {
+        let input = http::open_input(path).map_err(MediaError::Open)?;
+        let duration_ms = (input.duration() / 1000).max(0);
+        if duration_ms == 0 {
+            eprintln!("Skipping {}, which has no duration", path);
+            continue;
+        }
+
+        chapters.push(TimelineChapter {
+            title: Path::new(path)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.clone()),
+            start_ms: position_ms,
+            end_ms: position_ms + duration_ms,
+        });
+        parts.push(TimelinePart {
+            path: path.clone(),
+            start_ms: 0,
+            end_ms: duration_ms,
+        });
+        position_ms += duration_ms;
+    }
+
+    if parts.is_empty() {
+        return Err(MediaError::Open(ffmpeg::Error::InvalidData));
+    }
+
+    println!("Joined {} files into one timeline", parts.len());
+    Ok(OrderedTimeline { parts, chapters })
+}
+
+fn timelines() -> &'static RwLock<HashMap<String, OrderedTimeline>> {
+    static TIMELINES: OnceLock<RwLock<HashMap<String, OrderedTimeline>>> = OnceLock::new();
+    TIMELINES.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// Plays `timeline` whenever its first file is loaded.
+pub fn register(timeline: OrderedTimeline) {
+    if let Some(first) = timeline.parts.first() {
+        timelines()
+            .write()
+            .unwrap()
+            .insert(first.path.clone(), timeline);
+    }
+}
+
+pub fn timeline_for(filename: &str) -> Option<OrderedTimeline> {
+    timelines().read().unwrap().get(filename).cloned()
+}
+
+/// Forgets the joined files, so that opening the first one again plays it on its own.
+pub fn clear() {
+    timelines().write().unwrap().clear();
+}
@@ -0,0 +1,170 @@
+use std::fmt::Write;
+use std::path::Path;
+
+/// Frame rate of the EDL timecode for files without video.
+pub const AUDIO_EDL_FPS: f64 = 25.0;
+/// Record timecode of the first event, the usual start of an edit.
+const RECORD_START_MS: i64 = 3_600_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Marker {
+    pub name: String,
+    pub time_ms: i64,
+}
+
+/// A part of the file to hand to an editor or to FFmpeg.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cut {
+    pub name: String,
+    pub start_ms: i64,
+    pub end_ms: i64,
+}
+
+/// In and out marks and named markers set on the open file.
+#[derive(Debug, Clone, Default)]
+pub struct Cutlist {
+    pub in_ms: Option<i64>,
+    pub out_ms: Option<i64>,
+    pub markers: Vec<Marker>,
+}
+
+impl Cutlist {
+    pub fn add_marker(&mut self, name: String, time_ms: i64) {
+        let index = self
+            .markers
+            .partition_point(|marker| marker.time_ms <= time_ms);
+        self.markers.insert(index, Marker { name, time_ms });
+    }
+
+    /// The part between the in and out marks, split at the markers inside it. Parts that start
+    /// at a marker are named after it, the first one after the file.
+    pub fn cuts(&self, duration_ms: i64, clip_name: &str) -> Vec<Cut> {
+        let start_ms = self.in_ms.unwrap_or(0);
+        let end_ms = self.out_ms.unwrap_or(duration_ms);
+        if end_ms <= start_ms {
+            return Vec::new();
+        }
+
+        let mut cuts = vec![Cut {
+            name: clip_name.to_string(),
+            start_ms,
+            end_ms,
+        }];
+        for marker in &self.markers {
+            let last = cuts.last_mut().unwrap();
+            if marker.time_ms == last.start_ms {
+                last.name = marker.name.clone();
+                continue;
+            }
+            if marker.time_ms < last.start_ms || marker.time_ms >= end_ms {
+                continue;
+            }
+            last.end_ms = marker.time_ms;
+            cuts.push(Cut {
+                name: marker.name.clone(),
+                start_ms: marker.time_ms,
+                end_ms,
+            });
+        }
+        cuts
+    }
+}
+
+/// HH:MM:SS:FF, non-drop.
+fn timecode(ms: i64, fps: f64) -> String {
+    let rate = fps.round().max(1.0) as i64;
+    let frames = (ms as f64 * fps / 1000.0).round() as i64;
+    let seconds = frames / rate;
+    format!(
+        "{:02}:{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        frames % rate
+    )
+}
+
+/// HH:MM:SS.mmm as FFmpeg takes it.
+fn ffmpeg_time(ms: i64) -> String {
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
+
+/// A CMX3600 edit decision list with the cuts one after another, each with a locator named
+/// after it, which DaVinci Resolve, Premiere and Avid import as markers.
+pub fn to_edl(filename: &str, cuts: &[Cut], fps: f64, has_video: bool) -> String {
+    let clip_name = Path::new(filename)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| filename.to_string());
+    let title = Path::new(&clip_name)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| clip_name.clone());
+    let track = if has_video { "AA/V" } else { "AA" };
+
+    let mut edl = format!("TITLE: {}\nFCM: NON-DROP FRAME\n", title);
+    let mut record_ms = RECORD_START_MS;
+    for (index, cut) in cuts.iter().enumerate() {
+        let length_ms = cut.end_ms - cut.start_ms;
+        let _ = write!(
+            edl,
+            "\n{:03}  AX       {:<4}  C        {} {} {} {}\n* FROM CLIP NAME: {}\n* LOC: {} BLUE    {}\n",
+            index + 1,
+            track,
+            timecode(cut.start_ms, fps),
+            timecode(cut.end_ms, fps),
+            timecode(record_ms, fps),
+            timecode(record_ms + length_ms, fps),
+            clip_name,
+            timecode(record_ms, fps),
+            cut.name,
+        );
+        record_ms += length_ms;
+    }
+    edl
+}
+
+/// One `ffmpeg -ss … -to …` command per cut, copying the streams into a file named after it.
+pub fn to_ffmpeg_commands(filename: &str, cuts: &[Cut]) -> String {
+    let path = Path::new(filename);
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "cut".to_string());
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "mkv".to_string());
+    let directory = path.parent().unwrap_or(Path::new(""));
+
+    let mut commands = String::new();
+    for (index, cut) in cuts.iter().enumerate() {
+        let name: String = cut
+            .name
+            .chars()
+            .map(|c| if r#"/\:*?"<>|"#.contains(c) { '_' } else { c })
+            .collect();
+        let output = directory.join(format!(
+            "{} - {:02} {}.{}",
+            stem,
+            index + 1,
+            name.trim(),
+            extension
+        ));
+        let _ = writeln!(
+            commands,
+            "ffmpeg -ss {} -to {} -i \"{}\" -map 0 -c copy \"{}\"",
+            ffmpeg_time(cut.start_ms),
+            ffmpeg_time(cut.end_ms),
+            filename,
+            output.display()
+        );
+    }
+    commands
+}
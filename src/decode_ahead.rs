@@ -0,0 +1,201 @@
+extern crate ffmpeg_next as ffmpeg;
+
+use ffmpeg::{codec, format, Packet, Rational};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+pub const DEFAULT_FRAME_QUEUE: usize = 4;
+pub const DEFAULT_READ_AHEAD_MB: u32 = 16;
+/// How often the end of a followed file is checked for new data.
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(100);
+
+static FRAME_QUEUE: AtomicUsize = AtomicUsize::new(DEFAULT_FRAME_QUEUE);
+static READ_AHEAD_MB: AtomicU32 = AtomicU32::new(DEFAULT_READ_AHEAD_MB);
+
+/// Sets how far videos opened from now on decode and read ahead of playback.
+pub fn configure(frame_queue: usize, read_ahead_mb: u32) {
+    FRAME_QUEUE.store(frame_queue, Ordering::Relaxed);
+    READ_AHEAD_MB.store(read_ahead_mb, Ordering::Relaxed);
+}
+
+/// How many decoded frames to keep ready beyond the one being shown.
+pub fn frame_queue() -> usize {
+    FRAME_QUEUE.load(Ordering::Relaxed)
+}
+
+struct Queue {
+    packets: VecDeque<Packet>,
+    bytes: usize,
+    /// The reader got to the end of the input, or of what a followed file has so far.
+    ended: bool,
+    /// Bumped by every seek, so that a packet read from before it is dropped.
+    generation: u64,
+    /// Timestamp the reader is to seek to before reading on.
+    seek_to: Option<i64>,
+    stopped: bool,
+}
+
+struct Shared {
+    queue: Mutex<Queue>,
+    changed: Condvar,
+    following: AtomicBool,
+    limit: usize,
+}
+
+/// What is looked up about a stream while playing, copied when reading starts.
+#[derive(Clone)]
+pub struct StreamInfo {
+    pub parameters: codec::Parameters,
+    pub time_base: Rational,
+    pub avg_frame_rate: Rational,
+}
+
+/// Reads packets on a thread of its own into a queue of at most the configured size, so
+/// that slow reads from the network or a busy disk are absorbed before the decoder runs dry.
+/// The input belongs to that thread, so nothing here waits for a read that is stuck.
+pub struct PacketReader {
+    shared: Arc<Shared>,
+    streams: Vec<StreamInfo>,
+}
+
+impl PacketReader {
+    /// Starts reading from the input's current position. `following` keeps looking for more
+    /// at the end of a file that is still being written.
+    pub fn start(input: format::context::Input, following: bool) -> Self {
+        let limit = READ_AHEAD_MB.load(Ordering::Relaxed).max(1) as usize * 1024 * 1024;
+        let streams = input
+            .streams()
+            .map(|stream| StreamInfo {
+                parameters: stream.parameters(),
+                time_base: stream.time_base(),
+                avg_frame_rate: stream.avg_frame_rate(),
+            })
+            .collect();
+        let shared = Arc::new(Shared {
+            queue: Mutex::new(Queue {
+                packets: VecDeque::new(),
+                bytes: 0,
+                ended: false,
+                generation: 0,
+                seek_to: None,
+                stopped: false,
+            }),
+            changed: Condvar::new(),
+            following: AtomicBool::new(following),
+            limit,
+        });
+
+        let reader = Arc::clone(&shared);
+        thread::spawn(move || read_packets(input, &reader));
+
+        Self { shared, streams }
+    }
+
+    pub fn stream(&self, index: usize) -> Option<&StreamInfo> {
+        self.streams.get(index)
+    }
+
+    /// Takes the next packet, waiting for one to be read if `wait` is set. `None` means the
+    /// end of the input, or without `wait`, that nothing is queued.
+    pub fn next(&self, wait: bool) -> Option<Packet> {
+        let mut queue = self.shared.queue.lock().unwrap();
+        loop {
+            if let Some(packet) = queue.packets.pop_front() {
+                queue.bytes -= packet.size();
+                self.shared.changed.notify_all();
+                return Some(packet);
+            }
+            if !wait || queue.ended {
+                return None;
+            }
+            queue = self.shared.changed.wait(queue).unwrap();
+        }
+    }
+
+    /// Drops everything read before and has the reader seek the input before reading on.
+    pub fn seek(&self, timestamp: i64) {
+        let mut queue = self.shared.queue.lock().unwrap();
+        queue.packets.clear();
+        queue.bytes = 0;
+        queue.ended = false;
+        queue.generation += 1;
+        queue.seek_to = Some(timestamp);
+        self.shared.changed.notify_all();
+    }
+
+    pub fn stop_following(&self) {
+        self.shared.following.store(false, Ordering::Relaxed);
+    }
+}
+
+impl Drop for PacketReader {
+    fn drop(&mut self) {
+        self.shared.queue.lock().unwrap().stopped = true;
+        self.shared.changed.notify_all();
+    }
+}
+
+fn read_packets(mut input: format::context::Input, shared: &Shared) {
+    while wait_for_room(shared) {
+        let (generation, seek_to) = {
+            let mut queue = shared.queue.lock().unwrap();
+            (queue.generation, queue.seek_to.take())
+        };
+        if let Some(timestamp) = seek_to {
+            if let Err(e) = input.seek(timestamp, ..timestamp) {
+                eprintln!("Error seeking: {}", e);
+            }
+        }
+
+        let mut packet = Packet::empty();
+        let result = packet.read(&mut input);
+        if matches!(result, Err(ffmpeg::Error::Eof)) && shared.following.load(Ordering::Relaxed) {
+            // Read on from here once the file has grown
+            unsafe {
+                let pb = (*input.as_mut_ptr()).pb;
+                if !pb.is_null() {
+                    (*pb).eof_reached = 0;
+                }
+            }
+        }
+
+        let mut queue = shared.queue.lock().unwrap();
+        if queue.generation != generation {
+            continue;
+        }
+        match result {
+            Ok(()) => {
+                queue.bytes += packet.size();
+                queue.packets.push_back(packet);
+                queue.ended = false;
+            }
+            Err(ffmpeg::Error::Eof) => queue.ended = true,
+            // Like the demuxer's packet iterator, skip over packets that fail to read
+            Err(_) => continue,
+        }
+        shared.changed.notify_all();
+    }
+}
+
+/// Waits until the queue has room, or a followed file may have grown. Returns false once
+/// the reader is dropped.
+fn wait_for_room(shared: &Shared) -> bool {
+    let mut queue = shared.queue.lock().unwrap();
+    loop {
+        if queue.stopped {
+            return false;
+        }
+        if queue.ended {
+            if shared.following.load(Ordering::Relaxed) {
+                let (queue, _) = shared.changed.wait_timeout(queue, FOLLOW_INTERVAL).unwrap();
+                return !queue.stopped;
+            }
+        } else if queue.bytes < shared.limit {
+            return true;
+        }
+        queue = shared.changed.wait(queue).unwrap();
+    }
+}
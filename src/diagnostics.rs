@@ -0,0 +1,366 @@
+extern crate ffmpeg_next as ffmpeg;
+
+use crate::audio::AudioBackend;
+use crate::i18n::tr;
+use cpal::traits::{DeviceTrait, HostTrait};
+use eframe::glow::{self, HasContext};
+use ffmpeg::{codec, ffi, media};
+use std::ffi::{c_char, CStr};
+use std::ptr;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+pub struct Section {
+    pub title: String,
+    pub entries: Vec<(String, String)>,
+}
+
+/// What Avio runs on, for bug reports. Probing hardware devices and audio outputs can take
+/// a moment, so it happens on a thread of its own.
+pub struct Diagnostics {
+    sections: Vec<Section>,
+    receiver: Option<Receiver<Vec<Section>>>,
+}
+
+impl Diagnostics {
+    /// Starts collecting. The GPU section comes from the UI thread, which owns the GL context.
+    pub fn collect(gpu: Vec<(String, String)>, backend: AudioBackend) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(vec![
+                application(),
+                ffmpeg_build(),
+                Section {
+                    title: tr!("diagnostics-hwaccel"),
+                    entries: hardware_devices(),
+                },
+                Section {
+                    title: tr!("diagnostics-decoders"),
+                    entries: decoders(),
+                },
+                Section {
+                    title: tr!("diagnostics-codec-test"),
+                    entries: codec_test(),
+                },
+                audio(backend),
+            ]);
+        });
+
+        Self {
+            sections: vec![Section {
+                title: tr!("diagnostics-gpu"),
+                entries: gpu,
+            }],
+            receiver: Some(receiver),
+        }
+    }
+
+    /// Takes in the results once they are ready. Returns whether collecting is still going on.
+    pub fn update(&mut self) -> bool {
+        let Some(receiver) = &self.receiver else {
+            return false;
+        };
+        match receiver.try_recv() {
+            Ok(mut sections) => {
+                sections.append(&mut self.sections);
+                self.sections = sections;
+                self.receiver = None;
+                false
+            }
+            Err(mpsc::TryRecvError::Empty) => true,
+            Err(mpsc::TryRecvError::Disconnected) => {
+                self.receiver = None;
+                false
+            }
+        }
+    }
+
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    /// The sections as plain text, to paste into a bug report.
+    pub fn report(&self) -> String {
+        let mut report = String::new();
+        for section in &self.sections {
+            report.push_str(&format!("## {}\n", section.title));
+            for (label, value) in &section.entries {
+                report.push_str(&format!("{}: {}\n", label, value));
+            }
+            report.push('\n');
+        }
+        report
+    }
+}
+
+/// Vendor, renderer and versions of the OpenGL context the UI draws with.
+pub fn gpu_info(gl: &glow::Context) -> Vec<(String, String)> {
+    [
+        (tr!("diagnostics-gpu-vendor"), glow::VENDOR),
+        (tr!("diagnostics-gpu-renderer"), glow::RENDERER),
+        (tr!("diagnostics-gpu-version"), glow::VERSION),
+        (tr!("diagnostics-gpu-glsl"), glow::SHADING_LANGUAGE_VERSION),
+    ]
+    .into_iter()
+    .map(|(label, parameter)| (label, unsafe { gl.get_parameter_string(parameter) }))
+    .collect()
+}
+
+fn application() -> Section {
+    Section {
+        title: "Avio".to_string(),
+        entries: vec![
+            (
+                tr!("diagnostics-version"),
+                env!("CARGO_PKG_VERSION").to_string(),
+            ),
+            (
+                tr!("diagnostics-os"),
+                format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+            ),
+        ],
+    }
+}
+
+unsafe fn c_string(string: *const c_char) -> String {
+    if string.is_null() {
+        return String::new();
+    }
+    CStr::from_ptr(string).to_string_lossy().into_owned()
+}
+
+/// A library version as packed by `AV_VERSION_INT`.
+fn library_version(version: u32) -> String {
+    format!(
+        "{}.{}.{}",
+        version >> 16,
+        (version >> 8) & 0xff,
+        version & 0xff
+    )
+}
+
+fn ffmpeg_build() -> Section {
+    Section {
+        title: "FFmpeg".to_string(),
+        entries: vec![
+            (tr!("diagnostics-version"), unsafe {
+                c_string(ffi::av_version_info())
+            }),
+            (
+                "libavutil".to_string(),
+                library_version(ffmpeg::util::version()),
+            ),
+            (
+                "libavcodec".to_string(),
+                library_version(ffmpeg::codec::version()),
+            ),
+            (
+                "libavformat".to_string(),
+                library_version(ffmpeg::format::version()),
+            ),
+            (
+                tr!("diagnostics-license"),
+                ffmpeg::util::license().to_string(),
+            ),
+            (
+                tr!("diagnostics-configuration"),
+                ffmpeg::util::configuration().to_string(),
+            ),
+        ],
+    }
+}
+
+/// The hardware device types FFmpeg was built with, and whether one can be created here.
+fn hardware_devices() -> Vec<(String, String)> {
+    let mut devices = Vec::new();
+    let mut device_type = ffi::AVHWDeviceType::AV_HWDEVICE_TYPE_NONE;
+    loop {
+        device_type = unsafe { ffi::av_hwdevice_iterate_types(device_type) };
+        if device_type == ffi::AVHWDeviceType::AV_HWDEVICE_TYPE_NONE {
+            break;
+        }
+
+        // Only creating a device tells whether the driver and hardware are there
+        let mut context = ptr::null_mut();
+        let available = unsafe {
+            ffi::av_hwdevice_ctx_create(&mut context, device_type, ptr::null(), ptr::null_mut(), 0)
+                >= 0
+        };
+        if available {
+            unsafe { ffi::av_buffer_unref(&mut context) };
+        }
+
+        devices.push((
+            unsafe { c_string(ffi::av_hwdevice_get_type_name(device_type)) },
+            if available {
+                tr!("diagnostics-available")
+            } else {
+                tr!("diagnostics-unavailable")
+            },
+        ));
+    }
+
+    if devices.is_empty() {
+        devices.push((tr!("diagnostics-none"), String::new()));
+    }
+    devices
+}
+
+fn decoders() -> Vec<(String, String)> {
+    let mut video = Vec::new();
+    let mut audio = Vec::new();
+    let mut subtitle = Vec::new();
+    let mut hardware = Vec::new();
+
+    let mut opaque = ptr::null_mut();
+    loop {
+        let codec = unsafe { ffi::av_codec_iterate(&mut opaque) };
+        if codec.is_null() {
+            break;
+        }
+        let hardware_backed = unsafe { (*codec).capabilities }
+            & (ffi::AV_CODEC_CAP_HARDWARE | ffi::AV_CODEC_CAP_HYBRID) as i32
+            != 0;
+        let codec = unsafe { ffmpeg::codec::codec::Codec::wrap(codec) };
+        if !codec.is_decoder() {
+            continue;
+        }
+
+        let name = codec.name().to_string();
+        if hardware_backed {
+            hardware.push(name.clone());
+        }
+        match codec.medium() {
+            media::Type::Video => video.push(name),
+            media::Type::Audio => audio.push(name),
+            media::Type::Subtitle => subtitle.push(name),
+            _ => {}
+        }
+    }
+
+    let list = |names: &[String]| format!("{}: {}", names.len(), names.join(", "));
+    vec![
+        (tr!("diagnostics-decoders-video"), list(&video)),
+        (tr!("diagnostics-decoders-audio"), list(&audio)),
+        (tr!("diagnostics-decoders-subtitle"), list(&subtitle)),
+        (tr!("diagnostics-decoders-hardware"), list(&hardware)),
+    ]
+}
+
+/// Common formats, and whether their decoder needs headers from a file before it opens.
+const COMMON_CODECS: [(&str, codec::Id, bool); 25] = [
+    ("H.264", codec::Id::H264, false),
+    ("HEVC", codec::Id::HEVC, false),
+    ("AV1", codec::Id::AV1, false),
+    ("VP9", codec::Id::VP9, false),
+    ("VP8", codec::Id::VP8, false),
+    ("MPEG-2", codec::Id::MPEG2VIDEO, false),
+    ("MPEG-4 Part 2", codec::Id::MPEG4, false),
+    ("VC-1", codec::Id::VC1, false),
+    ("ProRes", codec::Id::PRORES, false),
+    ("Motion JPEG", codec::Id::MJPEG, false),
+    ("AAC", codec::Id::AAC, false),
+    ("MP3", codec::Id::MP3, false),
+    ("Opus", codec::Id::OPUS, false),
+    ("Vorbis", codec::Id::VORBIS, true),
+    ("FLAC", codec::Id::FLAC, false),
+    ("AC-3", codec::Id::AC3, false),
+    ("E-AC-3", codec::Id::EAC3, false),
+    ("DTS", codec::Id::DTS, false),
+    ("TrueHD", codec::Id::TRUEHD, false),
+    ("ALAC", codec::Id::ALAC, true),
+    ("PCM", codec::Id::PCM_S16LE, false),
+    ("SubRip", codec::Id::SUBRIP, false),
+    ("ASS/SSA", codec::Id::ASS, false),
+    ("PGS", codec::Id::HDMV_PGS_SUBTITLE, false),
+    ("VobSub", codec::Id::DVD_SUBTITLE, false),
+];
+
+/// Tries to open the decoder FFmpeg would pick for each common format.
+fn codec_test() -> Vec<(String, String)> {
+    COMMON_CODECS
+        .iter()
+        .map(|(name, id, needs_headers)| {
+            let result = match codec::decoder::find(*id) {
+                None => format!("✖ {}", tr!("codec-test-missing")),
+                Some(decoder) if *needs_headers => {
+                    format!("✔ {} ({})", decoder.name(), tr!("codec-test-found"))
+                }
+                Some(decoder) => match open_decoder(decoder) {
+                    Ok(()) => format!("✔ {}", decoder.name()),
+                    Err(e) => format!("✖ {}: {}", decoder.name(), e),
+                },
+            };
+            (name.to_string(), result)
+        })
+        .collect()
+}
+
+fn open_decoder(decoder: codec::codec::Codec) -> Result<(), ffmpeg::Error> {
+    let mut context = codec::Context::new_with_codec(decoder);
+
+    // What a stream would tell the decoder; some refuse to open without it
+    unsafe {
+        let context = context.as_mut_ptr();
+        match decoder.medium() {
+            media::Type::Video => {
+                (*context).width = 1920;
+                (*context).height = 1080;
+            }
+            media::Type::Audio => {
+                (*context).sample_rate = 48000;
+                ffi::av_channel_layout_default(&mut (*context).ch_layout, 2);
+            }
+            _ => {}
+        }
+    }
+
+    context.decoder().open().map(|_| ())
+}
+
+fn audio(backend: AudioBackend) -> Section {
+    let backend = match backend {
+        AudioBackend::Rodio => "rodio",
+        AudioBackend::Cpal => "cpal",
+    };
+    let mut entries = vec![(tr!("diagnostics-audio-output"), backend.to_string())];
+
+    for host_id in cpal::available_hosts() {
+        let Ok(host) = cpal::host_from_id(host_id) else {
+            continue;
+        };
+        let default_device = host
+            .default_output_device()
+            .and_then(|device| device.name().ok());
+        let devices = host
+            .output_devices()
+            .map(|devices| {
+                devices
+                    .filter_map(|device| device.name().ok())
+                    .map(|name| {
+                        if Some(&name) == default_device.as_ref() {
+                            tr!("diagnostics-default-device", device = name)
+                        } else {
+                            name
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .unwrap_or_default();
+
+        entries.push((
+            tr!("diagnostics-audio-host", host = host_id.name()),
+            if devices.is_empty() {
+                tr!("diagnostics-none")
+            } else {
+                devices
+            },
+        ));
+    }
+
+    Section {
+        title: tr!("diagnostics-audio"),
+        entries,
+    }
+}
@@ -0,0 +1,132 @@
+extern crate ffmpeg_next as ffmpeg;
+
+use crate::{playlist, raw_stream};
+use ffmpeg::{format, Dictionary};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{OnceLock, RwLock};
+use std::thread;
+
+/// FFmpeg's DVD-Video demuxer, which reads discs through libdvdnav and libdvdread.
+const DEMUXER: &str = "dvdvideo";
+/// Title numbers on a DVD go up to 99.
+const MAX_TITLES: u32 = 99;
+
+pub struct Title {
+    pub number: u32,
+    pub duration_ms: i64,
+    pub chapters: usize,
+}
+
+/// True for a DVD image, a folder holding `VIDEO_TS`, or the `VIDEO_TS` folder itself.
+pub fn is_dvd(filename: &str) -> bool {
+    if playlist::is_url(filename) {
+        return false;
+    }
+
+    let path = Path::new(filename);
+    if path.is_dir() {
+        let is_video_ts = path
+            .file_name()
+            .is_some_and(|name| name.eq_ignore_ascii_case("VIDEO_TS"));
+        return is_video_ts || path.join("VIDEO_TS").is_dir() || path.join("video_ts").is_dir();
+    }
+
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("iso"))
+}
+
+fn titles() -> &'static RwLock<HashMap<String, u32>> {
+    static TITLES: OnceLock<RwLock<HashMap<String, u32>>> = OnceLock::new();
+    TITLES.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// Plays `title` of the disc from now on.
+pub fn set_title(filename: &str, title: u32) {
+    titles()
+        .write()
+        .unwrap()
+        .insert(filename.to_string(), title);
+}
+
+/// The title chosen for the disc. Without one the demuxer plays the first title.
+pub fn title_for(filename: &str) -> Option<u32> {
+    titles().read().unwrap().get(filename).copied()
+}
+
+/// Opens the chosen title of the disc.
+pub fn open(
+    filename: &str,
+    mut options: Dictionary,
+) -> Result<format::context::Input, ffmpeg::Error> {
+    if let Some(title) = title_for(filename) {
+        options.set("title", &title.to_string());
+    }
+    raw_stream::open_as(filename, DEMUXER, options)
+}
+
+/// Lists the titles of a disc by opening each one, which takes a moment per title, so it
+/// happens on a thread of its own.
+pub struct TitleScan {
+    filename: String,
+    titles: Vec<Title>,
+    receiver: Option<Receiver<Title>>,
+}
+
+impl TitleScan {
+    pub fn start(filename: &str) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let path = filename.to_string();
+        thread::spawn(move || {
+            for number in 1..=MAX_TITLES {
+                let mut options = Dictionary::new();
+                options.set("title", &number.to_string());
+                // Titles are numbered without gaps, so the first one missing ends the list
+                let Ok(input) = raw_stream::open_as(&path, DEMUXER, options) else {
+                    break;
+                };
+
+                let title = Title {
+                    number,
+                    duration_ms: (input.duration() / 1000).max(0),
+                    chapters: input.chapters().count(),
+                };
+                if sender.send(title).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            filename: filename.to_string(),
+            titles: Vec::new(),
+            receiver: Some(receiver),
+        }
+    }
+
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    /// Takes in the titles found so far. Returns whether scanning is still going on.
+    pub fn update(&mut self) -> bool {
+        let Some(receiver) = &self.receiver else {
+            return false;
+        };
+        loop {
+            match receiver.try_recv() {
+                Ok(title) => self.titles.push(title),
+                Err(mpsc::TryRecvError::Empty) => return true,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.receiver = None;
+                    return false;
+                }
+            }
+        }
+    }
+
+    pub fn titles(&self) -> &[Title] {
+        &self.titles
+    }
+}
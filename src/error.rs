@@ -0,0 +1,63 @@
+use ffmpeg_next as ffmpeg;
+use std::fmt;
+
+/// Reasons a media file cannot be opened or played.
+#[derive(Debug)]
+pub enum MediaError {
+    /// The file is missing, unreadable, or not a container FFmpeg recognizes.
+    Open(ffmpeg::Error),
+    NoVideoStream,
+    NoAudioStream,
+    /// Stream index that does not exist or holds another kind of media.
+    InvalidStream(usize),
+    /// No decoder is available for the codec.
+    UnsupportedCodec(String),
+    /// The stream could be opened but not decoded, e.g. because the file is corrupt.
+    Decode(ffmpeg::Error),
+    /// The file decoded fine but no audio device could play it.
+    AudioOutput(String),
+    /// yt-dlp could not turn a web page into a playable stream.
+    WebVideo(String),
+}
+
+impl MediaError {
+    /// Maps a failure to open a decoder, naming the codec when none is available.
+    pub fn decoder(error: ffmpeg::Error, codec: ffmpeg::codec::Id) -> Self {
+        match error {
+            ffmpeg::Error::DecoderNotFound => MediaError::UnsupportedCodec(format!("{:?}", codec)),
+            other => MediaError::Decode(other),
+        }
+    }
+}
+
+impl fmt::Display for MediaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MediaError::Open(e) => write!(f, "The file could not be opened: {}", e),
+            MediaError::NoVideoStream => write!(f, "The file contains no video stream"),
+            MediaError::NoAudioStream => write!(f, "The file contains no audio stream"),
+            MediaError::InvalidStream(index) => {
+                write!(f, "Stream {} is not a playable stream", index)
+            }
+            MediaError::UnsupportedCodec(codec) => write!(f, "Unsupported codec: {}", codec),
+            MediaError::Decode(e) => write!(f, "The file could not be decoded: {}", e),
+            MediaError::AudioOutput(e) => write!(f, "Audio output failed: {}", e),
+            MediaError::WebVideo(e) => write!(f, "The video page could not be resolved: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for MediaError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MediaError::Open(e) | MediaError::Decode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ffmpeg::Error> for MediaError {
+    fn from(error: ffmpeg::Error) -> Self {
+        MediaError::Decode(error)
+    }
+}
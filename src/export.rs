@@ -0,0 +1,155 @@
+use crate::video::{Video, VideoFrame};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+}
+
+impl ImageFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg => "jpg",
+        }
+    }
+}
+
+pub struct ExportSettings {
+    pub filename: String,
+    pub start_ms: i64,
+    pub end_ms: i64,
+    /// Exported frames per second; `None` keeps every decoded frame.
+    pub fps: Option<f64>,
+    pub format: ImageFormat,
+    pub output_dir: PathBuf,
+}
+
+pub enum ExportEvent {
+    Progress { frames: usize, position_ms: i64 },
+    Finished(Result<usize, String>),
+}
+
+/// Frame export running on a worker thread. Dropping the job cancels it.
+pub struct ExportJob {
+    pub receiver: mpsc::Receiver<ExportEvent>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl ExportJob {
+    pub fn start(settings: ExportSettings) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancel_flag = cancel.clone();
+
+        thread::spawn(move || {
+            let result = export_frames(&settings, &sender, &cancel_flag);
+            let _ = sender.send(ExportEvent::Finished(result.map_err(|e| e.to_string())));
+        });
+
+        Self { receiver, cancel }
+    }
+
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Drop for ExportJob {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+fn export_frames(
+    settings: &ExportSettings,
+    sender: &mpsc::Sender<ExportEvent>,
+    cancel: &AtomicBool,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let mut video = Video::new(&settings.filename)?;
+    video.seek(settings.start_ms)?;
+
+    let stem = Path::new(&settings.filename)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "frame".to_string());
+    let interval_ms = settings.fps.map(|fps| 1000.0 / fps);
+    let mut next_export_ms = settings.start_ms as f64;
+    let mut frames = 0;
+
+    while let Some(frame) = video.next_frame() {
+        if cancel.load(Ordering::Relaxed) {
+            return Err("Export cancelled".into());
+        }
+
+        let frame = match frame {
+            Ok(frame) => frame,
+            Err(e) => {
+                eprintln!("Error decoding frame: {}", e);
+                continue;
+            }
+        };
+
+        if frame.timestamp_ms > settings.end_ms {
+            break;
+        }
+
+        if let Some(interval_ms) = interval_ms {
+            if (frame.timestamp_ms as f64) < next_export_ms {
+                continue;
+            }
+            while next_export_ms <= frame.timestamp_ms as f64 {
+                next_export_ms += interval_ms;
+            }
+        }
+
+        frames += 1;
+        let path = settings.output_dir.join(format!(
+            "{}_{:06}.{}",
+            stem,
+            frames,
+            settings.format.extension()
+        ));
+        save_frame(frame, settings.format, &path)?;
+
+        let _ = sender.send(ExportEvent::Progress {
+            frames,
+            position_ms: frame.timestamp_ms,
+        });
+    }
+
+    Ok(frames)
+}
+
+fn save_frame(
+    frame: &VideoFrame,
+    format: ImageFormat,
+    path: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Frames are always opaque, so the alpha channel is dropped
+    let rgb: Vec<u8> = frame
+        .buffer
+        .chunks_exact(4)
+        .flat_map(|pixel| [pixel[0], pixel[1], pixel[2]])
+        .collect();
+
+    let image_format = match format {
+        ImageFormat::Png => image::ImageFormat::Png,
+        ImageFormat::Jpeg => image::ImageFormat::Jpeg,
+    };
+
+    image::save_buffer_with_format(
+        path,
+        &rgb,
+        frame.width as u32,
+        frame.height as u32,
+        image::ExtendedColorType::Rgb8,
+        image_format,
+    )?;
+
+    Ok(())
+}
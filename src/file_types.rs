@@ -0,0 +1,252 @@
+use directories::BaseDirs;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process::Command;
+
+const DESKTOP_FILE: &str = "avio.desktop";
+const PROG_ID: &str = "Avio.MediaFile";
+
+const MIME_TYPES: [(&str, &str); 23] = [
+    ("mp4", "video/mp4"),
+    ("avi", "video/x-msvideo"),
+    ("mkv", "video/x-matroska"),
+    ("mov", "video/quicktime"),
+    ("wmv", "video/x-ms-wmv"),
+    ("flv", "video/x-flv"),
+    ("webm", "video/webm"),
+    ("m4v", "video/x-m4v"),
+    ("mpg", "video/mpeg"),
+    ("mpeg", "video/mpeg"),
+    ("ts", "video/mp2t"),
+    ("m2ts", "video/mp2t"),
+    ("ogv", "video/ogg"),
+    ("3gp", "video/3gpp"),
+    ("mp3", "audio/mpeg"),
+    ("flac", "audio/flac"),
+    ("ogg", "audio/ogg"),
+    ("opus", "audio/x-opus+ogg"),
+    ("m4a", "audio/mp4"),
+    ("wav", "audio/x-wav"),
+    ("aac", "audio/aac"),
+    ("wma", "audio/x-ms-wma"),
+    ("mka", "audio/x-matroska"),
+];
+
+/// Makes the player the handler for the given extensions: a `.desktop` entry set as
+/// the default for their MIME types on Linux, "Open with" registry keys on Windows.
+/// Windows only lets the user pick the default app, so files without one open in Avio
+/// and the rest list it under "Open with".
+pub fn register(extensions: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let exe = std::env::current_exe()?.to_string_lossy().into_owned();
+
+    if cfg!(target_os = "windows") {
+        register_windows(&exe, extensions)
+    } else if cfg!(target_os = "linux") {
+        register_linux(&exe, extensions)
+    } else {
+        Err("File type registration is only supported on Windows and Linux".into())
+    }
+}
+
+pub fn unregister(extensions: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    if cfg!(target_os = "windows") {
+        unregister_windows(extensions)
+    } else if cfg!(target_os = "linux") {
+        unregister_linux()
+    } else {
+        Err("File type registration is only supported on Windows and Linux".into())
+    }
+}
+
+fn register_linux(exe: &str, extensions: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = applications_dir()?;
+    fs::create_dir_all(&dir)?;
+
+    let mut mime_types: Vec<&str> = extensions.iter().filter_map(|ext| mime_type(ext)).collect();
+    mime_types.sort_unstable();
+    mime_types.dedup();
+
+    let entry = format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name=Avio Player\n\
+         Comment=Play video and audio files\n\
+         Exec={} %F\n\
+         Terminal=false\n\
+         Categories=AudioVideo;Video;Player;\n\
+         MimeType={};\n",
+        desktop_exec_arg(exe),
+        mime_types.join(";")
+    );
+    fs::write(dir.join(DESKTOP_FILE), entry)?;
+
+    let mut args = vec!["default", DESKTOP_FILE];
+    args.extend(&mime_types);
+    run("xdg-mime", &args)?;
+
+    // Only refreshes the cache, the entry works without it
+    let _ = run("update-desktop-database", &[&dir.to_string_lossy()]);
+
+    Ok(())
+}
+
+fn unregister_linux() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = applications_dir()?;
+
+    match fs::remove_file(dir.join(DESKTOP_FILE)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+        _ => {}
+    }
+
+    remove_mime_defaults()?;
+    let _ = run("update-desktop-database", &[&dir.to_string_lossy()]);
+
+    Ok(())
+}
+
+/// Quotes `arg` for the `Exec` key of a desktop entry: `"`, `` ` ``, `$` and `\` are escaped
+/// inside the quotes, `%` is doubled, and the backslashes are escaped once more as the key is
+/// a string value.
+fn desktop_exec_arg(arg: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in arg.chars() {
+        match c {
+            '"' | '`' | '$' | '\\' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            '%' => quoted.push_str("%%"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted.replace('\\', "\\\\")
+}
+
+/// Takes the entry out of the defaults `xdg-mime default` set in `mimeapps.list`, which
+/// would otherwise point at the removed file. There is no `xdg-mime` command for it.
+fn remove_mime_defaults() -> Result<(), Box<dyn std::error::Error>> {
+    let dirs = BaseDirs::new().ok_or("Could not find the home directory")?;
+    let path = dirs.config_dir().join("mimeapps.list");
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut changed = false;
+    let lines: Vec<String> = content
+        .lines()
+        .filter_map(|line| {
+            let Some((key, value)) = line.split_once('=') else {
+                return Some(line.to_string());
+            };
+            let entries: Vec<&str> = value.split(';').filter(|e| !e.is_empty()).collect();
+            if !entries.contains(&DESKTOP_FILE) {
+                return Some(line.to_string());
+            }
+            changed = true;
+            let rest: Vec<&str> = entries.into_iter().filter(|e| *e != DESKTOP_FILE).collect();
+            // A type left without any handler goes back to the system default
+            (!rest.is_empty()).then(|| format!("{}={};", key, rest.join(";")))
+        })
+        .collect();
+
+    if changed {
+        fs::write(&path, lines.join("\n") + "\n")?;
+    }
+    Ok(())
+}
+
+fn register_windows(exe: &str, extensions: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let prog_id_key = format!(r"HKCU\Software\Classes\{}", PROG_ID);
+    let command = format!("\"{}\" \"%1\"", exe);
+
+    run(
+        "reg",
+        &["add", &prog_id_key, "/ve", "/d", "Avio media file", "/f"],
+    )?;
+    run(
+        "reg",
+        &[
+            "add",
+            &format!(r"{}\shell\open\command", prog_id_key),
+            "/ve",
+            "/d",
+            &command,
+            "/f",
+        ],
+    )?;
+
+    for ext in extensions {
+        run(
+            "reg",
+            &[
+                "add",
+                &format!(r"HKCU\Software\Classes\.{}\OpenWithProgids", ext),
+                "/v",
+                PROG_ID,
+                "/t",
+                "REG_NONE",
+                "/f",
+            ],
+        )?;
+    }
+
+    Ok(())
+}
+
+fn unregister_windows(extensions: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    // Extensions that were never registered have nothing to delete
+    for ext in extensions {
+        let _ = run(
+            "reg",
+            &[
+                "delete",
+                &format!(r"HKCU\Software\Classes\.{}\OpenWithProgids", ext),
+                "/v",
+                PROG_ID,
+                "/f",
+            ],
+        );
+    }
+
+    let _ = run(
+        "reg",
+        &[
+            "delete",
+            &format!(r"HKCU\Software\Classes\{}", PROG_ID),
+            "/f",
+        ],
+    );
+
+    Ok(())
+}
+
+fn mime_type(extension: &str) -> Option<&'static str> {
+    let extension = extension.to_lowercase();
+    MIME_TYPES
+        .iter()
+        .find(|(ext, _)| *ext == extension)
+        .map(|(_, mime_type)| *mime_type)
+}
+
+fn applications_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let dirs = BaseDirs::new().ok_or("Could not find the home directory")?;
+    Ok(dirs.data_local_dir().join("applications"))
+}
+
+fn run(program: &str, args: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Could not run {}: {}", program, e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("{} failed: {}", program, stderr.trim()).into());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,200 @@
+use crate::video::{Video, VideoFrame};
+use eframe::egui;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+
+const THUMBNAIL_COUNT: usize = 24;
+const THUMBNAIL_HEIGHT: u32 = 72;
+
+struct Thumbnail {
+    index: usize,
+    image: egui::ColorImage,
+}
+
+/// Thumbnails spread evenly over the whole file, decoded on a worker thread.
+pub struct Filmstrip {
+    receiver: mpsc::Receiver<Thumbnail>,
+    cancel: Arc<AtomicBool>,
+    textures: Vec<Option<egui::TextureHandle>>,
+    duration_ms: i64,
+}
+
+impl Filmstrip {
+    pub fn start(filename: &str, duration_ms: i64) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancel_flag = cancel.clone();
+        let filename = filename.to_string();
+
+        thread::spawn(move || {
+            if let Err(e) = generate(&filename, duration_ms, &sender, &cancel_flag) {
+                eprintln!("Error generating filmstrip: {}", e);
+            }
+        });
+
+        Self {
+            receiver,
+            cancel,
+            textures: vec![None; THUMBNAIL_COUNT],
+            duration_ms,
+        }
+    }
+
+    /// Uploads thumbnails that finished decoding. Returns true while some are still pending.
+    pub fn update(&mut self, ctx: &egui::Context) -> bool {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(thumbnail) => {
+                    self.textures[thumbnail.index] = Some(ctx.load_texture(
+                        format!("filmstrip_{}", thumbnail.index),
+                        thumbnail.image,
+                        egui::TextureOptions::LINEAR,
+                    ));
+                }
+                Err(mpsc::TryRecvError::Empty) => return true,
+                Err(mpsc::TryRecvError::Disconnected) => return false,
+            }
+        }
+    }
+
+    /// Draws the strip into `rect`, marking the playback position in `accent`, and returns
+    /// the time to seek to when a thumbnail is clicked.
+    pub fn show(
+        &self,
+        ui: &mut egui::Ui,
+        rect: egui::Rect,
+        current_ms: i64,
+        accent: egui::Color32,
+    ) -> Option<i64> {
+        let response = ui.interact(rect, ui.id().with("filmstrip"), egui::Sense::click());
+        let painter = ui.painter_at(rect);
+        let cell_width = rect.width() / THUMBNAIL_COUNT as f32;
+
+        painter.rect_filled(rect, egui::Rounding::ZERO, egui::Color32::from_gray(20));
+
+        for (index, texture) in self.textures.iter().enumerate() {
+            let cell = egui::Rect::from_min_size(
+                rect.min + egui::vec2(cell_width * index as f32, 0.0),
+                egui::vec2(cell_width, rect.height()),
+            )
+            .shrink(1.0);
+
+            if let Some(texture) = texture {
+                painter.image(
+                    texture.id(),
+                    cell,
+                    crop_uv(texture.size_vec2(), cell.size()),
+                    egui::Color32::WHITE,
+                );
+            }
+        }
+
+        if self.duration_ms > 0 {
+            let x = rect.left() + rect.width() * current_ms as f32 / self.duration_ms as f32;
+            painter.vline(
+                x.clamp(rect.left(), rect.right()),
+                rect.y_range(),
+                egui::Stroke::new(2.0, accent),
+            );
+        }
+
+        if let Some(hover_pos) = response.hover_pos() {
+            let index = self.cell_at(rect, hover_pos.x);
+            let cell = egui::Rect::from_min_size(
+                rect.min + egui::vec2(cell_width * index as f32, 0.0),
+                egui::vec2(cell_width, rect.height()),
+            );
+            painter.rect_stroke(
+                cell,
+                egui::Rounding::ZERO,
+                egui::Stroke::new(1.0, egui::Color32::WHITE),
+            );
+        }
+
+        if response.clicked() {
+            let pointer_pos = response.interact_pointer_pos()?;
+            let index = self.cell_at(rect, pointer_pos.x);
+            return Some(self.duration_ms * index as i64 / THUMBNAIL_COUNT as i64);
+        }
+
+        None
+    }
+
+    fn cell_at(&self, rect: egui::Rect, x: f32) -> usize {
+        let relative = ((x - rect.left()) / rect.width()).clamp(0.0, 1.0);
+        ((relative * THUMBNAIL_COUNT as f32) as usize).min(THUMBNAIL_COUNT - 1)
+    }
+}
+
+impl Drop for Filmstrip {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Crops the thumbnail to the cell's aspect ratio so the strip has no gaps.
+fn crop_uv(image_size: egui::Vec2, cell_size: egui::Vec2) -> egui::Rect {
+    let image_aspect = image_size.x / image_size.y;
+    let cell_aspect = cell_size.x / cell_size.y;
+
+    let uv_size = if image_aspect > cell_aspect {
+        egui::vec2(cell_aspect / image_aspect, 1.0)
+    } else {
+        egui::vec2(1.0, image_aspect / cell_aspect)
+    };
+
+    egui::Rect::from_center_size(egui::pos2(0.5, 0.5), uv_size)
+}
+
+/// Scales a decoded frame down to `height`, keeping its aspect ratio.
+pub fn thumbnail(frame: &VideoFrame, height: u32) -> Option<egui::ColorImage> {
+    let image = image::RgbaImage::from_raw(
+        frame.width as u32,
+        frame.height as u32,
+        frame.buffer.clone(),
+    )?;
+    let width = (frame.width as u32 * height / frame.height.max(1) as u32).max(1);
+    let thumbnail = image::imageops::thumbnail(&image, width, height);
+
+    Some(egui::ColorImage::from_rgba_unmultiplied(
+        [thumbnail.width() as usize, thumbnail.height() as usize],
+        thumbnail.as_raw(),
+    ))
+}
+
+fn generate(
+    filename: &str,
+    duration_ms: i64,
+    sender: &mpsc::Sender<Thumbnail>,
+    cancel: &AtomicBool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut video = Video::new(filename)?;
+
+    for index in 0..THUMBNAIL_COUNT {
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+
+        // Sample the middle of each segment
+        let time_ms = duration_ms * (index as i64 * 2 + 1) / (THUMBNAIL_COUNT as i64 * 2);
+        video.seek(time_ms)?;
+
+        let frame = match video.next_frame() {
+            Some(Ok(frame)) => frame,
+            Some(Err(e)) => {
+                eprintln!("Error decoding filmstrip frame: {}", e);
+                continue;
+            }
+            None => break,
+        };
+
+        let image = thumbnail(frame, THUMBNAIL_HEIGHT).ok_or("Invalid frame buffer")?;
+
+        if sender.send(Thumbnail { index, image }).is_err() {
+            break;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,238 @@
+extern crate ffmpeg_next as ffmpeg;
+
+use crate::http;
+use crate::subtitles::{SubtitleFont, SubtitleStyle};
+use eframe::egui;
+use ffmpeg::media;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::slice;
+
+/// Family subtitles are drawn with: the chosen font file, the fonts attached to the open
+/// file, then the default fonts with their fallbacks.
+pub const SUBTITLE_FAMILY: &str = "subtitle";
+
+const FONT_MIME_TYPES: [&str; 9] = [
+    "font/ttf",
+    "font/otf",
+    "font/sfnt",
+    "font/collection",
+    "application/x-truetype-font",
+    "application/x-font-ttf",
+    "application/x-font-otf",
+    "application/vnd.ms-opentype",
+    "application/font-sfnt",
+];
+const FONT_EXTENSIONS: [&str; 3] = ["ttf", "otf", "ttc"];
+
+/// Fonts with the CJK, Hebrew and Arabic glyphs egui's own fonts lack. The first one found
+/// of each group is used.
+#[cfg(target_os = "windows")]
+const SYSTEM_FALLBACKS: &[&[&str]] = &[
+    &[
+        "C:\\Windows\\Fonts\\msyh.ttc",
+        "C:\\Windows\\Fonts\\simsun.ttc",
+    ],
+    &[
+        "C:\\Windows\\Fonts\\YuGothM.ttc",
+        "C:\\Windows\\Fonts\\msgothic.ttc",
+    ],
+    &["C:\\Windows\\Fonts\\malgun.ttf"],
+    &["C:\\Windows\\Fonts\\arial.ttf"],
+];
+#[cfg(target_os = "macos")]
+const SYSTEM_FALLBACKS: &[&[&str]] = &[
+    &[
+        "/System/Library/Fonts/PingFang.ttc",
+        "/System/Library/Fonts/STHeiti Medium.ttc",
+    ],
+    &["/System/Library/Fonts/AppleSDGothicNeo.ttc"],
+    &[
+        "/System/Library/Fonts/Supplemental/Arial.ttf",
+        "/Library/Fonts/Arial.ttf",
+    ],
+];
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const SYSTEM_FALLBACKS: &[&[&str]] = &[
+    &[
+        "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+        "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+        "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+        "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
+        "/usr/share/fonts/wenquanyi/wqy-microhei/wqy-microhei.ttc",
+    ],
+    &[
+        "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+        "/usr/share/fonts/TTF/DejaVuSans.ttf",
+        "/usr/share/fonts/dejavu/DejaVuSans.ttf",
+        "/usr/share/fonts/dejavu-sans-fonts/DejaVuSans.ttf",
+    ],
+];
+
+pub struct AttachedFont {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+/// Whether an attachment stream holds a font, by its MIME type or file name.
+pub fn is_font_attachment(metadata: &HashMap<String, String>) -> bool {
+    let mime_type = metadata.get("mimetype").map(|m| m.to_lowercase());
+    let by_mime_type = mime_type.is_some_and(|m| FONT_MIME_TYPES.contains(&m.as_str()));
+    let by_extension = metadata
+        .get("filename")
+        .and_then(|name| Path::new(name).extension())
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| FONT_EXTENSIONS.contains(&extension.to_lowercase().as_str()));
+    by_mime_type || by_extension
+}
+
+/// The fonts attached to a Matroska file, which its styled subtitles are usually made for.
+pub fn attached_fonts(filename: &str) -> Vec<AttachedFont> {
+    let input = match http::open_input(filename) {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("Error reading attached fonts: {}", e);
+            return Vec::new();
+        }
+    };
+
+    let mut fonts = Vec::new();
+    for stream in input.streams() {
+        let parameters = stream.parameters();
+        if parameters.medium() != media::Type::Attachment {
+            continue;
+        }
+        let metadata: HashMap<String, String> = stream
+            .metadata()
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        if !is_font_attachment(&metadata) {
+            continue;
+        }
+
+        // The demuxer hands the attached file over as the stream's extradata
+        let data = unsafe {
+            let parameters = parameters.as_ptr();
+            if (*parameters).extradata.is_null() || (*parameters).extradata_size <= 0 {
+                continue;
+            }
+            slice::from_raw_parts(
+                (*parameters).extradata,
+                (*parameters).extradata_size as usize,
+            )
+            .to_vec()
+        };
+        fonts.push(AttachedFont {
+            name: metadata
+                .get("filename")
+                .cloned()
+                .unwrap_or_else(|| format!("attachment {}", stream.index())),
+            data,
+        });
+    }
+
+    if !fonts.is_empty() {
+        println!("Loaded {} attached fonts", fonts.len());
+    }
+    fonts
+}
+
+fn system_fallbacks() -> Vec<&'static str> {
+    SYSTEM_FALLBACKS
+        .iter()
+        .filter_map(|group| group.iter().find(|path| Path::new(path).is_file()))
+        .copied()
+        .collect()
+}
+
+/// What the fonts were last built from, so that they are only rebuilt when it changes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontSetup {
+    font: SubtitleFont,
+    fallback_fonts: Vec<String>,
+    system_fallback_fonts: bool,
+    attachments: Vec<String>,
+}
+
+impl FontSetup {
+    pub fn new(style: &SubtitleStyle, attached: &[AttachedFont]) -> Self {
+        Self {
+            font: style.font.clone(),
+            fallback_fonts: style.fallback_fonts.clone(),
+            system_fallback_fonts: style.system_fallback_fonts,
+            attachments: attached.iter().map(|font| font.name.clone()).collect(),
+        }
+    }
+}
+
+/// Hands egui the subtitle font, the attached fonts and the fallbacks. The fallbacks also
+/// serve the interface, e.g. for file names in other scripts. Fails only when the chosen
+/// subtitle font can't be read; fallbacks that can't be read are skipped.
+pub fn apply(
+    ctx: &egui::Context,
+    style: &SubtitleStyle,
+    attached: &[AttachedFont],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut fonts = egui::FontDefinitions::default();
+
+    let mut subtitle_family = Vec::new();
+    if let SubtitleFont::File(path) = &style.font {
+        let bytes = fs::read(path)?;
+        fonts.font_data.insert(
+            SUBTITLE_FAMILY.to_string(),
+            egui::FontData::from_owned(bytes),
+        );
+        subtitle_family.push(SUBTITLE_FAMILY.to_string());
+    }
+    for font in attached {
+        let name = format!("attachment:{}", font.name);
+        fonts
+            .font_data
+            .insert(name.clone(), egui::FontData::from_owned(font.data.clone()));
+        subtitle_family.push(name);
+    }
+
+    let system = if style.system_fallback_fonts {
+        system_fallbacks()
+    } else {
+        Vec::new()
+    };
+    let mut fallbacks = Vec::new();
+    for path in style
+        .fallback_fonts
+        .iter()
+        .map(String::as_str)
+        .chain(system)
+    {
+        match fs::read(path) {
+            Ok(bytes) => {
+                let name = format!("fallback:{}", path);
+                fonts
+                    .font_data
+                    .insert(name.clone(), egui::FontData::from_owned(bytes));
+                fallbacks.push(name);
+            }
+            Err(e) => eprintln!("Error loading fallback font {}: {}", path, e),
+        }
+    }
+    for family in [egui::FontFamily::Proportional, egui::FontFamily::Monospace] {
+        if let Some(fonts) = fonts.families.get_mut(&family) {
+            fonts.extend(fallbacks.iter().cloned());
+        }
+    }
+
+    let base = match style.font {
+        SubtitleFont::Monospace => egui::FontFamily::Monospace,
+        _ => egui::FontFamily::Proportional,
+    };
+    subtitle_family.extend(fonts.families[&base].iter().cloned());
+    fonts.families.insert(
+        egui::FontFamily::Name(SUBTITLE_FAMILY.into()),
+        subtitle_family,
+    );
+
+    ctx.set_fonts(fonts);
+    Ok(())
+}
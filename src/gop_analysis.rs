@@ -0,0 +1,371 @@
+extern crate ffmpeg_next as ffmpeg;
+
+use crate::http;
+use crate::i18n::tr;
+use eframe::egui;
+use ffmpeg::{codec, frame, picture, Rational, Rescale};
+use ffmpeg_next::threading::Type::Frame;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const FRAME_TYPES: [(&str, egui::Color32); 3] = [
+    ("I", egui::Color32::from_rgb(230, 90, 80)),
+    ("P", egui::Color32::from_rgb(100, 190, 110)),
+    ("B", egui::Color32::from_rgb(100, 150, 255)),
+];
+const CHART_HEIGHT: f32 = 140.0;
+/// Narrowest bar; longer files put several seconds into one bar.
+const MIN_BAR_WIDTH: f32 = 3.0;
+
+/// Frame types and keyframe spacing of one video stream.
+#[derive(Default)]
+pub struct GopReport {
+    /// Decoded I, P and B frames. SI/SP/S frames count as I/P, BI as B.
+    pub frame_counts: [usize; 3],
+    /// Frames from each keyframe up to the next one.
+    pub gop_lengths: Vec<usize>,
+    pub keyframes_ms: Vec<i64>,
+    /// I, P and B frame counts for every second of the stream.
+    pub seconds: Vec<[u32; 3]>,
+    frames_since_keyframe: usize,
+}
+
+impl GopReport {
+    fn add(&mut self, frame: &frame::Video, time_base: Rational) {
+        let kind = match frame.kind() {
+            picture::Type::I | picture::Type::SI => 0,
+            picture::Type::P | picture::Type::S | picture::Type::SP => 1,
+            picture::Type::B | picture::Type::BI => 2,
+            picture::Type::None if frame.is_key() => 0,
+            picture::Type::None => 1,
+        };
+        self.frame_counts[kind] += 1;
+
+        let time_ms = frame
+            .timestamp()
+            .or(frame.pts())
+            .map(|ts| ts.rescale(time_base, Rational(1, 1000)))
+            .unwrap_or(0)
+            .max(0);
+        let second = (time_ms / 1000) as usize;
+        if self.seconds.len() <= second {
+            self.seconds.resize(second + 1, [0; 3]);
+        }
+        self.seconds[second][kind] += 1;
+
+        if frame.is_key() {
+            if !self.keyframes_ms.is_empty() {
+                self.gop_lengths.push(self.frames_since_keyframe);
+            }
+            self.keyframes_ms.push(time_ms);
+            self.frames_since_keyframe = 0;
+        }
+        self.frames_since_keyframe += 1;
+    }
+
+    fn finish(&mut self) {
+        if !self.keyframes_ms.is_empty() && self.frames_since_keyframe > 0 {
+            self.gop_lengths.push(self.frames_since_keyframe);
+        }
+    }
+
+    fn total_frames(&self) -> usize {
+        self.frame_counts.iter().sum()
+    }
+
+    /// Longest stretch between keyframes, i.e. the worst case for seeking.
+    fn max_keyframe_interval_ms(&self) -> Option<i64> {
+        self.keyframes_ms
+            .windows(2)
+            .map(|pair| pair[1] - pair[0])
+            .max()
+    }
+}
+
+enum AnalysisEvent {
+    Progress(i64),
+    Finished(Result<GopReport, String>),
+}
+
+/// Decodes the whole video stream on a worker thread. Dropping it stops the scan.
+pub struct GopAnalysis {
+    receiver: mpsc::Receiver<AnalysisEvent>,
+    cancel: Arc<AtomicBool>,
+    duration_ms: i64,
+    position_ms: i64,
+    result: Option<Result<GopReport, String>>,
+}
+
+impl GopAnalysis {
+    pub fn start(filename: &str, stream_index: usize, duration_ms: i64) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancel_flag = cancel.clone();
+        let filename = filename.to_string();
+
+        thread::spawn(move || {
+            let result = analyze(&filename, stream_index, &sender, &cancel_flag);
+            let _ = sender.send(AnalysisEvent::Finished(result.map_err(|e| e.to_string())));
+        });
+
+        Self {
+            receiver,
+            cancel,
+            duration_ms,
+            position_ms: 0,
+            result: None,
+        }
+    }
+
+    /// Returns true while the scan is still running.
+    pub fn update(&mut self) -> bool {
+        for event in self.receiver.try_iter() {
+            match event {
+                AnalysisEvent::Progress(position_ms) => self.position_ms = position_ms,
+                AnalysisEvent::Finished(result) => self.result = Some(result),
+            }
+        }
+        self.result.is_none()
+    }
+
+    /// Draws progress or the report. Returns the time to seek to when the chart is clicked.
+    pub fn show(&self, ui: &mut egui::Ui, current_ms: i64) -> Option<i64> {
+        match &self.result {
+            None => {
+                let progress = if self.duration_ms > 0 {
+                    self.position_ms as f32 / self.duration_ms as f32
+                } else {
+                    0.0
+                };
+                ui.label(tr!("gop-decoding"));
+                ui.add(egui::ProgressBar::new(progress.clamp(0.0, 1.0)).show_percentage());
+                None
+            }
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::LIGHT_RED, tr!("gop-failed", error = e));
+                None
+            }
+            Some(Ok(report)) => show_report(ui, report, current_ms),
+        }
+    }
+}
+
+impl Drop for GopAnalysis {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+fn analyze(
+    filename: &str,
+    stream_index: usize,
+    sender: &mpsc::Sender<AnalysisEvent>,
+    cancel: &AtomicBool,
+) -> Result<GopReport, Box<dyn std::error::Error>> {
+    let mut input = http::open_input(&filename)?;
+    let (mut decoder, time_base) = {
+        let stream = input
+            .stream(stream_index)
+            .ok_or("The video stream was not found")?;
+        let mut decoder_ctx = codec::Context::from_parameters(stream.parameters())?;
+        decoder_ctx.set_threading(ffmpeg::threading::Config {
+            count: num_cpus::get(),
+            kind: Frame,
+        });
+        (decoder_ctx.decoder().video()?, stream.time_base())
+    };
+
+    let mut report = GopReport::default();
+    let mut decoded = frame::Video::empty();
+    let mut last_progress = Instant::now();
+
+    for (stream, packet) in input.packets() {
+        if cancel.load(Ordering::Relaxed) {
+            return Err("Cancelled".into());
+        }
+        if stream.index() != stream_index {
+            continue;
+        }
+
+        // Damaged packets are skipped like during playback
+        if decoder.send_packet(&packet).is_err() {
+            continue;
+        }
+        while decoder.receive_frame(&mut decoded).is_ok() {
+            report.add(&decoded, time_base);
+        }
+
+        if last_progress.elapsed() > Duration::from_millis(100) {
+            last_progress = Instant::now();
+            let position_ms = packet
+                .pts()
+                .map(|pts| pts.rescale(time_base, Rational(1, 1000)))
+                .unwrap_or(0);
+            let _ = sender.send(AnalysisEvent::Progress(position_ms));
+        }
+    }
+
+    decoder.send_eof()?;
+    while decoder.receive_frame(&mut decoded).is_ok() {
+        report.add(&decoded, time_base);
+    }
+
+    report.finish();
+    Ok(report)
+}
+
+fn show_report(ui: &mut egui::Ui, report: &GopReport, current_ms: i64) -> Option<i64> {
+    let total = report.total_frames();
+    if total == 0 {
+        ui.label(tr!("gop-no-frames"));
+        return None;
+    }
+
+    egui::Grid::new("gop_summary")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label(tr!("gop-frames"));
+            ui.label(total.to_string());
+            ui.end_row();
+
+            for ((name, color), count) in FRAME_TYPES.iter().zip(report.frame_counts) {
+                ui.colored_label(*color, tr!("gop-frames-of-type", kind = name));
+                ui.label(format!(
+                    "{} ({:.1}%)",
+                    count,
+                    count as f64 * 100.0 / total as f64
+                ));
+                ui.end_row();
+            }
+
+            ui.label(tr!("gop-keyframes"));
+            ui.label(report.keyframes_ms.len().to_string());
+            ui.end_row();
+
+            if let (Some(min), Some(max)) = (
+                report.gop_lengths.iter().min(),
+                report.gop_lengths.iter().max(),
+            ) {
+                let average = report.gop_lengths.iter().sum::<usize>() as f64
+                    / report.gop_lengths.len() as f64;
+                ui.label(tr!("gop-length"));
+                ui.label(tr!(
+                    "gop-length-value",
+                    average = format!("{:.1}", average),
+                    min = min,
+                    max = max
+                ));
+                ui.end_row();
+            }
+
+            if let Some(interval_ms) = report.max_keyframe_interval_ms() {
+                ui.label(tr!("gop-longest-gap"));
+                ui.label(format!("{:.2} s", interval_ms as f64 / 1000.0));
+                ui.end_row();
+            }
+        });
+
+    ui.add_space(8.0);
+    ui.horizontal(|ui| {
+        ui.label(tr!("gop-frame-types-per-second"));
+        for (name, color) in FRAME_TYPES {
+            ui.colored_label(color, format!("■ {}", name));
+        }
+    });
+
+    draw_chart(ui, report, current_ms)
+}
+
+/// Stacked bars of I/P/B frames over time, with keyframes marked below.
+fn draw_chart(ui: &mut egui::Ui, report: &GopReport, current_ms: i64) -> Option<i64> {
+    let width = ui.available_width().max(100.0);
+    let (rect, response) =
+        ui.allocate_exact_size(egui::vec2(width, CHART_HEIGHT), egui::Sense::click());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, egui::Rounding::ZERO, egui::Color32::from_gray(20));
+
+    let seconds = report.seconds.len().max(1);
+    let seconds_per_bar = (seconds as f32 / (width / MIN_BAR_WIDTH)).ceil().max(1.0) as usize;
+    let bars: Vec<[u32; 3]> = report
+        .seconds
+        .chunks(seconds_per_bar)
+        .map(|chunk| {
+            chunk.iter().fold([0; 3], |sum, counts| {
+                [sum[0] + counts[0], sum[1] + counts[1], sum[2] + counts[2]]
+            })
+        })
+        .collect();
+
+    let marker_height = 10.0;
+    let chart_bottom = rect.bottom() - marker_height;
+    let max_total = bars
+        .iter()
+        .map(|counts| counts.iter().sum::<u32>())
+        .max()
+        .unwrap_or(1)
+        .max(1);
+    let bar_width = rect.width() / bars.len().max(1) as f32;
+    let scale = (chart_bottom - rect.top() - 4.0) / max_total as f32;
+
+    for (index, counts) in bars.iter().enumerate() {
+        let left = rect.left() + bar_width * index as f32;
+        let mut bottom = chart_bottom;
+        for (count, (_, color)) in counts.iter().zip(FRAME_TYPES) {
+            let height = *count as f32 * scale;
+            painter.rect_filled(
+                egui::Rect::from_min_max(
+                    egui::pos2(left, bottom - height),
+                    egui::pos2(left + (bar_width - 1.0).max(1.0), bottom),
+                ),
+                egui::Rounding::ZERO,
+                color,
+            );
+            bottom -= height;
+        }
+    }
+
+    let duration_ms = (seconds * 1000) as f32;
+    let x_at = |time_ms: i64| rect.left() + rect.width() * time_ms as f32 / duration_ms;
+    for &keyframe_ms in &report.keyframes_ms {
+        painter.vline(
+            x_at(keyframe_ms),
+            chart_bottom..=rect.bottom(),
+            egui::Stroke::new(1.0, FRAME_TYPES[0].1),
+        );
+    }
+    painter.vline(
+        x_at(current_ms).clamp(rect.left(), rect.right()),
+        rect.y_range(),
+        egui::Stroke::new(1.0, egui::Color32::WHITE),
+    );
+
+    let bar_at = |x: f32| {
+        let relative = ((x - rect.left()) / rect.width()).clamp(0.0, 1.0);
+        ((relative * bars.len() as f32) as usize).min(bars.len().saturating_sub(1))
+    };
+
+    if let Some(hover_pos) = response.hover_pos() {
+        let index = bar_at(hover_pos.x);
+        if let Some(counts) = bars.get(index) {
+            let start = index * seconds_per_bar;
+            let range = if seconds_per_bar == 1 {
+                format!("{} s", start)
+            } else {
+                format!("{}–{} s", start, start + seconds_per_bar)
+            };
+            response.clone().on_hover_text(format!(
+                "{}\nI: {}  P: {}  B: {}",
+                range, counts[0], counts[1], counts[2]
+            ));
+        }
+    }
+
+    if response.clicked() {
+        let pointer_pos = response.interact_pointer_pos()?;
+        return Some((bar_at(pointer_pos.x) * seconds_per_bar * 1000) as i64);
+    }
+
+    None
+}
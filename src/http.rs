@@ -0,0 +1,464 @@
+extern crate ffmpeg_next as ffmpeg;
+
+use crate::{dvd, raw_stream, security};
+use ffmpeg::{format, Dictionary};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::sync::{OnceLock, RwLock};
+
+/// What FFmpeg sends along with HTTP requests for network playback.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpOptions {
+    pub user_agent: String,
+    /// One `Name: value` per line.
+    pub headers: String,
+    /// A cookies.txt file in the Netscape format browsers export.
+    pub cookies_file: String,
+    /// Sent as basic auth when not empty.
+    pub username: String,
+    pub password: String,
+}
+
+impl HttpOptions {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// These options with the ones set in `other` taking precedence; headers of both are sent.
+    fn merged(&self, other: &HttpOptions) -> HttpOptions {
+        let pick = |own: &String, other: &String| {
+            if other.is_empty() {
+                own.clone()
+            } else {
+                other.clone()
+            }
+        };
+        let headers = [self.headers.trim(), other.headers.trim()]
+            .into_iter()
+            .filter(|headers| !headers.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        HttpOptions {
+            user_agent: pick(&self.user_agent, &other.user_agent),
+            headers,
+            cookies_file: pick(&self.cookies_file, &other.cookies_file),
+            username: pick(&self.username, &other.username),
+            password: if other.username.is_empty() {
+                self.password.clone()
+            } else {
+                other.password.clone()
+            },
+        }
+    }
+
+    fn header_lines(&self) -> Vec<String> {
+        let mut lines: Vec<String> = (self.headers.lines().map(str::trim))
+            .filter(|line| line.contains(':'))
+            .map(str::to_string)
+            .collect();
+        if !self.username.is_empty() {
+            let credentials = format!("{}:{}", self.username, self.password);
+            lines.push(format!(
+                "Authorization: Basic {}",
+                base64(credentials.as_bytes())
+            ));
+        }
+        lines
+    }
+
+    /// Options for FFmpeg's HTTP protocol.
+    fn dictionary(&self, url: &str) -> Dictionary<'static> {
+        let mut options = Dictionary::new();
+        if !self.user_agent.is_empty() {
+            options.set("user_agent", &self.user_agent);
+        }
+        let headers = self.header_lines();
+        if !headers.is_empty() {
+            options.set("headers", &format!("{}\r\n", headers.join("\r\n")));
+        }
+        if !self.cookies_file.is_empty() {
+            match fs::read_to_string(&self.cookies_file) {
+                Ok(source) => {
+                    let cookies = cookies_for(&source, host(url).unwrap_or_default());
+                    if !cookies.is_empty() {
+                        options.set("cookies", &cookies.join("\n"));
+                    }
+                }
+                Err(e) => eprintln!("Error reading cookies {}: {}", self.cookies_file, e),
+            }
+        }
+        options
+    }
+
+    fn yt_dlp_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if !self.user_agent.is_empty() {
+            args.extend(["--user-agent".to_string(), self.user_agent.clone()]);
+        }
+        for header in self.headers.lines().map(str::trim) {
+            if header.contains(':') {
+                args.extend(["--add-header".to_string(), header.to_string()]);
+            }
+        }
+        if !self.cookies_file.is_empty() {
+            args.extend(["--cookies".to_string(), self.cookies_file.clone()]);
+        }
+        if !self.username.is_empty() {
+            args.extend(["--username".to_string(), self.username.clone()]);
+            args.extend(["--password".to_string(), self.password.clone()]);
+        }
+        args
+    }
+}
+
+#[derive(Default)]
+struct Registry {
+    defaults: HttpOptions,
+    /// From the settings, by host name.
+    sites: HashMap<String, HttpOptions>,
+    /// Entered when opening a URL, for this session.
+    urls: HashMap<String, HttpOptions>,
+    /// Proxy URL from the settings; empty for the system proxy, `direct` for none.
+    proxy: String,
+}
+
+fn registry() -> &'static RwLock<Registry> {
+    static REGISTRY: OnceLock<RwLock<Registry>> = OnceLock::new();
+    REGISTRY.get_or_init(Default::default)
+}
+
+/// Sets the options from the settings: `defaults` for every URL, `sites` for URLs on a host,
+/// and the proxy for all network access.
+pub fn configure(defaults: &HttpOptions, sites: &HashMap<String, HttpOptions>, proxy: &str) {
+    let mut registry = registry().write().unwrap();
+    registry.defaults = defaults.clone();
+    registry.sites = (sites.iter())
+        .map(|(host, options)| (host.to_lowercase(), options.clone()))
+        .collect();
+    registry.proxy = match proxy.trim() {
+        proxy if proxy.is_empty() || proxy == "direct" || proxy.contains("://") => {
+            proxy.to_string()
+        }
+        proxy => format!("http://{}", proxy),
+    };
+}
+
+/// Uses `options` for this URL on top of the configured ones.
+pub fn set_url_options(url: &str, options: HttpOptions) {
+    let mut registry = registry().write().unwrap();
+    if options.is_empty() {
+        registry.urls.remove(url);
+    } else {
+        registry.urls.insert(url.to_string(), options);
+    }
+}
+
+/// The options entered for this URL, if any.
+pub fn url_options(url: &str) -> Option<HttpOptions> {
+    registry().read().unwrap().urls.get(url).cloned()
+}
+
+/// Everything that applies to this URL: defaults, then the site's, then the URL's own.
+pub fn options_for(url: &str) -> HttpOptions {
+    let registry = registry().read().unwrap();
+    let mut options = registry.defaults.clone();
+    if let Some(site) = host(url).and_then(|host| registry.sites.get(&host.to_lowercase())) {
+        options = options.merged(site);
+    }
+    if let Some(own) = registry.urls.get(url) {
+        options = options.merged(own);
+    }
+    options
+}
+
+/// The proxy to reach this URL through, if any.
+pub fn proxy_for(url: &str) -> Option<String> {
+    match registry().read().unwrap().proxy.as_str() {
+        "" => system_proxy().for_url(url).map(str::to_string),
+        "direct" => None,
+        proxy => Some(proxy.to_string()),
+    }
+}
+
+/// Opens a file or URL for demuxing, with the HTTP options and proxy for http(s) URLs and
+/// the protocol and format restrictions from the security settings.
+pub fn open_input(filename: &str) -> Result<format::context::Input, ffmpeg::Error> {
+    let mut options = if is_http(filename) {
+        http_dictionary(filename)?
+    } else {
+        Dictionary::new()
+    };
+    security::restrict(filename, &mut options);
+
+    if dvd::is_dvd(filename) {
+        return dvd::open(filename, options);
+    }
+    if let Some(hint) = raw_stream::hint_for(filename) {
+        return raw_stream::open(filename, &hint, options);
+    }
+    format::input_with_dictionary(&filename, options)
+}
+
+/// Fails for a proxy FFmpeg can't use rather than opening the URL without it.
+fn http_dictionary(filename: &str) -> Result<Dictionary<'static>, ffmpeg::Error> {
+    let mut options = options_for(filename).dictionary(filename);
+    // An empty proxy also keeps FFmpeg from reading `http_proxy` itself
+    let proxy = match proxy_for(filename) {
+        Some(proxy) if proxy.to_ascii_lowercase().starts_with("http://") => proxy,
+        Some(proxy) => {
+            eprintln!(
+                "FFmpeg only supports HTTP proxies, not opening {} without {}",
+                filename, proxy
+            );
+            return Err(ffmpeg::Error::ProtocolNotFound);
+        }
+        None => String::new(),
+    };
+    options.set("http_proxy", &proxy);
+    Ok(options)
+}
+
+/// Arguments for yt-dlp with the HTTP options and proxy for this URL.
+pub fn yt_dlp_args(url: &str) -> Vec<String> {
+    let mut args = options_for(url).yt_dlp_args();
+    // yt-dlp takes an empty proxy as a direct connection
+    args.extend(["--proxy".to_string(), proxy_for(url).unwrap_or_default()]);
+    args
+}
+
+/// An HTTP client for the player's own requests to `url`, going through its proxy. Fails
+/// for a proxy that can't be used rather than connecting directly.
+pub fn agent(url: &str) -> Result<ureq::Agent, Box<dyn std::error::Error>> {
+    let builder = ureq::AgentBuilder::new();
+    let builder = match proxy_for(url) {
+        Some(proxy) => builder.proxy(ureq::Proxy::new(&proxy)?),
+        None => builder,
+    };
+    Ok(builder.build())
+}
+
+/// The proxies the system is set up to use, by URL scheme.
+#[derive(Debug, Default)]
+struct SystemProxy {
+    http: Option<String>,
+    https: Option<String>,
+    /// Hosts and domains reached directly.
+    bypass: Vec<String>,
+}
+
+impl SystemProxy {
+    /// Reads the `*_proxy` environment variables, or the system-wide setting on Windows and
+    /// macOS when they are not set.
+    fn detect() -> Self {
+        let env = |names: [&str; 2]| {
+            (names.iter())
+                .find_map(|name| std::env::var(name).ok())
+                .filter(|value| !value.trim().is_empty())
+        };
+        let all = env(["ALL_PROXY", "all_proxy"]);
+        let bypass = env(["NO_PROXY", "no_proxy"])
+            .map(|hosts| {
+                (hosts.split(','))
+                    .map(|host| host.trim().to_lowercase())
+                    .filter(|host| !host.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let proxy = SystemProxy {
+            http: env(["HTTP_PROXY", "http_proxy"]).or_else(|| all.clone()),
+            https: env(["HTTPS_PROXY", "https_proxy"]).or(all),
+            bypass,
+        };
+
+        if proxy.http.is_none() && proxy.https.is_none() {
+            if let Some(platform) = platform_proxy() {
+                return platform;
+            }
+        }
+        proxy
+    }
+
+    fn for_url(&self, url: &str) -> Option<&str> {
+        let host = host(url)?.to_lowercase();
+        let bypassed = self.bypass.iter().any(|entry| {
+            let domain = entry.trim_start_matches('*').trim_start_matches('.');
+            entry == "*" || host == domain || host.ends_with(&format!(".{}", domain))
+        });
+        if bypassed {
+            return None;
+        }
+
+        if url.to_ascii_lowercase().starts_with("https://") {
+            self.https.as_deref()
+        } else {
+            self.http.as_deref()
+        }
+    }
+}
+
+fn system_proxy() -> &'static SystemProxy {
+    static PROXY: OnceLock<SystemProxy> = OnceLock::new();
+    PROXY.get_or_init(|| {
+        let proxy = SystemProxy::detect();
+        if proxy.http.is_some() || proxy.https.is_some() {
+            println!("Using system proxy {:?}", proxy);
+        }
+        proxy
+    })
+}
+
+/// The proxy set in the Internet Options.
+#[cfg(windows)]
+fn platform_proxy() -> Option<SystemProxy> {
+    let output = std::process::Command::new("reg")
+        .args([
+            "query",
+            r"HKCU\Software\Microsoft\Windows\CurrentVersion\Internet Settings",
+        ])
+        .output()
+        .ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    // Lines are `    <name>    <type>    <value>`
+    let value = |name: &str| {
+        text.lines().find_map(|line| {
+            let mut fields = line.split_whitespace();
+            if fields.next()? != name {
+                return None;
+            }
+            fields.nth(1).map(str::to_string)
+        })
+    };
+
+    if value("ProxyEnable")? != "0x1" {
+        return None;
+    }
+    let server = value("ProxyServer")?;
+    let mut proxy = SystemProxy::default();
+    if server.contains('=') {
+        // Per protocol, as `http=host:port;https=host:port;socks=host:port`
+        let mut socks = None;
+        for (protocol, address) in server.split(';').filter_map(|part| part.split_once('=')) {
+            match protocol {
+                "http" => proxy.http = Some(format!("http://{}", address)),
+                "https" => proxy.https = Some(format!("http://{}", address)),
+                "socks" => socks = Some(format!("socks4://{}", address)),
+                _ => {}
+            }
+        }
+        proxy.http = proxy.http.or_else(|| socks.clone());
+        proxy.https = proxy.https.or(socks);
+    } else {
+        proxy.http = Some(format!("http://{}", server));
+        proxy.https = proxy.http.clone();
+    }
+    proxy.bypass = (value("ProxyOverride").unwrap_or_default().split(';'))
+        .filter(|host| !host.is_empty() && *host != "<local>")
+        .map(str::to_lowercase)
+        .collect();
+    Some(proxy)
+}
+
+/// The proxy of the current network service in the System Settings.
+#[cfg(target_os = "macos")]
+fn platform_proxy() -> Option<SystemProxy> {
+    let output = std::process::Command::new("scutil")
+        .arg("--proxy")
+        .output()
+        .ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    // Lines are `  <key> : <value>`
+    let value = |key: String| {
+        text.lines().find_map(|line| {
+            let (name, value) = line.split_once(" : ")?;
+            (name.trim() == key).then(|| value.trim().to_string())
+        })
+    };
+    let proxy = |kind: &str, scheme: &str| {
+        if value(format!("{}Enable", kind))? != "1" {
+            return None;
+        }
+        let host = value(format!("{}Proxy", kind))?;
+        let port = value(format!("{}Port", kind))?;
+        Some(format!("{}://{}:{}", scheme, host, port))
+    };
+
+    let socks = proxy("SOCKS", "socks5");
+    let http = proxy("HTTP", "http").or_else(|| socks.clone());
+    let https = proxy("HTTPS", "http").or(socks);
+    (http.is_some() || https.is_some()).then(|| SystemProxy {
+        http,
+        https,
+        bypass: Vec::new(),
+    })
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn platform_proxy() -> Option<SystemProxy> {
+    None
+}
+
+fn is_http(url: &str) -> bool {
+    let lower = url.to_ascii_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://")
+}
+
+fn host(url: &str) -> Option<&str> {
+    let (_, rest) = url.split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?;
+    Some(host.split(':').next().unwrap_or(host))
+}
+
+/// Turns the cookies of a cookies.txt file that apply to `host` into the `Set-Cookie` lines
+/// FFmpeg takes.
+fn cookies_for(source: &str, host: &str) -> Vec<String> {
+    let host = host.to_lowercase();
+    let mut cookies = Vec::new();
+
+    for line in source.lines() {
+        let line = line.strip_prefix("#HttpOnly_").unwrap_or(line);
+        if line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.trim_end_matches('\r').split('\t').collect();
+        let [domain, _, path, _, _, name, value] = fields[..] else {
+            continue;
+        };
+
+        let domain = domain.trim_start_matches('.').to_lowercase();
+        if host == domain || host.ends_with(&format!(".{}", domain)) {
+            cookies.push(format!(
+                "{}={}; path={}; domain={}",
+                name, value, path, domain
+            ));
+        }
+    }
+
+    cookies
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                text.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+
+    text
+}
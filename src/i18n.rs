@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{OnceLock, RwLock};
+
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+const ENGLISH: &str = include_str!("../locales/en.ftl");
+
+/// Looks up a UI string in the current language, filling in `{ $name }` placeholders.
+///
+/// ```ignore
+/// tr!("button-cancel")
+/// tr!("osd-volume", percent = 80)
+/// ```
+macro_rules! tr {
+    ($id:literal) => {
+        $crate::i18n::message($id, &[])
+    };
+    ($id:literal, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::message(
+            $id,
+            &[$((stringify!($name), &$value as &dyn std::fmt::Display)),+],
+        )
+    };
+}
+pub(crate) use tr;
+
+type Catalog = HashMap<String, String>;
+
+/// Parses the subset of Fluent used by the catalogs: `id = value` messages, indented
+/// continuation lines and `#` comments.
+fn parse(source: &str) -> Catalog {
+    let mut catalog = Catalog::new();
+    let mut current: Option<String> = None;
+
+    for line in source.lines() {
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+        if line.starts_with([' ', '\t']) && !line.trim().is_empty() {
+            if let Some(value) = current.as_ref().and_then(|id| catalog.get_mut(id)) {
+                if !value.is_empty() {
+                    value.push('\n');
+                }
+                value.push_str(line.trim());
+            }
+            continue;
+        }
+
+        current = None;
+        if let Some((id, value)) = line.split_once('=') {
+            let id = id.trim();
+            if !id.is_empty() {
+                catalog.insert(id.to_string(), value.trim().to_string());
+                current = Some(id.to_string());
+            }
+        }
+    }
+
+    catalog
+}
+
+fn english() -> &'static Catalog {
+    static CATALOG: OnceLock<Catalog> = OnceLock::new();
+    CATALOG.get_or_init(|| parse(ENGLISH))
+}
+
+/// The selected language's catalog; messages missing from it fall back to English.
+fn current() -> &'static RwLock<Catalog> {
+    static CATALOG: OnceLock<RwLock<Catalog>> = OnceLock::new();
+    CATALOG.get_or_init(Default::default)
+}
+
+/// Catalogs added by the user, as `<code>.ftl` files next to the settings.
+fn user_catalog_dir() -> Option<PathBuf> {
+    crate::storage::config_dir().map(|dir| dir.join("locales"))
+}
+
+pub struct Language {
+    pub code: String,
+    /// The `language-name` message of the catalog, e.g. "Deutsch".
+    pub name: String,
+}
+
+/// English and every catalog found in the user catalog folder.
+pub fn available_languages() -> Vec<Language> {
+    let mut languages = vec![Language {
+        code: DEFAULT_LANGUAGE.to_string(),
+        name: english()["language-name"].clone(),
+    }];
+
+    let entries = user_catalog_dir().and_then(|dir| fs::read_dir(dir).ok());
+    for path in entries.into_iter().flatten().flatten().map(|e| e.path()) {
+        if path.extension().is_none_or(|ext| ext != "ftl") {
+            continue;
+        }
+        let Some(code) = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+        else {
+            continue;
+        };
+        if code == DEFAULT_LANGUAGE {
+            continue;
+        }
+        let name = fs::read_to_string(&path)
+            .ok()
+            .and_then(|source| parse(&source).remove("language-name"))
+            .unwrap_or_else(|| code.clone());
+        languages.push(Language { code, name });
+    }
+
+    languages[1..].sort_by(|a, b| a.name.cmp(&b.name));
+    languages
+}
+
+/// Switches the UI to the language with this code, or back to English if it has no catalog.
+pub fn set_language(code: &str) {
+    let catalog = if code == DEFAULT_LANGUAGE {
+        Catalog::new()
+    } else {
+        let path = user_catalog_dir().map(|dir| dir.join(format!("{}.ftl", code)));
+        match path.map(fs::read_to_string) {
+            Some(Ok(source)) => parse(&source),
+            Some(Err(e)) => {
+                eprintln!("Error loading language {}: {}", code, e);
+                Catalog::new()
+            }
+            None => Catalog::new(),
+        }
+    };
+
+    *current().write().unwrap() = catalog;
+}
+
+pub fn message(id: &str, args: &[(&str, &dyn Display)]) -> String {
+    let catalog = current().read().unwrap();
+    let Some(pattern) = catalog.get(id).or_else(|| english().get(id)) else {
+        return id.to_string();
+    };
+    if args.is_empty() {
+        return pattern.clone();
+    }
+
+    let mut text = String::with_capacity(pattern.len());
+    let mut rest = pattern.as_str();
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        text.push_str(&rest[..start]);
+
+        let placeholder = rest[start + 1..start + end].trim();
+        match args
+            .iter()
+            .find(|(name, _)| placeholder.strip_prefix('$') == Some(name))
+        {
+            Some((_, value)) => text.push_str(&value.to_string()),
+            None => text.push_str(&rest[start..=start + end]),
+        }
+        rest = &rest[start + end + 1..];
+    }
+    text.push_str(rest);
+    text
+}
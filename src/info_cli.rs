@@ -0,0 +1,379 @@
+use crate::media_info::{self, MediaInfo, RationalValue};
+use crate::VideoPlayer;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+
+/// `avio --info <file> [--json]`: prints the media information and returns without
+/// opening a window.
+pub fn run(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let json = args.iter().any(|arg| arg == "--json");
+    let mut files = args.iter().filter(|arg| *arg != "--json");
+    let (Some(filename), None) = (files.next(), files.next()) else {
+        return Err("Usage: avio --info <file> [--json]".into());
+    };
+
+    let info = media_info::get_media_info(filename)?;
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&to_json(filename, &info))?
+        );
+    } else {
+        print_text(filename, &info);
+    }
+
+    Ok(())
+}
+
+fn debug_name<T: Debug>(value: &Option<T>) -> Option<String> {
+    value.as_ref().map(|value| format!("{:?}", value))
+}
+
+fn rational_json(rational: &RationalValue) -> Value {
+    json!({
+        "numerator": rational.numerator,
+        "denominator": rational.denominator,
+        "value": rational.value,
+    })
+}
+
+/// Sorted so that the output is stable between runs.
+fn sorted(metadata: &HashMap<String, String>) -> BTreeMap<&String, &String> {
+    metadata.iter().collect()
+}
+
+fn to_json(filename: &str, info: &MediaInfo) -> Value {
+    let profiles = |profiles: &Option<Vec<_>>| -> Option<Vec<String>> {
+        profiles.as_ref().map(|profiles| {
+            profiles
+                .iter()
+                .map(|profile| format!("{:?}", profile))
+                .collect()
+        })
+    };
+
+    let video_streams: Vec<Value> = info
+        .video_streams
+        .iter()
+        .map(|stream| {
+            json!({
+                "index": stream.index,
+                "codec_name": stream.codec_name,
+                "codec_id": stream.codec_id,
+                "codec_description": stream.codec_description,
+                "codec_capabilities": debug_name(&stream.codec_capabilities),
+                "codec_profiles": profiles(&stream.codec_profiles),
+                "width": stream.width,
+                "height": stream.height,
+                "pixel_format": debug_name(&stream.pixel_format),
+                "frame_rate": stream.frame_rate.as_ref().map(rational_json),
+                "bit_rate": stream.bit_rate,
+                "frames": stream.frames,
+                "color_space": debug_name(&stream.color_space),
+                "aspect_ratio": stream.aspect_ratio.as_ref().map(rational_json),
+                "time_base": rational_json(&stream.time_base),
+                "disposition": stream.disposition,
+                "attached_pic": stream.is_attached_pic(),
+                "equirectangular": stream.equirectangular,
+                "metadata": sorted(&stream.metadata),
+            })
+        })
+        .collect();
+
+    let audio_streams: Vec<Value> = info
+        .audio_streams
+        .iter()
+        .map(|stream| {
+            json!({
+                "index": stream.index,
+                "codec_name": stream.codec_name,
+                "codec_id": stream.codec_id,
+                "codec_description": stream.codec_description,
+                "codec_capabilities": debug_name(&stream.codec_capabilities),
+                "codec_profiles": profiles(&stream.codec_profiles),
+                "channels": stream.channels,
+                "sample_rate": stream.sample_rate,
+                "sample_format": debug_name(&stream.sample_format),
+                "bit_rate": stream.bit_rate,
+                "channel_layout": debug_name(&stream.channel_layout),
+                "frames": stream.frames,
+                "time_base": rational_json(&stream.time_base),
+                "disposition": stream.disposition,
+                "profile": debug_name(&stream.profile),
+                "metadata": sorted(&stream.metadata),
+            })
+        })
+        .collect();
+
+    let subtitle_streams: Vec<Value> = info
+        .subtitle_streams
+        .iter()
+        .map(|stream| {
+            json!({
+                "index": stream.index,
+                "codec_name": stream.codec_name,
+                "codec_id": stream.codec_id,
+                "language": stream.language,
+                "time_base": rational_json(&stream.time_base),
+                "disposition": stream.disposition,
+                "metadata": sorted(&stream.metadata),
+            })
+        })
+        .collect();
+
+    let other_streams: Vec<Value> = info
+        .other_streams
+        .iter()
+        .map(|stream| {
+            json!({
+                "index": stream.index,
+                "codec_name": stream.codec_name,
+                "codec_id": stream.codec_id,
+                "stream_type": stream.stream_type,
+                "time_base": rational_json(&stream.time_base),
+                "disposition": stream.disposition,
+                "metadata": sorted(&stream.metadata),
+            })
+        })
+        .collect();
+
+    let chapters: Vec<Value> = info
+        .chapters
+        .iter()
+        .map(|chapter| {
+            json!({
+                "index": chapter.index,
+                "title": chapter.title,
+                "start_time_ms": chapter.start_time_ms,
+                "end_time_ms": chapter.end_time_ms,
+                "metadata": sorted(&chapter.metadata),
+            })
+        })
+        .collect();
+
+    let tags = info.tags();
+
+    json!({
+        "path": filename,
+        "format_name": info.format_name,
+        "format_description": info.format_description,
+        "duration_ms": info.duration_ms,
+        "bit_rate": info.bit_rate,
+        "audio_only": info.is_audio_only(),
+        "tags": {
+            "title": tags.title,
+            "artist": tags.artist,
+            "album": tags.album,
+            "track": tags.track,
+            "year": tags.year,
+        },
+        "video_streams": video_streams,
+        "audio_streams": audio_streams,
+        "subtitle_streams": subtitle_streams,
+        "other_streams": other_streams,
+        "chapters": chapters,
+        "metadata": sorted(&info.metadata),
+    })
+}
+
+fn print_field(label: &str, value: impl std::fmt::Display) {
+    println!("  {:<20}{}", format!("{}:", label), value);
+}
+
+fn print_rational(label: &str, rational: &RationalValue) {
+    print_field(
+        label,
+        format!(
+            "{}/{} ({:.6})",
+            rational.numerator, rational.denominator, rational.value
+        ),
+    );
+}
+
+fn print_metadata(metadata: &HashMap<String, String>) {
+    if metadata.is_empty() {
+        return;
+    }
+    println!("  Metadata:");
+    for (key, value) in sorted(metadata) {
+        println!("    {}: {}", key, value);
+    }
+}
+
+fn print_text(filename: &str, info: &MediaInfo) {
+    println!("File Information");
+    print_field("Path", filename);
+    print_field(
+        "Format",
+        format!("{} ({})", info.format_name, info.format_description),
+    );
+    print_field("Duration", VideoPlayer::format_duration(info.duration_ms));
+    print_field(
+        "Overall Bitrate",
+        VideoPlayer::format_bitrate(info.bit_rate),
+    );
+    print_metadata(&info.metadata);
+
+    let tags = info.tags();
+    if !tags.is_empty() {
+        println!();
+        println!("Tags");
+        let fields = [
+            ("Title", &tags.title),
+            ("Artist", &tags.artist),
+            ("Album", &tags.album),
+            ("Track", &tags.track),
+            ("Year", &tags.year),
+        ];
+        for (label, value) in fields {
+            if let Some(value) = value {
+                print_field(label, value);
+            }
+        }
+    }
+
+    for (i, stream) in info.video_streams.iter().enumerate() {
+        println!();
+        println!("Video Stream {} (Index: {})", i, stream.index);
+        print_field(
+            "Resolution",
+            format!(
+                "{}x{}",
+                VideoPlayer::format_optional_u32(stream.width),
+                VideoPlayer::format_optional_u32(stream.height)
+            ),
+        );
+        print_field(
+            "Codec",
+            format!("{} ({})", stream.codec_name, stream.codec_description),
+        );
+        print_field("Codec ID", &stream.codec_id);
+        if let Some(frame_rate) = &stream.frame_rate {
+            print_field(
+                "Frame Rate",
+                format!(
+                    "{:.3} fps ({}/{})",
+                    frame_rate.value, frame_rate.numerator, frame_rate.denominator
+                ),
+            );
+        }
+        if let Some(pixel_format) = debug_name(&stream.pixel_format) {
+            print_field("Pixel Format", pixel_format);
+        }
+        print_field("Bitrate", VideoPlayer::format_bitrate(stream.bit_rate));
+        if let Some(frames) = stream.frames {
+            print_field("Frame Count", frames);
+        }
+        if let Some(aspect_ratio) = &stream.aspect_ratio {
+            print_field(
+                "Aspect Ratio",
+                format!(
+                    "{:.3} ({}/{})",
+                    aspect_ratio.value, aspect_ratio.numerator, aspect_ratio.denominator
+                ),
+            );
+        }
+        if let Some(color_space) = debug_name(&stream.color_space) {
+            print_field("Color Space", color_space);
+        }
+        if stream.is_attached_pic() {
+            print_field("Attached Picture", "Yes");
+        }
+        if stream.equirectangular {
+            print_field("Projection", "Equirectangular (360°)");
+        }
+        print_rational("Time Base", &stream.time_base);
+        print_field("Disposition", format!("0x{:X}", stream.disposition));
+        if let Some(capabilities) = debug_name(&stream.codec_capabilities) {
+            print_field("Codec Capabilities", capabilities);
+        }
+        if let Some(profiles) = &stream.codec_profiles {
+            let names: Vec<String> = profiles.iter().map(|p| format!("{:?}", p)).collect();
+            print_field("Codec Profiles", names.join(", "));
+        }
+        print_metadata(&stream.metadata);
+    }
+
+    for (i, stream) in info.audio_streams.iter().enumerate() {
+        println!();
+        println!("Audio Stream {} (Index: {})", i, stream.index);
+        print_field(
+            "Sample Rate",
+            format!(
+                "{} Hz",
+                VideoPlayer::format_optional_u32(stream.sample_rate)
+            ),
+        );
+        print_field(
+            "Channels",
+            VideoPlayer::format_optional_u16(stream.channels),
+        );
+        print_field(
+            "Codec",
+            format!("{} ({})", stream.codec_name, stream.codec_description),
+        );
+        print_field("Codec ID", &stream.codec_id);
+        if let Some(sample_format) = debug_name(&stream.sample_format) {
+            print_field("Sample Format", sample_format);
+        }
+        print_field("Bitrate", VideoPlayer::format_bitrate(stream.bit_rate));
+        if let Some(channel_layout) = debug_name(&stream.channel_layout) {
+            print_field("Channel Layout", channel_layout);
+        }
+        if let Some(frames) = stream.frames {
+            print_field("Frame Count", frames);
+        }
+        if let Some(profile) = debug_name(&stream.profile) {
+            print_field("Profile", profile);
+        }
+        print_rational("Time Base", &stream.time_base);
+        print_field("Disposition", format!("0x{:X}", stream.disposition));
+        if let Some(capabilities) = debug_name(&stream.codec_capabilities) {
+            print_field("Codec Capabilities", capabilities);
+        }
+        if let Some(profiles) = &stream.codec_profiles {
+            let names: Vec<String> = profiles.iter().map(|p| format!("{:?}", p)).collect();
+            print_field("Codec Profiles", names.join(", "));
+        }
+        print_metadata(&stream.metadata);
+    }
+
+    for (i, stream) in info.subtitle_streams.iter().enumerate() {
+        println!();
+        println!("Subtitle Stream {} (Index: {})", i, stream.index);
+        print_field("Codec", &stream.codec_name);
+        print_field("Codec ID", &stream.codec_id);
+        if let Some(language) = &stream.language {
+            print_field("Language", language);
+        }
+        print_rational("Time Base", &stream.time_base);
+        print_field("Disposition", format!("0x{:X}", stream.disposition));
+        print_metadata(&stream.metadata);
+    }
+
+    for (i, stream) in info.other_streams.iter().enumerate() {
+        println!();
+        println!("Other Stream {} (Index: {})", i, stream.index);
+        print_field("Type", &stream.stream_type);
+        print_field("Codec", &stream.codec_name);
+        print_field("Codec ID", &stream.codec_id);
+        print_rational("Time Base", &stream.time_base);
+        print_field("Disposition", format!("0x{:X}", stream.disposition));
+        print_metadata(&stream.metadata);
+    }
+
+    if !info.chapters.is_empty() {
+        println!();
+        println!("Chapters");
+        for chapter in &info.chapters {
+            println!(
+                "  {}: {} - {}  {}",
+                chapter.index,
+                VideoPlayer::format_time(chapter.start_time_ms),
+                VideoPlayer::format_time(chapter.end_time_ms),
+                chapter.title
+            );
+        }
+    }
+}
@@ -0,0 +1,527 @@
+extern crate ffmpeg_next as ffmpeg;
+
+use crate::error::MediaError;
+use crate::http;
+use crate::i18n::tr;
+use eframe::egui;
+use ffmpeg::codec::decoder::{self, Check};
+use ffmpeg::{codec, format, media, threading, Packet, Rational, Rescale};
+use std::fmt::Write;
+use std::io::Write as _;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Only the first issues are kept; a badly damaged file can produce one per packet.
+const MAX_ISSUES: usize = 10_000;
+/// Gives up when the demuxer can't get past a damaged region.
+const MAX_READ_ERRORS: usize = 100;
+
+pub struct Issue {
+    pub stream_index: Option<usize>,
+    pub time_ms: Option<i64>,
+    pub message: String,
+}
+
+pub struct StreamSummary {
+    pub index: usize,
+    pub description: String,
+    pub packets: usize,
+    pub frames: usize,
+    pub errors: usize,
+    /// Why the stream was not decoded, e.g. attachments or a missing decoder.
+    pub skipped: Option<String>,
+}
+
+#[derive(Default)]
+pub struct IntegrityReport {
+    pub streams: Vec<StreamSummary>,
+    pub issues: Vec<Issue>,
+    pub total_issues: usize,
+}
+
+impl IntegrityReport {
+    fn add_issue(&mut self, stream_index: Option<usize>, time_ms: Option<i64>, message: String) {
+        if let Some(summary) = stream_index.and_then(|index| self.streams.get_mut(index)) {
+            summary.errors += 1;
+        }
+        self.total_issues += 1;
+        if self.issues.len() < MAX_ISSUES {
+            self.issues.push(Issue {
+                stream_index,
+                time_ms,
+                message,
+            });
+        }
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.total_issues == 0
+    }
+
+    /// Plain-text report, as printed by `--check` and saved from the window.
+    pub fn to_text(&self, filename: &str) -> String {
+        let mut text = String::new();
+        let _ = writeln!(text, "File: {}", filename);
+        let _ = writeln!(
+            text,
+            "Result: {}",
+            if self.is_clean() {
+                "no errors found".to_string()
+            } else {
+                format!("{} errors found", self.total_issues)
+            }
+        );
+
+        let _ = writeln!(text, "\nStreams:");
+        for stream in &self.streams {
+            match &stream.skipped {
+                Some(reason) => {
+                    let _ = writeln!(
+                        text,
+                        "  #{} {}: {} packets, not decoded ({})",
+                        stream.index, stream.description, stream.packets, reason
+                    );
+                }
+                None => {
+                    let _ = writeln!(
+                        text,
+                        "  #{} {}: {} packets, {} frames, {} errors",
+                        stream.index,
+                        stream.description,
+                        stream.packets,
+                        stream.frames,
+                        stream.errors
+                    );
+                }
+            }
+        }
+
+        if !self.issues.is_empty() {
+            let _ = writeln!(text, "\nErrors:");
+            for issue in &self.issues {
+                let _ = writeln!(text, "  {}", issue_line(issue));
+            }
+            if self.total_issues > self.issues.len() {
+                let _ = writeln!(
+                    text,
+                    "  ... and {} more",
+                    self.total_issues - self.issues.len()
+                );
+            }
+        }
+
+        text
+    }
+}
+
+fn format_timestamp(time_ms: Option<i64>) -> String {
+    match time_ms {
+        Some(ms) => {
+            let ms = ms.max(0);
+            format!(
+                "{:02}:{:02}:{:02}.{:03}",
+                ms / 3_600_000,
+                ms / 60_000 % 60,
+                ms / 1000 % 60,
+                ms % 1000
+            )
+        }
+        None => "--:--:--.---".to_string(),
+    }
+}
+
+fn issue_line(issue: &Issue) -> String {
+    match issue.stream_index {
+        Some(index) => format!(
+            "{}  stream #{}: {}",
+            format_timestamp(issue.time_ms),
+            index,
+            issue.message
+        ),
+        None => format!("{}  {}", format_timestamp(issue.time_ms), issue.message),
+    }
+}
+
+enum StreamDecoder {
+    Frames(decoder::Opened),
+    Subtitle(decoder::Subtitle),
+}
+
+fn open_decoder(stream: &format::stream::Stream) -> Result<StreamDecoder, String> {
+    let parameters = stream.parameters();
+    let medium = parameters.medium();
+    if !matches!(
+        medium,
+        media::Type::Video | media::Type::Audio | media::Type::Subtitle
+    ) {
+        return Err(format!("{:?} stream", medium));
+    }
+
+    let codec_id = parameters.id();
+    let codec = decoder::find(codec_id).ok_or_else(|| format!("no decoder for {:?}", codec_id))?;
+    let mut context = codec::Context::from_parameters(parameters).map_err(|e| e.to_string())?;
+    // Slice threads keep decode errors in step with the packet that caused them
+    context.set_threading(threading::Config {
+        kind: threading::Type::Slice,
+        count: num_cpus::get(),
+    });
+    let mut decoder = context.decoder();
+    // Report damage instead of silently concealing it
+    decoder.check(Check::CRC | Check::BISTREAM | Check::BUFFER | Check::EXPLODE);
+
+    let opened = decoder.open_as(codec).map_err(|e| e.to_string())?;
+    match medium {
+        media::Type::Subtitle => opened
+            .subtitle()
+            .map(StreamDecoder::Subtitle)
+            .map_err(|e| e.to_string()),
+        _ => Ok(StreamDecoder::Frames(opened)),
+    }
+}
+
+fn to_ms(ts: Option<i64>, time_base: Rational) -> Option<i64> {
+    ts.map(|ts| ts.rescale(time_base, Rational(1, 1000)))
+}
+
+/// Pulls every frame the decoder has ready, recording the ones it flags as damaged.
+fn receive_frames(
+    decoder: &mut decoder::Opened,
+    frame: &mut ffmpeg::Frame,
+    index: usize,
+    time_base: Rational,
+    fallback_ms: Option<i64>,
+    report: &mut IntegrityReport,
+) {
+    loop {
+        match decoder.receive_frame(frame) {
+            Ok(()) => {
+                report.streams[index].frames += 1;
+                if frame.is_corrupt() {
+                    let time_ms = to_ms(frame.timestamp().or(frame.pts()), time_base);
+                    report.add_issue(
+                        Some(index),
+                        time_ms.or(fallback_ms),
+                        "Decoded frame is marked as corrupt".to_string(),
+                    );
+                }
+            }
+            Err(ffmpeg::Error::Eof) => break,
+            Err(ffmpeg::Error::Other { errno }) if errno == ffmpeg::error::EAGAIN => break,
+            Err(e) => {
+                report.add_issue(Some(index), fallback_ms, format!("Decoding failed: {}", e));
+                break;
+            }
+        }
+    }
+}
+
+/// Decodes every packet of every stream and collects everything that went wrong.
+/// `progress` is called with the position that has been reached.
+pub fn check(
+    filename: &str,
+    progress: &mut dyn FnMut(i64),
+    cancel: &AtomicBool,
+) -> Result<IntegrityReport, Box<dyn std::error::Error>> {
+    ffmpeg::init().map_err(MediaError::Open)?;
+    let mut input = http::open_input(&filename).map_err(MediaError::Open)?;
+
+    let mut report = IntegrityReport::default();
+    let mut decoders = Vec::new();
+    let mut time_bases = Vec::new();
+    for stream in input.streams() {
+        let parameters = stream.parameters();
+        let decoder = open_decoder(&stream);
+        report.streams.push(StreamSummary {
+            index: stream.index(),
+            description: format!("{:?} ({:?})", parameters.medium(), parameters.id()),
+            packets: 0,
+            frames: 0,
+            errors: 0,
+            skipped: decoder.as_ref().err().cloned(),
+        });
+        decoders.push(decoder.ok());
+        time_bases.push(stream.time_base());
+    }
+
+    let mut frame = unsafe { ffmpeg::Frame::empty() };
+    let mut subtitle = ffmpeg::Subtitle::new();
+    let mut packet = Packet::empty();
+    let mut position_ms = None;
+    let mut read_errors = 0;
+    let mut last_progress = Instant::now();
+
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            return Err("Cancelled".into());
+        }
+
+        match packet.read(&mut input) {
+            Ok(()) => read_errors = 0,
+            Err(ffmpeg::Error::Eof) => break,
+            Err(e) => {
+                report.add_issue(None, position_ms, format!("Reading failed: {}", e));
+                read_errors += 1;
+                if read_errors >= MAX_READ_ERRORS {
+                    report.add_issue(
+                        None,
+                        position_ms,
+                        "Stopped reading after repeated errors".to_string(),
+                    );
+                    break;
+                }
+                continue;
+            }
+        }
+
+        let index = packet.stream();
+        let Some(&time_base) = time_bases.get(index) else {
+            continue;
+        };
+        report.streams[index].packets += 1;
+
+        let time_ms = to_ms(packet.pts().or(packet.dts()), time_base).or(position_ms);
+        if time_ms.is_some() {
+            position_ms = time_ms;
+        }
+
+        if packet.is_corrupt() {
+            report.add_issue(
+                Some(index),
+                time_ms,
+                "Packet is marked as corrupt by the demuxer".to_string(),
+            );
+        }
+
+        match &mut decoders[index] {
+            Some(StreamDecoder::Frames(decoder)) => {
+                if let Err(e) = decoder.send_packet(&packet) {
+                    report.add_issue(Some(index), time_ms, format!("Decoding failed: {}", e));
+                }
+                receive_frames(decoder, &mut frame, index, time_base, time_ms, &mut report);
+            }
+            Some(StreamDecoder::Subtitle(decoder)) => {
+                match decoder.decode(&packet, &mut subtitle) {
+                    Ok(true) => report.streams[index].frames += 1,
+                    Ok(false) => {}
+                    Err(e) => {
+                        report.add_issue(Some(index), time_ms, format!("Decoding failed: {}", e))
+                    }
+                }
+            }
+            None => {}
+        }
+
+        if last_progress.elapsed() > Duration::from_millis(100) {
+            last_progress = Instant::now();
+            if let Some(position_ms) = position_ms {
+                progress(position_ms);
+            }
+        }
+    }
+
+    for (index, decoder) in decoders.iter_mut().enumerate() {
+        if let Some(StreamDecoder::Frames(decoder)) = decoder {
+            if decoder.send_eof().is_ok() {
+                receive_frames(
+                    decoder,
+                    &mut frame,
+                    index,
+                    time_bases[index],
+                    position_ms,
+                    &mut report,
+                );
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// `avio --check <file>`: prints the report and fails when errors were found.
+pub fn run_cli(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let [filename] = args else {
+        return Err("Usage: avio --check <file>".into());
+    };
+
+    let cancel = AtomicBool::new(false);
+    let report = check(
+        filename,
+        &mut |position_ms| {
+            eprint!("\rChecking... {}", format_timestamp(Some(position_ms)));
+            let _ = std::io::stderr().flush();
+        },
+        &cancel,
+    )?;
+    eprintln!();
+
+    print!("{}", report.to_text(filename));
+    if !report.is_clean() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+enum CheckEvent {
+    Progress(i64),
+    Finished(Result<IntegrityReport, String>),
+}
+
+/// Runs `check` on a worker thread. Dropping it stops the check.
+pub struct IntegrityCheck {
+    receiver: mpsc::Receiver<CheckEvent>,
+    cancel: Arc<AtomicBool>,
+    filename: String,
+    duration_ms: i64,
+    position_ms: i64,
+    result: Option<Result<IntegrityReport, String>>,
+}
+
+impl IntegrityCheck {
+    pub fn start(filename: &str, duration_ms: i64) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancel_flag = cancel.clone();
+        let worker_filename = filename.to_string();
+
+        thread::spawn(move || {
+            let progress_sender = sender.clone();
+            let result = check(
+                &worker_filename,
+                &mut |position_ms| {
+                    let _ = progress_sender.send(CheckEvent::Progress(position_ms));
+                },
+                &cancel_flag,
+            );
+            let _ = sender.send(CheckEvent::Finished(result.map_err(|e| e.to_string())));
+        });
+
+        Self {
+            receiver,
+            cancel,
+            filename: filename.to_string(),
+            duration_ms,
+            position_ms: 0,
+            result: None,
+        }
+    }
+
+    /// Returns true while the check is still running.
+    pub fn update(&mut self) -> bool {
+        for event in self.receiver.try_iter() {
+            match event {
+                CheckEvent::Progress(position_ms) => self.position_ms = position_ms,
+                CheckEvent::Finished(result) => self.result = Some(result),
+            }
+        }
+        self.result.is_none()
+    }
+
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    pub fn report(&self) -> Option<&IntegrityReport> {
+        self.result.as_ref()?.as_ref().ok()
+    }
+
+    /// Draws progress or the report. Returns the time to seek to when an error is clicked.
+    pub fn show(&self, ui: &mut egui::Ui) -> Option<i64> {
+        match &self.result {
+            None => {
+                let progress = if self.duration_ms > 0 {
+                    self.position_ms as f32 / self.duration_ms as f32
+                } else {
+                    0.0
+                };
+                ui.label(tr!("verify-decoding"));
+                ui.add(egui::ProgressBar::new(progress.clamp(0.0, 1.0)).show_percentage());
+                None
+            }
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::LIGHT_RED, tr!("verify-failed", error = e));
+                None
+            }
+            Some(Ok(report)) => show_report(ui, report),
+        }
+    }
+}
+
+impl Drop for IntegrityCheck {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+fn show_report(ui: &mut egui::Ui, report: &IntegrityReport) -> Option<i64> {
+    if report.is_clean() {
+        ui.colored_label(egui::Color32::LIGHT_GREEN, tr!("verify-no-errors"));
+    } else {
+        ui.colored_label(
+            egui::Color32::LIGHT_RED,
+            tr!("verify-errors-found", count = report.total_issues),
+        );
+    }
+    ui.add_space(4.0);
+
+    egui::Grid::new("integrity_streams")
+        .num_columns(4)
+        .striped(true)
+        .show(ui, |ui| {
+            ui.strong(tr!("verify-column-stream"));
+            ui.strong(tr!("verify-column-packets"));
+            ui.strong(tr!("verify-column-frames"));
+            ui.strong(tr!("verify-column-errors"));
+            ui.end_row();
+
+            for stream in &report.streams {
+                ui.label(format!("#{} {}", stream.index, stream.description));
+                ui.label(stream.packets.to_string());
+                match &stream.skipped {
+                    Some(reason) => {
+                        ui.label("–")
+                            .on_hover_text(tr!("verify-not-decoded", reason = reason));
+                        ui.label("–");
+                    }
+                    None => {
+                        ui.label(stream.frames.to_string());
+                        ui.label(stream.errors.to_string());
+                    }
+                }
+                ui.end_row();
+            }
+        });
+
+    if report.issues.is_empty() {
+        return None;
+    }
+
+    ui.add_space(8.0);
+    ui.label(tr!("verify-click-to-jump"));
+    let mut seek_target = None;
+    egui::ScrollArea::vertical()
+        .max_height(240.0)
+        .auto_shrink([false, true])
+        .show_rows(
+            ui,
+            ui.text_style_height(&egui::TextStyle::Body),
+            report.issues.len(),
+            |ui, rows| {
+                for issue in &report.issues[rows] {
+                    let response = ui.selectable_label(false, issue_line(issue));
+                    if let (true, Some(time_ms)) = (response.clicked(), issue.time_ms) {
+                        seek_target = Some(time_ms);
+                    }
+                }
+            },
+        );
+    if report.total_issues > report.issues.len() {
+        ui.label(format!(
+            "... and {} more",
+            report.total_issues - report.issues.len()
+        ));
+    }
+
+    seek_target
+}
@@ -0,0 +1,206 @@
+use crate::audio::SampleWindow;
+use eframe::egui;
+use std::time::Instant;
+
+/// Levels are measured over this much audio before the playback position.
+const WINDOW_MS: usize = 50;
+/// How fast the bars fall back after a loud moment.
+const DECAY_DB_PER_SECOND: f32 = 24.0;
+/// How long the peak marker stays at the highest peak before it falls too.
+const PEAK_HOLD_SECONDS: f32 = 1.5;
+const MIN_DB: f32 = -60.0;
+/// A channel below this for `DEAD_AFTER_SECONDS` while another one plays is marked dead.
+const SILENT_DB: f32 = -90.0;
+const DEAD_AFTER_SECONDS: f32 = 3.0;
+
+fn to_db(amplitude: f32) -> f32 {
+    20.0 * amplitude.max(1e-6).log10()
+}
+
+/// Names for FFmpeg's native order of the common layouts, or numbers for the others.
+fn channel_labels(channels: usize) -> Vec<String> {
+    let named: &[&str] = match channels {
+        1 => &["C"],
+        2 => &["L", "R"],
+        6 => &["L", "R", "C", "LFE", "Ls", "Rs"],
+        8 => &["L", "R", "C", "LFE", "Lb", "Rb", "Ls", "Rs"],
+        _ => &[],
+    };
+    (0..channels)
+        .map(|index| {
+            named
+                .get(index)
+                .map_or_else(|| (index + 1).to_string(), |name| name.to_string())
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy)]
+struct ChannelLevel {
+    peak_db: f32,
+    rms_db: f32,
+    held_db: f32,
+    held_for: f32,
+    silent_for: f32,
+}
+
+impl Default for ChannelLevel {
+    fn default() -> Self {
+        Self {
+            peak_db: to_db(0.0),
+            rms_db: to_db(0.0),
+            held_db: to_db(0.0),
+            held_for: 0.0,
+            silent_for: 0.0,
+        }
+    }
+}
+
+/// Peak and RMS level of each channel around the playback position, falling back slowly so
+/// that short peaks stay readable.
+pub struct LevelMeter {
+    channels: usize,
+    sample_rate: u32,
+    levels: Vec<ChannelLevel>,
+    last_update: Option<Instant>,
+}
+
+impl LevelMeter {
+    pub fn new(channels: u16, sample_rate: u32) -> Self {
+        let channels = channels.max(1) as usize;
+        Self {
+            channels,
+            sample_rate,
+            levels: vec![ChannelLevel::default(); channels],
+            last_update: None,
+        }
+    }
+
+    /// Measures the interleaved `samples` just before `position_ms`.
+    pub fn update(&mut self, samples: &SampleWindow, position_ms: i64) {
+        let now = Instant::now();
+        let elapsed = self
+            .last_update
+            .map_or(0.0, |last| now.duration_since(last).as_secs_f32());
+        self.last_update = Some(now);
+
+        let window_frames = self.sample_rate as usize * WINDOW_MS / 1000;
+        let end_frame = position_ms.max(0) as usize * self.sample_rate as usize / 1000;
+        let start = end_frame.saturating_sub(window_frames) * self.channels;
+        let end = (end_frame * self.channels).min(samples.end());
+
+        let mut peaks = vec![0.0f32; self.channels];
+        let mut sums = vec![0.0f64; self.channels];
+        if let Some(values) = samples.range(start, end) {
+            for (index, sample) in values.enumerate() {
+                let channel = index % self.channels;
+                peaks[channel] = peaks[channel].max(sample.abs());
+                sums[channel] += (*sample as f64) * (*sample as f64);
+            }
+        }
+        let frames = (end.saturating_sub(start) / self.channels).max(1);
+
+        let fall = DECAY_DB_PER_SECOND * elapsed;
+        for (channel, level) in self.levels.iter_mut().enumerate() {
+            let peak_db = to_db(peaks[channel]);
+            let rms_db = to_db((sums[channel] / frames as f64).sqrt() as f32);
+
+            level.peak_db = peak_db.max(level.peak_db - fall);
+            level.rms_db = rms_db.max(level.rms_db - fall);
+            if peak_db >= level.held_db {
+                level.held_db = peak_db;
+                level.held_for = 0.0;
+            } else {
+                level.held_for += elapsed;
+                if level.held_for > PEAK_HOLD_SECONDS {
+                    level.held_db = (level.held_db - fall).max(peak_db);
+                }
+            }
+            level.silent_for = if peak_db < SILENT_DB {
+                level.silent_for + elapsed
+            } else {
+                0.0
+            };
+        }
+    }
+
+    /// Whether the channel has been silent for a while as another one plays.
+    fn is_dead(&self, channel: usize) -> bool {
+        self.levels[channel].silent_for > DEAD_AFTER_SECONDS
+            && self.levels.iter().any(|level| level.silent_for == 0.0)
+    }
+}
+
+/// Draws a bar for each channel in the top left corner of `area`: the RMS level solid, the
+/// peak level faint behind it and the held peak as a line. Dead channels have a red label.
+pub fn draw(painter: &egui::Painter, area: egui::Rect, meter: &LevelMeter) {
+    const BAR_WIDTH: f32 = 16.0;
+    const GAP: f32 = 8.0;
+    const BAR_HEIGHT: f32 = 120.0;
+    const LABEL_HEIGHT: f32 = 16.0;
+
+    let width = meter.channels as f32 * (BAR_WIDTH + GAP) - GAP;
+    let rect = egui::Rect::from_min_size(
+        area.left_top() + egui::vec2(12.0, 12.0),
+        egui::vec2(width, BAR_HEIGHT + LABEL_HEIGHT),
+    );
+    painter.rect_filled(
+        rect.expand(4.0),
+        egui::Rounding::same(4.0),
+        egui::Color32::from_black_alpha(180),
+    );
+
+    let db_to_y = |db: f32| {
+        let fraction = ((db - MIN_DB) / -MIN_DB).clamp(0.0, 1.0);
+        rect.top() + BAR_HEIGHT * (1.0 - fraction)
+    };
+    let color = |db: f32| {
+        if db > -3.0 {
+            egui::Color32::from_rgb(230, 80, 60)
+        } else if db > -12.0 {
+            egui::Color32::from_rgb(230, 200, 60)
+        } else {
+            egui::Color32::from_rgb(90, 200, 90)
+        }
+    };
+
+    for (channel, label) in channel_labels(meter.channels).into_iter().enumerate() {
+        let level = &meter.levels[channel];
+        let left = rect.left() + channel as f32 * (BAR_WIDTH + GAP);
+        let bar = egui::Rect::from_min_max(
+            egui::pos2(left, rect.top()),
+            egui::pos2(left + BAR_WIDTH, rect.top() + BAR_HEIGHT),
+        );
+        painter.rect_filled(bar, egui::Rounding::ZERO, egui::Color32::from_gray(40));
+
+        for (db, alpha) in [(level.peak_db, 0.35), (level.rms_db, 1.0)] {
+            if db > MIN_DB {
+                painter.rect_filled(
+                    egui::Rect::from_min_max(egui::pos2(bar.left(), db_to_y(db)), bar.max),
+                    egui::Rounding::ZERO,
+                    color(db).gamma_multiply(alpha),
+                );
+            }
+        }
+        if level.held_db > MIN_DB {
+            painter.hline(
+                bar.x_range(),
+                db_to_y(level.held_db),
+                egui::Stroke::new(2.0, color(level.held_db)),
+            );
+        }
+
+        let label_color = if meter.is_dead(channel) {
+            egui::Color32::from_rgb(230, 80, 60)
+        } else {
+            egui::Color32::from_gray(220)
+        };
+        painter.text(
+            egui::pos2(bar.center().x, rect.bottom()),
+            egui::Align2::CENTER_BOTTOM,
+            label,
+            egui::FontId::proportional(11.0),
+            label_color,
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use crate::i18n::tr;
+use crate::remux::{RemuxEvent, RemuxJob, RemuxSettings};
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_LATENCY_MS: u32 = 3000;
+/// How far the delay may grow past the target before catching up.
+const TOLERANCE_MS: i64 = 1500;
+/// Playback rate while catching up by speeding up.
+const CATCH_UP_SPEED: f64 = 1.1;
+/// How much of the stream the timeshift buffer holds before playback starts.
+const START_BUFFER_MS: i64 = 2000;
+const START_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// How playback gets back to the target delay once it has fallen behind the live edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CatchUp {
+    /// Lets the delay grow, e.g. after pausing.
+    Off,
+    /// Plays slightly faster until the delay is back at the target.
+    #[default]
+    SpeedUp,
+    /// Jumps ahead, dropping what was in between.
+    Skip,
+}
+
+impl CatchUp {
+    pub const ALL: [CatchUp; 3] = [CatchUp::Off, CatchUp::SpeedUp, CatchUp::Skip];
+
+    pub fn name(&self) -> String {
+        match self {
+            CatchUp::Off => tr!("catch-up-off"),
+            CatchUp::SpeedUp => tr!("catch-up-speed-up"),
+            CatchUp::Skip => tr!("catch-up-skip"),
+        }
+    }
+}
+
+pub enum Correction {
+    /// Catching up by speeding up started or ended; see [`LatencyControl::speed_factor`].
+    SpeedChanged,
+    /// Jump to this position.
+    SkipTo(i64),
+}
+
+/// Keeps a live stream playing about the target delay behind its live edge.
+#[derive(Default)]
+pub struct LatencyControl {
+    catching_up: bool,
+}
+
+impl LatencyControl {
+    pub fn update(
+        &mut self,
+        mode: CatchUp,
+        target_ms: i64,
+        position_ms: i64,
+        edge_ms: i64,
+    ) -> Option<Correction> {
+        let latency_ms = edge_ms - position_ms;
+
+        match mode {
+            CatchUp::SpeedUp if !self.catching_up && latency_ms > target_ms + TOLERANCE_MS => {
+                self.catching_up = true;
+                Some(Correction::SpeedChanged)
+            }
+            CatchUp::SpeedUp if self.catching_up && latency_ms > target_ms => None,
+            CatchUp::Skip if latency_ms > target_ms + TOLERANCE_MS => {
+                Some(Correction::SkipTo(edge_ms - target_ms))
+            }
+            _ => self.finish(),
+        }
+    }
+
+    fn finish(&mut self) -> Option<Correction> {
+        std::mem::take(&mut self.catching_up).then_some(Correction::SpeedChanged)
+    }
+
+    /// Factor on the playback speed while catching up.
+    pub fn speed_factor(&self) -> f64 {
+        if self.catching_up {
+            CATCH_UP_SPEED
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Copies a live stream into an MPEG-TS file in the cache folder as it arrives, so that it
+/// can be paused and rewound. The player reads the file instead of the network while it
+/// grows. Dropping the buffer stops the copy and deletes the file.
+pub struct Timeshift {
+    job: Option<RemuxJob>,
+    path: PathBuf,
+    first_ms: Option<i64>,
+    /// How much of the stream is on disk.
+    buffered_ms: i64,
+    /// When buffering started, until the first seconds are on disk.
+    starting: Option<Instant>,
+}
+
+impl Timeshift {
+    /// Starts buffering. The file can be played once `update_start` reports that the first
+    /// seconds are on disk, which the demuxers need to find the streams.
+    pub fn start(url: &str, streams: Vec<usize>) -> Result<Self, String> {
+        let path = storage::timeshift_path().map_err(|e| e.to_string())?;
+
+        let job = RemuxJob::start(RemuxSettings {
+            filename: url.to_string(),
+            output: path.clone(),
+            streams,
+            recording: true,
+        });
+        Ok(Self {
+            job: Some(job),
+            path,
+            first_ms: None,
+            buffered_ms: 0,
+            starting: Some(Instant::now()),
+        })
+    }
+
+    /// Takes in the copy's progress while starting. Returns `Ok` once enough is buffered to
+    /// play, or why the stream can't be buffered.
+    pub fn update_start(&mut self) -> Option<Result<(), String>> {
+        let started = self.starting?;
+        match self.update() {
+            Some(Err(e)) => return Some(Err(e)),
+            Some(Ok(())) => return Some(Err("The stream ended".to_string())),
+            None => {}
+        }
+
+        if self.buffered_ms >= START_BUFFER_MS {
+            println!("Buffering live stream to {}", self.path.display());
+            self.starting = None;
+            Some(Ok(()))
+        } else if started.elapsed() >= START_TIMEOUT {
+            Some(Err("Timed out waiting for the stream".to_string()))
+        } else {
+            None
+        }
+    }
+
+    fn progress(&mut self, position_ms: i64) {
+        let first_ms = *self.first_ms.get_or_insert(position_ms);
+        self.buffered_ms = self.buffered_ms.max(position_ms - first_ms);
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn buffered_ms(&self) -> i64 {
+        self.buffered_ms
+    }
+
+    /// Takes in the copy's progress. Returns how it ended once the stream is over, after
+    /// which the file is complete.
+    pub fn update(&mut self) -> Option<Result<(), String>> {
+        let mut result = None;
+        if let Some(job) = &self.job {
+            let mut positions = Vec::new();
+            while let Ok(event) = job.receiver.try_recv() {
+                match event {
+                    RemuxEvent::Progress(position_ms) => positions.push(position_ms),
+                    RemuxEvent::Finished(finished) => result = Some(finished),
+                }
+            }
+            for position_ms in positions {
+                self.progress(position_ms);
+            }
+        }
+        result
+    }
+}
+
+impl Drop for Timeshift {
+    fn drop(&mut self) {
+        let job = self.job.take();
+        let path = self.path.clone();
+
+        // The muxer and the player may still have the file open for a moment
+        thread::spawn(move || {
+            if let Some(job) = job {
+                job.cancel();
+                let _ = job.receiver.recv_timeout(Duration::from_secs(5));
+            }
+            for _ in 0..10 {
+                if fs::remove_file(&path).is_ok() || !path.exists() {
+                    return;
+                }
+                thread::sleep(Duration::from_secs(1));
+            }
+            eprintln!("Could not delete timeshift buffer {}", path.display());
+        });
+    }
+}
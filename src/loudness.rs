@@ -0,0 +1,342 @@
+use crate::audio::SampleWindow;
+use crate::i18n::tr;
+use eframe::egui;
+
+/// Loudness is measured in 100 ms segments; gating blocks are four of them (400 ms, 75% overlap).
+const SEGMENT_MS: usize = 100;
+const MOMENTARY_SEGMENTS: usize = 4;
+const SHORT_TERM_SEGMENTS: usize = 30;
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = -10.0;
+/// Forward jumps up to this many segments are measured as if played, longer ones are seeks.
+const MAX_CATCH_UP_SEGMENTS: usize = 100;
+
+/// EBU R128 programme loudness target.
+pub const TARGET_LUFS: f64 = -23.0;
+
+fn to_lufs(mean_square: f64) -> f64 {
+    -0.691 + 10.0 * mean_square.log10()
+}
+
+/// BS.1770 channel weights: surround channels count more and the LFE is left out.
+/// Assumes FFmpeg's native order for 5.1 and 7.1.
+fn channel_weight(channels: usize, index: usize) -> f64 {
+    match (channels, index) {
+        (6 | 8, 3) => 0.0,
+        (6, 4 | 5) | (8, 4..=7) => 1.41,
+        _ => 1.0,
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    state: [f64; 2],
+}
+
+impl Biquad {
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.state[0];
+        self.state[0] = self.b[1] * x - self.a[0] * y + self.state[1];
+        self.state[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+/// The K-weighting pre-filter (high shelf) and RLB high-pass from BS.1770, derived for
+/// any sample rate.
+#[derive(Clone, Copy)]
+struct KWeighting {
+    shelf: Biquad,
+    high_pass: Biquad,
+}
+
+impl KWeighting {
+    fn new(sample_rate: u32) -> Self {
+        let rate = sample_rate as f64;
+
+        let f0 = 1681.974450955533;
+        let gain_db = 3.999843853973347;
+        let q = 0.7071752369554196;
+        let k = (std::f64::consts::PI * f0 / rate).tan();
+        let vh = 10f64.powf(gain_db / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        let shelf = Biquad {
+            b: [
+                (vh + vb * k / q + k * k) / a0,
+                2.0 * (k * k - vh) / a0,
+                (vh - vb * k / q + k * k) / a0,
+            ],
+            a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+            state: [0.0; 2],
+        };
+
+        let f0 = 38.13547087602444;
+        let q = 0.5003270373238773;
+        let k = (std::f64::consts::PI * f0 / rate).tan();
+        let a0 = 1.0 + k / q + k * k;
+        let high_pass = Biquad {
+            b: [1.0, -2.0, 1.0],
+            a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+            state: [0.0; 2],
+        };
+
+        Self { shelf, high_pass }
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        self.high_pass.process(self.shelf.process(x))
+    }
+
+    fn reset(&mut self) {
+        self.shelf.state = [0.0; 2];
+        self.high_pass.state = [0.0; 2];
+    }
+}
+
+/// EBU R128 loudness of the decoded samples around the playback position. Only the
+/// parts that have been played are measured, so the integrated value covers the whole
+/// programme once it has played through.
+pub struct LoudnessMeter {
+    channels: usize,
+    sample_rate: u32,
+    filters: Vec<KWeighting>,
+    /// Weighted mean square of each segment, once it has been measured.
+    segments: Vec<Option<f64>>,
+    next_segment: usize,
+}
+
+impl LoudnessMeter {
+    pub fn new(channels: u16, sample_rate: u32, sample_count: usize) -> Self {
+        let channels = channels.max(1) as usize;
+        let frames = sample_count / channels;
+        let segment_frames = Self::segment_frames(sample_rate);
+
+        Self {
+            channels,
+            sample_rate,
+            filters: vec![KWeighting::new(sample_rate); channels],
+            segments: vec![None; frames / segment_frames],
+            next_segment: 0,
+        }
+    }
+
+    fn segment_frames(sample_rate: u32) -> usize {
+        (sample_rate as usize * SEGMENT_MS / 1000).max(1)
+    }
+
+    /// Measures the segments played up to `position_ms`. `samples` are the interleaved
+    /// samples the meter was created for, which may have grown since for a live stream.
+    /// Segments outside the samples held in memory are left unmeasured.
+    pub fn update(&mut self, samples: &SampleWindow, position_ms: i64) {
+        let available = samples.end() / self.channels / Self::segment_frames(self.sample_rate);
+        if available > self.segments.len() {
+            self.segments.resize(available, None);
+        }
+
+        let target = (position_ms.max(0) as usize / SEGMENT_MS).min(self.segments.len());
+
+        if target + 1 < self.next_segment || target > self.next_segment + MAX_CATCH_UP_SEGMENTS {
+            // Seeked: start over one segment early so that the filters settle
+            for filter in &mut self.filters {
+                filter.reset();
+            }
+            if target > 0 {
+                self.measure(samples, target - 1);
+            }
+            self.next_segment = target;
+        }
+
+        while self.next_segment < target {
+            self.segments[self.next_segment] = self.measure(samples, self.next_segment);
+            self.next_segment += 1;
+        }
+    }
+
+    fn measure(&mut self, samples: &SampleWindow, segment: usize) -> Option<f64> {
+        let segment_frames = Self::segment_frames(self.sample_rate);
+        let start = segment * segment_frames * self.channels;
+        let end = (start + segment_frames * self.channels).min(samples.end());
+
+        let mut sums = vec![0.0; self.channels];
+        let values = samples.range(start, end)?;
+        for (index, sample) in values.enumerate() {
+            let channel = index % self.channels;
+            let weighted = self.filters[channel].process(*sample as f64);
+            sums[channel] += weighted * weighted;
+        }
+
+        let mean_square = sums
+            .iter()
+            .enumerate()
+            .map(|(channel, sum)| channel_weight(self.channels, channel) * sum)
+            .sum::<f64>()
+            / segment_frames as f64;
+        Some(mean_square)
+    }
+
+    /// Loudness of the `count` segments before the playback position, if all were measured.
+    fn window(&self, count: usize) -> Option<f64> {
+        let start = self.next_segment.checked_sub(count)?;
+        let mut total = 0.0;
+        for segment in &self.segments[start..self.next_segment] {
+            total += (*segment)?;
+        }
+        Some(to_lufs(total / count as f64))
+    }
+
+    /// Loudness over the last 400 ms.
+    pub fn momentary(&self) -> Option<f64> {
+        self.window(MOMENTARY_SEGMENTS)
+    }
+
+    /// Loudness over the last 3 s.
+    pub fn short_term(&self) -> Option<f64> {
+        self.window(SHORT_TERM_SEGMENTS)
+    }
+
+    /// Gated loudness of everything measured so far.
+    pub fn integrated(&self) -> Option<f64> {
+        let blocks: Vec<f64> = self
+            .segments
+            .windows(MOMENTARY_SEGMENTS)
+            .filter_map(|window| {
+                window
+                    .iter()
+                    .copied()
+                    .sum::<Option<f64>>()
+                    .map(|total| total / MOMENTARY_SEGMENTS as f64)
+            })
+            .filter(|block| to_lufs(*block) > ABSOLUTE_GATE_LUFS)
+            .collect();
+        if blocks.is_empty() {
+            return None;
+        }
+
+        let relative_gate =
+            to_lufs(blocks.iter().sum::<f64>() / blocks.len() as f64) + RELATIVE_GATE_LU;
+        let gated: Vec<f64> = blocks
+            .into_iter()
+            .filter(|block| to_lufs(*block) > relative_gate)
+            .collect();
+        if gated.is_empty() {
+            return None;
+        }
+
+        Some(to_lufs(gated.iter().sum::<f64>() / gated.len() as f64))
+    }
+
+    /// Fraction of the programme that has been measured.
+    pub fn coverage(&self) -> f32 {
+        if self.segments.is_empty() {
+            return 0.0;
+        }
+        let measured = self
+            .segments
+            .iter()
+            .filter(|segment| segment.is_some())
+            .count();
+        measured as f32 / self.segments.len() as f32
+    }
+}
+
+pub fn format_lufs(lufs: Option<f64>) -> String {
+    match lufs {
+        Some(lufs) if lufs.is_finite() => format!("{:.1} LUFS", lufs),
+        Some(_) => "-inf LUFS".to_string(),
+        None => "— LUFS".to_string(),
+    }
+}
+
+/// Draws the meter in the top right corner of `area`.
+pub fn draw(painter: &egui::Painter, area: egui::Rect, meter: &LoudnessMeter) {
+    const MIN_LUFS: f64 = -60.0;
+    let size = egui::vec2(240.0, 92.0);
+    let rect = egui::Rect::from_min_size(
+        egui::pos2(area.right() - size.x - 12.0, area.top() + 12.0),
+        size,
+    );
+    painter.rect_filled(
+        rect.expand(4.0),
+        egui::Rounding::same(4.0),
+        egui::Color32::from_black_alpha(180),
+    );
+
+    let font = egui::FontId::monospace(13.0);
+    let text_color = egui::Color32::from_gray(220);
+    let row_height = size.y / 4.0;
+    let bar_left = rect.left() + 24.0;
+    let bar_width = rect.width() - 24.0;
+    let lufs_to_x =
+        |lufs: f64| bar_left + bar_width * ((lufs - MIN_LUFS) / -MIN_LUFS).clamp(0.0, 1.0) as f32;
+
+    for (row, (label, lufs)) in [("M", meter.momentary()), ("S", meter.short_term())]
+        .into_iter()
+        .enumerate()
+    {
+        let top = rect.top() + row as f32 * row_height;
+        painter.text(
+            egui::pos2(rect.left(), top + row_height / 2.0),
+            egui::Align2::LEFT_CENTER,
+            label,
+            font.clone(),
+            text_color,
+        );
+
+        let bar = egui::Rect::from_min_max(
+            egui::pos2(bar_left, top + 3.0),
+            egui::pos2(rect.right(), top + row_height - 3.0),
+        );
+        painter.rect_filled(bar, egui::Rounding::ZERO, egui::Color32::from_gray(40));
+        if let Some(lufs) = lufs.filter(|lufs| lufs.is_finite()) {
+            let color = if lufs > TARGET_LUFS + 9.0 {
+                egui::Color32::from_rgb(230, 80, 60)
+            } else if lufs > TARGET_LUFS {
+                egui::Color32::from_rgb(230, 200, 60)
+            } else {
+                egui::Color32::from_rgb(90, 200, 90)
+            };
+            painter.rect_filled(
+                egui::Rect::from_min_max(bar.min, egui::pos2(lufs_to_x(lufs), bar.bottom())),
+                egui::Rounding::ZERO,
+                color,
+            );
+        }
+        painter.vline(
+            lufs_to_x(TARGET_LUFS),
+            bar.y_range(),
+            egui::Stroke::new(1.0, egui::Color32::WHITE),
+        );
+        painter.text(
+            bar.right_center() - egui::vec2(4.0, 0.0),
+            egui::Align2::RIGHT_CENTER,
+            format_lufs(lufs),
+            font.clone(),
+            egui::Color32::WHITE,
+        );
+    }
+
+    let top = rect.top() + 2.0 * row_height;
+    painter.text(
+        egui::pos2(rect.left(), top + row_height / 2.0),
+        egui::Align2::LEFT_CENTER,
+        format!("I  {}", format_lufs(meter.integrated())),
+        font.clone(),
+        text_color,
+    );
+    painter.text(
+        egui::pos2(rect.left(), top + row_height * 1.5),
+        egui::Align2::LEFT_CENTER,
+        format!(
+            "   {}",
+            tr!(
+                "loudness-coverage",
+                percent = format!("{:.0}", meter.coverage() * 100.0)
+            )
+        ),
+        egui::FontId::proportional(11.0),
+        egui::Color32::GRAY,
+    );
+}
@@ -1,24 +1,525 @@
+mod ab_loop;
+mod actions;
+mod annotations;
 mod audio;
+mod audio_profiles;
+mod av_sync;
+mod bidi;
+mod bitmap_subtitles;
+mod bitrate_graph;
+mod captions;
+mod chapters;
+mod commands;
+mod concat;
+mod cutlist;
+mod decode_ahead;
+mod diagnostics;
+mod dvd;
+mod error;
+mod export;
+mod file_types;
+mod filmstrip;
+mod fonts;
+mod gop_analysis;
+mod http;
+mod i18n;
+mod info_cli;
+mod integrity;
+mod levels;
+mod live;
+mod loudness;
+mod matroska;
 mod media_info;
+mod now_playing;
+mod opensubtitles;
+mod playlist;
+mod preload;
+mod presets;
+mod properties;
+mod raw_stream;
+mod remux;
+mod scene_detect;
+mod scopes;
+mod screenshot;
+mod security;
+mod session;
+mod settings;
+mod shaders;
+mod skip_segments;
+mod sleep_inhibitor;
+mod sleep_timer;
+mod sponsorblock;
+mod stats_log;
+mod storage;
+mod subtitle_ocr;
+mod subtitles;
+mod sync_analysis;
+mod theme;
+mod timecode;
+mod touch;
+mod transcode;
+mod tray;
 mod video;
+mod web_video;
 
+use actions::{Action, KeyBindings};
 use eframe::egui;
+use i18n::tr;
+use playlist::Playlist;
+use settings::Settings;
+use shaders::{DisplayMode, ShaderManager};
 use std::env;
-use std::time::Instant;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+use subtitles::Subtitles;
+
+/// Volumes above 1.0 amplify the audio with soft clipping.
+const MAX_VOLUME: f32 = 2.0;
+const MAX_ZOOM: f32 = 4.0;
+const OSD_DURATION: Duration = Duration::from_millis(1500);
+const PLAYBACK_SPEEDS: [f64; 8] = [0.25, 0.5, 0.75, 1.0, 1.25, 1.5, 1.75, 2.0];
+/// How far the pitch can be shifted either way, an octave.
+const MAX_PITCH_SEMITONES: i32 = 12;
+/// How far the audio delay can be set either way.
+const MAX_AUDIO_DELAY_MS: i64 = 10_000;
+/// Most files a video wall plays at once; each is decoded on the UI thread.
+const MAX_WALL_FILES: usize = 16;
+/// How long before the end of an entry the next one is shown.
+const UP_NEXT_MS: i64 = 10_000;
+/// How close to a chapter boundary a click on the seek bar lands on it, in points.
+const CHAPTER_SNAP_PX: f32 = 6.0;
 
 struct VideoPlayer {
     video: Option<video::Video>,
     audio: Option<audio::Audio>,
-    video_texture: Option<egui::TextureHandle>,
+    video_textures: VideoTextures,
     paused: bool,
     last_frame_time: Instant,
     frame_interval: f64,
     fps_counter: FpsCounter,
+    av_sync: av_sync::AvSync,
     volume: f32,
+    playback_speed: f64,
+    /// Shifts the audio pitch on top of the speed.
+    pitch_semitones: i32,
+    /// How much later the sound plays than the picture, for files recorded out of sync.
+    audio_delay_ms: i64,
+    muted: bool,
+    device_watcher: audio_profiles::DeviceWatcher,
+    output_devices: audio_profiles::OutputDevices,
+    /// The device audio plays on, once known.
+    output_device: Option<String>,
+    /// Size of the monitor the window is on, as a key into the per-monitor devices.
+    monitor: Option<String>,
     is_fullscreen: bool,
+    /// Fullscreen state last requested from the window system.
+    window_fullscreen: bool,
+    /// Fullscreen state the window system reported last frame, to notice it leaving fullscreen
+    /// by itself.
+    reported_fullscreen: Option<bool>,
+    /// Where the window was before it moved to the fullscreen display.
+    windowed_position: Option<egui::Pos2>,
+    /// The viewport last put into fullscreen: the main window or the video window.
+    fullscreen_viewport: egui::ViewportId,
+    /// The picture is shown in a window of its own.
+    video_window: bool,
+    /// Percentage of the video's pixel size the window is to be resized to.
+    window_scale: Option<u16>,
+    /// Where the picture was drawn last frame, to size the window around it.
+    last_video_area: egui::Rect,
+    /// Shown outside fullscreen; tapping the video toggles them.
+    show_controls: bool,
     show_media_info: bool,
+    bitrate_measurement: Option<BitrateMeasurement>,
+    show_analysis: bool,
+    gop_analysis: Option<gop_analysis::GopAnalysis>,
+    show_integrity: bool,
+    integrity_check: Option<integrity::IntegrityCheck>,
+    show_scene_detection: bool,
+    /// Cache usage shown in the Storage window while it is open.
+    storage_usage: Option<storage::CacheUsage>,
+    /// Open while logging playback statistics is turned on.
+    stats_log: Option<stats_log::StatsLog>,
+    session_snapshots: session::SnapshotWriter,
+    /// What was playing when the last session crashed, offered on the start screen.
+    recovery: Option<session::Leftover>,
+    /// Open while the Diagnostics window is.
+    diagnostics: Option<diagnostics::Diagnostics>,
+    /// OpenGL vendor, renderer and versions, read once the context exists.
+    gpu_info: Vec<(String, String)>,
+    scene_detection: Option<scene_detect::SceneDetection>,
+    show_subtitle_ocr: bool,
+    /// Image subtitle stream picked in the OCR window.
+    subtitle_ocr_stream: Option<usize>,
+    subtitle_ocr: Option<subtitle_ocr::SubtitleOcr>,
+    show_sync_analysis: bool,
+    sync_analysis: Option<sync_analysis::SyncAnalysis>,
+    show_presets: bool,
+    /// Name of the preset applied to the file playing.
+    active_preset: Option<String>,
+    /// What the active preset replaced, put back when it is undone.
+    preset_restore: Option<presets::Preset>,
+    bitrate_scan: Option<bitrate_graph::BitrateScan>,
+    bitrate_graph_stream: Option<usize>,
+    show_bitrate_overlay: bool,
     media_info: Option<media_info::MediaInfo>,
     current_filename: Option<String>,
+    timeline: Option<TimelinePlayback>,
+    /// Titles of the open DVD, kept while switching between them.
+    dvd_titles: Option<dvd::TitleScan>,
+    /// The next playlist entry, loading in the background.
+    preload: Option<preload::Preload>,
+    live: Option<LivePlayback>,
+    live_start: Option<LiveStart>,
+    web_start: Option<WebStart>,
+    web_video: Option<WebVideo>,
+    key_bindings: KeyBindings,
+    playlist: Playlist,
+    goto_dialog: Option<GoToTimeDialog>,
+    url_dialog: Option<OpenUrlDialog>,
+    raw_stream_dialog: Option<RawStreamDialog>,
+    settings: Settings,
+    subtitles: Option<Subtitles>,
+    subtitle_search: Option<SubtitleSearchDialog>,
+    show_subtitle_style: bool,
+    show_captions: bool,
+    bitmap_subtitle_textures: bitmap_subtitles::BitmapSubtitleTextures,
+    /// What egui's fonts were last built from.
+    loaded_fonts: Option<fonts::FontSetup>,
+    /// Fonts attached to the open file, for its subtitles.
+    attached_fonts: Vec<fonts::AttachedFont>,
+    track_tags: media_info::TrackTags,
+    window_title: String,
+    tray: Option<tray::Tray>,
+    /// Set while the window is hidden because it was minimized to the tray.
+    hidden_in_tray: bool,
+    shaders: ShaderManager,
+    export_dialog: Option<ExportDialog>,
+    transcode_dialog: Option<TranscodeDialog>,
+    remux_dialog: Option<RemuxDialog>,
+    recording: Option<Recording>,
+    skip_segments: skip_segments::SkipSegmentStore,
+    /// The skip segment playback is in, so that each one is skipped or offered once.
+    skip_segment_entered: Option<usize>,
+    skip_segment_dialog: Option<SkipSegmentDialog>,
+    /// In and out marks and markers of the open file, for exporting as an edit list.
+    cutlist: cutlist::Cutlist,
+    annotations: annotations::AnnotationStore,
+    annotation_panel: Option<AnnotationPanel>,
+    show_cutlist: bool,
+    cutlist_error: Option<String>,
+    ab_loop: Option<ab_loop::AbLoop>,
+    /// Pause at the end of the current file instead of playing the next one.
+    stop_after_current: bool,
+    sleep_timer: Option<sleep_timer::SleepTimer>,
+    /// Given with `--at-end`, which overrides the setting for this run.
+    end_of_playlist: Option<playlist::EndOfPlaylist>,
+    comparison: Option<Comparison>,
+    video_wall: Option<VideoWall>,
+    spherical_view: Option<SphericalView>,
+    /// Magnification of the picture from pinching or scrolling; 1.0 fits it to the window.
+    video_zoom: f32,
+    /// Offset of the zoomed picture's center from the center of the video area.
+    video_pan: egui::Vec2,
+    /// The open file is a picture, which stays on screen instead of playing.
+    still_image: bool,
+    touch_gestures: touch::TouchGestures,
+    /// The seek bar is being dragged with audible scrubbing on, so seeks play a snippet.
+    scrubbing: bool,
+    /// Position the seek bar is being dragged to, while the keyframe there is shown.
+    scrub_preview_ms: Option<i64>,
+    filmstrip: Option<filmstrip::Filmstrip>,
+    show_pixel_inspector: bool,
+    scopes: scopes::Scopes,
+    show_loudness: bool,
+    show_levels: bool,
+    /// Colors for the current frame, resolved from the theme setting and the system theme.
+    theme: theme::Theme,
+    osd: Option<Osd>,
+    /// Set by the "as displayed" screenshot; the next frame captures the window.
+    screenshot_requested: bool,
+    /// Video area of the frame the pending window capture shows.
+    screenshot_area: Option<egui::Rect>,
+    screenshot_sender: mpsc::Sender<Result<PathBuf, String>>,
+    screenshot_receiver: mpsc::Receiver<Result<PathBuf, String>>,
+    error_dialog: Option<ErrorDialog>,
+    now_playing: Option<now_playing::NowPlaying>,
+    commands: commands::CommandBus,
+    properties: properties::PropertyObservers,
+    /// Volume and speed changes, which the OSD shows wherever they came from.
+    osd_properties: mpsc::Receiver<properties::PropertyChange>,
+    sleep_inhibitor: Option<sleep_inhibitor::SleepInhibitor>,
+}
+
+/// Modal message explaining why a file could not be played.
+struct ErrorDialog {
+    title: String,
+    message: String,
+}
+
+/// Short message drawn over the video, e.g. after a volume change.
+struct Osd {
+    text: String,
+    color: egui::Color32,
+    shown_at: Instant,
+}
+
+/// Look direction for 360° video, in degrees.
+#[derive(Clone, Copy)]
+struct SphericalView {
+    yaw: f32,
+    pitch: f32,
+    fov: f32,
+}
+
+impl SphericalView {
+    fn new() -> Self {
+        Self {
+            yaw: 0.0,
+            pitch: 0.0,
+            fov: 90.0,
+        }
+    }
+
+    /// Turns the view so that the dragged point stays under the pointer.
+    fn drag(&mut self, delta: egui::Vec2, view_height: f32) {
+        let degrees_per_point = self.fov / view_height;
+        self.yaw = (self.yaw - delta.x * degrees_per_point).rem_euclid(360.0);
+        self.pitch = (self.pitch + delta.y * degrees_per_point).clamp(-90.0, 90.0);
+    }
+
+    fn zoom(&mut self, scroll: f32) {
+        self.fov = (self.fov - scroll * 0.1).clamp(30.0, 120.0);
+    }
+
+    fn pinch(&mut self, zoom_delta: f32) {
+        self.fov = (self.fov / zoom_delta).clamp(30.0, 120.0);
+    }
+
+    fn radians(&self) -> [f32; 3] {
+        [
+            self.yaw.to_radians(),
+            self.pitch.to_radians(),
+            self.fov.to_radians(),
+        ]
+    }
+}
+
+/// Pass over the whole file that measures the real bitrates. Dropping it stops the pass.
+struct BitrateMeasurement {
+    receiver: mpsc::Receiver<Result<media_info::MeasuredBitrates, String>>,
+    result: Option<Result<media_info::MeasuredBitrates, String>>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl BitrateMeasurement {
+    fn start(filename: String) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancel_flag = cancel.clone();
+
+        thread::spawn(move || {
+            let result = media_info::measure_bitrates(&filename, &cancel_flag);
+            let _ = sender.send(result.map_err(|e| e.to_string()));
+        });
+
+        Self {
+            receiver,
+            result: None,
+            cancel,
+        }
+    }
+
+    /// Returns true while the measurement is still running.
+    fn update(&mut self) -> bool {
+        if self.result.is_none() {
+            self.result = self.receiver.try_recv().ok();
+        }
+        self.result.is_none()
+    }
+
+    fn measured(&self) -> Option<&media_info::MeasuredBitrates> {
+        self.result.as_ref().and_then(|result| result.as_ref().ok())
+    }
+}
+
+impl Drop for BitrateMeasurement {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Playback of a Matroska ordered edition or of joined files, which may switch between files.
+struct TimelinePlayback {
+    timeline: matroska::OrderedTimeline,
+    part: usize,
+    /// The file that is currently open, i.e. the one of the current part.
+    open_path: String,
+}
+
+/// A live stream being played, positioned from its first frame.
+struct LivePlayback {
+    /// Buffer on disk that the stream is played from, if timeshift is on.
+    timeshift: Option<live::Timeshift>,
+    latency: live::LatencyControl,
+}
+
+/// A live stream whose timeshift buffer is filling up before playback starts.
+struct LiveStart {
+    filename: String,
+    media_info: media_info::MediaInfo,
+    web_video: Option<(WebVideo, Option<String>)>,
+    timeshift: live::Timeshift,
+}
+
+/// A YouTube page that yt-dlp is resolving before playback starts.
+struct WebStart {
+    page_url: String,
+    video_id: String,
+    resolving: web_video::Resolving,
+}
+
+/// A YouTube page opened through the stream yt-dlp resolved it to.
+struct WebVideo {
+    page_url: String,
+    video_id: String,
+    sponsor_lookup: Option<sponsorblock::SponsorLookup>,
+}
+
+/// Second video shown next to the main one, following the main video's clock.
+struct Comparison {
+    video: video::Video,
+    textures: VideoTextures,
+    filename: String,
+    frame_ms: Option<i64>,
+    wipe: bool,
+    wipe_position: f32,
+}
+
+/// Another file of the video wall, following the main video's clock like a comparison.
+struct WallTile {
+    video: video::Video,
+    textures: VideoTextures,
+    filename: String,
+    frame_ms: Option<i64>,
+}
+
+/// Files played side by side in a grid, with the open file in the first cell.
+struct VideoWall {
+    tiles: Vec<WallTile>,
+    /// Cell whose sound plays: 0 for the open file, otherwise the tile after it.
+    audible: usize,
+}
+
+struct GoToTimeDialog {
+    input: String,
+    error: Option<String>,
+    focus_requested: bool,
+}
+
+/// Notes of the open file being written and reviewed.
+struct AnnotationPanel {
+    media_path: String,
+    annotations: Vec<annotations::Annotation>,
+    draft: String,
+    /// Where the note being written goes, from when writing it started.
+    draft_ms: Option<i64>,
+    focus_requested: bool,
+    error: Option<String>,
+}
+
+struct OpenUrlDialog {
+    input: String,
+    error: Option<String>,
+    focus_requested: bool,
+    /// Sent with the requests for this URL only.
+    http: http::HttpOptions,
+}
+
+/// Asks how to open a raw elementary stream, whose frame rate probing can only guess.
+struct RawStreamDialog {
+    filename: String,
+    format: String,
+    framerate: f64,
+}
+
+enum SubtitleSearchEvent {
+    Results(Result<Vec<opensubtitles::SubtitleResult>, String>),
+    /// A subtitle downloaded for the file in `filename`.
+    Downloaded {
+        filename: String,
+        result: Result<String, String>,
+    },
+}
+
+struct ExportDialog {
+    start: String,
+    end: String,
+    fps: String,
+    format: export::ImageFormat,
+    output_dir: Option<PathBuf>,
+    error: Option<String>,
+    job: Option<export::ExportJob>,
+    progress: f32,
+    frames: usize,
+}
+
+struct TranscodeDialog {
+    start: String,
+    end: String,
+    video_codec: transcode::VideoCodec,
+    use_crf: bool,
+    crf: u32,
+    video_bitrate: String,
+    /// Output height, or `None` for the source size.
+    height: Option<u32>,
+    audio_codec: transcode::AudioCodec,
+    audio_bitrate: usize,
+    output: Option<PathBuf>,
+    error: Option<String>,
+    job: Option<transcode::TranscodeJob>,
+    progress: f32,
+    finished: bool,
+}
+
+/// A network stream being copied to a file while it plays.
+struct Recording {
+    job: remux::RemuxJob,
+    output: PathBuf,
+    started: Instant,
+}
+
+struct RemuxDialog {
+    container: remux::Container,
+    /// Stream index, label and whether it is copied.
+    streams: Vec<(usize, String, bool)>,
+    error: Option<String>,
+    job: Option<remux::RemuxJob>,
+    progress: f32,
+    saved_to: Option<PathBuf>,
+}
+
+/// A skip segment being edited, with the times as typed.
+struct SkipSegmentRow {
+    name: String,
+    start: String,
+    end: String,
+    auto: bool,
+}
+
+struct SkipSegmentDialog {
+    media_path: String,
+    rows: Vec<SkipSegmentRow>,
+    error: Option<String>,
+}
+
+struct SubtitleSearchDialog {
+    results: Vec<opensubtitles::SubtitleResult>,
+    status: Option<String>,
+    pending: Option<mpsc::Receiver<SubtitleSearchEvent>>,
 }
 
 struct FpsCounter {
@@ -49,76 +550,7436 @@ impl FpsCounter {
     }
 }
 
+struct VideoTextures {
+    textures: [Option<egui::TextureHandle>; 2],
+    images: [Arc<egui::ColorImage>; 2],
+    front: usize,
+    options: egui::TextureOptions,
+}
+
+impl VideoTextures {
+    fn new(options: egui::TextureOptions) -> Self {
+        Self {
+            textures: [None, None],
+            images: [
+                Arc::new(egui::ColorImage::default()),
+                Arc::new(egui::ColorImage::default()),
+            ],
+            front: 0,
+            options,
+        }
+    }
+
+    /// Changes the sampler, re-uploading the textures so that a paused frame updates too.
+    fn set_options(&mut self, options: egui::TextureOptions) {
+        if options == self.options {
+            return;
+        }
+
+        self.options = options;
+        for (texture, image) in self.textures.iter_mut().zip(&self.images) {
+            if let Some(texture) = texture {
+                texture.set(image.clone(), options);
+            }
+        }
+    }
+
+    fn current(&self) -> Option<&egui::TextureHandle> {
+        self.textures[self.front].as_ref()
+    }
+
+    /// The decoded frame on screen, before shaders and overlays.
+    fn current_image(&self) -> Option<Arc<egui::ColorImage>> {
+        self.current()?;
+        Some(self.images[self.front].clone())
+    }
+
+    fn upload(&mut self, ctx: &egui::Context, frame: &video::VideoFrame) {
+        let back = 1 - self.front;
+        let size = [frame.width, frame.height];
+        let pixel_count = frame.width * frame.height;
+
+        // The renderer releases its reference once the previous delta is applied,
+        // so normally the back image can be refilled in place.
+        if Arc::get_mut(&mut self.images[back]).is_none() {
+            self.images[back] = Arc::new(egui::ColorImage::default());
+        }
+        let image = Arc::get_mut(&mut self.images[back]).unwrap();
+
+        image.size = size;
+        image.pixels.resize(pixel_count, egui::Color32::TRANSPARENT);
+
+        for (dst, p) in image.pixels.iter_mut().zip(frame.buffer.chunks_exact(4)) {
+            *dst = egui::Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]);
+        }
+
+        let image = self.images[back].clone();
+
+        if let Some(texture) = &mut self.textures[back] {
+            texture.set(image, self.options);
+        } else {
+            self.textures[back] =
+                Some(ctx.load_texture(format!("video_frame_{}", back), image, self.options));
+        }
+
+        self.front = back;
+    }
+}
+
 impl VideoPlayer {
-    fn new(filename: Option<&str>) -> Result<Self, Box<dyn std::error::Error>> {
+    fn new(files: Vec<String>) -> Result<Self, Box<dyn std::error::Error>> {
         ffmpeg_next::init()?;
 
-        let (video, audio, frame_interval) = if let Some(filename) = filename {
-            let video = video::Video::new(filename)?;
-            let frame_interval = 1.0 / video.get_frame_rate();
-            let audio = audio::Audio::new(filename).ok();
-            (Some(video), audio, frame_interval)
+        let settings = Settings::load();
+        i18n::set_language(&settings.language);
+        http::configure(&settings.http, &settings.http_sites, &settings.proxy);
+        decode_ahead::configure(settings.frame_queue, settings.read_ahead_mb);
+        security::configure(&settings.security);
+        audio::select_output_device(settings.output_device.as_deref());
+        let cache_limit_mb = settings.cache_limit_mb;
+        thread::spawn(move || storage::trim_cache(cache_limit_mb));
+        let stats_log = if settings.stats_log {
+            Self::open_stats_log()
+        } else {
+            None
+        };
+        let playlist = Playlist::from_entries(
+            playlist::expand_entries(files, &settings.media_extensions),
+            settings.shuffle,
+        );
+        let (screenshot_sender, screenshot_receiver) = mpsc::channel();
+        let mut properties = properties::PropertyObservers::default();
+        let osd_properties = properties.observe();
+        let theme = theme::Theme::new(egui::Theme::Dark, settings.accent_color);
+
+        let mut player = Self {
+            video: None,
+            audio: None,
+            video_textures: VideoTextures::new(settings.sampler.texture_options()),
+            paused: false,
+            last_frame_time: Instant::now(),
+            frame_interval: 1.0 / 30.0,
+            fps_counter: FpsCounter::new(),
+            av_sync: av_sync::AvSync::default(),
+            volume: 0.7,
+            playback_speed: 1.0,
+            pitch_semitones: 0,
+            audio_delay_ms: 0,
+            muted: false,
+            device_watcher: audio_profiles::DeviceWatcher::start(),
+            output_devices: Default::default(),
+            output_device: None,
+            monitor: None,
+            is_fullscreen: settings.window.fullscreen,
+            window_fullscreen: settings.window.fullscreen,
+            reported_fullscreen: None,
+            windowed_position: settings
+                .window
+                .fullscreen_display
+                .filter(|_| settings.window.fullscreen)
+                .and(settings.window.position)
+                .map(egui::Pos2::from),
+            fullscreen_viewport: egui::ViewportId::ROOT,
+            video_window: false,
+            window_scale: None,
+            last_video_area: egui::Rect::NOTHING,
+            show_controls: !settings.window.fullscreen,
+            show_media_info: false,
+            bitrate_measurement: None,
+            show_analysis: false,
+            gop_analysis: None,
+            show_integrity: false,
+            integrity_check: None,
+            show_scene_detection: false,
+            storage_usage: None,
+            stats_log,
+            session_snapshots: session::SnapshotWriter::default(),
+            recovery: None,
+            diagnostics: None,
+            gpu_info: Vec::new(),
+            scene_detection: None,
+            show_subtitle_ocr: false,
+            subtitle_ocr_stream: None,
+            subtitle_ocr: None,
+            show_sync_analysis: false,
+            sync_analysis: None,
+            show_presets: false,
+            active_preset: None,
+            preset_restore: None,
+            bitrate_scan: None,
+            bitrate_graph_stream: None,
+            show_bitrate_overlay: false,
+            media_info: None,
+            current_filename: None,
+            timeline: None,
+            dvd_titles: None,
+            preload: None,
+            live: None,
+            live_start: None,
+            web_start: None,
+            web_video: None,
+            key_bindings: KeyBindings::new(),
+            playlist,
+            goto_dialog: None,
+            url_dialog: None,
+            raw_stream_dialog: None,
+            settings,
+            subtitles: None,
+            subtitle_search: None,
+            show_subtitle_style: false,
+            show_captions: false,
+            bitmap_subtitle_textures: Default::default(),
+            loaded_fonts: None,
+            attached_fonts: Vec::new(),
+            track_tags: media_info::TrackTags::default(),
+            window_title: "Avio Player".to_string(),
+            tray: None,
+            hidden_in_tray: false,
+            shaders: ShaderManager::new(),
+            export_dialog: None,
+            transcode_dialog: None,
+            remux_dialog: None,
+            recording: None,
+            skip_segments: skip_segments::SkipSegmentStore::load(),
+            skip_segment_entered: None,
+            ab_loop: None,
+            stop_after_current: false,
+            sleep_timer: None,
+            end_of_playlist: None,
+            skip_segment_dialog: None,
+            cutlist: cutlist::Cutlist::default(),
+            annotations: annotations::AnnotationStore::load(),
+            annotation_panel: None,
+            show_cutlist: false,
+            cutlist_error: None,
+            comparison: None,
+            video_wall: None,
+            spherical_view: None,
+            video_zoom: 1.0,
+            video_pan: egui::Vec2::ZERO,
+            still_image: false,
+            touch_gestures: touch::TouchGestures::default(),
+            scrubbing: false,
+            scrub_preview_ms: None,
+            filmstrip: None,
+            show_pixel_inspector: false,
+            scopes: scopes::Scopes::new(),
+            show_loudness: false,
+            show_levels: false,
+            theme,
+            osd: None,
+            screenshot_requested: false,
+            screenshot_area: None,
+            screenshot_sender,
+            screenshot_receiver,
+            error_dialog: None,
+            now_playing: None,
+            commands: commands::CommandBus::new(),
+            properties,
+            osd_properties,
+            sleep_inhibitor: None,
+        };
+
+        if let Some(filename) = player.playlist.current().map(|s| s.to_string()) {
+            if let Err(e) = player.load_video(&filename) {
+                player.show_error(&tr!("error-cannot-open", file = filename), e);
+            }
+        } else if player.settings.crash_recovery {
+            player.recovery = session::Leftover::find();
+        }
+
+        Ok(player)
+    }
+
+    fn init_gl(&mut self, gl: Option<Arc<eframe::glow::Context>>) {
+        if let Some(gl) = &gl {
+            self.gpu_info = diagnostics::gpu_info(gl);
+        }
+        self.shaders.set_gl(gl);
+
+        if let Some(path) = self.settings.active_shader.clone() {
+            self.shaders.load(&path);
+        }
+    }
+
+    /// Hooks up the system media controls once the window exists.
+    fn init_now_playing(&mut self, cc: &eframe::CreationContext) {
+        let notify = self.commands.sender(&cc.egui_ctx);
+        self.now_playing = Some(now_playing::NowPlaying::new(cc, notify));
+        self.update_now_playing_track();
+    }
+
+    fn update_now_playing_track(&mut self) {
+        let title = match self
+            .track_tags
+            .title
+            .clone()
+            .or_else(|| self.display_title())
+        {
+            Some(title) => title,
+            None => return,
+        };
+        let duration_ms = self.duration_ms();
+
+        if let Some(now_playing) = &mut self.now_playing {
+            now_playing.set_track(
+                &title,
+                self.track_tags.artist.as_deref(),
+                self.track_tags.album.as_deref(),
+                duration_ms,
+            );
+        }
+        self.update_now_playing_state();
+    }
+
+    fn update_now_playing_state(&mut self) {
+        let position_ms = self.current_time_ms();
+
+        if let Some(now_playing) = &mut self.now_playing {
+            now_playing.set_playback(self.paused, position_ms);
+        }
+    }
+
+    /// Keeps the screen on while a video plays; pausing or stopping releases it.
+    fn update_sleep_inhibitor(&mut self) {
+        let playing = self.video.is_some() && !self.paused;
+
+        if playing != self.sleep_inhibitor.is_some() {
+            self.sleep_inhibitor = playing.then(sleep_inhibitor::SleepInhibitor::new);
+        }
+    }
+
+    /// Carries out what the tray and the system controls sent. Their actions go through the
+    /// same dispatch as shortcuts and buttons.
+    fn handle_commands(&mut self, ctx: &egui::Context) {
+        for command in self.commands.commands() {
+            match command {
+                commands::PlayerCommand::Action(action) => self.perform_action(action),
+                commands::PlayerCommand::ShowWindow => {
+                    self.hidden_in_tray = false;
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Visible(true));
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+                }
+                commands::PlayerCommand::Quit => {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Close)
+                }
+            }
+        }
+    }
+
+    /// Brings what mirrors the player up to date with the changes made this frame.
+    fn handle_events(&mut self) {
+        for event in self.commands.take_events() {
+            match event {
+                commands::PlayerEvent::TrackChanged => self.update_now_playing_track(),
+                commands::PlayerEvent::PlaybackChanged => self.update_now_playing_state(),
+            }
+        }
+    }
+
+    fn load_video(&mut self, filename: &str) -> Result<(), error::MediaError> {
+        // Raw audio probes fine, so only raw video asks for a frame rate
+        if raw_stream::hint_for(filename).is_none() {
+            let format =
+                raw_stream::format_for(filename).filter(|f| raw_stream::is_video_format(f));
+            if let Some(format) = format {
+                self.raw_stream_dialog = Some(RawStreamDialog {
+                    filename: filename.to_string(),
+                    format: format.to_string(),
+                    framerate: raw_stream::DEFAULT_FRAMERATE,
+                });
+                return Ok(());
+            }
+        }
+
+        self.web_start = None;
+        // yt-dlp takes a few seconds, so playback starts from `update_web_start`
+        if let Some(video_id) = web_video::youtube_id(filename) {
+            self.close_media();
+            self.web_start = Some(WebStart {
+                page_url: filename.to_string(),
+                video_id,
+                resolving: web_video::Resolving::start(filename, http::yt_dlp_args(filename)),
+            });
+            return Ok(());
+        }
+        self.load_resolved(filename, None)
+    }
+
+    /// Opens a YouTube page once yt-dlp has resolved it to a stream.
+    fn update_web_start(&mut self) {
+        let Some(result) = (self.web_start.as_ref()).and_then(|start| start.resolving.update())
+        else {
+            return;
+        };
+        let Some(start) = self.web_start.take() else {
+            return;
+        };
+
+        let result = result
+            .map_err(error::MediaError::WebVideo)
+            .and_then(|resolved| {
+                if let Some(options) = http::url_options(&start.page_url) {
+                    http::set_url_options(&resolved.stream_url, options);
+                }
+                let page = WebVideo {
+                    page_url: start.page_url.clone(),
+                    video_id: start.video_id,
+                    sponsor_lookup: None,
+                };
+                self.load_resolved(&resolved.stream_url, Some((page, resolved.title)))
+            });
+        if let Err(e) = result {
+            self.show_error(&tr!("error-cannot-open", file = start.page_url), e);
+        }
+    }
+
+    /// Loads a file or stream, with the page it was resolved from if any.
+    fn load_resolved(
+        &mut self,
+        filename: &str,
+        web_video: Option<(WebVideo, Option<String>)>,
+    ) -> Result<(), error::MediaError> {
+        // The entry may have been probed and its audio decoded while the last one played
+        let (preloaded_info, prepared_audio) = match self.preload.take() {
+            Some(preload) if preload.filename() == filename => {
+                preload.into_loaded(self.settings.downmix)
+            }
+            _ => (None, None),
+        };
+
+        let media_info = match preloaded_info {
+            Some(media_info) => media_info,
+            None => media_info::get_media_info(filename)?,
+        };
+        self.live_start = None;
+        let is_live = playlist::is_url(filename) && media_info.is_live();
+        if is_live && self.settings.timeshift {
+            let streams = (media_info.video_streams.iter().map(|stream| stream.index))
+                .chain(media_info.audio_streams.iter().map(|stream| stream.index))
+                .chain(
+                    media_info
+                        .subtitle_streams
+                        .iter()
+                        .map(|stream| stream.index),
+                )
+                .collect();
+            // Filling the buffer can take a while, so playback starts from `update_live_start`
+            match live::Timeshift::start(filename, streams) {
+                Ok(timeshift) => {
+                    self.close_media();
+                    self.live_start = Some(LiveStart {
+                        filename: filename.to_string(),
+                        media_info,
+                        web_video,
+                        timeshift,
+                    });
+                    return Ok(());
+                }
+                Err(e) => eprintln!("Timeshift unavailable, playing the stream directly: {}", e),
+            }
+        }
+
+        self.open_media(filename, media_info, web_video, prepared_audio, None)
+    }
+
+    /// Starts playing a live stream once its timeshift buffer holds enough, or directly from
+    /// the network if buffering failed.
+    fn update_live_start(&mut self) {
+        let Some(start) = &mut self.live_start else {
+            return;
+        };
+        let Some(result) = start.timeshift.update_start() else {
+            return;
+        };
+        let Some(start) = self.live_start.take() else {
+            return;
+        };
+
+        let timeshift = match result {
+            Ok(()) => Some(start.timeshift),
+            Err(e) => {
+                eprintln!("Timeshift unavailable, playing the stream directly: {}", e);
+                None
+            }
+        };
+        let filename = start.filename;
+        if let Err(e) = self.open_media(
+            &filename,
+            start.media_info,
+            start.web_video,
+            None,
+            timeshift,
+        ) {
+            self.show_error(&tr!("error-cannot-open", file = filename), e);
+        }
+    }
+
+    /// Opens the streams of a probed file and resets the player for it.
+    fn open_media(
+        &mut self,
+        filename: &str,
+        media_info: media_info::MediaInfo,
+        web_video: Option<(WebVideo, Option<String>)>,
+        prepared_audio: Option<audio::PreparedAudio>,
+        timeshift: Option<live::Timeshift>,
+    ) -> Result<(), error::MediaError> {
+        let audio_only = media_info.is_audio_only();
+        let still_image = media_info.is_still_image();
+        let is_live = playlist::is_url(filename) && media_info.is_live();
+
+        // With timeshift, the stream plays from the buffer while it is being written
+        let source = match &timeshift {
+            Some(timeshift) => timeshift.path().to_string_lossy().into_owned(),
+            None => filename.to_string(),
+        };
+        let following = timeshift.is_some();
+
+        let video = if audio_only {
+            None
+        } else if is_live {
+            Some(video::Video::new_live(&source, following)?)
+        } else {
+            Some(video::Video::new(filename)?)
+        };
+        let audio = if is_live {
+            audio::Audio::new_live(
+                &source,
+                self.settings.downmix,
+                self.settings.audio_backend,
+                following,
+                self.settings.audio_memory_mb,
+            )
+        } else if let Some(prepared) = prepared_audio {
+            audio::Audio::from_prepared(prepared, self.settings.audio_backend)
+        } else {
+            audio::Audio::new(
+                filename,
+                self.settings.downmix,
+                self.settings.audio_backend,
+                self.settings.audio_memory_mb,
+            )
+        };
+        self.audio = if audio_only { Some(audio?) } else { audio.ok() };
+        self.av_sync.reset();
+        self.live = is_live.then(|| LivePlayback {
+            timeshift,
+            latency: live::LatencyControl::default(),
+        });
+        self.apply_volume();
+        self.apply_night_mode();
+
+        if let Some(video) = &video {
+            self.frame_interval = 1.0 / video.get_frame_rate();
+        }
+
+        self.track_tags = media_info.tags();
+        let has_attached_fonts = media_info
+            .other_streams
+            .iter()
+            .any(|stream| fonts::is_font_attachment(&stream.metadata));
+        self.attached_fonts = if has_attached_fonts {
+            fonts::attached_fonts(filename)
         } else {
-            (None, None, 1.0 / 30.0)
+            Vec::new()
+        };
+        if let Some(log) = &mut self.stats_log {
+            log.file_opened(filename, media_info.duration_ms);
+        }
+        self.media_info = Some(media_info);
+        self.current_filename = Some(filename.to_string());
+        let same_disc = self
+            .dvd_titles
+            .as_ref()
+            .is_some_and(|scan| scan.filename() == filename);
+        if !same_disc {
+            self.dvd_titles = dvd::is_dvd(filename).then(|| dvd::TitleScan::start(filename));
+        }
+        self.web_video = web_video.map(|(mut page, title)| {
+            if self.track_tags.title.is_none() {
+                self.track_tags.title = title;
+            }
+            if self.settings.sponsorblock.enabled {
+                page.sponsor_lookup = Some(sponsorblock::SponsorLookup::start(&page.video_id));
+            }
+            page
+        });
+        self.bitrate_measurement = None;
+        self.gop_analysis = None;
+        self.integrity_check = None;
+        self.scene_detection = None;
+        self.subtitle_ocr_stream = None;
+        self.subtitle_ocr = None;
+        self.sync_analysis = None;
+        self.audio_delay_ms = 0;
+        // Presets last for one file; the delay already starts over for each
+        if let Some(restore) = &mut self.preset_restore {
+            restore.audio_delay_ms = None;
+        }
+        let preset = presets::find_match(&self.settings.presets, filename).cloned();
+        if preset.is_some() || self.active_preset.is_some() {
+            if let Some(preset) = &preset {
+                println!("Applying preset {} to {}", preset.name, filename);
+            }
+            self.switch_preset(preset);
+        }
+        self.skip_segment_entered = None;
+        self.ab_loop = None;
+        self.cutlist = cutlist::Cutlist::default();
+        self.bitrate_scan = None;
+        self.bitrate_graph_stream = None;
+        let timeline =
+            concat::timeline_for(filename).or_else(|| matroska::OrderedTimeline::load(filename));
+        self.timeline = timeline.map(|timeline| TimelinePlayback {
+            timeline,
+            part: 0,
+            open_path: filename.to_string(),
+        });
+
+        self.video = video;
+        self.comparison = None;
+        self.video_wall = None;
+        self.filmstrip = None;
+        self.spherical_view = self.is_equirectangular().then(SphericalView::new);
+        self.video_zoom = 1.0;
+        self.video_pan = egui::Vec2::ZERO;
+        self.still_image = still_image;
+        self.apply_video_filters();
+        self.apply_playback_speed();
+        self.video_textures = VideoTextures::new(self.settings.sampler.texture_options());
+        self.subtitles = None;
+        self.paused = false;
+        self.last_frame_time = Instant::now();
+
+        if let Some(playback) = &self.timeline {
+            let chapters = playback
+                .timeline
+                .chapters
+                .iter()
+                .enumerate()
+                .map(|(index, chapter)| media_info::ChapterInfo {
+                    index,
+                    title: chapter.title.clone(),
+                    start_time_ms: chapter.start_ms,
+                    end_time_ms: chapter.end_ms,
+                    metadata: Default::default(),
+                })
+                .collect();
+            if let Some(media_info) = &mut self.media_info {
+                media_info.chapters = chapters;
+            }
+
+            let start_ms = playback.timeline.parts[0].start_ms;
+            self.open_timeline_part(0, start_ms)?;
+        } else if self.chapters().is_empty()
+            && !playlist::is_url(filename)
+            && security::autoload_sidecar_files()
+        {
+            if let Some(path) = chapters::find_for(filename) {
+                if let Err(e) = self.load_chapters(&path) {
+                    eprintln!("Error loading chapters from {}: {}", path, e);
+                }
+            }
+        }
+
+        self.commands.emit(commands::PlayerEvent::TrackChanged);
+        Ok(())
+    }
+
+    /// Switches to a part of the ordered edition, opening its file if it is not the
+    /// open one, and seeks to the given position in that file.
+    fn open_timeline_part(&mut self, index: usize, file_ms: i64) -> Result<(), error::MediaError> {
+        let path = match &self.timeline {
+            Some(playback) if playback.open_path != playback.timeline.parts[index].path => {
+                Some(playback.timeline.parts[index].path.clone())
+            }
+            Some(_) => None,
+            None => return Ok(()),
+        };
+
+        if let Some(path) = &path {
+            if self.video.is_some() {
+                let video = video::Video::new(path)?;
+                self.frame_interval = 1.0 / video.get_frame_rate();
+                self.video = Some(video);
+                self.apply_video_filters();
+            }
+
+            let audio = audio::Audio::new(
+                path,
+                self.settings.downmix,
+                self.settings.audio_backend,
+                self.settings.audio_memory_mb,
+            );
+            self.audio = if self.video.is_some() {
+                audio.ok()
+            } else {
+                Some(audio?)
+            };
+            self.apply_volume();
+            self.apply_night_mode();
+            self.apply_playback_speed();
+            self.apply_audio_delay();
+            self.set_paused(self.paused);
+        }
+
+        if let Some(playback) = &mut self.timeline {
+            playback.part = index;
+            if let Some(path) = path {
+                playback.open_path = path;
+            }
+        }
+
+        self.seek_media(file_ms);
+        Ok(())
+    }
+
+    /// Moves on to the next part of the ordered edition; false after the last one.
+    fn next_timeline_part(&mut self) -> bool {
+        let (next, start_ms) = match &self.timeline {
+            Some(playback) if playback.part + 1 < playback.timeline.parts.len() => (
+                playback.part + 1,
+                playback.timeline.parts[playback.part + 1].start_ms,
+            ),
+            _ => return false,
+        };
+
+        match self.open_timeline_part(next, start_ms) {
+            Ok(()) => true,
+            Err(e) => {
+                self.show_error(&tr!("error-open-linked-segment"), e);
+                false
+            }
+        }
+    }
+
+    /// Chapters of an ordered edition end before the file does.
+    fn update_timeline(&mut self, ctx: &egui::Context) {
+        let part_end_ms = match &self.timeline {
+            Some(playback) => playback.timeline.parts[playback.part].end_ms,
+            None => return,
         };
 
-        let current_filename = filename.map(|s| s.to_string());
-        let media_info = if let Some(filename) = filename {
-            media_info::get_media_info(filename)
-        } else {
-            None
-        };
+        if !self.paused && self.has_media() && self.file_time_ms() >= part_end_ms {
+            self.handle_end_of_file(ctx);
+        }
+    }
+
+    /// The chapter boundary within `CHAPTER_SNAP_PX` of `x` on the seek bar `bar`, when
+    /// snapping is on.
+    fn chapter_snap(&self, bar: egui::Rect, x: f32) -> Option<i64> {
+        if !self.settings.snap_to_chapters {
+            return None;
+        }
+        let duration_ms = self.duration_ms().max(1) as f32;
+        let boundary_x = |ms: i64| bar.left() + bar.width() * ms as f32 / duration_ms;
+        self.chapters()
+            .iter()
+            .flat_map(|chapter| [chapter.start_time_ms, chapter.end_time_ms])
+            .map(|ms| (ms, (boundary_x(ms) - x).abs()))
+            .filter(|(_, distance)| *distance <= CHAPTER_SNAP_PX)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(ms, _)| ms)
+    }
+
+    fn chapters(&self) -> &[media_info::ChapterInfo] {
+        self.media_info
+            .as_ref()
+            .map(|info| info.chapters.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Replaces the chapters of the file with those of a CUE sheet or FFmpeg metadata file.
+    fn load_chapters(&mut self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let chapters = chapters::load(path, self.duration_ms())?;
+        println!("Loaded {} chapters from {}", chapters.len(), path);
+
+        if let Some(media_info) = &mut self.media_info {
+            media_info.chapters = chapters;
+        }
+        Ok(())
+    }
+
+    fn load_chapters_dialog(&mut self) {
+        if !self.has_media() {
+            return;
+        }
+
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter(tr!("filter-chapters"), &["cue", "ffmetadata", "txt"])
+            .add_filter(tr!("filter-all-files"), &["*"])
+            .pick_file()
+        {
+            let path = path.to_string_lossy().into_owned();
+            if let Err(e) = self.load_chapters(&path) {
+                self.show_error(&tr!("error-load-chapters"), e);
+            }
+        }
+    }
+
+    fn current_chapter(&self) -> Option<usize> {
+        let current_ms = self.current_time_ms();
+        self.chapters()
+            .iter()
+            .rposition(|chapter| chapter.start_time_ms <= current_ms)
+    }
+
+    fn seek_to_chapter(&mut self, index: usize) {
+        if let Some(chapter) = self.chapters().get(index) {
+            let (start_ms, title) = (chapter.start_time_ms, chapter.title.clone());
+            self.seek_to(start_ms);
+            self.show_osd(title, egui::Color32::WHITE);
+        }
+    }
+
+    /// The file being played, which differs from the playlist entry for linked segments.
+    fn media_path(&self) -> Option<&String> {
+        match &self.timeline {
+            Some(playback) => Some(&playback.open_path),
+            None => self.current_filename.as_ref(),
+        }
+    }
+
+    fn open_file(&mut self, filename: &str) {
+        concat::clear();
+        self.playlist = Playlist::from_entries(
+            playlist::expand_entries(vec![filename.to_string()], &self.settings.media_extensions),
+            self.settings.shuffle,
+        );
+
+        if let Some(filename) = self.playlist.current().map(|s| s.to_string()) {
+            if let Err(e) = self.load_video(&filename) {
+                self.show_error(&tr!("error-cannot-open", file = filename), e);
+            }
+        }
+    }
+
+    fn set_file_types_registered(&mut self, register: bool) {
+        let result = if register {
+            file_types::register(&self.settings.media_extensions)
+        } else {
+            file_types::unregister(&self.settings.media_extensions)
+        };
+
+        match result {
+            Ok(()) => self.settings.register_file_types = register,
+            Err(e) => self.show_error(&tr!("error-file-associations"), e),
+        }
+    }
+
+    /// Logs the error and shows it in a modal dialog.
+    fn show_error(&mut self, title: &str, error: impl std::fmt::Display) {
+        eprintln!("{}: {}", title, error);
+        self.error_dialog = Some(ErrorDialog {
+            title: title.to_string(),
+            message: error.to_string(),
+        });
+    }
+
+    fn open_file_dialog(&mut self) {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter(
+                tr!("filter-media-files"),
+                self.settings.media_extensions.as_slice(),
+            )
+            .add_filter(tr!("filter-playlists"), &["m3u", "m3u8", "pls"])
+            .add_filter(tr!("filter-dvd-images"), &["iso"])
+            .add_filter(tr!("filter-all-files"), &["*"])
+            .pick_file()
+        {
+            if let Some(path_str) = path.to_str() {
+                self.open_file(path_str);
+            }
+        }
+    }
+
+    fn open_comparison_dialog(&mut self) {
+        if self.video.is_none() {
+            return;
+        }
+
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter(
+                tr!("filter-media-files"),
+                self.settings.media_extensions.as_slice(),
+            )
+            .add_filter(tr!("filter-all-files"), &["*"])
+            .pick_file()
+        {
+            let filename = path.to_string_lossy().into_owned();
+            if let Err(e) = self.open_comparison(&filename) {
+                self.show_error(&tr!("error-open-comparison"), e);
+            }
+        }
+    }
+
+    fn open_comparison(&mut self, filename: &str) -> Result<(), error::MediaError> {
+        let mut video = video::Video::new(filename)?;
+        video.seek(self.current_time_ms())?;
+
+        self.comparison = Some(Comparison {
+            video,
+            textures: VideoTextures::new(self.settings.sampler.texture_options()),
+            filename: filename.to_string(),
+            frame_ms: None,
+            wipe: false,
+            wipe_position: 0.5,
+        });
+        self.apply_video_filters();
+        Ok(())
+    }
+
+    /// Decodes the comparison video up to the frame that is visible at `target_ms`.
+    fn sync_comparison(&mut self, ctx: &egui::Context, target_ms: i64) {
+        if let Some(comparison) = &mut self.comparison {
+            Self::follow_video(
+                ctx,
+                &mut comparison.video,
+                &mut comparison.textures,
+                &mut comparison.frame_ms,
+                target_ms,
+            );
+        }
+    }
+
+    fn sync_video_wall(&mut self, ctx: &egui::Context, target_ms: i64) {
+        if let Some(wall) = &mut self.video_wall {
+            for tile in &mut wall.tiles {
+                Self::follow_video(
+                    ctx,
+                    &mut tile.video,
+                    &mut tile.textures,
+                    &mut tile.frame_ms,
+                    target_ms,
+                );
+            }
+        }
+    }
+
+    /// Decodes a video that follows the main one up to the frame visible at `target_ms`.
+    fn follow_video(
+        ctx: &egui::Context,
+        video: &mut video::Video,
+        textures: &mut VideoTextures,
+        frame_ms: &mut Option<i64>,
+        target_ms: i64,
+    ) {
+        let frame_duration_ms = ((1000.0 / video.get_frame_rate()) as i64).max(1);
+
+        // Bounded so that catching up after a seek does not stall the UI
+        for _ in 0..30 {
+            if frame_ms.is_some_and(|frame_ms| frame_ms + frame_duration_ms > target_ms) {
+                break;
+            }
+
+            match video.next_frame() {
+                Some(Ok(frame)) => {
+                    *frame_ms = Some(frame.timestamp_ms);
+                    if frame.timestamp_ms + frame_duration_ms > target_ms {
+                        textures.upload(ctx, frame);
+                    }
+                }
+                Some(Err(_)) => {}
+                None => break,
+            }
+        }
+    }
+
+    fn open_folder_dialog(&mut self) {
+        if let Some(path) = rfd::FileDialog::new().pick_folder() {
+            if let Some(path_str) = path.to_str() {
+                self.open_file(path_str);
+            }
+        }
+    }
+
+    fn join_files_dialog(&mut self) {
+        if let Some(paths) = rfd::FileDialog::new()
+            .add_filter(
+                tr!("filter-media-files"),
+                self.settings.media_extensions.as_slice(),
+            )
+            .add_filter(tr!("filter-all-files"), &["*"])
+            .pick_files()
+        {
+            let mut paths: Vec<String> = paths
+                .iter()
+                .map(|path| path.to_string_lossy().into_owned())
+                .collect();
+            // Split recordings number their parts, but the dialog returns them in any order
+            paths.sort_by(|a, b| playlist::natural_cmp(a, b));
+            self.join_files(&paths);
+        }
+    }
+
+    /// Plays the files as one timeline, with a single seek bar and duration.
+    fn join_files(&mut self, paths: &[String]) {
+        if paths.len() < 2 {
+            if let Some(path) = paths.first() {
+                self.open_file(path);
+            }
+            return;
+        }
+
+        concat::clear();
+        match concat::timeline(paths) {
+            Ok(timeline) => {
+                let first = timeline.parts[0].path.clone();
+                concat::register(timeline);
+                self.playlist = Playlist::from_entries(vec![first.clone()], false);
+                if let Err(e) = self.load_video(&first) {
+                    self.show_error(&tr!("error-cannot-open", file = first), e);
+                }
+            }
+            Err(e) => self.show_error(&tr!("error-join-files"), e),
+        }
+    }
+
+    fn video_wall_dialog(&mut self) {
+        if let Some(paths) = rfd::FileDialog::new()
+            .add_filter(
+                tr!("filter-media-files"),
+                self.settings.media_extensions.as_slice(),
+            )
+            .add_filter(tr!("filter-all-files"), &["*"])
+            .pick_files()
+        {
+            let mut paths: Vec<String> = paths
+                .iter()
+                .map(|path| path.to_string_lossy().into_owned())
+                .collect();
+            paths.sort_by(|a, b| playlist::natural_cmp(a, b));
+            self.open_video_wall(&paths);
+        }
+    }
+
+    /// Plays the files at once in a grid. The first one is opened as usual and drives the
+    /// controls; the others follow its clock without sound.
+    fn open_video_wall(&mut self, paths: &[String]) {
+        let Some((first, others)) = paths.split_first() else {
+            return;
+        };
+        if paths.len() > MAX_WALL_FILES {
+            eprintln!(
+                "Playing the first {} of {} files in the video wall",
+                MAX_WALL_FILES,
+                paths.len()
+            );
+        }
+
+        concat::clear();
+        self.playlist = Playlist::from_entries(vec![first.clone()], false);
+        if let Err(e) = self.load_video(first) {
+            self.show_error(&tr!("error-cannot-open", file = first), e);
+            return;
+        }
+        if self.video.is_none() {
+            return;
+        }
+
+        let position_ms = self.file_time_ms();
+        let mut tiles = Vec::new();
+        for path in others.iter().take(MAX_WALL_FILES - 1) {
+            let opened = video::Video::new(path).and_then(|mut video| {
+                video.seek(position_ms)?;
+                Ok(video)
+            });
+            match opened {
+                Ok(video) => tiles.push(WallTile {
+                    video,
+                    textures: VideoTextures::new(self.settings.sampler.texture_options()),
+                    filename: path.clone(),
+                    frame_ms: None,
+                }),
+                Err(e) => self.show_error(&tr!("error-cannot-open", file = path), e),
+            }
+        }
+
+        self.comparison = None;
+        self.video_wall = Some(VideoWall { tiles, audible: 0 });
+        self.apply_video_filters();
+    }
+
+    fn close_video_wall(&mut self) {
+        if self
+            .video_wall
+            .as_ref()
+            .is_some_and(|wall| wall.audible != 0)
+        {
+            self.select_wall_audio(0);
+        }
+        self.video_wall = None;
+    }
+
+    /// Plays the sound of one cell of the video wall instead of the others.
+    fn select_wall_audio(&mut self, cell: usize) {
+        let Some(wall) = &mut self.video_wall else {
+            return;
+        };
+        if wall.audible == cell {
+            return;
+        }
+        wall.audible = cell;
+        if let Err(e) = self.reload_audio() {
+            self.show_error(&tr!("error-reload-audio"), e);
+        }
+    }
+
+    /// The file the sound comes from: the open one, or the chosen cell of the video wall.
+    fn audio_path(&self) -> Option<String> {
+        match &self.video_wall {
+            Some(wall) if wall.audible > 0 => wall
+                .tiles
+                .get(wall.audible - 1)
+                .map(|tile| tile.filename.clone()),
+            _ => self.media_path().cloned(),
+        }
+    }
+
+    /// Decodes the sound again from the current position, e.g. for another layout.
+    fn reload_audio(&mut self) -> Result<(), error::MediaError> {
+        let Some(filename) = self.audio_path() else {
+            return Ok(());
+        };
+
+        let position_ms = self.file_time_ms();
+        let secondary_stream = self
+            .audio
+            .as_ref()
+            .and_then(|audio| audio.secondary_stream());
+        let mut audio = audio::Audio::new(
+            &filename,
+            self.settings.downmix,
+            self.settings.audio_backend,
+            self.settings.audio_memory_mb,
+        )?;
+        audio.set_delay(self.audio_delay_ms);
+        audio.seek(position_ms);
+        self.audio = Some(audio);
+        self.av_sync.reset();
+        self.apply_volume();
+        self.apply_night_mode();
+        self.apply_playback_speed();
+        self.set_paused(self.paused);
+        if secondary_stream.is_some() {
+            self.set_secondary_audio(secondary_stream);
+        }
+        Ok(())
+    }
+
+    /// Mixes another audio stream of the file in, such as a commentary track, or stops.
+    fn set_secondary_audio(&mut self, stream_index: Option<usize>) {
+        let Some(filename) = self.audio_path() else {
+            return;
+        };
+        let memory_mb = self.settings.audio_memory_mb;
+        let Some(audio) = &mut self.audio else {
+            return;
+        };
+
+        audio.set_secondary_stream(&filename, stream_index, memory_mb);
+        if stream_index.is_none() {
+            self.show_osd(tr!("osd-secondary-audio-off"), egui::Color32::WHITE);
+        }
+    }
+
+    /// Mixes in a secondary stream once it has been decoded.
+    fn update_secondary_audio(&mut self) {
+        let Some(audio) = &mut self.audio else {
+            return;
+        };
+        let Some((index, result)) = audio.update_secondary() else {
+            return;
+        };
+
+        match result {
+            Ok(()) => {
+                audio.set_mix_balance(self.settings.mix_balance);
+                self.show_osd(
+                    tr!("osd-secondary-audio", index = index),
+                    egui::Color32::WHITE,
+                );
+            }
+            Err(e) => self.show_error(&tr!("error-secondary-audio"), e),
+        }
+    }
+
+    /// Audio streams that can be mixed into the one playing.
+    fn secondary_audio_streams(&self) -> Vec<(usize, String)> {
+        let Some(main_stream) = self.audio.as_ref().map(|audio| audio.stream_index()) else {
+            return Vec::new();
+        };
+        self.media_info
+            .iter()
+            .flat_map(|info| info.audio_streams.iter())
+            .filter(|stream| stream.index != main_stream)
+            .map(|stream| {
+                let mut label = tr!(
+                    "stream-label",
+                    index = stream.index,
+                    description = stream.codec_name
+                );
+                for key in ["title", "language"] {
+                    if let Some(value) = stream.metadata.get(key) {
+                        label.push_str(&format!(" [{}]", value));
+                    }
+                }
+                (stream.index, label)
+            })
+            .collect()
+    }
+
+    fn play_next(&mut self) -> bool {
+        // Taken out while trying entries, as loading needs the player
+        let mut playlist = std::mem::take(&mut self.playlist);
+        // Broken entries are skipped; the dialog reports the last one
+        let played = playlist.next_playable(|filename| match self.load_video(filename) {
+            Ok(()) => true,
+            Err(e) => {
+                self.show_error(&tr!("error-cannot-open", file = filename), e);
+                false
+            }
+        });
+        self.playlist = playlist;
+        played
+    }
+
+    fn play_previous(&mut self) {
+        if let Some(filename) = self.playlist.previous().map(|s| s.to_string()) {
+            if let Err(e) = self.load_video(&filename) {
+                self.show_error(&tr!("error-cannot-open", file = filename), e);
+            }
+        }
+    }
+
+    fn export_playlist(&self) {
+        if self.playlist.current().is_none() {
+            return;
+        }
+
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter(tr!("filter-m3u8-playlist"), &["m3u8"])
+            .set_file_name("playlist.m3u8")
+            .save_file()
+        {
+            if let Err(e) = self.playlist.export_m3u8(&path) {
+                eprintln!("Error exporting playlist: {}", e);
+            }
+        }
+    }
+
+    fn handle_end_of_file(&mut self, ctx: &egui::Context) {
+        if self.show_loudness {
+            let integrated = self
+                .audio
+                .as_mut()
+                .and_then(|audio| audio.update_loudness().integrated());
+            if integrated.is_some() {
+                self.show_osd(
+                    tr!(
+                        "osd-integrated-loudness",
+                        value = loudness::format_lufs(integrated)
+                    ),
+                    egui::Color32::WHITE,
+                );
+            }
+        }
+
+        if self.next_timeline_part() {
+            return;
+        }
+        if self.stop_after_current {
+            self.stop_after_current = false;
+            self.set_paused(true);
+            self.show_osd(tr!("osd-stopped-after-file"), egui::Color32::WHITE);
+            return;
+        }
+        if self.sleep_timer == Some(sleep_timer::SleepTimer::EndOfFile) {
+            self.sleep_timer = None;
+            self.run_sleep_action(ctx);
+            return;
+        }
+        // Shuffle starts a new cycle instead of running out, so when something is set to happen
+        // at the end, the end of the cycle counts
+        let end_action = self
+            .end_of_playlist
+            .unwrap_or(self.settings.end_of_playlist);
+        if end_action != playlist::EndOfPlaylist::Stay && self.playlist.is_last() {
+            self.finish_playlist(ctx);
+            return;
+        }
+        if !self.play_next() {
+            self.finish_playlist(ctx);
+        }
+    }
+
+    fn finish_playlist(&mut self, ctx: &egui::Context) {
+        self.set_paused(true);
+        match self
+            .end_of_playlist
+            .unwrap_or(self.settings.end_of_playlist)
+        {
+            playlist::EndOfPlaylist::Stay => {}
+            playlist::EndOfPlaylist::Close => self.close_media(),
+            playlist::EndOfPlaylist::Quit => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+            playlist::EndOfPlaylist::Suspend => {
+                self.sleep_inhibitor = None;
+                sleep_inhibitor::suspend();
+            }
+        }
+    }
+
+    /// Closes the open file and goes back to the start screen.
+    fn close_media(&mut self) {
+        self.live_start = None;
+        self.web_start = None;
+        self.video = None;
+        self.audio = None;
+        self.live = None;
+        self.timeline = None;
+        self.web_video = None;
+        self.media_info = None;
+        self.current_filename = None;
+        self.track_tags = media_info::TrackTags::default();
+        self.still_image = false;
+        self.subtitles = None;
+        self.ab_loop = None;
+        self.cutlist = cutlist::Cutlist::default();
+        self.comparison = None;
+        self.filmstrip = None;
+        self.spherical_view = None;
+        self.video_textures = VideoTextures::new(self.settings.sampler.texture_options());
+        self.commands.emit(commands::PlayerEvent::TrackChanged);
+    }
+
+    fn has_media(&self) -> bool {
+        self.video.is_some() || self.audio.is_some()
+    }
+
+    /// Position on the timeline, which for ordered editions spans several parts.
+    fn current_time_ms(&self) -> i64 {
+        if let Some(preview_ms) = self.scrub_preview_ms {
+            return preview_ms;
+        }
+        let file_ms = self.file_time_ms();
+        match &self.timeline {
+            Some(playback) => playback.timeline.to_timeline_ms(playback.part, file_ms),
+            None => file_ms,
+        }
+    }
+
+    /// Position in the open file.
+    fn file_time_ms(&self) -> i64 {
+        match (&self.video, &self.audio) {
+            (Some(video), _) => video.get_current_timestamp_ms(),
+            (None, Some(audio)) => audio.get_current_time(),
+            (None, None) => 0,
+        }
+    }
+
+    fn duration_ms(&self) -> i64 {
+        if let Some(playback) = &self.timeline {
+            return playback.timeline.duration_ms();
+        }
+
+        if let Some(edge_ms) = self.live_edge_ms() {
+            return edge_ms;
+        }
+
+        match (&self.video, &self.audio) {
+            (Some(video), _) => video.get_duration_ms(),
+            (None, Some(audio)) => audio.get_duration_ms(),
+            (None, None) => 0,
+        }
+    }
+
+    /// How much of a live stream has arrived, measured from its first frame.
+    fn live_edge_ms(&self) -> Option<i64> {
+        let live = self.live.as_ref()?;
+        let buffered_ms = live
+            .timeshift
+            .as_ref()
+            .map(|timeshift| timeshift.buffered_ms());
+        // The audio is decoded as fast as the stream comes in, the video only as it plays
+        let decoded_ms = [
+            self.audio.as_ref().map(|audio| audio.get_duration_ms()),
+            self.video.as_ref().map(|video| video.get_duration_ms()),
+        ];
+        buffered_ms
+            .into_iter()
+            .chain(decoded_ms.into_iter().flatten())
+            .max()
+    }
+
+    /// Follows the timeshift buffer and keeps a live stream near its live edge.
+    fn update_live(&mut self) {
+        let Some(live) = &mut self.live else {
+            return;
+        };
+
+        let ended = live
+            .timeshift
+            .as_mut()
+            .and_then(|timeshift| timeshift.update());
+        if let Some(result) = ended {
+            if let Err(e) = result {
+                eprintln!("Timeshift buffer stopped: {}", e);
+            }
+            // The buffer is complete now, so its end is the end of the stream
+            if let Some(video) = &mut self.video {
+                video.stop_following();
+            }
+            if let Some(audio) = &self.audio {
+                audio.stop_following();
+            }
+        }
+
+        if self.paused {
+            return;
+        }
+        let position_ms = self.file_time_ms();
+        let (Some(edge_ms), Some(live)) = (self.live_edge_ms(), &mut self.live) else {
+            return;
+        };
+        let correction = live.latency.update(
+            self.settings.live_catch_up,
+            self.settings.live_latency_ms as i64,
+            position_ms,
+            edge_ms,
+        );
+
+        match correction {
+            Some(live::Correction::SpeedChanged) => self.apply_playback_speed(),
+            Some(live::Correction::SkipTo(target_ms)) => self.skip_live(target_ms),
+            None => {}
+        }
+    }
+
+    /// Jumps ahead in a live stream. Without timeshift, the frames in between are dropped.
+    fn skip_live(&mut self, target_ms: i64) {
+        println!("Catching up with the live stream at {}ms", target_ms);
+        if let Some(video) = &mut self.video {
+            if let Err(e) = video.skip_to(target_ms) {
+                eprintln!("Seek error: {}", e);
+            }
+        }
+        if let Some(audio) = &self.audio {
+            audio.seek(target_ms);
+        }
+    }
+
+    /// Playback speed including the speed-up of catching up with a live stream and the ramp
+    /// of an A-B loop.
+    fn effective_speed(&self) -> f64 {
+        let factor = self
+            .live
+            .as_ref()
+            .map_or(1.0, |live| live.latency.speed_factor());
+        let ramp = self
+            .ab_loop
+            .as_ref()
+            .map_or(1.0, |ab_loop| ab_loop.speed_factor(&self.settings.ab_loop));
+        self.playback_speed * factor * ramp
+    }
+
+    /// "Artist - Title" from the tags, or the file name when the file is untagged.
+    fn display_title(&self) -> Option<String> {
+        match (&self.track_tags.artist, &self.track_tags.title) {
+            (Some(artist), Some(title)) => Some(format!("{} - {}", artist, title)),
+            (None, Some(title)) => Some(title.clone()),
+            _ => self.current_filename.as_ref().map(|filename| {
+                Path::new(filename)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| filename.clone())
+            }),
+        }
+    }
+
+    fn update_window_title(&mut self, ctx: &egui::Context) {
+        let title = match self.display_title() {
+            Some(title) => format!(
+                "{} — {}/{} — Avio",
+                title,
+                Self::format_time(self.current_time_ms()),
+                Self::format_time(self.duration_ms())
+            ),
+            None => "Avio Player".to_string(),
+        };
+
+        if title != self.window_title {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(title.clone()));
+            self.window_title = title;
+        }
+    }
+
+    /// Creates or removes the tray icon to match the setting and hides the window when it is
+    /// minimized to the tray.
+    fn update_tray(&mut self, ctx: &egui::Context, frame: &eframe::Frame) {
+        if self.settings.tray_icon != self.tray.is_some() {
+            self.tray = None;
+            if self.settings.tray_icon {
+                match tray::Tray::new(self.commands.sender(ctx), frame) {
+                    Ok(tray) => self.tray = Some(tray),
+                    Err(e) => {
+                        eprintln!("Error creating tray icon: {}", e);
+                        self.settings.tray_icon = false;
+                    }
+                }
+            }
+        }
+
+        let Some(tray) = &mut self.tray else {
+            return;
+        };
+        tray.set_title(&self.window_title);
+
+        let minimized = ctx.input(|i| i.viewport().minimized == Some(true));
+        if self.settings.minimize_to_tray && minimized && !self.hidden_in_tray {
+            self.hidden_in_tray = true;
+            ctx.send_viewport_cmd(egui::ViewportCommand::Visible(false));
+        }
+    }
+
+    /// Puts the window into or out of fullscreen when `is_fullscreen` changes, and follows the
+    /// window system when it leaves fullscreen on its own.
+    fn update_fullscreen(&mut self, ctx: &egui::Context) {
+        if self.is_fullscreen != self.window_fullscreen {
+            self.window_fullscreen = self.is_fullscreen;
+            if self.is_fullscreen {
+                let viewport = if self.video_window {
+                    Self::video_window_id()
+                } else {
+                    egui::ViewportId::ROOT
+                };
+                self.fullscreen_viewport = viewport;
+
+                if let Some(position) = self.settings.window.fullscreen_display {
+                    self.windowed_position =
+                        ctx.input_for(viewport, |i| i.viewport().outer_rect.map(|rect| rect.min));
+                    ctx.send_viewport_cmd_to(
+                        viewport,
+                        egui::ViewportCommand::OuterPosition(position.into()),
+                    );
+                }
+                ctx.send_viewport_cmd_to(viewport, egui::ViewportCommand::Fullscreen(true));
+            } else {
+                let viewport = self.fullscreen_viewport;
+                ctx.send_viewport_cmd_to(viewport, egui::ViewportCommand::Fullscreen(false));
+                if let Some(position) = self.windowed_position.take() {
+                    ctx.send_viewport_cmd_to(
+                        viewport,
+                        egui::ViewportCommand::OuterPosition(position),
+                    );
+                }
+            }
+        }
+
+        let reported = ctx.input_for(self.fullscreen_viewport, |i| i.viewport().fullscreen);
+        if reported != self.reported_fullscreen
+            && reported.is_some_and(|fullscreen| fullscreen != self.window_fullscreen)
+        {
+            self.is_fullscreen = !self.window_fullscreen;
+            self.window_fullscreen = self.is_fullscreen;
+            self.show_controls = !self.is_fullscreen || self.video_window;
+        }
+
+        self.reported_fullscreen = reported;
+    }
+
+    /// Resizes the window around the picture once it has left fullscreen and maximized,
+    /// keeping the controls and panels as they are.
+    fn update_window_scale(&mut self, ctx: &egui::Context) {
+        let Some(percent) = self.window_scale else {
+            return;
+        };
+        let viewport = if self.video_window {
+            Self::video_window_id()
+        } else {
+            egui::ViewportId::ROOT
+        };
+        let (fullscreen, maximized, inner_rect, pixels_per_point) = ctx.input_for(viewport, |i| {
+            let info = i.viewport();
+            (
+                info.fullscreen.unwrap_or(false),
+                info.maximized.unwrap_or(false),
+                info.inner_rect,
+                i.pixels_per_point(),
+            )
+        });
+        if fullscreen {
+            return;
+        }
+        if maximized {
+            ctx.send_viewport_cmd_to(viewport, egui::ViewportCommand::Maximized(false));
+            return;
+        }
+        let (Some(inner_rect), Some(texture)) = (inner_rect, self.video_textures.current()) else {
+            return;
+        };
+        if !self.last_video_area.is_positive() {
+            return;
+        }
+        self.window_scale = None;
+
+        // Points are pixels divided by the display scale, so this comes out in screen pixels
+        let [width, height] = texture.size();
+        let scale = percent as f32 / 100.0;
+        let video_size = egui::vec2(width as f32, height as f32) * scale / pixels_per_point;
+        let size = inner_rect.size() - self.last_video_area.size() + video_size;
+        ctx.send_viewport_cmd_to(viewport, egui::ViewportCommand::InnerSize(size));
+        self.show_osd(
+            tr!("osd-window-size", percent = percent),
+            egui::Color32::WHITE,
+        );
+    }
+
+    /// Keeps the window geometry in the settings up to date, so that exiting saves it.
+    fn remember_window_geometry(&mut self, ctx: &egui::Context) {
+        let window = &mut self.settings.window;
+        window.fullscreen = self.is_fullscreen;
+
+        ctx.input(|i| {
+            let viewport = i.viewport();
+            window.maximized = viewport.maximized.unwrap_or(window.maximized);
+            // Keep the normal geometry so that the window can be restored to it
+            if window.maximized
+                || window.fullscreen
+                || viewport.fullscreen == Some(true)
+                || viewport.minimized == Some(true)
+            {
+                return;
+            }
+            if let Some(rect) = viewport.inner_rect {
+                window.size = Some(rect.size().into());
+            }
+            if let Some(rect) = viewport.outer_rect {
+                window.position = Some(rect.min.into());
+            }
+        });
+    }
+
+    fn draw_track_info(&self, ui: &mut egui::Ui, area: egui::Rect) {
+        let tags = &self.track_tags;
+        let title = tags
+            .title
+            .clone()
+            .or_else(|| self.display_title())
+            .unwrap_or_default();
+
+        ui.allocate_new_ui(egui::UiBuilder::new().max_rect(area), |ui| {
+            ui.vertical_centered(|ui| {
+                ui.add_space((area.height() / 2.0 - 80.0).max(0.0));
+
+                ui.add(egui::Label::new(
+                    egui::RichText::new(title).size(32.0).color(self.theme.text),
+                ));
+
+                if let Some(artist) = &tags.artist {
+                    ui.add_space(8.0);
+                    ui.add(egui::Label::new(
+                        egui::RichText::new(artist)
+                            .size(20.0)
+                            .color(self.theme.weak_text),
+                    ));
+                }
+
+                let album = match (&tags.album, &tags.year) {
+                    (Some(album), Some(year)) => Some(format!("{} ({})", album, year)),
+                    (Some(album), None) => Some(album.clone()),
+                    (None, Some(year)) => Some(year.clone()),
+                    (None, None) => None,
+                };
+                if let Some(album) = album {
+                    ui.add_space(8.0);
+                    ui.add(egui::Label::new(
+                        egui::RichText::new(album)
+                            .size(16.0)
+                            .color(self.theme.faint_text),
+                    ));
+                }
+
+                if let Some(track) = &tags.track {
+                    ui.add_space(4.0);
+                    ui.add(egui::Label::new(
+                        egui::RichText::new(tr!("track-number", track = track))
+                            .size(14.0)
+                            .color(self.theme.faint_text),
+                    ));
+                }
+            });
+        });
+    }
+
+    fn seek_to(&mut self, target_ms: i64) {
+        let target_ms = target_ms.min(self.duration_ms()).max(0);
+        if self
+            .live
+            .as_ref()
+            .is_some_and(|live| live.timeshift.is_none())
+        {
+            // What has passed of a live stream is gone without the timeshift buffer
+            if target_ms > self.file_time_ms() {
+                self.skip_live(target_ms);
+            } else {
+                self.show_osd(tr!("osd-live-no-timeshift"), egui::Color32::WHITE);
+            }
+            return;
+        }
+        self.scrub_preview_ms = None;
+        // Seeking into a segment on purpose shouldn't skip it
+        self.skip_segment_entered = self.skip_segment_at(target_ms);
+
+        let located = self
+            .timeline
+            .as_ref()
+            .map(|playback| playback.timeline.locate(target_ms));
+        match located {
+            Some((part, file_ms)) => {
+                if let Err(e) = self.open_timeline_part(part, file_ms) {
+                    self.show_error(&tr!("error-open-linked-segment"), e);
+                }
+            }
+            None => self.seek_media(target_ms),
+        }
+
+        self.commands.emit(commands::PlayerEvent::PlaybackChanged);
+    }
+
+    /// Shows the keyframe nearest before `target_ms` while the seek bar is dragged. Timelines
+    /// and live streams, where a seek may open another file or wait for the stream, seek
+    /// as usual instead.
+    fn preview_seek(&mut self, ctx: &egui::Context, target_ms: i64) {
+        let target_ms = target_ms.min(self.duration_ms()).max(0);
+        if self.timeline.is_some() || self.live.is_some() || self.video.is_none() {
+            self.seek_to(target_ms);
+            return;
+        }
+        if self.scrub_preview_ms == Some(target_ms) {
+            return;
+        }
+        self.scrub_preview_ms = Some(target_ms);
+
+        if let Some(video) = &mut self.video {
+            if let Err(e) = video.seek_keyframe(target_ms) {
+                eprintln!("Seek error: {}", e);
+                return;
+            }
+            // Decoders may need a few packets before the keyframe comes out
+            for _ in 0..30 {
+                match video.next_frame() {
+                    Some(Ok(frame)) => {
+                        self.video_textures.upload(ctx, frame);
+                        self.scopes.invalidate();
+                        break;
+                    }
+                    Some(Err(_)) => {}
+                    None => break,
+                }
+            }
+        }
+
+        if self.settings.audible_scrubbing {
+            if let Some(audio) = &self.audio {
+                audio.scrub(target_ms);
+            }
+        }
+    }
+
+    /// Seeks the open file, without mapping through the ordered edition.
+    fn seek_media(&mut self, target_ms: i64) {
+        if let Some(video) = &mut self.video {
+            if let Err(e) = video.seek(target_ms) {
+                eprintln!("Seek error: {}", e);
+            }
+        }
+
+        if let Some(audio) = &self.audio {
+            if self.scrubbing {
+                audio.scrub(target_ms);
+            } else {
+                audio.seek(target_ms);
+            }
+        }
+
+        if let Some(comparison) = &mut self.comparison {
+            if let Err(e) = comparison.video.seek(target_ms) {
+                eprintln!("Seek error: {}", e);
+            }
+            comparison.frame_ms = None;
+        }
+
+        if let Some(wall) = &mut self.video_wall {
+            for tile in &mut wall.tiles {
+                if let Err(e) = tile.video.seek(target_ms) {
+                    eprintln!("Seek error: {}", e);
+                }
+                tile.frame_ms = None;
+            }
+        }
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        // A picture has nothing to play; resuming would only move on to the next file
+        let paused = paused || self.still_image;
+        let resumed = self.paused && !paused;
+        self.paused = paused;
+
+        // Without the timeshift buffer, a paused live stream resumes at the live edge
+        let live_without_timeshift = self
+            .live
+            .as_ref()
+            .is_some_and(|live| live.timeshift.is_none());
+        if resumed && live_without_timeshift {
+            if let Some(edge_ms) = self.live_edge_ms() {
+                self.skip_live(edge_ms - self.settings.live_latency_ms as i64);
+            }
+        }
+
+        if let Some(audio) = &self.audio {
+            if self.paused {
+                audio.pause();
+            } else {
+                audio.play();
+            }
+        }
+
+        self.commands.emit(commands::PlayerEvent::PlaybackChanged);
+    }
+
+    fn apply_volume(&self) {
+        if let Some(audio) = &self.audio {
+            audio.set_volume(if self.muted { 0.0 } else { self.volume });
+        }
+    }
+
+    fn apply_night_mode(&self) {
+        if let Some(audio) = &self.audio {
+            audio.set_compression(if self.settings.night_mode {
+                self.settings.night_mode_strength
+            } else {
+                0.0
+            });
+        }
+    }
+
+    fn change_volume(&mut self, delta: f32) {
+        self.volume = (self.volume + delta).clamp(0.0, MAX_VOLUME);
+        self.muted = false;
+        self.apply_volume();
+    }
+
+    fn volume_color(&self) -> egui::Color32 {
+        if self.volume > 1.0 {
+            egui::Color32::from_rgb(255, 170, 60)
+        } else {
+            self.theme.accent
+        }
+    }
+
+    /// Applies `playback_speed` to the audio output and the frame pacing, and sets up
+    /// motion interpolation when slowed down with it enabled.
+    fn apply_playback_speed(&mut self) {
+        if let Some(audio) = &self.audio {
+            let speed = self.effective_speed();
+            audio.set_speed(speed as f32);
+            // Resampling for the speed moves the pitch with it, which keep_pitch undoes
+            let shift = 2f64.powf(self.pitch_semitones as f64 / 12.0);
+            let correction = if self.settings.keep_pitch {
+                1.0 / speed
+            } else {
+                1.0
+            };
+            audio.set_pitch((shift * correction) as f32);
+        }
+
+        if let Some(video) = &mut self.video {
+            let slow_motion = (self.settings.interpolate_slow_motion && self.playback_speed < 1.0)
+                .then_some(self.playback_speed);
+            if let Err(e) = video.set_slow_motion(slow_motion) {
+                eprintln!("Error setting up motion interpolation: {}", e);
+            }
+            self.frame_interval = 1.0 / video.get_frame_rate();
+        }
+    }
+
+    /// Moves `steps` entries through `PLAYBACK_SPEEDS`.
+    fn change_speed(&mut self, steps: isize) {
+        let current = PLAYBACK_SPEEDS
+            .iter()
+            .position(|speed| *speed >= self.playback_speed)
+            .unwrap_or(PLAYBACK_SPEEDS.len() - 1);
+        let index = current
+            .saturating_add_signed(steps)
+            .min(PLAYBACK_SPEEDS.len() - 1);
+        self.set_speed(PLAYBACK_SPEEDS[index]);
+    }
+
+    fn set_speed(&mut self, speed: f64) {
+        self.playback_speed = speed;
+        self.apply_playback_speed();
+    }
+
+    fn set_pitch(&mut self, semitones: i32) {
+        self.pitch_semitones = semitones.clamp(-MAX_PITCH_SEMITONES, MAX_PITCH_SEMITONES);
+        self.apply_playback_speed();
+        self.show_osd(
+            tr!(
+                "osd-pitch",
+                semitones = format!("{:+}", self.pitch_semitones)
+            ),
+            egui::Color32::WHITE,
+        );
+    }
+
+    fn set_audio_delay(&mut self, delay_ms: i64) {
+        self.audio_delay_ms = delay_ms.clamp(-MAX_AUDIO_DELAY_MS, MAX_AUDIO_DELAY_MS);
+        self.apply_audio_delay();
+        self.show_osd(
+            tr!("osd-audio-delay", ms = self.audio_delay_ms),
+            egui::Color32::WHITE,
+        );
+    }
+
+    fn apply_audio_delay(&mut self) {
+        if let Some(audio) = &mut self.audio {
+            audio.set_delay(self.audio_delay_ms);
+            self.av_sync.reset();
+        }
+    }
+
+    fn handle_gesture(&mut self, gesture: touch::Gesture, video_area: egui::Rect) {
+        match gesture {
+            touch::Gesture::Tap => self.show_controls = !self.show_controls,
+            touch::Gesture::DoubleTap(pos) => {
+                let offset_ms = if pos.x < video_area.center().x {
+                    -10000
+                } else {
+                    10000
+                };
+                self.perform_action(Action::SeekRelative(offset_ms));
+            }
+            // Dragging turns the 360° view, so only the right half of a flat picture
+            // takes volume swipes
+            touch::Gesture::Swipe { start, delta_y } => {
+                if start.x >= video_area.center().x && self.spherical_view.is_none() {
+                    self.change_volume(-delta_y / video_area.height());
+                }
+            }
+            touch::Gesture::Pinch(zoom_delta) => {
+                if let Some(view) = &mut self.spherical_view {
+                    view.pinch(zoom_delta);
+                } else if self.video.is_some()
+                    && self.comparison.is_none()
+                    && self.video_wall.is_none()
+                {
+                    self.video_zoom = (self.video_zoom * zoom_delta).clamp(1.0, MAX_ZOOM);
+                    self.show_osd(
+                        tr!("osd-zoom", percent = (self.video_zoom * 100.0).round()),
+                        egui::Color32::WHITE,
+                    );
+                }
+            }
+        }
+    }
+
+    fn current_properties(&self) -> properties::Properties {
+        let tracks = self
+            .media_info
+            .as_ref()
+            .map(|info| properties::Tracks {
+                video: info.video_streams.iter().map(|s| s.index).collect(),
+                audio: info.audio_streams.iter().map(|s| s.index).collect(),
+                subtitles: info.subtitle_streams.iter().map(|s| s.index).collect(),
+            })
+            .unwrap_or_default();
+        properties::Properties {
+            position_ms: self.current_time_ms(),
+            duration_ms: self.duration_ms(),
+            paused: self.paused,
+            volume: self.volume,
+            muted: self.muted,
+            speed: self.playback_speed,
+            tracks,
+        }
+    }
+
+    /// Tells the property observers what changed this frame, and shows volume and speed
+    /// changes on the OSD.
+    fn update_properties(&mut self, ctx: &egui::Context) {
+        self.properties.update(self.current_properties());
+
+        let changes: Vec<_> = self.osd_properties.try_iter().collect();
+        for change in changes {
+            match change {
+                properties::PropertyChange::Volume { volume, muted } => {
+                    self.show_volume_osd(volume, muted);
+                    ctx.request_repaint();
+                }
+                properties::PropertyChange::Speed(speed) => {
+                    self.show_speed_osd(speed);
+                    ctx.request_repaint();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn show_volume_osd(&mut self, volume: f32, muted: bool) {
+        let text = if muted {
+            tr!("osd-muted")
+        } else {
+            tr!("osd-volume", percent = (volume * 100.0).round())
+        };
+        self.show_osd(text, self.volume_color());
+    }
+
+    fn show_speed_osd(&mut self, speed: f64) {
+        let interpolated = self.settings.interpolate_slow_motion && speed < 1.0;
+        let text = if interpolated && self.video.is_some() {
+            tr!("osd-speed-interpolated", speed = format!("{:.2}", speed))
+        } else {
+            tr!("osd-speed", speed = format!("{:.2}", speed))
+        };
+        self.show_osd(text, egui::Color32::WHITE);
+    }
+
+    /// Saves window captures requested by `ScreenshotAsDisplayed` and reports finished saves.
+    fn update_screenshots(&mut self, ctx: &egui::Context) {
+        let captured = ctx.input(|i| {
+            i.events.iter().find_map(|event| match event {
+                egui::Event::Screenshot { image, .. } => Some(image.clone()),
+                _ => None,
+            })
+        });
+        if let (Some(image), Some(area)) = (captured, self.screenshot_area.take()) {
+            let image = Arc::new(image.region(&area, Some(ctx.pixels_per_point())));
+            let media_path = self.current_filename.clone().unwrap_or_default();
+            let path = screenshot::output_path(
+                &media_path,
+                self.current_time_ms(),
+                &self.settings.screenshot,
+            );
+            screenshot::save(
+                image,
+                path,
+                &self.settings.screenshot,
+                self.screenshot_sender.clone(),
+            );
+        }
+
+        while let Ok(result) = self.screenshot_receiver.try_recv() {
+            match result {
+                Ok(path) => {
+                    println!("Saved screenshot to {}", path.display());
+                    let name = path
+                        .file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .unwrap_or_default();
+                    self.show_osd(
+                        tr!("osd-screenshot-saved", file = name),
+                        egui::Color32::WHITE,
+                    );
+                }
+                Err(e) => {
+                    eprintln!("Error saving screenshot: {}", e);
+                    self.show_osd(
+                        tr!("osd-screenshot-failed", error = e),
+                        egui::Color32::LIGHT_RED,
+                    );
+                }
+            }
+        }
+    }
+
+    fn show_osd(&mut self, text: String, color: egui::Color32) {
+        self.osd = Some(Osd {
+            text,
+            color,
+            shown_at: Instant::now(),
+        });
+    }
+
+    fn draw_osd(&mut self, ctx: &egui::Context, painter: &egui::Painter, area: egui::Rect) {
+        let osd = match &self.osd {
+            Some(osd) if osd.shown_at.elapsed() < OSD_DURATION => osd,
+            Some(_) => {
+                self.osd = None;
+                return;
+            }
+            None => return,
+        };
+
+        let galley = painter.layout_no_wrap(
+            osd.text.clone(),
+            egui::FontId::proportional(20.0),
+            osd.color,
+        );
+        let pos = area.min + egui::vec2(24.0, 24.0);
+
+        let background = galley.rect.translate(pos.to_vec2()).expand(8.0);
+        painter.rect_filled(
+            background,
+            egui::Rounding::same(4.0),
+            egui::Color32::from_black_alpha(160),
+        );
+        painter.rect_filled(
+            background.with_max_x(background.left() + 3.0),
+            egui::Rounding {
+                nw: 4.0,
+                sw: 4.0,
+                ..Default::default()
+            },
+            self.theme.accent,
+        );
+        painter.galley(pos, galley, osd.color);
+
+        ctx.request_repaint_after(OSD_DURATION.saturating_sub(osd.shown_at.elapsed()));
+    }
+
+    /// Shows the coordinates and values of the frame pixel under `pointer` at the
+    /// video's native resolution.
+    fn draw_pixel_inspector(
+        &self,
+        painter: &egui::Painter,
+        area: egui::Rect,
+        video_rect: egui::Rect,
+        pointer: egui::Pos2,
+    ) {
+        let Some(image) = self.video_textures.current_image() else {
+            return;
+        };
+        let [width, height] = image.size;
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        let relative = (pointer - video_rect.min) / video_rect.size();
+        let x = ((relative.x * width as f32) as usize).min(width - 1);
+        let y = ((relative.y * height as f32) as usize).min(height - 1);
+        let color = image.pixels[y * width + x];
+
+        let mut text = format!(
+            "X {}  Y {}\nRGB {} {} {}  #{:02X}{:02X}{:02X}",
+            x,
+            y,
+            color.r(),
+            color.g(),
+            color.b(),
+            color.r(),
+            color.g(),
+            color.b()
+        );
+        if let Some(source) = self
+            .video
+            .as_ref()
+            .and_then(|video| video.source_pixel(x, y))
+        {
+            let (labels, values): (Vec<&str>, Vec<String>) = source
+                .components
+                .iter()
+                .map(|(label, value)| (*label, value.to_string()))
+                .unzip();
+            text.push_str(&format!(
+                "\n{} {}  ({}, {}-bit)",
+                labels.join(""),
+                values.join(" "),
+                source.format,
+                source.bit_depth
+            ));
+        }
+
+        let galley =
+            painter.layout_no_wrap(text, egui::FontId::monospace(14.0), egui::Color32::WHITE);
+        let swatch_size = galley.rect.height();
+        let box_size = egui::vec2(swatch_size + 8.0 + galley.rect.width(), swatch_size);
+
+        // Keep the box next to the cursor, flipping sides near the edges of the video area
+        let mut pos = pointer + egui::vec2(20.0, 20.0);
+        if pos.x + box_size.x + 8.0 > area.right() {
+            pos.x = pointer.x - 20.0 - box_size.x;
+        }
+        if pos.y + box_size.y + 8.0 > area.bottom() {
+            pos.y = pointer.y - 20.0 - box_size.y;
+        }
+
+        painter.rect_filled(
+            egui::Rect::from_min_size(pos, box_size).expand(8.0),
+            egui::Rounding::same(4.0),
+            egui::Color32::from_black_alpha(200),
+        );
+        painter.rect(
+            egui::Rect::from_min_size(pos, egui::vec2(swatch_size, swatch_size)),
+            egui::Rounding::same(2.0),
+            egui::Color32::from_rgb(color.r(), color.g(), color.b()),
+            egui::Stroke::new(1.0, egui::Color32::GRAY),
+        );
+        painter.galley(
+            pos + egui::vec2(swatch_size + 8.0, 0.0),
+            galley,
+            egui::Color32::WHITE,
+        );
+    }
+
+    fn perform_action(&mut self, action: Action) {
+        match action {
+            Action::TogglePause => self.set_paused(!self.paused),
+            Action::Play => self.set_paused(false),
+            Action::Pause => self.set_paused(true),
+            Action::ToggleFullscreen => {
+                self.is_fullscreen = !self.is_fullscreen;
+                self.show_controls = !self.is_fullscreen || self.video_window;
+            }
+            Action::ExitFullscreen => {
+                self.is_fullscreen = false;
+                self.show_controls = true;
+            }
+            Action::ToggleMute => {
+                self.muted = !self.muted;
+                self.apply_volume();
+            }
+            Action::ResyncAudio => self.resync_audio(),
+            Action::ChangeAudioDelay(delta_ms) => {
+                self.set_audio_delay(self.audio_delay_ms + delta_ms)
+            }
+            Action::ResetAudioDelay => self.set_audio_delay(0),
+            Action::MeasureSync => self.show_sync_analysis = !self.show_sync_analysis,
+            Action::VolumeUp => self.change_volume(0.05),
+            Action::VolumeDown => self.change_volume(-0.05),
+            Action::SpeedUp => self.change_speed(1),
+            Action::SpeedDown => self.change_speed(-1),
+            Action::PitchUp => self.set_pitch(self.pitch_semitones + 1),
+            Action::PitchDown => self.set_pitch(self.pitch_semitones - 1),
+            Action::ResetPitch => self.set_pitch(0),
+            Action::CycleAbLoop => self.cycle_ab_loop(),
+            Action::ResetSpeed => self.set_speed(1.0),
+            Action::ToggleSlowMotionInterpolation => {
+                self.settings.interpolate_slow_motion = !self.settings.interpolate_slow_motion;
+                self.apply_playback_speed();
+                self.show_osd(
+                    if self.settings.interpolate_slow_motion {
+                        tr!("osd-interpolation-on")
+                    } else {
+                        tr!("osd-interpolation-off")
+                    },
+                    egui::Color32::WHITE,
+                );
+            }
+            Action::SeekRelative(offset_ms) => self.seek_to(self.current_time_ms() + offset_ms),
+            Action::SeekPercent(percent) => self.seek_to(self.duration_ms() * percent as i64 / 100),
+            Action::SeekTo(target_ms) => self.seek_to(target_ms),
+            Action::SeekStart => self.seek_to(0),
+            Action::SeekEnd => self.seek_to(self.duration_ms()),
+            Action::PlaylistNext => {
+                self.play_next();
+            }
+            Action::PlaylistPrevious => self.play_previous(),
+            Action::OpenFile => self.open_file_dialog(),
+            Action::OpenFolder => self.open_folder_dialog(),
+            Action::JoinFiles => self.join_files_dialog(),
+            Action::OpenUrl => {
+                // Start from the clipboard when it already holds a link
+                let input = Self::clipboard_text()
+                    .filter(|text| Self::validate_url(text).is_ok())
+                    .unwrap_or_default();
+                let http = http::url_options(&input).unwrap_or_default();
+                self.url_dialog = Some(OpenUrlDialog {
+                    input,
+                    error: None,
+                    focus_requested: false,
+                    http,
+                });
+            }
+            Action::ExportPlaylist => self.export_playlist(),
+            Action::ToggleShuffle => {
+                self.settings.shuffle = !self.settings.shuffle;
+                self.playlist.set_shuffle(self.settings.shuffle);
+            }
+            Action::ToggleStopAfterCurrent => {
+                self.stop_after_current = !self.stop_after_current;
+                self.show_osd(
+                    if self.stop_after_current {
+                        tr!("osd-stop-after-file-on")
+                    } else {
+                        tr!("osd-stop-after-file-off")
+                    },
+                    egui::Color32::WHITE,
+                );
+            }
+            Action::CycleSleepTimer => {
+                self.set_sleep_timer(sleep_timer::SleepTimer::cycle(self.sleep_timer))
+            }
+            Action::ToggleMediaInfo => self.show_media_info = !self.show_media_info,
+            Action::ToggleAnalysis => self.show_analysis = !self.show_analysis,
+            Action::VerifyFile => self.show_integrity = !self.show_integrity,
+            Action::ToggleCutlist => self.show_cutlist = !self.show_cutlist,
+            Action::ToggleAnnotations => {
+                self.annotation_panel = match self.annotation_panel.take() {
+                    Some(_) => None,
+                    None => self.open_annotation_panel(),
+                }
+            }
+            Action::AddAnnotation => {
+                let current_ms = self.current_time_ms();
+                if self.annotation_panel.is_none() {
+                    self.annotation_panel = self.open_annotation_panel();
+                }
+                if let Some(panel) = &mut self.annotation_panel {
+                    panel.draft_ms = Some(current_ms);
+                    panel.focus_requested = false;
+                }
+            }
+            Action::MarkIn | Action::MarkOut | Action::AddMarker if self.has_media() => {
+                let time_ms = self.current_time_ms();
+                let text = match action {
+                    Action::MarkIn => {
+                        self.cutlist.in_ms = Some(time_ms);
+                        tr!("osd-mark-in", time = Self::format_time(time_ms))
+                    }
+                    Action::MarkOut => {
+                        self.cutlist.out_ms = Some(time_ms);
+                        tr!("osd-mark-out", time = Self::format_time(time_ms))
+                    }
+                    _ => {
+                        let name = tr!(
+                            "cutlist-marker-name",
+                            number = self.cutlist.markers.len() + 1
+                        );
+                        self.cutlist.add_marker(name.clone(), time_ms);
+                        tr!("osd-marker-added", name = name)
+                    }
+                };
+                self.show_osd(text, egui::Color32::WHITE);
+            }
+            Action::MarkIn | Action::MarkOut | Action::AddMarker => {}
+            Action::GoToTime => {
+                if self.has_media() {
+                    self.goto_dialog = Some(GoToTimeDialog {
+                        input: String::new(),
+                        error: None,
+                        focus_requested: false,
+                    });
+                }
+            }
+            Action::SelectVideoStream(stream_index) => self.select_video_stream(stream_index),
+            Action::SelectSubtitleStream(stream_index) => {
+                if let Some(video) = &mut self.video {
+                    if let Err(e) = video.select_subtitle_stream(stream_index) {
+                        eprintln!("Error selecting subtitle stream: {}", e);
+                    }
+                }
+            }
+            Action::Screenshot => {
+                let image = self.video_textures.current_image();
+                match (image, self.current_filename.clone()) {
+                    (Some(image), Some(media_path)) => {
+                        let path = screenshot::output_path(
+                            &media_path,
+                            self.current_time_ms(),
+                            &self.settings.screenshot,
+                        );
+                        screenshot::save(
+                            image,
+                            path,
+                            &self.settings.screenshot,
+                            self.screenshot_sender.clone(),
+                        );
+                    }
+                    _ => self.show_osd(tr!("osd-no-frame-to-save"), egui::Color32::LIGHT_RED),
+                }
+            }
+            Action::ScreenshotAsDisplayed => {
+                if self.video.is_some() {
+                    self.screenshot_requested = true;
+                }
+            }
+            Action::ExportFrames => {
+                if self.video.is_some() {
+                    self.export_dialog = Some(ExportDialog {
+                        start: Self::format_time(self.current_time_ms()),
+                        end: Self::format_time(self.duration_ms()),
+                        fps: String::new(),
+                        format: export::ImageFormat::Png,
+                        output_dir: None,
+                        error: None,
+                        job: None,
+                        progress: 0.0,
+                        frames: 0,
+                    });
+                }
+            }
+            Action::Transcode => {
+                if self.has_media() && self.transcode_dialog.is_none() {
+                    let video_codec = transcode::VideoCodec::H264;
+                    self.transcode_dialog = Some(TranscodeDialog {
+                        start: Self::format_time(0),
+                        end: Self::format_time(self.duration_ms()),
+                        video_codec,
+                        use_crf: true,
+                        crf: video_codec.default_crf(),
+                        video_bitrate: "4000".to_string(),
+                        height: None,
+                        audio_codec: transcode::AudioCodec::Aac,
+                        audio_bitrate: 160,
+                        output: None,
+                        error: None,
+                        job: None,
+                        progress: 0.0,
+                        finished: false,
+                    });
+                }
+            }
+            Action::ToggleRecording => self.toggle_recording(),
+            Action::Remux => {
+                if self.has_media() && self.remux_dialog.is_none() {
+                    self.remux_dialog = Some(RemuxDialog {
+                        container: remux::Container::Mkv,
+                        streams: self
+                            .remux_streams()
+                            .into_iter()
+                            .map(|(index, label)| (index, label, true))
+                            .collect(),
+                        error: None,
+                        job: None,
+                        progress: 0.0,
+                        saved_to: None,
+                    });
+                }
+            }
+            Action::ToggleSphericalView => {
+                self.spherical_view = match self.spherical_view {
+                    Some(_) => None,
+                    None => Some(SphericalView::new()),
+                };
+            }
+            Action::ToggleVideoWindow => {
+                // Fullscreen belongs to the window the picture leaves
+                if self.is_fullscreen {
+                    self.perform_action(Action::ExitFullscreen);
+                }
+                self.video_window = !self.video_window;
+            }
+            Action::ScaleWindow(percent) => {
+                if self.video.is_some() {
+                    self.perform_action(Action::ExitFullscreen);
+                    self.video_zoom = 1.0;
+                    self.video_pan = egui::Vec2::ZERO;
+                    self.window_scale = Some(percent);
+                }
+            }
+            Action::SetDownmix(downmix) => self.set_downmix(downmix),
+            Action::SelectSecondaryAudio(stream_index) => self.set_secondary_audio(stream_index),
+            Action::SetScaling(scaling) => {
+                self.settings.scaling = scaling;
+                self.apply_video_filters();
+            }
+            Action::SetDisplayMode(mode) => {
+                self.settings.display_mode = mode;
+                self.show_osd(mode.name(), egui::Color32::WHITE);
+            }
+            Action::CycleDisplayMode => {
+                self.perform_action(Action::SetDisplayMode(self.settings.display_mode.next()));
+            }
+            Action::SetSampler(sampler) => {
+                self.settings.sampler = sampler;
+                let options = sampler.texture_options();
+                self.video_textures.set_options(options);
+                if let Some(comparison) = &mut self.comparison {
+                    comparison.textures.set_options(options);
+                }
+                if let Some(wall) = &mut self.video_wall {
+                    for tile in &mut wall.tiles {
+                        tile.textures.set_options(options);
+                    }
+                }
+            }
+            Action::SetAudioBackend(backend) => self.set_audio_backend(backend),
+            Action::ToggleNightMode => {
+                self.settings.night_mode = !self.settings.night_mode;
+                self.apply_night_mode();
+                self.show_osd(
+                    if self.settings.night_mode {
+                        tr!("osd-night-mode-on")
+                    } else {
+                        tr!("osd-night-mode-off")
+                    },
+                    egui::Color32::WHITE,
+                );
+            }
+            Action::ToggleFilmstrip => self.settings.show_filmstrip = !self.settings.show_filmstrip,
+            Action::TogglePixelInspector => self.show_pixel_inspector = !self.show_pixel_inspector,
+            Action::ToggleScope(scope) => self.scopes.toggle(scope),
+            Action::ToggleLoudnessMeter => self.show_loudness = !self.show_loudness,
+            Action::ToggleLevelMeters => self.show_levels = !self.show_levels,
+            Action::OpenComparison => self.open_comparison_dialog(),
+            Action::CloseComparison => self.comparison = None,
+            Action::OpenVideoWall => self.video_wall_dialog(),
+            Action::CloseVideoWall => self.close_video_wall(),
+            Action::ToggleWipe => {
+                if let Some(comparison) = &mut self.comparison {
+                    comparison.wipe = !comparison.wipe;
+                }
+            }
+            Action::LoadShader => self.load_shader_dialog(),
+            Action::SelectShader(index) => self.select_shader(index),
+            Action::ApplyPreset(index) => {
+                let preset = index.and_then(|index| self.settings.presets.get(index).cloned());
+                let message = match &preset {
+                    Some(preset) => tr!("osd-preset", name = preset.name.clone()),
+                    None => tr!("osd-preset-off"),
+                };
+                self.switch_preset(preset);
+                self.show_osd(message, egui::Color32::WHITE);
+            }
+            Action::ToggleDenoise => {
+                self.settings.denoise = !self.settings.denoise;
+                self.apply_video_filters();
+            }
+            Action::ToggleDeband => {
+                self.settings.deband = !self.settings.deband;
+                self.apply_video_filters();
+            }
+            Action::NextChapter => {
+                // A little slack so that a seek that landed just before a chapter
+                // start doesn't jump to the same chapter again
+                let current_ms = self.current_time_ms() + 500;
+                if let Some(index) = self
+                    .chapters()
+                    .iter()
+                    .position(|chapter| chapter.start_time_ms > current_ms)
+                {
+                    self.seek_to_chapter(index);
+                }
+            }
+            Action::PreviousChapter => {
+                if let Some(index) = self.current_chapter() {
+                    // Like a CD player: restart the chapter unless it has only just begun
+                    let elapsed_ms = self.current_time_ms() - self.chapters()[index].start_time_ms;
+                    if elapsed_ms > 3000 || index == 0 {
+                        self.seek_to_chapter(index);
+                    } else {
+                        self.seek_to_chapter(index - 1);
+                    }
+                }
+            }
+            Action::LoadChapters => self.load_chapters_dialog(),
+            Action::DetectScenes => self.show_scene_detection = !self.show_scene_detection,
+            Action::RecognizeSubtitles => self.show_subtitle_ocr = !self.show_subtitle_ocr,
+            Action::SkipSegment => self.skip_current_segment(),
+            Action::EditSkipSegments => self.open_skip_segment_dialog(),
+            Action::ToggleCaptions => {
+                self.show_captions = !self.show_captions;
+                let text = if self.show_captions {
+                    tr!("osd-captions-on")
+                } else {
+                    tr!("osd-captions-off")
+                };
+                self.show_osd(text, egui::Color32::WHITE);
+            }
+            Action::MoveSubtitles(step) => {
+                let style = &mut self.settings.subtitle_style;
+                style.bottom_margin = (style.bottom_margin + step as f32 / 100.0).clamp(0.0, 0.9);
+                let text = tr!(
+                    "osd-subtitle-position",
+                    percent = format!("{:.0}", style.bottom_margin * 100.0)
+                );
+                self.show_osd(text, egui::Color32::WHITE);
+            }
+            Action::SearchSubtitles => {
+                if self.has_media() {
+                    self.subtitle_search = Some(SubtitleSearchDialog {
+                        results: Vec::new(),
+                        status: None,
+                        pending: None,
+                    });
+                }
+            }
+        }
+    }
+
+    fn select_video_stream(&mut self, stream_index: usize) {
+        if let Some(video) = &mut self.video {
+            match video.select_stream(stream_index) {
+                Ok(()) => self.frame_interval = 1.0 / video.get_frame_rate(),
+                Err(e) => eprintln!("Error selecting video stream: {}", e),
+            }
+        }
+
+        self.spherical_view = self.is_equirectangular().then(SphericalView::new);
+    }
+
+    /// True when the playing video stream carries equirectangular spherical metadata.
+    fn is_equirectangular(&self) -> bool {
+        let stream_index = match &self.video {
+            Some(video) => video.stream_index(),
+            None => return false,
+        };
+
+        self.media_info
+            .iter()
+            .flat_map(|info| info.video_streams.iter())
+            .any(|stream| stream.index == stream_index && stream.equirectangular)
+    }
+
+    /// Video streams that can be switched between, skipping embedded cover art.
+    fn selectable_video_streams(&self) -> Vec<(usize, String)> {
+        self.media_info
+            .iter()
+            .flat_map(|info| info.video_streams.iter())
+            .filter(|stream| !stream.is_attached_pic())
+            .map(|stream| {
+                let description = format!(
+                    "{}x{} {}",
+                    Self::format_optional_u32(stream.width),
+                    Self::format_optional_u32(stream.height),
+                    stream.codec_name
+                );
+                let mut label = tr!(
+                    "stream-label",
+                    index = stream.index,
+                    description = description
+                );
+                for key in ["title", "language"] {
+                    if let Some(value) = stream.metadata.get(key) {
+                        label.push_str(&format!(" [{}]", value));
+                    }
+                }
+                (stream.index, label)
+            })
+            .collect()
+    }
+
+    /// PGS and VobSub streams, which are rendered from the video's own demuxer.
+    fn bitmap_subtitle_streams(&self) -> Vec<(usize, String)> {
+        self.media_info
+            .iter()
+            .flat_map(|info| info.subtitle_streams.iter())
+            .filter(|stream| matches!(stream.codec_name.as_str(), "pgssub" | "dvdsub"))
+            .map(|stream| {
+                let mut label = tr!(
+                    "stream-label",
+                    index = stream.index,
+                    description = stream.codec_name
+                );
+                for key in ["title", "language"] {
+                    if let Some(value) = stream.metadata.get(key) {
+                        label.push_str(&format!(" [{}]", value));
+                    }
+                }
+                (stream.index, label)
+            })
+            .collect()
+    }
+
+    /// Video and audio streams that the bitrate graph can show.
+    fn bitrate_graph_streams(&self) -> Vec<(usize, String)> {
+        let Some(info) = &self.media_info else {
+            return Vec::new();
+        };
+
+        let video = info.video_streams.iter().map(|stream| {
+            (
+                stream.index,
+                tr!("stream-kind-video"),
+                &stream.codec_name,
+                &stream.metadata,
+            )
+        });
+        let audio = info.audio_streams.iter().map(|stream| {
+            (
+                stream.index,
+                tr!("stream-kind-audio"),
+                &stream.codec_name,
+                &stream.metadata,
+            )
+        });
+
+        let mut streams: Vec<(usize, String)> = video
+            .chain(audio)
+            .map(|(index, kind, codec_name, metadata)| {
+                let mut label = tr!(
+                    "stream-label",
+                    index = index,
+                    description = format!("{} ({})", kind, codec_name)
+                );
+                if let Some(language) = metadata.get("language") {
+                    label.push_str(&format!(" [{}]", language));
+                }
+                (index, label)
+            })
+            .collect();
+        streams.sort_by_key(|(index, _)| *index);
+        streams
+    }
+
+    /// Per-second bitrate of the stream picked in the analysis window, once scanned.
+    fn bitrate_graph_seconds(&self) -> Option<&[u64]> {
+        self.bitrate_scan
+            .as_ref()?
+            .history()?
+            .stream(self.bitrate_graph_stream?)
+    }
+
+    fn show_video_menu(&mut self, ui: &mut egui::Ui) {
+        if let Some(scan) = &mut self.dvd_titles {
+            let scanning = scan.update();
+            ui.label(tr!("menu-dvd-titles"));
+
+            let current = dvd::title_for(scan.filename()).unwrap_or(1);
+            let mut selected = None;
+            for title in scan.titles() {
+                let label = tr!(
+                    "menu-dvd-title",
+                    number = title.number,
+                    duration = Self::format_time(title.duration_ms),
+                    chapters = title.chapters
+                );
+                if ui.radio(title.number == current, label).clicked() {
+                    selected = Some((scan.filename().to_string(), title.number));
+                }
+            }
+            if scanning {
+                ui.spinner();
+                ui.ctx().request_repaint();
+            }
+
+            if let Some((filename, number)) = selected {
+                dvd::set_title(&filename, number);
+                if let Err(e) = self.load_video(&filename) {
+                    self.show_error(&tr!("error-cannot-open", file = filename), e);
+                }
+                ui.close_menu();
+            }
+
+            ui.separator();
+        }
+
+        let video_streams = self.selectable_video_streams();
+        if video_streams.len() > 1 {
+            ui.label(tr!("menu-video-stream"));
+
+            let current_stream = self.video.as_ref().map(|video| video.stream_index());
+            for (index, label) in video_streams {
+                if ui.radio(current_stream == Some(index), label).clicked() {
+                    self.perform_action(Action::SelectVideoStream(index));
+                    ui.close_menu();
+                }
+            }
+
+            ui.separator();
+        }
+
+        let raw_stream = self.current_filename.clone().and_then(|filename| {
+            let hint = raw_stream::hint_for(&filename)?;
+            Some((filename, hint))
+        });
+        if let Some((filename, hint)) = raw_stream {
+            if ui.button(tr!("menu-raw-stream")).clicked() {
+                self.raw_stream_dialog = Some(RawStreamDialog {
+                    format: hint
+                        .format
+                        .or_else(|| raw_stream::format_for(&filename).map(str::to_string))
+                        .unwrap_or_else(|| raw_stream::FORMATS[0].0.to_string()),
+                    framerate: hint.framerate.unwrap_or(raw_stream::DEFAULT_FRAMERATE),
+                    filename,
+                });
+                ui.close_menu();
+            }
+            ui.separator();
+        }
+
+        let subtitle_streams = self.bitmap_subtitle_streams();
+        if !subtitle_streams.is_empty() {
+            ui.label(tr!("menu-image-subtitles"));
+
+            let current_stream = self
+                .video
+                .as_ref()
+                .and_then(|video| video.subtitle_stream_index());
+            if ui
+                .radio(current_stream.is_none(), tr!("menu-off"))
+                .clicked()
+            {
+                self.perform_action(Action::SelectSubtitleStream(None));
+                ui.close_menu();
+            }
+            for (index, label) in subtitle_streams {
+                if ui.radio(current_stream == Some(index), label).clicked() {
+                    self.perform_action(Action::SelectSubtitleStream(Some(index)));
+                    ui.close_menu();
+                }
+            }
+            if ui.button(tr!("menu-ocr-subtitles")).clicked() {
+                self.subtitle_ocr_stream = self.subtitle_ocr_stream.or(current_stream);
+                self.perform_action(Action::RecognizeSubtitles);
+                ui.close_menu();
+            }
+
+            ui.separator();
+        }
+
+        let has_captions = self
+            .video
+            .as_ref()
+            .is_some_and(|video| video.has_captions());
+        let mut show_captions = self.show_captions;
+        if ui
+            .add_enabled(
+                has_captions,
+                egui::Checkbox::new(&mut show_captions, tr!("menu-closed-captions")),
+            )
+            .on_disabled_hover_text(tr!("menu-no-closed-captions"))
+            .changed()
+        {
+            self.perform_action(Action::ToggleCaptions);
+        }
+
+        let mut show_filmstrip = self.settings.show_filmstrip;
+        if ui
+            .checkbox(&mut show_filmstrip, tr!("menu-filmstrip"))
+            .changed()
+        {
+            self.perform_action(Action::ToggleFilmstrip);
+        }
+
+        let mut show_pixel_inspector = self.show_pixel_inspector;
+        if ui
+            .checkbox(&mut show_pixel_inspector, tr!("menu-pixel-inspector"))
+            .changed()
+        {
+            self.perform_action(Action::TogglePixelInspector);
+        }
+
+        for scope in scopes::Scope::ALL {
+            let mut shown = self.scopes.is_shown(scope);
+            if ui.checkbox(&mut shown, scope.name()).changed() {
+                self.perform_action(Action::ToggleScope(scope));
+            }
+        }
+
+        let mut show_loudness = self.show_loudness;
+        if ui
+            .checkbox(&mut show_loudness, tr!("menu-loudness-meter"))
+            .changed()
+        {
+            self.perform_action(Action::ToggleLoudnessMeter);
+        }
+
+        let mut show_levels = self.show_levels;
+        if ui
+            .checkbox(&mut show_levels, tr!("menu-level-meters"))
+            .changed()
+        {
+            self.perform_action(Action::ToggleLevelMeters);
+        }
+
+        let mut spherical = self.spherical_view.is_some();
+        if ui
+            .checkbox(&mut spherical, tr!("menu-spherical-view"))
+            .changed()
+        {
+            self.perform_action(Action::ToggleSphericalView);
+        }
+
+        if let Some(view) = &mut self.spherical_view {
+            ui.add(
+                egui::Slider::new(&mut view.fov, 30.0..=120.0)
+                    .text(tr!("menu-field-of-view"))
+                    .suffix("°"),
+            );
+            if ui.button(tr!("menu-reset-view")).clicked() {
+                *view = SphericalView::new();
+            }
+        }
+
+        let mut video_window = self.video_window;
+        if ui
+            .checkbox(&mut video_window, tr!("menu-video-window"))
+            .on_hover_text(tr!("menu-video-window-hint"))
+            .changed()
+        {
+            ui.close_menu();
+            self.perform_action(Action::ToggleVideoWindow);
+        }
+
+        ui.horizontal(|ui| {
+            ui.label(tr!("menu-window-size"));
+            for percent in [50, 100, 200] {
+                if ui.button(format!("{}%", percent)).clicked() {
+                    ui.close_menu();
+                    self.perform_action(Action::ScaleWindow(percent));
+                }
+            }
+        })
+        .response
+        .on_hover_text(tr!("menu-window-size-hint"));
+
+        ui.separator();
+        ui.label(tr!("menu-screenshot"));
+
+        ui.horizontal(|ui| {
+            if ui.button(tr!("menu-screenshot-frame")).clicked() {
+                ui.close_menu();
+                self.perform_action(Action::Screenshot);
+            }
+            if ui
+                .button(tr!("menu-screenshot-displayed"))
+                .on_hover_text(tr!("menu-screenshot-displayed-hint"))
+                .clicked()
+            {
+                ui.close_menu();
+                self.perform_action(Action::ScreenshotAsDisplayed);
+            }
+        });
+        ui.collapsing(tr!("menu-screenshot-options"), |ui| {
+            let options = &mut self.settings.screenshot;
+            ui.horizontal(|ui| {
+                ui.label(tr!("menu-screenshot-folder"));
+                let folder = options
+                    .directory
+                    .clone()
+                    .unwrap_or_else(screenshot::default_directory);
+                ui.label(folder.display().to_string());
+            });
+            ui.horizontal(|ui| {
+                if ui.button(tr!("menu-screenshot-choose-folder")).clicked() {
+                    if let Some(dir) = rfd::FileDialog::new().pick_folder() {
+                        options.directory = Some(dir);
+                    }
+                }
+                if ui
+                    .add_enabled(
+                        options.directory.is_some(),
+                        egui::Button::new(tr!("button-reset")),
+                    )
+                    .clicked()
+                {
+                    options.directory = None;
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label(tr!("menu-screenshot-name"));
+                ui.add(egui::TextEdit::singleline(&mut options.template).desired_width(140.0));
+            })
+            .response
+            .on_hover_text(tr!("menu-screenshot-name-hint"));
+            ui.horizontal(|ui| {
+                ui.label(tr!("menu-screenshot-format"));
+                for format in screenshot::ScreenshotFormat::ALL {
+                    ui.radio_value(&mut options.format, format, format.name());
+                }
+            });
+            ui.add_enabled_ui(options.format == screenshot::ScreenshotFormat::Jpeg, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tr!("menu-screenshot-quality"));
+                    ui.add(egui::Slider::new(&mut options.jpeg_quality, 1..=100));
+                });
+            });
+        });
+
+        ui.separator();
+        ui.label(tr!("menu-filters"));
+
+        let mut denoise = self.settings.denoise;
+        if ui.checkbox(&mut denoise, tr!("menu-denoise")).changed() {
+            self.perform_action(Action::ToggleDenoise);
+        }
+
+        let mut deband = self.settings.deband;
+        if ui.checkbox(&mut deband, tr!("menu-deband")).changed() {
+            self.perform_action(Action::ToggleDeband);
+        }
+
+        ui.separator();
+        ui.label(tr!("menu-scaling"));
+
+        egui::Grid::new("scaling_options")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label(tr!("menu-color-conversion"))
+                    .on_hover_text(tr!("menu-color-conversion-hint"));
+                egui::ComboBox::from_id_salt("scaling_algorithm")
+                    .selected_text(self.settings.scaling.name())
+                    .show_ui(ui, |ui| {
+                        for scaling in video::ScalingAlgorithm::ALL {
+                            if ui
+                                .selectable_label(self.settings.scaling == scaling, scaling.name())
+                                .clicked()
+                            {
+                                self.perform_action(Action::SetScaling(scaling));
+                            }
+                        }
+                    });
+                ui.end_row();
+
+                ui.label(tr!("menu-display-sampling"))
+                    .on_hover_text(tr!("menu-display-sampling-hint"));
+                egui::ComboBox::from_id_salt("video_sampler")
+                    .selected_text(self.settings.sampler.name())
+                    .show_ui(ui, |ui| {
+                        for sampler in shaders::Sampler::ALL {
+                            if ui
+                                .selectable_label(self.settings.sampler == sampler, sampler.name())
+                                .clicked()
+                            {
+                                self.perform_action(Action::SetSampler(sampler));
+                            }
+                        }
+                    });
+                ui.end_row();
+
+                ui.label(tr!("menu-display-mode"))
+                    .on_hover_text(tr!("menu-display-mode-hint"));
+                egui::ComboBox::from_id_salt("video_display_mode")
+                    .selected_text(self.settings.display_mode.name())
+                    .show_ui(ui, |ui| {
+                        for mode in DisplayMode::ALL {
+                            if ui
+                                .selectable_label(self.settings.display_mode == mode, mode.name())
+                                .clicked()
+                            {
+                                self.perform_action(Action::SetDisplayMode(mode));
+                            }
+                        }
+                    });
+                ui.end_row();
+            });
+
+        ui.separator();
+        ui.label(tr!("menu-speed"));
+
+        ui.horizontal(|ui| {
+            if Self::label_control(ui.button("−"), tr!("control-speed-down")).clicked() {
+                self.perform_action(Action::SpeedDown);
+            }
+            ui.label(format!("{:.2}x", self.playback_speed));
+            if Self::label_control(ui.button("+"), tr!("control-speed-up")).clicked() {
+                self.perform_action(Action::SpeedUp);
+            }
+            if ui
+                .add_enabled(
+                    self.playback_speed != 1.0,
+                    egui::Button::new(tr!("button-reset")),
+                )
+                .clicked()
+            {
+                self.perform_action(Action::ResetSpeed);
+            }
+        });
+
+        let mut interpolate = self.settings.interpolate_slow_motion;
+        if ui
+            .checkbox(&mut interpolate, tr!("menu-interpolate-slow-motion"))
+            .on_hover_text(tr!("menu-interpolate-slow-motion-hint"))
+            .changed()
+        {
+            self.perform_action(Action::ToggleSlowMotionInterpolation);
+        }
+
+        ui.separator();
+        ui.label(tr!("menu-ab-loop"));
+        ui.horizontal(|ui| {
+            let (label, range) = match &self.ab_loop {
+                None => (tr!("menu-ab-loop-set-a"), None),
+                Some(ab_loop) => match ab_loop.end_ms {
+                    None => (
+                        tr!("menu-ab-loop-set-b"),
+                        Some(format!("{} –", Self::format_time(ab_loop.start_ms))),
+                    ),
+                    Some(end_ms) => (
+                        tr!("menu-ab-loop-clear"),
+                        Some(format!(
+                            "{} – {}",
+                            Self::format_time(ab_loop.start_ms),
+                            Self::format_time(end_ms)
+                        )),
+                    ),
+                },
+            };
+            if ui
+                .add_enabled(self.has_media(), egui::Button::new(label))
+                .clicked()
+            {
+                self.perform_action(Action::CycleAbLoop);
+            }
+            if let Some(range) = range {
+                ui.label(range);
+            }
+        });
+        let repeats = ui
+            .horizontal(|ui| {
+                ui.label(tr!("menu-ab-loop-repeats"));
+                ui.add(
+                    egui::DragValue::new(&mut self.settings.ab_loop.repeats)
+                        .range(0..=99)
+                        .custom_formatter(|value, _| {
+                            if value == 0.0 {
+                                tr!("menu-ab-loop-forever")
+                            } else {
+                                format!("{}", value)
+                            }
+                        }),
+                )
+            })
+            .inner
+            .on_hover_text(tr!("menu-ab-loop-repeats-hint"));
+        let ramp = ui
+            .checkbox(
+                &mut self.settings.ab_loop.ramp_speed,
+                tr!("menu-ab-loop-ramp"),
+            )
+            .on_hover_text(tr!("menu-ab-loop-ramp-hint"));
+        let ramp_start = ui
+            .add_enabled_ui(self.settings.ab_loop.ramp_speed, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tr!("menu-ab-loop-ramp-start"));
+                    ui.add(
+                        egui::Slider::new(&mut self.settings.ab_loop.ramp_start, 0.25..=1.0)
+                            .custom_formatter(|value, _| format!("{:.0}%", value * 100.0)),
+                    )
+                })
+                .inner
+            })
+            .inner;
+        if repeats.changed() || ramp.changed() || ramp_start.changed() {
+            self.apply_playback_speed();
+        }
+
+        ui.separator();
+        ui.label(tr!("menu-shader"));
+
+        let active_shader = self.shaders.active_path().map(|path| path.to_string());
+        if ui
+            .radio(active_shader.is_none(), tr!("menu-none"))
+            .clicked()
+        {
+            self.perform_action(Action::SelectShader(None));
+        }
+
+        for (index, path) in self.settings.shaders.clone().iter().enumerate() {
+            let name = Path::new(path)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.clone());
+
+            if ui
+                .radio(active_shader.as_deref() == Some(path.as_str()), name)
+                .clicked()
+            {
+                self.perform_action(Action::SelectShader(Some(index)));
+            }
+        }
+
+        if ui.button(tr!("menu-load-shader")).clicked() {
+            ui.close_menu();
+            self.perform_action(Action::LoadShader);
+        }
+
+        if let Some(error) = self.shaders.error() {
+            ui.colored_label(egui::Color32::from_rgb(255, 100, 100), error);
+        }
+
+        ui.separator();
+        ui.label(tr!("menu-compare"));
+
+        if let Some(comparison) = &self.comparison {
+            let name = Path::new(&comparison.filename)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| comparison.filename.clone());
+            ui.label(tr!("menu-compare-with-file", file = name));
+
+            let mut wipe = comparison.wipe;
+            if ui.checkbox(&mut wipe, tr!("menu-wipe")).changed() {
+                self.perform_action(Action::ToggleWipe);
+            }
+
+            if ui.button(tr!("menu-close-comparison")).clicked() {
+                ui.close_menu();
+                self.perform_action(Action::CloseComparison);
+            }
+        } else if ui.button(tr!("menu-compare-with")).clicked() {
+            ui.close_menu();
+            self.perform_action(Action::OpenComparison);
+        }
+
+        ui.separator();
+        ui.label(tr!("menu-video-wall"));
+
+        if let Some(wall) = &self.video_wall {
+            let mut audible = wall.audible;
+            let names = self
+                .current_filename
+                .iter()
+                .chain(wall.tiles.iter().map(|tile| &tile.filename));
+            for (cell, filename) in names.enumerate() {
+                let name = Path::new(filename)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| filename.clone());
+                ui.radio_value(&mut audible, cell, name);
+            }
+            if audible != wall.audible {
+                self.select_wall_audio(audible);
+            }
+
+            if ui.button(tr!("menu-close-video-wall")).clicked() {
+                ui.close_menu();
+                self.perform_action(Action::CloseVideoWall);
+            }
+        } else if ui
+            .button(tr!("menu-open-video-wall"))
+            .on_hover_text(tr!("menu-open-video-wall-hint"))
+            .clicked()
+        {
+            ui.close_menu();
+            self.perform_action(Action::OpenVideoWall);
+        }
+
+        ui.separator();
+        let mut buffering_changed = false;
+        ui.horizontal(|ui| {
+            ui.label(tr!("menu-frame-queue"));
+            buffering_changed |= ui
+                .add(egui::DragValue::new(&mut self.settings.frame_queue).range(0..=64))
+                .changed();
+        })
+        .response
+        .on_hover_text(tr!("menu-frame-queue-hint"));
+        ui.horizontal(|ui| {
+            ui.label(tr!("menu-read-ahead"));
+            buffering_changed |= ui
+                .add(
+                    egui::DragValue::new(&mut self.settings.read_ahead_mb)
+                        .range(1..=1024)
+                        .suffix(" MB"),
+                )
+                .changed();
+        })
+        .response
+        .on_hover_text(tr!("menu-read-ahead-hint"));
+        if buffering_changed {
+            decode_ahead::configure(self.settings.frame_queue, self.settings.read_ahead_mb);
+        }
+
+        ui.separator();
+        ui.menu_button(tr!("menu-presets"), |ui| self.show_presets_menu(ui));
+        ui.menu_button(tr!("menu-appearance"), |ui| self.show_appearance_menu(ui));
+    }
+
+    fn show_presets_menu(&mut self, ui: &mut egui::Ui) {
+        if ui
+            .radio(self.active_preset.is_none(), tr!("menu-off"))
+            .clicked()
+        {
+            self.perform_action(Action::ApplyPreset(None));
+            ui.close_menu();
+        }
+        let mut selected = None;
+        for (index, preset) in self.settings.presets.iter().enumerate() {
+            let active = self.active_preset.as_ref() == Some(&preset.name);
+            let response = ui.radio(active, &preset.name);
+            let response = if preset
+                .patterns
+                .iter()
+                .any(|pattern| !pattern.trim().is_empty())
+            {
+                response.on_hover_text(tr!(
+                    "menu-preset-patterns",
+                    patterns = preset.patterns.join(" ")
+                ))
+            } else {
+                response
+            };
+            if response.clicked() {
+                selected = Some(index);
+            }
+        }
+        if let Some(index) = selected {
+            self.perform_action(Action::ApplyPreset(Some(index)));
+            ui.close_menu();
+        }
+        ui.separator();
+        if ui.button(tr!("menu-edit-presets")).clicked() {
+            self.show_presets = true;
+            ui.close_menu();
+        }
+    }
+
+    fn show_sleep_timer_menu(&mut self, ui: &mut egui::Ui) {
+        let mut stop_after_current = self.stop_after_current;
+        if ui
+            .checkbox(&mut stop_after_current, tr!("menu-stop-after-file"))
+            .changed()
+        {
+            self.perform_action(Action::ToggleStopAfterCurrent);
+        }
+
+        ui.separator();
+        ui.label(tr!("menu-sleep-timer"));
+        if ui
+            .radio(self.sleep_timer.is_none(), tr!("menu-none"))
+            .clicked()
+        {
+            self.set_sleep_timer(None);
+            ui.close_menu();
+        }
+        let current_minutes = match self.sleep_timer {
+            Some(sleep_timer::SleepTimer::At { minutes, .. }) => Some(minutes),
+            _ => None,
+        };
+        for minutes in sleep_timer::PRESETS {
+            let label = match (
+                current_minutes,
+                self.sleep_timer.and_then(|t| t.remaining()),
+            ) {
+                (Some(current), Some(remaining)) if current == minutes => tr!(
+                    "menu-sleep-timer-remaining",
+                    time = sleep_timer::SleepTimer::after_minutes(minutes).label(),
+                    remaining = Self::format_time(remaining.as_millis() as i64)
+                ),
+                _ => sleep_timer::SleepTimer::after_minutes(minutes).label(),
+            };
+            if ui.radio(current_minutes == Some(minutes), label).clicked() {
+                self.set_sleep_timer(Some(sleep_timer::SleepTimer::after_minutes(minutes)));
+                ui.close_menu();
+            }
+        }
+        let end_of_file = sleep_timer::SleepTimer::EndOfFile;
+        if ui
+            .radio(self.sleep_timer == Some(end_of_file), end_of_file.label())
+            .clicked()
+        {
+            self.set_sleep_timer(Some(end_of_file));
+            ui.close_menu();
+        }
+
+        ui.separator();
+        ui.label(tr!("menu-sleep-action"));
+        for action in [
+            sleep_timer::SleepAction::Pause,
+            sleep_timer::SleepAction::Quit,
+        ] {
+            ui.radio_value(&mut self.settings.sleep_action, action, action.label());
+        }
+
+        ui.separator();
+        ui.label(tr!("menu-end-of-playlist"));
+        let current = self
+            .end_of_playlist
+            .unwrap_or(self.settings.end_of_playlist);
+        for action in playlist::EndOfPlaylist::ALL {
+            if ui.radio(current == action, action.label()).clicked() {
+                self.settings.end_of_playlist = action;
+                self.end_of_playlist = None;
+            }
+        }
+    }
+
+    fn show_chapters_menu(&mut self, ui: &mut egui::Ui) {
+        let current_chapter = self.current_chapter();
+        let chapters: Vec<(i64, String)> = self
+            .chapters()
+            .iter()
+            .map(|chapter| (chapter.start_time_ms, chapter.title.clone()))
+            .collect();
+
+        if chapters.is_empty() {
+            ui.label(tr!("menu-no-chapters"));
+        }
+
+        egui::ScrollArea::vertical()
+            .max_height(300.0)
+            .show(ui, |ui| {
+                for (index, (start_ms, title)) in chapters.into_iter().enumerate() {
+                    let label = format!("{}  {}", Self::format_time(start_ms), title);
+                    if ui
+                        .selectable_label(current_chapter == Some(index), label)
+                        .clicked()
+                    {
+                        self.seek_to_chapter(index);
+                        ui.close_menu();
+                    }
+                }
+            });
+
+        ui.separator();
+        ui.checkbox(
+            &mut self.settings.snap_to_chapters,
+            tr!("menu-snap-to-chapters"),
+        )
+        .on_hover_text(tr!("menu-snap-to-chapters-hint"));
+        if ui.button(tr!("menu-load-chapters")).clicked() {
+            ui.close_menu();
+            self.perform_action(Action::LoadChapters);
+        }
+        if ui.button(tr!("menu-detect-scenes")).clicked() {
+            ui.close_menu();
+            self.perform_action(Action::DetectScenes);
+        }
+        if ui.button(tr!("menu-skip-segments")).clicked() {
+            ui.close_menu();
+            self.perform_action(Action::EditSkipSegments);
+        }
+        if ui.button(tr!("menu-cutlist")).clicked() {
+            ui.close_menu();
+            self.perform_action(Action::ToggleCutlist);
+        }
+        if ui.button(tr!("menu-annotations")).clicked() {
+            ui.close_menu();
+            self.perform_action(Action::ToggleAnnotations);
+        }
+
+        if self.web_video.is_some() {
+            ui.separator();
+            self.show_sponsorblock_menu(ui);
+        }
+    }
+
+    fn show_sponsorblock_menu(&mut self, ui: &mut egui::Ui) {
+        if ui
+            .checkbox(&mut self.settings.sponsorblock.enabled, "SponsorBlock")
+            .changed()
+        {
+            let enabled = self.settings.sponsorblock.enabled;
+            if let Some(page) = &mut self.web_video {
+                page.sponsor_lookup =
+                    enabled.then(|| sponsorblock::SponsorLookup::start(&page.video_id));
+            }
+            self.skip_segment_entered = None;
+        }
+
+        ui.add_enabled_ui(self.settings.sponsorblock.enabled, |ui| {
+            egui::Grid::new("sponsorblock_categories").show(ui, |ui| {
+                for category in sponsorblock::SponsorCategory::ALL {
+                    ui.colored_label(category.color(), "■");
+                    ui.label(category.name());
+                    let action = self
+                        .settings
+                        .sponsorblock
+                        .categories
+                        .entry(category)
+                        .or_insert(sponsorblock::SponsorAction::Ignore);
+                    egui::ComboBox::from_id_salt(("sponsorblock", category))
+                        .selected_text(action.name())
+                        .show_ui(ui, |ui| {
+                            for option in sponsorblock::SponsorAction::ALL {
+                                ui.selectable_value(action, option, option.name());
+                            }
+                        });
+                    ui.end_row();
+                }
+            });
+        });
+    }
+
+    fn downmix_name(downmix: audio::Downmix) -> String {
+        match downmix {
+            audio::Downmix::Stereo => tr!("menu-downmix-stereo"),
+            audio::Downmix::Mono => tr!("menu-downmix-mono"),
+            audio::Downmix::Passthrough => tr!("menu-downmix-source"),
+        }
+    }
+
+    fn show_audio_menu(&mut self, ui: &mut egui::Ui) {
+        ui.label(tr!("menu-channels"));
+
+        for downmix in [
+            audio::Downmix::Stereo,
+            audio::Downmix::Mono,
+            audio::Downmix::Passthrough,
+        ] {
+            if ui
+                .radio(
+                    self.settings.downmix == downmix,
+                    Self::downmix_name(downmix),
+                )
+                .clicked()
+            {
+                self.perform_action(Action::SetDownmix(downmix));
+                ui.close_menu();
+            }
+        }
+
+        ui.separator();
+        ui.label(tr!("menu-audio-output"));
+
+        for (backend, label) in [
+            (audio::AudioBackend::Rodio, "rodio".to_string()),
+            (audio::AudioBackend::Cpal, tr!("menu-audio-output-cpal")),
+        ] {
+            if ui
+                .radio(self.settings.audio_backend == backend, label)
+                .clicked()
+            {
+                self.perform_action(Action::SetAudioBackend(backend));
+                ui.close_menu();
+            }
+        }
+
+        ui.separator();
+        ui.label(tr!("menu-output-device"));
+        let default_label = match &self.output_devices.default {
+            Some(device) => tr!("menu-output-device-default-named", device = device),
+            None => tr!("menu-output-device-default"),
+        };
+        if ui
+            .radio(self.settings.output_device.is_none(), default_label)
+            .clicked()
+        {
+            self.set_output_device(None);
+            ui.close_menu();
+        }
+        for device in self.output_devices.names.clone() {
+            let selected = self.settings.output_device.as_ref() == Some(&device);
+            if ui.radio(selected, &device).clicked() {
+                self.set_output_device(Some(device));
+                ui.close_menu();
+            }
+        }
+        ui.checkbox(
+            &mut self.settings.audio_profiles.enabled,
+            tr!("menu-audio-profiles"),
+        )
+        .on_hover_text(tr!("menu-audio-profiles-hint"));
+
+        ui.separator();
+        ui.label(tr!("menu-audio-delay"));
+        ui.horizontal(|ui| {
+            if Self::label_control(ui.button("−"), tr!("control-audio-delay-down")).clicked() {
+                self.perform_action(Action::ChangeAudioDelay(-50));
+            }
+            ui.label(tr!("menu-audio-delay-ms", ms = self.audio_delay_ms));
+            if Self::label_control(ui.button("+"), tr!("control-audio-delay-up")).clicked() {
+                self.perform_action(Action::ChangeAudioDelay(50));
+            }
+            if ui
+                .add_enabled(
+                    self.audio_delay_ms != 0,
+                    egui::Button::new(tr!("button-reset")),
+                )
+                .clicked()
+            {
+                self.perform_action(Action::ResetAudioDelay);
+            }
+        });
+        if ui.button(tr!("menu-measure-sync")).clicked() {
+            ui.close_menu();
+            self.perform_action(Action::MeasureSync);
+        }
+
+        ui.separator();
+        ui.label(tr!("menu-pitch"));
+        ui.horizontal(|ui| {
+            if Self::label_control(ui.button("−"), tr!("control-pitch-down")).clicked() {
+                self.perform_action(Action::PitchDown);
+            }
+            ui.label(tr!(
+                "menu-pitch-semitones",
+                semitones = format!("{:+}", self.pitch_semitones)
+            ));
+            if Self::label_control(ui.button("+"), tr!("control-pitch-up")).clicked() {
+                self.perform_action(Action::PitchUp);
+            }
+            if ui
+                .add_enabled(
+                    self.pitch_semitones != 0,
+                    egui::Button::new(tr!("button-reset")),
+                )
+                .clicked()
+            {
+                self.perform_action(Action::ResetPitch);
+            }
+        });
+        if ui
+            .checkbox(&mut self.settings.keep_pitch, tr!("menu-keep-pitch"))
+            .on_hover_text(tr!("menu-keep-pitch-hint"))
+            .changed()
+        {
+            self.apply_playback_speed();
+        }
+
+        let secondary_streams = self.secondary_audio_streams();
+        if !secondary_streams.is_empty() {
+            ui.separator();
+            ui.label(tr!("menu-mix-in"));
+
+            let current_stream = self
+                .audio
+                .as_ref()
+                .and_then(|audio| audio.secondary_stream());
+            if ui
+                .radio(current_stream.is_none(), tr!("menu-off"))
+                .clicked()
+            {
+                self.perform_action(Action::SelectSecondaryAudio(None));
+                ui.close_menu();
+            }
+            for (index, label) in secondary_streams {
+                if ui.radio(current_stream == Some(index), label).clicked() {
+                    self.perform_action(Action::SelectSecondaryAudio(Some(index)));
+                    ui.close_menu();
+                }
+            }
+            ui.add_enabled_ui(current_stream.is_some(), |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tr!("menu-mix-main"));
+                    let response = ui
+                        .add(
+                            egui::Slider::new(&mut self.settings.mix_balance, 0.0..=1.0)
+                                .show_value(false),
+                        )
+                        .on_hover_text(tr!("menu-mix-balance-hint"));
+                    ui.label(tr!("menu-mix-secondary"));
+                    if response.changed() {
+                        if let Some(audio) = &self.audio {
+                            audio.set_mix_balance(self.settings.mix_balance);
+                        }
+                    }
+                    if response.double_clicked() {
+                        self.settings.mix_balance = 0.5;
+                        if let Some(audio) = &self.audio {
+                            audio.set_mix_balance(0.5);
+                        }
+                    }
+                });
+            });
+        }
+
+        ui.separator();
+        let mut night_mode = self.settings.night_mode;
+        if ui
+            .checkbox(&mut night_mode, tr!("menu-night-mode"))
+            .on_hover_text(tr!("menu-night-mode-hint"))
+            .changed()
+        {
+            self.perform_action(Action::ToggleNightMode);
+        }
+        ui.add_enabled_ui(self.settings.night_mode, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(tr!("menu-night-mode-strength"));
+                let response = ui.add(
+                    egui::Slider::new(&mut self.settings.night_mode_strength, 0.0..=1.0)
+                        .custom_formatter(|value, _| format!("{:.0}%", value * 100.0)),
+                );
+                if response.changed() {
+                    self.apply_night_mode();
+                }
+            });
+        });
+
+        ui.separator();
+        ui.checkbox(
+            &mut self.settings.audible_scrubbing,
+            tr!("menu-audible-scrubbing"),
+        )
+        .on_hover_text(tr!("menu-audible-scrubbing-hint"));
+
+        ui.horizontal(|ui| {
+            ui.label(tr!("menu-audio-memory"));
+            ui.add(
+                egui::DragValue::new(&mut self.settings.audio_memory_mb)
+                    .range(64..=65_536)
+                    .speed(16)
+                    .suffix(" MB"),
+            );
+        })
+        .response
+        .on_hover_text(tr!("menu-audio-memory-hint"));
+
+        // Audio-only files have no video menu to hold it
+        if self.video.is_none() {
+            ui.separator();
+            ui.menu_button(tr!("menu-appearance"), |ui| self.show_appearance_menu(ui));
+        }
+    }
+
+    fn show_appearance_menu(&mut self, ui: &mut egui::Ui) {
+        let mut changed = false;
+
+        ui.label(tr!("menu-theme"));
+        for mode in theme::ThemeMode::ALL {
+            changed |= ui
+                .radio_value(&mut self.settings.theme, mode, mode.name())
+                .changed();
+        }
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label(tr!("menu-accent-color"));
+            changed |= ui
+                .color_edit_button_srgb(&mut self.settings.accent_color)
+                .changed();
+            if ui.button(tr!("button-reset")).clicked() {
+                self.settings.accent_color = theme::DEFAULT_ACCENT;
+                changed = true;
+            }
+        });
+
+        if changed {
+            theme::apply(ui.ctx(), self.settings.theme, self.settings.accent_color);
+        }
+
+        ui.separator();
+        ui.label(tr!("menu-language"));
+        for language in i18n::available_languages() {
+            if ui
+                .radio(self.settings.language == language.code, language.name)
+                .clicked()
+            {
+                i18n::set_language(&language.code);
+                self.settings.language = language.code;
+            }
+        }
+
+        ui.separator();
+        let mut this_display = self.settings.window.fullscreen_display.is_some();
+        if ui
+            .checkbox(&mut this_display, tr!("menu-fullscreen-this-display"))
+            .on_hover_text(tr!("menu-fullscreen-this-display-hint"))
+            .changed()
+        {
+            self.settings.window.fullscreen_display = if this_display {
+                ui.input(|i| i.viewport().outer_rect)
+                    .map(|rect| rect.min.into())
+            } else {
+                None
+            };
+        }
+        ui.checkbox(
+            &mut self.settings.click_to_pause,
+            tr!("menu-click-to-pause"),
+        );
+
+        ui.separator();
+        ui.label(tr!("menu-live-streams"));
+        ui.horizontal(|ui| {
+            ui.label(tr!("menu-live-latency"));
+            ui.add(
+                egui::Slider::new(&mut self.settings.live_latency_ms, 1000..=30_000)
+                    .step_by(500.0)
+                    .custom_formatter(|ms, _| format!("{:.1} s", ms / 1000.0)),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr!("menu-live-catch-up"));
+            for mode in live::CatchUp::ALL {
+                ui.radio_value(&mut self.settings.live_catch_up, mode, mode.name());
+            }
+        });
+        ui.checkbox(&mut self.settings.timeshift, tr!("menu-timeshift"))
+            .on_hover_text(tr!("menu-timeshift-hint"));
+
+        ui.separator();
+        ui.label(tr!("menu-security"));
+        let security = &mut self.settings.security;
+        let mut security_changed = false;
+        security_changed |= ui
+            .checkbox(
+                &mut security.restrict_protocols,
+                tr!("menu-restrict-protocols"),
+            )
+            .on_hover_text(tr!("menu-restrict-protocols-hint"))
+            .changed();
+        security_changed |= ui
+            .checkbox(
+                &mut security.block_expanding_formats,
+                tr!("menu-block-expanding-formats"),
+            )
+            .on_hover_text(tr!("menu-block-expanding-formats-hint"))
+            .changed();
+        security_changed |= ui
+            .checkbox(
+                &mut security.autoload_sidecar_files,
+                tr!("menu-autoload-sidecar-files"),
+            )
+            .on_hover_text(tr!("menu-autoload-sidecar-files-hint"))
+            .changed();
+        if security_changed {
+            security::configure(&self.settings.security);
+        }
+
+        ui.separator();
+        if ui.button(tr!("menu-storage")).clicked() {
+            self.storage_usage = Some(storage::cache_usage());
+            ui.close_menu();
+        }
+        let hint = match &self.stats_log {
+            Some(log) => tr!(
+                "menu-stats-log-path",
+                path = log.path().display().to_string()
+            ),
+            None => tr!("menu-stats-log-hint"),
+        };
+        if ui
+            .checkbox(&mut self.settings.stats_log, tr!("menu-stats-log"))
+            .on_hover_text(hint)
+            .changed()
+        {
+            self.stats_log = if self.settings.stats_log {
+                Self::open_stats_log()
+            } else {
+                None
+            };
+        }
+        if ui
+            .checkbox(
+                &mut self.settings.crash_recovery,
+                tr!("menu-crash-recovery"),
+            )
+            .on_hover_text(tr!("menu-crash-recovery-hint"))
+            .changed()
+            && !self.settings.crash_recovery
+        {
+            self.session_snapshots.finish();
+        }
+        if ui.button(tr!("menu-diagnostics")).clicked() {
+            self.diagnostics = Some(diagnostics::Diagnostics::collect(
+                self.gpu_info.clone(),
+                self.settings.audio_backend,
+            ));
+            ui.close_menu();
+        }
+
+        ui.separator();
+        ui.add_enabled_ui(tray::Tray::SUPPORTED, |ui| {
+            ui.checkbox(&mut self.settings.tray_icon, tr!("menu-tray-icon"))
+                .on_disabled_hover_text(tr!("menu-tray-icon-unsupported"));
+            ui.add_enabled(
+                self.settings.tray_icon,
+                egui::Checkbox::new(
+                    &mut self.settings.minimize_to_tray,
+                    tr!("menu-minimize-to-tray"),
+                ),
+            );
+        });
+    }
+
+    fn set_audio_backend(&mut self, backend: audio::AudioBackend) {
+        if backend == self.settings.audio_backend {
+            return;
+        }
+        self.move_audio_output(backend);
+    }
+
+    /// Opens a new output for the playing audio, on the selected device.
+    fn move_audio_output(&mut self, backend: audio::AudioBackend) {
+        if let Some(audio) = &mut self.audio {
+            if let Err(e) = audio.set_backend(backend) {
+                self.show_error(&tr!("error-switch-audio-output"), e);
+                return;
+            }
+        }
+
+        self.settings.audio_backend = backend;
+        self.apply_volume();
+        self.apply_night_mode();
+        self.apply_playback_speed();
+        self.set_paused(self.paused);
+    }
+
+    /// Picks the output device, or the system default with `None`, remembering it for the
+    /// monitor the window is on.
+    fn set_output_device(&mut self, device: Option<String>) {
+        self.settings.output_device = device.clone();
+        if self.settings.audio_profiles.enabled {
+            if let Some(monitor) = &self.monitor {
+                let monitors = &mut self.settings.audio_profiles.monitors;
+                match device {
+                    Some(device) => monitors.insert(monitor.clone(), device),
+                    None => monitors.remove(monitor),
+                };
+            }
+        }
+    }
+
+    /// Follows the system default device and the monitor the window is on, moving playback
+    /// when the device to use changes.
+    fn update_output_device(&mut self, ctx: &egui::Context) {
+        if let Some(devices) = self.device_watcher.poll() {
+            self.output_devices = devices;
+        }
+
+        let monitor = ctx
+            .input(|i| i.viewport().monitor_size)
+            .map(audio_profiles::monitor_key);
+        if monitor != self.monitor {
+            self.monitor = monitor;
+            let profiles = &self.settings.audio_profiles;
+            let remembered = self
+                .monitor
+                .as_ref()
+                .filter(|_| profiles.enabled)
+                .and_then(|monitor| profiles.monitors.get(monitor));
+            if let Some(device) = remembered {
+                self.settings.output_device = Some(device.clone());
+            }
+        }
+
+        let device = self
+            .settings
+            .output_device
+            .clone()
+            .or_else(|| self.output_devices.default.clone());
+        if device.is_some() && device != self.output_device {
+            self.switch_output_device(device);
+        }
+    }
+
+    /// Moves playback to another device, remembering the audio settings used on the one it
+    /// leaves and restoring those of the new one.
+    fn switch_output_device(&mut self, device: Option<String>) {
+        self.store_audio_profile();
+        let previous = std::mem::replace(&mut self.output_device, device.clone());
+        audio::select_output_device(self.settings.output_device.as_deref());
+        println!(
+            "Audio output device: {}",
+            device.as_deref().unwrap_or("none")
+        );
+
+        let profile = device
+            .as_ref()
+            .filter(|_| self.settings.audio_profiles.enabled)
+            .and_then(|device| self.settings.audio_profiles.devices.get(device))
+            .cloned();
+        let mut backend = self.settings.audio_backend;
+        if let Some(profile) = profile {
+            self.volume = profile.volume.clamp(0.0, MAX_VOLUME);
+            backend = profile.backend;
+            if profile.downmix != self.settings.downmix {
+                // A fresh decode opens its output on the new device
+                self.settings.audio_backend = backend;
+                self.set_downmix(profile.downmix);
+                return;
+            }
+        }
+
+        // The first device found is the one audio already opened on
+        if previous.is_some() || backend != self.settings.audio_backend {
+            self.move_audio_output(backend);
+            if let Some(device) = &device {
+                self.show_osd(
+                    tr!("osd-output-device", device = device),
+                    egui::Color32::WHITE,
+                );
+            }
+        } else {
+            self.apply_volume();
+        }
+    }
+
+    fn store_audio_profile(&mut self) {
+        let Some(device) = &self.output_device else {
+            return;
+        };
+        if self.settings.audio_profiles.enabled {
+            self.settings.audio_profiles.devices.insert(
+                device.clone(),
+                audio_profiles::AudioProfile {
+                    volume: self.volume,
+                    downmix: self.settings.downmix,
+                    backend: self.settings.audio_backend,
+                },
+            );
+        }
+    }
+
+    /// The values a preset can set, as they are now.
+    fn current_preset_values(&self) -> presets::Preset {
+        presets::Preset {
+            name: String::new(),
+            patterns: Vec::new(),
+            scaling: Some(self.settings.scaling),
+            sampler: Some(self.settings.sampler),
+            display_mode: Some(self.settings.display_mode),
+            denoise: Some(self.settings.denoise),
+            deband: Some(self.settings.deband),
+            speed: Some(self.playback_speed),
+            audio_delay_ms: Some(self.audio_delay_ms),
+            downmix: Some(self.settings.downmix),
+            night_mode: Some(self.settings.night_mode),
+        }
+    }
+
+    /// Puts back what the active preset replaced and applies `preset` on top, remembering
+    /// what it replaces in turn.
+    fn switch_preset(&mut self, preset: Option<presets::Preset>) {
+        let mut values = self.preset_restore.take().unwrap_or_default();
+        if let Some(preset) = &preset {
+            let mut restore = self.current_preset_values().masked(preset);
+            restore.overlay(&values.masked(preset));
+            self.preset_restore = Some(restore);
+            values.overlay(preset);
+        }
+        self.apply_preset_values(&values);
+        self.active_preset = preset.map(|preset| preset.name);
+    }
+
+    fn apply_preset_values(&mut self, values: &presets::Preset) {
+        let downmix = self.settings.downmix;
+        values.apply_to_settings(&mut self.settings);
+
+        if let Some(sampler) = values.sampler {
+            self.perform_action(Action::SetSampler(sampler));
+        }
+        if values.scaling.is_some() || values.denoise.is_some() || values.deband.is_some() {
+            self.apply_video_filters();
+        }
+        if values.night_mode.is_some() {
+            self.apply_night_mode();
+        }
+        if let Some(speed) = values.speed {
+            self.set_speed(speed);
+        }
+        if let Some(delay_ms) = values.audio_delay_ms {
+            self.audio_delay_ms = delay_ms;
+            self.apply_audio_delay();
+        }
+        if self.settings.downmix != downmix && self.audio.is_some() {
+            if let Err(e) = self.reload_audio() {
+                self.show_error(&tr!("error-reload-audio"), e);
+            }
+        }
+    }
+
+    fn set_downmix(&mut self, downmix: audio::Downmix) {
+        if downmix == self.settings.downmix {
+            return;
+        }
+        self.settings.downmix = downmix;
+        if self.audio.is_none() {
+            return;
+        }
+
+        // The new layout needs a fresh decode
+        if let Err(e) = self.reload_audio() {
+            self.show_error(&tr!("error-reload-audio"), e);
+        }
+    }
+
+    fn load_shader_dialog(&mut self) {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter(tr!("filter-shaders"), &["glsl", "hook", "frag", "wgsl"])
+            .add_filter(tr!("filter-all-files"), &["*"])
+            .pick_file()
+        {
+            let path = path.to_string_lossy().into_owned();
+            let index = match self.settings.shaders.iter().position(|p| *p == path) {
+                Some(index) => index,
+                None => {
+                    self.settings.shaders.push(path);
+                    self.settings.shaders.len() - 1
+                }
+            };
+            self.select_shader(Some(index));
+        }
+    }
+
+    fn select_shader(&mut self, index: Option<usize>) {
+        match index.and_then(|index| self.settings.shaders.get(index).cloned()) {
+            Some(path) => self.shaders.load(&path),
+            None => self.shaders.clear(),
+        }
+
+        self.settings.active_shader = self.shaders.active_path().map(|path| path.to_string());
+    }
+
+    fn apply_video_filters(&mut self) {
+        let mut filters = Vec::new();
+        if self.settings.denoise {
+            filters.push("hqdn3d");
+        }
+        if self.settings.deband {
+            filters.push("deband");
+        }
+
+        let wall_videos = self
+            .video_wall
+            .iter_mut()
+            .flat_map(|wall| wall.tiles.iter_mut().map(|tile| &mut tile.video));
+        let videos = self
+            .video
+            .iter_mut()
+            .chain(
+                self.comparison
+                    .iter_mut()
+                    .map(|comparison| &mut comparison.video),
+            )
+            .chain(wall_videos);
+        for video in videos {
+            if let Err(e) = video.set_filters(&filters) {
+                eprintln!("Error setting up video filters: {}", e);
+            }
+            if let Err(e) = video.set_scaling(self.settings.scaling) {
+                eprintln!("Error setting up video scaling: {}", e);
+            }
+        }
+    }
+
+    fn start_subtitle_search(&self, dialog: &mut SubtitleSearchDialog) {
+        let filename = match &self.current_filename {
+            Some(filename) => filename.clone(),
+            None => return,
+        };
+        let api_key = self.settings.opensubtitles_api_key.trim().to_string();
+        let language = self.settings.subtitle_language.trim().to_string();
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            let hash = if playlist::is_url(&filename) {
+                None
+            } else {
+                opensubtitles::compute_hash(Path::new(&filename)).ok()
+            };
+            let query = Path::new(&filename)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+
+            let mut result = opensubtitles::search(&api_key, hash.as_deref(), &query, &language);
+            // Fall back to a name search when nothing matches the exact file
+            if hash.is_some() && matches!(&result, Ok(results) if results.is_empty()) {
+                result = opensubtitles::search(&api_key, None, &query, &language);
+            }
+
+            let _ = sender.send(SubtitleSearchEvent::Results(
+                result.map_err(|e| e.to_string()),
+            ));
+        });
+
+        dialog.results.clear();
+        dialog.status = Some(tr!("subtitle-search-searching"));
+        dialog.pending = Some(receiver);
+    }
+
+    fn start_subtitle_download(
+        &self,
+        dialog: &mut SubtitleSearchDialog,
+        result: opensubtitles::SubtitleResult,
+    ) {
+        let filename = match &self.current_filename {
+            Some(filename) => filename.clone(),
+            None => return,
+        };
+        let api_key = self.settings.opensubtitles_api_key.trim().to_string();
+        let (sender, receiver) = mpsc::channel();
+        dialog.status = Some(tr!("subtitle-search-downloading", file = result.file_name));
+
+        thread::spawn(move || {
+            let path = opensubtitles::download(&api_key, result.file_id)
+                .map_err(|e| e.to_string())
+                .and_then(|content| {
+                    opensubtitles::save_subtitle(
+                        &filename,
+                        &result.language,
+                        &result.file_name,
+                        &content,
+                    )
+                    .map_err(|e| e.to_string())
+                });
+
+            let _ = sender.send(SubtitleSearchEvent::Downloaded {
+                filename,
+                result: path.map(|path| path.to_string_lossy().into_owned()),
+            });
+        });
+
+        dialog.pending = Some(receiver);
+    }
+
+    fn show_subtitle_search_dialog(&mut self, ctx: &egui::Context) {
+        let mut dialog = match self.subtitle_search.take() {
+            Some(dialog) => dialog,
+            None => return,
+        };
+        let mut search = false;
+        let mut download = None;
+        let mut close = false;
+
+        if let Some(pending) = &dialog.pending {
+            match pending.try_recv() {
+                Ok(SubtitleSearchEvent::Results(Ok(results))) => {
+                    dialog.status = if results.is_empty() {
+                        Some(tr!("subtitle-search-no-results"))
+                    } else {
+                        None
+                    };
+                    dialog.results = results;
+                    dialog.pending = None;
+                }
+                Ok(SubtitleSearchEvent::Downloaded {
+                    filename,
+                    result: Ok(path),
+                }) => {
+                    // Another file may have been opened while it downloaded
+                    let same_file = self.current_filename.as_ref() == Some(&filename);
+                    dialog.status = Some(match Subtitles::load(&path) {
+                        Ok(_) if !same_file => tr!("subtitle-search-other-file", file = path),
+                        Ok(subtitles) => {
+                            self.subtitles = Some(subtitles);
+                            tr!("subtitle-search-loaded", file = path)
+                        }
+                        Err(e) => tr!("subtitle-search-load-failed", error = e),
+                    });
+                    dialog.pending = None;
+                }
+                Ok(SubtitleSearchEvent::Results(Err(e)))
+                | Ok(SubtitleSearchEvent::Downloaded { result: Err(e), .. }) => {
+                    dialog.status = Some(tr!("subtitle-search-error", error = e));
+                    dialog.pending = None;
+                }
+                Err(mpsc::TryRecvError::Empty) => {
+                    ctx.request_repaint_after(Duration::from_millis(100));
+                }
+                Err(mpsc::TryRecvError::Disconnected) => dialog.pending = None,
+            }
+        }
+
+        let busy = dialog.pending.is_some();
+
+        egui::Window::new(tr!("window-subtitle-search"))
+            .collapsible(false)
+            .default_size([500.0, 350.0])
+            .show(ctx, |ui| {
+                egui::Grid::new("subtitle_search_options")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label(tr!("subtitle-search-api-key"));
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.settings.opensubtitles_api_key)
+                                .password(true),
+                        );
+                        ui.end_row();
+
+                        ui.label(tr!("subtitle-search-language"));
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.settings.subtitle_language)
+                                .hint_text("en"),
+                        );
+                        ui.end_row();
+                    });
+
+                ui.horizontal(|ui| {
+                    let can_search =
+                        !busy && !self.settings.opensubtitles_api_key.trim().is_empty();
+                    if ui
+                        .add_enabled(can_search, egui::Button::new(tr!("button-search")))
+                        .clicked()
+                    {
+                        search = true;
+                    }
+                    if ui.button(tr!("button-subtitle-style")).clicked() {
+                        self.show_subtitle_style = true;
+                    }
+                    if ui.button(tr!("button-close")).clicked() {
+                        close = true;
+                    }
+                    if busy {
+                        ui.spinner();
+                    }
+                });
+
+                if let Some(status) = &dialog.status {
+                    ui.label(status);
+                }
+
+                ui.separator();
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for result in &dialog.results {
+                        ui.horizontal(|ui| {
+                            if ui
+                                .add_enabled(!busy, egui::Button::new(tr!("button-download")))
+                                .clicked()
+                            {
+                                download = Some(result.clone());
+                            }
+
+                            let name = if result.release.is_empty() {
+                                &result.file_name
+                            } else {
+                                &result.release
+                            };
+                            ui.label(format!(
+                                "[{}] {} ({}){}",
+                                result.language,
+                                name,
+                                tr!("subtitle-search-downloads", count = result.download_count),
+                                if result.hash_match { " ✔" } else { "" }
+                            ));
+                        });
+                    }
+                });
+            });
+
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            close = true;
+        }
+
+        if search {
+            self.start_subtitle_search(&mut dialog);
+        } else if let Some(result) = download {
+            self.start_subtitle_download(&mut dialog, result);
+        }
+
+        if !close {
+            self.subtitle_search = Some(dialog);
+        }
+    }
+
+    fn show_subtitle_style_dialog(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_subtitle_style;
+        let mut pick_font = false;
+        let mut add_fallback = false;
+        let attached_fonts = self.attached_fonts.len();
+        let style = &mut self.settings.subtitle_style;
+
+        egui::Window::new(tr!("window-subtitle-style"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("subtitle_style")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label(tr!("subtitle-style-font"));
+                        let font_name = match &style.font {
+                            subtitles::SubtitleFont::Proportional => {
+                                tr!("subtitle-style-font-default")
+                            }
+                            subtitles::SubtitleFont::Monospace => {
+                                tr!("subtitle-style-font-monospace")
+                            }
+                            subtitles::SubtitleFont::File(path) => Path::new(path)
+                                .file_name()
+                                .map(|name| name.to_string_lossy().into_owned())
+                                .unwrap_or_else(|| path.clone()),
+                        };
+                        egui::ComboBox::from_id_salt("subtitle_font")
+                            .selected_text(font_name)
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(
+                                    &mut style.font,
+                                    subtitles::SubtitleFont::Proportional,
+                                    tr!("subtitle-style-font-default"),
+                                );
+                                ui.selectable_value(
+                                    &mut style.font,
+                                    subtitles::SubtitleFont::Monospace,
+                                    tr!("subtitle-style-font-monospace"),
+                                );
+                                if ui
+                                    .selectable_label(false, tr!("subtitle-style-font-file"))
+                                    .clicked()
+                                {
+                                    pick_font = true;
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label(tr!("subtitle-style-size"));
+                        ui.add(
+                            egui::Slider::new(&mut style.size, 0.02..=0.1)
+                                .custom_formatter(|size, _| format!("{:.1}%", size * 100.0))
+                                .custom_parser(|text| {
+                                    let text = text.trim_end_matches('%');
+                                    text.trim().parse::<f64>().ok().map(|size| size / 100.0)
+                                }),
+                        );
+                        ui.end_row();
+
+                        ui.label(tr!("subtitle-style-color"));
+                        ui.color_edit_button_srgb(&mut style.color);
+                        ui.end_row();
+
+                        ui.label(tr!("subtitle-style-outline"));
+                        ui.horizontal(|ui| {
+                            ui.add(egui::Slider::new(&mut style.outline_width, 0.0..=4.0));
+                            ui.color_edit_button_srgb(&mut style.outline_color);
+                        });
+                        ui.end_row();
+
+                        ui.label(tr!("subtitle-style-position"));
+                        ui.add(
+                            egui::Slider::new(&mut style.bottom_margin, 0.0..=0.9)
+                                .custom_formatter(|margin, _| {
+                                    tr!(
+                                        "subtitle-style-position-value",
+                                        percent = format!("{:.0}", margin * 100.0)
+                                    )
+                                })
+                                .custom_parser(|text| {
+                                    let text: String = text
+                                        .chars()
+                                        .filter(|c| c.is_ascii_digit() || *c == '.')
+                                        .collect();
+                                    text.trim().parse::<f64>().ok().map(|margin| margin / 100.0)
+                                }),
+                        );
+                        ui.end_row();
+
+                        ui.checkbox(&mut style.background, tr!("subtitle-style-background"));
+                        ui.add_enabled(
+                            style.background,
+                            egui::Slider::new(&mut style.background_opacity, 0..=255)
+                                .text(tr!("subtitle-style-opacity")),
+                        );
+                        ui.end_row();
+
+                        ui.label(tr!("subtitle-style-fallback-fonts"));
+                        ui.vertical(|ui| {
+                            let mut remove = None;
+                            for (index, path) in style.fallback_fonts.iter().enumerate() {
+                                ui.horizontal(|ui| {
+                                    if ui.small_button("✖").clicked() {
+                                        remove = Some(index);
+                                    }
+                                    ui.label(
+                                        Path::new(path)
+                                            .file_name()
+                                            .map(|name| name.to_string_lossy().into_owned())
+                                            .unwrap_or_else(|| path.clone()),
+                                    )
+                                    .on_hover_text(path);
+                                });
+                            }
+                            if let Some(index) = remove {
+                                style.fallback_fonts.remove(index);
+                            }
+                            if ui.button(tr!("subtitle-style-add-font")).clicked() {
+                                add_fallback = true;
+                            }
+                            ui.checkbox(
+                                &mut style.system_fallback_fonts,
+                                tr!("subtitle-style-system-fonts"),
+                            )
+                            .on_hover_text(tr!("subtitle-style-system-fonts-hint"));
+                            if attached_fonts > 0 {
+                                ui.label(tr!(
+                                    "subtitle-style-attached-fonts",
+                                    count = attached_fonts
+                                ));
+                            }
+                        });
+                        ui.end_row();
+                    });
+
+                ui.add_space(8.0);
+                if ui.button(tr!("button-reset")).clicked() {
+                    *style = subtitles::SubtitleStyle::default();
+                }
+            });
+
+        if pick_font {
+            if let Some(path) = rfd::FileDialog::new()
+                .add_filter(tr!("filter-fonts"), &["ttf", "otf"])
+                .pick_file()
+            {
+                style.font = subtitles::SubtitleFont::File(path.to_string_lossy().into_owned());
+            }
+        }
+        if add_fallback {
+            if let Some(paths) = rfd::FileDialog::new()
+                .add_filter(tr!("filter-fonts"), &["ttf", "otf", "ttc"])
+                .pick_files()
+            {
+                style
+                    .fallback_fonts
+                    .extend(paths.iter().map(|path| path.to_string_lossy().into_owned()));
+            }
+        }
+
+        self.show_subtitle_style = open;
+        self.update_fonts(ctx);
+    }
+
+    /// Rebuilds egui's fonts when the subtitle font, its fallbacks or the fonts attached to
+    /// the open file change. The fonts become available from the next frame on.
+    fn update_fonts(&mut self, ctx: &egui::Context) {
+        let setup = fonts::FontSetup::new(&self.settings.subtitle_style, &self.attached_fonts);
+        if self.loaded_fonts.as_ref() == Some(&setup) {
+            return;
+        }
+
+        if let Err(e) = fonts::apply(ctx, &self.settings.subtitle_style, &self.attached_fonts) {
+            self.settings.subtitle_style.font = subtitles::SubtitleFont::Proportional;
+            self.show_error(&tr!("error-load-subtitle-font"), e);
+            return;
+        }
+        self.loaded_fonts = Some(setup);
+    }
+
+    fn show_analysis_window(&mut self, ctx: &egui::Context) {
+        if !self.show_analysis {
+            return;
+        }
+
+        if let Some(analysis) = &mut self.gop_analysis {
+            if analysis.update() {
+                ctx.request_repaint_after(Duration::from_millis(100));
+            }
+        }
+        if let Some(scan) = &mut self.bitrate_scan {
+            if scan.update() {
+                ctx.request_repaint_after(Duration::from_millis(100));
+            }
+        }
+
+        let mut open = self.show_analysis;
+        let mut start = false;
+        let mut start_scan = false;
+        let mut seek_target = None;
+        let current_ms = self.current_time_ms();
+        let graph_streams = self.bitrate_graph_streams();
+        let mut graph_stream = self
+            .bitrate_graph_stream
+            .or_else(|| self.video.as_ref().map(|video| video.stream_index()))
+            .or_else(|| graph_streams.first().map(|(index, _)| *index));
+
+        egui::Window::new(tr!("window-analysis"))
+            .open(&mut open)
+            .default_size([520.0, 560.0])
+            .vscroll(true)
+            .show(ctx, |ui| {
+                if !self.has_media() {
+                    ui.label(tr!("analysis-no-file"));
+                    return;
+                }
+
+                ui.heading(tr!("analysis-bitrate"));
+                match &self.bitrate_scan {
+                    None => {
+                        ui.label(tr!("analysis-bitrate-description"));
+                        if ui.button(tr!("analysis-scan-bitrate")).clicked() {
+                            start_scan = true;
+                        }
+                    }
+                    Some(scan) => {
+                        let selected_label = graph_streams
+                            .iter()
+                            .find(|(index, _)| Some(*index) == graph_stream)
+                            .map(|(_, label)| label.clone())
+                            .unwrap_or_default();
+                        ui.horizontal(|ui| {
+                            egui::ComboBox::from_id_salt("bitrate_graph_stream")
+                                .selected_text(selected_label)
+                                .width(280.0)
+                                .show_ui(ui, |ui| {
+                                    for (index, label) in &graph_streams {
+                                        ui.selectable_value(&mut graph_stream, Some(*index), label);
+                                    }
+                                });
+                            ui.checkbox(
+                                &mut self.show_bitrate_overlay,
+                                tr!("analysis-show-under-seek-bar"),
+                            );
+                        });
+
+                        if let Some(stream_index) = graph_stream {
+                            seek_target = scan.show(ui, stream_index, current_ms);
+                        }
+                        if ui.button(tr!("analysis-scan-again")).clicked() {
+                            start_scan = true;
+                        }
+                    }
+                }
+
+                ui.add_space(8.0);
+                ui.separator();
+                ui.heading(tr!("analysis-gop"));
+                if self.video.is_none() {
+                    ui.label(tr!("analysis-gop-no-video"));
+                    return;
+                }
+
+                match &self.gop_analysis {
+                    None => {
+                        ui.label(tr!("analysis-gop-description"));
+                        if ui.button(tr!("analysis-analyze-gop")).clicked() {
+                            start = true;
+                        }
+                    }
+                    Some(analysis) => {
+                        seek_target = analysis.show(ui, current_ms);
+                        ui.add_space(8.0);
+                        if ui.button(tr!("analysis-analyze-again")).clicked() {
+                            start = true;
+                        }
+                    }
+                }
+            });
+        self.show_analysis = open;
+        self.bitrate_graph_stream = graph_stream;
+
+        if start_scan {
+            if let Some(filename) = &self.current_filename {
+                self.bitrate_scan = Some(bitrate_graph::BitrateScan::start(
+                    filename,
+                    self.media_info
+                        .as_ref()
+                        .map_or(0, |media_info| media_info.duration_ms),
+                ));
+            }
+        }
+        if start {
+            if let (Some(filename), Some(video)) = (&self.current_filename, &self.video) {
+                self.gop_analysis = Some(gop_analysis::GopAnalysis::start(
+                    filename,
+                    video.stream_index(),
+                    video.get_duration_ms(),
+                ));
+            }
+        }
+        if let Some(target_ms) = seek_target {
+            self.seek_to(target_ms);
+        }
+    }
+
+    fn show_integrity_window(&mut self, ctx: &egui::Context) {
+        if !self.show_integrity {
+            return;
+        }
+
+        if let Some(check) = &mut self.integrity_check {
+            if check.update() {
+                ctx.request_repaint_after(Duration::from_millis(100));
+            }
+        }
+
+        let mut open = self.show_integrity;
+        let mut start = false;
+        let mut save = false;
+        let mut seek_target = None;
+        let is_url = self
+            .current_filename
+            .as_deref()
+            .is_some_and(playlist::is_url);
+
+        egui::Window::new(tr!("window-verify-file"))
+            .open(&mut open)
+            .default_size([520.0, 420.0])
+            .show(ctx, |ui| {
+                if !self.has_media() {
+                    ui.label(tr!("verify-no-file"));
+                    return;
+                }
+                if is_url {
+                    ui.label(tr!("verify-local-only"));
+                    return;
+                }
+
+                match &self.integrity_check {
+                    None => {
+                        ui.label(tr!("verify-description"));
+                        if ui.button(tr!("verify-start")).clicked() {
+                            start = true;
+                        }
+                    }
+                    Some(check) => {
+                        seek_target = check.show(ui);
+                        ui.add_space(8.0);
+                        ui.horizontal(|ui| {
+                            if ui
+                                .add_enabled(
+                                    check.report().is_some(),
+                                    egui::Button::new(tr!("verify-save-report")),
+                                )
+                                .clicked()
+                            {
+                                save = true;
+                            }
+                            if ui.button(tr!("verify-check-again")).clicked() {
+                                start = true;
+                            }
+                        });
+                    }
+                }
+            });
+        self.show_integrity = open;
+
+        if start {
+            if let Some(filename) = &self.current_filename {
+                self.integrity_check = Some(integrity::IntegrityCheck::start(
+                    filename,
+                    self.duration_ms(),
+                ));
+            }
+        }
+        if save {
+            self.save_integrity_report();
+        }
+        if let Some(target_ms) = seek_target {
+            self.seek_to(target_ms);
+        }
+    }
+
+    fn show_cutlist_window(&mut self, ctx: &egui::Context) {
+        if !self.show_cutlist {
+            return;
+        }
+
+        let mut open = self.show_cutlist;
+        let mut export = None;
+        let mut seek_target = None;
+        let current_ms = self.current_time_ms();
+        let has_media = self.has_media();
+
+        egui::Window::new(tr!("window-cutlist"))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                if !has_media {
+                    ui.label(tr!("cutlist-no-file"));
+                    return;
+                }
+                ui.label(tr!("cutlist-description"));
+                ui.add_space(4.0);
+
+                egui::Grid::new("cutlist_marks")
+                    .num_columns(4)
+                    .show(ui, |ui| {
+                        for (label, mark) in [
+                            (tr!("cutlist-in"), &mut self.cutlist.in_ms),
+                            (tr!("cutlist-out"), &mut self.cutlist.out_ms),
+                        ] {
+                            ui.label(label);
+                            match *mark {
+                                Some(time_ms) => {
+                                    if ui.link(Self::format_time(time_ms)).clicked() {
+                                        seek_target = Some(time_ms);
+                                    }
+                                }
+                                None => {
+                                    ui.weak("–");
+                                }
+                            }
+                            if ui.small_button(tr!("cutlist-now")).clicked() {
+                                *mark = Some(current_ms);
+                            }
+                            if ui
+                                .add_enabled(mark.is_some(), egui::Button::new("🗑").small())
+                                .clicked()
+                            {
+                                *mark = None;
+                            }
+                            ui.end_row();
+                        }
+                    });
+
+                ui.separator();
+                let mut remove = None;
+                egui::Grid::new("cutlist_markers")
+                    .num_columns(3)
+                    .show(ui, |ui| {
+                        for (index, marker) in self.cutlist.markers.iter_mut().enumerate() {
+                            ui.add(
+                                egui::TextEdit::singleline(&mut marker.name).desired_width(160.0),
+                            );
+                            if ui.link(Self::format_time(marker.time_ms)).clicked() {
+                                seek_target = Some(marker.time_ms);
+                            }
+                            if Self::label_control(
+                                ui.small_button("🗑"),
+                                tr!("cutlist-remove-marker"),
+                            )
+                            .clicked()
+                            {
+                                remove = Some(index);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                if let Some(index) = remove {
+                    self.cutlist.markers.remove(index);
+                }
+                if ui.button(tr!("cutlist-add-marker")).clicked() {
+                    let name = tr!(
+                        "cutlist-marker-name",
+                        number = self.cutlist.markers.len() + 1
+                    );
+                    self.cutlist.add_marker(name, current_ms);
+                }
+
+                if let Some(error) = &self.cutlist_error {
+                    ui.colored_label(egui::Color32::from_rgb(255, 100, 100), error);
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let enabled = self.current_filename.is_some();
+                    if ui
+                        .add_enabled(enabled, egui::Button::new(tr!("cutlist-export-edl")))
+                        .clicked()
+                    {
+                        export = Some(true);
+                    }
+                    if ui
+                        .add_enabled(enabled, egui::Button::new(tr!("cutlist-export-ffmpeg")))
+                        .clicked()
+                    {
+                        export = Some(false);
+                    }
+                });
+            });
+        self.show_cutlist = open;
+
+        if let Some(edl) = export {
+            self.export_cutlist(edl);
+        }
+        if let Some(target_ms) = seek_target {
+            self.seek_to(target_ms);
+        }
+    }
+
+    /// Saves the cutlist as a CMX3600 EDL, or with `edl` false as FFmpeg commands.
+    fn export_cutlist(&mut self, edl: bool) {
+        let Some(filename) = self.current_filename.clone() else {
+            return;
+        };
+        let stem = Path::new(&filename)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "cutlist".to_string());
+        let cuts = self.cutlist.cuts(self.duration_ms(), &stem);
+        if cuts.is_empty() {
+            self.cutlist_error = Some(tr!("cutlist-empty-range"));
+            return;
+        }
+
+        let (content, filter, extension) = if edl {
+            let fps = self
+                .video
+                .as_ref()
+                .map_or(cutlist::AUDIO_EDL_FPS, |video| video.stream_frame_rate());
+            (
+                cutlist::to_edl(&filename, &cuts, fps, self.video.is_some()),
+                tr!("filter-edl"),
+                "edl",
+            )
+        } else {
+            (
+                cutlist::to_ffmpeg_commands(&filename, &cuts),
+                tr!("filter-text"),
+                "txt",
+            )
+        };
+
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter(filter, &[extension])
+            .set_file_name(format!("{}.{}", stem, extension))
+            .save_file()
+        {
+            self.cutlist_error = std::fs::write(&path, content)
+                .err()
+                .map(|e| tr!("error-cannot-export", error = e));
+        }
+    }
+
+    fn open_annotation_panel(&self) -> Option<AnnotationPanel> {
+        let media_path = self.media_path()?.clone();
+        Some(AnnotationPanel {
+            annotations: self.annotations.get(&media_path).to_vec(),
+            media_path,
+            draft: String::new(),
+            draft_ms: None,
+            focus_requested: true,
+            error: None,
+        })
+    }
+
+    fn show_annotations_window(&mut self, ctx: &egui::Context) {
+        let Some(mut panel) = self.annotation_panel.take() else {
+            return;
+        };
+        // Follows the player to the next file
+        if self.media_path() != Some(&panel.media_path) {
+            match self.open_annotation_panel() {
+                Some(next) => panel = next,
+                None => return,
+            }
+        }
+
+        let mut open = true;
+        let mut changed = false;
+        let mut seek_target = None;
+        let mut export = None;
+        let current_ms = self.current_time_ms();
+
+        egui::Window::new(tr!("window-annotations"))
+            .open(&mut open)
+            .default_size([440.0, 360.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let time_ms = panel.draft_ms.unwrap_or(current_ms);
+                    ui.label(Self::format_time(time_ms));
+                    let response = ui.add(
+                        egui::TextEdit::singleline(&mut panel.draft)
+                            .hint_text(tr!("annotations-hint"))
+                            .desired_width(280.0),
+                    );
+                    if !panel.focus_requested {
+                        response.request_focus();
+                        panel.focus_requested = true;
+                    }
+                    if response.changed() && panel.draft_ms.is_none() {
+                        panel.draft_ms = Some(current_ms);
+                    }
+                    let submitted =
+                        response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    if (ui.button(tr!("annotations-add")).clicked() || submitted)
+                        && !panel.draft.trim().is_empty()
+                    {
+                        panel.annotations.push(annotations::Annotation {
+                            time_ms,
+                            text: panel.draft.trim().to_string(),
+                        });
+                        panel
+                            .annotations
+                            .sort_by_key(|annotation| annotation.time_ms);
+                        panel.draft.clear();
+                        panel.draft_ms = None;
+                        changed = true;
+                    }
+                });
+
+                ui.separator();
+                if panel.annotations.is_empty() {
+                    ui.weak(tr!("annotations-empty"));
+                }
+                let mut remove = None;
+                egui::ScrollArea::vertical()
+                    .max_height(240.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("annotations")
+                            .num_columns(3)
+                            .show(ui, |ui| {
+                                for (index, annotation) in panel.annotations.iter_mut().enumerate()
+                                {
+                                    if ui.link(Self::format_time(annotation.time_ms)).clicked() {
+                                        seek_target = Some(annotation.time_ms);
+                                    }
+                                    let response = ui.add(
+                                        egui::TextEdit::singleline(&mut annotation.text)
+                                            .desired_width(280.0),
+                                    );
+                                    changed |= response.lost_focus();
+                                    if Self::label_control(
+                                        ui.small_button("🗑"),
+                                        tr!("annotations-remove"),
+                                    )
+                                    .clicked()
+                                    {
+                                        remove = Some(index);
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+                if let Some(index) = remove {
+                    panel.annotations.remove(index);
+                    changed = true;
+                }
+
+                if let Some(error) = &panel.error {
+                    ui.colored_label(egui::Color32::from_rgb(255, 100, 100), error);
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label(tr!("annotations-export"));
+                    for format in annotations::ExportFormat::ALL {
+                        if ui
+                            .add_enabled(
+                                !panel.annotations.is_empty(),
+                                egui::Button::new(format.name()),
+                            )
+                            .clicked()
+                        {
+                            export = Some(format);
+                        }
+                    }
+                });
+            });
+
+        if changed {
+            self.annotations
+                .set(&panel.media_path, panel.annotations.clone());
+        }
+        if let Some(format) = export {
+            let stem = Path::new(&panel.media_path)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "annotations".to_string());
+            if let Some(path) = rfd::FileDialog::new()
+                .add_filter(format.name(), &[format.extension()])
+                .set_file_name(format!("{}_notes.{}", stem, format.extension()))
+                .save_file()
+            {
+                panel.error = annotations::export(&path, format, &panel.annotations)
+                    .err()
+                    .map(|e| tr!("error-cannot-export", error = e));
+            }
+        }
+        if let Some(target_ms) = seek_target {
+            self.seek_to(target_ms);
+        }
+        if open {
+            self.annotation_panel = Some(panel);
+        }
+    }
+
+    fn show_storage_window(&mut self, ctx: &egui::Context) {
+        let Some(usage) = &self.storage_usage else {
+            return;
+        };
+        let mut open = true;
+        let mut clear = false;
+        let mut trim = false;
+        let folder = |dir: Option<PathBuf>| {
+            dir.map(|dir| dir.display().to_string())
+                .unwrap_or_else(|| tr!("storage-no-folder"))
+        };
+
+        egui::Window::new(tr!("window-storage"))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("storage_folders")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label(tr!("storage-config-folder"));
+                        ui.label(folder(storage::config_dir()));
+                        ui.end_row();
+
+                        ui.label(tr!("storage-cache-folder"));
+                        ui.label(folder(storage::cache_dir()));
+                        ui.end_row();
+                    });
+
+                ui.separator();
+                egui::Grid::new("storage_usage")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        for (kind, size) in &usage.kinds {
+                            ui.label(kind.name());
+                            ui.label(storage::format_size(*size));
+                            ui.end_row();
+                        }
+                        ui.strong(tr!("storage-total"));
+                        ui.strong(storage::format_size(usage.total));
+                        ui.end_row();
+                    });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label(tr!("storage-cache-limit"));
+                    let response = ui.add(
+                        egui::DragValue::new(&mut self.settings.cache_limit_mb)
+                            .range(0..=1_000_000)
+                            .speed(16)
+                            .suffix(" MB"),
+                    );
+                    trim = response.drag_stopped() || response.lost_focus();
+                })
+                .response
+                .on_hover_text(tr!("storage-cache-limit-hint"));
+
+                if ui.button(tr!("storage-clear-cache")).clicked() {
+                    clear = true;
+                }
+            });
+
+        if clear {
+            let freed = storage::clear_cache();
+            self.show_osd(
+                tr!("osd-cache-cleared", size = storage::format_size(freed)),
+                egui::Color32::WHITE,
+            );
+        } else if trim {
+            storage::trim_cache(self.settings.cache_limit_mb);
+        }
+
+        self.storage_usage = if !open {
+            None
+        } else if clear || trim {
+            Some(storage::cache_usage())
+        } else {
+            self.storage_usage.take()
+        };
+    }
+
+    fn show_diagnostics_window(&mut self, ctx: &egui::Context) {
+        let Some(diagnostics) = &mut self.diagnostics else {
+            return;
+        };
+        let collecting = diagnostics.update();
+        if collecting {
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
+
+        let mut open = true;
+        egui::Window::new(tr!("window-diagnostics"))
+            .open(&mut open)
+            .default_size([560.0, 480.0])
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(!collecting, egui::Button::new(tr!("diagnostics-copy")))
+                        .clicked()
+                    {
+                        ctx.copy_text(diagnostics.report());
+                    }
+                    if collecting {
+                        ui.spinner();
+                        ui.label(tr!("diagnostics-collecting"));
+                    }
+                });
+                ui.separator();
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for (index, section) in diagnostics.sections().iter().enumerate() {
+                        egui::CollapsingHeader::new(&section.title)
+                            .default_open(true)
+                            .show(ui, |ui| {
+                                egui::Grid::new(("diagnostics", index))
+                                    .num_columns(2)
+                                    .striped(true)
+                                    .show(ui, |ui| {
+                                        for (label, value) in &section.entries {
+                                            ui.label(label);
+                                            ui.add(egui::Label::new(value).wrap());
+                                            ui.end_row();
+                                        }
+                                    });
+                            });
+                    }
+                });
+            });
+
+        if !open {
+            self.diagnostics = None;
+        }
+    }
+
+    fn show_scene_detection_window(&mut self, ctx: &egui::Context) {
+        if !self.show_scene_detection {
+            return;
+        }
+
+        let mut open = self.show_scene_detection;
+        let mut start = false;
+        let mut use_as_chapters = false;
+        let mut seek_target = None;
+        let current_ms = self.file_time_ms();
+        let chapter_count = self.chapters().len();
+        let is_url = self
+            .current_filename
+            .as_deref()
+            .is_some_and(playlist::is_url);
+
+        egui::Window::new(tr!("window-scene-detection"))
+            .open(&mut open)
+            .default_size([560.0, 440.0])
+            .show(ctx, |ui| {
+                if self.video.is_none() {
+                    ui.label(tr!("scenes-no-video"));
+                    return;
+                }
+                if is_url || self.timeline.is_some() {
+                    ui.label(tr!("scenes-local-only"));
+                    return;
+                }
+
+                ui.label(tr!("scenes-description"));
+                ui.add(
+                    egui::Slider::new(&mut self.settings.scene_threshold, 0.02..=0.5)
+                        .text(tr!("scenes-threshold"))
+                        .fixed_decimals(2),
+                )
+                .on_hover_text(tr!("scenes-threshold-hint"));
+
+                ui.horizontal(|ui| {
+                    let label = if self.scene_detection.is_some() {
+                        tr!("scenes-detect-again")
+                    } else {
+                        tr!("button-start")
+                    };
+                    if ui.button(label).clicked() {
+                        start = true;
+                    }
+                    let has_chapters = self
+                        .scene_detection
+                        .as_ref()
+                        .is_some_and(|detection| detection.chapters().is_some());
+                    if ui
+                        .add_enabled(
+                            has_chapters,
+                            egui::Button::new(tr!("scenes-use-as-chapters")),
+                        )
+                        .on_hover_text(tr!("scenes-use-as-chapters-hint", count = chapter_count))
+                        .clicked()
+                    {
+                        use_as_chapters = true;
+                    }
+                });
+                ui.add_space(8.0);
+
+                if let Some(detection) = &self.scene_detection {
+                    seek_target = detection.show(ui, current_ms);
+                }
+            });
+        self.show_scene_detection = open;
+
+        if start {
+            if let Some(filename) = &self.current_filename {
+                self.scene_detection = Some(scene_detect::SceneDetection::start(
+                    filename,
+                    self.duration_ms(),
+                    self.settings.scene_threshold,
+                ));
+            }
+        }
+        if use_as_chapters {
+            let chapters = self
+                .scene_detection
+                .as_ref()
+                .and_then(|detection| detection.chapters());
+            if let (Some(chapters), Some(media_info)) = (chapters, &mut self.media_info) {
+                media_info.chapters = chapters;
+            }
+        }
+        if let Some(target_ms) = seek_target {
+            self.seek_to(target_ms);
+        }
+    }
+
+    fn save_integrity_report(&mut self) {
+        let Some(check) = &self.integrity_check else {
+            return;
+        };
+        let Some(report) = check.report() else {
+            return;
+        };
+
+        let filename = check.filename();
+        let default_name = Path::new(filename)
+            .file_stem()
+            .map(|stem| format!("{}_check.txt", stem.to_string_lossy()))
+            .unwrap_or_else(|| "check.txt".to_string());
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter(tr!("filter-text"), &["txt"])
+            .set_file_name(&default_name)
+            .save_file()
+        else {
+            return;
+        };
+
+        match std::fs::write(&path, report.to_text(filename)) {
+            Ok(()) => self.show_osd(tr!("osd-report-saved"), egui::Color32::WHITE),
+            Err(e) => {
+                eprintln!("Failed to save report: {}", e);
+                self.show_osd(
+                    tr!("osd-report-failed", error = e),
+                    egui::Color32::LIGHT_RED,
+                );
+            }
+        }
+    }
+
+    fn show_goto_dialog(&mut self, ctx: &egui::Context) {
+        let mut dialog = match self.goto_dialog.take() {
+            Some(dialog) => dialog,
+            None => return,
+        };
+        let mut submit = false;
+        let mut close = false;
+
+        egui::Window::new(tr!("window-go-to-time"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(tr!("go-to-time-hint"));
+
+                let response =
+                    ui.add(egui::TextEdit::singleline(&mut dialog.input).hint_text("00:00:00.000"));
+                if !dialog.focus_requested {
+                    response.request_focus();
+                    dialog.focus_requested = true;
+                }
+                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    submit = true;
+                }
+
+                if let Some(error) = &dialog.error {
+                    ui.colored_label(egui::Color32::from_rgb(255, 100, 100), error);
+                }
+
+                ui.horizontal(|ui| {
+                    if ui.button(tr!("button-go")).clicked() {
+                        submit = true;
+                    }
+                    if ui.button(tr!("button-cancel")).clicked() {
+                        close = true;
+                    }
+                });
+            });
+
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            close = true;
+        }
+
+        if submit {
+            let duration_ms = self.duration_ms();
+
+            match Self::parse_time(&dialog.input) {
+                // Live and other streams of unknown length have no end to check against
+                Some(target_ms) if duration_ms <= 0 || target_ms <= duration_ms => {
+                    self.seek_to(target_ms);
+                    close = true;
+                }
+                Some(_) => {
+                    dialog.error = Some(tr!(
+                        "go-to-time-past-end",
+                        duration = Self::format_time(duration_ms)
+                    ));
+                }
+                None => {
+                    dialog.error = Some(tr!("error-invalid-time"));
+                }
+            }
+        }
+
+        if !close {
+            self.goto_dialog = Some(dialog);
+        }
+    }
+
+    fn show_error_dialog(&mut self, ctx: &egui::Context) {
+        let dialog = match &self.error_dialog {
+            Some(dialog) => dialog,
+            None => return,
+        };
+        let mut close = false;
+
+        // Swallow clicks on everything behind the dialog
+        egui::Area::new(egui::Id::new("error_dialog_backdrop"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(egui::Pos2::ZERO)
+            .show(ctx, |ui| {
+                let screen = ctx.screen_rect();
+                ui.allocate_rect(screen, egui::Sense::click_and_drag());
+                ui.painter().rect_filled(
+                    screen,
+                    egui::Rounding::ZERO,
+                    egui::Color32::from_black_alpha(120),
+                );
+            });
+
+        egui::Window::new(&dialog.title)
+            .id(egui::Id::new("error_dialog"))
+            .order(egui::Order::Tooltip)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.colored_label(egui::Color32::from_rgb(255, 100, 100), &dialog.message);
+                ui.add_space(10.0);
+                if ui.button(tr!("button-ok")).clicked() {
+                    close = true;
+                }
+            });
+
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape) || i.key_pressed(egui::Key::Enter)) {
+            close = true;
+        }
+
+        if close {
+            self.error_dialog = None;
+        }
+    }
+
+    fn show_url_dialog(&mut self, ctx: &egui::Context) {
+        let mut dialog = match self.url_dialog.take() {
+            Some(dialog) => dialog,
+            None => return,
+        };
+        let mut submit = false;
+        let mut close = false;
+
+        egui::Window::new(tr!("window-open-url"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(tr!("open-url-hint"));
+
+                ui.horizontal(|ui| {
+                    let response = ui.add(
+                        egui::TextEdit::singleline(&mut dialog.input)
+                            .hint_text("https://example.com/video.mp4")
+                            .desired_width(360.0),
+                    );
+                    if !dialog.focus_requested {
+                        response.request_focus();
+                        dialog.focus_requested = true;
+                    }
+                    if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                        submit = true;
+                    }
+
+                    if ui.button(tr!("button-paste")).clicked() {
+                        if let Some(text) = Self::clipboard_text() {
+                            dialog.input = text;
+                            dialog.error = None;
+                        }
+                    }
+                });
+
+                egui::CollapsingHeader::new(tr!("open-url-http-options"))
+                    .default_open(!dialog.http.is_empty())
+                    .show(ui, |ui| Self::http_options_grid(ui, &mut dialog.http));
+
+                if let Some(error) = &dialog.error {
+                    ui.colored_label(egui::Color32::from_rgb(255, 100, 100), error);
+                }
+
+                ui.horizontal(|ui| {
+                    if ui.button(tr!("button-open")).clicked() {
+                        submit = true;
+                    }
+                    if ui.button(tr!("button-cancel")).clicked() {
+                        close = true;
+                    }
+                });
+            });
+
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            close = true;
+        }
+
+        if submit {
+            match Self::validate_url(&dialog.input) {
+                Ok(url) => {
+                    http::set_url_options(&url, dialog.http.clone());
+                    self.open_file(&url);
+                    close = true;
+                }
+                Err(error) => dialog.error = Some(error),
+            }
+        }
+
+        if !close {
+            self.url_dialog = Some(dialog);
+        }
+    }
+
+    fn show_raw_stream_dialog(&mut self, ctx: &egui::Context) {
+        let mut dialog = match self.raw_stream_dialog.take() {
+            Some(dialog) => dialog,
+            None => return,
+        };
+        let mut submit = false;
+        let mut close = false;
+
+        egui::Window::new(tr!("window-raw-stream"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(tr!("raw-stream-hint"));
+                let name = Path::new(&dialog.filename)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| dialog.filename.clone());
+                ui.label(egui::RichText::new(name).strong());
+                ui.add_space(4.0);
+
+                egui::Grid::new("raw_stream").num_columns(2).show(ui, |ui| {
+                    ui.label(tr!("raw-stream-format"));
+                    egui::ComboBox::from_id_salt("raw_stream_format")
+                        .selected_text(dialog.format.as_str())
+                        .show_ui(ui, |ui| {
+                            for (format, _, _) in raw_stream::FORMATS {
+                                ui.selectable_value(&mut dialog.format, format.to_string(), format);
+                            }
+                        });
+                    ui.end_row();
+
+                    ui.label(tr!("raw-stream-framerate"));
+                    ui.add_enabled(
+                        raw_stream::is_video_format(&dialog.format),
+                        egui::DragValue::new(&mut dialog.framerate)
+                            .range(1.0..=240.0)
+                            .speed(0.1)
+                            .suffix(" fps"),
+                    );
+                    ui.end_row();
+                });
+
+                ui.horizontal(|ui| {
+                    if ui.button(tr!("button-open")).clicked() {
+                        submit = true;
+                    }
+                    if ui.button(tr!("button-cancel")).clicked() {
+                        close = true;
+                    }
+                });
+            });
+
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            close = true;
+        }
+
+        if submit {
+            let hint = raw_stream::FormatHint {
+                framerate: raw_stream::is_video_format(&dialog.format).then_some(dialog.framerate),
+                format: Some(dialog.format),
+            };
+            raw_stream::set_hint(&dialog.filename, Some(hint));
+            if let Err(e) = self.load_video(&dialog.filename) {
+                self.show_error(&tr!("error-cannot-open", file = dialog.filename), e);
+            }
+            return;
+        }
+
+        if !close {
+            self.raw_stream_dialog = Some(dialog);
+        }
+    }
+
+    fn http_options_grid(ui: &mut egui::Ui, options: &mut http::HttpOptions) {
+        egui::Grid::new("http_options")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label(tr!("http-user-agent"));
+                ui.add(egui::TextEdit::singleline(&mut options.user_agent).desired_width(280.0));
+                ui.end_row();
+
+                ui.label(tr!("http-headers"));
+                ui.add(
+                    egui::TextEdit::multiline(&mut options.headers)
+                        .hint_text("Referer: https://example.com/")
+                        .desired_rows(2)
+                        .desired_width(280.0),
+                );
+                ui.end_row();
+
+                ui.label(tr!("http-cookies-file"));
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut options.cookies_file)
+                            .hint_text("cookies.txt")
+                            .desired_width(200.0),
+                    );
+                    if ui.button(tr!("button-choose")).clicked() {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter(tr!("filter-cookies"), &["txt"])
+                            .add_filter(tr!("filter-all-files"), &["*"])
+                            .pick_file()
+                        {
+                            options.cookies_file = path.to_string_lossy().into_owned();
+                        }
+                    }
+                });
+                ui.end_row();
+
+                ui.label(tr!("http-username"));
+                ui.add(egui::TextEdit::singleline(&mut options.username).desired_width(280.0));
+                ui.end_row();
+
+                ui.label(tr!("http-password"));
+                ui.add(
+                    egui::TextEdit::singleline(&mut options.password)
+                        .password(true)
+                        .desired_width(280.0),
+                );
+                ui.end_row();
+            });
+    }
+
+    fn clipboard_text() -> Option<String> {
+        let text = arboard::Clipboard::new().ok()?.get_text().ok()?;
+        let text = text.trim();
+        (!text.is_empty()).then(|| text.to_string())
+    }
+
+    fn validate_url(input: &str) -> Result<String, String> {
+        let url = input.trim();
+
+        if url.is_empty() {
+            return Err(tr!("open-url-empty"));
+        }
+        if url.chars().any(char::is_whitespace) {
+            return Err(tr!("open-url-spaces"));
+        }
+        if !playlist::is_url(url) {
+            return Err(tr!("open-url-invalid"));
+        }
+        if url
+            .split_once("://")
+            .is_some_and(|(_, rest)| rest.is_empty())
+        {
+            return Err(tr!("open-url-no-host"));
+        }
+
+        Ok(url.to_string())
+    }
+
+    fn start_export(&self, dialog: &mut ExportDialog) {
+        let filename = match &self.current_filename {
+            Some(filename) => filename.clone(),
+            None => return,
+        };
+
+        let (start_ms, end_ms) = match (
+            Self::parse_time(&dialog.start),
+            Self::parse_time(&dialog.end),
+        ) {
+            (Some(start_ms), Some(end_ms)) if start_ms < end_ms => (start_ms, end_ms),
+            (Some(_), Some(_)) => {
+                dialog.error = Some(tr!("error-end-before-start"));
+                return;
+            }
+            _ => {
+                dialog.error = Some(tr!("error-invalid-time"));
+                return;
+            }
+        };
+
+        let fps = match dialog.fps.trim() {
+            "" => None,
+            fps => match fps.parse::<f64>() {
+                Ok(fps) if fps > 0.0 => Some(fps),
+                _ => {
+                    dialog.error = Some(tr!("export-invalid-frame-rate"));
+                    return;
+                }
+            },
+        };
+
+        let output_dir = match &dialog.output_dir {
+            Some(output_dir) => output_dir.clone(),
+            None => {
+                dialog.error = Some(tr!("export-choose-folder"));
+                return;
+            }
+        };
+
+        dialog.error = None;
+        dialog.progress = 0.0;
+        dialog.frames = 0;
+        dialog.job = Some(export::ExportJob::start(export::ExportSettings {
+            filename,
+            start_ms,
+            end_ms,
+            fps,
+            format: dialog.format,
+            output_dir,
+        }));
+    }
+
+    fn show_export_dialog(&mut self, ctx: &egui::Context) {
+        let mut dialog = match self.export_dialog.take() {
+            Some(dialog) => dialog,
+            None => return,
+        };
+        let mut start = false;
+        let mut close = false;
+
+        if let Some(job) = &dialog.job {
+            let mut finished = None;
+
+            while let Ok(event) = job.receiver.try_recv() {
+                match event {
+                    export::ExportEvent::Progress {
+                        frames,
+                        position_ms,
+                    } => {
+                        let start_ms = Self::parse_time(&dialog.start).unwrap_or(0);
+                        let end_ms = Self::parse_time(&dialog.end).unwrap_or(0);
+                        dialog.frames = frames;
+                        dialog.progress = ((position_ms - start_ms) as f32
+                            / (end_ms - start_ms).max(1) as f32)
+                            .clamp(0.0, 1.0);
+                    }
+                    export::ExportEvent::Finished(result) => finished = Some(result),
+                }
+            }
+
+            match finished {
+                Some(Ok(frames)) => {
+                    dialog.job = None;
+                    dialog.frames = frames;
+                    dialog.progress = 1.0;
+                }
+                Some(Err(e)) => {
+                    dialog.job = None;
+                    dialog.error = Some(e);
+                }
+                None => ctx.request_repaint_after(Duration::from_millis(100)),
+            }
+        }
+
+        let running = dialog.job.is_some();
+
+        egui::Window::new(tr!("window-export-frames"))
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.add_enabled_ui(!running, |ui| {
+                    egui::Grid::new("export_options")
+                        .num_columns(2)
+                        .show(ui, |ui| {
+                            ui.label(tr!("label-start"));
+                            ui.add(
+                                egui::TextEdit::singleline(&mut dialog.start)
+                                    .hint_text("00:00:00.000"),
+                            );
+                            ui.end_row();
+
+                            ui.label(tr!("label-end"));
+                            ui.add(
+                                egui::TextEdit::singleline(&mut dialog.end)
+                                    .hint_text("00:00:00.000"),
+                            );
+                            ui.end_row();
+
+                            ui.label(tr!("export-frame-rate"));
+                            ui.add(
+                                egui::TextEdit::singleline(&mut dialog.fps)
+                                    .hint_text(tr!("export-every-frame")),
+                            );
+                            ui.end_row();
+
+                            ui.label(tr!("export-format"));
+                            ui.horizontal(|ui| {
+                                ui.radio_value(&mut dialog.format, export::ImageFormat::Png, "PNG");
+                                ui.radio_value(
+                                    &mut dialog.format,
+                                    export::ImageFormat::Jpeg,
+                                    "JPEG",
+                                );
+                            });
+                            ui.end_row();
+
+                            ui.label(tr!("export-output-folder"));
+                            ui.horizontal(|ui| {
+                                if ui.button(tr!("button-choose")).clicked() {
+                                    if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                                        dialog.output_dir = Some(path);
+                                    }
+                                }
+                                if let Some(output_dir) = &dialog.output_dir {
+                                    ui.label(output_dir.to_string_lossy());
+                                }
+                            });
+                            ui.end_row();
+                        });
+                });
+
+                if running || dialog.frames > 0 {
+                    ui.add(
+                        egui::ProgressBar::new(dialog.progress)
+                            .text(tr!("export-progress", count = dialog.frames)),
+                    );
+                }
+
+                if let Some(error) = &dialog.error {
+                    ui.colored_label(egui::Color32::from_rgb(255, 100, 100), error);
+                }
+
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(!running, egui::Button::new(tr!("button-export")))
+                        .clicked()
+                    {
+                        start = true;
+                    }
+                    if running {
+                        if ui.button(tr!("button-cancel")).clicked() {
+                            if let Some(job) = &dialog.job {
+                                job.cancel();
+                            }
+                        }
+                    } else if ui.button(tr!("button-close")).clicked() {
+                        close = true;
+                    }
+                });
+            });
+
+        if !running && ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            close = true;
+        }
+
+        if start {
+            self.start_export(&mut dialog);
+        }
+
+        if !close {
+            self.export_dialog = Some(dialog);
+        }
+    }
+
+    fn start_transcode(&self, dialog: &mut TranscodeDialog) {
+        let filename = match &self.current_filename {
+            Some(filename) => filename.clone(),
+            None => return,
+        };
+
+        let (start_ms, end_ms) = match (
+            Self::parse_time(&dialog.start),
+            Self::parse_time(&dialog.end),
+        ) {
+            (Some(start_ms), Some(end_ms)) if start_ms < end_ms => (start_ms, end_ms),
+            (Some(_), Some(_)) => {
+                dialog.error = Some(tr!("error-end-before-start"));
+                return;
+            }
+            _ => {
+                dialog.error = Some(tr!("error-invalid-time"));
+                return;
+            }
+        };
+
+        let rate_control = if dialog.use_crf {
+            transcode::RateControl::Crf(dialog.crf)
+        } else {
+            match dialog.video_bitrate.trim().parse::<usize>() {
+                Ok(kbps) if kbps > 0 => transcode::RateControl::Bitrate(kbps),
+                _ => {
+                    dialog.error = Some(tr!("transcode-invalid-bitrate"));
+                    return;
+                }
+            }
+        };
+
+        let output = match &dialog.output {
+            Some(output) => output.clone(),
+            None => {
+                dialog.error = Some(tr!("transcode-choose-file"));
+                return;
+            }
+        };
+
+        dialog.error = None;
+        dialog.progress = 0.0;
+        dialog.finished = false;
+        dialog.job = Some(transcode::TranscodeJob::start(
+            transcode::TranscodeSettings {
+                filename,
+                output,
+                start_ms,
+                end_ms,
+                video_stream: self.video.as_ref().map(|video| video.stream_index()),
+                video_codec: dialog.video_codec,
+                rate_control,
+                height: dialog.height,
+                audio_codec: dialog.audio_codec,
+                audio_bitrate_kbps: dialog.audio_bitrate,
+            },
+        ));
+    }
+
+    fn show_transcode_dialog(&mut self, ctx: &egui::Context) {
+        let mut dialog = match self.transcode_dialog.take() {
+            Some(dialog) => dialog,
+            None => return,
+        };
+        let mut start = false;
+        let mut close = false;
+
+        if let Some(job) = &dialog.job {
+            let mut finished = None;
+
+            while let Ok(event) = job.receiver.try_recv() {
+                match event {
+                    transcode::TranscodeEvent::Progress(position_ms) => {
+                        let start_ms = Self::parse_time(&dialog.start).unwrap_or(0);
+                        let end_ms = Self::parse_time(&dialog.end).unwrap_or(0);
+                        dialog.progress = (position_ms as f32 / (end_ms - start_ms).max(1) as f32)
+                            .clamp(0.0, 1.0);
+                    }
+                    transcode::TranscodeEvent::Finished(result) => finished = Some(result),
+                }
+            }
+
+            match finished {
+                Some(Ok(())) => {
+                    dialog.job = None;
+                    dialog.progress = 1.0;
+                    dialog.finished = true;
+                }
+                Some(Err(e)) => {
+                    dialog.job = None;
+                    dialog.error = Some(e);
+                }
+                None => ctx.request_repaint_after(Duration::from_millis(100)),
+            }
+        }
+
+        let running = dialog.job.is_some();
+        let default_name = self
+            .current_filename
+            .as_deref()
+            .and_then(|filename| Path::new(filename).file_stem())
+            .map(|stem| format!("{}_export.mp4", stem.to_string_lossy()))
+            .unwrap_or_else(|| "export.mp4".to_string());
+
+        egui::Window::new(tr!("window-transcode"))
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.add_enabled_ui(!running, |ui| {
+                    egui::Grid::new("transcode_options")
+                        .num_columns(2)
+                        .show(ui, |ui| {
+                            ui.label(tr!("label-start"));
+                            ui.add(
+                                egui::TextEdit::singleline(&mut dialog.start)
+                                    .hint_text("00:00:00.000"),
+                            );
+                            ui.end_row();
+
+                            ui.label(tr!("label-end"));
+                            ui.add(
+                                egui::TextEdit::singleline(&mut dialog.end)
+                                    .hint_text("00:00:00.000"),
+                            );
+                            ui.end_row();
+
+                            ui.label(tr!("transcode-video-codec"));
+                            ui.horizontal(|ui| {
+                                for codec in transcode::VideoCodec::ALL {
+                                    if ui
+                                        .radio_value(&mut dialog.video_codec, codec, codec.name())
+                                        .changed()
+                                    {
+                                        dialog.crf = codec.default_crf();
+                                    }
+                                }
+                            });
+                            ui.end_row();
+
+                            ui.label(tr!("transcode-quality"));
+                            ui.horizontal(|ui| {
+                                ui.radio_value(&mut dialog.use_crf, true, "CRF");
+                                ui.radio_value(
+                                    &mut dialog.use_crf,
+                                    false,
+                                    tr!("transcode-bitrate"),
+                                );
+                            });
+                            ui.end_row();
+
+                            if dialog.use_crf {
+                                ui.label("CRF:");
+                                ui.add(
+                                    egui::Slider::new(
+                                        &mut dialog.crf,
+                                        0..=dialog.video_codec.max_crf(),
+                                    )
+                                    .text(tr!("transcode-crf-hint")),
+                                );
+                            } else {
+                                ui.label(tr!("transcode-video-bitrate"));
+                                ui.horizontal(|ui| {
+                                    ui.add(
+                                        egui::TextEdit::singleline(&mut dialog.video_bitrate)
+                                            .desired_width(80.0),
+                                    );
+                                    ui.label("kbps");
+                                });
+                            }
+                            ui.end_row();
+
+                            ui.label(tr!("transcode-resolution"));
+                            let resolution_label = |height: Option<u32>| match height {
+                                Some(height) => format!("{}p", height),
+                                None => tr!("transcode-original-resolution"),
+                            };
+                            egui::ComboBox::from_id_salt("transcode_height")
+                                .selected_text(resolution_label(dialog.height))
+                                .show_ui(ui, |ui| {
+                                    for height in [
+                                        None,
+                                        Some(2160),
+                                        Some(1440),
+                                        Some(1080),
+                                        Some(720),
+                                        Some(480),
+                                        Some(360),
+                                    ] {
+                                        ui.selectable_value(
+                                            &mut dialog.height,
+                                            height,
+                                            resolution_label(height),
+                                        );
+                                    }
+                                });
+                            ui.end_row();
+
+                            ui.label(tr!("transcode-audio-codec"));
+                            ui.horizontal(|ui| {
+                                for codec in transcode::AudioCodec::ALL {
+                                    ui.radio_value(&mut dialog.audio_codec, codec, codec.name());
+                                }
+                            });
+                            ui.end_row();
+
+                            ui.label(tr!("transcode-audio-bitrate"));
+                            ui.add(
+                                egui::Slider::new(&mut dialog.audio_bitrate, 32..=512)
+                                    .suffix(" kbps"),
+                            );
+                            ui.end_row();
+
+                            ui.label(tr!("transcode-output-file"));
+                            ui.horizontal(|ui| {
+                                if ui.button(tr!("button-choose")).clicked() {
+                                    if let Some(path) = rfd::FileDialog::new()
+                                        .add_filter("MP4", &["mp4"])
+                                        .add_filter("Matroska", &["mkv"])
+                                        .add_filter("WebM (AV1 + Opus)", &["webm"])
+                                        .set_file_name(&default_name)
+                                        .save_file()
+                                    {
+                                        dialog.output = Some(path);
+                                    }
+                                }
+                                if let Some(output) = &dialog.output {
+                                    ui.label(output.to_string_lossy());
+                                }
+                            });
+                            ui.end_row();
+                        });
+                });
+
+                if running || dialog.finished {
+                    let paused = dialog.job.as_ref().is_some_and(|job| job.is_paused());
+                    let text = if dialog.finished {
+                        tr!("transcode-done")
+                    } else if paused {
+                        tr!("transcode-paused")
+                    } else {
+                        format!("{:.0}%", dialog.progress * 100.0)
+                    };
+                    ui.add(egui::ProgressBar::new(dialog.progress).text(text));
+                }
+
+                if let Some(error) = &dialog.error {
+                    ui.colored_label(egui::Color32::from_rgb(255, 100, 100), error);
+                }
+
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(!running, egui::Button::new(tr!("button-start")))
+                        .clicked()
+                    {
+                        start = true;
+                    }
+                    if let Some(job) = &dialog.job {
+                        let label = if job.is_paused() {
+                            tr!("button-resume")
+                        } else {
+                            tr!("button-pause")
+                        };
+                        if ui.button(label).clicked() {
+                            job.set_paused(!job.is_paused());
+                        }
+                        if ui.button(tr!("button-cancel")).clicked() {
+                            job.cancel();
+                        }
+                    } else if ui.button(tr!("button-close")).clicked() {
+                        close = true;
+                    }
+                });
+            });
+
+        if !running && ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            close = true;
+        }
+
+        if start {
+            self.start_transcode(&mut dialog);
+        }
+
+        if !close {
+            self.transcode_dialog = Some(dialog);
+        }
+    }
+
+    /// Video, audio and subtitle streams; attachments and data streams carry no packets to copy.
+    fn remux_streams(&self) -> Vec<(usize, String)> {
+        let Some(info) = &self.media_info else {
+            return Vec::new();
+        };
+
+        let video = info.video_streams.iter().map(|stream| {
+            let size = match (stream.width, stream.height) {
+                (Some(width), Some(height)) => format!(" {}x{}", width, height),
+                _ => String::new(),
+            };
+            (
+                stream.index,
+                format!(
+                    "{}: {}{}",
+                    tr!("stream-kind-video"),
+                    stream.codec_name,
+                    size
+                ),
+                &stream.metadata,
+            )
+        });
+        let audio = info.audio_streams.iter().map(|stream| {
+            (
+                stream.index,
+                format!("{}: {}", tr!("stream-kind-audio"), stream.codec_name),
+                &stream.metadata,
+            )
+        });
+        let subtitles = info.subtitle_streams.iter().map(|stream| {
+            (
+                stream.index,
+                format!("{}: {}", tr!("stream-kind-subtitles"), stream.codec_name),
+                &stream.metadata,
+            )
+        });
+
+        let mut streams: Vec<(usize, String)> = video
+            .chain(audio)
+            .chain(subtitles)
+            .map(|(index, description, metadata)| {
+                let mut label = tr!("stream-label", index = index, description = description);
+                for key in ["title", "language"] {
+                    if let Some(value) = metadata.get(key) {
+                        label.push_str(&format!(" [{}]", value));
+                    }
+                }
+                (index, label)
+            })
+            .collect();
+        streams.sort_by_key(|(index, _)| *index);
+        streams
+    }
+
+    fn start_remux(&self, dialog: &mut RemuxDialog) {
+        let filename = match &self.current_filename {
+            Some(filename) => filename.clone(),
+            None => return,
+        };
+
+        let streams: Vec<usize> = dialog
+            .streams
+            .iter()
+            .filter(|(_, _, selected)| *selected)
+            .map(|(index, _, _)| *index)
+            .collect();
+        if streams.is_empty() {
+            dialog.error = Some(tr!("remux-no-streams"));
+            return;
+        }
+
+        let extension = dialog.container.extension();
+        let default_name = Path::new(&filename)
+            .file_stem()
+            .map(|stem| format!("{}_remux.{}", stem.to_string_lossy(), extension))
+            .unwrap_or_else(|| format!("remux.{}", extension));
+        let mut file_dialog = rfd::FileDialog::new()
+            .add_filter(dialog.container.name(), &[extension])
+            .set_file_name(&default_name);
+        if let Some(dir) = Path::new(&filename).parent().filter(|dir| dir.is_dir()) {
+            file_dialog = file_dialog.set_directory(dir);
+        }
+        let Some(output) = file_dialog.save_file() else {
+            return;
+        };
+        if Path::new(&filename) == output {
+            dialog.error = Some(tr!("remux-same-file"));
+            return;
+        }
+
+        dialog.error = None;
+        dialog.progress = 0.0;
+        dialog.saved_to = None;
+        dialog.job = Some(remux::RemuxJob::start(remux::RemuxSettings {
+            filename,
+            output: output.clone(),
+            streams,
+            recording: false,
+        }));
+        dialog.saved_to = Some(output);
+    }
+
+    /// Starts copying the network stream being played to a file, or stops the recording.
+    fn toggle_recording(&mut self) {
+        if let Some(recording) = &self.recording {
+            // The muxer finishes the file on its thread; update_recording reports it
+            recording.job.cancel();
+            return;
+        }
+
+        let Some(url) = self
+            .media_path()
+            .filter(|path| playlist::is_url(path))
+            .cloned()
+        else {
+            return;
+        };
+        let streams = self
+            .remux_streams()
+            .into_iter()
+            .map(|(index, _)| index)
+            .collect();
+        let output = remux::recording_path(&url);
+        println!("Recording {} to {}", url, output.display());
+
+        self.recording = Some(Recording {
+            job: remux::RemuxJob::start(remux::RemuxSettings {
+                filename: url,
+                output: output.clone(),
+                streams,
+                recording: true,
+            }),
+            output,
+            started: Instant::now(),
+        });
+        self.show_osd(tr!("osd-recording-started"), egui::Color32::LIGHT_RED);
+    }
+
+    fn update_recording(&mut self) {
+        let Some(recording) = &self.recording else {
+            return;
+        };
+        let mut result = None;
+        while let Ok(event) = recording.job.receiver.try_recv() {
+            if let remux::RemuxEvent::Finished(finished) = event {
+                result = Some(finished);
+            }
+        }
+        let Some(result) = result else {
+            return;
+        };
+
+        let output = recording.output.clone();
+        self.recording = None;
+        match result {
+            Ok(()) => {
+                println!("Saved recording to {}", output.display());
+                let name = output
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                self.show_osd(
+                    tr!("osd-recording-saved", file = name),
+                    egui::Color32::WHITE,
+                );
+            }
+            Err(e) => self.show_error(&tr!("error-recording"), e),
+        }
+    }
+
+    fn show_remux_dialog(&mut self, ctx: &egui::Context) {
+        let mut dialog = match self.remux_dialog.take() {
+            Some(dialog) => dialog,
+            None => return,
+        };
+        let mut start = false;
+        let mut close = false;
+        let mut finished = false;
+
+        if let Some(job) = &dialog.job {
+            let mut result = None;
+
+            while let Ok(event) = job.receiver.try_recv() {
+                match event {
+                    remux::RemuxEvent::Progress(position_ms) => {
+                        dialog.progress =
+                            (position_ms as f32 / self.duration_ms().max(1) as f32).clamp(0.0, 1.0);
+                    }
+                    remux::RemuxEvent::Finished(finished) => result = Some(finished),
+                }
+            }
+
+            match result {
+                Some(Ok(())) => {
+                    dialog.job = None;
+                    dialog.progress = 1.0;
+                }
+                Some(Err(e)) => {
+                    dialog.job = None;
+                    dialog.saved_to = None;
+                    dialog.error = Some(e);
+                }
+                None => ctx.request_repaint_after(Duration::from_millis(100)),
+            }
+        } else if dialog.saved_to.is_some() {
+            finished = true;
+        }
+
+        let running = dialog.job.is_some();
+
+        egui::Window::new(tr!("window-remux"))
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(tr!("remux-description"));
+                ui.add_space(4.0);
+
+                ui.add_enabled_ui(!running, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(tr!("remux-container"));
+                        for container in remux::Container::ALL {
+                            ui.radio_value(&mut dialog.container, container, container.name());
+                        }
+                    });
+
+                    for (_, label, selected) in &mut dialog.streams {
+                        ui.checkbox(selected, label.as_str());
+                    }
+                });
+
+                if running || finished {
+                    ui.add(egui::ProgressBar::new(dialog.progress).show_percentage());
+                }
+                if let (true, Some(path)) = (finished, &dialog.saved_to) {
+                    ui.label(tr!("remux-saved", file = path.display()));
+                }
+
+                if let Some(error) = &dialog.error {
+                    ui.colored_label(egui::Color32::from_rgb(255, 100, 100), error);
+                }
+
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(!running, egui::Button::new(tr!("remux-start")))
+                        .clicked()
+                    {
+                        start = true;
+                    }
+                    if let Some(job) = &dialog.job {
+                        if ui.button(tr!("button-cancel")).clicked() {
+                            job.cancel();
+                        }
+                    } else if ui.button(tr!("button-close")).clicked() {
+                        close = true;
+                    }
+                });
+            });
+
+        if !running && ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            close = true;
+        }
+
+        if start {
+            self.start_remux(&mut dialog);
+        }
+
+        if !close {
+            self.remux_dialog = Some(dialog);
+        }
+    }
+
+    fn update_filmstrip(&mut self, ctx: &egui::Context) {
+        // Thumbnails cover a single file, which doesn't match an ordered edition
+        if !self.settings.show_filmstrip
+            || self.video.is_none()
+            || self.timeline.is_some()
+            || self.live.is_some()
+        {
+            self.filmstrip = None;
+            return;
+        }
+
+        if self.filmstrip.is_none() {
+            if let Some(filename) = &self.current_filename {
+                self.filmstrip = Some(filmstrip::Filmstrip::start(filename, self.duration_ms()));
+            }
+        }
+
+        if let Some(filmstrip) = &mut self.filmstrip {
+            if filmstrip.update(ctx) {
+                ctx.request_repaint_after(Duration::from_millis(100));
+            }
+        }
+    }
+
+    /// Loads the next playlist entry while the current one plays. Streams and discs are left
+    /// alone, as opening them is slow or costs traffic.
+    fn update_preload(&mut self, ctx: &egui::Context) {
+        let next = self
+            .playlist
+            .peek_next()
+            .filter(|next| !playlist::is_url(next) && !dvd::is_dvd(next));
+        if self.preload.as_ref().map(|preload| preload.filename()) != next {
+            self.preload = match next {
+                Some(next) if self.has_media() => Some(preload::Preload::start(
+                    next,
+                    self.settings.downmix,
+                    self.settings.audio_memory_mb,
+                )),
+                _ => None,
+            };
+        }
+
+        if let Some(preload) = &mut self.preload {
+            if preload.update(ctx) {
+                ctx.request_repaint_after(Duration::from_millis(100));
+            }
+        }
+    }
+
+    fn show_subtitle_ocr_window(&mut self, ctx: &egui::Context) {
+        if !self.show_subtitle_ocr {
+            return;
+        }
+
+        let streams = self.bitmap_subtitle_streams();
+        let mut open = self.show_subtitle_ocr;
+        let mut start = false;
+        let mut load = false;
+        let mut save = false;
+
+        egui::Window::new(tr!("window-subtitle-ocr"))
+            .open(&mut open)
+            .default_size([480.0, 380.0])
+            .show(ctx, |ui| {
+                if streams.is_empty() {
+                    ui.label(tr!("ocr-no-streams"));
+                    return;
+                }
+
+                ui.label(tr!("ocr-description"));
+                let selected = self
+                    .subtitle_ocr_stream
+                    .filter(|index| streams.iter().any(|(stream, _)| stream == index))
+                    .unwrap_or(streams[0].0);
+                let selected_label = streams
+                    .iter()
+                    .find(|(index, _)| *index == selected)
+                    .map(|(_, label)| label.clone())
+                    .unwrap_or_default();
+                egui::ComboBox::from_label(tr!("ocr-stream"))
+                    .selected_text(selected_label)
+                    .show_ui(ui, |ui| {
+                        for (index, label) in &streams {
+                            if ui.selectable_label(selected == *index, label).clicked() {
+                                self.subtitle_ocr_stream = Some(*index);
+                            }
+                        }
+                    });
+                ui.horizontal(|ui| {
+                    ui.label(tr!("ocr-language"));
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.settings.ocr_language)
+                            .desired_width(120.0),
+                    )
+                    .on_hover_text(tr!("ocr-language-hint"));
+                });
+
+                ui.horizontal(|ui| {
+                    if ui.button(tr!("button-start")).clicked() {
+                        self.subtitle_ocr_stream = Some(selected);
+                        start = true;
+                    }
+                    let finished = self
+                        .subtitle_ocr
+                        .as_ref()
+                        .is_some_and(|ocr| ocr.subtitles().is_some());
+                    if ui
+                        .add_enabled(finished, egui::Button::new(tr!("ocr-load")))
+                        .on_hover_text(tr!("ocr-load-hint"))
+                        .clicked()
+                    {
+                        load = true;
+                    }
+                    if ui
+                        .add_enabled(finished, egui::Button::new(tr!("ocr-save")))
+                        .clicked()
+                    {
+                        save = true;
+                    }
+                });
+                ui.add_space(8.0);
+
+                if let Some(ocr) = &self.subtitle_ocr {
+                    ocr.show(ui);
+                }
+            });
+        self.show_subtitle_ocr = open;
+
+        if start {
+            if let (Some(filename), Some(stream_index)) =
+                (&self.current_filename, self.subtitle_ocr_stream)
+            {
+                self.subtitle_ocr = Some(subtitle_ocr::SubtitleOcr::start(
+                    filename,
+                    stream_index,
+                    self.duration_ms(),
+                    self.settings.ocr_language.trim(),
+                ));
+            }
+        }
+        let subtitles = self
+            .subtitle_ocr
+            .as_ref()
+            .filter(|_| load || save)
+            .and_then(|ocr| ocr.subtitles());
+        let Some(subtitles) = subtitles else {
+            return;
+        };
+        if save {
+            self.save_ocr_subtitles(&subtitles);
+        }
+        if load {
+            // The text replaces the images, which would otherwise be drawn over it
+            let stream_index = self.subtitle_ocr.as_ref().map(|ocr| ocr.stream_index());
+            let showing_stream = self
+                .video
+                .as_ref()
+                .and_then(|video| video.subtitle_stream_index());
+            if showing_stream.is_some() && showing_stream == stream_index {
+                self.perform_action(Action::SelectSubtitleStream(None));
+            }
+            self.subtitles = Some(subtitles);
+            self.show_osd(tr!("osd-ocr-loaded"), egui::Color32::WHITE);
+        }
+    }
+
+    fn show_sync_analysis_window(&mut self, ctx: &egui::Context) {
+        if !self.show_sync_analysis {
+            return;
+        }
+
+        let mut open = self.show_sync_analysis;
+        let mut start = false;
+        let mut apply = None;
+        let is_url = self
+            .current_filename
+            .as_deref()
+            .is_some_and(playlist::is_url);
+
+        egui::Window::new(tr!("window-sync-analysis"))
+            .open(&mut open)
+            .default_size([420.0, 200.0])
+            .show(ctx, |ui| {
+                if self.video.is_none() || self.audio.is_none() {
+                    ui.label(tr!("sync-no-streams"));
+                    return;
+                }
+                if is_url || self.timeline.is_some() {
+                    ui.label(tr!("sync-local-only"));
+                    return;
+                }
+
+                ui.label(tr!("sync-description"));
+                ui.horizontal(|ui| {
+                    let label = if self.sync_analysis.is_some() {
+                        tr!("sync-measure-again")
+                    } else {
+                        tr!("button-start")
+                    };
+                    if ui.button(label).clicked() {
+                        start = true;
+                    }
+                    let estimate = self
+                        .sync_analysis
+                        .as_ref()
+                        .and_then(|analysis| analysis.estimate());
+                    if let Some(estimate) = estimate {
+                        if ui
+                            .add_enabled(
+                                estimate.delay_ms != self.audio_delay_ms,
+                                egui::Button::new(tr!("sync-apply", ms = estimate.delay_ms)),
+                            )
+                            .clicked()
+                        {
+                            apply = Some(estimate.delay_ms);
+                        }
+                    }
+                });
+                ui.add_space(8.0);
+
+                if let Some(analysis) = &self.sync_analysis {
+                    analysis.show(ui);
+                }
+            });
+        self.show_sync_analysis = open;
+
+        if start {
+            if let Some(filename) = &self.current_filename {
+                self.sync_analysis = Some(sync_analysis::SyncAnalysis::start(
+                    filename,
+                    self.duration_ms(),
+                ));
+            }
+        }
+        if let Some(delay_ms) = apply {
+            self.set_audio_delay(delay_ms);
+        }
+    }
+
+    fn show_presets_window(&mut self, ctx: &egui::Context) {
+        if !self.show_presets {
+            return;
+        }
+
+        let mut open = self.show_presets;
+        let mut apply = None;
+        let mut delete = None;
+        let current = self.current_preset_values();
+
+        egui::Window::new(tr!("window-presets"))
+            .open(&mut open)
+            .default_size([460.0, 420.0])
+            .show(ctx, |ui| {
+                ui.label(tr!("presets-description"));
+                ui.add_space(4.0);
+
+                egui::ScrollArea::vertical()
+                    .max_height(480.0)
+                    .show(ui, |ui| {
+                        for (index, preset) in self.settings.presets.iter_mut().enumerate() {
+                            egui::CollapsingHeader::new(&preset.name)
+                                .id_salt(("preset", index))
+                                .show(ui, |ui| {
+                                    ui.horizontal(|ui| {
+                                        ui.label(tr!("presets-name"));
+                                        ui.text_edit_singleline(&mut preset.name);
+                                    });
+                                    ui.label(tr!("presets-patterns"))
+                                        .on_hover_text(tr!("presets-patterns-hint"));
+                                    let mut patterns = preset.patterns.join("\n");
+                                    if ui
+                                        .add(
+                                            egui::TextEdit::multiline(&mut patterns)
+                                                .desired_rows(2)
+                                                .hint_text("*[SubsPlease]*.mkv"),
+                                        )
+                                        .changed()
+                                    {
+                                        preset.patterns =
+                                            patterns.split('\n').map(String::from).collect();
+                                    }
+
+                                    egui::Grid::new(("preset_values", index))
+                                        .num_columns(2)
+                                        .show(ui, |ui| {
+                                            Self::show_preset_values(ui, preset, &current);
+                                        });
+
+                                    ui.horizontal(|ui| {
+                                        if ui.button(tr!("presets-apply")).clicked() {
+                                            apply = Some(index);
+                                        }
+                                        if ui
+                                            .button(tr!("presets-update"))
+                                            .on_hover_text(tr!("presets-update-hint"))
+                                            .clicked()
+                                        {
+                                            preset.overlay(&current.masked(preset));
+                                        }
+                                        if ui.button(tr!("presets-delete")).clicked() {
+                                            delete = Some(index);
+                                        }
+                                    });
+                                });
+                        }
+                    });
+
+                ui.add_space(4.0);
+                if ui.button(tr!("presets-new")).clicked() {
+                    self.settings.presets.push(presets::Preset {
+                        name: tr!("presets-new-name"),
+                        ..current.clone()
+                    });
+                }
+            });
+        self.show_presets = open;
+
+        if let Some(index) = delete {
+            self.settings.presets.remove(index);
+        }
+        if let Some(index) = apply {
+            self.perform_action(Action::ApplyPreset(Some(index)));
+        }
+    }
+
+    /// A row per value a preset can set, ticked when the preset includes it. Ticking one
+    /// takes the value in use now.
+    fn show_preset_values(
+        ui: &mut egui::Ui,
+        preset: &mut presets::Preset,
+        current: &presets::Preset,
+    ) {
+        fn row<T: Copy>(
+            ui: &mut egui::Ui,
+            label: String,
+            value: &mut Option<T>,
+            current: Option<T>,
+            describe: impl Fn(T) -> String,
+        ) {
+            let mut included = value.is_some();
+            if ui.checkbox(&mut included, label).changed() {
+                *value = if included { current } else { None };
+            }
+            ui.label(value.map(describe).unwrap_or_default());
+            ui.end_row();
+        }
+        let on_off = |on: bool| {
+            if on {
+                tr!("presets-on")
+            } else {
+                tr!("menu-off")
+            }
+        };
+
+        row(
+            ui,
+            tr!("menu-color-conversion"),
+            &mut preset.scaling,
+            current.scaling,
+            |scaling| scaling.name().to_string(),
+        );
+        row(
+            ui,
+            tr!("menu-display-sampling"),
+            &mut preset.sampler,
+            current.sampler,
+            |sampler| sampler.name(),
+        );
+        row(
+            ui,
+            tr!("menu-display-mode"),
+            &mut preset.display_mode,
+            current.display_mode,
+            |mode| mode.name(),
+        );
+        row(
+            ui,
+            tr!("menu-denoise"),
+            &mut preset.denoise,
+            current.denoise,
+            on_off,
+        );
+        row(
+            ui,
+            tr!("menu-deband"),
+            &mut preset.deband,
+            current.deband,
+            on_off,
+        );
+        row(
+            ui,
+            tr!("menu-speed"),
+            &mut preset.speed,
+            current.speed,
+            |speed| format!("{:.2}x", speed),
+        );
+        row(
+            ui,
+            tr!("menu-audio-delay"),
+            &mut preset.audio_delay_ms,
+            current.audio_delay_ms,
+            |ms| tr!("menu-audio-delay-ms", ms = ms),
+        );
+        row(
+            ui,
+            tr!("menu-channels"),
+            &mut preset.downmix,
+            current.downmix,
+            Self::downmix_name,
+        );
+        row(
+            ui,
+            tr!("menu-night-mode"),
+            &mut preset.night_mode,
+            current.night_mode,
+            on_off,
+        );
+    }
+
+    fn save_ocr_subtitles(&mut self, subtitles: &Subtitles) {
+        let Some(filename) = &self.current_filename else {
+            return;
+        };
+        let default_name = Path::new(filename)
+            .file_stem()
+            .map(|stem| format!("{}.srt", stem.to_string_lossy()))
+            .unwrap_or_else(|| "subtitles.srt".to_string());
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("SubRip", &["srt"])
+            .set_file_name(&default_name)
+            .save_file()
+        else {
+            return;
+        };
+
+        match std::fs::write(&path, subtitles.to_srt()) {
+            Ok(()) => self.show_osd(tr!("osd-ocr-saved"), egui::Color32::WHITE),
+            Err(e) => {
+                eprintln!("Failed to save subtitles: {}", e);
+                self.show_osd(
+                    tr!("osd-ocr-save-failed", error = e),
+                    egui::Color32::LIGHT_RED,
+                );
+            }
+        }
+    }
+
+    fn update_scene_detection(&mut self, ctx: &egui::Context) {
+        let has_chapters = !self.chapters().is_empty();
+        let Some(detection) = &mut self.scene_detection else {
+            return;
+        };
+
+        if detection.update(ctx) {
+            ctx.request_repaint_after(Duration::from_millis(100));
+        } else if !has_chapters {
+            // Files without chapters get the scenes as chapters right away
+            if let Some(chapters) = detection.chapters() {
+                println!("Created {} chapters from scene cuts", chapters.len());
+                if let Some(media_info) = &mut self.media_info {
+                    media_info.chapters = chapters;
+                }
+            }
+        }
+    }
+
+    /// Key of the open file in the skip segment store; stream URLs of web videos expire.
+    fn skip_segment_key(&self) -> Option<&String> {
+        match &self.web_video {
+            Some(page) => Some(&page.page_url),
+            None => self.current_filename.as_ref(),
+        }
+    }
+
+    /// The file's own skip segments followed by the SponsorBlock ones that are not ignored.
+    fn current_skip_segments(&self) -> Vec<skip_segments::SkipSegment> {
+        let mut segments = match self.skip_segment_key() {
+            Some(key) => self.skip_segments.get(key).to_vec(),
+            None => Vec::new(),
+        };
+
+        if let Some(lookup) = self
+            .web_video
+            .as_ref()
+            .and_then(|page| page.sponsor_lookup.as_ref())
+        {
+            for segment in lookup.segments() {
+                let auto = match self.settings.sponsorblock.action(segment.category) {
+                    sponsorblock::SponsorAction::Ignore => continue,
+                    sponsorblock::SponsorAction::Mark => false,
+                    sponsorblock::SponsorAction::Skip => true,
+                };
+                segments.push(skip_segments::SkipSegment {
+                    name: segment.category.name().to_string(),
+                    start_ms: segment.start_ms,
+                    end_ms: segment.end_ms,
+                    auto,
+                });
+            }
+        }
+
+        segments
+    }
+
+    fn update_sponsor_lookup(&mut self) {
+        let Some(lookup) = self
+            .web_video
+            .as_mut()
+            .and_then(|page| page.sponsor_lookup.as_mut())
+        else {
+            return;
+        };
+
+        match lookup.update() {
+            Some(Ok(0)) | None => {}
+            Some(Ok(count)) => self.show_osd(
+                tr!("osd-sponsorblock-segments", count = count),
+                egui::Color32::WHITE,
+            ),
+            Some(Err(e)) => eprintln!("Error fetching SponsorBlock segments: {}", e),
+        }
+    }
+
+    fn skip_segment_at(&self, time_ms: i64) -> Option<usize> {
+        self.current_skip_segments()
+            .iter()
+            .position(|segment| segment.contains(time_ms))
+    }
+
+    /// Skips automatic segments when playback reaches them.
+    fn update_skip_segments(&mut self) {
+        if !self.has_media() {
+            return;
+        }
+
+        let entered = self.skip_segment_at(self.current_time_ms());
+        if entered == self.skip_segment_entered {
+            return;
+        }
+        self.skip_segment_entered = entered;
+
+        if let Some(index) = entered {
+            if self.current_skip_segments()[index].auto && !self.paused {
+                self.skip_current_segment();
+            }
+        }
+    }
+
+    fn set_sleep_timer(&mut self, timer: Option<sleep_timer::SleepTimer>) {
+        self.sleep_timer = timer;
+        let text = match &self.sleep_timer {
+            Some(timer) => tr!("osd-sleep-timer", time = timer.label()),
+            None => tr!("osd-sleep-timer-off"),
+        };
+        self.show_osd(text, egui::Color32::WHITE);
+    }
+
+    fn run_sleep_action(&mut self, ctx: &egui::Context) {
+        match self.settings.sleep_action {
+            sleep_timer::SleepAction::Pause => {
+                self.set_paused(true);
+                self.show_osd(tr!("osd-sleep-timer-paused"), egui::Color32::WHITE);
+            }
+            sleep_timer::SleepAction::Quit => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+        }
+    }
+
+    /// Opens what was playing when the last session crashed, where it was.
+    fn restore_session(&mut self, snapshot: session::Snapshot) {
+        println!(
+            "Restoring {} at {} ms",
+            snapshot.current, snapshot.position_ms
+        );
+        if snapshot.joined.len() > 1 {
+            self.join_files(&snapshot.joined);
+        } else {
+            concat::clear();
+            self.playlist = Playlist::from_entries(snapshot.playlist, self.settings.shuffle);
+            if !self.playlist.select(&snapshot.current) {
+                self.playlist = Playlist::from_entries(vec![snapshot.current], false);
+            }
+            if let Some(filename) = self.playlist.current().map(|s| s.to_string()) {
+                if let Err(e) = self.load_video(&filename) {
+                    self.show_error(&tr!("error-cannot-open", file = filename), e);
+                }
+            }
+        }
+
+        if !self.has_media() {
+            return;
+        }
+        if self.live.is_none() {
+            self.seek_to(snapshot.position_ms);
+        }
+        self.set_paused(snapshot.paused);
+    }
+
+    fn update_session_snapshot(&mut self) {
+        if !self.settings.crash_recovery || !self.session_snapshots.is_due() || !self.has_media() {
+            return;
+        }
+        let Some(current) = self.playlist.current() else {
+            return;
+        };
+        let joined = concat::timeline_for(current)
+            .map(|timeline| timeline.parts.into_iter().map(|part| part.path).collect())
+            .unwrap_or_default();
+        let snapshot = session::Snapshot {
+            playlist: self.playlist.entries().to_vec(),
+            current: current.to_string(),
+            joined,
+            position_ms: self.current_time_ms(),
+            paused: self.paused,
+        };
+        self.session_snapshots.write(snapshot);
+    }
+
+    fn open_stats_log() -> Option<stats_log::StatsLog> {
+        match stats_log::StatsLog::open() {
+            Ok(log) => Some(log),
+            Err(e) => {
+                eprintln!("Cannot open statistics log: {}", e);
+                None
+            }
+        }
+    }
+
+    fn update_stats_log(&mut self) {
+        let position_ms = self.current_time_ms();
+        let underruns = self.audio.as_ref().map_or(0, |audio| audio.underruns());
+        if let Some(log) = &mut self.stats_log {
+            log.update(position_ms, underruns);
+        }
+    }
+
+    /// Pauses or quits when the sleep timer runs out, counting down in its last minute.
+    fn update_sleep_timer(&mut self, ctx: &egui::Context) {
+        let Some(remaining) = self.sleep_timer.and_then(|timer| timer.remaining()) else {
+            return;
+        };
+
+        if remaining.is_zero() {
+            self.sleep_timer = None;
+            self.run_sleep_action(ctx);
+            return;
+        }
+
+        if remaining <= sleep_timer::COUNTDOWN {
+            self.show_osd(
+                tr!(
+                    "osd-sleep-countdown",
+                    action = self.settings.sleep_action.label(),
+                    seconds = remaining.as_secs() + 1
+                ),
+                egui::Color32::WHITE,
+            );
+            ctx.request_repaint_after(Duration::from_millis(250));
+        } else {
+            ctx.request_repaint_after(remaining - sleep_timer::COUNTDOWN);
+        }
+    }
+
+    /// Sets A, then B, then clears the loop.
+    fn cycle_ab_loop(&mut self) {
+        if !self.has_media() {
+            return;
+        }
+        let position_ms = self.current_time_ms();
+        match &mut self.ab_loop {
+            None => {
+                self.ab_loop = Some(ab_loop::AbLoop::new(position_ms));
+                self.show_osd(
+                    tr!("osd-loop-start", time = Self::format_time(position_ms)),
+                    egui::Color32::WHITE,
+                );
+            }
+            Some(ab_loop) if ab_loop.end_ms.is_none() => {
+                if !ab_loop.set_end(position_ms) {
+                    self.show_osd(tr!("osd-loop-too-short"), egui::Color32::LIGHT_RED);
+                    return;
+                }
+                let (start_ms, end_ms) = (ab_loop.start_ms, ab_loop.end_ms.unwrap_or(position_ms));
+                self.seek_to(start_ms);
+                self.apply_playback_speed();
+                self.show_osd(
+                    tr!(
+                        "osd-loop",
+                        start = Self::format_time(start_ms),
+                        end = Self::format_time(end_ms)
+                    ),
+                    egui::Color32::WHITE,
+                );
+            }
+            Some(_) => {
+                self.ab_loop = None;
+                self.apply_playback_speed();
+                self.show_osd(tr!("osd-loop-cleared"), egui::Color32::WHITE);
+            }
+        }
+    }
+
+    /// Goes back to A when playback passes B, and lets playback go on after the last pass.
+    fn update_ab_loop(&mut self) {
+        if self.paused || self.scrub_preview_ms.is_some() {
+            return;
+        }
+        let position_ms = self.current_time_ms();
+        let Some(event) = self
+            .ab_loop
+            .as_mut()
+            .and_then(|ab_loop| ab_loop.update(position_ms, &self.settings.ab_loop))
+        else {
+            return;
+        };
+
+        match event {
+            ab_loop::LoopEvent::Repeat => {
+                let Some(ab_loop) = &self.ab_loop else {
+                    return;
+                };
+                let (start_ms, pass) = (ab_loop.start_ms, ab_loop.pass());
+                self.seek_to(start_ms);
+                self.apply_playback_speed();
+                let repeats = self.settings.ab_loop.repeats;
+                if repeats > 0 {
+                    let speed = format!("{:.2}", self.effective_speed());
+                    self.show_osd(
+                        tr!(
+                            "osd-loop-pass",
+                            pass = pass,
+                            repeats = repeats,
+                            speed = speed
+                        ),
+                        egui::Color32::WHITE,
+                    );
+                }
+            }
+            ab_loop::LoopEvent::Finished => {
+                self.ab_loop = None;
+                self.apply_playback_speed();
+                self.show_osd(tr!("osd-loop-finished"), egui::Color32::WHITE);
+            }
+        }
+    }
+
+    fn skip_current_segment(&mut self) {
+        let Some(segment) = self
+            .skip_segment_at(self.current_time_ms())
+            .map(|index| self.current_skip_segments().swap_remove(index))
+        else {
+            return;
+        };
+
+        self.seek_to(segment.end_ms);
+        self.show_osd(
+            tr!("osd-skipped-segment", name = segment.name),
+            egui::Color32::WHITE,
+        );
+    }
+
+    /// Offers to skip the segment playback is in, in the bottom right of `area`.
+    fn draw_skip_button(&mut self, ui: &mut egui::Ui, area: egui::Rect) {
+        let Some(name) = self
+            .skip_segment_at(self.current_time_ms())
+            .map(|index| self.current_skip_segments().swap_remove(index).name)
+        else {
+            return;
+        };
+
+        let size = egui::vec2(160.0, 36.0);
+        let rect = egui::Rect::from_min_size(area.max - size - egui::vec2(24.0, 24.0), size);
+        let button = egui::Button::new(
+            egui::RichText::new(tr!("skip-segment-button", name = name)).size(16.0),
+        )
+        .fill(egui::Color32::from_black_alpha(200));
+        if ui.put(rect, button).clicked() {
+            self.perform_action(Action::SkipSegment);
+        }
+    }
+
+    /// Shows the next entry near the end of the current one; clicking it plays it now.
+    fn draw_up_next(&mut self, ui: &mut egui::Ui, area: egui::Rect) {
+        let duration_ms = self.duration_ms();
+        if self.live.is_some()
+            || duration_ms <= 0
+            || duration_ms - self.current_time_ms() > UP_NEXT_MS
+        {
+            return;
+        }
+        let Some(preload) = &self.preload else {
+            return;
+        };
+        let Some(info) = preload.info() else {
+            return;
+        };
+
+        let name = Path::new(preload.filename())
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| preload.filename().to_string());
+        let mut details = vec![Self::format_time(info.duration_ms)];
+        if let Some(stream) = info.video_streams.first() {
+            if let (Some(width), Some(height)) = (stream.width, stream.height) {
+                details.push(format!("{}×{} {}", width, height, stream.codec_name));
+            }
+        }
+        if !info.audio_streams.is_empty() {
+            details.push(tr!("up-next-audio", count = info.audio_streams.len()));
+        }
+        if !info.subtitle_streams.is_empty() {
+            details.push(tr!(
+                "up-next-subtitles",
+                count = info.subtitle_streams.len()
+            ));
+        }
+
+        let size = egui::vec2(360.0, 90.0);
+        let rect = egui::Rect::from_min_size(
+            egui::pos2(area.left() + 24.0, area.bottom() - 24.0 - size.y),
+            size,
+        );
+        let response = ui
+            .interact(rect, ui.id().with("up_next"), egui::Sense::click())
+            .on_hover_cursor(egui::CursorIcon::PointingHand);
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 4.0, egui::Color32::from_black_alpha(200));
+
+        let mut text_left = rect.left() + 10.0;
+        if let Some(texture) = preload.thumbnail() {
+            let height = rect.height() - 16.0;
+            let width = height * texture.aspect_ratio();
+            let image_rect = egui::Rect::from_min_size(
+                rect.left_top() + egui::vec2(8.0, 8.0),
+                egui::vec2(width.min(rect.width() / 2.0), height),
+            );
+            painter.image(
+                texture.id(),
+                image_rect,
+                egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                egui::Color32::WHITE,
+            );
+            text_left = image_rect.right() + 10.0;
+        }
+
+        let lines = [
+            (tr!("up-next"), 12.0, egui::Color32::from_gray(170)),
+            (name, 16.0, egui::Color32::WHITE),
+            (details.join(" · "), 12.0, egui::Color32::from_gray(200)),
+        ];
+        let mut y = rect.top() + 12.0;
+        for (text, size, color) in lines {
+            let galley = painter.layout(
+                text,
+                egui::FontId::proportional(size),
+                color,
+                rect.right() - 10.0 - text_left,
+            );
+            let height = galley.size().y;
+            painter.galley(egui::pos2(text_left, y), galley, color);
+            y += height + 4.0;
+        }
+
+        if response.hovered() {
+            painter.rect_stroke(rect, 4.0, egui::Stroke::new(1.0, egui::Color32::WHITE));
+        }
+        if response.clicked() {
+            self.perform_action(Action::PlaylistNext);
+        }
+    }
+
+    fn open_skip_segment_dialog(&mut self) {
+        let Some(media_path) = self.skip_segment_key().cloned() else {
+            return;
+        };
+        if self.skip_segment_dialog.is_some() {
+            return;
+        }
+
+        let rows = self
+            .skip_segments
+            .get(&media_path)
+            .iter()
+            .map(Self::skip_segment_row)
+            .collect();
+        self.skip_segment_dialog = Some(SkipSegmentDialog {
+            media_path,
+            rows,
+            error: None,
+        });
+    }
+
+    fn skip_segment_row(segment: &skip_segments::SkipSegment) -> SkipSegmentRow {
+        SkipSegmentRow {
+            name: segment.name.clone(),
+            start: Self::format_time(segment.start_ms),
+            end: Self::format_time(segment.end_ms),
+            auto: segment.auto,
+        }
+    }
+
+    fn parse_skip_segments(
+        rows: &[SkipSegmentRow],
+    ) -> Result<Vec<skip_segments::SkipSegment>, String> {
+        rows.iter()
+            .map(|row| {
+                let name = match row.name.trim() {
+                    "" => tr!("skip-segment-default-name"),
+                    name => name.to_string(),
+                };
+                let start_ms = Self::parse_time(&row.start)
+                    .ok_or_else(|| tr!("skip-segment-invalid-start", name = name))?;
+                let end_ms = Self::parse_time(&row.end)
+                    .ok_or_else(|| tr!("skip-segment-invalid-end", name = name))?;
+                if end_ms <= start_ms {
+                    return Err(tr!("skip-segment-ends-before-start", name = name));
+                }
+                Ok(skip_segments::SkipSegment {
+                    name,
+                    start_ms,
+                    end_ms,
+                    auto: row.auto,
+                })
+            })
+            .collect()
+    }
+
+    fn show_skip_segment_dialog(&mut self, ctx: &egui::Context) {
+        let mut dialog = match self.skip_segment_dialog.take() {
+            Some(dialog) => dialog,
+            None => return,
+        };
+        let mut save = false;
+        let mut close = false;
+        let mut import = false;
+        let mut export = false;
+        let current_ms = self.current_time_ms();
+
+        egui::Window::new(tr!("window-skip-segments"))
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(tr!("skip-segments-description"));
+                ui.add_space(4.0);
+
+                let mut remove = None;
+                egui::Grid::new("skip_segments")
+                    .num_columns(5)
+                    .show(ui, |ui| {
+                        ui.strong(tr!("skip-segments-name"));
+                        ui.strong(tr!("skip-segments-start"));
+                        ui.strong(tr!("skip-segments-end"));
+                        ui.strong(tr!("skip-segments-skip"));
+                        ui.end_row();
+
+                        for (index, row) in dialog.rows.iter_mut().enumerate() {
+                            ui.add(egui::TextEdit::singleline(&mut row.name).desired_width(120.0));
+                            for time in [&mut row.start, &mut row.end] {
+                                ui.horizontal(|ui| {
+                                    ui.add(egui::TextEdit::singleline(time).desired_width(70.0));
+                                    if ui
+                                        .small_button(tr!("skip-segments-now"))
+                                        .on_hover_text(tr!("skip-segments-now-hint"))
+                                        .clicked()
+                                    {
+                                        *time = Self::format_time(current_ms);
+                                    }
+                                });
+                            }
+                            egui::ComboBox::from_id_salt(("skip_mode", index))
+                                .selected_text(if row.auto {
+                                    tr!("skip-segments-automatically")
+                                } else {
+                                    tr!("skip-segments-ask")
+                                })
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(
+                                        &mut row.auto,
+                                        false,
+                                        tr!("skip-segments-ask"),
+                                    );
+                                    ui.selectable_value(
+                                        &mut row.auto,
+                                        true,
+                                        tr!("skip-segments-automatically"),
+                                    );
+                                });
+                            if Self::label_control(
+                                ui.small_button("🗑"),
+                                tr!("skip-segments-remove"),
+                            )
+                            .clicked()
+                            {
+                                remove = Some(index);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                if let Some(index) = remove {
+                    dialog.rows.remove(index);
+                }
+
+                if ui.button(tr!("skip-segments-add")).clicked() {
+                    dialog.rows.push(SkipSegmentRow {
+                        name: tr!("skip-segments-new-name"),
+                        start: Self::format_time(current_ms),
+                        end: Self::format_time(current_ms + 90_000),
+                        auto: false,
+                    });
+                }
+
+                if let Some(error) = &dialog.error {
+                    ui.colored_label(egui::Color32::from_rgb(255, 100, 100), error);
+                }
 
-        let player = Self {
-            video,
-            audio,
-            video_texture: None,
-            paused: false,
-            last_frame_time: Instant::now(),
-            frame_interval,
-            fps_counter: FpsCounter::new(),
-            volume: 0.7,
-            is_fullscreen: false,
-            show_media_info: false,
-            media_info,
-            current_filename,
-        };
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button(tr!("button-import")).clicked() {
+                        import = true;
+                    }
+                    if ui.button(tr!("button-export-file")).clicked() {
+                        export = true;
+                    }
+                    if ui.button(tr!("button-save")).clicked() {
+                        save = true;
+                    }
+                    if ui.button(tr!("button-cancel")).clicked() {
+                        close = true;
+                    }
+                });
+            });
 
-        if let Some(audio) = &player.audio {
-            audio.set_volume(player.volume);
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            close = true;
         }
 
-        Ok(player)
-    }
-
-    fn load_video(&mut self, filename: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let video = video::Video::new(filename)?;
-        self.frame_interval = 1.0 / video.get_frame_rate();
-        self.audio = audio::Audio::new(filename).ok();
+        if import {
+            if let Some(path) = rfd::FileDialog::new()
+                .add_filter(tr!("filter-skip-segments"), &["json"])
+                .pick_file()
+            {
+                match skip_segments::import(&path.to_string_lossy()) {
+                    Ok(segments) => {
+                        dialog.rows = segments.iter().map(Self::skip_segment_row).collect();
+                        dialog.error = None;
+                    }
+                    Err(e) => dialog.error = Some(tr!("error-cannot-import", error = e)),
+                }
+            }
+        }
 
-        if let Some(audio) = &self.audio {
-            audio.set_volume(self.volume);
+        if export {
+            match Self::parse_skip_segments(&dialog.rows) {
+                Ok(segments) => {
+                    let default_name = Path::new(&dialog.media_path)
+                        .file_stem()
+                        .map(|stem| format!("{}_segments.json", stem.to_string_lossy()))
+                        .unwrap_or_else(|| "segments.json".to_string());
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter(tr!("filter-skip-segments"), &["json"])
+                        .set_file_name(default_name)
+                        .save_file()
+                    {
+                        match skip_segments::export(&path.to_string_lossy(), &segments) {
+                            Ok(()) => dialog.error = None,
+                            Err(e) => dialog.error = Some(tr!("error-cannot-export", error = e)),
+                        }
+                    }
+                }
+                Err(e) => dialog.error = Some(e),
+            }
         }
 
-        self.media_info = media_info::get_media_info(filename);
-        self.current_filename = Some(filename.to_string());
+        if save {
+            match Self::parse_skip_segments(&dialog.rows) {
+                Ok(segments) => {
+                    self.skip_segments.set(&dialog.media_path, segments);
+                    self.skip_segment_entered = self.skip_segment_at(self.current_time_ms());
+                    close = true;
+                }
+                Err(e) => dialog.error = Some(e),
+            }
+        }
 
-        self.video = Some(video);
-        self.video_texture = None;
-        self.paused = false;
-        self.last_frame_time = Instant::now();
-        Ok(())
+        if !close {
+            self.skip_segment_dialog = Some(dialog);
+        }
     }
 
     fn should_process_next_frame(&mut self) -> bool {
-        if self.paused {
+        // The frame on screen is the scrub preview until the seek bar is let go
+        if self.paused || self.scrub_preview_ms.is_some() {
             return false;
         }
 
         let now = Instant::now();
         let elapsed = now.duration_since(self.last_frame_time).as_secs_f64();
 
-        if elapsed >= self.frame_interval {
+        if elapsed * self.effective_speed() >= self.frame_interval {
             self.last_frame_time = now;
             true
         } else {
@@ -127,33 +7988,49 @@ impl VideoPlayer {
     }
 
     fn update_video_frame(&mut self, ctx: &egui::Context) {
+        let mut reached_end = false;
+        let mut presented_ms = None;
+        // Gaps of a second or more are pauses and seeks rather than late frames
+        let gap = self.last_frame_time.elapsed().as_secs_f64() * self.effective_speed();
+        let late = gap >= self.frame_interval * 2.0 && gap < 1.0;
+
         if self.video.is_some() && self.should_process_next_frame() {
             if let Some(video) = &mut self.video {
-                if let Some(Ok(frame)) = video.next_frame() {
-                    let size = [frame.width, frame.height];
-                    let pixels: Vec<egui::Color32> = frame
-                        .buffer
-                        .chunks_exact(4)
-                        .map(|p| egui::Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]))
-                        .collect();
-
-                    let color_image = egui::ColorImage { size, pixels };
-
-                    if let Some(texture) = &mut self.video_texture {
-                        texture.set(color_image, egui::TextureOptions::LINEAR);
-                    } else {
-                        self.video_texture = Some(ctx.load_texture(
-                            "video_frame",
-                            color_image,
-                            egui::TextureOptions::LINEAR,
-                        ));
-                    }
+                let decode_started = Instant::now();
+                match video.next_frame() {
+                    Some(Ok(frame)) => {
+                        if let Some(log) = &mut self.stats_log {
+                            log.frame(decode_started.elapsed(), late);
+                        }
+                        self.video_textures.upload(ctx, frame);
+                        self.scopes.invalidate();
+                        presented_ms = Some(frame.timestamp_ms);
 
-                    self.fps_counter.update();
+                        self.fps_counter.update();
+                        if self.still_image {
+                            self.set_paused(true);
+                        }
+                    }
+                    Some(Err(_)) => {}
+                    // A growing timeshift buffer only has no frame yet
+                    None => reached_end = !video.is_following(),
                 }
             }
         }
 
+        if reached_end {
+            self.handle_end_of_file(ctx);
+            return;
+        }
+
+        if let Some(presented_ms) = presented_ms {
+            if let Some(audio) = &self.audio {
+                self.av_sync.update(presented_ms, audio.get_current_time());
+            }
+            self.sync_comparison(ctx, presented_ms);
+            self.sync_video_wall(ctx, presented_ms);
+        }
+
         if self.video.is_some() && !self.paused && self.fps_counter.frame_count % 150 == 0 {
             if let Some(audio) = &self.audio {
                 if let Some(video) = &self.video {
@@ -161,11 +8038,497 @@ impl VideoPlayer {
                     let audio_time_ms = audio.get_current_time();
                     let sync_diff = (video_time_ms - audio_time_ms).abs();
 
-                    if sync_diff > 200 {
+                    if sync_diff > av_sync::THRESHOLD_MS {
+                        if let Some(log) = &mut self.stats_log {
+                            log.sync_correction(video_time_ms, video_time_ms - audio_time_ms);
+                        }
                         audio.seek(video_time_ms);
+                        self.av_sync.reset();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Zooms the picture towards the pointer with the scroll wheel and drags it around while
+    /// zoomed in, keeping it from leaving any part of the video area empty.
+    fn zoom_and_pan(
+        ctx: &egui::Context,
+        ui: &egui::Ui,
+        video_area: egui::Rect,
+        fitted: egui::Rect,
+        zoom: &mut f32,
+        pan: &mut egui::Vec2,
+    ) {
+        let response = ui.interact(video_area, ui.id().with("video_pan"), egui::Sense::drag());
+
+        let scroll = ui.input(|i| i.smooth_scroll_delta.y);
+        if response.hovered() && scroll != 0.0 {
+            let new_zoom = (*zoom * (scroll * 0.002).exp()).clamp(1.0, MAX_ZOOM);
+            if let Some(pointer) = response.hover_pos() {
+                // Keep the point under the pointer in place
+                let anchor = pointer - fitted.center();
+                *pan = anchor - (anchor - *pan) * (new_zoom / *zoom);
+            }
+            *zoom = new_zoom;
+        }
+
+        if *zoom > 1.0 {
+            *pan += response.drag_delta();
+            if response.dragged() {
+                ctx.set_cursor_icon(egui::CursorIcon::Grabbing);
+            }
+        }
+
+        let overflow = ((fitted.size() * *zoom - video_area.size()) / 2.0).max(egui::Vec2::ZERO);
+        *pan = pan.clamp(-overflow, overflow);
+    }
+
+    /// Moves the audio to the position of the frame on screen.
+    fn resync_audio(&mut self) {
+        if let (Some(video), Some(audio)) = (&self.video, &self.audio) {
+            audio.seek(video.get_current_timestamp_ms());
+            self.av_sync.reset();
+            self.show_osd(tr!("osd-audio-resynced"), egui::Color32::WHITE);
+        }
+    }
+
+    /// Draws a video texture into `rect`, showing only the part inside `clip`.
+    fn draw_video_texture(
+        ui: &egui::Ui,
+        shaders: &mut ShaderManager,
+        texture: &egui::TextureHandle,
+        rect: egui::Rect,
+        clip: egui::Rect,
+    ) {
+        let painter = ui.painter_at(clip);
+
+        if let Some(callback) = shaders.paint_callback(texture, rect) {
+            painter.add(callback);
+        } else {
+            painter.image(
+                texture.id(),
+                rect,
+                egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                egui::Color32::WHITE,
+            );
+        }
+    }
+
+    /// Splits `area` into a grid of `count` cells, filled row by row.
+    fn grid_cells(area: egui::Rect, count: usize) -> Vec<egui::Rect> {
+        let columns = (count as f32).sqrt().ceil().max(1.0) as usize;
+        let rows = count.div_ceil(columns).max(1);
+        let size = egui::vec2(area.width() / columns as f32, area.height() / rows as f32);
+        (0..count)
+            .map(|index| {
+                let offset = egui::vec2(
+                    (index % columns) as f32 * size.x,
+                    (index / columns) as f32 * size.y,
+                );
+                egui::Rect::from_min_size(area.min + offset, size)
+            })
+            .collect()
+    }
+
+    /// Draws the open file and the wall's other files in a grid, each with a button that
+    /// plays its sound. Returns where the open file was drawn and the cell whose button was
+    /// clicked.
+    fn draw_video_wall(
+        ui: &mut egui::Ui,
+        shaders: &mut ShaderManager,
+        texture: &egui::TextureHandle,
+        wall: &VideoWall,
+        video_area: egui::Rect,
+        display_mode: DisplayMode,
+    ) -> (egui::Rect, Option<usize>) {
+        let cells = Self::grid_cells(video_area, wall.tiles.len() + 1);
+        let textures = std::iter::once(Some(texture))
+            .chain(wall.tiles.iter().map(|tile| tile.textures.current()));
+
+        let mut video_rect = cells[0];
+        let mut clicked = None;
+        for (index, (cell, texture)) in cells.iter().zip(textures).enumerate() {
+            let cell = cell.shrink(1.0);
+            if let Some(texture) = texture {
+                let rect = display_mode.rect(cell, texture.size_vec2());
+                Self::draw_video_texture(ui, shaders, texture, rect, rect.intersect(cell));
+                if index == 0 {
+                    video_rect = rect;
+                }
+            }
+
+            let audible = index == wall.audible;
+            if audible {
+                let stroke = egui::Stroke::new(2.0, ui.visuals().selection.bg_fill);
+                ui.painter().rect_stroke(cell, 0.0, stroke);
+            }
+
+            let button_rect =
+                egui::Rect::from_min_size(cell.min + egui::vec2(6.0, 6.0), egui::vec2(28.0, 28.0));
+            let icon = if audible { "🔊" } else { "🔈" };
+            if ui
+                .put(button_rect, egui::Button::new(icon))
+                .on_hover_text(tr!("video-wall-play-sound"))
+                .clicked()
+            {
+                clicked = Some(index);
+            }
+        }
+
+        (video_rect, clicked)
+    }
+
+    /// The picture with everything drawn over it, and the mouse and touch controls on it.
+    fn draw_video_area(&mut self, ctx: &egui::Context, ui: &mut egui::Ui, video_area: egui::Rect) {
+        self.last_video_area = video_area;
+        let gestures = ctx.input(|i| self.touch_gestures.update(i, video_area));
+        for gesture in gestures {
+            self.handle_gesture(gesture, video_area);
+        }
+        if self.touch_gestures.tap_pending() {
+            ctx.request_repaint_after(touch::DOUBLE_TAP_TIME);
+        }
+
+        // Added before the widgets on the video, such as the wipe divider and the skip
+        // button, so that those take their own clicks
+        let video_response =
+            ui.interact(video_area, ui.id().with("video_area"), egui::Sense::click());
+        if !self.touch_gestures.touched() {
+            if video_response.clicked() && self.settings.click_to_pause {
+                self.perform_action(Action::TogglePause);
+            }
+            // The two clicks have paused and resumed by now, leaving playback as it was
+            if video_response.double_clicked() {
+                self.perform_action(Action::ToggleFullscreen);
+            }
+            if video_response.middle_clicked() {
+                self.perform_action(Action::TogglePause);
+            }
+        }
+
+        let mut wall_audio = None;
+        if self.video.is_none() {
+            self.draw_track_info(ui, video_area);
+        } else if let Some(texture) = self.video_textures.current() {
+            // The wipe view mixes two videos and the 360° view reprojects the frame
+            let inspectable =
+                self.spherical_view.is_none() && !self.comparison.as_ref().is_some_and(|c| c.wipe);
+            let spherical_callback = match (&self.comparison, self.spherical_view) {
+                (None, Some(view)) if self.video_wall.is_none() => {
+                    self.shaders
+                        .spherical_callback(texture, video_area, view.radians())
+                }
+                _ => None,
+            };
+
+            let video_rect = if let Some(callback) = spherical_callback {
+                ui.painter().add(callback);
+
+                let response = ui.interact(
+                    video_area,
+                    ui.id().with("spherical_view"),
+                    egui::Sense::drag(),
+                );
+                if let Some(view) = &mut self.spherical_view {
+                    view.drag(response.drag_delta(), video_area.height());
+                    if response.hovered() {
+                        view.zoom(ui.input(|i| i.smooth_scroll_delta.y));
+                    }
+                }
+                if response.dragged() {
+                    ctx.set_cursor_icon(egui::CursorIcon::Grabbing);
+                }
+
+                video_area
+            } else if let Some(wall) = &mut self.video_wall {
+                let (video_rect, cell) = Self::draw_video_wall(
+                    ui,
+                    &mut self.shaders,
+                    texture,
+                    wall,
+                    video_area,
+                    self.settings.display_mode,
+                );
+                wall_audio = cell;
+                video_rect
+            } else {
+                match &mut self.comparison {
+                    None => {
+                        let fitted = self
+                            .settings
+                            .display_mode
+                            .rect(video_area, texture.size_vec2());
+                        Self::zoom_and_pan(
+                            ctx,
+                            ui,
+                            video_area,
+                            fitted,
+                            &mut self.video_zoom,
+                            &mut self.video_pan,
+                        );
+                        let video_rect = egui::Rect::from_center_size(
+                            fitted.center() + self.video_pan,
+                            fitted.size() * self.video_zoom,
+                        );
+                        Self::draw_video_texture(
+                            ui,
+                            &mut self.shaders,
+                            texture,
+                            video_rect,
+                            video_rect.intersect(video_area),
+                        );
+                        video_rect
+                    }
+                    Some(comparison) if comparison.wipe => {
+                        // Both videos share one rect; the comparison shows right of the divider
+                        let video_rect = DisplayMode::Fit.rect(video_area, texture.size_vec2());
+                        let split_x =
+                            video_rect.left() + video_rect.width() * comparison.wipe_position;
+                        let left = egui::Rect::from_min_max(
+                            video_rect.min,
+                            egui::pos2(split_x, video_rect.bottom()),
+                        );
+                        let right = egui::Rect::from_min_max(
+                            egui::pos2(split_x, video_rect.top()),
+                            video_rect.max,
+                        );
+
+                        Self::draw_video_texture(ui, &mut self.shaders, texture, video_rect, left);
+                        if let Some(other) = comparison.textures.current() {
+                            Self::draw_video_texture(
+                                ui,
+                                &mut self.shaders,
+                                other,
+                                video_rect,
+                                right,
+                            );
+                        }
+
+                        let response = ui.interact(
+                            video_rect,
+                            ui.id().with("comparison_wipe"),
+                            egui::Sense::click_and_drag(),
+                        );
+                        if response.clicked() || response.dragged() {
+                            if let Some(pointer_pos) = response.interact_pointer_pos() {
+                                comparison.wipe_position = ((pointer_pos.x - video_rect.left())
+                                    / video_rect.width())
+                                .clamp(0.0, 1.0);
+                            }
+                        }
+                        if response.hovered() || response.dragged() {
+                            ctx.set_cursor_icon(egui::CursorIcon::ResizeHorizontal);
+                        }
+
+                        ui.painter().vline(
+                            split_x,
+                            video_rect.y_range(),
+                            egui::Stroke::new(2.0, egui::Color32::WHITE),
+                        );
+                        video_rect
+                    }
+                    Some(comparison) => {
+                        let half_size = egui::vec2(video_area.width() / 2.0, video_area.height());
+                        let left_area = egui::Rect::from_min_size(video_area.min, half_size);
+                        let right_area = left_area.translate(egui::vec2(half_size.x, 0.0));
+
+                        let video_rect = DisplayMode::Fit.rect(left_area, texture.size_vec2());
+                        Self::draw_video_texture(
+                            ui,
+                            &mut self.shaders,
+                            texture,
+                            video_rect,
+                            video_rect,
+                        );
+
+                        if let Some(other) = comparison.textures.current() {
+                            let other_rect = DisplayMode::Fit.rect(right_area, other.size_vec2());
+                            Self::draw_video_texture(
+                                ui,
+                                &mut self.shaders,
+                                other,
+                                other_rect,
+                                other_rect,
+                            );
+                        }
+                        video_rect
                     }
                 }
+            };
+
+            let file_time_ms = self.file_time_ms();
+            if let Some((cue, canvas_size)) = self
+                .video
+                .as_mut()
+                .and_then(|video| video.bitmap_subtitle_at(file_time_ms))
+            {
+                self.bitmap_subtitle_textures.draw(
+                    ctx,
+                    ui.painter(),
+                    video_rect.intersect(video_area),
+                    cue,
+                    canvas_size,
+                );
+            }
+
+            let text = if self.show_captions {
+                self.video.as_ref().and_then(|video| video.caption_text())
+            } else {
+                self.subtitles
+                    .as_ref()
+                    .and_then(|subtitles| subtitles.text_at(self.current_time_ms()))
+            };
+            if let Some(text) = text {
+                subtitles::draw(
+                    ui.painter(),
+                    video_rect.intersect(video_area),
+                    &text,
+                    &self.settings.subtitle_style,
+                );
+            }
+
+            if self.show_pixel_inspector && inspectable {
+                if let Some(pointer) = ctx
+                    .pointer_hover_pos()
+                    .filter(|pos| video_rect.contains(*pos))
+                {
+                    ctx.set_cursor_icon(egui::CursorIcon::Crosshair);
+                    self.draw_pixel_inspector(ui.painter(), video_area, video_rect, pointer);
+                }
+            }
+
+            self.scopes.draw(
+                ctx,
+                ui.painter(),
+                video_area,
+                self.video_textures.current_image().as_deref(),
+            );
+        }
+
+        if let Some(cell) = wall_audio {
+            self.select_wall_audio(cell);
+        }
+
+        if self.has_media() {
+            self.draw_skip_button(ui, video_area);
+            self.draw_up_next(ui, video_area);
+        }
+
+        if self.show_loudness {
+            if let Some(audio) = &self.audio {
+                loudness::draw(ui.painter(), video_area, audio.loudness());
+            }
+        }
+        if self.show_levels {
+            if let Some(audio) = &self.audio {
+                levels::draw(ui.painter(), video_area, audio.levels());
+            }
+        }
+
+        self.draw_osd(ctx, ui.painter(), video_area);
+
+        // Requested here rather than in perform_action so that menus opened for the
+        // request have closed by the time the window is captured
+        if self.screenshot_requested {
+            self.screenshot_requested = false;
+            self.screenshot_area = Some(video_area);
+            ctx.send_viewport_cmd(egui::ViewportCommand::Screenshot);
+            ctx.request_repaint();
+        }
+    }
+
+    fn draw_video_window_placeholder(&mut self, ui: &mut egui::Ui, area: egui::Rect) {
+        ui.allocate_new_ui(egui::UiBuilder::new().max_rect(area), |ui| {
+            ui.centered_and_justified(|ui| {
+                ui.vertical_centered(|ui| {
+                    ui.add_space(area.height() / 2.0 - 30.0);
+                    ui.label(
+                        egui::RichText::new(tr!("video-window-placeholder"))
+                            .color(self.theme.weak_text),
+                    );
+                    if ui.button(tr!("video-window-return")).clicked() {
+                        self.perform_action(Action::ToggleVideoWindow);
+                    }
+                });
+            });
+        });
+    }
+
+    fn video_window_id() -> egui::ViewportId {
+        egui::ViewportId::from_hash_of("video_window")
+    }
+
+    /// Shows the picture in a window of its own while `video_window` is set.
+    fn show_video_window(&mut self, ctx: &egui::Context) {
+        if !self.video_window {
+            return;
+        }
+
+        let viewport = egui::ViewportBuilder::default()
+            .with_title(self.window_title.clone())
+            .with_inner_size([960.0, 540.0]);
+        ctx.show_viewport_immediate(Self::video_window_id(), viewport, |ctx, _| {
+            // Window captures and shortcuts arrive in this viewport's input
+            self.update_screenshots(ctx);
+
+            egui::CentralPanel::default()
+                .frame(egui::Frame::none().fill(egui::Color32::BLACK))
+                .show(ctx, |ui| {
+                    let area = ui.max_rect();
+                    self.draw_video_area(ctx, ui, area);
+                });
+
+            self.handle_key_bindings(ctx);
+
+            if ctx.input(|i| i.viewport().close_requested()) {
+                self.perform_action(Action::ToggleVideoWindow);
+            }
+        });
+    }
+
+    /// Runs the actions bound to this frame's key presses.
+    fn handle_key_bindings(&mut self, ctx: &egui::Context) {
+        if self.error_dialog.is_none() && !ctx.wants_keyboard_input() {
+            // A control focused with Tab gets Space, Enter and the arrow keys; Escape
+            // hands them back to the player
+            if ctx.memory(|memory| memory.focused().is_some()) {
+                ctx.input_mut(|i| {
+                    for key in [
+                        egui::Key::Space,
+                        egui::Key::Enter,
+                        egui::Key::ArrowLeft,
+                        egui::Key::ArrowRight,
+                        egui::Key::ArrowUp,
+                        egui::Key::ArrowDown,
+                    ] {
+                        i.consume_key(egui::Modifiers::NONE, key);
+                    }
+                });
             }
+            let actions = ctx.input(|i| self.key_bindings.actions(i));
+            for action in actions {
+                self.perform_action(action);
+            }
+        }
+    }
+
+    /// Names an icon-only control for screen readers and shows the name as a tooltip.
+    fn label_control(response: egui::Response, label: String) -> egui::Response {
+        response
+            .ctx
+            .accesskit_node_builder(response.id, |builder| builder.set_name(label.clone()));
+        response.on_hover_text(label)
+    }
+
+    /// Outlines a frameless control while it has keyboard focus.
+    fn draw_focus_ring(&self, ui: &egui::Ui, response: &egui::Response) {
+        if response.has_focus() {
+            ui.painter().rect_stroke(
+                response.rect.expand(3.0),
+                egui::Rounding::same(4.0),
+                egui::Stroke::new(2.0, self.theme.accent),
+            );
         }
     }
 
@@ -177,12 +8540,81 @@ impl VideoPlayer {
         format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
     }
 
+    fn parse_time(input: &str) -> Option<i64> {
+        let input = input.trim();
+        let mut parts: Vec<&str> = input.split(':').collect();
+        if input.is_empty() || parts.len() > 3 {
+            return None;
+        }
+
+        let seconds: f64 = parts.pop()?.parse().ok()?;
+        if !seconds.is_finite() || seconds < 0.0 || (!parts.is_empty() && seconds >= 60.0) {
+            return None;
+        }
+
+        let mut total_seconds = seconds;
+        let mut multiplier = 60.0;
+        for (i, part) in parts.iter().rev().enumerate() {
+            let value: u32 = part.parse().ok()?;
+            if i == 0 && parts.len() == 2 && value >= 60 {
+                return None;
+            }
+            total_seconds += value as f64 * multiplier;
+            multiplier *= 60.0;
+        }
+
+        Some((total_seconds * 1000.0).round() as i64)
+    }
+
     fn format_bitrate(bitrate: Option<usize>) -> String {
         match bitrate {
             Some(br) if br >= 1_000_000 => format!("{:.1} Mbps", br as f64 / 1_000_000.0),
             Some(br) if br >= 1_000 => format!("{:.1} kbps", br as f64 / 1_000.0),
             Some(br) => format!("{} bps", br),
-            None => "Unknown".to_string(),
+            None => tr!("info-unknown"),
+        }
+    }
+
+    /// The declared bitrate, followed by the measured one once it is known.
+    fn format_measured_bitrate(declared: Option<usize>, measured: Option<usize>) -> String {
+        match measured {
+            Some(measured) => format!(
+                "{} ({})",
+                Self::format_bitrate(declared),
+                tr!(
+                    "info-measured",
+                    bitrate = Self::format_bitrate(Some(measured))
+                )
+            ),
+            None => Self::format_bitrate(declared),
+        }
+    }
+
+    /// The position in the mode chosen by clicking the readout, next to the duration.
+    fn time_readout(&self) -> String {
+        let (position_ms, duration_ms) = (self.current_time_ms(), self.duration_ms());
+        let fps = self.video.as_ref().map(|video| video.stream_frame_rate());
+        match (self.settings.time_display, fps) {
+            (timecode::TimeDisplay::Remaining, _) => format!(
+                "-{} / {}",
+                Self::format_time((duration_ms - position_ms).max(0)),
+                Self::format_time(duration_ms)
+            ),
+            (timecode::TimeDisplay::Timecode, Some(fps)) => format!(
+                "{} / {}",
+                timecode::smpte(position_ms, fps),
+                timecode::smpte(duration_ms, fps)
+            ),
+            (timecode::TimeDisplay::Frame, Some(fps)) => tr!(
+                "time-frame",
+                frame = timecode::frame_number(position_ms, fps),
+                total = timecode::frame_number(duration_ms, fps)
+            ),
+            _ => format!(
+                "{} / {}",
+                Self::format_time(position_ms),
+                Self::format_time(duration_ms)
+            ),
         }
     }
 
@@ -190,33 +8622,77 @@ impl VideoPlayer {
         if ms > 0 {
             format!("{} ({})", Self::format_time(ms), ms)
         } else {
-            "Unknown".to_string()
+            tr!("info-unknown")
         }
     }
 
     fn format_optional_u32(value: Option<u32>) -> String {
         value
             .map(|v| v.to_string())
-            .unwrap_or_else(|| "Unknown".to_string())
+            .unwrap_or_else(|| tr!("info-unknown"))
     }
 
     fn format_optional_u16(value: Option<u16>) -> String {
         value
             .map(|v| v.to_string())
-            .unwrap_or_else(|| "Unknown".to_string())
+            .unwrap_or_else(|| tr!("info-unknown"))
     }
 }
 
 impl eframe::App for VideoPlayer {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
         if self.video.is_some() {
             self.update_video_frame(ctx);
+        } else if !self.paused && self.audio.as_ref().is_some_and(|audio| audio.is_finished()) {
+            self.handle_end_of_file(ctx);
+        }
+
+        if let Some(audio) = &mut self.audio {
+            audio.update_loudness();
+            if self.show_levels {
+                audio.update_levels();
+            }
+        }
+        self.update_timeline(ctx);
+        self.update_screenshots(ctx);
+        self.update_window_title(ctx);
+        self.update_tray(ctx, frame);
+        self.update_fullscreen(ctx);
+        self.update_window_scale(ctx);
+        self.remember_window_geometry(ctx);
+        self.update_filmstrip(ctx);
+        self.update_preload(ctx);
+        self.update_scene_detection(ctx);
+        self.update_output_device(ctx);
+        if self.subtitle_ocr.as_mut().is_some_and(|ocr| ocr.update()) {
+            ctx.request_repaint_after(Duration::from_millis(100));
         }
+        if self
+            .sync_analysis
+            .as_mut()
+            .is_some_and(|analysis| analysis.update())
+        {
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
+        self.update_sponsor_lookup();
+        self.update_recording();
+        self.update_web_start();
+        self.update_live_start();
+        self.update_live();
+        self.update_skip_segments();
+        self.update_ab_loop();
+        self.update_secondary_audio();
+        self.update_sleep_timer(ctx);
+        self.update_stats_log();
+        self.update_session_snapshot();
+        self.handle_commands(ctx);
+        self.update_sleep_inhibitor();
+        self.theme = theme::Theme::new(ctx.theme(), self.settings.accent_color);
 
         egui::CentralPanel::default().show(ctx, |ui| {
             let available_size = ui.available_size();
 
-            if self.video.is_none() {
+            if !self.has_media() {
                 ui.centered_and_justified(|ui| {
                     ui.vertical_centered(|ui| {
                         ui.add_space(50.0);
@@ -224,74 +8700,201 @@ impl eframe::App for VideoPlayer {
                         ui.add(egui::Label::new(
                             egui::RichText::new("Avio Player")
                                 .size(32.0)
-                                .color(egui::Color32::WHITE),
+                                .color(self.theme.text),
                         ));
 
                         ui.add_space(20.0);
 
                         ui.add(egui::Label::new(
-                            egui::RichText::new("Select a video file to start playing")
+                            egui::RichText::new(tr!("start-hint"))
                                 .size(16.0)
-                                .color(egui::Color32::LIGHT_GRAY),
+                                .color(self.theme.weak_text),
                         ));
 
-                        ui.add_space(30.0);
+                        ui.add_space(30.0);
+
+                        if let Some(start) = &self.web_start {
+                            let mut cancel = false;
+                            ui.horizontal(|ui| {
+                                ui.spinner();
+                                ui.label(tr!("start-web-resolving", url = start.page_url.clone()));
+                                cancel = ui.button(tr!("start-cancel")).clicked();
+                            });
+                            ui.add_space(20.0);
+                            ui.ctx().request_repaint_after(Duration::from_millis(100));
+                            if cancel {
+                                self.web_start = None;
+                            }
+                        }
+
+                        if let Some(start) = &self.live_start {
+                            let mut cancel = false;
+                            ui.horizontal(|ui| {
+                                ui.spinner();
+                                ui.label(tr!(
+                                    "start-live-buffering",
+                                    seconds = format!(
+                                        "{:.1}",
+                                        start.timeshift.buffered_ms() as f64 / 1000.0
+                                    )
+                                ));
+                                cancel = ui.button(tr!("start-cancel")).clicked();
+                            });
+                            ui.add_space(20.0);
+                            ui.ctx().request_repaint_after(Duration::from_millis(100));
+                            if cancel {
+                                self.live_start = None;
+                            }
+                        }
+
+                        if let Some(snapshot) =
+                            self.recovery.as_ref().map(|leftover| &leftover.snapshot)
+                        {
+                            let mut restore = false;
+                            let mut dismiss = false;
+                            let name = Path::new(&snapshot.current)
+                                .file_name()
+                                .map(|name| name.to_string_lossy().into_owned())
+                                .unwrap_or_else(|| snapshot.current.clone());
+                            egui::Frame::group(ui.style()).show(ui, |ui| {
+                                ui.set_max_width(420.0);
+                                ui.label(tr!(
+                                    "start-recovery",
+                                    file = name,
+                                    time = Self::format_time(snapshot.position_ms)
+                                ));
+                                ui.horizontal(|ui| {
+                                    restore = ui.button(tr!("start-recovery-continue")).clicked();
+                                    dismiss = ui.button(tr!("start-recovery-dismiss")).clicked();
+                                });
+                            });
+                            ui.add_space(20.0);
+
+                            if restore || dismiss {
+                                if let Some(leftover) = self.recovery.take() {
+                                    leftover.remove();
+                                    if restore {
+                                        self.restore_session(leftover.snapshot);
+                                    }
+                                }
+                            }
+                        }
+
+                        if ui
+                            .add(
+                                egui::Button::new(tr!("start-open-file"))
+                                    .min_size(egui::vec2(150.0, 40.0)),
+                            )
+                            .clicked()
+                        {
+                            self.perform_action(Action::OpenFile);
+                        }
+
+                        ui.add_space(10.0);
+
+                        if ui
+                            .add(
+                                egui::Button::new(tr!("start-open-folder"))
+                                    .min_size(egui::vec2(150.0, 40.0)),
+                            )
+                            .clicked()
+                        {
+                            self.perform_action(Action::OpenFolder);
+                        }
+
+                        ui.add_space(10.0);
+
+                        if ui
+                            .add(
+                                egui::Button::new(tr!("start-join-files"))
+                                    .min_size(egui::vec2(150.0, 40.0)),
+                            )
+                            .on_hover_text(tr!("start-join-files-hint"))
+                            .clicked()
+                        {
+                            self.perform_action(Action::JoinFiles);
+                        }
+
+                        ui.add_space(10.0);
+
+                        if ui
+                            .add(
+                                egui::Button::new(tr!("start-video-wall"))
+                                    .min_size(egui::vec2(150.0, 40.0)),
+                            )
+                            .on_hover_text(tr!("menu-open-video-wall-hint"))
+                            .clicked()
+                        {
+                            self.perform_action(Action::OpenVideoWall);
+                        }
+
+                        ui.add_space(10.0);
 
                         if ui
                             .add(
-                                egui::Button::new("Open Video File")
+                                egui::Button::new(tr!("start-open-url"))
                                     .min_size(egui::vec2(150.0, 40.0)),
                             )
                             .clicked()
                         {
-                            if let Some(path) = rfd::FileDialog::new()
-                                .add_filter(
-                                    "Video files",
-                                    &["mp4", "avi", "mkv", "mov", "wmv", "flv", "webm", "m4v"],
-                                )
-                                .add_filter("All files", &["*"])
-                                .pick_file()
-                            {
-                                if let Some(path_str) = path.to_str() {
-                                    if let Err(e) = self.load_video(path_str) {
-                                        eprintln!("Error loading video: {}", e);
-                                    }
-                                }
-                            }
+                            self.perform_action(Action::OpenUrl);
+                        }
+
+                        ui.add_space(20.0);
+
+                        let mut register = self.settings.register_file_types;
+                        if ui
+                            .checkbox(&mut register, tr!("start-register-file-types"))
+                            .changed()
+                        {
+                            self.set_file_types_registered(register);
                         }
+
+                        ui.add_space(10.0);
+                        ui.menu_button(tr!("menu-appearance"), |ui| self.show_appearance_menu(ui));
                     });
                 });
                 return;
             }
 
-            let control_height = if self.is_fullscreen { 0.0 } else { 80.0 };
-            let video_area_height = available_size.y - control_height;
+            let control_height = if self.show_controls { 80.0 } else { 0.0 };
+            let filmstrip_height = if self.filmstrip.is_some() && !self.is_fullscreen {
+                56.0
+            } else {
+                0.0
+            };
+            let video_area_height = available_size.y - control_height - filmstrip_height;
             let video_area = egui::Rect::from_min_size(
                 ui.min_rect().min,
                 egui::vec2(available_size.x, video_area_height),
             );
 
-            if let Some(texture) = &self.video_texture {
-                let texture_size = texture.size_vec2();
-                let aspect_ratio = texture_size.x / texture_size.y;
-
-                let display_size = if video_area.width() / video_area.height() > aspect_ratio {
-                    egui::vec2(video_area.height() * aspect_ratio, video_area.height())
-                } else {
-                    egui::vec2(video_area.width(), video_area.width() / aspect_ratio)
-                };
+            if self.video_window {
+                self.draw_video_window_placeholder(ui, video_area);
+            } else {
+                self.draw_video_area(ctx, ui, video_area);
+            }
 
-                let video_pos = video_area.center() - display_size * 0.5;
-                let video_rect = egui::Rect::from_min_size(video_pos, display_size);
+            let main_fullscreen = self.is_fullscreen && !self.video_window;
+            if let Some(filmstrip) = self.filmstrip.as_ref().filter(|_| !main_fullscreen) {
+                let filmstrip_area = egui::Rect::from_min_size(
+                    egui::pos2(0.0, video_area_height),
+                    egui::vec2(available_size.x, filmstrip_height),
+                );
 
-                ui.allocate_new_ui(egui::UiBuilder::new().max_rect(video_rect), |ui| {
-                    ui.add(egui::Image::from_texture(texture).fit_to_exact_size(display_size));
-                });
+                if let Some(target_ms) = filmstrip.show(
+                    ui,
+                    filmstrip_area,
+                    self.current_time_ms(),
+                    self.theme.accent,
+                ) {
+                    self.seek_to(target_ms);
+                }
             }
 
-            if !self.is_fullscreen {
+            if self.show_controls {
                 let control_area = egui::Rect::from_min_size(
-                    egui::pos2(0.0, video_area_height),
+                    egui::pos2(0.0, video_area_height + filmstrip_height),
                     egui::vec2(available_size.x, control_height),
                 );
 
@@ -299,7 +8902,7 @@ impl eframe::App for VideoPlayer {
                     ui.painter().rect_filled(
                         ui.max_rect(),
                         egui::Rounding::ZERO,
-                        egui::Color32::from_rgba_unmultiplied(0, 0, 0, 200),
+                        self.theme.control_bar,
                     );
 
                     ui.with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
@@ -308,27 +8911,37 @@ impl eframe::App for VideoPlayer {
                         ui.horizontal(|ui| {
                             ui.add_space(16.0);
 
-                            let current_time = if let Some(video) = &self.video {
-                                Self::format_time(video.get_current_timestamp_ms())
-                            } else {
-                                "00:00:00".to_string()
-                            };
-                            let total_time = if let Some(video) = &self.video {
-                                Self::format_time(video.get_duration_ms())
+                            let current_time = Self::format_time(self.current_time_ms());
+                            let total_time = Self::format_time(self.duration_ms());
+                            let time_text = if self.live.is_some() {
+                                let latency_ms = self.duration_ms() - self.current_time_ms();
+                                tr!(
+                                    "live-position",
+                                    time = current_time,
+                                    latency = format!("{:.1}", latency_ms.max(0) as f64 / 1000.0)
+                                )
                             } else {
-                                "00:00:00".to_string()
+                                self.time_readout()
                             };
-                            ui.add(egui::Label::new(
-                                egui::RichText::new(format!("{} / {}", current_time, total_time))
-                                    .color(egui::Color32::WHITE)
-                                    .size(14.0),
-                            ));
+                            let readout = ui.add(
+                                egui::Label::new(
+                                    egui::RichText::new(time_text)
+                                        .color(self.theme.text)
+                                        .size(14.0),
+                                )
+                                .sense(egui::Sense::click()),
+                            );
+                            if self.live.is_none()
+                                && readout.on_hover_text(tr!("control-time-display")).clicked()
+                            {
+                                self.settings.time_display =
+                                    self.settings.time_display.next(self.video.is_some());
+                            }
 
                             ui.add_space(12.0);
 
-                            let progress = if let Some(video) = &self.video {
-                                video.get_current_timestamp_ms() as f32
-                                    / video.get_duration_ms() as f32
+                            let progress = if self.duration_ms() > 0 {
+                                self.current_time_ms() as f32 / self.duration_ms() as f32
                             } else {
                                 0.0
                             };
@@ -339,10 +8952,26 @@ impl eframe::App for VideoPlayer {
                                 egui::Sense::click_and_drag(),
                             );
 
+                            // The scan covers the open file, which is only part of a timeline
+                            if self.show_bitrate_overlay && self.timeline.is_none() {
+                                if let Some(seconds) = self.bitrate_graph_seconds() {
+                                    let graph_rect = egui::Rect::from_min_max(
+                                        egui::pos2(rect.left(), rect.top() - 10.0),
+                                        egui::pos2(rect.right(), rect.top()),
+                                    );
+                                    bitrate_graph::draw_overlay(
+                                        ui.painter(),
+                                        graph_rect,
+                                        seconds,
+                                        self.duration_ms(),
+                                    );
+                                }
+                            }
+
                             ui.painter().rect_filled(
                                 rect,
                                 egui::Rounding::same(4.0),
-                                egui::Color32::from_gray(60),
+                                self.theme.track,
                             );
 
                             let fill_width = rect.width() * progress;
@@ -353,39 +8982,252 @@ impl eframe::App for VideoPlayer {
                             ui.painter().rect_filled(
                                 fill_rect,
                                 egui::Rounding::same(4.0),
-                                egui::Color32::from_rgb(100, 150, 255),
+                                self.theme.accent,
+                            );
+
+                            let duration_ms = self.duration_ms().max(1) as f32;
+                            let chapter_x = |start_ms: i64| {
+                                rect.left() + rect.width() * start_ms as f32 / duration_ms
+                            };
+                            if let Some(lookup) = self
+                                .web_video
+                                .as_ref()
+                                .and_then(|page| page.sponsor_lookup.as_ref())
+                            {
+                                for segment in lookup.segments() {
+                                    if self.settings.sponsorblock.action(segment.category)
+                                        == sponsorblock::SponsorAction::Ignore
+                                    {
+                                        continue;
+                                    }
+                                    let span = egui::Rect::from_x_y_ranges(
+                                        chapter_x(segment.start_ms)..=chapter_x(segment.end_ms),
+                                        rect.y_range(),
+                                    );
+                                    ui.painter().rect_filled(
+                                        span,
+                                        egui::Rounding::ZERO,
+                                        segment.category.color().gamma_multiply(0.8),
+                                    );
+                                }
+                            }
+
+                            if let Some(ab_loop) = &self.ab_loop {
+                                let start_x = chapter_x(ab_loop.start_ms);
+                                let end_x = ab_loop.end_ms.map_or(start_x, chapter_x);
+                                ui.painter().rect_filled(
+                                    egui::Rect::from_x_y_ranges(start_x..=end_x, rect.y_range()),
+                                    egui::Rounding::ZERO,
+                                    self.theme.text.gamma_multiply(0.3),
+                                );
+                                for x in [start_x, end_x] {
+                                    ui.painter().line_segment(
+                                        [
+                                            egui::pos2(x, rect.top() - 3.0),
+                                            egui::pos2(x, rect.bottom() + 3.0),
+                                        ],
+                                        egui::Stroke::new(2.0, self.theme.text),
+                                    );
+                                }
+                            }
+
+                            let marks = [self.cutlist.in_ms, self.cutlist.out_ms];
+                            for x in marks.into_iter().flatten().map(chapter_x) {
+                                ui.painter().line_segment(
+                                    [
+                                        egui::pos2(x, rect.top() - 4.0),
+                                        egui::pos2(x, rect.bottom()),
+                                    ],
+                                    egui::Stroke::new(2.0, self.theme.accent),
+                                );
+                            }
+                            for marker in &self.cutlist.markers {
+                                let x = chapter_x(marker.time_ms);
+                                ui.painter().line_segment(
+                                    [egui::pos2(x, rect.top() - 4.0), egui::pos2(x, rect.top())],
+                                    egui::Stroke::new(2.0, self.theme.accent),
+                                );
+                            }
+
+                            for chapter in self.chapters().iter().filter(|c| c.start_time_ms > 0) {
+                                let x = chapter_x(chapter.start_time_ms);
+                                ui.painter().line_segment(
+                                    [
+                                        egui::pos2(x, rect.top() - 2.0),
+                                        egui::pos2(x, rect.bottom() + 2.0),
+                                    ],
+                                    egui::Stroke::new(2.0, self.theme.marker),
+                                );
+                            }
+                            // Chapters that end before the next one starts get a tick where
+                            // they end
+                            let chapters = self.chapters();
+                            for (index, chapter) in chapters.iter().enumerate() {
+                                let next_start = chapters.get(index + 1).map(|c| c.start_time_ms);
+                                if chapter.end_time_ms >= self.duration_ms()
+                                    || next_start == Some(chapter.end_time_ms)
+                                {
+                                    continue;
+                                }
+                                let x = chapter_x(chapter.end_time_ms);
+                                ui.painter().line_segment(
+                                    [
+                                        egui::pos2(x, rect.bottom()),
+                                        egui::pos2(x, rect.bottom() + 3.0),
+                                    ],
+                                    egui::Stroke::new(1.0, self.theme.faint_text),
+                                );
+                            }
+
+                            // Time under the cursor, with the chapter it falls in on a second line
+                            let hover_text = response.hover_pos().filter(|_| self.has_media()).map(
+                                |hover_pos| {
+                                    let relative_pos = (hover_pos.x - rect.left()) / rect.width();
+                                    let hover_ms = self.chapter_snap(rect, hover_pos.x).unwrap_or(
+                                        (self.duration_ms() as f32 * relative_pos.clamp(0.0, 1.0))
+                                            as i64,
+                                    );
+                                    let time = Self::format_time(hover_ms);
+                                    match self
+                                        .chapters()
+                                        .iter()
+                                        .rev()
+                                        .find(|chapter| chapter.start_time_ms <= hover_ms)
+                                    {
+                                        Some(chapter) => format!("{}\n{}", time, chapter.title),
+                                        None => time,
+                                    }
+                                },
                             );
+                            let response = match hover_text {
+                                Some(text) => response.on_hover_text(text),
+                                None => response,
+                            };
 
                             if response.hovered() {
                                 if let Some(hover_pos) = response.hover_pos() {
-                                    let hover_x = hover_pos.x.clamp(rect.left(), rect.right());
+                                    let hover_x = match self.chapter_snap(rect, hover_pos.x) {
+                                        Some(snapped_ms) => chapter_x(snapped_ms),
+                                        None => hover_pos.x.clamp(rect.left(), rect.right()),
+                                    };
                                     ui.painter().circle_filled(
                                         egui::pos2(hover_x, rect.center().y),
                                         6.0,
-                                        egui::Color32::WHITE,
+                                        self.theme.text,
                                     );
                                 }
                             }
 
-                            if (response.clicked() || response.dragged()) && self.video.is_some() {
+                            // Dragging previews keyframes; letting go seeks to the exact frame and
+                            // brings the audio along
+                            if (response.clicked() || response.dragged() || response.drag_stopped())
+                                && self.has_media()
+                            {
                                 if let Some(pointer_pos) = response.interact_pointer_pos() {
                                     let relative_pos = (pointer_pos.x - rect.left()) / rect.width();
                                     let seek_progress = relative_pos.clamp(0.0, 1.0);
 
-                                    if let Some(video) = &mut self.video {
-                                        let target_ms =
-                                            (video.get_duration_ms() as f32 * seek_progress) as i64;
+                                    let target_ms =
+                                        self.chapter_snap(rect, pointer_pos.x).unwrap_or(
+                                            (self.duration_ms() as f32 * seek_progress) as i64,
+                                        );
+                                    if response.dragged() {
+                                        self.scrubbing = self.settings.audible_scrubbing;
+                                        self.preview_seek(ui.ctx(), target_ms);
+                                        self.scrubbing = false;
+                                    } else {
+                                        self.seek_to(target_ms);
+                                    }
+                                } else {
+                                    // Space or Enter on the focused seek bar
+                                    self.perform_action(Action::TogglePause);
+                                }
+                            }
 
-                                        if let Err(e) = video.seek(target_ms) {
-                                            eprintln!("Seek error: {}", e);
-                                        }
+                            // Reached with Tab; the arrow keys seek instead of moving focus
+                            ui.memory_mut(|memory| {
+                                memory.set_focus_lock_filter(
+                                    response.id,
+                                    egui::EventFilter {
+                                        horizontal_arrows: true,
+                                        ..Default::default()
+                                    },
+                                )
+                            });
+                            if response.has_focus() {
+                                ui.painter().rect_stroke(
+                                    rect.expand(3.0),
+                                    egui::Rounding::same(6.0),
+                                    egui::Stroke::new(2.0, self.theme.accent),
+                                );
+                                ui.painter().circle_filled(
+                                    egui::pos2(rect.left() + fill_width, rect.center().y),
+                                    6.0,
+                                    self.theme.text,
+                                );
 
-                                        if let Some(audio) = &self.audio {
-                                            audio.seek(target_ms);
-                                        }
-                                    }
+                                let (back, forward) = ui.input_mut(|i| {
+                                    (
+                                        i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowLeft),
+                                        i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowRight),
+                                    )
+                                });
+                                if back {
+                                    self.perform_action(Action::SeekRelative(-5000));
                                 }
+                                if forward {
+                                    self.perform_action(Action::SeekRelative(5000));
+                                }
+                            }
+                            let (decrement, increment) = ui.input(|i| {
+                                (
+                                    i.has_accesskit_action_request(
+                                        response.id,
+                                        egui::accesskit::Action::Decrement,
+                                    ),
+                                    i.has_accesskit_action_request(
+                                        response.id,
+                                        egui::accesskit::Action::Increment,
+                                    ),
+                                )
+                            });
+                            if decrement {
+                                self.perform_action(Action::SeekRelative(-5000));
                             }
+                            if increment {
+                                self.perform_action(Action::SeekRelative(5000));
+                            }
+
+                            let position_seconds = self.current_time_ms() as f64 / 1000.0;
+                            let duration_seconds = self.duration_ms() as f64 / 1000.0;
+                            let state = if self.paused {
+                                tr!(
+                                    "playback-state-paused",
+                                    position = current_time,
+                                    duration = total_time
+                                )
+                            } else {
+                                tr!(
+                                    "playback-state-playing",
+                                    position = current_time,
+                                    duration = total_time
+                                )
+                            };
+                            response.widget_info(|| {
+                                egui::WidgetInfo::slider(
+                                    self.has_media(),
+                                    position_seconds,
+                                    tr!("control-seek"),
+                                )
+                            });
+                            response.ctx.accesskit_node_builder(response.id, |builder| {
+                                builder.set_value(state);
+                                builder.set_min_numeric_value(0.0);
+                                builder.set_max_numeric_value(duration_seconds);
+                                builder.set_numeric_value_step(5.0);
+                                builder.add_action(egui::accesskit::Action::Decrement);
+                                builder.add_action(egui::accesskit::Action::Increment);
+                            });
 
                             ui.add_space(16.0);
                         });
@@ -402,110 +9244,204 @@ impl eframe::App for VideoPlayer {
                                     let play_button = egui::Button::new(
                                         egui::RichText::new(button_text)
                                             .size(16.0)
-                                            .color(egui::Color32::WHITE),
+                                            .color(self.theme.text),
                                     )
                                     .min_size(egui::vec2(40.0, 32.0))
-                                    .fill(egui::Color32::from_gray(40));
+                                    .fill(self.theme.button);
 
-                                    if ui.add(play_button).clicked() {
-                                        self.paused = !self.paused;
-                                        if let Some(audio) = &self.audio {
-                                            if self.paused {
-                                                audio.pause();
-                                            } else {
-                                                audio.play();
-                                            }
-                                        }
+                                    let play_label = if self.paused {
+                                        tr!("control-play")
+                                    } else {
+                                        tr!("control-pause")
+                                    };
+                                    if Self::label_control(ui.add(play_button), play_label)
+                                        .clicked()
+                                    {
+                                        self.perform_action(Action::TogglePause);
                                     }
 
                                     ui.add_space(8.0);
 
                                     let back_button = egui::Button::new(
-                                        egui::RichText::new("⏪")
-                                            .size(14.0)
-                                            .color(egui::Color32::WHITE),
+                                        egui::RichText::new("⏪").size(14.0).color(self.theme.text),
                                     )
                                     .min_size(egui::vec2(36.0, 32.0))
-                                    .fill(egui::Color32::from_gray(40));
-
-                                    if ui.add(back_button).clicked() && self.video.is_some() {
-                                        if let Some(video) = &mut self.video {
-                                            let target_ms =
-                                                (video.get_current_timestamp_ms() - 10000).max(0);
-                                            if let Err(e) = video.seek(target_ms) {
-                                                eprintln!("Seek error: {}", e);
-                                            }
-                                            if let Some(audio) = &self.audio {
-                                                audio.seek(target_ms);
-                                            }
-                                        }
+                                    .fill(self.theme.button);
+
+                                    if Self::label_control(ui.add(back_button), tr!("control-back"))
+                                        .clicked()
+                                    {
+                                        self.perform_action(Action::SeekRelative(-10000));
                                     }
 
                                     ui.add_space(12.0);
 
                                     let open_button = egui::Button::new(
-                                        egui::RichText::new("📁")
-                                            .size(14.0)
-                                            .color(egui::Color32::WHITE),
+                                        egui::RichText::new("📁").size(14.0).color(self.theme.text),
                                     )
                                     .min_size(egui::vec2(36.0, 32.0))
-                                    .fill(egui::Color32::from_gray(40));
-
-                                    if ui.add(open_button).clicked() {
-                                        if let Some(path) = rfd::FileDialog::new()
-                                            .add_filter(
-                                                "Video files",
-                                                &[
-                                                    "mp4", "avi", "mkv", "mov", "wmv", "flv",
-                                                    "webm", "m4v",
-                                                ],
-                                            )
-                                            .add_filter("All files", &["*"])
-                                            .pick_file()
-                                        {
-                                            if let Some(path_str) = path.to_str() {
-                                                if let Err(e) = self.load_video(path_str) {
-                                                    eprintln!("Error loading video: {}", e);
-                                                }
-                                            }
-                                        }
+                                    .fill(self.theme.button);
+
+                                    if Self::label_control(ui.add(open_button), tr!("control-open"))
+                                        .clicked()
+                                    {
+                                        self.perform_action(Action::OpenFile);
                                     }
 
                                     ui.add_space(8.0);
 
                                     let info_button = egui::Button::new(
-                                        egui::RichText::new("ℹ")
-                                            .size(14.0)
-                                            .color(egui::Color32::WHITE),
+                                        egui::RichText::new("ℹ").size(14.0).color(self.theme.text),
+                                    )
+                                    .min_size(egui::vec2(36.0, 32.0))
+                                    .fill(self.theme.button);
+
+                                    if Self::label_control(
+                                        ui.add(info_button),
+                                        tr!("control-media-info"),
+                                    )
+                                    .clicked()
+                                    {
+                                        self.perform_action(Action::ToggleMediaInfo);
+                                    }
+
+                                    ui.add_space(8.0);
+
+                                    let subtitle_button = egui::Button::new(
+                                        egui::RichText::new("💬").size(14.0).color(self.theme.text),
                                     )
                                     .min_size(egui::vec2(36.0, 32.0))
-                                    .fill(egui::Color32::from_gray(40));
+                                    .fill(self.theme.button);
+
+                                    if Self::label_control(
+                                        ui.add(subtitle_button),
+                                        tr!("control-search-subtitles"),
+                                    )
+                                    .clicked()
+                                    {
+                                        self.perform_action(Action::SearchSubtitles);
+                                    }
+
+                                    if self.has_media() {
+                                        ui.add_space(8.0);
+
+                                        let menu = ui.menu_button(
+                                            egui::RichText::new("📑")
+                                                .size(14.0)
+                                                .color(self.theme.text),
+                                            |ui| self.show_chapters_menu(ui),
+                                        );
+                                        Self::label_control(menu.response, tr!("control-chapters"));
+                                    }
+
+                                    if self.video.is_some() {
+                                        ui.add_space(8.0);
+
+                                        let menu = ui.menu_button(
+                                            egui::RichText::new("🎞")
+                                                .size(14.0)
+                                                .color(self.theme.text),
+                                            |ui| self.show_video_menu(ui),
+                                        );
+                                        Self::label_control(menu.response, tr!("control-video"));
+                                    }
 
-                                    if ui.add(info_button).clicked() {
-                                        self.show_media_info = !self.show_media_info;
+                                    if self.audio.is_some() {
+                                        ui.add_space(8.0);
+
+                                        let menu = ui.menu_button(
+                                            egui::RichText::new("🔈")
+                                                .size(14.0)
+                                                .color(self.theme.text),
+                                            |ui| self.show_audio_menu(ui),
+                                        );
+                                        Self::label_control(menu.response, tr!("control-audio"));
                                     }
 
                                     ui.add_space(8.0);
 
                                     let forward_button = egui::Button::new(
-                                        egui::RichText::new("⏩")
-                                            .size(14.0)
-                                            .color(egui::Color32::WHITE),
+                                        egui::RichText::new("⏩").size(14.0).color(self.theme.text),
                                     )
                                     .min_size(egui::vec2(36.0, 32.0))
-                                    .fill(egui::Color32::from_gray(40));
-
-                                    if ui.add(forward_button).clicked() && self.video.is_some() {
-                                        if let Some(video) = &mut self.video {
-                                            let target_ms = (video.get_current_timestamp_ms()
-                                                + 10000)
-                                                .min(video.get_duration_ms());
-                                            if let Err(e) = video.seek(target_ms) {
-                                                eprintln!("Seek error: {}", e);
-                                            }
-                                            if let Some(audio) = &self.audio {
-                                                audio.seek(target_ms);
-                                            }
+                                    .fill(self.theme.button);
+
+                                    if Self::label_control(
+                                        ui.add(forward_button),
+                                        tr!("control-forward"),
+                                    )
+                                    .clicked()
+                                    {
+                                        self.perform_action(Action::SeekRelative(10000));
+                                    }
+
+                                    ui.add_space(8.0);
+
+                                    let shuffle_button = egui::Button::new(
+                                        egui::RichText::new("🔀").size(14.0).color(self.theme.text),
+                                    )
+                                    .min_size(egui::vec2(36.0, 32.0))
+                                    .fill(if self.settings.shuffle {
+                                        self.theme.accent
+                                    } else {
+                                        self.theme.button
+                                    });
+
+                                    if Self::label_control(
+                                        ui.add(shuffle_button),
+                                        tr!("control-shuffle"),
+                                    )
+                                    .clicked()
+                                    {
+                                        self.perform_action(Action::ToggleShuffle);
+                                    }
+
+                                    ui.add_space(8.0);
+
+                                    let sleep_active =
+                                        self.stop_after_current || self.sleep_timer.is_some();
+                                    let menu = ui.menu_button(
+                                        egui::RichText::new("🌙").size(14.0).color(
+                                            if sleep_active {
+                                                self.theme.accent
+                                            } else {
+                                                self.theme.text
+                                            },
+                                        ),
+                                        |ui| self.show_sleep_timer_menu(ui),
+                                    );
+                                    Self::label_control(menu.response, tr!("control-sleep-timer"));
+
+                                    let is_stream = self
+                                        .media_path()
+                                        .is_some_and(|path| playlist::is_url(path));
+                                    if is_stream || self.recording.is_some() {
+                                        ui.add_space(8.0);
+
+                                        let record_color = if self.recording.is_some() {
+                                            egui::Color32::from_rgb(255, 80, 80)
+                                        } else {
+                                            self.theme.text
+                                        };
+                                        let record_button = egui::Button::new(
+                                            egui::RichText::new("⏺").size(14.0).color(record_color),
+                                        )
+                                        .min_size(egui::vec2(36.0, 32.0))
+                                        .fill(self.theme.button);
+
+                                        let record_label = match &self.recording {
+                                            Some(recording) => tr!(
+                                                "control-stop-recording",
+                                                time = Self::format_time(
+                                                    recording.started.elapsed().as_millis() as i64
+                                                )
+                                            ),
+                                            None => tr!("control-record"),
+                                        };
+                                        if Self::label_control(ui.add(record_button), record_label)
+                                            .clicked()
+                                        {
+                                            self.perform_action(Action::ToggleRecording);
                                         }
                                     }
                                 },
@@ -521,33 +9457,80 @@ impl eframe::App for VideoPlayer {
                                     let fullscreen_button = egui::Button::new(
                                         egui::RichText::new(fullscreen_icon)
                                             .size(14.0)
-                                            .color(egui::Color32::WHITE),
+                                            .color(self.theme.text),
                                     )
                                     .min_size(egui::vec2(36.0, 32.0))
-                                    .fill(egui::Color32::from_gray(40));
+                                    .fill(self.theme.button);
 
-                                    if ui.add(fullscreen_button).clicked() {
-                                        self.is_fullscreen = !self.is_fullscreen;
+                                    if Self::label_control(
+                                        ui.add(fullscreen_button),
+                                        tr!("control-fullscreen"),
+                                    )
+                                    .clicked()
+                                    {
+                                        self.perform_action(Action::ToggleFullscreen);
                                     }
 
                                     ui.add_space(12.0);
 
-                                    ui.add(egui::Label::new(
-                                        egui::RichText::new("🔊")
+                                    let volume_icon = if self.muted { "🔇" } else { "🔊" };
+                                    let mute_button = egui::Button::new(
+                                        egui::RichText::new(volume_icon)
                                             .size(14.0)
-                                            .color(egui::Color32::WHITE),
-                                    ));
+                                            .color(self.theme.text),
+                                    )
+                                    .frame(false);
+
+                                    let mute_label = if self.muted {
+                                        tr!("control-unmute")
+                                    } else {
+                                        tr!("control-mute")
+                                    };
+                                    let mute_response =
+                                        Self::label_control(ui.add(mute_button), mute_label);
+                                    self.draw_focus_ring(ui, &mute_response);
+                                    if mute_response.clicked() {
+                                        self.perform_action(Action::ToggleMute);
+                                    }
                                     ui.add_space(4.0);
-                                    let volume_response = ui.add_sized(
-                                        [80.0, 20.0],
-                                        egui::Slider::new(&mut self.volume, 0.0..=1.0)
-                                            .show_value(false),
+                                    let volume_color = self.volume_color();
+                                    let volume_response = ui
+                                        .scope(|ui| {
+                                            ui.visuals_mut().selection.bg_fill = volume_color;
+                                            ui.add_sized(
+                                                [100.0, 20.0],
+                                                egui::Slider::new(
+                                                    &mut self.volume,
+                                                    0.0..=MAX_VOLUME,
+                                                )
+                                                .trailing_fill(true)
+                                                .show_value(false),
+                                            )
+                                        })
+                                        .inner;
+                                    volume_response
+                                        .ctx
+                                        .accesskit_node_builder(volume_response.id, |builder| {
+                                            builder.set_name(tr!("control-volume"))
+                                        });
+
+                                    // Mark 100%; the range above it is amplified
+                                    let marker_x = egui::lerp(
+                                        volume_response.rect.x_range(),
+                                        1.0 / MAX_VOLUME,
+                                    );
+                                    ui.painter().vline(
+                                        marker_x,
+                                        volume_response
+                                            .rect
+                                            .shrink2(egui::vec2(0.0, 4.0))
+                                            .y_range(),
+                                        egui::Stroke::new(1.0, self.theme.faint_text),
                                     );
 
                                     if volume_response.changed() {
-                                        if let Some(audio) = &self.audio {
-                                            audio.set_volume(self.volume);
-                                        }
+                                        self.muted = false;
+                                        self.apply_volume();
                                     }
 
                                     ui.add_space(20.0);
@@ -558,8 +9541,36 @@ impl eframe::App for VideoPlayer {
                                             self.fps_counter.fps
                                         ))
                                         .size(12.0)
-                                        .color(egui::Color32::from_gray(180)),
+                                        .color(self.theme.faint_text),
                                     ));
+
+                                    if let Some(offset_ms) = self.av_sync.offset_ms() {
+                                        let text = tr!(
+                                            "control-av-offset",
+                                            offset = format!("{:+}", offset_ms)
+                                        );
+                                        if self.av_sync.is_out_of_sync() {
+                                            let badge = egui::Button::new(
+                                                egui::RichText::new(format!("⚠ {}", text))
+                                                    .size(12.0)
+                                                    .color(egui::Color32::BLACK),
+                                            )
+                                            .fill(egui::Color32::from_rgb(255, 170, 0));
+                                            if ui
+                                                .add(badge)
+                                                .on_hover_text(tr!("control-av-resync"))
+                                                .clicked()
+                                            {
+                                                self.perform_action(Action::ResyncAudio);
+                                            }
+                                        } else {
+                                            ui.add(egui::Label::new(
+                                                egui::RichText::new(text)
+                                                    .size(12.0)
+                                                    .color(self.theme.faint_text),
+                                            ));
+                                        }
+                                    }
                                 },
                             );
                         });
@@ -570,25 +9581,39 @@ impl eframe::App for VideoPlayer {
             }
         });
 
+        if let Some(measurement) = &mut self.bitrate_measurement {
+            if self.show_media_info && measurement.update() {
+                ctx.request_repaint_after(Duration::from_millis(100));
+            }
+        }
+
+        let mut start_bitrate_measurement = false;
         if self.show_media_info {
-            egui::Window::new("Media Information")
+            egui::Window::new(tr!("window-media-information"))
                 .default_size([600.0, 400.0])
                 .resizable(true)
                 .show(ctx, |ui| {
                     if let Some(media_info) = &self.media_info {
+                        let measured = self
+                            .bitrate_measurement
+                            .as_ref()
+                            .and_then(|measurement| measurement.measured());
+                        let measured_stream =
+                            |index: usize| measured.and_then(|m| m.streams.get(&index).copied());
+
                         egui::ScrollArea::vertical().show(ui, |ui| {
-                            ui.heading("File Information");
+                            ui.heading(tr!("info-file-information"));
                             ui.separator();
 
                             if let Some(filename) = &self.current_filename {
                                 ui.horizontal(|ui| {
-                                    ui.label("Path:");
+                                    ui.label(tr!("info-path"));
                                     ui.label(filename);
                                 });
                             }
 
                             ui.horizontal(|ui| {
-                                ui.label("Format:");
+                                ui.label(tr!("info-format"));
                                 ui.label(format!(
                                     "{} ({})",
                                     media_info.format_name, media_info.format_description
@@ -596,25 +9621,87 @@ impl eframe::App for VideoPlayer {
                             });
 
                             ui.horizontal(|ui| {
-                                ui.label("Duration:");
+                                ui.label(tr!("info-duration"));
                                 ui.label(Self::format_duration(media_info.duration_ms));
                             });
 
                             ui.horizontal(|ui| {
-                                ui.label("Overall Bitrate:");
-                                ui.label(Self::format_bitrate(media_info.bit_rate));
+                                ui.label(tr!("info-overall-bitrate"));
+                                ui.label(Self::format_measured_bitrate(
+                                    media_info.bit_rate,
+                                    measured.and_then(|m| m.overall),
+                                ));
+                            });
+
+                            let is_url = self
+                                .current_filename
+                                .as_deref()
+                                .is_some_and(playlist::is_url);
+                            ui.horizontal(|ui| match &self.bitrate_measurement {
+                                None => {
+                                    if ui
+                                        .add_enabled(
+                                            !is_url,
+                                            egui::Button::new(tr!("info-measure-bitrates")),
+                                        )
+                                        .on_hover_text(tr!("info-measure-bitrates-hint"))
+                                        .on_disabled_hover_text(tr!("info-local-only"))
+                                        .clicked()
+                                    {
+                                        start_bitrate_measurement = true;
+                                    }
+                                }
+                                Some(measurement) => match &measurement.result {
+                                    None => {
+                                        ui.spinner();
+                                        ui.label(tr!("info-measuring-bitrates"));
+                                    }
+                                    Some(Err(e)) => {
+                                        ui.colored_label(
+                                            egui::Color32::LIGHT_RED,
+                                            tr!("info-measuring-failed", error = e),
+                                        );
+                                    }
+                                    Some(Ok(_)) => {}
+                                },
                             });
 
                             ui.add_space(15.0);
 
+                            if !self.track_tags.is_empty() {
+                                ui.heading(tr!("info-tags"));
+                                ui.separator();
+
+                                let tags = [
+                                    (tr!("info-tag-title"), &self.track_tags.title),
+                                    (tr!("info-tag-artist"), &self.track_tags.artist),
+                                    (tr!("info-tag-album"), &self.track_tags.album),
+                                    (tr!("info-tag-track"), &self.track_tags.track),
+                                    (tr!("info-tag-year"), &self.track_tags.year),
+                                ];
+                                for (label, value) in tags {
+                                    if let Some(value) = value {
+                                        ui.horizontal(|ui| {
+                                            ui.label(label);
+                                            ui.label(value);
+                                        });
+                                    }
+                                }
+                                ui.add_space(15.0);
+                            }
+
                             if !media_info.video_streams.is_empty() {
-                                ui.heading("Video Streams");
+                                ui.heading(tr!("info-video-streams"));
                                 ui.separator();
 
                                 for (i, stream) in media_info.video_streams.iter().enumerate() {
-                                    ui.label(format!("Stream {} (Index: {})", i, stream.index));
+                                    ui.label(tr!(
+                                        "info-stream-heading",
+                                        number = i,
+                                        index = stream.index
+                                    ));
                                     ui.horizontal(|ui| {
-                                        ui.label("  Resolution:");
+                                        ui.label(format!("  {}", tr!("info-resolution")));
                                         ui.label(format!(
                                             "{}x{}",
                                             Self::format_optional_u32(stream.width),
@@ -622,18 +9709,18 @@ impl eframe::App for VideoPlayer {
                                         ));
                                     });
                                     ui.horizontal(|ui| {
-                                        ui.label("  Codec:");
+                                        ui.label(format!("  {}", tr!("info-codec")));
                                         ui.label(format!(
                                             "{} ({})",
                                             stream.codec_name, stream.codec_description
                                         ));
                                     });
                                     ui.horizontal(|ui| {
-                                        ui.label("  Codec ID:");
+                                        ui.label(format!("  {}", tr!("info-codec-id")));
                                         ui.label(&stream.codec_id);
                                     });
                                     ui.horizontal(|ui| {
-                                        ui.label("  Frame Rate:");
+                                        ui.label(format!("  {}", tr!("info-frame-rate")));
                                         let fps = stream
                                             .frame_rate
                                             .as_ref()
@@ -643,31 +9730,34 @@ impl eframe::App for VideoPlayer {
                                                     fr.value, fr.numerator, fr.denominator
                                                 )
                                             })
-                                            .unwrap_or_else(|| "Unknown".to_string());
+                                            .unwrap_or_else(|| tr!("info-unknown"));
                                         ui.label(fps);
                                     });
                                     ui.horizontal(|ui| {
-                                        ui.label("  Pixel Format:");
+                                        ui.label(format!("  {}", tr!("info-pixel-format")));
                                         let pixel_fmt = stream
                                             .pixel_format
                                             .as_ref()
                                             .map(|pf| format!("{:?}", pf))
-                                            .unwrap_or_else(|| "Unknown".to_string());
+                                            .unwrap_or_else(|| tr!("info-unknown"));
                                         ui.label(pixel_fmt);
                                     });
                                     ui.horizontal(|ui| {
-                                        ui.label("  Bitrate:");
-                                        ui.label(Self::format_bitrate(stream.bit_rate));
+                                        ui.label(format!("  {}", tr!("info-bitrate")));
+                                        ui.label(Self::format_measured_bitrate(
+                                            stream.bit_rate,
+                                            measured_stream(stream.index),
+                                        ));
                                     });
                                     if let Some(frames) = stream.frames {
                                         ui.horizontal(|ui| {
-                                            ui.label("  Frame Count:");
+                                            ui.label(format!("  {}", tr!("info-frame-count")));
                                             ui.label(frames.to_string());
                                         });
                                     }
                                     if let Some(ref aspect_ratio) = stream.aspect_ratio {
                                         ui.horizontal(|ui| {
-                                            ui.label("  Aspect Ratio:");
+                                            ui.label(format!("  {}", tr!("info-aspect-ratio")));
                                             ui.label(format!(
                                                 "{:.3} ({}/{})",
                                                 aspect_ratio.value,
@@ -678,12 +9768,18 @@ impl eframe::App for VideoPlayer {
                                     }
                                     if let Some(ref color_space) = stream.color_space {
                                         ui.horizontal(|ui| {
-                                            ui.label("  Color Space:");
+                                            ui.label(format!("  {}", tr!("info-color-space")));
                                             ui.label(format!("{:?}", color_space));
                                         });
                                     }
+                                    if stream.equirectangular {
+                                        ui.horizontal(|ui| {
+                                            ui.label(format!("  {}", tr!("info-projection")));
+                                            ui.label(tr!("info-equirectangular"));
+                                        });
+                                    }
                                     ui.horizontal(|ui| {
-                                        ui.label("  Time Base:");
+                                        ui.label(format!("  {}", tr!("info-time-base")));
                                         ui.label(format!(
                                             "{}/{} ({:.6})",
                                             stream.time_base.numerator,
@@ -692,18 +9788,21 @@ impl eframe::App for VideoPlayer {
                                         ));
                                     });
                                     ui.horizontal(|ui| {
-                                        ui.label("  Disposition:");
+                                        ui.label(format!("  {}", tr!("info-disposition")));
                                         ui.label(format!("0x{:X}", stream.disposition));
                                     });
                                     if let Some(ref capabilities) = stream.codec_capabilities {
                                         ui.horizontal(|ui| {
-                                            ui.label("  Codec Capabilities:");
+                                            ui.label(format!(
+                                                "  {}",
+                                                tr!("info-codec-capabilities")
+                                            ));
                                             ui.label(format!("{:?}", capabilities));
                                         });
                                     }
                                     if let Some(ref profiles) = stream.codec_profiles {
                                         ui.horizontal(|ui| {
-                                            ui.label("  Codec Profiles:");
+                                            ui.label(format!("  {}", tr!("info-codec-profiles")));
                                             let profile_names: Vec<String> = profiles
                                                 .iter()
                                                 .map(|p| format!("{:?}", p))
@@ -712,14 +9811,17 @@ impl eframe::App for VideoPlayer {
                                         });
                                     }
                                     if !stream.metadata.is_empty() {
-                                        ui.collapsing("  Video Stream Metadata", |ui| {
-                                            for (key, value) in &stream.metadata {
-                                                ui.horizontal(|ui| {
-                                                    ui.label(format!("    {}:", key));
-                                                    ui.label(value);
-                                                });
-                                            }
-                                        });
+                                        ui.collapsing(
+                                            format!("  {}", tr!("info-video-stream-metadata")),
+                                            |ui| {
+                                                for (key, value) in &stream.metadata {
+                                                    ui.horizontal(|ui| {
+                                                        ui.label(format!("    {}:", key));
+                                                        ui.label(value);
+                                                    });
+                                                }
+                                            },
+                                        );
                                     }
                                     ui.add_space(10.0);
                                 }
@@ -727,60 +9829,67 @@ impl eframe::App for VideoPlayer {
                             }
 
                             if !media_info.audio_streams.is_empty() {
-                                ui.heading("Audio Streams");
+                                ui.heading(tr!("info-audio-streams"));
                                 ui.separator();
 
                                 for (i, stream) in media_info.audio_streams.iter().enumerate() {
-                                    ui.label(format!("Stream {} (Index: {})", i, stream.index));
+                                    ui.label(tr!(
+                                        "info-stream-heading",
+                                        number = i,
+                                        index = stream.index
+                                    ));
                                     ui.horizontal(|ui| {
-                                        ui.label("  Sample Rate:");
+                                        ui.label(format!("  {}", tr!("info-sample-rate")));
                                         ui.label(format!(
                                             "{} Hz",
                                             Self::format_optional_u32(stream.sample_rate)
                                         ));
                                     });
                                     ui.horizontal(|ui| {
-                                        ui.label("  Channels:");
+                                        ui.label(format!("  {}", tr!("info-channels")));
                                         ui.label(Self::format_optional_u16(stream.channels));
                                     });
                                     ui.horizontal(|ui| {
-                                        ui.label("  Codec:");
+                                        ui.label(format!("  {}", tr!("info-codec")));
                                         ui.label(format!(
                                             "{} ({})",
                                             stream.codec_name, stream.codec_description
                                         ));
                                     });
                                     ui.horizontal(|ui| {
-                                        ui.label("  Codec ID:");
+                                        ui.label(format!("  {}", tr!("info-codec-id")));
                                         ui.label(&stream.codec_id);
                                     });
                                     ui.horizontal(|ui| {
-                                        ui.label("  Sample Format:");
+                                        ui.label(format!("  {}", tr!("info-sample-format")));
                                         let sample_fmt = stream
                                             .sample_format
                                             .as_ref()
                                             .map(|sf| format!("{:?}", sf))
-                                            .unwrap_or_else(|| "Unknown".to_string());
+                                            .unwrap_or_else(|| tr!("info-unknown"));
                                         ui.label(sample_fmt);
                                     });
                                     ui.horizontal(|ui| {
-                                        ui.label("  Bitrate:");
-                                        ui.label(Self::format_bitrate(stream.bit_rate));
+                                        ui.label(format!("  {}", tr!("info-bitrate")));
+                                        ui.label(Self::format_measured_bitrate(
+                                            stream.bit_rate,
+                                            measured_stream(stream.index),
+                                        ));
                                     });
                                     if let Some(ref channel_layout) = stream.channel_layout {
                                         ui.horizontal(|ui| {
-                                            ui.label("  Channel Layout:");
+                                            ui.label(format!("  {}", tr!("info-channel-layout")));
                                             ui.label(format!("{:?}", channel_layout));
                                         });
                                     }
                                     if let Some(frames) = stream.frames {
                                         ui.horizontal(|ui| {
-                                            ui.label("  Frame Count:");
+                                            ui.label(format!("  {}", tr!("info-frame-count")));
                                             ui.label(frames.to_string());
                                         });
                                     }
                                     ui.horizontal(|ui| {
-                                        ui.label("  Time Base:");
+                                        ui.label(format!("  {}", tr!("info-time-base")));
                                         ui.label(format!(
                                             "{}/{} ({:.6})",
                                             stream.time_base.numerator,
@@ -789,18 +9898,21 @@ impl eframe::App for VideoPlayer {
                                         ));
                                     });
                                     ui.horizontal(|ui| {
-                                        ui.label("  Disposition:");
+                                        ui.label(format!("  {}", tr!("info-disposition")));
                                         ui.label(format!("0x{:X}", stream.disposition));
                                     });
                                     if let Some(ref capabilities) = stream.codec_capabilities {
                                         ui.horizontal(|ui| {
-                                            ui.label("  Codec Capabilities:");
+                                            ui.label(format!(
+                                                "  {}",
+                                                tr!("info-codec-capabilities")
+                                            ));
                                             ui.label(format!("{:?}", capabilities));
                                         });
                                     }
                                     if let Some(ref profiles) = stream.codec_profiles {
                                         ui.horizontal(|ui| {
-                                            ui.label("  Codec Profiles:");
+                                            ui.label(format!("  {}", tr!("info-codec-profiles")));
                                             let profile_names: Vec<String> = profiles
                                                 .iter()
                                                 .map(|p| format!("{:?}", p))
@@ -810,19 +9922,22 @@ impl eframe::App for VideoPlayer {
                                     }
                                     if let Some(ref profile) = stream.profile {
                                         ui.horizontal(|ui| {
-                                            ui.label("  Profile:");
+                                            ui.label(format!("  {}", tr!("info-profile")));
                                             ui.label(format!("{:?}", profile));
                                         });
                                     }
                                     if !stream.metadata.is_empty() {
-                                        ui.collapsing("  Audio Stream Metadata", |ui| {
-                                            for (key, value) in &stream.metadata {
-                                                ui.horizontal(|ui| {
-                                                    ui.label(format!("    {}:", key));
-                                                    ui.label(value);
-                                                });
-                                            }
-                                        });
+                                        ui.collapsing(
+                                            format!("  {}", tr!("info-audio-stream-metadata")),
+                                            |ui| {
+                                                for (key, value) in &stream.metadata {
+                                                    ui.horizontal(|ui| {
+                                                        ui.label(format!("    {}:", key));
+                                                        ui.label(value);
+                                                    });
+                                                }
+                                            },
+                                        );
                                     }
                                     ui.add_space(10.0);
                                 }
@@ -830,27 +9945,31 @@ impl eframe::App for VideoPlayer {
                             }
 
                             if !media_info.subtitle_streams.is_empty() {
-                                ui.heading("Subtitle Streams");
+                                ui.heading(tr!("info-subtitle-streams"));
                                 ui.separator();
 
                                 for (i, stream) in media_info.subtitle_streams.iter().enumerate() {
-                                    ui.label(format!("Stream {} (Index: {})", i, stream.index));
+                                    ui.label(tr!(
+                                        "info-stream-heading",
+                                        number = i,
+                                        index = stream.index
+                                    ));
                                     ui.horizontal(|ui| {
-                                        ui.label("  Codec:");
+                                        ui.label(format!("  {}", tr!("info-codec")));
                                         ui.label(&stream.codec_name);
                                     });
                                     ui.horizontal(|ui| {
-                                        ui.label("  Codec ID:");
+                                        ui.label(format!("  {}", tr!("info-codec-id")));
                                         ui.label(&stream.codec_id);
                                     });
                                     if let Some(ref language) = stream.language {
                                         ui.horizontal(|ui| {
-                                            ui.label("  Language:");
+                                            ui.label(format!("  {}", tr!("info-language")));
                                             ui.label(language);
                                         });
                                     }
                                     ui.horizontal(|ui| {
-                                        ui.label("  Time Base:");
+                                        ui.label(format!("  {}", tr!("info-time-base")));
                                         ui.label(format!(
                                             "{}/{} ({:.6})",
                                             stream.time_base.numerator,
@@ -859,18 +9978,21 @@ impl eframe::App for VideoPlayer {
                                         ));
                                     });
                                     ui.horizontal(|ui| {
-                                        ui.label("  Disposition:");
+                                        ui.label(format!("  {}", tr!("info-disposition")));
                                         ui.label(format!("0x{:X}", stream.disposition));
                                     });
                                     if !stream.metadata.is_empty() {
-                                        ui.collapsing("  Subtitle Stream Metadata", |ui| {
-                                            for (key, value) in &stream.metadata {
-                                                ui.horizontal(|ui| {
-                                                    ui.label(format!("    {}:", key));
-                                                    ui.label(value);
-                                                });
-                                            }
-                                        });
+                                        ui.collapsing(
+                                            format!("  {}", tr!("info-subtitle-stream-metadata")),
+                                            |ui| {
+                                                for (key, value) in &stream.metadata {
+                                                    ui.horizontal(|ui| {
+                                                        ui.label(format!("    {}:", key));
+                                                        ui.label(value);
+                                                    });
+                                                }
+                                            },
+                                        );
                                     }
                                     ui.add_space(10.0);
                                 }
@@ -878,25 +10000,29 @@ impl eframe::App for VideoPlayer {
                             }
 
                             if !media_info.other_streams.is_empty() {
-                                ui.heading("Other Streams");
+                                ui.heading(tr!("info-other-streams"));
                                 ui.separator();
 
                                 for (i, stream) in media_info.other_streams.iter().enumerate() {
-                                    ui.label(format!("Stream {} (Index: {})", i, stream.index));
+                                    ui.label(tr!(
+                                        "info-stream-heading",
+                                        number = i,
+                                        index = stream.index
+                                    ));
                                     ui.horizontal(|ui| {
-                                        ui.label("  Type:");
+                                        ui.label(format!("  {}", tr!("info-type")));
                                         ui.label(&stream.stream_type);
                                     });
                                     ui.horizontal(|ui| {
-                                        ui.label("  Codec:");
+                                        ui.label(format!("  {}", tr!("info-codec")));
                                         ui.label(&stream.codec_name);
                                     });
                                     ui.horizontal(|ui| {
-                                        ui.label("  Codec ID:");
+                                        ui.label(format!("  {}", tr!("info-codec-id")));
                                         ui.label(&stream.codec_id);
                                     });
                                     ui.horizontal(|ui| {
-                                        ui.label("  Time Base:");
+                                        ui.label(format!("  {}", tr!("info-time-base")));
                                         ui.label(format!(
                                             "{}/{} ({:.6})",
                                             stream.time_base.numerator,
@@ -905,18 +10031,21 @@ impl eframe::App for VideoPlayer {
                                         ));
                                     });
                                     ui.horizontal(|ui| {
-                                        ui.label("  Disposition:");
+                                        ui.label(format!("  {}", tr!("info-disposition")));
                                         ui.label(format!("0x{:X}", stream.disposition));
                                     });
                                     if !stream.metadata.is_empty() {
-                                        ui.collapsing("  Other Stream Metadata", |ui| {
-                                            for (key, value) in &stream.metadata {
-                                                ui.horizontal(|ui| {
-                                                    ui.label(format!("    {}:", key));
-                                                    ui.label(value);
-                                                });
-                                            }
-                                        });
+                                        ui.collapsing(
+                                            format!("  {}", tr!("info-other-stream-metadata")),
+                                            |ui| {
+                                                for (key, value) in &stream.metadata {
+                                                    ui.horizontal(|ui| {
+                                                        ui.label(format!("    {}:", key));
+                                                        ui.label(value);
+                                                    });
+                                                }
+                                            },
+                                        );
                                     }
                                     ui.add_space(10.0);
                                 }
@@ -924,31 +10053,35 @@ impl eframe::App for VideoPlayer {
                             }
 
                             if !media_info.chapters.is_empty() {
-                                ui.heading("Chapters");
+                                ui.heading(tr!("info-chapters"));
                                 ui.separator();
 
                                 for chapter in media_info.chapters.iter() {
-                                    ui.label(format!(
-                                        "Chapter {}: {}",
-                                        chapter.index, chapter.title
+                                    ui.label(tr!(
+                                        "info-chapter-heading",
+                                        index = chapter.index,
+                                        title = chapter.title
                                     ));
                                     ui.horizontal(|ui| {
-                                        ui.label("  Start:");
+                                        ui.label(format!("  {}", tr!("info-start")));
                                         ui.label(Self::format_duration(chapter.start_time_ms));
                                     });
                                     ui.horizontal(|ui| {
-                                        ui.label("  End:");
+                                        ui.label(format!("  {}", tr!("info-end")));
                                         ui.label(Self::format_duration(chapter.end_time_ms));
                                     });
                                     if !chapter.metadata.is_empty() {
-                                        ui.collapsing("  Chapter Metadata", |ui| {
-                                            for (key, value) in &chapter.metadata {
-                                                ui.horizontal(|ui| {
-                                                    ui.label(format!("    {}:", key));
-                                                    ui.label(value);
-                                                });
-                                            }
-                                        });
+                                        ui.collapsing(
+                                            format!("  {}", tr!("info-chapter-metadata")),
+                                            |ui| {
+                                                for (key, value) in &chapter.metadata {
+                                                    ui.horizontal(|ui| {
+                                                        ui.label(format!("    {}:", key));
+                                                        ui.label(value);
+                                                    });
+                                                }
+                                            },
+                                        );
                                     }
                                     ui.add_space(5.0);
                                 }
@@ -956,7 +10089,7 @@ impl eframe::App for VideoPlayer {
                             }
 
                             if !media_info.metadata.is_empty() {
-                                ui.heading("Global Metadata");
+                                ui.heading(tr!("info-global-metadata"));
                                 ui.separator();
 
                                 for (key, value) in &media_info.metadata {
@@ -971,80 +10104,176 @@ impl eframe::App for VideoPlayer {
                     } else {
                         ui.vertical_centered(|ui| {
                             ui.add_space(50.0);
-                            ui.label("No media information available");
+                            ui.label(tr!("info-unavailable"));
                         });
                     }
 
                     ui.add_space(15.0);
-                    if ui.button("Close").clicked() {
+                    if ui.button(tr!("button-close")).clicked() {
                         self.show_media_info = false;
                     }
                 });
         }
 
+        if start_bitrate_measurement {
+            if let Some(filename) = &self.current_filename {
+                self.bitrate_measurement = Some(BitrateMeasurement::start(filename.clone()));
+            }
+        }
+
+        self.show_video_window(ctx);
+        self.show_goto_dialog(ctx);
+        self.show_analysis_window(ctx);
+        self.show_integrity_window(ctx);
+        self.show_cutlist_window(ctx);
+        self.show_annotations_window(ctx);
+        self.show_scene_detection_window(ctx);
+        self.show_subtitle_ocr_window(ctx);
+        self.show_sync_analysis_window(ctx);
+        self.show_presets_window(ctx);
+        self.show_storage_window(ctx);
+        self.show_diagnostics_window(ctx);
+        self.show_subtitle_style_dialog(ctx);
+        self.show_url_dialog(ctx);
+        self.show_raw_stream_dialog(ctx);
+        self.show_subtitle_search_dialog(ctx);
+        self.show_export_dialog(ctx);
+        self.show_transcode_dialog(ctx);
+        self.show_remux_dialog(ctx);
+        self.show_skip_segment_dialog(ctx);
+        self.show_error_dialog(ctx);
+
         if self.video.is_some() && !self.paused {
             ctx.request_repaint();
+        } else if self.audio.is_some() && !self.paused {
+            ctx.request_repaint_after(Duration::from_millis(100));
         }
 
-        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) && self.is_fullscreen {
-            self.is_fullscreen = false;
+        self.handle_key_bindings(ctx);
+        self.handle_events();
+        self.update_properties(ctx);
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        if let Some(recording) = self.recording.take() {
+            // Give the muxer a moment to write the trailer so the file stays playable
+            recording.job.cancel();
+            let _ = recording.job.receiver.recv_timeout(Duration::from_secs(5));
         }
+        self.shaders.destroy();
+        // Presets only last for the file, so they are not saved as the settings
+        if let Some(restore) = self.preset_restore.take() {
+            restore.apply_to_settings(&mut self.settings);
+        }
+        self.store_audio_profile();
+        self.settings.save();
+        self.session_snapshots.finish();
+    }
+}
 
-        if ctx.input(|i| i.key_pressed(egui::Key::Space)) {
-            self.paused = !self.paused;
-            if let Some(audio) = &self.audio {
-                if self.paused {
-                    audio.pause();
-                } else {
-                    audio.play();
-                }
-            }
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = env::args().collect();
+
+    if let Some(flag) = args
+        .get(1)
+        .filter(|arg| *arg == "--register-file-types" || *arg == "--unregister-file-types")
+    {
+        let mut settings = Settings::load();
+        let register = flag == "--register-file-types";
+
+        if register {
+            file_types::register(&settings.media_extensions)?;
+            println!(
+                "Registered Avio for {} file types",
+                settings.media_extensions.len()
+            );
+        } else {
+            file_types::unregister(&settings.media_extensions)?;
+            println!("Removed Avio file associations");
         }
 
-        if ctx.input(|i| i.key_pressed(egui::Key::ArrowLeft)) && self.video.is_some() {
-            if let Some(video) = &mut self.video {
-                let target_ms = (video.get_current_timestamp_ms() - 5000).max(0);
-                if let Err(e) = video.seek(target_ms) {
-                    eprintln!("Seek error: {}", e);
-                }
-                if let Some(audio) = &self.audio {
-                    audio.seek(target_ms);
-                }
-            }
+        settings.register_file_types = register;
+        settings.save();
+        return Ok(());
+    }
+
+    if args.get(1).is_some_and(|arg| arg == "--info") {
+        return info_cli::run(&args[2..]);
+    }
+    if args.get(1).is_some_and(|arg| arg == "--check") {
+        return integrity::run_cli(&args[2..]);
+    }
+
+    let (end_of_playlist, args) = match args.get(1..3) {
+        Some([flag, value]) if flag == "--at-end" => {
+            let action = playlist::EndOfPlaylist::from_arg(value).ok_or_else(|| {
+                format!(
+                    "Invalid --at-end action: {} (expected stay, close, quit or suspend)",
+                    value
+                )
+            })?;
+            (Some(action), &args[3..])
         }
+        _ => (None, &args[1..]),
+    };
 
-        if ctx.input(|i| i.key_pressed(egui::Key::ArrowRight)) && self.video.is_some() {
-            if let Some(video) = &mut self.video {
-                let target_ms =
-                    (video.get_current_timestamp_ms() + 5000).min(video.get_duration_ms());
-                if let Err(e) = video.seek(target_ms) {
-                    eprintln!("Seek error: {}", e);
-                }
-                if let Some(audio) = &self.audio {
-                    audio.seek(target_ms);
-                }
-            }
+    let (hint, files) = raw_stream::parse_args(args)?;
+    if let Some(hint) = hint {
+        for file in &files {
+            raw_stream::set_hint(file, Some(hint.clone()));
         }
     }
-}
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args: Vec<String> = env::args().collect();
+    let (files, wall) = match files.split_first() {
+        Some((flag, paths)) if flag == "--concat" => {
+            let timeline = concat::timeline(paths)?;
+            let first = timeline.parts[0].path.clone();
+            concat::register(timeline);
+            (vec![first], None)
+        }
+        Some((flag, paths)) if flag == "--wall" => (Vec::new(), Some(paths.to_vec())),
+        _ => (files, None),
+    };
 
-    let player = if args.len() == 2 {
-        VideoPlayer::new(Some(&args[1]))?
-    } else {
-        VideoPlayer::new(None)?
+    let mut player = VideoPlayer::new(files)?;
+    player.end_of_playlist = end_of_playlist;
+    if let Some(paths) = wall {
+        player.open_video_wall(&paths);
+    }
+
+    let window = &player.settings.window;
+    let mut viewport = egui::ViewportBuilder::default()
+        .with_inner_size(window.size.unwrap_or([1280.0, 720.0]))
+        .with_maximized(window.maximized)
+        .with_fullscreen(window.fullscreen)
+        .with_title("Avio Player");
+    let position = match window.fullscreen_display {
+        Some(display) if window.fullscreen => Some(display),
+        _ => window.position,
     };
+    if let Some(position) = position {
+        viewport = viewport.with_position(position);
+    }
 
     let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_inner_size([1280.0, 720.0])
-            .with_title("Avio Player"),
+        viewport,
         ..Default::default()
     };
 
-    eframe::run_native("Avio Player", options, Box::new(|_cc| Ok(Box::new(player))))?;
+    eframe::run_native(
+        "Avio Player",
+        options,
+        Box::new(move |cc| {
+            player.init_gl(cc.gl.clone());
+            player.init_now_playing(cc);
+            theme::apply(
+                &cc.egui_ctx,
+                player.settings.theme,
+                player.settings.accent_color,
+            );
+            Ok(Box::new(player))
+        }),
+    )?;
 
     Ok(())
 }
@@ -0,0 +1,492 @@
+use crate::security;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+const EBML_HEADER: u32 = 0x1A45_DFA3;
+const SEGMENT: u32 = 0x1853_8067;
+const SEEK_HEAD: u32 = 0x114D_9B74;
+const SEEK: u32 = 0x4DBB;
+const SEEK_ID: u32 = 0x53AB;
+const SEEK_POSITION: u32 = 0x53AC;
+const INFO: u32 = 0x1549_A966;
+const SEGMENT_UID: u32 = 0x73A4;
+const TIMESTAMP_SCALE: u32 = 0x2A_D7B1;
+const DURATION: u32 = 0x4489;
+const CLUSTER: u32 = 0x1F43_B675;
+const CHAPTERS: u32 = 0x1043_A770;
+const EDITION_ENTRY: u32 = 0x45B9;
+const EDITION_FLAG_DEFAULT: u32 = 0x45DB;
+const EDITION_FLAG_ORDERED: u32 = 0x45DD;
+const CHAPTER_ATOM: u32 = 0xB6;
+const CHAPTER_TIME_START: u32 = 0x91;
+const CHAPTER_TIME_END: u32 = 0x92;
+const CHAPTER_FLAG_HIDDEN: u32 = 0x98;
+const CHAPTER_FLAG_ENABLED: u32 = 0x4598;
+const CHAPTER_SEGMENT_UID: u32 = 0x6E67;
+const CHAPTER_DISPLAY: u32 = 0x80;
+const CHAP_STRING: u32 = 0x85;
+
+/// Linked segments are looked for next to the file with these extensions.
+const SEGMENT_EXTENSIONS: [&str; 3] = ["mkv", "mka", "mks"];
+
+/// A stretch of one file that plays as part of an ordered edition.
+#[derive(Debug, Clone)]
+pub struct TimelinePart {
+    pub path: String,
+    pub start_ms: i64,
+    pub end_ms: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct TimelineChapter {
+    pub title: String,
+    pub start_ms: i64,
+    pub end_ms: i64,
+}
+
+/// The edit described by a Matroska ordered edition: chapters that pick ranges of this
+/// file or of linked segments (e.g. a shared opening in a separate file), played in order.
+/// FFmpeg plays the file as stored, so the player stitches the parts together itself.
+#[derive(Debug, Clone)]
+pub struct OrderedTimeline {
+    pub parts: Vec<TimelinePart>,
+    pub chapters: Vec<TimelineChapter>,
+}
+
+impl OrderedTimeline {
+    /// Returns `None` for files without an ordered edition, including non-Matroska files.
+    pub fn load(path: &str) -> Option<Self> {
+        if !has_segment_extension(Path::new(path)) {
+            return None;
+        }
+
+        let segment = match read_segment(Path::new(path)) {
+            Ok(segment) => segment,
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => return None,
+            Err(e) => {
+                eprintln!("Error reading Matroska chapters: {}", e);
+                return None;
+            }
+        };
+
+        let mut ordered = segment.editions.iter().filter(|edition| edition.ordered);
+        let edition = ordered
+            .clone()
+            .find(|edition| edition.default)
+            .or_else(|| ordered.next())?;
+
+        let mut linked: Vec<(String, Segment)> = Vec::new();
+        let mut parts: Vec<TimelinePart> = Vec::new();
+        let mut chapters = Vec::new();
+        let mut position_ms = 0;
+
+        for chapter in edition.chapters.iter().filter(|chapter| chapter.enabled) {
+            let (part_path, part_segment) = match &chapter.segment_uid {
+                Some(uid) if Some(uid) != segment.uid.as_ref() => {
+                    if !linked.iter().any(|(_, s)| s.uid.as_ref() == Some(uid)) {
+                        match find_linked_segment(path, uid) {
+                            Some(found) => linked.push(found),
+                            None => {
+                                eprintln!(
+                                    "Linked segment {} not found, skipping chapter",
+                                    hex(uid)
+                                );
+                                continue;
+                            }
+                        }
+                    }
+                    let (part_path, part_segment) = linked
+                        .iter()
+                        .find(|(_, s)| s.uid.as_ref() == Some(uid))
+                        .expect("linked segment was just added");
+                    (part_path.clone(), part_segment)
+                }
+                _ => (path.to_string(), &segment),
+            };
+
+            let start_ms = (chapter.start_ns / 1_000_000) as i64;
+            let end_ms = match chapter.end_ns {
+                Some(end_ns) => (end_ns / 1_000_000) as i64,
+                None => match part_segment.duration_ms {
+                    Some(duration_ms) => duration_ms,
+                    None => continue,
+                },
+            };
+            if end_ms <= start_ms {
+                continue;
+            }
+
+            if !chapter.hidden {
+                chapters.push(TimelineChapter {
+                    title: chapter
+                        .title
+                        .clone()
+                        .unwrap_or_else(|| format!("Chapter {}", chapters.len() + 1)),
+                    start_ms: position_ms,
+                    end_ms: position_ms + end_ms - start_ms,
+                });
+            }
+            position_ms += end_ms - start_ms;
+
+            // Back-to-back chapters of the same file play without a seek in between
+            match parts.last_mut() {
+                Some(last) if last.path == part_path && last.end_ms == start_ms => {
+                    last.end_ms = end_ms;
+                }
+                _ => parts.push(TimelinePart {
+                    path: part_path,
+                    start_ms,
+                    end_ms,
+                }),
+            }
+        }
+
+        if parts.is_empty() {
+            return None;
+        }
+
+        println!(
+            "Ordered chapters: {} parts from {} files",
+            parts.len(),
+            linked.len() + 1
+        );
+        Some(Self { parts, chapters })
+    }
+
+    pub fn duration_ms(&self) -> i64 {
+        self.parts
+            .iter()
+            .map(|part| part.end_ms - part.start_ms)
+            .sum()
+    }
+
+    /// Position of the part on the timeline.
+    pub fn part_start_ms(&self, index: usize) -> i64 {
+        self.parts[..index]
+            .iter()
+            .map(|part| part.end_ms - part.start_ms)
+            .sum()
+    }
+
+    /// The part playing at a timeline position and the matching position in its file.
+    pub fn locate(&self, position_ms: i64) -> (usize, i64) {
+        let mut part_start_ms = 0;
+        for (index, part) in self.parts.iter().enumerate() {
+            let length_ms = part.end_ms - part.start_ms;
+            if position_ms < part_start_ms + length_ms || index == self.parts.len() - 1 {
+                let offset_ms = (position_ms - part_start_ms).clamp(0, length_ms);
+                return (index, part.start_ms + offset_ms);
+            }
+            part_start_ms += length_ms;
+        }
+        (0, 0)
+    }
+
+    /// Timeline position for a position in the file of the given part.
+    pub fn to_timeline_ms(&self, index: usize, file_ms: i64) -> i64 {
+        let part = &self.parts[index];
+        self.part_start_ms(index) + (file_ms - part.start_ms).clamp(0, part.end_ms - part.start_ms)
+    }
+}
+
+#[derive(Default)]
+struct Segment {
+    uid: Option<Vec<u8>>,
+    duration_ms: Option<i64>,
+    editions: Vec<Edition>,
+}
+
+struct Edition {
+    ordered: bool,
+    default: bool,
+    chapters: Vec<Chapter>,
+}
+
+struct Chapter {
+    start_ns: u64,
+    end_ns: Option<u64>,
+    enabled: bool,
+    hidden: bool,
+    segment_uid: Option<Vec<u8>>,
+    title: Option<String>,
+}
+
+fn find_linked_segment(path: &str, uid: &[u8]) -> Option<(String, Segment)> {
+    if !security::autoload_sidecar_files() {
+        return None;
+    }
+    let dir = match Path::new(path).parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let entries = std::fs::read_dir(dir).ok()?;
+
+    entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|candidate| has_segment_extension(candidate) && candidate != Path::new(path))
+        .find_map(|candidate| {
+            let segment = read_segment(&candidate).ok()?;
+            (segment.uid.as_deref() == Some(uid))
+                .then(|| (candidate.to_string_lossy().into_owned(), segment))
+        })
+}
+
+fn has_segment_extension(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| SEGMENT_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Reads the segment info and chapters, which sit in front of the clusters or are
+/// pointed to by the seek head. The media data itself is never read.
+fn read_segment(path: &Path) -> io::Result<Segment> {
+    let mut reader = BufReader::new(File::open(path)?);
+
+    let (id, size) = read_element_header(&mut reader)?;
+    if id != EBML_HEADER {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Not a Matroska file",
+        ));
+    }
+    reader.seek(SeekFrom::Current(size.unwrap_or(0) as i64))?;
+
+    let (id, segment_size) = read_element_header(&mut reader)?;
+    if id != SEGMENT {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "No Matroska segment",
+        ));
+    }
+    let segment_start = reader.stream_position()?;
+    let segment_end = segment_size.map(|size| segment_start + size);
+
+    let mut segment = Segment::default();
+    let mut info_found = false;
+    let mut chapters_found = false;
+    let mut seek_positions = Vec::new();
+
+    loop {
+        let position = reader.stream_position()?;
+        if segment_end.is_some_and(|end| position >= end) {
+            break;
+        }
+        let (id, size) = match read_element_header(&mut reader) {
+            Ok(header) => header,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        };
+
+        match (id, size) {
+            (CLUSTER, _) | (_, None) => break,
+            (INFO | CHAPTERS | SEEK_HEAD, Some(size)) => {
+                let data = read_bytes(&mut reader, size)?;
+                match id {
+                    INFO => {
+                        parse_info(&data, &mut segment);
+                        info_found = true;
+                    }
+                    CHAPTERS => {
+                        segment.editions = parse_chapters(&data);
+                        chapters_found = true;
+                    }
+                    _ => seek_positions.extend(parse_seek_head(&data)),
+                }
+            }
+            (_, Some(size)) => {
+                reader.seek(SeekFrom::Current(size as i64))?;
+            }
+        }
+
+        if info_found && chapters_found {
+            return Ok(segment);
+        }
+    }
+
+    // Files written in one pass keep the chapters after the clusters
+    for (id, offset) in seek_positions {
+        if (id == INFO && !info_found) || (id == CHAPTERS && !chapters_found) {
+            reader.seek(SeekFrom::Start(segment_start + offset))?;
+            if let (found_id, Some(size)) = read_element_header(&mut reader)? {
+                if found_id != id {
+                    continue;
+                }
+                let data = read_bytes(&mut reader, size)?;
+                if id == INFO {
+                    parse_info(&data, &mut segment);
+                } else {
+                    segment.editions = parse_chapters(&data);
+                }
+            }
+        }
+    }
+
+    Ok(segment)
+}
+
+fn parse_info(data: &[u8], segment: &mut Segment) {
+    let mut timestamp_scale = 1_000_000;
+    let mut duration = None;
+
+    for (id, value) in Elements(data) {
+        match id {
+            SEGMENT_UID => segment.uid = Some(value.to_vec()),
+            TIMESTAMP_SCALE => timestamp_scale = read_uint(value),
+            DURATION => duration = read_float(value),
+            _ => {}
+        }
+    }
+
+    segment.duration_ms = duration.map(|duration| (duration * timestamp_scale as f64 / 1e6) as i64);
+}
+
+fn parse_chapters(data: &[u8]) -> Vec<Edition> {
+    Elements(data)
+        .filter(|(id, _)| *id == EDITION_ENTRY)
+        .map(|(_, edition)| {
+            let mut parsed = Edition {
+                ordered: false,
+                default: false,
+                chapters: Vec::new(),
+            };
+            for (id, value) in Elements(edition) {
+                match id {
+                    EDITION_FLAG_ORDERED => parsed.ordered = read_uint(value) != 0,
+                    EDITION_FLAG_DEFAULT => parsed.default = read_uint(value) != 0,
+                    CHAPTER_ATOM => parsed.chapters.push(parse_chapter(value)),
+                    _ => {}
+                }
+            }
+            parsed
+        })
+        .collect()
+}
+
+/// Nested chapters only subdivide their parent, so just the top level is used.
+fn parse_chapter(data: &[u8]) -> Chapter {
+    let mut chapter = Chapter {
+        start_ns: 0,
+        end_ns: None,
+        enabled: true,
+        hidden: false,
+        segment_uid: None,
+        title: None,
+    };
+
+    for (id, value) in Elements(data) {
+        match id {
+            CHAPTER_TIME_START => chapter.start_ns = read_uint(value),
+            CHAPTER_TIME_END => chapter.end_ns = Some(read_uint(value)),
+            CHAPTER_FLAG_ENABLED => chapter.enabled = read_uint(value) != 0,
+            CHAPTER_FLAG_HIDDEN => chapter.hidden = read_uint(value) != 0,
+            CHAPTER_SEGMENT_UID => chapter.segment_uid = Some(value.to_vec()),
+            CHAPTER_DISPLAY if chapter.title.is_none() => {
+                chapter.title = Elements(value)
+                    .find(|(id, _)| *id == CHAP_STRING)
+                    .map(|(_, title)| String::from_utf8_lossy(title).into_owned());
+            }
+            _ => {}
+        }
+    }
+
+    chapter
+}
+
+/// Element IDs with their offsets relative to the segment data.
+fn parse_seek_head(data: &[u8]) -> Vec<(u32, u64)> {
+    Elements(data)
+        .filter(|(id, _)| *id == SEEK)
+        .filter_map(|(_, seek)| {
+            let mut seek_id = None;
+            let mut position = None;
+            for (id, value) in Elements(seek) {
+                match id {
+                    SEEK_ID => seek_id = Some(read_uint(value) as u32),
+                    SEEK_POSITION => position = Some(read_uint(value)),
+                    _ => {}
+                }
+            }
+            Some((seek_id?, position?))
+        })
+        .collect()
+}
+
+/// Iterates over the child elements of an element that was read into memory.
+struct Elements<'a>(&'a [u8]);
+
+impl<'a> Iterator for Elements<'a> {
+    type Item = (u32, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut data = self.0;
+        let (id, size) = read_element_header(&mut data).ok()?;
+        let size = (size? as usize).min(data.len());
+        let (value, rest) = data.split_at(size);
+        self.0 = rest;
+        Some((id, value))
+    }
+}
+
+/// Reads an element ID (which keeps its length marker) and a data size,
+/// where `None` is the reserved "unknown size".
+fn read_element_header<R: Read>(reader: &mut R) -> io::Result<(u32, Option<u64>)> {
+    let (id, _) = read_vint(reader, 4)?;
+    let (size, length) = read_vint(reader, 8)?;
+
+    let value_bits = 7 * length as u32;
+    let size = size & ((1u64 << value_bits) - 1);
+    let unknown = size == (1u64 << value_bits) - 1;
+
+    Ok((id as u32, if unknown { None } else { Some(size) }))
+}
+
+/// Returns the raw variable-length integer including its marker bit, and its length.
+fn read_vint<R: Read>(reader: &mut R, max_length: usize) -> io::Result<(u64, usize)> {
+    let mut first = [0u8];
+    reader.read_exact(&mut first)?;
+
+    let length = first[0].leading_zeros() as usize + 1;
+    if length > max_length {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Invalid EBML variable-length integer",
+        ));
+    }
+
+    let mut value = first[0] as u64;
+    let mut rest = [0u8; 7];
+    reader.read_exact(&mut rest[..length - 1])?;
+    for byte in &rest[..length - 1] {
+        value = (value << 8) | *byte as u64;
+    }
+
+    Ok((value, length))
+}
+
+fn read_bytes<R: Read>(reader: &mut R, size: u64) -> io::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    reader.take(size).read_to_end(&mut data)?;
+    if data.len() as u64 != size {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(data)
+}
+
+fn read_uint(data: &[u8]) -> u64 {
+    data.iter()
+        .take(8)
+        .fold(0, |value, byte| (value << 8) | *byte as u64)
+}
+
+fn read_float(data: &[u8]) -> Option<f64> {
+    match data.len() {
+        4 => Some(f32::from_be_bytes(data.try_into().ok()?) as f64),
+        8 => Some(f64::from_be_bytes(data.try_into().ok()?)),
+        _ => None,
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
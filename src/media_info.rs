@@ -1,8 +1,12 @@
-use ffmpeg::{codec, format, media, Rational};
+use crate::error::MediaError;
+use crate::http;
+use ffmpeg::{codec, format, media, Rational, Rescale};
 use ffmpeg_next as ffmpeg;
 use ffmpeg_next::codec::{Capabilities, Profile};
+use ffmpeg_next::ffi::AV_TIME_BASE;
 use ffmpeg_next::{color, ChannelLayout};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 #[derive(Debug, Clone)]
 pub struct MediaInfo {
@@ -36,6 +40,8 @@ pub struct VideoStreamInfo {
     pub aspect_ratio: Option<RationalValue>,
     pub time_base: RationalValue,
     pub disposition: u32,
+    /// Spherical metadata marks the video as an equirectangular 360° projection.
+    pub equirectangular: bool,
     pub metadata: HashMap<String, String>,
 }
 
@@ -90,6 +96,13 @@ pub struct ChapterInfo {
     pub metadata: HashMap<String, String>,
 }
 
+/// Average bitrates in bits per second, measured from the packet sizes.
+#[derive(Debug, Clone, Default)]
+pub struct MeasuredBitrates {
+    pub overall: Option<usize>,
+    pub streams: HashMap<usize, usize>,
+}
+
 #[derive(Debug, Clone)]
 pub struct RationalValue {
     pub numerator: i32,
@@ -97,6 +110,89 @@ pub struct RationalValue {
     pub value: f64,
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct TrackTags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub track: Option<String>,
+    pub year: Option<String>,
+}
+
+impl TrackTags {
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.artist.is_none()
+            && self.album.is_none()
+            && self.track.is_none()
+            && self.year.is_none()
+    }
+}
+
+impl VideoStreamInfo {
+    pub fn is_attached_pic(&self) -> bool {
+        self.disposition & format::stream::Disposition::ATTACHED_PIC.bits() as u32 != 0
+    }
+}
+
+impl MediaInfo {
+    /// Live streams report no duration.
+    pub fn is_live(&self) -> bool {
+        self.duration_ms <= 0
+    }
+
+    /// True when the file has audio and its only video streams are embedded cover art.
+    pub fn is_audio_only(&self) -> bool {
+        !self.audio_streams.is_empty()
+            && self
+                .video_streams
+                .iter()
+                .all(|stream| stream.is_attached_pic())
+    }
+
+    /// True for a picture such as a PNG, JPEG, WebP or AVIF file: a single frame and no sound.
+    pub fn is_still_image(&self) -> bool {
+        let image_format = self.format_name == "image2" || self.format_name.ends_with("_pipe");
+        match self.video_streams.as_slice() {
+            [stream] => self.audio_streams.is_empty() && (image_format || stream.frames == Some(1)),
+            _ => false,
+        }
+    }
+
+    /// Collects the common ID3/Vorbis/MP4 tags. Ogg files keep their Vorbis comments
+    /// on the audio stream rather than the container, so both are searched.
+    pub fn tags(&self) -> TrackTags {
+        let lookup = |keys: &[&str]| -> Option<String> {
+            let sources = std::iter::once(&self.metadata)
+                .chain(self.audio_streams.iter().map(|stream| &stream.metadata));
+
+            for metadata in sources {
+                for key in keys {
+                    let value = metadata
+                        .iter()
+                        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                        .map(|(_, v)| v.trim());
+
+                    if let Some(value) = value.filter(|v| !v.is_empty()) {
+                        return Some(value.to_string());
+                    }
+                }
+            }
+
+            None
+        };
+
+        TrackTags {
+            title: lookup(&["title"]),
+            artist: lookup(&["artist", "album_artist", "performer"]),
+            album: lookup(&["album"]),
+            track: lookup(&["track", "tracknumber"]),
+            // Dates are often full ISO dates ("2004-05-01")
+            year: lookup(&["date", "year"]).map(|date| date.chars().take(4).collect()),
+        }
+    }
+}
+
 impl From<Rational> for RationalValue {
     fn from(rational: Rational) -> Self {
         RationalValue {
@@ -111,26 +207,17 @@ impl From<Rational> for RationalValue {
     }
 }
 
-pub fn get_media_info(filename: &str) -> Option<MediaInfo> {
-    match ffmpeg::init() {
-        Ok(_) => {}
-        Err(_) => {
-            return None;
-        }
-    };
-
-    let input = match format::input(&filename) {
-        Ok(i) => i,
-        Err(_) => {
-            return None;
-        }
-    };
+pub fn get_media_info(filename: &str) -> Result<MediaInfo, MediaError> {
+    ffmpeg::init().map_err(MediaError::Open)?;
+    let input = http::open_input(&filename).map_err(MediaError::Open)?;
 
     let mut info = MediaInfo {
         format_name: input.format().name().to_string(),
         format_description: input.format().description().to_string(),
         duration_ms: input.duration(),
-        bit_rate: Some(0),
+        bit_rate: usize::try_from(input.bit_rate())
+            .ok()
+            .filter(|&bit_rate| bit_rate > 0),
         video_streams: Vec::new(),
         audio_streams: Vec::new(),
         subtitle_streams: Vec::new(),
@@ -186,11 +273,6 @@ pub fn get_media_info(filename: &str) -> Option<MediaInfo> {
 
         match stream.parameters().medium() {
             media::Type::Video => {
-                let video = input.stream(index).unwrap();
-                let video_params = video.parameters();
-                let context = ffmpeg::codec::Context::from_parameters(video_params).unwrap();
-                let decoder = context.decoder().video().unwrap();
-
                 let mut vs_info = VideoStreamInfo {
                     index,
                     codec_name,
@@ -208,15 +290,22 @@ pub fn get_media_info(filename: &str) -> Option<MediaInfo> {
                     aspect_ratio: None,
                     time_base,
                     disposition: disposition.bits() as u32,
+                    equirectangular: is_equirectangular(&stream),
                     metadata,
                 };
 
-                vs_info.width = Some(decoder.width());
-                vs_info.height = Some(decoder.height());
-                vs_info.pixel_format = Some(decoder.format());
-                vs_info.color_space = Some(decoder.color_space());
-                vs_info.bit_rate = Some(decoder.bit_rate());
-                vs_info.aspect_ratio = Some(RationalValue::from(decoder.aspect_ratio()));
+                // Streams without a usable decoder are still listed, just without the details
+                if let Some(decoder) = codec::Context::from_parameters(stream.parameters())
+                    .and_then(|context| context.decoder().video())
+                    .ok()
+                {
+                    vs_info.width = Some(decoder.width());
+                    vs_info.height = Some(decoder.height());
+                    vs_info.pixel_format = Some(decoder.format());
+                    vs_info.color_space = Some(decoder.color_space());
+                    vs_info.bit_rate = Some(decoder.bit_rate()).filter(|&bit_rate| bit_rate > 0);
+                    vs_info.aspect_ratio = Some(RationalValue::from(decoder.aspect_ratio()));
+                }
 
                 info.video_streams.push(vs_info);
             }
@@ -240,17 +329,17 @@ pub fn get_media_info(filename: &str) -> Option<MediaInfo> {
                     metadata,
                 };
 
-                let audio = input.stream(index).unwrap();
-                let audio_params = audio.parameters();
-                let context = ffmpeg::codec::Context::from_parameters(audio_params).unwrap();
-                let decoder = context.decoder().audio().unwrap();
-
-                as_info.channels = Some(decoder.channels());
-                as_info.sample_rate = Some(decoder.rate());
-                as_info.sample_format = Some(decoder.format());
-                as_info.channel_layout = Some(decoder.channel_layout());
-                as_info.profile = Some(decoder.profile());
-                as_info.bit_rate = Some(decoder.bit_rate());
+                if let Some(decoder) = codec::Context::from_parameters(stream.parameters())
+                    .and_then(|context| context.decoder().audio())
+                    .ok()
+                {
+                    as_info.channels = Some(decoder.channels());
+                    as_info.sample_rate = Some(decoder.rate());
+                    as_info.sample_format = Some(decoder.format());
+                    as_info.channel_layout = Some(decoder.channel_layout());
+                    as_info.profile = Some(decoder.profile());
+                    as_info.bit_rate = Some(decoder.bit_rate()).filter(|&bit_rate| bit_rate > 0);
+                }
 
                 info.audio_streams.push(as_info);
             }
@@ -285,11 +374,106 @@ pub fn get_media_info(filename: &str) -> Option<MediaInfo> {
         }
     }
 
-    Some(info)
+    for (index, chapter) in input.chapters().enumerate() {
+        let metadata: HashMap<String, String> = chapter
+            .metadata()
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let to_ms = |timestamp: i64| timestamp.rescale(chapter.time_base(), Rational(1, 1000));
+
+        info.chapters.push(ChapterInfo {
+            index,
+            title: metadata
+                .get("title")
+                .cloned()
+                .unwrap_or_else(|| format!("Chapter {}", index + 1)),
+            start_time_ms: to_ms(chapter.start()),
+            end_time_ms: to_ms(chapter.end()),
+            metadata,
+        });
+    }
+
+    Ok(info)
+}
+
+fn is_equirectangular(stream: &format::stream::Stream) -> bool {
+    // AVSphericalMapping starts with its AVSphericalProjection
+    const EQUIRECTANGULAR: u32 = 0;
+    const EQUIRECTANGULAR_TILE: u32 = 2;
+
+    stream.side_data().any(|side_data| {
+        side_data.kind() == codec::packet::side_data::Type::DataSpherical
+            && side_data
+                .data()
+                .get(..4)
+                .map(|bytes| u32::from_ne_bytes(bytes.try_into().unwrap()))
+                .is_some_and(|projection| {
+                    projection == EQUIRECTANGULAR || projection == EQUIRECTANGULAR_TILE
+                })
+    })
+}
+
+/// Reads every packet of the file, so this takes about as long as copying it.
+/// Each stream's bitrate is taken over the time span its own packets cover.
+pub fn measure_bitrates(
+    filename: &str,
+    cancel: &AtomicBool,
+) -> Result<MeasuredBitrates, MediaError> {
+    let mut input = http::open_input(&filename).map_err(MediaError::Open)?;
+    let time_bases: Vec<Rational> = input.streams().map(|stream| stream.time_base()).collect();
+
+    let mut bytes = vec![0u64; time_bases.len()];
+    let mut spans: Vec<Option<(i64, i64)>> = vec![None; time_bases.len()];
+
+    for (stream, packet) in input.packets() {
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+
+        let index = stream.index();
+        bytes[index] += packet.size() as u64;
+
+        if let Some(start) = packet.pts().or(packet.dts()) {
+            let end = start + packet.duration().max(0);
+            spans[index] = Some(match spans[index] {
+                Some((first, last)) => (first.min(start), last.max(end)),
+                None => (start, end),
+            });
+        }
+    }
+
+    let bit_rate = |bytes: u64, duration_ms: i64| {
+        (duration_ms > 0).then(|| (bytes * 8 * 1000 / duration_ms as u64) as usize)
+    };
+
+    let mut measured = MeasuredBitrates::default();
+    let mut longest_ms = 0;
+    for (index, span) in spans.into_iter().enumerate() {
+        if let Some((first, last)) = span {
+            let duration_ms = (last - first).rescale(time_bases[index], Rational(1, 1000));
+            longest_ms = longest_ms.max(duration_ms);
+            if let Some(bit_rate) = bit_rate(bytes[index], duration_ms) {
+                measured.streams.insert(index, bit_rate);
+            }
+        }
+    }
+
+    // The container overhead counts towards the overall bitrate
+    let total_bytes = std::fs::metadata(filename)
+        .map(|metadata| metadata.len())
+        .unwrap_or_else(|_| bytes.iter().sum());
+    let duration_ms = match input.duration() {
+        duration if duration > 0 => duration.rescale(Rational(1, AV_TIME_BASE), Rational(1, 1000)),
+        _ => longest_ms,
+    };
+    measured.overall = bit_rate(total_bytes, duration_ms);
+
+    Ok(measured)
 }
 
 fn estimate_frame_count(filename: &str, stream_index: usize) -> Option<u64> {
-    let mut input = match format::input(&filename) {
+    let mut input = match http::open_input(&filename) {
         Ok(i) => i,
         Err(_) => {
             return None;
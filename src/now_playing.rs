@@ -0,0 +1,126 @@
+use crate::actions::Action;
+use crate::commands::{CommandSender, PlayerCommand};
+use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+use souvlaki::{
+    MediaControlEvent, MediaControls, MediaMetadata, MediaPlayback, MediaPosition, PlatformConfig,
+    SeekDirection,
+};
+use std::ffi::c_void;
+use std::time::Duration;
+
+const SEEK_STEP_MS: i64 = 5000;
+
+/// Publishes the current track to the system media controls (MPNowPlayingInfoCenter on
+/// macOS, MPRIS on Linux, SystemMediaTransportControls on Windows) and turns their
+/// commands, including hardware media keys pressed while the window is unfocused, into actions
+/// sent through `notify`.
+pub struct NowPlaying {
+    controls: Option<MediaControls>,
+}
+
+impl NowPlaying {
+    /// Must be called on the main thread, which runs the event loop the commands arrive on.
+    pub fn new(cc: &eframe::CreationContext, notify: CommandSender) -> Self {
+        let config = PlatformConfig {
+            display_name: "Avio Player",
+            dbus_name: "avio",
+            hwnd: window_handle(cc),
+        };
+        let controls = MediaControls::new(config).and_then(|mut controls| {
+            controls.attach(move |event| {
+                if let Some(action) = to_action(event) {
+                    notify.send(PlayerCommand::Action(action));
+                }
+            })?;
+            Ok(controls)
+        });
+
+        let controls = match controls {
+            Ok(controls) => Some(controls),
+            Err(e) => {
+                eprintln!("Error setting up system media controls: {:?}", e);
+                None
+            }
+        };
+
+        Self { controls }
+    }
+
+    pub fn set_track(
+        &mut self,
+        title: &str,
+        artist: Option<&str>,
+        album: Option<&str>,
+        duration_ms: i64,
+    ) {
+        let controls = match &mut self.controls {
+            Some(controls) => controls,
+            None => return,
+        };
+
+        let metadata = MediaMetadata {
+            title: Some(title),
+            artist,
+            album,
+            duration: Some(Duration::from_millis(duration_ms.max(0) as u64)),
+            ..Default::default()
+        };
+
+        if let Err(e) = controls.set_metadata(metadata) {
+            eprintln!("Error updating system media controls: {:?}", e);
+        }
+    }
+
+    /// The system extrapolates the position while playing, so this is only
+    /// needed when playback starts, stops or jumps.
+    pub fn set_playback(&mut self, paused: bool, position_ms: i64) {
+        let controls = match &mut self.controls {
+            Some(controls) => controls,
+            None => return,
+        };
+
+        let progress = Some(MediaPosition(Duration::from_millis(
+            position_ms.max(0) as u64
+        )));
+        let playback = if paused {
+            MediaPlayback::Paused { progress }
+        } else {
+            MediaPlayback::Playing { progress }
+        };
+
+        if let Err(e) = controls.set_playback(playback) {
+            eprintln!("Error updating system media controls: {:?}", e);
+        }
+    }
+}
+
+/// Windows attaches the controls to the window; the other platforms don't need it.
+fn window_handle(cc: &eframe::CreationContext) -> Option<*mut c_void> {
+    match cc.window_handle().ok()?.as_raw() {
+        RawWindowHandle::Win32(handle) => Some(handle.hwnd.get() as *mut c_void),
+        _ => None,
+    }
+}
+
+fn to_action(event: MediaControlEvent) -> Option<Action> {
+    let signed = |direction: SeekDirection, ms: i64| match direction {
+        SeekDirection::Forward => ms,
+        SeekDirection::Backward => -ms,
+    };
+
+    Some(match event {
+        MediaControlEvent::Play => Action::Play,
+        MediaControlEvent::Pause | MediaControlEvent::Stop => Action::Pause,
+        MediaControlEvent::Toggle => Action::TogglePause,
+        MediaControlEvent::Next => Action::PlaylistNext,
+        MediaControlEvent::Previous => Action::PlaylistPrevious,
+        MediaControlEvent::Seek(direction) => Action::SeekRelative(signed(direction, SEEK_STEP_MS)),
+        MediaControlEvent::SeekBy(direction, amount) => {
+            Action::SeekRelative(signed(direction, amount.as_millis() as i64))
+        }
+        MediaControlEvent::SetPosition(MediaPosition(position)) => {
+            Action::SeekTo(position.as_millis() as i64)
+        }
+        _ => return None,
+    })
+}
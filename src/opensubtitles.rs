@@ -0,0 +1,203 @@
+use crate::http;
+use crate::playlist::is_url;
+use crate::storage::CacheKind;
+use serde::Deserialize;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+const API_URL: &str = "https://api.opensubtitles.com/api/v1";
+const USER_AGENT: &str = concat!("Avio v", env!("CARGO_PKG_VERSION"));
+const HASH_CHUNK_SIZE: u64 = 64 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct SubtitleResult {
+    pub file_id: u64,
+    pub file_name: String,
+    pub language: String,
+    pub release: String,
+    pub download_count: u64,
+    pub hash_match: bool,
+}
+
+#[derive(Deserialize)]
+struct SearchResponse {
+    data: Vec<SearchItem>,
+}
+
+#[derive(Deserialize)]
+struct SearchItem {
+    attributes: SearchAttributes,
+}
+
+#[derive(Deserialize)]
+struct SearchAttributes {
+    language: Option<String>,
+    release: Option<String>,
+    download_count: Option<u64>,
+    moviehash_match: Option<bool>,
+    files: Vec<SearchFile>,
+}
+
+#[derive(Deserialize)]
+struct SearchFile {
+    file_id: u64,
+    file_name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DownloadResponse {
+    link: String,
+}
+
+/// OpenSubtitles hash: the file size plus the 64-bit little-endian words
+/// of the first and last 64 KiB, with wrapping addition.
+pub fn compute_hash(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let size = file.metadata()?.len();
+
+    if size < HASH_CHUNK_SIZE * 2 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "File is too small to hash",
+        ));
+    }
+
+    let mut hash = size;
+    let mut buffer = vec![0u8; HASH_CHUNK_SIZE as usize];
+
+    for offset in [0, size - HASH_CHUNK_SIZE] {
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(&mut buffer)?;
+
+        for word in buffer.chunks_exact(8) {
+            hash = hash.wrapping_add(u64::from_le_bytes(word.try_into().unwrap()));
+        }
+    }
+
+    Ok(format!("{:016x}", hash))
+}
+
+pub fn search(
+    api_key: &str,
+    hash: Option<&str>,
+    query: &str,
+    language: &str,
+) -> Result<Vec<SubtitleResult>, Box<dyn std::error::Error>> {
+    let url = format!("{}/subtitles", API_URL);
+    let mut request = http::agent(&url)?
+        .get(&url)
+        .set("Api-Key", api_key)
+        .set("User-Agent", USER_AGENT)
+        .query("languages", language);
+
+    request = match hash {
+        Some(hash) => request.query("moviehash", hash),
+        None => request.query("query", query),
+    };
+
+    let response: SearchResponse = request.call()?.into_json()?;
+
+    let mut results: Vec<SubtitleResult> = response
+        .data
+        .into_iter()
+        .flat_map(|item| {
+            let attributes = item.attributes;
+            attributes
+                .files
+                .into_iter()
+                .map(move |file| SubtitleResult {
+                    file_id: file.file_id,
+                    file_name: file.file_name.unwrap_or_default(),
+                    language: attributes.language.clone().unwrap_or_default(),
+                    release: attributes.release.clone().unwrap_or_default(),
+                    download_count: attributes.download_count.unwrap_or(0),
+                    hash_match: attributes.moviehash_match.unwrap_or(false),
+                })
+        })
+        .collect();
+
+    results.sort_by(|a, b| {
+        b.hash_match
+            .cmp(&a.hash_match)
+            .then(b.download_count.cmp(&a.download_count))
+    });
+
+    Ok(results)
+}
+
+pub fn download(api_key: &str, file_id: u64) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let url = format!("{}/download", API_URL);
+    let response: DownloadResponse = http::agent(&url)?
+        .post(&url)
+        .set("Api-Key", api_key)
+        .set("User-Agent", USER_AGENT)
+        .send_json(serde_json::json!({ "file_id": file_id }))?
+        .into_json()?;
+
+    let mut content = Vec::new();
+    http::agent(&response.link)?
+        .get(&response.link)
+        .set("User-Agent", USER_AGENT)
+        .call()?
+        .into_reader()
+        .read_to_end(&mut content)?;
+
+    Ok(content)
+}
+
+/// Saves a downloaded subtitle next to the video as `<name>.<language>.<format>`,
+/// falling back to the cache directory for streams and read-only folders. The format is
+/// taken from the name the subtitle was uploaded under, or failing that, from its content.
+pub fn save_subtitle(
+    video_path: &str,
+    language: &str,
+    subtitle_name: &str,
+    content: &[u8],
+) -> io::Result<PathBuf> {
+    let is_stream = is_url(video_path);
+    let video_path = Path::new(video_path);
+    let stem = video_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "subtitle".to_string());
+    let file_name = format!(
+        "{}.{}.{}",
+        stem,
+        language,
+        subtitle_extension(subtitle_name, content)
+    );
+
+    if !is_stream {
+        if let Some(parent) = video_path.parent() {
+            let path = parent.join(&file_name);
+            if fs::write(&path, content).is_ok() {
+                return Ok(path);
+            }
+        }
+    }
+
+    let path = CacheKind::Subtitles.dir()?.join(file_name);
+    fs::write(&path, content)?;
+    Ok(path)
+}
+
+fn subtitle_extension(subtitle_name: &str, content: &[u8]) -> String {
+    let extension = Path::new(subtitle_name)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .filter(|ext| ["srt", "vtt", "ass", "ssa", "sub"].contains(&ext.as_str()));
+    if let Some(extension) = extension {
+        return extension;
+    }
+
+    let start = String::from_utf8_lossy(&content[..content.len().min(64)]);
+    let start = start.trim_start_matches('\u{feff}').trim_start();
+    if start.starts_with("WEBVTT") {
+        "vtt".to_string()
+    } else if start.starts_with("[Script Info]") {
+        "ass".to_string()
+    } else {
+        "srt".to_string()
+    }
+}
@@ -0,0 +1,494 @@
+use crate::i18n::tr;
+use crate::{dvd, security};
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fs;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+
+const PLAYLIST_EXTENSIONS: [&str; 3] = ["m3u", "m3u8", "pls"];
+
+/// What happens once the last playlist entry has played.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum EndOfPlaylist {
+    /// Stay paused at the end of the last entry.
+    #[default]
+    Stay,
+    /// Close the file and go back to the start screen.
+    Close,
+    Quit,
+    /// Put the computer to sleep.
+    Suspend,
+}
+
+impl EndOfPlaylist {
+    pub const ALL: [Self; 4] = [Self::Stay, Self::Close, Self::Quit, Self::Suspend];
+
+    pub fn label(&self) -> String {
+        match self {
+            Self::Stay => tr!("end-of-playlist-stay"),
+            Self::Close => tr!("end-of-playlist-close"),
+            Self::Quit => tr!("end-of-playlist-quit"),
+            Self::Suspend => tr!("end-of-playlist-suspend"),
+        }
+    }
+
+    /// Parses the value of `--at-end`.
+    pub fn from_arg(arg: &str) -> Option<Self> {
+        match arg {
+            "stay" => Some(Self::Stay),
+            "close" => Some(Self::Close),
+            "quit" => Some(Self::Quit),
+            "suspend" => Some(Self::Suspend),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Playlist {
+    entries: Vec<String>,
+    order: Vec<usize>,
+    position: Option<usize>,
+    shuffle: bool,
+}
+
+impl Playlist {
+    pub fn from_entries(entries: Vec<String>, shuffle: bool) -> Self {
+        let mut order: Vec<usize> = (0..entries.len()).collect();
+        if shuffle {
+            order.shuffle(&mut rand::thread_rng());
+        }
+
+        let position = if entries.is_empty() { None } else { Some(0) };
+
+        Self {
+            entries,
+            order,
+            position,
+            shuffle,
+        }
+    }
+
+    pub fn current(&self) -> Option<&str> {
+        self.position
+            .map(|position| self.entries[self.order[position]].as_str())
+    }
+
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    /// Moves to `entry`, if it is in the playlist.
+    pub fn select(&mut self, entry: &str) -> bool {
+        let index = self.entries.iter().position(|e| e == entry);
+        match index.and_then(|index| self.order.iter().position(|&i| i == index)) {
+            Some(position) => {
+                self.position = Some(position);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn set_shuffle(&mut self, shuffle: bool) {
+        let current = self.position.map(|position| self.order[position]);
+        self.shuffle = shuffle;
+
+        if shuffle {
+            self.order.shuffle(&mut rand::thread_rng());
+
+            // Keep the playing entry first so the rest of the cycle follows it
+            if let Some(current) = current {
+                if let Some(position) = self.order.iter().position(|&i| i == current) {
+                    self.order.swap(0, position);
+                }
+                self.position = Some(0);
+            }
+        } else {
+            self.order = (0..self.entries.len()).collect();
+            self.position = current;
+        }
+    }
+
+    pub fn next(&mut self) -> Option<&str> {
+        let next = match self.position {
+            Some(position) => position + 1,
+            None => 0,
+        };
+
+        if next < self.order.len() {
+            self.position = Some(next);
+        } else if self.shuffle && self.order.len() > 1 {
+            self.reshuffle();
+            self.position = Some(0);
+        } else {
+            return None;
+        }
+
+        self.current()
+    }
+
+    /// Moves to the next entry that `open` succeeds on. Every entry gets one try, as shuffle
+    /// would otherwise start new cycles forever when none of the files open.
+    pub fn next_playable(&mut self, mut open: impl FnMut(&str) -> bool) -> bool {
+        for _ in 0..self.entries.len() {
+            let Some(entry) = self.next().map(str::to_string) else {
+                break;
+            };
+            if open(&entry) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Whether the playing entry is the last one, or the last of this shuffle cycle.
+    pub fn is_last(&self) -> bool {
+        self.position
+            .is_none_or(|position| position + 1 >= self.order.len())
+    }
+
+    /// The entry `next` moves to, unless that starts a new shuffle cycle.
+    pub fn peek_next(&self) -> Option<&str> {
+        let next = self.position.map_or(0, |position| position + 1);
+        self.order
+            .get(next)
+            .map(|&index| self.entries[index].as_str())
+    }
+
+    pub fn previous(&mut self) -> Option<&str> {
+        match self.position {
+            Some(position) if position > 0 => {
+                self.position = Some(position - 1);
+                self.current()
+            }
+            _ => None,
+        }
+    }
+
+    /// Starts a new shuffle cycle without repeating the entry that just finished.
+    fn reshuffle(&mut self) {
+        let last = self.order.last().copied();
+        self.order.shuffle(&mut rand::thread_rng());
+
+        if self.order.first().copied() == last {
+            let end = self.order.len() - 1;
+            self.order.swap(0, end);
+        }
+    }
+
+    pub fn export_m3u8(&self, path: &Path) -> std::io::Result<()> {
+        let mut file = fs::File::create(path)?;
+        writeln!(file, "#EXTM3U")?;
+
+        for entry in &self.entries {
+            writeln!(file, "{}", entry)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub fn is_playlist_file(path: &str) -> bool {
+    if is_url(path) {
+        return false;
+    }
+
+    Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| PLAYLIST_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Replaces playlist files and folders in `paths` with the entries they contain.
+/// HLS manifests share the `.m3u8` extension but are streams, so they are kept as-is.
+pub fn expand_entries(paths: Vec<String>, extensions: &[String]) -> Vec<String> {
+    let mut entries = Vec::new();
+
+    for path in paths {
+        if !is_url(&path) && Path::new(&path).is_dir() && !dvd::is_dvd(&path) {
+            entries.extend(scan_folder(Path::new(&path), extensions));
+            continue;
+        }
+
+        if !is_playlist_file(&path) {
+            entries.push(path);
+            continue;
+        }
+
+        match load_playlist_file(&path) {
+            Ok(Some(playlist_entries)) => entries.extend(playlist_entries),
+            Ok(None) => entries.push(path),
+            Err(e) => eprintln!("Error reading playlist {}: {}", path, e),
+        }
+    }
+
+    entries
+}
+
+pub fn scan_folder(dir: &Path, extensions: &[String]) -> Vec<String> {
+    let mut files = Vec::new();
+    collect_media_files(dir, extensions, &mut files);
+
+    files.sort_by(|a, b| natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()));
+    files
+        .into_iter()
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect()
+}
+
+fn collect_media_files(dir: &Path, extensions: &[String], files: &mut Vec<PathBuf>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("Error reading folder {}: {}", dir.display(), e);
+            return;
+        }
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+
+        if hidden {
+            continue;
+        }
+
+        if path.is_dir() && dvd::is_dvd(&path.to_string_lossy()) {
+            files.push(path);
+        } else if path.is_dir() {
+            collect_media_files(&path, extensions, files);
+        } else if has_extension(&path, extensions) {
+            files.push(path);
+        }
+    }
+}
+
+fn has_extension(path: &Path, extensions: &[String]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+        .unwrap_or(false)
+}
+
+/// Compares strings so that embedded numbers sort by value ("ep2" before "ep10").
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+
+    loop {
+        match (a_chars.peek().copied(), b_chars.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let x_number = take_number(&mut a_chars);
+                let y_number = take_number(&mut b_chars);
+                let x_trimmed = x_number.trim_start_matches('0');
+                let y_trimmed = y_number.trim_start_matches('0');
+
+                let ordering = x_trimmed
+                    .len()
+                    .cmp(&y_trimmed.len())
+                    .then_with(|| x_trimmed.cmp(y_trimmed))
+                    .then_with(|| x_number.len().cmp(&y_number.len()));
+
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                let ordering = x.to_lowercase().cmp(y.to_lowercase());
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                a_chars.next();
+                b_chars.next();
+            }
+        }
+    }
+}
+
+fn take_number(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut number = String::new();
+    while let Some(c) = chars.peek().copied().filter(|c| c.is_ascii_digit()) {
+        number.push(c);
+        chars.next();
+    }
+    number
+}
+
+fn load_playlist_file(path: &str) -> Result<Option<Vec<String>>, Box<dyn std::error::Error>> {
+    let bytes = fs::read(path)?;
+    let content = String::from_utf8_lossy(&bytes);
+    let content = content.trim_start_matches('\u{feff}');
+
+    let base_dir = Path::new(path).parent().unwrap_or(Path::new(""));
+
+    let is_pls = content
+        .lines()
+        .next()
+        .map(|line| line.trim().eq_ignore_ascii_case("[playlist]"))
+        .unwrap_or(false);
+
+    let entries = if is_pls {
+        parse_pls(content)
+    } else if content.contains("#EXT-X-") {
+        return Ok(None);
+    } else {
+        parse_m3u(content)
+    };
+
+    // With restricted protocols, a downloaded playlist can't send the player elsewhere either
+    let restricted = security::restrict_protocols();
+    Ok(Some(
+        entries
+            .iter()
+            .map(|entry| resolve_entry(entry, base_dir))
+            .filter(|entry| {
+                let allowed = !restricted || is_inside(entry, base_dir);
+                if !allowed {
+                    eprintln!(
+                        "Skipping {} in playlist {}: outside its folder",
+                        entry, path
+                    );
+                }
+                allowed
+            })
+            .collect(),
+    ))
+}
+
+fn parse_m3u(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.to_string())
+        .collect()
+}
+
+fn parse_pls(content: &str) -> Vec<String> {
+    let mut files: Vec<(u32, String)> = content
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.trim().split_once('=')?;
+            let number = key.trim().strip_prefix("File")?.parse().ok()?;
+            Some((number, value.trim().to_string()))
+        })
+        .collect();
+
+    files.sort_by_key(|(number, _)| *number);
+    files.into_iter().map(|(_, file)| file).collect()
+}
+
+fn resolve_entry(entry: &str, base_dir: &Path) -> String {
+    if let Some(path) = entry.strip_prefix("file://") {
+        let path = percent_decode(path);
+        // file:///C:/video.mp4 on Windows
+        if path.as_bytes().get(2) == Some(&b':') {
+            return path[1..].to_string();
+        }
+        return path;
+    }
+
+    if is_url(entry) {
+        return entry.to_string();
+    }
+
+    let path = Path::new(entry);
+    if path.is_absolute() {
+        entry.to_string()
+    } else {
+        base_dir.join(path).to_string_lossy().into_owned()
+    }
+}
+
+/// Whether a resolved entry is a file in `base_dir` or below it, rather than a URL or a file
+/// elsewhere.
+fn is_inside(entry: &str, base_dir: &Path) -> bool {
+    if is_url(entry) {
+        return false;
+    }
+    let path = Path::new(entry);
+    path.is_absolute() == base_dir.is_absolute()
+        && path.starts_with(base_dir)
+        && !path.components().any(|c| c == Component::ParentDir)
+}
+
+pub fn is_url(path: &str) -> bool {
+    match path.split_once("://") {
+        Some((scheme, _)) => {
+            !scheme.is_empty()
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.')
+        }
+        None => false,
+    }
+}
+
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(byte) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("{}.mkv", i)).collect()
+    }
+
+    #[test]
+    fn shuffle_gives_up_when_no_entry_opens() {
+        let mut playlist = Playlist::from_entries(entries(5), true);
+        let mut tries = 0;
+        assert!(!playlist.next_playable(|_| {
+            tries += 1;
+            false
+        }));
+        assert_eq!(tries, 5);
+    }
+
+    #[test]
+    fn restricted_entries_stay_in_the_folder() {
+        let base_dir = Path::new("/downloads/show");
+        let inside = |entry: &str| is_inside(&resolve_entry(entry, base_dir), base_dir);
+        assert!(inside("1.mkv"));
+        assert!(inside("extras/2.mkv"));
+        assert!(inside("file:///downloads/show/3.mkv"));
+        assert!(!inside("https://example.com/video.mp4"));
+        assert!(!inside("/home/me/.ssh/id_rsa"));
+        assert!(!inside("../other/4.mkv"));
+        assert!(!inside("file:///etc/passwd"));
+    }
+
+    #[test]
+    fn skips_entries_that_fail() {
+        let mut playlist = Playlist::from_entries(entries(3), false);
+        assert!(playlist.next_playable(|entry| entry == "2.mkv"));
+        assert_eq!(playlist.current(), Some("2.mkv"));
+        assert!(!playlist.next_playable(|_| true));
+    }
+}
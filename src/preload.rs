@@ -0,0 +1,131 @@
+use crate::audio::{Audio, Downmix, PreparedAudio};
+use crate::filmstrip;
+use crate::media_info::{self, MediaInfo};
+use crate::video::Video;
+use eframe::egui;
+use std::sync::mpsc;
+use std::thread;
+
+const THUMBNAIL_HEIGHT: u32 = 90;
+
+enum Loaded {
+    Probe {
+        info: Box<MediaInfo>,
+        thumbnail: Option<egui::ColorImage>,
+    },
+    Audio(PreparedAudio),
+}
+
+/// The next playlist entry, probed and with its first frame and audio decoded on a worker
+/// thread while the current one plays, so that it can be shown ahead and start at once.
+pub struct Preload {
+    filename: String,
+    downmix: Downmix,
+    receiver: Option<mpsc::Receiver<Loaded>>,
+    info: Option<MediaInfo>,
+    thumbnail: Option<egui::TextureHandle>,
+    audio: Option<PreparedAudio>,
+}
+
+impl Preload {
+    pub fn start(filename: &str, downmix: Downmix, memory_mb: u32) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let path = filename.to_string();
+
+        thread::spawn(move || {
+            let info = match media_info::get_media_info(&path) {
+                Ok(info) => info,
+                Err(e) => {
+                    eprintln!("Error preloading {}: {}", path, e);
+                    return;
+                }
+            };
+            let has_audio = !info.audio_streams.is_empty();
+            let thumbnail = if info.video_streams.is_empty() {
+                None
+            } else {
+                first_frame(&path)
+            };
+            let info = Box::new(info);
+            if sender.send(Loaded::Probe { info, thumbnail }).is_err() || !has_audio {
+                return;
+            }
+
+            match Audio::prepare(&path, downmix, memory_mb) {
+                Ok(audio) => {
+                    let _ = sender.send(Loaded::Audio(audio));
+                }
+                Err(e) => eprintln!("Error preloading the audio of {}: {}", path, e),
+            }
+        });
+
+        Self {
+            filename: filename.to_string(),
+            downmix,
+            receiver: Some(receiver),
+            info: None,
+            thumbnail: None,
+            audio: None,
+        }
+    }
+
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    /// Takes in what finished loading. Returns whether loading is still going on.
+    pub fn update(&mut self, ctx: &egui::Context) -> bool {
+        let Some(receiver) = &self.receiver else {
+            return false;
+        };
+        loop {
+            match receiver.try_recv() {
+                Ok(Loaded::Probe { info, thumbnail }) => {
+                    self.info = Some(*info);
+                    self.thumbnail = thumbnail.map(|image| {
+                        ctx.load_texture("preload_thumbnail", image, egui::TextureOptions::LINEAR)
+                    });
+                }
+                Ok(Loaded::Audio(audio)) => self.audio = Some(audio),
+                Err(mpsc::TryRecvError::Empty) => return true,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.receiver = None;
+                    return false;
+                }
+            }
+        }
+    }
+
+    pub fn info(&self) -> Option<&MediaInfo> {
+        self.info.as_ref()
+    }
+
+    pub fn thumbnail(&self) -> Option<&egui::TextureHandle> {
+        self.thumbnail.as_ref()
+    }
+
+    /// Hands over the probe and the audio if they are ready. The audio is left out when it
+    /// was decoded for another channel layout.
+    pub fn into_loaded(self, downmix: Downmix) -> (Option<MediaInfo>, Option<PreparedAudio>) {
+        let audio = self.audio.filter(|_| self.downmix == downmix);
+        (self.info, audio)
+    }
+}
+
+fn first_frame(filename: &str) -> Option<egui::ColorImage> {
+    let mut video = match Video::new(filename) {
+        Ok(video) => video,
+        Err(e) => {
+            eprintln!("Error preloading the first frame of {}: {}", filename, e);
+            return None;
+        }
+    };
+    // Decoders may need a few packets before the first frame comes out
+    for _ in 0..30 {
+        match video.next_frame()? {
+            Ok(frame) => return filmstrip::thumbnail(frame, THUMBNAIL_HEIGHT),
+            Err(_) => continue,
+        }
+    }
+    None
+}
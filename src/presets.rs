@@ -0,0 +1,132 @@
+use crate::audio::Downmix;
+use crate::settings::Settings;
+use crate::shaders::{DisplayMode, Sampler};
+use crate::video::ScalingAlgorithm;
+use serde::{Deserialize, Serialize};
+
+/// A named bundle of playback settings. Only the values that are set are changed when it is
+/// applied; the rest stay as they are.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preset {
+    pub name: String,
+    /// File name patterns, `*` matching any run of characters and `?` one, that apply the
+    /// preset when a matching file is opened.
+    pub patterns: Vec<String>,
+    pub scaling: Option<ScalingAlgorithm>,
+    pub sampler: Option<Sampler>,
+    pub display_mode: Option<DisplayMode>,
+    pub denoise: Option<bool>,
+    pub deband: Option<bool>,
+    pub speed: Option<f64>,
+    pub audio_delay_ms: Option<i64>,
+    pub downmix: Option<Downmix>,
+    pub night_mode: Option<bool>,
+}
+
+impl Preset {
+    /// Sets every value that `other` sets.
+    pub fn overlay(&mut self, other: &Preset) {
+        self.scaling = other.scaling.or(self.scaling);
+        self.sampler = other.sampler.or(self.sampler);
+        self.display_mode = other.display_mode.or(self.display_mode);
+        self.denoise = other.denoise.or(self.denoise);
+        self.deband = other.deband.or(self.deband);
+        self.speed = other.speed.or(self.speed);
+        self.audio_delay_ms = other.audio_delay_ms.or(self.audio_delay_ms);
+        self.downmix = other.downmix.or(self.downmix);
+        self.night_mode = other.night_mode.or(self.night_mode);
+    }
+
+    /// Keeps only the values that `other` sets too.
+    pub fn masked(&self, other: &Preset) -> Preset {
+        Preset {
+            name: String::new(),
+            patterns: Vec::new(),
+            scaling: other.scaling.and(self.scaling),
+            sampler: other.sampler.and(self.sampler),
+            display_mode: other.display_mode.and(self.display_mode),
+            denoise: other.denoise.and(self.denoise),
+            deband: other.deband.and(self.deband),
+            speed: other.speed.and(self.speed),
+            audio_delay_ms: other.audio_delay_ms.and(self.audio_delay_ms),
+            downmix: other.downmix.and(self.downmix),
+            night_mode: other.night_mode.and(self.night_mode),
+        }
+    }
+
+    /// Writes the values kept in the settings; speed and audio delay belong to the player.
+    pub fn apply_to_settings(&self, settings: &mut Settings) {
+        settings.scaling = self.scaling.unwrap_or(settings.scaling);
+        settings.sampler = self.sampler.unwrap_or(settings.sampler);
+        settings.display_mode = self.display_mode.unwrap_or(settings.display_mode);
+        settings.denoise = self.denoise.unwrap_or(settings.denoise);
+        settings.deband = self.deband.unwrap_or(settings.deband);
+        settings.downmix = self.downmix.unwrap_or(settings.downmix);
+        settings.night_mode = self.night_mode.unwrap_or(settings.night_mode);
+    }
+
+    /// Whether the file name (not the folder) of `path` matches one of the patterns,
+    /// ignoring case.
+    pub fn matches(&self, path: &str) -> bool {
+        let name = path
+            .rsplit(['/', '\\'])
+            .next()
+            .unwrap_or(path)
+            .to_lowercase();
+        self.patterns
+            .iter()
+            .map(|pattern| pattern.trim().to_lowercase())
+            .filter(|pattern| !pattern.is_empty())
+            .any(|pattern| wildcard_match(&pattern, &name))
+    }
+}
+
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Where the last `*` was and how much of the text it has taken, to backtrack to
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// The first preset whose patterns match `path`.
+pub fn find_match<'a>(presets: &'a [Preset], path: &str) -> Option<&'a Preset> {
+    presets.iter().find(|preset| preset.matches(path))
+}
+
+/// Examples to start from; they match no files until patterns are added.
+pub fn default_presets() -> Vec<Preset> {
+    vec![
+        Preset {
+            name: "Anime".to_string(),
+            scaling: Some(ScalingAlgorithm::Lanczos),
+            deband: Some(true),
+            audio_delay_ms: Some(0),
+            ..Preset::default()
+        },
+        Preset {
+            name: "Screencast".to_string(),
+            sampler: Some(Sampler::Nearest),
+            speed: Some(1.5),
+            ..Preset::default()
+        },
+    ]
+}
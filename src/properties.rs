@@ -0,0 +1,96 @@
+use std::sync::mpsc;
+
+/// Stream indices of the open file's tracks.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Tracks {
+    pub video: Vec<usize>,
+    pub audio: Vec<usize>,
+    pub subtitles: Vec<usize>,
+}
+
+/// The state of the player that can be observed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Properties {
+    pub position_ms: i64,
+    pub duration_ms: i64,
+    pub paused: bool,
+    pub volume: f32,
+    pub muted: bool,
+    pub speed: f64,
+    pub tracks: Tracks,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyChange {
+    Position(i64),
+    Duration(i64),
+    Paused(bool),
+    Volume { volume: f32, muted: bool },
+    Speed(f64),
+    Tracks(Tracks),
+}
+
+/// Sends the properties that changed to everything observing them, so that overlays and
+/// other clients react to the player instead of polling it.
+#[derive(Default)]
+pub struct PropertyObservers {
+    current: Option<Properties>,
+    observers: Vec<mpsc::Sender<PropertyChange>>,
+}
+
+impl PropertyObservers {
+    /// Receives every change from now on, starting with the current values.
+    pub fn observe(&mut self) -> mpsc::Receiver<PropertyChange> {
+        let (sender, receiver) = mpsc::channel();
+        if let Some(current) = &self.current {
+            for change in changes(&Properties::default(), current, true) {
+                let _ = sender.send(change);
+            }
+        }
+        self.observers.push(sender);
+        receiver
+    }
+
+    /// Compares the properties with the last ones and sends what changed. The first call
+    /// only records them. Observers that dropped their receiver are forgotten.
+    pub fn update(&mut self, properties: Properties) {
+        if let Some(current) = &self.current {
+            let changes = changes(current, &properties, false);
+            if !changes.is_empty() {
+                self.observers.retain(|observer| {
+                    changes
+                        .iter()
+                        .all(|change| observer.send(change.clone()).is_ok())
+                });
+            }
+        }
+        self.current = Some(properties);
+    }
+}
+
+/// The changes from `old` to `new`, or with `all`, every property of `new`.
+fn changes(old: &Properties, new: &Properties, all: bool) -> Vec<PropertyChange> {
+    let mut changes = Vec::new();
+    if all || old.position_ms != new.position_ms {
+        changes.push(PropertyChange::Position(new.position_ms));
+    }
+    if all || old.duration_ms != new.duration_ms {
+        changes.push(PropertyChange::Duration(new.duration_ms));
+    }
+    if all || old.paused != new.paused {
+        changes.push(PropertyChange::Paused(new.paused));
+    }
+    if all || old.volume != new.volume || old.muted != new.muted {
+        changes.push(PropertyChange::Volume {
+            volume: new.volume,
+            muted: new.muted,
+        });
+    }
+    if all || old.speed != new.speed {
+        changes.push(PropertyChange::Speed(new.speed));
+    }
+    if all || old.tracks != new.tracks {
+        changes.push(PropertyChange::Tracks(new.tracks.clone()));
+    }
+    changes
+}
@@ -0,0 +1,130 @@
+extern crate ffmpeg_next as ffmpeg;
+
+use ffmpeg::{ffi, format, Dictionary, Format};
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::path::Path;
+use std::sync::{OnceLock, RwLock};
+
+pub const DEFAULT_FRAMERATE: f64 = 25.0;
+
+/// Demuxers for raw elementary streams, with the extensions they are picked for and
+/// whether they carry video.
+pub const FORMATS: [(&str, &[&str], bool); 9] = [
+    ("h264", &["h264", "264", "avc", "jsv"], true),
+    ("hevc", &["hevc", "h265", "265"], true),
+    ("vvc", &["vvc", "h266", "266"], true),
+    ("obu", &["obu"], true),
+    ("mpegvideo", &["m1v", "m2v", "mpv"], true),
+    ("m4v", &["cmp"], true),
+    ("aac", &["aac", "adts"], false),
+    ("ac3", &["ac3"], false),
+    ("eac3", &["ec3", "eac3"], false),
+];
+
+/// How to open a stream that has no container to describe it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormatHint {
+    /// Demuxer to use instead of probing.
+    pub format: Option<String>,
+    /// Frames per second of a raw video stream, which has no timestamps of its own.
+    pub framerate: Option<f64>,
+}
+
+fn hints() -> &'static RwLock<HashMap<String, FormatHint>> {
+    static HINTS: OnceLock<RwLock<HashMap<String, FormatHint>>> = OnceLock::new();
+    HINTS.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// Opens `filename` with `hint` from now on, or by probing with `None`.
+pub fn set_hint(filename: &str, hint: Option<FormatHint>) {
+    let mut hints = hints().write().unwrap();
+    match hint {
+        Some(hint) => hints.insert(filename.to_string(), hint),
+        None => hints.remove(filename),
+    };
+}
+
+pub fn hint_for(filename: &str) -> Option<FormatHint> {
+    hints().read().unwrap().get(filename).cloned()
+}
+
+/// The raw stream demuxer for the file's extension, if it is one.
+pub fn format_for(filename: &str) -> Option<&'static str> {
+    let extension = Path::new(filename).extension()?.to_str()?.to_lowercase();
+    FORMATS
+        .iter()
+        .find(|(_, extensions, _)| extensions.contains(&extension.as_str()))
+        .map(|(format, _, _)| *format)
+}
+
+pub fn is_video_format(format: &str) -> bool {
+    FORMATS
+        .iter()
+        .any(|(name, _, video)| *name == format && *video)
+}
+
+/// Opens the file with the demuxer and frame rate of the hint.
+pub fn open(
+    filename: &str,
+    hint: &FormatHint,
+    mut options: Dictionary,
+) -> Result<format::context::Input, ffmpeg::Error> {
+    if let Some(framerate) = hint.framerate {
+        // Private option of the raw video demuxers
+        options.set("framerate", &framerate.to_string());
+    }
+
+    match &hint.format {
+        Some(name) => open_as(filename, name, options),
+        None => format::input_with_dictionary(&filename, options),
+    }
+}
+
+/// Opens the file with the named demuxer instead of probing for one.
+pub fn open_as(
+    filename: &str,
+    name: &str,
+    options: Dictionary,
+) -> Result<format::context::Input, ffmpeg::Error> {
+    let name = CString::new(name).map_err(|_| ffmpeg::Error::DemuxerNotFound)?;
+    let demuxer = unsafe { ffi::av_find_input_format(name.as_ptr()) };
+    if demuxer.is_null() {
+        return Err(ffmpeg::Error::DemuxerNotFound);
+    }
+
+    let demuxer = unsafe { format::Input::wrap(demuxer as *mut _) };
+    format::open_with(&filename, &Format::Input(demuxer), options).map(|context| context.input())
+}
+
+/// Takes `--format <demuxer>` and `--framerate <fps>` off the front of the command line.
+/// Returns the hint they make up, if any, and the remaining arguments.
+pub fn parse_args(args: &[String]) -> Result<(Option<FormatHint>, Vec<String>), String> {
+    let mut hint = FormatHint {
+        format: None,
+        framerate: None,
+    };
+    let mut rest = args;
+
+    loop {
+        match rest {
+            [flag, format, tail @ ..] if flag == "--format" => {
+                hint.format = Some(format.clone());
+                rest = tail;
+            }
+            [flag, framerate, tail @ ..] if flag == "--framerate" => {
+                let framerate = framerate
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|framerate| *framerate > 0.0)
+                    .ok_or_else(|| format!("Invalid frame rate: {}", framerate))?;
+                hint.framerate = Some(framerate);
+                rest = tail;
+            }
+            _ => break,
+        }
+    }
+
+    let hint = (hint.format.is_some() || hint.framerate.is_some()).then_some(hint);
+    Ok((hint, rest.to_vec()))
+}
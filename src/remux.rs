@@ -0,0 +1,211 @@
+extern crate ffmpeg_next as ffmpeg;
+
+use crate::http;
+use directories::UserDirs;
+use ffmpeg::{codec, encoder, format, Rational, Rescale};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Container {
+    Mp4,
+    Mkv,
+}
+
+impl Container {
+    pub const ALL: [Container; 2] = [Container::Mp4, Container::Mkv];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Container::Mp4 => "MP4",
+            Container::Mkv => "MKV",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Container::Mp4 => "mp4",
+            Container::Mkv => "mkv",
+        }
+    }
+}
+
+pub struct RemuxSettings {
+    pub filename: String,
+    pub output: PathBuf,
+    /// Input streams to copy, in output order.
+    pub streams: Vec<usize>,
+    /// Recording a network stream: streams the container can't hold are left out, and
+    /// cancelling finishes the file instead of deleting it.
+    pub recording: bool,
+}
+
+/// `<videos>/<stream name>_recording.mkv`, with a counter added if that file exists.
+pub fn recording_path(url: &str) -> PathBuf {
+    let dir = UserDirs::new()
+        .and_then(|dirs| dirs.video_dir().map(Path::to_path_buf))
+        .unwrap_or_else(|| PathBuf::from("."));
+    let stem = Path::new(url.split(['?', '#']).next().unwrap_or(url))
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .filter(|stem| !stem.is_empty())
+        .unwrap_or_else(|| "stream".to_string());
+    let extension = Container::Mkv.extension();
+
+    let mut path = dir.join(format!("{}_recording.{}", stem, extension));
+    let mut counter = 2;
+    while path.exists() {
+        path = dir.join(format!("{}_recording_{}.{}", stem, counter, extension));
+        counter += 1;
+    }
+    path
+}
+
+pub enum RemuxEvent {
+    Progress(i64),
+    Finished(Result<(), String>),
+}
+
+/// Stream copy running on a worker thread. Dropping the job cancels it.
+pub struct RemuxJob {
+    pub receiver: mpsc::Receiver<RemuxEvent>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl RemuxJob {
+    pub fn start(settings: RemuxSettings) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancel_flag = cancel.clone();
+
+        thread::spawn(move || {
+            let result = remux(&settings, &sender, &cancel_flag);
+            if result.is_err() {
+                // Don't leave a truncated file behind
+                let _ = fs::remove_file(&settings.output);
+            }
+            let _ = sender.send(RemuxEvent::Finished(result.map_err(|e| e.to_string())));
+        });
+
+        Self { receiver, cancel }
+    }
+
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Drop for RemuxJob {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+fn remux(
+    settings: &RemuxSettings,
+    sender: &mpsc::Sender<RemuxEvent>,
+    cancel: &AtomicBool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut input = http::open_input(&settings.filename)?;
+    let mut output = format::output(&settings.output)?;
+
+    // Output index for every input stream that is copied
+    let mut mapping: Vec<Option<usize>> = vec![None; input.nb_streams() as usize];
+    let mut input_time_bases = vec![Rational(0, 1); input.nb_streams() as usize];
+
+    for &index in &settings.streams {
+        let stream = input
+            .stream(index)
+            .ok_or_else(|| format!("Stream {} was not found", index))?;
+        let parameters = stream.parameters();
+        let codec_id = parameters.id();
+
+        // Checked up front so that the error names the stream instead of failing in write_header
+        let supported = unsafe {
+            ffmpeg::ffi::avformat_query_codec(
+                output.format().as_ptr(),
+                codec_id.into(),
+                ffmpeg::ffi::FF_COMPLIANCE_NORMAL,
+            )
+        };
+        if supported == 0 {
+            if settings.recording {
+                continue;
+            }
+            return Err(format!(
+                "Stream {} ({:?}) can't be stored in this container",
+                index, codec_id
+            )
+            .into());
+        }
+
+        let mut output_stream = output.add_stream(encoder::find(codec::Id::None))?;
+        output_stream.set_parameters(parameters);
+        output_stream.set_metadata(stream.metadata().to_owned());
+        unsafe {
+            // Codec tags are container specific; let the muxer choose its own
+            (*output_stream.parameters().as_mut_ptr()).codec_tag = 0;
+            (*output_stream.as_mut_ptr()).disposition = (*stream.as_ptr()).disposition;
+        }
+
+        mapping[index] = Some(output_stream.index());
+        input_time_bases[index] = stream.time_base();
+    }
+
+    if mapping.iter().all(Option::is_none) {
+        return Err("No streams selected".into());
+    }
+
+    for chapter in input.chapters() {
+        output.add_chapter(
+            chapter.id(),
+            chapter.time_base(),
+            chapter.start(),
+            chapter.end(),
+            chapter.metadata().get("title").unwrap_or_default(),
+        )?;
+    }
+    output.set_metadata(input.metadata().to_owned());
+    output.write_header()?;
+
+    let output_time_bases: Vec<Rational> = output.streams().map(|s| s.time_base()).collect();
+    let mut last_progress = Instant::now();
+
+    for (stream, mut packet) in input.packets() {
+        if cancel.load(Ordering::Relaxed) {
+            if settings.recording {
+                break;
+            }
+            return Err("Remux cancelled".into());
+        }
+
+        let input_index = stream.index();
+        let output_index = match mapping.get(input_index).copied().flatten() {
+            Some(output_index) => output_index,
+            None => continue,
+        };
+
+        if last_progress.elapsed() > Duration::from_millis(100) {
+            last_progress = Instant::now();
+            if let Some(pts) = packet.pts() {
+                let position_ms = pts.rescale(input_time_bases[input_index], Rational(1, 1000));
+                let _ = sender.send(RemuxEvent::Progress(position_ms));
+            }
+        }
+
+        packet.rescale_ts(
+            input_time_bases[input_index],
+            output_time_bases[output_index],
+        );
+        packet.set_position(-1);
+        packet.set_stream(output_index);
+        packet.write_interleaved(&mut output)?;
+    }
+
+    output.write_trailer()?;
+    Ok(())
+}
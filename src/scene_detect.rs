@@ -0,0 +1,272 @@
+use crate::i18n::tr;
+use crate::media_info::ChapterInfo;
+use crate::video::Video;
+use eframe::egui;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+
+/// Frames are compared as a grid of average luma values of this size.
+const GRID_WIDTH: usize = 64;
+const GRID_HEIGHT: usize = 36;
+/// Cuts closer together than this are merged into the earlier scene.
+const MIN_SCENE_MS: i64 = 1000;
+const THUMBNAIL_HEIGHT: u32 = 72;
+
+pub const DEFAULT_THRESHOLD: f32 = 0.15;
+
+/// Average luma of each grid cell, in 0..=1.
+fn luma_grid(width: usize, height: usize, buffer: &[u8]) -> Vec<f32> {
+    let mut sums = vec![0.0f32; GRID_WIDTH * GRID_HEIGHT];
+    let mut counts = vec![0u32; GRID_WIDTH * GRID_HEIGHT];
+
+    // Every other pixel in both directions is plenty for block averages
+    for y in (0..height).step_by(2) {
+        let cell_row = y * GRID_HEIGHT / height * GRID_WIDTH;
+        for x in (0..width).step_by(2) {
+            let offset = (y * width + x) * 4;
+            let luma = 0.2126 * buffer[offset] as f32
+                + 0.7152 * buffer[offset + 1] as f32
+                + 0.0722 * buffer[offset + 2] as f32;
+            let cell = cell_row + x * GRID_WIDTH / width;
+            sums[cell] += luma;
+            counts[cell] += 1;
+        }
+    }
+
+    sums.iter()
+        .zip(&counts)
+        .map(|(sum, &count)| sum / count.max(1) as f32 / 255.0)
+        .collect()
+}
+
+/// Mean absolute difference between two grids, in 0..=1.
+fn difference(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(a, b)| (a - b).abs()).sum::<f32>() / a.len() as f32
+}
+
+fn thumbnail(width: usize, height: usize, buffer: &[u8]) -> Option<egui::ColorImage> {
+    let image = image::RgbaImage::from_raw(width as u32, height as u32, buffer.to_vec())?;
+    let thumbnail_width = (width as u32 * THUMBNAIL_HEIGHT / height.max(1) as u32).max(1);
+    let thumbnail = image::imageops::thumbnail(&image, thumbnail_width, THUMBNAIL_HEIGHT);
+
+    Some(egui::ColorImage::from_rgba_unmultiplied(
+        [thumbnail.width() as usize, thumbnail.height() as usize],
+        thumbnail.as_raw(),
+    ))
+}
+
+pub struct Scene {
+    pub start_ms: i64,
+    /// Difference to the previous frame that started the scene; 1.0 for the first one.
+    pub score: f32,
+}
+
+enum DetectEvent {
+    Progress(i64),
+    Scene(Scene, Option<egui::ColorImage>),
+    Finished(Result<(), String>),
+}
+
+/// Decodes the whole video and reports a scene wherever consecutive frames differ by
+/// more than `threshold`.
+fn detect(
+    filename: &str,
+    threshold: f32,
+    sender: &mpsc::Sender<DetectEvent>,
+    cancel: &AtomicBool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut video = Video::new(filename)?;
+    let mut previous: Option<Vec<f32>> = None;
+    let mut last_scene_ms = None;
+
+    while let Some(frame) = video.next_frame() {
+        if cancel.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        let frame = match frame {
+            Ok(frame) => frame,
+            Err(e) => {
+                eprintln!("Error decoding frame for scene detection: {}", e);
+                continue;
+            }
+        };
+
+        let grid = luma_grid(frame.width, frame.height, &frame.buffer);
+        let score = match &previous {
+            Some(previous) => difference(previous, &grid),
+            None => 1.0,
+        };
+        let far_enough =
+            last_scene_ms.is_none_or(|last_ms| frame.timestamp_ms - last_ms >= MIN_SCENE_MS);
+
+        if score > threshold && far_enough {
+            last_scene_ms = Some(frame.timestamp_ms);
+            let scene = Scene {
+                start_ms: frame.timestamp_ms,
+                score,
+            };
+            let image = thumbnail(frame.width, frame.height, &frame.buffer);
+            if sender.send(DetectEvent::Scene(scene, image)).is_err() {
+                return Ok(());
+            }
+        }
+
+        let _ = sender.send(DetectEvent::Progress(frame.timestamp_ms));
+        previous = Some(grid);
+    }
+
+    Ok(())
+}
+
+/// Runs scene detection on a worker thread. Dropping it stops the detection.
+pub struct SceneDetection {
+    receiver: mpsc::Receiver<DetectEvent>,
+    cancel: Arc<AtomicBool>,
+    duration_ms: i64,
+    position_ms: i64,
+    scenes: Vec<Scene>,
+    thumbnails: Vec<Option<egui::TextureHandle>>,
+    result: Option<Result<(), String>>,
+}
+
+impl SceneDetection {
+    pub fn start(filename: &str, duration_ms: i64, threshold: f32) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancel_flag = cancel.clone();
+        let filename = filename.to_string();
+
+        thread::spawn(move || {
+            let result = detect(&filename, threshold, &sender, &cancel_flag);
+            let _ = sender.send(DetectEvent::Finished(result.map_err(|e| e.to_string())));
+        });
+
+        Self {
+            receiver,
+            cancel,
+            duration_ms,
+            position_ms: 0,
+            scenes: Vec::new(),
+            thumbnails: Vec::new(),
+            result: None,
+        }
+    }
+
+    /// Returns true while the detection is still running.
+    pub fn update(&mut self, ctx: &egui::Context) -> bool {
+        for event in self.receiver.try_iter() {
+            match event {
+                DetectEvent::Progress(position_ms) => self.position_ms = position_ms,
+                DetectEvent::Scene(scene, image) => {
+                    self.thumbnails.push(image.map(|image| {
+                        ctx.load_texture(
+                            format!("scene_{}", self.scenes.len()),
+                            image,
+                            egui::TextureOptions::LINEAR,
+                        )
+                    }));
+                    self.scenes.push(scene);
+                }
+                DetectEvent::Finished(result) => self.result = Some(result),
+            }
+        }
+        self.result.is_none()
+    }
+
+    /// The detected scenes as chapters, once the whole file has been analyzed.
+    pub fn chapters(&self) -> Option<Vec<ChapterInfo>> {
+        if !matches!(self.result, Some(Ok(()))) || self.scenes.is_empty() {
+            return None;
+        }
+
+        Some(
+            self.scenes
+                .iter()
+                .enumerate()
+                .map(|(index, scene)| ChapterInfo {
+                    index,
+                    title: tr!("scenes-chapter-title", number = index + 1),
+                    // The first frame may not be at exactly zero
+                    start_time_ms: if index == 0 { 0 } else { scene.start_ms },
+                    end_time_ms: self
+                        .scenes
+                        .get(index + 1)
+                        .map_or(self.duration_ms, |next| next.start_ms),
+                    metadata: HashMap::from([(
+                        "scene_score".to_string(),
+                        format!("{:.3}", scene.score),
+                    )]),
+                })
+                .collect(),
+        )
+    }
+
+    /// Draws progress and the scenes found so far. Returns the time to seek to when a
+    /// thumbnail is clicked.
+    pub fn show(&self, ui: &mut egui::Ui, current_ms: i64) -> Option<i64> {
+        match &self.result {
+            None => {
+                let progress = if self.duration_ms > 0 {
+                    self.position_ms as f32 / self.duration_ms as f32
+                } else {
+                    0.0
+                };
+                ui.label(tr!("scenes-decoding"));
+                ui.add(egui::ProgressBar::new(progress.clamp(0.0, 1.0)).show_percentage());
+            }
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::LIGHT_RED, tr!("scenes-failed", error = e));
+            }
+            Some(Ok(())) => {
+                ui.label(tr!("scenes-found", count = self.scenes.len()));
+            }
+        }
+        ui.add_space(4.0);
+
+        let current_scene = self
+            .scenes
+            .iter()
+            .rposition(|scene| scene.start_ms <= current_ms);
+        let mut seek_target = None;
+
+        egui::ScrollArea::vertical()
+            .max_height(320.0)
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    for (index, (scene, texture)) in
+                        self.scenes.iter().zip(&self.thumbnails).enumerate()
+                    {
+                        let response = ui
+                            .vertical(|ui| {
+                                let response = match texture {
+                                    Some(texture) => ui.add(
+                                        egui::ImageButton::new((texture.id(), texture.size_vec2()))
+                                            .selected(current_scene == Some(index)),
+                                    ),
+                                    None => {
+                                        ui.button(tr!("scenes-chapter-title", number = index + 1))
+                                    }
+                                };
+                                ui.small(crate::VideoPlayer::format_time(scene.start_ms));
+                                response
+                            })
+                            .inner;
+                        if response.clicked() {
+                            seek_target = Some(scene.start_ms);
+                        }
+                    }
+                });
+            });
+
+        seek_target
+    }
+}
+
+impl Drop for SceneDetection {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,348 @@
+use crate::i18n::tr;
+use eframe::egui;
+
+/// Frames are sampled on a grid at most this many pixels wide.
+const SAMPLE_WIDTH: usize = 320;
+const PARADE_COLUMNS: usize = 128;
+const VECTORSCOPE_SIZE: usize = 256;
+
+const PANEL_HEIGHT: f32 = 160.0;
+const PANEL_MARGIN: f32 = 12.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    Histogram,
+    Parade,
+    Vectorscope,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 3] = [Scope::Histogram, Scope::Parade, Scope::Vectorscope];
+
+    pub fn name(&self) -> String {
+        match self {
+            Scope::Histogram => tr!("scope-histogram"),
+            Scope::Parade => tr!("scope-parade"),
+            Scope::Vectorscope => tr!("scope-vectorscope"),
+        }
+    }
+}
+
+/// BT.709 luma and scaled color differences, each in 0..=255.
+fn ycbcr(color: egui::Color32) -> (f32, f32, f32) {
+    let (r, g, b) = (color.r() as f32, color.g() as f32, color.b() as f32);
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let cb = (b - y) / 1.8556 + 127.5;
+    let cr = (r - y) / 1.5748 + 127.5;
+    (y, cb, cr)
+}
+
+/// Maps hit counts to brightness, with a square root so sparse values stay visible.
+fn density_image(
+    size: [usize; 2],
+    counts: &[u32],
+    tint: impl Fn(usize) -> [f32; 3],
+) -> egui::ColorImage {
+    let max = counts.iter().copied().max().unwrap_or(0).max(1) as f32;
+    let pixels = counts
+        .iter()
+        .enumerate()
+        .map(|(i, &count)| {
+            if count == 0 {
+                return egui::Color32::TRANSPARENT;
+            }
+            let level = (count as f32 / max).sqrt().max(0.25);
+            let [r, g, b] = tint(i);
+            egui::Color32::from_rgba_unmultiplied(
+                (r * 255.0) as u8,
+                (g * 255.0) as u8,
+                (b * 255.0) as u8,
+                (level * 255.0) as u8,
+            )
+        })
+        .collect();
+
+    egui::ColorImage { size, pixels }
+}
+
+/// Histogram, RGB parade and vectorscope of the displayed frame, drawn over the video.
+pub struct Scopes {
+    histogram: bool,
+    parade: bool,
+    vectorscope: bool,
+    /// Set when a new frame was uploaded, so the scopes are recomputed once per frame.
+    stale: bool,
+    luma: [u32; 256],
+    parade_texture: Option<egui::TextureHandle>,
+    vectorscope_texture: Option<egui::TextureHandle>,
+}
+
+impl Scopes {
+    pub fn new() -> Self {
+        Self {
+            histogram: false,
+            parade: false,
+            vectorscope: false,
+            stale: true,
+            luma: [0; 256],
+            parade_texture: None,
+            vectorscope_texture: None,
+        }
+    }
+
+    pub fn is_shown(&self, scope: Scope) -> bool {
+        match scope {
+            Scope::Histogram => self.histogram,
+            Scope::Parade => self.parade,
+            Scope::Vectorscope => self.vectorscope,
+        }
+    }
+
+    pub fn toggle(&mut self, scope: Scope) {
+        let shown = match scope {
+            Scope::Histogram => &mut self.histogram,
+            Scope::Parade => &mut self.parade,
+            Scope::Vectorscope => &mut self.vectorscope,
+        };
+        *shown = !*shown;
+        self.stale = true;
+    }
+
+    fn any_shown(&self) -> bool {
+        self.histogram || self.parade || self.vectorscope
+    }
+
+    pub fn invalidate(&mut self) {
+        self.stale = true;
+    }
+
+    fn update(&mut self, ctx: &egui::Context, image: &egui::ColorImage) {
+        let [width, height] = image.size;
+        if width == 0 || height == 0 {
+            return;
+        }
+        let step = width.div_ceil(SAMPLE_WIDTH);
+
+        let mut luma = [0u32; 256];
+        let mut parade = vec![0u32; PARADE_COLUMNS * 3 * 256];
+        let mut vectorscope = vec![0u32; VECTORSCOPE_SIZE * VECTORSCOPE_SIZE];
+
+        for y in (0..height).step_by(step) {
+            let row = &image.pixels[y * width..(y + 1) * width];
+            for x in (0..width).step_by(step) {
+                let color = row[x];
+                let (luma_value, cb, cr) = ycbcr(color);
+                luma[luma_value.round() as usize] += 1;
+
+                // Channels side by side, with 255 at the top
+                let column = x * PARADE_COLUMNS / width;
+                for (channel, value) in [color.r(), color.g(), color.b()].into_iter().enumerate() {
+                    let level = 255 - value as usize;
+                    parade[level * PARADE_COLUMNS * 3 + channel * PARADE_COLUMNS + column] += 1;
+                }
+
+                let vx = (cb as usize).min(VECTORSCOPE_SIZE - 1);
+                let vy = (255.0 - cr) as usize;
+                vectorscope[vy.min(VECTORSCOPE_SIZE - 1) * VECTORSCOPE_SIZE + vx] += 1;
+            }
+        }
+
+        self.luma = luma;
+
+        let parade = density_image([PARADE_COLUMNS * 3, 256], &parade, |i| {
+            match i % (PARADE_COLUMNS * 3) / PARADE_COLUMNS {
+                0 => [1.0, 0.3, 0.3],
+                1 => [0.3, 1.0, 0.3],
+                _ => [0.4, 0.5, 1.0],
+            }
+        });
+        let vectorscope = density_image([VECTORSCOPE_SIZE, VECTORSCOPE_SIZE], &vectorscope, |_| {
+            [0.6, 1.0, 0.6]
+        });
+
+        for (texture, image, name) in [
+            (&mut self.parade_texture, parade, "scope_parade"),
+            (
+                &mut self.vectorscope_texture,
+                vectorscope,
+                "scope_vectorscope",
+            ),
+        ] {
+            match texture {
+                Some(texture) => texture.set(image, egui::TextureOptions::LINEAR),
+                None => {
+                    *texture = Some(ctx.load_texture(name, image, egui::TextureOptions::LINEAR))
+                }
+            }
+        }
+    }
+
+    /// Draws the enabled scopes in a row along the bottom right of `area`.
+    pub fn draw(
+        &mut self,
+        ctx: &egui::Context,
+        painter: &egui::Painter,
+        area: egui::Rect,
+        image: Option<&egui::ColorImage>,
+    ) {
+        if !self.any_shown() {
+            return;
+        }
+        let Some(image) = image else {
+            return;
+        };
+        if self.stale {
+            self.update(ctx, image);
+            self.stale = false;
+        }
+
+        let mut right = area.right() - PANEL_MARGIN;
+        let bottom = area.bottom() - PANEL_MARGIN;
+        let mut next_panel = |width: f32| {
+            let rect = egui::Rect::from_min_max(
+                egui::pos2(right - width, bottom - PANEL_HEIGHT),
+                egui::pos2(right, bottom),
+            );
+            right -= width + PANEL_MARGIN;
+            painter.rect_filled(
+                rect.expand(4.0),
+                egui::Rounding::same(4.0),
+                egui::Color32::from_black_alpha(180),
+            );
+            rect
+        };
+
+        if self.vectorscope {
+            let rect = next_panel(PANEL_HEIGHT);
+            self.draw_vectorscope(painter, rect);
+        }
+        if self.parade {
+            let rect = next_panel(PANEL_HEIGHT * 1.8);
+            self.draw_parade(painter, rect);
+        }
+        if self.histogram {
+            let rect = next_panel(PANEL_HEIGHT * 1.6);
+            self.draw_histogram(painter, rect);
+        }
+    }
+
+    fn draw_histogram(&self, painter: &egui::Painter, rect: egui::Rect) {
+        let max = self.luma.iter().copied().max().unwrap_or(0).max(1) as f32;
+        let bin_width = rect.width() / 256.0;
+
+        let mut mesh = egui::Mesh::default();
+        for (value, &count) in self.luma.iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+            let height = rect.height() * (count as f32 / max).sqrt();
+            let left = rect.left() + value as f32 * bin_width;
+            mesh.add_colored_rect(
+                egui::Rect::from_min_max(
+                    egui::pos2(left, rect.bottom() - height),
+                    egui::pos2(left + bin_width, rect.bottom()),
+                ),
+                egui::Color32::from_gray(200),
+            );
+        }
+        painter.add(mesh);
+
+        let stroke = egui::Stroke::new(1.0, egui::Color32::from_white_alpha(40));
+        for fraction in [0.25, 0.5, 0.75] {
+            painter.vline(
+                rect.left() + rect.width() * fraction,
+                rect.y_range(),
+                stroke,
+            );
+        }
+
+        // Clipped shadows and highlights are marked in red
+        for (value, x) in [(0, rect.left()), (255, rect.right())] {
+            if self.luma[value] > 0 {
+                painter.vline(
+                    x,
+                    rect.y_range(),
+                    egui::Stroke::new(2.0, egui::Color32::RED),
+                );
+            }
+        }
+    }
+
+    fn draw_parade(&self, painter: &egui::Painter, rect: egui::Rect) {
+        let stroke = egui::Stroke::new(1.0, egui::Color32::from_white_alpha(40));
+        for fraction in [0.0, 0.25, 0.5, 0.75, 1.0] {
+            painter.hline(
+                rect.x_range(),
+                rect.top() + rect.height() * fraction,
+                stroke,
+            );
+        }
+
+        if let Some(texture) = &self.parade_texture {
+            painter.image(
+                texture.id(),
+                rect,
+                egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                egui::Color32::WHITE,
+            );
+        }
+
+        for fraction in [1.0 / 3.0, 2.0 / 3.0] {
+            painter.vline(
+                rect.left() + rect.width() * fraction,
+                rect.y_range(),
+                stroke,
+            );
+        }
+    }
+
+    fn draw_vectorscope(&self, painter: &egui::Painter, rect: egui::Rect) {
+        let center = rect.center();
+        let radius = rect.width() / 2.0;
+        let stroke = egui::Stroke::new(1.0, egui::Color32::from_white_alpha(50));
+        painter.circle_stroke(center, radius, stroke);
+        painter.hline(rect.x_range(), center.y, stroke);
+        painter.vline(center.x, rect.y_range(), stroke);
+
+        // Skin tone line
+        let skin = egui::vec2(-0.55, -0.835) * radius;
+        painter.line_segment([center, center + skin], stroke);
+
+        // 75% color bar targets
+        for (label, color) in [
+            ("R", [191, 0, 0]),
+            ("G", [0, 191, 0]),
+            ("B", [0, 0, 191]),
+            ("Cy", [0, 191, 191]),
+            ("Mg", [191, 0, 191]),
+            ("Yl", [191, 191, 0]),
+        ] {
+            let (_, cb, cr) = ycbcr(egui::Color32::from_rgb(color[0], color[1], color[2]));
+            let pos = egui::pos2(
+                rect.left() + rect.width() * cb / 255.0,
+                rect.top() + rect.height() * (255.0 - cr) / 255.0,
+            );
+            painter.rect_stroke(
+                egui::Rect::from_center_size(pos, egui::vec2(8.0, 8.0)),
+                egui::Rounding::ZERO,
+                stroke,
+            );
+            painter.text(
+                pos + egui::vec2(6.0, -6.0),
+                egui::Align2::LEFT_BOTTOM,
+                label,
+                egui::FontId::proportional(10.0),
+                egui::Color32::from_white_alpha(120),
+            );
+        }
+
+        if let Some(texture) = &self.vectorscope_texture {
+            painter.image(
+                texture.id(),
+                rect,
+                egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                egui::Color32::WHITE,
+            );
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use directories::UserDirs;
+use eframe::egui;
+use image::ImageEncoder;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
+use std::thread;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ScreenshotFormat {
+    #[default]
+    Png,
+    Jpeg,
+    /// Lossless, smaller than PNG.
+    WebP,
+}
+
+impl ScreenshotFormat {
+    pub const ALL: [ScreenshotFormat; 3] = [
+        ScreenshotFormat::Png,
+        ScreenshotFormat::Jpeg,
+        ScreenshotFormat::WebP,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScreenshotFormat::Png => "PNG",
+            ScreenshotFormat::Jpeg => "JPEG",
+            ScreenshotFormat::WebP => "WebP",
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            ScreenshotFormat::Png => "png",
+            ScreenshotFormat::Jpeg => "jpg",
+            ScreenshotFormat::WebP => "webp",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScreenshotSettings {
+    /// Where screenshots are saved, or `None` for the Pictures folder.
+    pub directory: Option<PathBuf>,
+    /// File name without the extension. `%f` is replaced with the media file name, `%t` with
+    /// the position as hh-mm-ss.mmm, `%n` with a counter and `%%` with a percent sign.
+    pub template: String,
+    pub format: ScreenshotFormat,
+    /// JPEG quality from 1 to 100.
+    pub jpeg_quality: u8,
+}
+
+impl Default for ScreenshotSettings {
+    fn default() -> Self {
+        Self {
+            directory: None,
+            template: "%f_%t".to_string(),
+            format: ScreenshotFormat::Png,
+            jpeg_quality: 90,
+        }
+    }
+}
+
+pub fn default_directory() -> PathBuf {
+    UserDirs::new()
+        .and_then(|dirs| dirs.picture_dir().map(Path::to_path_buf))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Fills in the tokens of `template`. Path separators are replaced so that the name stays in
+/// the screenshot folder.
+fn expand(template: &str, stem: &str, time_ms: i64, counter: u32) -> String {
+    let mut name = String::new();
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            name.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('f') => name.push_str(stem),
+            Some('t') => name.push_str(&format!(
+                "{:02}-{:02}-{:02}.{:03}",
+                time_ms / 3_600_000,
+                time_ms / 60_000 % 60,
+                time_ms / 1000 % 60,
+                time_ms % 1000
+            )),
+            Some('n') => name.push_str(&format!("{:04}", counter)),
+            Some('%') => name.push('%'),
+            Some(other) => {
+                name.push('%');
+                name.push(other);
+            }
+            None => name.push('%'),
+        }
+    }
+
+    let name = name.replace(['/', '\\'], "_");
+    if name.trim().is_empty() {
+        "screenshot".to_string()
+    } else {
+        name
+    }
+}
+
+/// The template filled in for `media_path` at `time_ms`, in the screenshot folder. `%n`
+/// counts up to the first free name; without it a counter is added if the file exists.
+pub fn output_path(media_path: &str, time_ms: i64, settings: &ScreenshotSettings) -> PathBuf {
+    let dir = settings.directory.clone().unwrap_or_else(default_directory);
+    let stem = Path::new(media_path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "screenshot".to_string());
+    let time_ms = time_ms.max(0);
+    let extension = settings.format.extension();
+
+    // Whether `%n` is there as a token rather than in an escaped `%%n`
+    let uses_counter = expand(&settings.template, &stem, time_ms, 1)
+        != expand(&settings.template, &stem, time_ms, 2);
+    if uses_counter {
+        let mut counter = 1;
+        loop {
+            let name = expand(&settings.template, &stem, time_ms, counter);
+            let path = dir.join(format!("{}.{}", name, extension));
+            if !path.exists() {
+                return path;
+            }
+            counter += 1;
+        }
+    }
+
+    let name = expand(&settings.template, &stem, time_ms, 0);
+    let mut path = dir.join(format!("{}.{}", name, extension));
+    let mut counter = 2;
+    while path.exists() {
+        path = dir.join(format!("{}_{}.{}", name, counter, extension));
+        counter += 1;
+    }
+    path
+}
+
+/// Encodes the image on a worker thread and reports the saved path through `sender`.
+pub fn save(
+    image: Arc<egui::ColorImage>,
+    path: PathBuf,
+    settings: &ScreenshotSettings,
+    sender: mpsc::Sender<Result<PathBuf, String>>,
+) {
+    let format = settings.format;
+    let quality = settings.jpeg_quality.clamp(1, 100);
+    thread::spawn(move || {
+        let result = write_image(&image, &path, format, quality)
+            .map(|_| path)
+            .map_err(|e| e.to_string());
+        let _ = sender.send(result);
+    });
+}
+
+fn write_image(
+    image: &egui::ColorImage,
+    path: &Path,
+    format: ScreenshotFormat,
+    quality: u8,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if image.width() == 0 || image.height() == 0 {
+        return Err("No frame to save".into());
+    }
+
+    // Both the decoded frames and the window contents are opaque
+    let rgb: Vec<u8> = image
+        .pixels
+        .iter()
+        .flat_map(|pixel| [pixel.r(), pixel.g(), pixel.b()])
+        .collect();
+
+    let (width, height) = (image.width() as u32, image.height() as u32);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let writer = BufWriter::new(File::create(path)?);
+    let color = image::ExtendedColorType::Rgb8;
+    match format {
+        ScreenshotFormat::Png => {
+            image::codecs::png::PngEncoder::new(writer).write_image(&rgb, width, height, color)?
+        }
+        ScreenshotFormat::Jpeg => {
+            image::codecs::jpeg::JpegEncoder::new_with_quality(writer, quality)
+                .write_image(&rgb, width, height, color)?
+        }
+        ScreenshotFormat::WebP => image::codecs::webp::WebPEncoder::new_lossless(writer)
+            .write_image(&rgb, width, height, color)?,
+    }
+
+    Ok(())
+}
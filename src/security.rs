@@ -0,0 +1,95 @@
+extern crate ffmpeg_next as ffmpeg;
+
+use crate::playlist;
+use ffmpeg::ffi;
+use serde::{Deserialize, Serialize};
+use std::ffi::CStr;
+use std::ptr;
+use std::sync::{OnceLock, RwLock};
+
+/// Protocols a local file may use, including those nested in it such as HLS segments.
+const LOCAL_PROTOCOLS: &str = "file,crypto,data,subfile";
+/// Protocols a URL may use. Leaves out `file`, so that a remote playlist can't read local files.
+const NETWORK_PROTOCOLS: &str =
+    "http,https,httpproxy,tcp,tls,udp,rtp,rtmp,rtmps,rtsp,srt,crypto,data";
+/// Demuxers that read other files or URLs named in the file.
+const EXPANDING_FORMATS: [&str; 2] = ["concat", "ffmetadata"];
+
+/// Restrictions for opening files from untrusted sources.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SecuritySettings {
+    /// Keeps local files to local protocols and URLs to network ones.
+    pub restrict_protocols: bool,
+    /// Refuses concat lists and FFmpeg metadata files as media.
+    pub block_expanding_formats: bool,
+    /// Loads chapter files and linked Matroska segments found next to the file.
+    pub autoload_sidecar_files: bool,
+}
+
+impl Default for SecuritySettings {
+    fn default() -> Self {
+        Self {
+            restrict_protocols: false,
+            block_expanding_formats: false,
+            autoload_sidecar_files: true,
+        }
+    }
+}
+
+fn current() -> &'static RwLock<SecuritySettings> {
+    static SETTINGS: OnceLock<RwLock<SecuritySettings>> = OnceLock::new();
+    SETTINGS.get_or_init(|| RwLock::new(SecuritySettings::default()))
+}
+
+pub fn configure(settings: &SecuritySettings) {
+    *current().write().unwrap() = settings.clone();
+}
+
+pub fn autoload_sidecar_files() -> bool {
+    current().read().unwrap().autoload_sidecar_files
+}
+
+pub fn restrict_protocols() -> bool {
+    current().read().unwrap().restrict_protocols
+}
+
+/// Adds the configured restrictions to the options `filename` is opened with.
+pub fn restrict(filename: &str, options: &mut ffmpeg::Dictionary) {
+    let settings = current().read().unwrap().clone();
+    if settings.restrict_protocols {
+        let protocols = if playlist::is_url(filename) && !filename.starts_with("file:") {
+            NETWORK_PROTOCOLS
+        } else {
+            LOCAL_PROTOCOLS
+        };
+        options.set("protocol_whitelist", protocols);
+    }
+    if settings.block_expanding_formats {
+        options.set("format_whitelist", &allowed_formats());
+    }
+}
+
+/// Every demuxer in the build but the expanding ones, as FFmpeg has no list of formats to refuse.
+fn allowed_formats() -> String {
+    static FORMATS: OnceLock<String> = OnceLock::new();
+    FORMATS
+        .get_or_init(|| {
+            let mut names = Vec::new();
+            let mut opaque = ptr::null_mut();
+            loop {
+                let format = unsafe { ffi::av_demuxer_iterate(&mut opaque) };
+                if format.is_null() {
+                    break;
+                }
+                let name = unsafe { CStr::from_ptr((*format).name) }.to_string_lossy();
+                names.extend(
+                    name.split(',')
+                        .filter(|name| !EXPANDING_FORMATS.contains(name))
+                        .map(str::to_string),
+                );
+            }
+            names.join(",")
+        })
+        .clone()
+}
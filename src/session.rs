@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often the state is written while it changes.
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Where playback was, written while playing and removed on a clean exit, so that finding it
+/// at startup means the last session ended in a crash or power loss.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// The playlist, in its own order.
+    pub playlist: Vec<String>,
+    /// The entry that was playing.
+    pub current: String,
+    /// Files that were played back to back as one, starting with `current`.
+    #[serde(default)]
+    pub joined: Vec<String>,
+    pub position_ms: i64,
+    pub paused: bool,
+}
+
+/// Each running instance writes a snapshot of its own, next to a lock file it holds while
+/// running.
+fn dir() -> Option<PathBuf> {
+    crate::storage::config_dir().map(|dir| dir.join("sessions"))
+}
+
+impl Snapshot {
+    fn load(path: &Path) -> Option<Self> {
+        let content = fs::read_to_string(path).ok()?;
+        match serde_json::from_str(&content) {
+            Ok(snapshot) => Some(snapshot),
+            Err(e) => {
+                eprintln!("Error parsing session snapshot {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Writes to a temporary file first and swaps it in, so that a crash while writing
+    /// leaves the previous snapshot rather than half of this one.
+    fn save(&self, path: &Path) -> io::Result<()> {
+        let temp_path = path.with_extension("json.tmp");
+        let mut file = File::create(&temp_path)?;
+        file.write_all(serde_json::to_string_pretty(self)?.as_bytes())?;
+        // Written through to the disk, as power loss is one of the things this is for
+        file.sync_all()?;
+        fs::rename(&temp_path, path)
+    }
+}
+
+fn remove_file(path: &Path) {
+    if let Err(e) = fs::remove_file(path) {
+        if e.kind() != io::ErrorKind::NotFound {
+            eprintln!("Error removing session snapshot {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Whether the instance that wrote the snapshot at `path` is still running, i.e. holds its
+/// lock file.
+fn is_running(path: &Path) -> bool {
+    match File::open(path.with_extension("lock")) {
+        Ok(file) => matches!(file.try_lock(), Err(fs::TryLockError::WouldBlock)),
+        Err(_) => false,
+    }
+}
+
+/// A snapshot that an instance which is no longer running left behind.
+pub struct Leftover {
+    pub snapshot: Snapshot,
+    path: PathBuf,
+}
+
+impl Leftover {
+    /// The latest snapshot of a session that didn't exit cleanly.
+    pub fn find() -> Option<Self> {
+        let entries = fs::read_dir(dir()?).ok()?;
+        entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter(|path| !is_running(path))
+            .filter_map(|path| {
+                let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+                Some((modified, path))
+            })
+            .max()
+            .and_then(|(_, path)| {
+                Some(Self {
+                    snapshot: Snapshot::load(&path)?,
+                    path,
+                })
+            })
+    }
+
+    /// Removes the snapshot once the user has dealt with it.
+    pub fn remove(&self) {
+        remove_file(&self.path);
+        remove_file(&self.path.with_extension("lock"));
+    }
+}
+
+/// Writes this instance's snapshots every few seconds while something changes, on a thread
+/// of its own so that a slow disk doesn't hold up playback.
+pub struct SnapshotWriter {
+    last: Option<Snapshot>,
+    last_write: Instant,
+    sender: Option<mpsc::Sender<Snapshot>>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Default for SnapshotWriter {
+    fn default() -> Self {
+        Self {
+            last: None,
+            last_write: Instant::now(),
+            sender: None,
+            thread: None,
+        }
+    }
+}
+
+impl SnapshotWriter {
+    pub fn is_due(&self) -> bool {
+        self.last_write.elapsed() >= SNAPSHOT_INTERVAL
+    }
+
+    pub fn write(&mut self, snapshot: Snapshot) {
+        self.last_write = Instant::now();
+        if self.last.as_ref() == Some(&snapshot) {
+            return;
+        }
+        if self.sender.is_none() {
+            self.start();
+        }
+        if let Some(sender) = &self.sender {
+            let _ = sender.send(snapshot.clone());
+        }
+        self.last = Some(snapshot);
+    }
+
+    fn start(&mut self) {
+        let Some(dir) = dir() else {
+            eprintln!("Error writing session snapshot: no config directory");
+            return;
+        };
+        let path = dir.join(format!("{}.json", std::process::id()));
+        let (sender, receiver) = mpsc::channel::<Snapshot>();
+
+        self.thread = Some(thread::spawn(move || {
+            // Held until the thread ends, so that other instances know this one is running
+            let lock = fs::create_dir_all(&dir)
+                .and_then(|()| File::create(path.with_extension("lock")))
+                .and_then(|file| file.lock().map(|()| file));
+            if let Err(e) = &lock {
+                eprintln!("Error locking session snapshot: {}", e);
+            }
+
+            // Only the latest of what queued up while writing is worth writing
+            while let Ok(mut snapshot) = receiver.recv() {
+                while let Ok(newer) = receiver.try_recv() {
+                    snapshot = newer;
+                }
+                if let Err(e) = snapshot.save(&path) {
+                    eprintln!("Error writing session snapshot: {}", e);
+                }
+            }
+
+            remove_file(&path);
+            drop(lock);
+            remove_file(&path.with_extension("lock"));
+        }));
+        self.sender = Some(sender);
+    }
+
+    /// Removes this instance's snapshot, on a clean exit or when snapshots are turned off.
+    /// Waits for a write in progress, so that it doesn't bring the file back.
+    pub fn finish(&mut self) {
+        self.sender = None;
+        self.last = None;
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use crate::ab_loop::AbLoopSettings;
+use crate::audio::{AudioBackend, Downmix};
+use crate::audio_profiles::AudioProfiles;
+use crate::http::HttpOptions;
+use crate::playlist::EndOfPlaylist;
+use crate::presets::{self, Preset};
+use crate::screenshot::ScreenshotSettings;
+use crate::security::SecuritySettings;
+use crate::shaders::{DisplayMode, Sampler};
+use crate::sleep_timer::SleepAction;
+use crate::sponsorblock::SponsorBlockSettings;
+use crate::subtitles::SubtitleStyle;
+use crate::theme::ThemeMode;
+use crate::timecode::TimeDisplay;
+use crate::video::ScalingAlgorithm;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+const DEFAULT_MEDIA_EXTENSIONS: [&str; 28] = [
+    "mp4", "avi", "mkv", "mov", "wmv", "flv", "webm", "m4v", "mpg", "mpeg", "ts", "m2ts", "ogv",
+    "3gp", "mp3", "flac", "ogg", "opus", "m4a", "wav", "aac", "wma", "mka", "png", "jpg", "jpeg",
+    "webp", "avif",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub media_extensions: Vec<String>,
+    pub shuffle: bool,
+    pub opensubtitles_api_key: String,
+    pub subtitle_language: String,
+    pub subtitle_style: SubtitleStyle,
+    /// Tesseract language of subtitle OCR, such as `eng` or `eng+deu`.
+    pub ocr_language: String,
+    pub denoise: bool,
+    pub deband: bool,
+    pub scaling: ScalingAlgorithm,
+    pub sampler: Sampler,
+    pub display_mode: DisplayMode,
+    pub shaders: Vec<String>,
+    pub active_shader: Option<String>,
+    pub show_filmstrip: bool,
+    pub theme: ThemeMode,
+    /// Code of the UI language, see [`crate::i18n`].
+    pub language: String,
+    /// Clicking the picture plays or pauses.
+    pub click_to_pause: bool,
+    /// How far behind the live edge live streams play, in ms.
+    pub live_latency_ms: u32,
+    pub live_catch_up: crate::live::CatchUp,
+    /// Buffers live streams on disk so that they can be paused and rewound.
+    pub timeshift: bool,
+    /// HTTP options for every URL.
+    pub http: HttpOptions,
+    /// HTTP options by host name, added to `http`.
+    pub http_sites: HashMap<String, HttpOptions>,
+    /// Proxy for all network access, e.g. `http://host:8080` or `socks5://host:1080`. Empty
+    /// uses the system proxy, `direct` none.
+    pub proxy: String,
+    /// Size the cache folder is trimmed to, oldest files first; 0 is no limit.
+    pub cache_limit_mb: u32,
+    /// Writes dropped frames, decode times, underruns and sync corrections to a log.
+    pub stats_log: bool,
+    /// Keeps a snapshot of what is playing, to offer continuing after a crash.
+    pub crash_recovery: bool,
+    /// Shows an icon with play/pause and next in the system tray.
+    pub tray_icon: bool,
+    /// Minimizing hides the window until it is brought back from the tray icon.
+    pub minimize_to_tray: bool,
+    /// sRGB color of the seek bar, active buttons and selections.
+    pub accent_color: [u8; 3],
+    /// Frame difference above which scene detection reports a cut.
+    pub scene_threshold: f32,
+    /// Interpolate frames with `minterpolate` when playing slower than 1x.
+    pub interpolate_slow_motion: bool,
+    /// Dragging the seek bar plays snippets of the audio it passes over.
+    pub audible_scrubbing: bool,
+    /// Clicks on the seek bar close to a chapter boundary seek to the boundary.
+    pub snap_to_chapters: bool,
+    /// Keep the audio pitch when the playback speed changes.
+    pub keep_pitch: bool,
+    /// Compress the dynamic range, for watching quietly at night.
+    pub night_mode: bool,
+    /// How hard night mode compresses, from 0 to 1.
+    pub night_mode_strength: f32,
+    /// Share of the secondary audio stream in the mix, from 0 (main only) to 1.
+    pub mix_balance: f32,
+    /// What the sleep timer does when it runs out.
+    pub sleep_action: SleepAction,
+    pub end_of_playlist: EndOfPlaylist,
+    pub time_display: TimeDisplay,
+    pub ab_loop: AbLoopSettings,
+    pub screenshot: ScreenshotSettings,
+    pub sponsorblock: SponsorBlockSettings,
+    pub downmix: Downmix,
+    pub audio_backend: AudioBackend,
+    /// Output device by name, or `None` for the system default.
+    pub output_device: Option<String>,
+    pub audio_profiles: AudioProfiles,
+    /// Named bundles of settings, applied from the menu or to files matching their patterns.
+    pub presets: Vec<Preset>,
+    /// Most memory decoded audio may take, in MB. Longer files are decoded while they play.
+    pub audio_memory_mb: u32,
+    /// Decoded video frames kept ready beyond the next one.
+    pub frame_queue: usize,
+    /// Most the demuxer may read ahead of the decoder, in MB.
+    pub read_ahead_mb: u32,
+    pub security: SecuritySettings,
+    /// Whether Avio is registered as the handler for `media_extensions`.
+    pub register_file_types: bool,
+    pub window: WindowGeometry,
+}
+
+/// Where the window was when Avio last closed, restored at startup.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowGeometry {
+    /// Inner size in points while neither maximized nor fullscreen.
+    pub size: Option<[f32; 2]>,
+    /// Outer position in points while neither maximized nor fullscreen.
+    pub position: Option<[f32; 2]>,
+    pub maximized: bool,
+    pub fullscreen: bool,
+    /// Outer position that puts the window on the display chosen for fullscreen. Without one,
+    /// fullscreen uses the display the window is on.
+    pub fullscreen_display: Option<[f32; 2]>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            media_extensions: DEFAULT_MEDIA_EXTENSIONS
+                .iter()
+                .map(|ext| ext.to_string())
+                .collect(),
+            shuffle: false,
+            opensubtitles_api_key: String::new(),
+            subtitle_language: "en".to_string(),
+            subtitle_style: SubtitleStyle::default(),
+            ocr_language: "eng".to_string(),
+            denoise: false,
+            deband: false,
+            scaling: ScalingAlgorithm::default(),
+            sampler: Sampler::default(),
+            display_mode: DisplayMode::default(),
+            shaders: Vec::new(),
+            active_shader: None,
+            show_filmstrip: false,
+            theme: ThemeMode::default(),
+            language: crate::i18n::DEFAULT_LANGUAGE.to_string(),
+            click_to_pause: true,
+            live_latency_ms: crate::live::DEFAULT_LATENCY_MS,
+            live_catch_up: crate::live::CatchUp::default(),
+            timeshift: false,
+            http: HttpOptions::default(),
+            http_sites: HashMap::new(),
+            proxy: String::new(),
+            cache_limit_mb: crate::storage::DEFAULT_CACHE_LIMIT_MB,
+            stats_log: false,
+            crash_recovery: true,
+            tray_icon: false,
+            minimize_to_tray: false,
+            accent_color: crate::theme::DEFAULT_ACCENT,
+            scene_threshold: crate::scene_detect::DEFAULT_THRESHOLD,
+            interpolate_slow_motion: false,
+            audible_scrubbing: false,
+            snap_to_chapters: true,
+            keep_pitch: false,
+            night_mode: false,
+            night_mode_strength: 0.5,
+            mix_balance: 0.5,
+            sleep_action: SleepAction::Pause,
+            end_of_playlist: EndOfPlaylist::Stay,
+            time_display: TimeDisplay::Elapsed,
+            ab_loop: AbLoopSettings::default(),
+            screenshot: ScreenshotSettings::default(),
+            sponsorblock: SponsorBlockSettings::default(),
+            downmix: Downmix::default(),
+            audio_backend: AudioBackend::default(),
+            output_device: None,
+            audio_profiles: AudioProfiles::default(),
+            presets: presets::default_presets(),
+            audio_memory_mb: crate::audio::DEFAULT_MEMORY_MB,
+            frame_queue: crate::decode_ahead::DEFAULT_FRAME_QUEUE,
+            read_ahead_mb: crate::decode_ahead::DEFAULT_READ_AHEAD_MB,
+            security: SecuritySettings::default(),
+            register_file_types: false,
+            window: WindowGeometry::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        let path = match Settings::path() {
+            Some(path) => path,
+            None => return Settings::default(),
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(content) => match serde_json::from_str(&content) {
+                Ok(settings) => settings,
+                Err(e) => {
+                    eprintln!("Error parsing settings {}: {}", path.display(), e);
+                    Settings::default()
+                }
+            },
+            Err(_) => Settings::default(),
+        }
+    }
+
+    pub fn save(&self) {
+        let path = match Settings::path() {
+            Some(path) => path,
+            None => return,
+        };
+
+        let result = path
+            .parent()
+            .map(fs::create_dir_all)
+            .unwrap_or(Ok(()))
+            .and_then(|_| {
+                let content = serde_json::to_string_pretty(self)?;
+                fs::write(&path, content)
+            });
+
+        if let Err(e) = result {
+            eprintln!("Error saving settings {}: {}", path.display(), e);
+        }
+    }
+
+    fn path() -> Option<PathBuf> {
+        crate::storage::config_dir().map(|dir| dir.join("settings.json"))
+    }
+}
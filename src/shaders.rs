@@ -0,0 +1,463 @@
+use crate::i18n::tr;
+use eframe::egui;
+use eframe::egui_glow;
+use eframe::glow::{self, HasContext};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+const VERTEX_SHADER: &str = r#"
+out vec2 v_tex_coord;
+
+void main() {
+    vec2 position = vec2(float(gl_VertexID & 1), float((gl_VertexID >> 1) & 1));
+    v_tex_coord = vec2(position.x, 1.0 - position.y);
+    gl_Position = vec4(position * 2.0 - 1.0, 0.0, 1.0);
+}
+"#;
+
+/// Provides the subset of the mpv user shader interface used by single-pass hooks.
+const FRAGMENT_PRELUDE: &str = r#"
+#ifdef GL_ES
+precision highp float;
+#endif
+
+uniform sampler2D HOOKED_raw;
+uniform vec2 HOOKED_size;
+uniform vec2 target_size;
+uniform int frame;
+uniform float random;
+
+in vec2 v_tex_coord;
+out vec4 f_color;
+
+vec4 HOOKED_tex(vec2 pos) {
+    vec4 color = texture(HOOKED_raw, pos);
+#if SRGB_TEXTURE
+    // egui stores textures as sRGB, so sampling returns linear values
+    bvec3 cutoff = lessThan(color.rgb, vec3(0.0031308));
+    vec3 lower = color.rgb * vec3(12.92);
+    vec3 higher = vec3(1.055) * pow(color.rgb, vec3(1.0 / 2.4)) - vec3(0.055);
+    color.rgb = mix(higher, lower, vec3(cutoff));
+#endif
+    return color;
+}
+
+#define HOOKED_pos v_tex_coord
+#define HOOKED_pt (1.0 / HOOKED_size)
+#define HOOKED_texOff(off) HOOKED_tex(HOOKED_pos + HOOKED_pt * vec2(off))
+#define MAIN_raw HOOKED_raw
+#define MAIN_size HOOKED_size
+#define MAIN_pos HOOKED_pos
+#define MAIN_pt HOOKED_pt
+#define MAIN_tex HOOKED_tex
+#define MAIN_texOff HOOKED_texOff
+"#;
+
+/// Projects an equirectangular frame onto a sphere seen from its center.
+const SPHERICAL_HOOK: &str = r#"
+// yaw, pitch and vertical field of view in radians
+uniform vec3 view;
+
+vec4 hook() {
+    vec2 ndc = vec2(HOOKED_pos.x * 2.0 - 1.0, 1.0 - HOOKED_pos.y * 2.0);
+    float scale = tan(view.z * 0.5);
+    vec3 ray = normalize(vec3(ndc.x * scale * target_size.x / target_size.y, ndc.y * scale, 1.0));
+
+    float cos_pitch = cos(view.y);
+    float sin_pitch = sin(view.y);
+    ray = vec3(ray.x, ray.y * cos_pitch + ray.z * sin_pitch, ray.z * cos_pitch - ray.y * sin_pitch);
+
+    float cos_yaw = cos(view.x);
+    float sin_yaw = sin(view.x);
+    ray = vec3(ray.x * cos_yaw + ray.z * sin_yaw, ray.y, ray.z * cos_yaw - ray.x * sin_yaw);
+
+    float longitude = atan(ray.x, ray.z);
+    float latitude = asin(clamp(ray.y, -1.0, 1.0));
+    return HOOKED_tex(vec2(longitude / 6.2831853 + 0.5, 0.5 - latitude / 3.1415927));
+}
+"#;
+
+const FRAGMENT_MAIN: &str = r#"
+void main() {
+    f_color = hook();
+}
+"#;
+
+/// How the GPU samples the video texture when it is drawn larger or smaller than its size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Sampler {
+    /// Blocky pixels, e.g. for pixel art or inspecting single pixels.
+    Nearest,
+    #[default]
+    Linear,
+    /// Linear with mipmaps, which avoids shimmering when the video is shown much smaller.
+    Mipmapped,
+}
+
+impl Sampler {
+    pub const ALL: [Sampler; 3] = [Sampler::Nearest, Sampler::Linear, Sampler::Mipmapped];
+
+    pub fn name(&self) -> String {
+        match self {
+            Sampler::Nearest => tr!("sampler-nearest"),
+            Sampler::Linear => tr!("sampler-linear"),
+            Sampler::Mipmapped => tr!("sampler-mipmapped"),
+        }
+    }
+
+    pub fn texture_options(&self) -> egui::TextureOptions {
+        match self {
+            Sampler::Nearest => egui::TextureOptions::NEAREST,
+            Sampler::Linear => egui::TextureOptions::LINEAR,
+            Sampler::Mipmapped => egui::TextureOptions {
+                mipmap_mode: Some(egui::TextureFilter::Linear),
+                ..egui::TextureOptions::LINEAR
+            },
+        }
+    }
+}
+
+/// How the picture is fitted into the video area when their shapes differ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DisplayMode {
+    /// The whole picture, with black bars where the shapes differ.
+    #[default]
+    Fit,
+    /// Covers the area, cutting off the edges of the picture instead of showing bars.
+    Fill,
+    /// Covers the area with the whole picture, distorting it.
+    Stretch,
+}
+
+impl DisplayMode {
+    pub const ALL: [DisplayMode; 3] = [DisplayMode::Fit, DisplayMode::Fill, DisplayMode::Stretch];
+
+    pub fn name(&self) -> String {
+        match self {
+            DisplayMode::Fit => tr!("display-mode-fit"),
+            DisplayMode::Fill => tr!("display-mode-fill"),
+            DisplayMode::Stretch => tr!("display-mode-stretch"),
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            DisplayMode::Fit => DisplayMode::Fill,
+            DisplayMode::Fill => DisplayMode::Stretch,
+            DisplayMode::Stretch => DisplayMode::Fit,
+        }
+    }
+
+    /// Where a picture of `size` is drawn in `area`, centered. May reach outside `area`.
+    pub fn rect(&self, area: egui::Rect, size: egui::Vec2) -> egui::Rect {
+        let scale_x = area.width() / size.x;
+        let scale_y = area.height() / size.y;
+        let display_size = match self {
+            DisplayMode::Fit => size * scale_x.min(scale_y),
+            DisplayMode::Fill => size * scale_x.max(scale_y),
+            DisplayMode::Stretch => area.size(),
+        };
+        egui::Rect::from_center_size(area.center(), display_size)
+    }
+}
+
+struct ShaderProgram {
+    program: glow::Program,
+    vertex_array: glow::VertexArray,
+}
+
+impl ShaderProgram {
+    unsafe fn paint(
+        &self,
+        gl: &glow::Context,
+        texture: glow::Texture,
+        texture_size: egui::Vec2,
+        target_size: egui::Vec2,
+        frame: u32,
+        view: [f32; 3],
+    ) {
+        gl.use_program(Some(self.program));
+        gl.bind_vertex_array(Some(self.vertex_array));
+        gl.active_texture(glow::TEXTURE0);
+        gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+
+        let location = |name: &str| gl.get_uniform_location(self.program, name);
+        gl.uniform_1_i32(location("HOOKED_raw").as_ref(), 0);
+        gl.uniform_2_f32(
+            location("HOOKED_size").as_ref(),
+            texture_size.x,
+            texture_size.y,
+        );
+        gl.uniform_2_f32(
+            location("target_size").as_ref(),
+            target_size.x,
+            target_size.y,
+        );
+        gl.uniform_1_i32(location("frame").as_ref(), frame as i32);
+        gl.uniform_1_f32(location("random").as_ref(), rand::random());
+        gl.uniform_3_f32(location("view").as_ref(), view[0], view[1], view[2]);
+
+        gl.draw_arrays(glow::TRIANGLE_STRIP, 0, 4);
+    }
+
+    unsafe fn destroy(&self, gl: &glow::Context) {
+        gl.delete_program(self.program);
+        gl.delete_vertex_array(self.vertex_array);
+    }
+}
+
+/// Compiles user shaders and draws the video texture through the active one.
+pub struct ShaderManager {
+    gl: Option<Arc<glow::Context>>,
+    active: Option<(String, Arc<ShaderProgram>)>,
+    error: Option<String>,
+    spherical: Option<Arc<ShaderProgram>>,
+    spherical_failed: bool,
+    frame: u32,
+}
+
+impl ShaderManager {
+    pub fn new() -> Self {
+        Self {
+            gl: None,
+            active: None,
+            error: None,
+            spherical: None,
+            spherical_failed: false,
+            frame: 0,
+        }
+    }
+
+    pub fn set_gl(&mut self, gl: Option<Arc<glow::Context>>) {
+        self.gl = gl;
+    }
+
+    pub fn active_path(&self) -> Option<&str> {
+        self.active.as_ref().map(|(path, _)| path.as_str())
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    pub fn load(&mut self, path: &str) {
+        self.clear();
+
+        match self.compile_file(path) {
+            Ok(program) => self.active = Some((path.to_string(), Arc::new(program))),
+            Err(e) => {
+                eprintln!("Error loading shader {}: {}", path, e);
+                self.error = Some(e);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.error = None;
+
+        if let (Some((_, program)), Some(gl)) = (self.active.take(), &self.gl) {
+            unsafe { program.destroy(gl) };
+        }
+    }
+
+    /// Releases every GL resource, including the built-in programs.
+    pub fn destroy(&mut self) {
+        self.clear();
+
+        if let (Some(program), Some(gl)) = (self.spherical.take(), &self.gl) {
+            unsafe { program.destroy(gl) };
+        }
+    }
+
+    pub fn paint_callback(
+        &mut self,
+        texture: &egui::TextureHandle,
+        rect: egui::Rect,
+    ) -> Option<egui::PaintCallback> {
+        let program = self.active.as_ref()?.1.clone();
+        Some(self.callback(program, texture, rect, [0.0; 3]))
+    }
+
+    /// Draws an equirectangular frame as seen from the center of the sphere.
+    /// `view` holds yaw, pitch and vertical field of view in radians.
+    pub fn spherical_callback(
+        &mut self,
+        texture: &egui::TextureHandle,
+        rect: egui::Rect,
+        view: [f32; 3],
+    ) -> Option<egui::PaintCallback> {
+        if self.spherical.is_none() && !self.spherical_failed {
+            let gl = self.gl.as_ref()?;
+
+            match unsafe { compile_program(gl, SPHERICAL_HOOK) } {
+                Ok(program) => self.spherical = Some(Arc::new(program)),
+                Err(e) => {
+                    eprintln!("Error compiling 360° view shader: {}", e);
+                    self.spherical_failed = true;
+                }
+            }
+        }
+
+        let program = self.spherical.clone()?;
+        Some(self.callback(program, texture, rect, view))
+    }
+
+    fn callback(
+        &mut self,
+        program: Arc<ShaderProgram>,
+        texture: &egui::TextureHandle,
+        rect: egui::Rect,
+        view: [f32; 3],
+    ) -> egui::PaintCallback {
+        let texture_id = texture.id();
+        let texture_size = texture.size_vec2();
+        self.frame = self.frame.wrapping_add(1);
+        let frame = self.frame;
+
+        egui::PaintCallback {
+            rect,
+            callback: Arc::new(egui_glow::CallbackFn::new(move |info, painter| {
+                let viewport = info.viewport_in_pixels();
+                let target_size = egui::vec2(viewport.width_px as f32, viewport.height_px as f32);
+
+                if let Some(texture) = painter.texture(texture_id) {
+                    unsafe {
+                        program.paint(
+                            painter.gl(),
+                            texture,
+                            texture_size,
+                            target_size,
+                            frame,
+                            view,
+                        );
+                    }
+                }
+            })),
+        }
+    }
+
+    fn compile_file(&self, path: &str) -> Result<ShaderProgram, String> {
+        let gl = self
+            .gl
+            .as_ref()
+            .ok_or("Custom shaders need the OpenGL renderer")?;
+
+        let is_wgsl = Path::new(path)
+            .extension()
+            .map(|ext| ext.eq_ignore_ascii_case("wgsl"))
+            .unwrap_or(false);
+        if is_wgsl {
+            return Err("WGSL shaders need the wgpu renderer; only GLSL is supported".to_string());
+        }
+
+        let source = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let hook = parse_hook(&source)?;
+
+        unsafe { compile_program(gl, &hook) }
+    }
+}
+
+/// Extracts the first pass of an mpv-style hook shader. Passes are separated by
+/// `//!HOOK` directives; the remaining `//!` lines are comments to GLSL.
+fn parse_hook(source: &str) -> Result<String, String> {
+    let passes: Vec<&str> = source
+        .split("//!HOOK")
+        .enumerate()
+        .map(|(i, pass)| {
+            if i == 0 {
+                pass
+            } else {
+                // Drop the rest of the `//!HOOK MAIN` line
+                pass.split_once('\n').map(|(_, body)| body).unwrap_or("")
+            }
+        })
+        .filter(|pass| pass.contains("hook()"))
+        .collect();
+
+    match passes.as_slice() {
+        [] => Err("Shader does not define vec4 hook()".to_string()),
+        [pass] => {
+            if pass.contains("//!SAVE") || pass.contains("//!TEXTURE") {
+                return Err("Shaders with saved textures are not supported".to_string());
+            }
+            Ok(pass.to_string())
+        }
+        _ => Err("Multi-pass shaders are not supported".to_string()),
+    }
+}
+
+unsafe fn compile_program(gl: &glow::Context, hook: &str) -> Result<ShaderProgram, String> {
+    let version = egui_glow::ShaderVersion::get(gl);
+    if !version.is_new_shader_interface() {
+        return Err("Custom shaders need OpenGL 3 or OpenGL ES 3".to_string());
+    }
+
+    let srgb_textures = gl
+        .supported_extensions()
+        .iter()
+        .any(|extension| extension.contains("sRGB"));
+
+    let vertex_source = format!("{}\n{}", version.version_declaration(), VERTEX_SHADER);
+    let fragment_source = format!(
+        "{}\n#define SRGB_TEXTURE {}\n{}\n{}\n{}",
+        version.version_declaration(),
+        srgb_textures as i32,
+        FRAGMENT_PRELUDE,
+        hook,
+        FRAGMENT_MAIN
+    );
+
+    let program = gl.create_program()?;
+    let mut shaders = Vec::new();
+
+    for (shader_type, source) in [
+        (glow::VERTEX_SHADER, vertex_source),
+        (glow::FRAGMENT_SHADER, fragment_source),
+    ] {
+        let shader = gl.create_shader(shader_type)?;
+        gl.shader_source(shader, &source);
+        gl.compile_shader(shader);
+
+        if !gl.get_shader_compile_status(shader) {
+            let log = gl.get_shader_info_log(shader);
+            gl.delete_shader(shader);
+            for shader in shaders {
+                gl.delete_shader(shader);
+            }
+            gl.delete_program(program);
+            return Err(format!("Shader compilation failed: {}", log));
+        }
+
+        gl.attach_shader(program, shader);
+        shaders.push(shader);
+    }
+
+    gl.link_program(program);
+    let linked = gl.get_program_link_status(program);
+
+    for shader in shaders {
+        gl.detach_shader(program, shader);
+        gl.delete_shader(shader);
+    }
+
+    if !linked {
+        let log = gl.get_program_info_log(program);
+        gl.delete_program(program);
+        return Err(format!("Shader linking failed: {}", log));
+    }
+
+    let vertex_array = match gl.create_vertex_array() {
+        Ok(vertex_array) => vertex_array,
+        Err(e) => {
+            gl.delete_program(program);
+            return Err(e);
+        }
+    };
+
+    Ok(ShaderProgram {
+        program,
+        vertex_array,
+    })
+}
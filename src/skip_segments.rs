@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// A named part of a file, such as an intro, that playback can skip.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SkipSegment {
+    pub name: String,
+    pub start_ms: i64,
+    pub end_ms: i64,
+    /// Skip without asking when playback reaches the segment.
+    #[serde(default)]
+    pub auto: bool,
+}
+
+impl SkipSegment {
+    pub fn contains(&self, time_ms: i64) -> bool {
+        (self.start_ms..self.end_ms).contains(&time_ms)
+    }
+}
+
+/// Skip segments of every file, keyed by path and saved next to the settings.
+#[derive(Default)]
+pub struct SkipSegmentStore {
+    files: HashMap<String, Vec<SkipSegment>>,
+}
+
+impl SkipSegmentStore {
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(content) => match serde_json::from_str(&content) {
+                Ok(files) => Self { files },
+                Err(e) => {
+                    eprintln!("Error parsing skip segments {}: {}", path.display(), e);
+                    Self::default()
+                }
+            },
+            Err(_) => Self::default(),
+        }
+    }
+
+    fn save(&self) {
+        let Some(path) = Self::path() else {
+            return;
+        };
+
+        let result = path
+            .parent()
+            .map(fs::create_dir_all)
+            .unwrap_or(Ok(()))
+            .and_then(|_| {
+                let content = serde_json::to_string_pretty(&self.files)?;
+                fs::write(&path, content)
+            });
+
+        if let Err(e) = result {
+            eprintln!("Error saving skip segments {}: {}", path.display(), e);
+        }
+    }
+
+    fn path() -> Option<PathBuf> {
+        crate::storage::config_dir().map(|dir| dir.join("skip_segments.json"))
+    }
+
+    pub fn get(&self, media_path: &str) -> &[SkipSegment] {
+        self.files
+            .get(media_path)
+            .map(|segments| segments.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Replaces the segments of a file, sorted by start time, and saves the store.
+    pub fn set(&mut self, media_path: &str, mut segments: Vec<SkipSegment>) {
+        segments.sort_by_key(|segment| segment.start_ms);
+        if segments.is_empty() {
+            self.files.remove(media_path);
+        } else {
+            self.files.insert(media_path.to_string(), segments);
+        }
+        self.save();
+    }
+}
+
+/// Reads a segment list written by `export`.
+pub fn import(path: &str) -> Result<Vec<SkipSegment>, Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(path)?;
+    let segments: Vec<SkipSegment> = serde_json::from_str(&content)?;
+    if let Some(segment) = segments
+        .iter()
+        .find(|s| s.start_ms < 0 || s.end_ms <= s.start_ms)
+    {
+        return Err(format!("Segment \"{}\" has an invalid time range", segment.name).into());
+    }
+    Ok(segments)
+}
+
+pub fn export(path: &str, segments: &[SkipSegment]) -> Result<(), Box<dyn std::error::Error>> {
+    fs::write(path, serde_json::to_string_pretty(segments)?)?;
+    Ok(())
+}
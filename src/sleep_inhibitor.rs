@@ -0,0 +1,127 @@
+use std::process::Child;
+
+/// Keeps the system awake and the screen on while it exists. Linux holds a logind
+/// inhibitor through `systemd-inhibit`, macOS an IOPMAssertion through `caffeinate`,
+/// and Windows sets the thread execution state. The helper processes watch the
+/// player's pid, so they also go away if the player crashes.
+pub struct SleepInhibitor {
+    process: Option<Child>,
+}
+
+impl SleepInhibitor {
+    #[cfg(windows)]
+    pub fn new() -> Self {
+        let previous = unsafe {
+            windows::SetThreadExecutionState(
+                windows::ES_CONTINUOUS | windows::ES_SYSTEM_REQUIRED | windows::ES_DISPLAY_REQUIRED,
+            )
+        };
+        if previous == 0 {
+            eprintln!("Error inhibiting sleep: SetThreadExecutionState failed");
+        }
+
+        Self { process: None }
+    }
+
+    #[cfg(not(windows))]
+    pub fn new() -> Self {
+        use std::process::{Command, Stdio};
+
+        let pid = std::process::id().to_string();
+        let mut command = if cfg!(target_os = "macos") {
+            let mut command = Command::new("caffeinate");
+            command.args(["-d", "-i", "-w", &pid]);
+            command
+        } else {
+            let mut command = Command::new("systemd-inhibit");
+            command.args([
+                "--what=idle:sleep",
+                "--who=Avio Player",
+                "--why=Playing video",
+                "--mode=block",
+                "tail",
+                &format!("--pid={}", pid),
+                "-f",
+                "/dev/null",
+            ]);
+            command
+        };
+
+        let process = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+
+        match process {
+            Ok(process) => Self {
+                process: Some(process),
+            },
+            Err(e) => {
+                eprintln!("Error inhibiting sleep: {}", e);
+                Self { process: None }
+            }
+        }
+    }
+}
+
+impl Drop for SleepInhibitor {
+    fn drop(&mut self) {
+        if let Some(mut process) = self.process.take() {
+            let _ = process.kill();
+            let _ = process.wait();
+        }
+
+        #[cfg(windows)]
+        unsafe {
+            windows::SetThreadExecutionState(windows::ES_CONTINUOUS);
+        }
+    }
+}
+
+/// Puts the computer to sleep: `systemctl suspend` on Linux, `pmset sleepnow` on macOS and
+/// SetSuspendState on Windows. An inhibitor still held would keep it awake.
+#[cfg(windows)]
+pub fn suspend() {
+    if unsafe { windows::SetSuspendState(0, 0, 0) } == 0 {
+        eprintln!("Error suspending: SetSuspendState failed");
+    }
+}
+
+#[cfg(not(windows))]
+pub fn suspend() {
+    use std::process::{Command, Stdio};
+
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("pmset");
+        command.arg("sleepnow");
+        command
+    } else {
+        let mut command = Command::new("systemctl");
+        command.arg("suspend");
+        command
+    };
+
+    match command.stdin(Stdio::null()).stdout(Stdio::null()).status() {
+        Ok(status) if !status.success() => eprintln!("Error suspending: {}", status),
+        Ok(_) => {}
+        Err(e) => eprintln!("Error suspending: {}", e),
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    pub const ES_CONTINUOUS: u32 = 0x8000_0000;
+    pub const ES_SYSTEM_REQUIRED: u32 = 0x0000_0001;
+    pub const ES_DISPLAY_REQUIRED: u32 = 0x0000_0002;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub fn SetThreadExecutionState(flags: u32) -> u32;
+    }
+
+    #[link(name = "powrprof")]
+    extern "system" {
+        pub fn SetSuspendState(hibernate: u8, force: u8, wake_events_disabled: u8) -> u8;
+    }
+}
@@ -0,0 +1,71 @@
+use crate::i18n::tr;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// Minutes the sleep timer can be set to.
+pub const PRESETS: [u64; 6] = [15, 30, 45, 60, 90, 120];
+/// The countdown is shown in the last minute.
+pub const COUNTDOWN: Duration = Duration::from_secs(60);
+
+/// What happens when the sleep timer runs out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SleepAction {
+    #[default]
+    Pause,
+    Quit,
+}
+
+impl SleepAction {
+    pub fn label(&self) -> String {
+        match self {
+            SleepAction::Pause => tr!("sleep-action-pause"),
+            SleepAction::Quit => tr!("sleep-action-quit"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SleepTimer {
+    /// Runs out at a set time, `minutes` after it was started.
+    At { deadline: Instant, minutes: u64 },
+    /// Runs out when the current file ends.
+    EndOfFile,
+}
+
+impl SleepTimer {
+    pub fn after_minutes(minutes: u64) -> Self {
+        Self::At {
+            deadline: Instant::now() + Duration::from_secs(minutes * 60),
+            minutes,
+        }
+    }
+
+    pub fn remaining(&self) -> Option<Duration> {
+        match self {
+            Self::At { deadline, .. } => Some(deadline.saturating_duration_since(Instant::now())),
+            Self::EndOfFile => None,
+        }
+    }
+
+    /// The next setting when cycling through them with a shortcut: the presets, then the end
+    /// of the file, then off.
+    pub fn cycle(timer: Option<Self>) -> Option<Self> {
+        match timer {
+            None => Some(Self::after_minutes(PRESETS[0])),
+            Some(Self::At { minutes, .. }) => Some(
+                PRESETS
+                    .iter()
+                    .find(|preset| **preset > minutes)
+                    .map_or(Self::EndOfFile, |preset| Self::after_minutes(*preset)),
+            ),
+            Some(Self::EndOfFile) => None,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Self::At { minutes, .. } => tr!("sleep-timer-minutes", minutes = minutes),
+            Self::EndOfFile => tr!("sleep-timer-end-of-file"),
+        }
+    }
+}
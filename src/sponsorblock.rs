@@ -0,0 +1,227 @@
+use crate::http;
+use crate::i18n::tr;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::mpsc;
+use std::thread;
+
+const API_URL: &str = "https://sponsor.ajay.app/api/skipSegments";
+const USER_AGENT: &str = concat!("Avio v", env!("CARGO_PKG_VERSION"));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum SponsorCategory {
+    Sponsor,
+    SelfPromo,
+    Interaction,
+    Intro,
+    Outro,
+    Preview,
+    MusicOfftopic,
+    Filler,
+}
+
+impl SponsorCategory {
+    pub const ALL: [SponsorCategory; 8] = [
+        SponsorCategory::Sponsor,
+        SponsorCategory::SelfPromo,
+        SponsorCategory::Interaction,
+        SponsorCategory::Intro,
+        SponsorCategory::Outro,
+        SponsorCategory::Preview,
+        SponsorCategory::MusicOfftopic,
+        SponsorCategory::Filler,
+    ];
+
+    fn api_name(&self) -> &'static str {
+        match self {
+            SponsorCategory::Sponsor => "sponsor",
+            SponsorCategory::SelfPromo => "selfpromo",
+            SponsorCategory::Interaction => "interaction",
+            SponsorCategory::Intro => "intro",
+            SponsorCategory::Outro => "outro",
+            SponsorCategory::Preview => "preview",
+            SponsorCategory::MusicOfftopic => "music_offtopic",
+            SponsorCategory::Filler => "filler",
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            SponsorCategory::Sponsor => tr!("sponsor-category-sponsor"),
+            SponsorCategory::SelfPromo => tr!("sponsor-category-selfpromo"),
+            SponsorCategory::Interaction => tr!("sponsor-category-interaction"),
+            SponsorCategory::Intro => tr!("sponsor-category-intro"),
+            SponsorCategory::Outro => tr!("sponsor-category-outro"),
+            SponsorCategory::Preview => tr!("sponsor-category-preview"),
+            SponsorCategory::MusicOfftopic => tr!("sponsor-category-music-offtopic"),
+            SponsorCategory::Filler => tr!("sponsor-category-filler"),
+        }
+    }
+
+    /// The colors the SponsorBlock browser extension uses.
+    pub fn color(&self) -> egui::Color32 {
+        match self {
+            SponsorCategory::Sponsor => egui::Color32::from_rgb(0, 212, 0),
+            SponsorCategory::SelfPromo => egui::Color32::from_rgb(255, 255, 0),
+            SponsorCategory::Interaction => egui::Color32::from_rgb(204, 0, 255),
+            SponsorCategory::Intro => egui::Color32::from_rgb(0, 255, 255),
+            SponsorCategory::Outro => egui::Color32::from_rgb(2, 2, 237),
+            SponsorCategory::Preview => egui::Color32::from_rgb(0, 143, 214),
+            SponsorCategory::MusicOfftopic => egui::Color32::from_rgb(255, 153, 0),
+            SponsorCategory::Filler => egui::Color32::from_rgb(115, 0, 255),
+        }
+    }
+
+    fn from_api_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|category| category.api_name() == name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SponsorAction {
+    Ignore,
+    /// Shown on the seek bar with a button to skip it.
+    Mark,
+    Skip,
+}
+
+impl SponsorAction {
+    pub const ALL: [SponsorAction; 3] = [
+        SponsorAction::Ignore,
+        SponsorAction::Mark,
+        SponsorAction::Skip,
+    ];
+
+    pub fn name(&self) -> String {
+        match self {
+            SponsorAction::Ignore => tr!("sponsor-action-ignore"),
+            SponsorAction::Mark => tr!("sponsor-action-mark"),
+            SponsorAction::Skip => tr!("sponsor-action-skip"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SponsorBlockSettings {
+    pub enabled: bool,
+    pub categories: BTreeMap<SponsorCategory, SponsorAction>,
+}
+
+impl Default for SponsorBlockSettings {
+    fn default() -> Self {
+        let categories = SponsorCategory::ALL
+            .into_iter()
+            .map(|category| {
+                let action = match category {
+                    SponsorCategory::Sponsor => SponsorAction::Skip,
+                    SponsorCategory::MusicOfftopic => SponsorAction::Ignore,
+                    _ => SponsorAction::Mark,
+                };
+                (category, action)
+            })
+            .collect();
+
+        Self {
+            enabled: true,
+            categories,
+        }
+    }
+}
+
+impl SponsorBlockSettings {
+    pub fn action(&self, category: SponsorCategory) -> SponsorAction {
+        self.categories
+            .get(&category)
+            .copied()
+            .unwrap_or(SponsorAction::Ignore)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SponsorSegment {
+    pub category: SponsorCategory,
+    pub start_ms: i64,
+    pub end_ms: i64,
+}
+
+#[derive(Deserialize)]
+struct ApiSegment {
+    segment: [f64; 2],
+    category: String,
+    #[serde(rename = "actionType")]
+    action_type: String,
+}
+
+/// Looks up the community-submitted segments of a YouTube video, in all categories.
+pub fn fetch(video_id: &str) -> Result<Vec<SponsorSegment>, Box<dyn std::error::Error>> {
+    let categories: Vec<&str> = SponsorCategory::ALL
+        .iter()
+        .map(|category| category.api_name())
+        .collect();
+
+    let response = http::agent(API_URL)?
+        .get(API_URL)
+        .set("User-Agent", USER_AGENT)
+        .query("videoID", video_id)
+        .query("categories", &serde_json::to_string(&categories)?)
+        .call();
+
+    let segments: Vec<ApiSegment> = match response {
+        Ok(response) => response.into_json()?,
+        // Videos nobody has submitted segments for
+        Err(ureq::Error::Status(404, _)) => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok(segments
+        .into_iter()
+        .filter(|segment| segment.action_type == "skip")
+        .filter_map(|segment| {
+            Some(SponsorSegment {
+                category: SponsorCategory::from_api_name(&segment.category)?,
+                start_ms: (segment.segment[0] * 1000.0) as i64,
+                end_ms: (segment.segment[1] * 1000.0) as i64,
+            })
+        })
+        .filter(|segment| segment.end_ms > segment.start_ms)
+        .collect())
+}
+
+/// Fetches the segments of a video on a worker thread.
+pub struct SponsorLookup {
+    receiver: mpsc::Receiver<Result<Vec<SponsorSegment>, String>>,
+    segments: Vec<SponsorSegment>,
+}
+
+impl SponsorLookup {
+    pub fn start(video_id: &str) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let video_id = video_id.to_string();
+
+        thread::spawn(move || {
+            let _ = sender.send(fetch(&video_id).map_err(|e| e.to_string()));
+        });
+
+        Self {
+            receiver,
+            segments: Vec::new(),
+        }
+    }
+
+    /// Returns the number of segments once the lookup has finished.
+    pub fn update(&mut self) -> Option<Result<usize, String>> {
+        let result = self.receiver.try_recv().ok()?;
+        Some(result.map(|segments| {
+            self.segments = segments;
+            self.segments.len()
+        }))
+    }
+
+    pub fn segments(&self) -> &[SponsorSegment] {
+        &self.segments
+    }
+}
@@ -0,0 +1,169 @@
+use crate::storage;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// How often the frames shown since the last summary are summed up.
+const SUMMARY_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum Entry<'a> {
+    Session {
+        version: &'static str,
+        os: &'static str,
+    },
+    File {
+        path: &'a str,
+        duration_ms: i64,
+    },
+    Summary {
+        position_ms: i64,
+        frames: u32,
+        /// Frames shown at least a frame interval after they were due, which other players
+        /// would have dropped.
+        late_frames: u32,
+        decode_ms_mean: f64,
+        decode_ms_max: f64,
+        /// Times the audio output waited for the decoder.
+        audio_underruns: usize,
+    },
+    SyncCorrection {
+        position_ms: i64,
+        /// How far the audio was behind the video when it was moved to it.
+        offset_ms: i64,
+    },
+}
+
+#[derive(Serialize)]
+struct Line<'a> {
+    /// Milliseconds since the Unix epoch.
+    time: u64,
+    #[serde(flatten)]
+    entry: Entry<'a>,
+}
+
+/// Writes what happened during playback to a JSON lines file, one per session, to attach
+/// to performance reports.
+pub struct StatsLog {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    frames: u32,
+    late_frames: u32,
+    decode_time: Duration,
+    decode_max: Duration,
+    /// Underruns of the audio output already counted; each new output starts from 0.
+    underruns_seen: usize,
+    underruns: usize,
+    position_ms: i64,
+    last_summary: Instant,
+}
+
+impl StatsLog {
+    /// Opens the log of this session, adding to it if logging was turned off and on again.
+    pub fn open() -> io::Result<Self> {
+        let path = storage::stats_log_path()?;
+        let file = File::options().create(true).append(true).open(&path)?;
+        let mut log = Self {
+            path,
+            writer: BufWriter::new(file),
+            frames: 0,
+            late_frames: 0,
+            decode_time: Duration::ZERO,
+            decode_max: Duration::ZERO,
+            underruns_seen: 0,
+            underruns: 0,
+            position_ms: 0,
+            last_summary: Instant::now(),
+        };
+        log.write(Entry::Session {
+            version: env!("CARGO_PKG_VERSION"),
+            os: std::env::consts::OS,
+        });
+        println!("Logging playback statistics to {}", log.path.display());
+        Ok(log)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn file_opened(&mut self, path: &str, duration_ms: i64) {
+        self.write_summary();
+        self.underruns_seen = 0;
+        self.write(Entry::File { path, duration_ms });
+    }
+
+    pub fn frame(&mut self, decode_time: Duration, late: bool) {
+        self.frames += 1;
+        self.late_frames += late as u32;
+        self.decode_time += decode_time;
+        self.decode_max = self.decode_max.max(decode_time);
+    }
+
+    pub fn sync_correction(&mut self, position_ms: i64, offset_ms: i64) {
+        self.write(Entry::SyncCorrection {
+            position_ms,
+            offset_ms,
+        });
+    }
+
+    /// Takes the underrun count of the audio output and writes a summary when it is due.
+    pub fn update(&mut self, position_ms: i64, underruns: usize) {
+        // A lower count is a new output, e.g. after changing the channels
+        if underruns < self.underruns_seen {
+            self.underruns_seen = 0;
+        }
+        self.underruns += underruns - self.underruns_seen;
+        self.underruns_seen = underruns;
+        self.position_ms = position_ms;
+
+        if self.last_summary.elapsed() >= SUMMARY_INTERVAL {
+            self.write_summary();
+        }
+    }
+
+    /// Sums up the frames since the last summary, if anything happened.
+    fn write_summary(&mut self) {
+        self.last_summary = Instant::now();
+        if self.frames == 0 && self.underruns == 0 {
+            return;
+        }
+
+        let decode_ms_mean = self.decode_time.as_secs_f64() * 1000.0 / self.frames.max(1) as f64;
+        self.write(Entry::Summary {
+            position_ms: self.position_ms,
+            frames: self.frames,
+            late_frames: self.late_frames,
+            decode_ms_mean: (decode_ms_mean * 100.0).round() / 100.0,
+            decode_ms_max: (self.decode_max.as_secs_f64() * 100_000.0).round() / 100.0,
+            audio_underruns: self.underruns,
+        });
+        self.frames = 0;
+        self.late_frames = 0;
+        self.decode_time = Duration::ZERO;
+        self.decode_max = Duration::ZERO;
+        self.underruns = 0;
+    }
+
+    fn write(&mut self, entry: Entry) {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as u64);
+        let result = serde_json::to_writer(&mut self.writer, &Line { time, entry })
+            .map_err(io::Error::from)
+            .and_then(|()| self.writer.write_all(b"\n"))
+            .and_then(|()| self.writer.flush());
+        if let Err(e) = result {
+            eprintln!("Error writing statistics log: {}", e);
+        }
+    }
+}
+
+impl Drop for StatsLog {
+    fn drop(&mut self) {
+        self.write_summary();
+    }
+}
@@ -0,0 +1,205 @@
+use crate::i18n::tr;
+use directories::ProjectDirs;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_CACHE_LIMIT_MB: u32 = 1024;
+
+fn project_dirs() -> Option<ProjectDirs> {
+    ProjectDirs::from("", "", "Avio")
+}
+
+/// Settings, skip segments and user catalogs.
+pub fn config_dir() -> Option<PathBuf> {
+    project_dirs().map(|dirs| dirs.config_dir().to_path_buf())
+}
+
+/// Files that can be thrown away at any time, in one folder per [`CacheKind`].
+pub fn cache_dir() -> Option<PathBuf> {
+    project_dirs().map(|dirs| dirs.cache_dir().to_path_buf())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheKind {
+    /// Subtitles downloaded for streams and read-only folders.
+    Subtitles,
+    /// Live streams buffered on disk.
+    Timeshift,
+    /// Playback statistics, when logging them is turned on.
+    StatsLogs,
+}
+
+impl CacheKind {
+    pub const ALL: [CacheKind; 3] = [
+        CacheKind::Subtitles,
+        CacheKind::Timeshift,
+        CacheKind::StatsLogs,
+    ];
+
+    fn folder(&self) -> &'static str {
+        match self {
+            CacheKind::Subtitles => "subtitles",
+            CacheKind::Timeshift => "timeshift",
+            CacheKind::StatsLogs => "stats",
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            CacheKind::Subtitles => tr!("cache-subtitles"),
+            CacheKind::Timeshift => tr!("cache-timeshift"),
+            CacheKind::StatsLogs => tr!("cache-stats-logs"),
+        }
+    }
+
+    /// The folder for this kind, created if needed.
+    pub fn dir(&self) -> io::Result<PathBuf> {
+        let dir = cache_dir()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No cache directory"))?
+            .join(self.folder());
+        fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+}
+
+/// Where this process buffers a live stream.
+pub fn timeshift_path() -> io::Result<PathBuf> {
+    Ok(CacheKind::Timeshift
+        .dir()?
+        .join(format!("timeshift-{}.ts", std::process::id())))
+}
+
+/// Where this process logs playback statistics, named after when it started.
+pub fn stats_log_path() -> io::Result<PathBuf> {
+    static STARTED: OnceLock<u64> = OnceLock::new();
+    let started = STARTED.get_or_init(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs())
+    });
+    Ok(CacheKind::StatsLogs
+        .dir()?
+        .join(format!("stats-{}-{}.jsonl", started, std::process::id())))
+}
+
+struct CacheFile {
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<CacheFile>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            collect_files(&path, files);
+        } else {
+            files.push(CacheFile {
+                path,
+                size: metadata.len(),
+                modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            });
+        }
+    }
+}
+
+fn cache_files() -> Vec<CacheFile> {
+    let mut files = Vec::new();
+    if let Some(dir) = cache_dir() {
+        collect_files(&dir, &mut files);
+    }
+    files
+}
+
+/// Bytes on disk per kind of cached file, and in total including anything else there.
+pub struct CacheUsage {
+    pub kinds: Vec<(CacheKind, u64)>,
+    pub total: u64,
+}
+
+pub fn cache_usage() -> CacheUsage {
+    let files = cache_files();
+    let dir = cache_dir().unwrap_or_default();
+    let kinds = CacheKind::ALL
+        .iter()
+        .map(|kind| {
+            let folder = dir.join(kind.folder());
+            let size = (files.iter())
+                .filter(|file| file.path.starts_with(&folder))
+                .map(|file| file.size)
+                .sum();
+            (*kind, size)
+        })
+        .collect();
+
+    CacheUsage {
+        kinds,
+        total: files.iter().map(|file| file.size).sum(),
+    }
+}
+
+/// Deletes every cached file that isn't in use. Returns how many bytes were freed.
+pub fn clear_cache() -> u64 {
+    remove_files(cache_files(), u64::MAX)
+}
+
+/// Deletes the oldest cached files until the cache takes at most `limit_mb`; 0 is no limit.
+pub fn trim_cache(limit_mb: u32) {
+    if limit_mb == 0 {
+        return;
+    }
+    let mut files = cache_files();
+    let total: u64 = files.iter().map(|file| file.size).sum();
+    let limit = limit_mb as u64 * 1024 * 1024;
+    if total <= limit {
+        return;
+    }
+
+    files.sort_by_key(|file| file.modified);
+    let freed = remove_files(files, total - limit);
+    println!("Trimmed the cache by {}", format_size(freed));
+}
+
+/// Removes files in order until at least `amount` bytes are freed. The timeshift buffer
+/// and statistics log being written are left alone.
+fn remove_files(files: Vec<CacheFile>, amount: u64) -> u64 {
+    let in_use = [timeshift_path().ok(), stats_log_path().ok()];
+    let mut freed = 0;
+    for file in files {
+        if freed >= amount {
+            break;
+        }
+        if in_use.iter().flatten().any(|path| *path == file.path) {
+            continue;
+        }
+        match fs::remove_file(&file.path) {
+            Ok(()) => freed += file.size,
+            Err(e) => eprintln!("Error deleting {}: {}", file.path.display(), e),
+        }
+    }
+    freed
+}
+
+/// A byte count for display, e.g. "12.3 MB".
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
@@ -0,0 +1,258 @@
+use crate::bitmap_subtitles::BitmapCue;
+use crate::http;
+use crate::i18n::tr;
+use crate::subtitles::{SubtitleCue, Subtitles};
+use eframe::egui;
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+
+/// How long the last cue stays up when the stream never ends it.
+const LAST_CUE_MS: i64 = 5000;
+/// White margin around the text, which Tesseract needs to find the first and last letters.
+const PADDING: u32 = 16;
+
+enum OcrEvent {
+    Progress(i64),
+    Cue(SubtitleCue),
+    Finished(Result<(), String>),
+}
+
+/// Puts the images of a cue on one white page with the text in black. Subtitles are light
+/// letters with a dark outline, so only the bright, opaque pixels are kept.
+fn render_cue(cue: &BitmapCue) -> Option<image::GrayImage> {
+    let left = cue.rects.iter().map(|rect| rect.x).min()?;
+    let top = cue.rects.iter().map(|rect| rect.y).min()?;
+    let right = cue.rects.iter().map(|rect| rect.x + rect.width).max()?;
+    let bottom = cue.rects.iter().map(|rect| rect.y + rect.height).max()?;
+
+    let mut page = image::GrayImage::from_pixel(
+        (right - left) as u32 + PADDING * 2,
+        (bottom - top) as u32 + PADDING * 2,
+        image::Luma([255]),
+    );
+    for rect in &cue.rects {
+        for (index, pixel) in rect.pixels.chunks_exact(4).enumerate() {
+            let luma = 0.299 * pixel[0] as f32 + 0.587 * pixel[1] as f32 + 0.114 * pixel[2] as f32;
+            let ink = luma * pixel[3] as f32 / 255.0;
+            let x = (rect.x - left + index % rect.width) as u32 + PADDING;
+            let y = (rect.y - top + index / rect.width) as u32 + PADDING;
+            page.put_pixel(x, y, image::Luma([255 - ink as u8]));
+        }
+    }
+    Some(page)
+}
+
+/// Runs `tesseract` on one page. Blocks until it exits.
+fn recognize(
+    page: &image::GrayImage,
+    page_path: &Path,
+    language: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    page.save(page_path)?;
+
+    // Page segmentation mode 6 reads a single block of text, which a subtitle is
+    let output = Command::new("tesseract")
+        .arg(page_path)
+        .args(["stdout", "--psm", "6", "-l", language])
+        .output()
+        .map_err(|e| format!("Cannot run tesseract (is it installed?): {}", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = stderr
+            .lines()
+            .find(|line| !line.trim().is_empty())
+            .unwrap_or("tesseract failed");
+        return Err(message.to_string().into());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+/// Decodes every cue of the stream in order.
+fn read_cues(
+    filename: &str,
+    stream_index: usize,
+    cancel: &AtomicBool,
+    on_cue: &mut dyn FnMut(BitmapCue) -> Result<(), Box<dyn std::error::Error>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut input = http::open_input(filename)?;
+    let stream = input
+        .stream(stream_index)
+        .ok_or_else(|| format!("No stream {}", stream_index))?;
+    let mut track = crate::bitmap_subtitles::BitmapSubtitleTrack::new(&stream, 0, 0)?;
+
+    for (stream, packet) in input.packets() {
+        if cancel.load(Ordering::Relaxed) {
+            return Err("Cancelled".into());
+        }
+        if stream.index() != stream_index {
+            continue;
+        }
+        if let Err(e) = track.decode(&packet) {
+            eprintln!("Error decoding subtitle packet for OCR: {}", e);
+            continue;
+        }
+        for cue in track.take_cues() {
+            on_cue(cue)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Recognizes the text of every cue. A cue without an end lasts until the next one starts,
+/// which for PGS is usually an empty cue that clears the screen.
+fn convert(
+    filename: &str,
+    stream_index: usize,
+    language: &str,
+    sender: &mpsc::Sender<OcrEvent>,
+    cancel: &AtomicBool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let page_path = std::env::temp_dir().join(format!("avio-ocr-{}.png", std::process::id()));
+    let mut pending: Option<SubtitleCue> = None;
+
+    let result = read_cues(filename, stream_index, cancel, &mut |cue| {
+        let _ = sender.send(OcrEvent::Progress(cue.start_ms));
+        if let Some(mut previous) = pending.take() {
+            if previous.end_ms <= previous.start_ms || previous.end_ms > cue.start_ms {
+                previous.end_ms = cue.start_ms;
+            }
+            let _ = sender.send(OcrEvent::Cue(previous));
+        }
+
+        let Some(page) = render_cue(&cue) else {
+            return Ok(());
+        };
+        let text = recognize(&page, &page_path, language)?;
+        if !text.is_empty() {
+            pending = Some(SubtitleCue {
+                start_ms: cue.start_ms,
+                end_ms: cue.end_ms.unwrap_or(cue.start_ms),
+                text,
+            });
+        }
+        Ok(())
+    });
+    let _ = std::fs::remove_file(&page_path);
+    result?;
+
+    if let Some(mut last) = pending {
+        if last.end_ms <= last.start_ms {
+            last.end_ms = last.start_ms + LAST_CUE_MS;
+        }
+        let _ = sender.send(OcrEvent::Cue(last));
+    }
+    Ok(())
+}
+
+/// Converts a PGS or VobSub stream to text on a worker thread. Dropping it stops the
+/// conversion.
+pub struct SubtitleOcr {
+    receiver: mpsc::Receiver<OcrEvent>,
+    cancel: Arc<AtomicBool>,
+    stream_index: usize,
+    duration_ms: i64,
+    position_ms: i64,
+    cues: Vec<SubtitleCue>,
+    result: Option<Result<(), String>>,
+}
+
+impl SubtitleOcr {
+    pub fn start(filename: &str, stream_index: usize, duration_ms: i64, language: &str) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancel_flag = cancel.clone();
+        let filename = filename.to_string();
+        let language = language.to_string();
+
+        thread::spawn(move || {
+            let result = convert(&filename, stream_index, &language, &sender, &cancel_flag);
+            let _ = sender.send(OcrEvent::Finished(result.map_err(|e| e.to_string())));
+        });
+
+        Self {
+            receiver,
+            cancel,
+            stream_index,
+            duration_ms,
+            position_ms: 0,
+            cues: Vec::new(),
+            result: None,
+        }
+    }
+
+    pub fn stream_index(&self) -> usize {
+        self.stream_index
+    }
+
+    /// Returns true while the conversion is still running.
+    pub fn update(&mut self) -> bool {
+        for event in self.receiver.try_iter() {
+            match event {
+                OcrEvent::Progress(position_ms) => self.position_ms = position_ms,
+                OcrEvent::Cue(cue) => self.cues.push(cue),
+                OcrEvent::Finished(result) => self.result = Some(result),
+            }
+        }
+        self.result.is_none()
+    }
+
+    /// The recognized cues as a text subtitle track, once the whole stream has been read.
+    pub fn subtitles(&self) -> Option<Subtitles> {
+        if !matches!(self.result, Some(Ok(()))) || self.cues.is_empty() {
+            return None;
+        }
+        Some(Subtitles::from_cues(self.cues.clone()))
+    }
+
+    /// Draws progress and the last recognized lines.
+    pub fn show(&self, ui: &mut egui::Ui) {
+        match &self.result {
+            None => {
+                let progress = if self.duration_ms > 0 {
+                    self.position_ms as f32 / self.duration_ms as f32
+                } else {
+                    0.0
+                };
+                ui.label(tr!("ocr-running", count = self.cues.len()));
+                ui.add(egui::ProgressBar::new(progress.clamp(0.0, 1.0)).show_percentage());
+            }
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::LIGHT_RED, tr!("ocr-failed", error = e));
+            }
+            Some(Ok(())) => {
+                ui.label(tr!("ocr-finished", count = self.cues.len()));
+            }
+        }
+        ui.add_space(4.0);
+
+        egui::ScrollArea::vertical()
+            .max_height(240.0)
+            .auto_shrink([false, true])
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                for cue in &self.cues {
+                    ui.horizontal(|ui| {
+                        ui.monospace(crate::VideoPlayer::format_time(cue.start_ms));
+                        ui.label(&cue.text);
+                    });
+                }
+            });
+    }
+}
+
+impl Drop for SubtitleOcr {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,257 @@
+use crate::{bidi, fonts};
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::fs;
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub enum SubtitleFont {
+    #[default]
+    Proportional,
+    Monospace,
+    /// Path to a TTF/OTF file, see [`fonts::apply`].
+    File(String),
+}
+
+/// Overrides for how subtitles are drawn. Styling from the subtitle file itself
+/// (`<font>`, `<i>`, ASS override tags) is always dropped, so these apply to every cue.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubtitleStyle {
+    pub font: SubtitleFont,
+    /// Text height as a fraction of the video height.
+    pub size: f32,
+    pub color: [u8; 3],
+    /// Outline width in points; 0 disables the outline.
+    pub outline_width: f32,
+    pub outline_color: [u8; 3],
+    pub background: bool,
+    pub background_opacity: u8,
+    /// Distance of the bottom line from the bottom of the video, as a fraction of its height.
+    pub bottom_margin: f32,
+    /// Font files for characters the subtitle font lacks, tried in order.
+    pub fallback_fonts: Vec<String>,
+    /// Falls back to fonts of the system for CJK, Hebrew and Arabic text.
+    pub system_fallback_fonts: bool,
+}
+
+impl Default for SubtitleStyle {
+    fn default() -> Self {
+        Self {
+            font: SubtitleFont::Proportional,
+            size: 0.045,
+            color: [255, 255, 255],
+            outline_width: 0.0,
+            outline_color: [0, 0, 0],
+            background: true,
+            background_opacity: 160,
+            bottom_margin: 0.05,
+            fallback_fonts: Vec::new(),
+            system_fallback_fonts: true,
+        }
+    }
+}
+
+impl SubtitleStyle {
+    fn font_family(&self, ctx: &egui::Context) -> egui::FontFamily {
+        let family = egui::FontFamily::Name(fonts::SUBTITLE_FAMILY.into());
+        // Laying out text with a family egui doesn't know yet panics
+        if ctx.fonts(|fonts| fonts.families().contains(&family)) {
+            family
+        } else if self.font == SubtitleFont::Monospace {
+            egui::FontFamily::Monospace
+        } else {
+            egui::FontFamily::Proportional
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct SubtitleCue {
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub text: String,
+}
+
+pub struct Subtitles {
+    cues: Vec<SubtitleCue>,
+}
+
+impl Subtitles {
+    pub fn load(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let bytes = fs::read(path)?;
+        let subtitles = Self::parse(&String::from_utf8_lossy(&bytes));
+
+        if subtitles.cues.is_empty() {
+            return Err(format!("No subtitle cues found in {}", path).into());
+        }
+
+        Ok(subtitles)
+    }
+
+    /// Parses SubRip (.srt) and WebVTT (.vtt) content; both use `start --> end` timing lines.
+    pub fn parse(content: &str) -> Self {
+        let content = content.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+        let mut cues = Vec::new();
+
+        for block in content.split("\n\n") {
+            let mut lines = block.lines().skip_while(|line| !line.contains("-->"));
+
+            let (start_ms, end_ms) = match lines.next().and_then(parse_timing) {
+                Some(timing) => timing,
+                None => continue,
+            };
+
+            let text = lines
+                .map(strip_tags)
+                .filter(|line| !line.trim().is_empty())
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            if !text.is_empty() {
+                cues.push(SubtitleCue {
+                    start_ms,
+                    end_ms,
+                    text,
+                });
+            }
+        }
+
+        Self::from_cues(cues)
+    }
+
+    pub fn from_cues(mut cues: Vec<SubtitleCue>) -> Self {
+        cues.sort_by_key(|cue| cue.start_ms);
+        Self { cues }
+    }
+
+    /// The cues as SubRip, with the text as it is shown.
+    pub fn to_srt(&self) -> String {
+        let mut srt = String::new();
+        for (index, cue) in self.cues.iter().enumerate() {
+            let _ = write!(
+                srt,
+                "{}\n{} --> {}\n{}\n\n",
+                index + 1,
+                srt_timestamp(cue.start_ms),
+                srt_timestamp(cue.end_ms),
+                cue.text
+            );
+        }
+        srt
+    }
+
+    pub fn text_at(&self, time_ms: i64) -> Option<String> {
+        let active: Vec<&str> = self
+            .cues
+            .iter()
+            .take_while(|cue| cue.start_ms <= time_ms)
+            .filter(|cue| time_ms < cue.end_ms)
+            .map(|cue| cue.text.as_str())
+            .collect();
+
+        if active.is_empty() {
+            None
+        } else {
+            Some(active.join("\n"))
+        }
+    }
+}
+
+fn srt_timestamp(ms: i64) -> String {
+    format!(
+        "{:02}:{:02}:{:02},{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
+
+fn parse_timing(line: &str) -> Option<(i64, i64)> {
+    let (start, end) = line.split_once("-->")?;
+    // WebVTT cue settings follow the end timestamp
+    let end = end.split_whitespace().next()?;
+
+    Some((parse_timestamp(start)?, parse_timestamp(end)?))
+}
+
+fn parse_timestamp(input: &str) -> Option<i64> {
+    let input = input.trim().replace(',', ".");
+    let parts: Vec<&str> = input.split(':').collect();
+
+    let (hours, minutes, seconds) = match parts.as_slice() {
+        [hours, minutes, seconds] => (hours.parse::<i64>().ok()?, minutes, seconds),
+        [minutes, seconds] => (0, minutes, seconds),
+        _ => return None,
+    };
+    let minutes: i64 = minutes.parse().ok()?;
+    let seconds: f64 = seconds.parse().ok()?;
+
+    Some((hours * 3600 + minutes * 60) * 1000 + (seconds * 1000.0).round() as i64)
+}
+
+/// Drops HTML-style (`<i>`) and ASS override (`{\an8}`) tags, which are not rendered.
+fn strip_tags(line: &str) -> String {
+    let mut text = String::with_capacity(line.len());
+    let mut closing = None;
+
+    for c in line.chars() {
+        match (closing, c) {
+            (None, '<') => closing = Some('>'),
+            (None, '{') => closing = Some('}'),
+            (None, _) => text.push(c),
+            (Some(end), _) if c == end => closing = None,
+            _ => {}
+        }
+    }
+
+    text
+}
+
+pub fn draw(painter: &egui::Painter, video_rect: egui::Rect, text: &str, style: &SubtitleStyle) {
+    let font_size = (video_rect.height() * style.size).max(10.0);
+    let color = rgb(style.color);
+    let text = text
+        .lines()
+        .map(bidi::visual_line)
+        .collect::<Vec<_>>()
+        .join("\n");
+    let mut job = egui::text::LayoutJob::simple(
+        text,
+        egui::FontId::new(font_size, style.font_family(painter.ctx())),
+        color,
+        video_rect.width() * 0.9,
+    );
+    job.halign = egui::Align::Center;
+
+    let galley = painter.layout_job(job);
+    let pos = egui::pos2(
+        video_rect.center().x,
+        video_rect.bottom() - video_rect.height() * style.bottom_margin - galley.rect.height(),
+    );
+
+    if style.background {
+        painter.rect_filled(
+            galley.rect.translate(pos.to_vec2()).expand(6.0),
+            egui::Rounding::same(4.0),
+            egui::Color32::from_black_alpha(style.background_opacity),
+        );
+    }
+
+    if style.outline_width > 0.0 {
+        let outline_color = rgb(style.outline_color);
+        // Eight offset copies approximate a stroke around the glyphs
+        for i in 0..8 {
+            let angle = i as f32 * std::f32::consts::FRAC_PI_4;
+            let offset = egui::vec2(angle.cos(), angle.sin()) * style.outline_width;
+            painter.galley_with_override_text_color(pos + offset, galley.clone(), outline_color);
+        }
+    }
+
+    painter.galley(pos, galley, color);
+}
+
+fn rgb(color: [u8; 3]) -> egui::Color32 {
+    egui::Color32::from_rgb(color[0], color[1], color[2])
+}
@@ -0,0 +1,240 @@
+use crate::audio;
+use crate::i18n::tr;
+use crate::video::Video;
+use eframe::egui;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+
+/// Resolution of the event curves that are lined up.
+const BIN_MS: i64 = 10;
+/// Only the start of the file is analyzed, which is where slates and claps are.
+const ANALYZED_MS: i64 = 5 * 60 * 1000;
+/// The largest offset looked for, either way.
+const MAX_OFFSET_MS: i64 = 2000;
+/// How far the best match has to stand out from the others, in standard deviations, to be
+/// trusted.
+const MIN_CONFIDENCE: f32 = 4.0;
+
+pub struct SyncEstimate {
+    /// How much later the audio should play to match the picture.
+    pub delay_ms: i64,
+    pub confidence: f32,
+}
+
+impl SyncEstimate {
+    pub fn is_reliable(&self) -> bool {
+        self.confidence >= MIN_CONFIDENCE
+    }
+}
+
+enum AnalysisEvent {
+    Progress(i64),
+    Finished(Result<SyncEstimate, String>),
+}
+
+/// Rises where something happens, without the steady level: changes in brightness for the
+/// picture (flashes, cuts) and rises in loudness for the sound (claps, hits, speech onsets).
+fn onsets(values: &[f32]) -> Vec<f32> {
+    let mut onsets: Vec<f32> = values
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]).max(0.0))
+        .collect();
+    normalize(&mut onsets);
+    onsets
+}
+
+fn normalize(values: &mut [f32]) {
+    let count = values.len().max(1) as f32;
+    let mean = values.iter().sum::<f32>() / count;
+    let variance = values
+        .iter()
+        .map(|value| (value - mean).powi(2))
+        .sum::<f32>()
+        / count;
+    let deviation = variance.sqrt().max(1e-6);
+    for value in values {
+        *value = (*value - mean) / deviation;
+    }
+}
+
+/// Finds the shift of the sound's events that lines them up best with the picture's, by
+/// cross-correlating the two onset curves.
+fn align(picture: &[f32], sound: &[f32]) -> Option<SyncEstimate> {
+    let max_lag = (MAX_OFFSET_MS / BIN_MS) as isize;
+    let length = picture.len().min(sound.len()) as isize;
+    if length <= max_lag * 2 {
+        return None;
+    }
+
+    let scores: Vec<(isize, f32)> = (-max_lag..=max_lag)
+        .map(|lag| {
+            let range = (-lag).max(0)..(length - lag.max(0));
+            let score = range
+                .clone()
+                .map(|index| picture[index as usize] * sound[(index + lag) as usize])
+                .sum::<f32>()
+                / range.len().max(1) as f32;
+            (lag, score)
+        })
+        .collect();
+
+    let (best_lag, best_score) = scores.iter().copied().max_by(|a, b| a.1.total_cmp(&b.1))?;
+    let mut values: Vec<f32> = scores.iter().map(|(_, score)| *score).collect();
+    let mean = values.iter().sum::<f32>() / values.len() as f32;
+    normalize(&mut values);
+    let deviation = scores
+        .iter()
+        .zip(&values)
+        .find(|((lag, _), _)| *lag == best_lag)
+        .map_or(0.0, |(_, value)| *value);
+
+    // Sound events that come `lag` bins after the picture's need the audio to play earlier
+    Some(SyncEstimate {
+        delay_ms: -(best_lag as i64) * BIN_MS,
+        confidence: if best_score > mean { deviation } else { 0.0 },
+    })
+}
+
+fn analyze(
+    filename: &str,
+    sender: &mpsc::Sender<AnalysisEvent>,
+    cancel: &AtomicBool,
+) -> Result<SyncEstimate, Box<dyn std::error::Error>> {
+    let mut video = Video::new(filename)?;
+    let mut brightness: Vec<f32> = Vec::new();
+
+    while let Some(frame) = video.next_frame() {
+        if cancel.load(Ordering::Relaxed) {
+            return Err("Cancelled".into());
+        }
+        let frame = match frame {
+            Ok(frame) => frame,
+            Err(e) => {
+                eprintln!("Error decoding frame for sync analysis: {}", e);
+                continue;
+            }
+        };
+        if frame.timestamp_ms >= ANALYZED_MS {
+            break;
+        }
+
+        // Every fourth pixel in both directions is plenty for the mean
+        let mut sum = 0.0;
+        let mut count = 0;
+        for y in (0..frame.height).step_by(4) {
+            for x in (0..frame.width).step_by(4) {
+                let offset = (y * frame.width + x) * 4;
+                sum += 0.2126 * frame.buffer[offset] as f32
+                    + 0.7152 * frame.buffer[offset + 1] as f32
+                    + 0.0722 * frame.buffer[offset + 2] as f32;
+                count += 1;
+            }
+        }
+
+        // Each frame holds until the next one
+        let bin = (frame.timestamp_ms.max(0) / BIN_MS) as usize;
+        let level = sum / count.max(1) as f32 / 255.0;
+        let last = brightness.last().copied().unwrap_or(level);
+        brightness.resize(bin.max(brightness.len()), last);
+        brightness.push(level);
+
+        let _ = sender.send(AnalysisEvent::Progress(frame.timestamp_ms / 2));
+    }
+
+    let levels = audio::level_envelope(filename, BIN_MS, ANALYZED_MS, cancel)?;
+    if cancel.load(Ordering::Relaxed) {
+        return Err("Cancelled".into());
+    }
+    let _ = sender.send(AnalysisEvent::Progress(ANALYZED_MS));
+
+    // Loudness is heard on a log scale, so a clap in a quiet room and one over music count
+    let loudness: Vec<f32> = levels.iter().map(|level| (level + 1e-4).ln()).collect();
+
+    align(&onsets(&brightness), &onsets(&loudness)).ok_or_else(|| tr!("sync-too-short").into())
+}
+
+/// Estimates how far the sound of a file is off from its picture, on a worker thread.
+/// Dropping it stops the analysis.
+pub struct SyncAnalysis {
+    receiver: mpsc::Receiver<AnalysisEvent>,
+    cancel: Arc<AtomicBool>,
+    analyzed_ms: i64,
+    position_ms: i64,
+    result: Option<Result<SyncEstimate, String>>,
+}
+
+impl SyncAnalysis {
+    pub fn start(filename: &str, duration_ms: i64) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancel_flag = cancel.clone();
+        let filename = filename.to_string();
+
+        thread::spawn(move || {
+            let result = analyze(&filename, &sender, &cancel_flag);
+            let _ = sender.send(AnalysisEvent::Finished(result.map_err(|e| e.to_string())));
+        });
+
+        Self {
+            receiver,
+            cancel,
+            analyzed_ms: duration_ms.clamp(1, ANALYZED_MS),
+            position_ms: 0,
+            result: None,
+        }
+    }
+
+    /// Returns true while the analysis is still running.
+    pub fn update(&mut self) -> bool {
+        for event in self.receiver.try_iter() {
+            match event {
+                AnalysisEvent::Progress(position_ms) => self.position_ms = position_ms,
+                AnalysisEvent::Finished(result) => self.result = Some(result),
+            }
+        }
+        self.result.is_none()
+    }
+
+    pub fn estimate(&self) -> Option<&SyncEstimate> {
+        self.result.as_ref()?.as_ref().ok()
+    }
+
+    /// Draws progress or the estimate.
+    pub fn show(&self, ui: &mut egui::Ui) {
+        match &self.result {
+            None => {
+                let progress = self.position_ms as f32 / self.analyzed_ms as f32;
+                ui.label(tr!("sync-analyzing"));
+                ui.add(egui::ProgressBar::new(progress.clamp(0.0, 1.0)).show_percentage());
+            }
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::LIGHT_RED, tr!("sync-failed", error = e));
+            }
+            Some(Ok(estimate)) => {
+                let message = match estimate.delay_ms {
+                    0 => tr!("sync-in-sync"),
+                    delay_ms if delay_ms > 0 => tr!("sync-audio-early", ms = delay_ms),
+                    delay_ms => tr!("sync-audio-late", ms = -delay_ms),
+                };
+                ui.label(message);
+                let confidence = tr!(
+                    "sync-confidence",
+                    score = format!("{:.1}", estimate.confidence)
+                );
+                if estimate.is_reliable() {
+                    ui.label(confidence);
+                } else {
+                    ui.colored_label(egui::Color32::YELLOW, confidence)
+                        .on_hover_text(tr!("sync-unreliable"));
+                }
+            }
+        }
+    }
+}
+
+impl Drop for SyncAnalysis {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
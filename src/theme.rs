@@ -0,0 +1,105 @@
+use crate::i18n::tr;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_ACCENT: [u8; 3] = [100, 150, 255];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ThemeMode {
+    #[default]
+    Dark,
+    Light,
+    /// Follows the light or dark preference of the operating system.
+    System,
+}
+
+impl ThemeMode {
+    pub const ALL: [ThemeMode; 3] = [ThemeMode::Dark, ThemeMode::Light, ThemeMode::System];
+
+    pub fn name(&self) -> String {
+        match self {
+            ThemeMode::Dark => tr!("theme-dark"),
+            ThemeMode::Light => tr!("theme-light"),
+            ThemeMode::System => tr!("theme-system"),
+        }
+    }
+
+    fn preference(&self) -> egui::ThemePreference {
+        match self {
+            ThemeMode::Dark => egui::ThemePreference::Dark,
+            ThemeMode::Light => egui::ThemePreference::Light,
+            ThemeMode::System => egui::ThemePreference::System,
+        }
+    }
+}
+
+/// Colors of the player's own widgets, which egui's visuals don't cover.
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    pub accent: egui::Color32,
+    pub control_bar: egui::Color32,
+    pub button: egui::Color32,
+    pub text: egui::Color32,
+    pub weak_text: egui::Color32,
+    pub faint_text: egui::Color32,
+    /// Unplayed part of the seek bar.
+    pub track: egui::Color32,
+    pub marker: egui::Color32,
+}
+
+impl Theme {
+    pub fn new(theme: egui::Theme, accent: [u8; 3]) -> Self {
+        let accent = egui::Color32::from_rgb(accent[0], accent[1], accent[2]);
+        match theme {
+            egui::Theme::Dark => Self {
+                accent,
+                control_bar: egui::Color32::from_rgba_unmultiplied(0, 0, 0, 200),
+                button: egui::Color32::from_gray(40),
+                text: egui::Color32::WHITE,
+                weak_text: egui::Color32::LIGHT_GRAY,
+                faint_text: egui::Color32::from_gray(180),
+                track: egui::Color32::from_gray(60),
+                marker: egui::Color32::from_gray(220),
+            },
+            egui::Theme::Light => Self {
+                accent,
+                control_bar: egui::Color32::from_rgba_unmultiplied(245, 245, 245, 230),
+                button: egui::Color32::from_gray(215),
+                text: egui::Color32::from_gray(20),
+                weak_text: egui::Color32::from_gray(70),
+                faint_text: egui::Color32::from_gray(100),
+                track: egui::Color32::from_gray(190),
+                marker: egui::Color32::from_gray(40),
+            },
+        }
+    }
+
+    /// Text color that stays readable on the accent color.
+    pub fn on_accent(&self) -> egui::Color32 {
+        let [r, g, b, _] = self.accent.to_array();
+        let luma = 0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32;
+        if luma > 160.0 {
+            egui::Color32::BLACK
+        } else {
+            egui::Color32::WHITE
+        }
+    }
+}
+
+/// Switches egui to the theme and uses the accent color for selections, links, sliders and
+/// keyboard focus.
+pub fn apply(ctx: &egui::Context, mode: ThemeMode, accent: [u8; 3]) {
+    ctx.set_theme(mode.preference());
+
+    for theme in [egui::Theme::Dark, egui::Theme::Light] {
+        let palette = Theme::new(theme, accent);
+        let mut visuals = theme.default_visuals();
+        visuals.selection.bg_fill = palette.accent;
+        visuals.selection.stroke.color = palette.on_accent();
+        visuals.hyperlink_color = palette.accent;
+        visuals.widgets.hovered.bg_stroke.color = palette.accent;
+        // Also the focus ring of controls reached with Tab
+        visuals.widgets.active.bg_stroke = egui::Stroke::new(2.0, palette.accent);
+        ctx.set_visuals_of(theme, visuals);
+    }
+}
@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+
+/// What the time readout in the control bar shows. Clicking it switches to the next one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TimeDisplay {
+    #[default]
+    Elapsed,
+    Remaining,
+    /// SMPTE timecode at the stream's frame rate.
+    Timecode,
+    Frame,
+}
+
+impl TimeDisplay {
+    /// The next mode; timecode and frame numbers are skipped for files without video.
+    pub fn next(self, has_video: bool) -> Self {
+        match self {
+            TimeDisplay::Elapsed => TimeDisplay::Remaining,
+            TimeDisplay::Remaining if has_video => TimeDisplay::Timecode,
+            TimeDisplay::Timecode => TimeDisplay::Frame,
+            TimeDisplay::Remaining | TimeDisplay::Frame => TimeDisplay::Elapsed,
+        }
+    }
+}
+
+/// The number of the frame shown at `ms`, counted from 0.
+pub fn frame_number(ms: i64, fps: f64) -> i64 {
+    (ms.max(0) as f64 * fps / 1000.0 + 1e-6).floor() as i64
+}
+
+/// HH:MM:SS:FF. The NTSC rates (29.97 and 59.94) use drop-frame numbering, which skips frame
+/// numbers at the start of most minutes to stay in step with the clock, marked by a semicolon.
+pub fn smpte(ms: i64, fps: f64) -> String {
+    let nominal = fps.round().max(1.0) as i64;
+    let drop_frame = nominal % 30 == 0 && (fps * 1.001 - nominal as f64).abs() < 0.01;
+    let mut frames = frame_number(ms, fps);
+
+    if drop_frame {
+        let dropped = nominal / 15;
+        let per_minute = nominal * 60 - dropped;
+        let per_ten_minutes = per_minute * 10 + dropped;
+        let tens = frames / per_ten_minutes;
+        let rest = frames % per_ten_minutes;
+        frames += dropped * 9 * tens;
+        if rest > dropped {
+            frames += dropped * ((rest - dropped) / per_minute);
+        }
+    }
+
+    let seconds = frames / nominal;
+    format!(
+        "{:02}:{:02}:{:02}{}{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        if drop_frame { ';' } else { ':' },
+        frames % nominal
+    )
+}
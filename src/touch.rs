@@ -0,0 +1,142 @@
+use eframe::egui;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Longest gap between the two taps of a double tap.
+pub const DOUBLE_TAP_TIME: Duration = Duration::from_millis(300);
+/// How far a finger may move, in points, and still count as a tap.
+const TAP_SLOP: f32 = 12.0;
+
+pub enum Gesture {
+    /// A single tap, reported once it can no longer become a double tap.
+    Tap,
+    DoubleTap(egui::Pos2),
+    /// Vertical movement of a finger since the last frame, in points.
+    Swipe {
+        start: egui::Pos2,
+        delta_y: f32,
+    },
+    /// Change of the distance between two fingers since the last frame, as a factor.
+    Pinch(f32),
+}
+
+struct Touch {
+    start: egui::Pos2,
+    last: egui::Pos2,
+    swiping: bool,
+}
+
+/// Turns the raw touch events over the video into player gestures.
+#[derive(Default)]
+pub struct TouchGestures {
+    /// Fingers down, by device and touch id.
+    touches: HashMap<(u64, u64), Touch>,
+    /// Set once a second finger joins, so lifting the fingers after a pinch isn't a tap.
+    multi_touch: bool,
+    pending_tap: Option<Instant>,
+    touched: bool,
+}
+
+impl TouchGestures {
+    /// Gestures completed this frame by touches that started inside `area`.
+    pub fn update(&mut self, input: &egui::InputState, area: egui::Rect) -> Vec<Gesture> {
+        let mut gestures = Vec::new();
+        self.touched = !self.touches.is_empty();
+
+        for event in &input.events {
+            let egui::Event::Touch {
+                device_id,
+                id,
+                phase,
+                pos,
+                ..
+            } = event
+            else {
+                continue;
+            };
+            let key = (device_id.0, id.0);
+            self.touched = true;
+
+            match phase {
+                egui::TouchPhase::Start => {
+                    if area.contains(*pos) {
+                        self.touches.insert(
+                            key,
+                            Touch {
+                                start: *pos,
+                                last: *pos,
+                                swiping: false,
+                            },
+                        );
+                        self.multi_touch |= self.touches.len() > 1;
+                    }
+                }
+                egui::TouchPhase::Move => {
+                    let Some(touch) = self.touches.get_mut(&key) else {
+                        continue;
+                    };
+                    let moved = *pos - touch.start;
+                    if !touch.swiping && moved.y.abs() > TAP_SLOP && moved.y.abs() > moved.x.abs() {
+                        touch.swiping = true;
+                    }
+                    if touch.swiping && !self.multi_touch {
+                        gestures.push(Gesture::Swipe {
+                            start: touch.start,
+                            delta_y: pos.y - touch.last.y,
+                        });
+                    }
+                    touch.last = *pos;
+                }
+                egui::TouchPhase::End | egui::TouchPhase::Cancel => {
+                    let Some(touch) = self.touches.remove(&key) else {
+                        continue;
+                    };
+                    let tapped = *phase == egui::TouchPhase::End
+                        && !self.multi_touch
+                        && !touch.swiping
+                        && (*pos - touch.start).length() <= TAP_SLOP;
+                    if tapped {
+                        match self.pending_tap.take() {
+                            Some(time) if time.elapsed() <= DOUBLE_TAP_TIME => {
+                                gestures.push(Gesture::DoubleTap(*pos));
+                            }
+                            _ => self.pending_tap = Some(Instant::now()),
+                        }
+                    }
+                    if self.touches.is_empty() {
+                        self.multi_touch = false;
+                    }
+                }
+            }
+        }
+
+        if self.touches.len() >= 2 {
+            if let Some(multi_touch) = input.multi_touch() {
+                if multi_touch.zoom_delta != 1.0 {
+                    gestures.push(Gesture::Pinch(multi_touch.zoom_delta));
+                }
+            }
+        }
+
+        if self
+            .pending_tap
+            .is_some_and(|time| time.elapsed() > DOUBLE_TAP_TIME)
+        {
+            self.pending_tap = None;
+            gestures.push(Gesture::Tap);
+        }
+
+        gestures
+    }
+
+    /// Whether a finger was down this frame. egui also reports touches as mouse clicks, which
+    /// the mouse controls ignore then.
+    pub fn touched(&self) -> bool {
+        self.touched
+    }
+
+    /// Whether a tap is waiting to find out if it is the first half of a double tap.
+    pub fn tap_pending(&self) -> bool {
+        self.pending_tap.is_some()
+    }
+}
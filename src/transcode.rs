@@ -0,0 +1,606 @@
+extern crate ffmpeg_next as ffmpeg;
+
+use crate::http;
+use ffmpeg::ffi::{AVChannelOrder, AV_TIME_BASE};
+use ffmpeg::{
+    codec, filter, format, media, picture, util::format::pixel::Pixel, ChannelLayout, Codec,
+    Dictionary, Packet, Rational, Rescale,
+};
+use ffmpeg_next::threading::Type::Frame;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const MS_TIME_BASE: Rational = Rational(1, 1000);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VideoCodec {
+    H264,
+    Hevc,
+    Av1,
+}
+
+impl VideoCodec {
+    pub const ALL: [VideoCodec; 3] = [VideoCodec::H264, VideoCodec::Hevc, VideoCodec::Av1];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            VideoCodec::H264 => "H.264",
+            VideoCodec::Hevc => "HEVC",
+            VideoCodec::Av1 => "AV1",
+        }
+    }
+
+    /// The encoders' usual default quality.
+    pub fn default_crf(&self) -> u32 {
+        match self {
+            VideoCodec::H264 => 23,
+            VideoCodec::Hevc => 28,
+            VideoCodec::Av1 => 35,
+        }
+    }
+
+    pub fn max_crf(&self) -> u32 {
+        match self {
+            VideoCodec::H264 | VideoCodec::Hevc => 51,
+            VideoCodec::Av1 => 63,
+        }
+    }
+
+    /// Encoders in order of preference. All of them understand `crf`.
+    fn encoders(&self) -> &'static [&'static str] {
+        match self {
+            VideoCodec::H264 => &["libx264"],
+            VideoCodec::Hevc => &["libx265"],
+            VideoCodec::Av1 => &["libsvtav1", "libaom-av1"],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioCodec {
+    Aac,
+    Opus,
+}
+
+impl AudioCodec {
+    pub const ALL: [AudioCodec; 2] = [AudioCodec::Aac, AudioCodec::Opus];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AudioCodec::Aac => "AAC",
+            AudioCodec::Opus => "Opus",
+        }
+    }
+
+    fn encoders(&self) -> &'static [&'static str] {
+        match self {
+            AudioCodec::Aac => &["aac"],
+            AudioCodec::Opus => &["libopus", "opus"],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateControl {
+    /// Constant quality; lower is better. The scale depends on the encoder.
+    Crf(u32),
+    /// Average bitrate in kbit/s.
+    Bitrate(usize),
+}
+
+pub struct TranscodeSettings {
+    pub filename: String,
+    pub output: PathBuf,
+    pub start_ms: i64,
+    pub end_ms: i64,
+    /// Video stream to encode; the best one is used when `None`.
+    pub video_stream: Option<usize>,
+    pub video_codec: VideoCodec,
+    pub rate_control: RateControl,
+    /// Output height; the width follows the aspect ratio. `None` keeps the size.
+    pub height: Option<u32>,
+    pub audio_codec: AudioCodec,
+    pub audio_bitrate_kbps: usize,
+}
+
+pub enum TranscodeEvent {
+    /// Encoded duration from the start of the range.
+    Progress(i64),
+    Finished(Result<(), String>),
+}
+
+/// Re-encoding running on a worker thread. Dropping the job cancels it.
+pub struct TranscodeJob {
+    pub receiver: mpsc::Receiver<TranscodeEvent>,
+    cancel: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+}
+
+impl TranscodeJob {
+    pub fn start(settings: TranscodeSettings) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let paused = Arc::new(AtomicBool::new(false));
+        let (cancel_flag, paused_flag) = (cancel.clone(), paused.clone());
+
+        thread::spawn(move || {
+            let result = transcode(&settings, &sender, &cancel_flag, &paused_flag);
+            if result.is_err() {
+                // Don't leave a truncated file behind
+                let _ = fs::remove_file(&settings.output);
+            }
+            let _ = sender.send(TranscodeEvent::Finished(result.map_err(|e| e.to_string())));
+        });
+
+        Self {
+            receiver,
+            cancel,
+            paused,
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+}
+
+impl Drop for TranscodeJob {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+fn transcode(
+    settings: &TranscodeSettings,
+    sender: &mpsc::Sender<TranscodeEvent>,
+    cancel: &AtomicBool,
+    paused: &AtomicBool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut input = http::open_input(&settings.filename)?;
+    let mut output = format::output(&settings.output)?;
+
+    let video_index = settings
+        .video_stream
+        .or_else(|| input.streams().best(media::Type::Video).map(|s| s.index()));
+    let audio_index = input
+        .streams()
+        .best(media::Type::Audio)
+        .map(|stream| stream.index());
+
+    let mut transcoders = Vec::new();
+    if let Some(stream) = video_index.and_then(|index| input.stream(index)) {
+        transcoders.push(StreamTranscoder::video(&stream, &mut output, settings)?);
+    }
+    if let Some(stream) = audio_index.and_then(|index| input.stream(index)) {
+        transcoders.push(StreamTranscoder::audio(&stream, &mut output, settings)?);
+    }
+    if transcoders.is_empty() {
+        return Err("The file has no audio or video to export".into());
+    }
+
+    output.set_metadata(input.metadata().to_owned());
+    output.write_header()?;
+    // The muxer may pick other time bases than the ones the streams were created with
+    for transcoder in &mut transcoders {
+        if let Some(stream) = output.stream(transcoder.output_index) {
+            transcoder.output_time_base = stream.time_base();
+        }
+    }
+
+    if settings.start_ms > 0 {
+        let position = settings
+            .start_ms
+            .rescale(MS_TIME_BASE, Rational(1, AV_TIME_BASE));
+        input.seek(position, ..position)?;
+    }
+
+    let mut last_progress = Instant::now();
+    for (stream, packet) in input.packets() {
+        while paused.load(Ordering::Relaxed) && !cancel.load(Ordering::Relaxed) {
+            thread::sleep(Duration::from_millis(50));
+        }
+        if cancel.load(Ordering::Relaxed) {
+            return Err("Export cancelled".into());
+        }
+
+        if let Some(transcoder) = transcoders
+            .iter_mut()
+            .find(|transcoder| transcoder.input_index == stream.index())
+        {
+            transcoder.decode(&packet, &mut output)?;
+        }
+
+        if transcoders.iter().all(|transcoder| transcoder.finished) {
+            break;
+        }
+
+        if last_progress.elapsed() > Duration::from_millis(100) {
+            last_progress = Instant::now();
+            let position_ms = transcoders
+                .iter()
+                .map(|transcoder| transcoder.position_ms)
+                .max()
+                .unwrap_or(0);
+            let _ = sender.send(TranscodeEvent::Progress(position_ms));
+        }
+    }
+
+    for transcoder in &mut transcoders {
+        transcoder.finish(&mut output)?;
+    }
+    output.write_trailer()?;
+
+    Ok(())
+}
+
+fn find_encoder(names: &[&str], kind: &str) -> Result<Codec, String> {
+    names
+        .iter()
+        .find_map(|name| ffmpeg::encoder::find_by_name(name))
+        .ok_or_else(|| {
+            format!(
+                "This FFmpeg build has no {} encoder ({})",
+                kind,
+                names.join(", ")
+            )
+        })
+}
+
+/// Even dimensions, since 4:2:0 chroma can't cover half a pixel.
+fn output_size(width: u32, height: u32, target_height: Option<u32>) -> (u32, u32) {
+    let (width, height) = match target_height {
+        Some(target) if height > 0 => (
+            (width as u64 * target as u64 / height as u64) as u32,
+            target,
+        ),
+        _ => (width, height),
+    };
+    ((width & !1).max(2), (height & !1).max(2))
+}
+
+/// Decodes one input stream, converts the frames with a filter graph and encodes them
+/// into one output stream. Frames outside the range are dropped and the output starts at 0.
+struct StreamTranscoder {
+    input_index: usize,
+    output_index: usize,
+    time_base: Rational,
+    decoder: ffmpeg::decoder::Opened,
+    filter: filter::Graph,
+    encoder: ffmpeg::encoder::Encoder,
+    encoder_time_base: Rational,
+    output_time_base: Rational,
+    start_pts: i64,
+    end_pts: i64,
+    position_ms: i64,
+    finished: bool,
+}
+
+impl StreamTranscoder {
+    fn video(
+        stream: &format::stream::Stream,
+        output: &mut format::context::Output,
+        settings: &TranscodeSettings,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut decoder_ctx = codec::Context::from_parameters(stream.parameters())?;
+        decoder_ctx.set_threading(ffmpeg::threading::Config {
+            count: num_cpus::get(),
+            kind: Frame,
+        });
+        let decoder = decoder_ctx.decoder().video()?;
+
+        let codec = find_encoder(settings.video_codec.encoders(), settings.video_codec.name())?;
+        let formats: Vec<Pixel> = codec
+            .video()?
+            .formats()
+            .map(Iterator::collect)
+            .unwrap_or_default();
+        let pixel_format = if formats.is_empty() || formats.contains(&Pixel::YUV420P) {
+            Pixel::YUV420P
+        } else {
+            formats[0]
+        };
+        let (width, height) = output_size(decoder.width(), decoder.height(), settings.height);
+        let time_base = stream.time_base();
+        let frame_rate = match stream.avg_frame_rate() {
+            rate if rate.numerator() > 0 => Some(rate),
+            _ => decoder.frame_rate(),
+        };
+
+        let mut encoder = codec::Context::new_with_codec(codec).encoder().video()?;
+        encoder.set_width(width);
+        encoder.set_height(height);
+        encoder.set_format(pixel_format);
+        encoder.set_aspect_ratio(decoder.aspect_ratio());
+        encoder.set_time_base(time_base);
+        encoder.set_frame_rate(frame_rate);
+        if output
+            .format()
+            .flags()
+            .contains(format::Flags::GLOBAL_HEADER)
+        {
+            encoder.set_flags(codec::Flags::GLOBAL_HEADER);
+        }
+
+        let mut options = Dictionary::new();
+        match settings.rate_control {
+            RateControl::Crf(crf) => options.set("crf", &crf.to_string()),
+            RateControl::Bitrate(kbps) => encoder.set_bit_rate(kbps * 1000),
+        }
+        let encoder = encoder.open_with(options)?;
+
+        let aspect_ratio = match decoder.aspect_ratio() {
+            ratio if ratio.numerator() > 0 => ratio,
+            _ => Rational(1, 1),
+        };
+        let args = format!(
+            "video_size={}x{}:pix_fmt={}:time_base={}:pixel_aspect={}",
+            decoder.width(),
+            decoder.height(),
+            ffmpeg::ffi::AVPixelFormat::from(decoder.format()) as i32,
+            time_base,
+            aspect_ratio
+        );
+        let mut graph = filter::Graph::new();
+        graph.add(
+            &filter::find("buffer").ok_or(ffmpeg::Error::FilterNotFound)?,
+            "in",
+            &args,
+        )?;
+        graph.add(
+            &filter::find("buffersink").ok_or(ffmpeg::Error::FilterNotFound)?,
+            "out",
+            "",
+        )?;
+        graph.get("out").unwrap().set_pixel_format(pixel_format);
+        graph
+            .output("in", 0)?
+            .input("out", 0)?
+            .parse(&format!("scale={}:{}", width, height))?;
+        graph.validate()?;
+
+        let mut output_stream = output.add_stream(codec)?;
+        output_stream.set_parameters(&encoder);
+        output_stream.set_time_base(time_base);
+        let output_index = output_stream.index();
+
+        Ok(Self::new(
+            stream,
+            output_index,
+            decoder.0,
+            graph,
+            encoder.0 .0,
+            time_base,
+            settings,
+        ))
+    }
+
+    fn audio(
+        stream: &format::stream::Stream,
+        output: &mut format::context::Output,
+        settings: &TranscodeSettings,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let decoder = codec::Context::from_parameters(stream.parameters())?
+            .decoder()
+            .audio()?;
+        // Unordered or custom layouts can't be described to the filter graph by a mask
+        let input_layout = match decoder.channel_layout() {
+            layout if layout.0.order == AVChannelOrder::AV_CHANNEL_ORDER_NATIVE => layout,
+            _ => ChannelLayout::default(decoder.channels() as i32),
+        };
+
+        let codec = find_encoder(settings.audio_codec.encoders(), settings.audio_codec.name())?;
+        let audio_codec = codec.audio()?;
+        let rates: Vec<i32> = audio_codec
+            .rates()
+            .map(Iterator::collect)
+            .unwrap_or_default();
+        let rate = if rates.is_empty() || rates.contains(&(decoder.rate() as i32)) {
+            decoder.rate() as i32
+        } else {
+            // Opus only runs at 48 kHz
+            *rates.iter().max().unwrap()
+        };
+        let channel_layout = audio_codec
+            .channel_layouts()
+            .map(|layouts| layouts.best(input_layout.channels()))
+            .unwrap_or(input_layout);
+        let sample_format = audio_codec
+            .formats()
+            .and_then(|mut formats| formats.next())
+            .ok_or("The audio encoder doesn't list a sample format")?;
+
+        let mut encoder = codec::Context::new_with_codec(codec).encoder().audio()?;
+        encoder.set_rate(rate);
+        encoder.set_channel_layout(channel_layout);
+        encoder.set_format(sample_format);
+        encoder.set_bit_rate(settings.audio_bitrate_kbps * 1000);
+        encoder.set_time_base(Rational(1, rate));
+        if output
+            .format()
+            .flags()
+            .contains(format::Flags::GLOBAL_HEADER)
+        {
+            encoder.set_flags(codec::Flags::GLOBAL_HEADER);
+        }
+
+        let mut options = Dictionary::new();
+        // FFmpeg's own Opus encoder is still marked experimental
+        options.set("strict", "experimental");
+        let encoder = encoder.open_with(options)?;
+
+        let time_base = stream.time_base();
+        let args = format!(
+            "time_base={}:sample_rate={}:sample_fmt={}:channel_layout=0x{:x}",
+            time_base,
+            decoder.rate(),
+            decoder.format().name(),
+            input_layout.bits()
+        );
+        let mut graph = filter::Graph::new();
+        graph.add(
+            &filter::find("abuffer").ok_or(ffmpeg::Error::FilterNotFound)?,
+            "in",
+            &args,
+        )?;
+        graph.add(
+            &filter::find("abuffersink").ok_or(ffmpeg::Error::FilterNotFound)?,
+            "out",
+            "",
+        )?;
+        {
+            let mut sink = graph.get("out").unwrap();
+            sink.set_sample_format(sample_format);
+            sink.set_channel_layout(channel_layout);
+            sink.set_sample_rate(rate as u32);
+        }
+        graph.output("in", 0)?.input("out", 0)?.parse("anull")?;
+        graph.validate()?;
+        if !codec
+            .capabilities()
+            .contains(codec::capabilities::Capabilities::VARIABLE_FRAME_SIZE)
+        {
+            graph
+                .get("out")
+                .unwrap()
+                .sink()
+                .set_frame_size(encoder.frame_size());
+        }
+
+        let mut output_stream = output.add_stream(codec)?;
+        output_stream.set_parameters(&encoder);
+        output_stream.set_time_base(Rational(1, rate));
+        let output_index = output_stream.index();
+
+        Ok(Self::new(
+            stream,
+            output_index,
+            decoder.0,
+            graph,
+            encoder.0 .0,
+            Rational(1, rate),
+            settings,
+        ))
+    }
+
+    fn new(
+        stream: &format::stream::Stream,
+        output_index: usize,
+        decoder: ffmpeg::decoder::Opened,
+        filter: filter::Graph,
+        encoder: ffmpeg::encoder::Encoder,
+        encoder_time_base: Rational,
+        settings: &TranscodeSettings,
+    ) -> Self {
+        let time_base = stream.time_base();
+        Self {
+            input_index: stream.index(),
+            output_index,
+            time_base,
+            decoder,
+            filter,
+            encoder,
+            encoder_time_base,
+            output_time_base: encoder_time_base,
+            start_pts: settings.start_ms.rescale(MS_TIME_BASE, time_base),
+            end_pts: settings.end_ms.rescale(MS_TIME_BASE, time_base),
+            position_ms: 0,
+            finished: false,
+        }
+    }
+
+    fn decode(
+        &mut self,
+        packet: &Packet,
+        output: &mut format::context::Output,
+    ) -> Result<(), ffmpeg::Error> {
+        // Damaged packets are skipped like during playback
+        if self.finished || self.decoder.send_packet(packet).is_err() {
+            return Ok(());
+        }
+        self.receive_frames(output)
+    }
+
+    fn finish(&mut self, output: &mut format::context::Output) -> Result<(), ffmpeg::Error> {
+        self.decoder.send_eof()?;
+        self.receive_frames(output)?;
+        self.filter.get("in").unwrap().source().flush()?;
+        self.receive_filtered(output)?;
+        self.encoder.send_eof()?;
+        self.write_packets(output)
+    }
+
+    fn receive_frames(
+        &mut self,
+        output: &mut format::context::Output,
+    ) -> Result<(), ffmpeg::Error> {
+        let mut decoded = unsafe { ffmpeg::Frame::empty() };
+        while self.decoder.receive_frame(&mut decoded).is_ok() {
+            let pts = match decoded.timestamp() {
+                Some(pts) => pts,
+                None => continue,
+            };
+            // Decoding started at the keyframe before the range
+            if pts < self.start_pts {
+                continue;
+            }
+            if pts >= self.end_pts {
+                self.finished = true;
+                continue;
+            }
+
+            self.position_ms = (pts - self.start_pts).rescale(self.time_base, MS_TIME_BASE);
+            decoded.set_pts(Some(pts - self.start_pts));
+            unsafe {
+                // Let the encoder place its own keyframes
+                (*decoded.as_mut_ptr()).pict_type = picture::Type::None.into();
+            }
+            self.filter.get("in").unwrap().source().add(&decoded)?;
+            self.receive_filtered(output)?;
+        }
+        Ok(())
+    }
+
+    fn receive_filtered(
+        &mut self,
+        output: &mut format::context::Output,
+    ) -> Result<(), ffmpeg::Error> {
+        let mut filtered = unsafe { ffmpeg::Frame::empty() };
+        loop {
+            let mut sink = self.filter.get("out").unwrap();
+            if sink.sink().frame(&mut filtered).is_err() {
+                return Ok(());
+            }
+            let filter_time_base = sink.sink().time_base();
+            filtered.set_pts(
+                filtered
+                    .pts()
+                    .map(|pts| pts.rescale(filter_time_base, self.encoder_time_base)),
+            );
+
+            self.encoder.send_frame(&filtered)?;
+            self.write_packets(output)?;
+        }
+    }
+
+    fn write_packets(&mut self, output: &mut format::context::Output) -> Result<(), ffmpeg::Error> {
+        let mut encoded = Packet::empty();
+        while self.encoder.receive_packet(&mut encoded).is_ok() {
+            encoded.set_stream(self.output_index);
+            encoded.rescale_ts(self.encoder_time_base, self.output_time_base);
+            encoded.write_interleaved(output)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,655 @@
+use crate::actions::Action;
+use crate::commands::{CommandSender, PlayerCommand};
+use crate::i18n::tr;
+use std::error::Error;
+
+/// The tray menu from top to bottom, `None` being a separator.
+fn menu_entries() -> [Option<(String, PlayerCommand)>; 5] {
+    [
+        Some((
+            tr!("tray-play-pause"),
+            PlayerCommand::Action(Action::TogglePause),
+        )),
+        Some((
+            tr!("tray-next"),
+            PlayerCommand::Action(Action::PlaylistNext),
+        )),
+        None,
+        Some((tr!("tray-show"), PlayerCommand::ShowWindow)),
+        Some((tr!("tray-quit"), PlayerCommand::Quit)),
+    ]
+}
+
+/// Icon in the system tray, a StatusNotifierItem on Linux and a notification area icon on
+/// Windows. Clicking it shows the window and its menu has play/pause and next. Dropping it
+/// removes the icon.
+pub struct Tray {
+    title: String,
+    icon: platform::TrayIcon,
+}
+
+impl Tray {
+    pub const SUPPORTED: bool = cfg!(any(target_os = "linux", windows));
+
+    /// The menu's commands are sent from the tray's own thread through `notify`.
+    pub fn new(notify: CommandSender, frame: &eframe::Frame) -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            title: String::new(),
+            icon: platform::TrayIcon::new(notify, frame)?,
+        })
+    }
+
+    /// Shown when hovering the icon.
+    pub fn set_title(&mut self, title: &str) {
+        if title != self.title {
+            self.title = title.to_string();
+            self.icon.set_title(title);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::{menu_entries, CommandSender, PlayerCommand};
+    use crate::actions::Action;
+    use std::collections::HashMap;
+    use std::error::Error;
+    use zbus::object_server::SignalContext;
+    use zbus::zvariant::{OwnedObjectPath, OwnedValue, Str, Structure};
+
+    const ITEM_PATH: &str = "/StatusNotifierItem";
+    const MENU_PATH: &str = "/MenuBar";
+    const ICON_NAME: &str = "applications-multimedia";
+
+    pub struct TrayIcon {
+        connection: zbus::blocking::Connection,
+    }
+
+    impl TrayIcon {
+        pub fn new(notify: CommandSender, _frame: &eframe::Frame) -> Result<Self, Box<dyn Error>> {
+            let name = format!("org.kde.StatusNotifierItem-{}-1", std::process::id());
+            let item = Item {
+                notify: notify.clone(),
+                title: String::new(),
+            };
+            let connection = zbus::blocking::connection::Builder::session()?
+                .name(name.as_str())?
+                .serve_at(ITEM_PATH, item)?
+                .serve_at(MENU_PATH, Menu { notify })?
+                .build()?;
+
+            // The panel (or the desktop's tray extension) shows the items registered here
+            connection.call_method(
+                Some("org.kde.StatusNotifierWatcher"),
+                "/StatusNotifierWatcher",
+                Some("org.kde.StatusNotifierWatcher"),
+                "RegisterStatusNotifierItem",
+                &(name.as_str(),),
+            )?;
+
+            Ok(Self { connection })
+        }
+
+        pub fn set_title(&self, title: &str) {
+            let result = self
+                .connection
+                .object_server()
+                .interface::<_, Item>(ITEM_PATH)
+                .and_then(|item| {
+                    item.get_mut().title = title.to_string();
+                    zbus::block_on(Item::new_title(item.signal_context()))?;
+                    zbus::block_on(Item::new_tool_tip(item.signal_context()))
+                });
+            if let Err(e) = result {
+                eprintln!("Error updating tray icon: {}", e);
+            }
+        }
+    }
+
+    /// Icon name, icon pixmaps (width, height, ARGB data), title and description.
+    type ToolTip = (String, Vec<(i32, i32, Vec<u8>)>, String, String);
+
+    struct Item {
+        notify: CommandSender,
+        title: String,
+    }
+
+    #[zbus::interface(name = "org.kde.StatusNotifierItem")]
+    impl Item {
+        #[zbus(property)]
+        fn category(&self) -> &str {
+            "ApplicationStatus"
+        }
+
+        #[zbus(property)]
+        fn id(&self) -> &str {
+            "avio"
+        }
+
+        #[zbus(property)]
+        fn title(&self) -> String {
+            self.title.clone()
+        }
+
+        #[zbus(property)]
+        fn status(&self) -> &str {
+            "Active"
+        }
+
+        #[zbus(property)]
+        fn icon_name(&self) -> &str {
+            ICON_NAME
+        }
+
+        #[zbus(property)]
+        fn tool_tip(&self) -> ToolTip {
+            (
+                ICON_NAME.to_string(),
+                Vec::new(),
+                self.title.clone(),
+                String::new(),
+            )
+        }
+
+        #[zbus(property)]
+        fn item_is_menu(&self) -> bool {
+            false
+        }
+
+        #[zbus(property)]
+        fn menu(&self) -> OwnedObjectPath {
+            OwnedObjectPath::try_from(MENU_PATH).expect("valid object path")
+        }
+
+        /// Left click.
+        fn activate(&self, _x: i32, _y: i32) {
+            self.notify.send(PlayerCommand::ShowWindow);
+        }
+
+        /// Middle click.
+        fn secondary_activate(&self, _x: i32, _y: i32) {
+            self.notify.send(PlayerCommand::Action(Action::TogglePause));
+        }
+
+        #[zbus(signal)]
+        async fn new_title(ctxt: &SignalContext<'_>) -> zbus::Result<()>;
+
+        #[zbus(signal)]
+        async fn new_tool_tip(ctxt: &SignalContext<'_>) -> zbus::Result<()>;
+    }
+
+    /// Item id, properties and children of a menu item.
+    type Layout = (i32, HashMap<String, OwnedValue>, Vec<OwnedValue>);
+
+    /// The context menu, served through the `com.canonical.dbusmenu` protocol. Item ids are
+    /// the positions in `menu_entries` plus one; 0 is the root.
+    struct Menu {
+        notify: CommandSender,
+    }
+
+    impl Menu {
+        fn properties(id: i32) -> HashMap<String, OwnedValue> {
+            let mut properties = HashMap::new();
+            if id == 0 {
+                properties.insert(
+                    "children-display".to_string(),
+                    OwnedValue::from(Str::from("submenu")),
+                );
+                return properties;
+            }
+
+            match menu_entries().into_iter().nth(id as usize - 1) {
+                Some(Some((label, _))) => {
+                    properties.insert("label".to_string(), OwnedValue::from(Str::from(label)));
+                }
+                Some(None) => {
+                    properties.insert("type".to_string(), OwnedValue::from(Str::from("separator")));
+                }
+                None => {}
+            }
+            properties
+        }
+    }
+
+    #[zbus::interface(name = "com.canonical.dbusmenu")]
+    impl Menu {
+        fn get_layout(
+            &self,
+            parent_id: i32,
+            _recursion_depth: i32,
+            _property_names: Vec<String>,
+        ) -> zbus::fdo::Result<(u32, Layout)> {
+            let mut children = Vec::new();
+            if parent_id == 0 {
+                for id in 1..=menu_entries().len() as i32 {
+                    let child: Layout = (id, Self::properties(id), Vec::new());
+                    let child = OwnedValue::try_from(Structure::from(child))
+                        .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
+                    children.push(child);
+                }
+            }
+
+            Ok((1, (parent_id, Self::properties(parent_id), children)))
+        }
+
+        fn get_group_properties(
+            &self,
+            ids: Vec<i32>,
+            _property_names: Vec<String>,
+        ) -> Vec<(i32, HashMap<String, OwnedValue>)> {
+            ids.into_iter()
+                .map(|id| (id, Self::properties(id)))
+                .collect()
+        }
+
+        fn get_property(&self, id: i32, name: String) -> zbus::fdo::Result<OwnedValue> {
+            Self::properties(id)
+                .remove(&name)
+                .ok_or_else(|| zbus::fdo::Error::InvalidArgs(name))
+        }
+
+        fn event(&self, id: i32, event_id: &str, _data: OwnedValue, _timestamp: u32) {
+            if event_id != "clicked" || id < 1 {
+                return;
+            }
+            if let Some(Some((_, command))) = menu_entries().into_iter().nth(id as usize - 1) {
+                self.notify.send(command);
+            }
+        }
+
+        fn event_group(&self, events: Vec<(i32, String, OwnedValue, u32)>) -> Vec<i32> {
+            for (id, event_id, data, timestamp) in events {
+                self.event(id, &event_id, data, timestamp);
+            }
+            Vec::new()
+        }
+
+        fn about_to_show(&self, _id: i32) -> bool {
+            false
+        }
+
+        fn about_to_show_group(&self, _ids: Vec<i32>) -> (Vec<i32>, Vec<i32>) {
+            (Vec::new(), Vec::new())
+        }
+
+        #[zbus(property)]
+        fn version(&self) -> u32 {
+            3
+        }
+
+        #[zbus(property)]
+        fn text_direction(&self) -> &str {
+            "ltr"
+        }
+
+        #[zbus(property)]
+        fn status(&self) -> &str {
+            "normal"
+        }
+
+        #[zbus(property)]
+        fn icon_theme_path(&self) -> Vec<String> {
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::{menu_entries, CommandSender, PlayerCommand};
+    use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+    use std::cell::RefCell;
+    use std::error::Error;
+    use std::sync::mpsc;
+    use std::{mem, ptr, thread};
+    use win32::*;
+
+    const CALLBACK_MESSAGE: u32 = WM_APP + 1;
+    const ICON_ID: u32 = 1;
+
+    thread_local! {
+        /// The command callback and the player window, for the tray thread's window procedure.
+        static STATE: RefCell<Option<(CommandSender, isize)>> = const { RefCell::new(None) };
+    }
+
+    pub struct TrayIcon {
+        /// Message-only window that receives the icon's mouse messages.
+        hwnd: isize,
+    }
+
+    impl TrayIcon {
+        pub fn new(notify: CommandSender, frame: &eframe::Frame) -> Result<Self, Box<dyn Error>> {
+            let main_window = match frame.window_handle()?.as_raw() {
+                RawWindowHandle::Win32(handle) => handle.hwnd.get(),
+                _ => return Err("The window has no Win32 handle".into()),
+            };
+
+            let (sender, receiver) = mpsc::channel();
+            thread::spawn(move || unsafe { run(notify, main_window, sender) });
+            let hwnd = receiver.recv()??;
+
+            Ok(Self { hwnd })
+        }
+
+        pub fn set_title(&self, title: &str) {
+            let mut data = icon_data(self.hwnd);
+            data.flags = NIF_TIP;
+            copy_wide(&mut data.tip, title);
+            unsafe {
+                Shell_NotifyIconW(NIM_MODIFY, &data);
+            }
+        }
+    }
+
+    impl Drop for TrayIcon {
+        fn drop(&mut self) {
+            unsafe {
+                PostMessageW(self.hwnd, WM_CLOSE, 0, 0);
+            }
+        }
+    }
+
+    /// Creates the icon and runs the message loop of the tray thread until the icon is dropped.
+    unsafe fn run(
+        notify: CommandSender,
+        main_window: isize,
+        created: mpsc::Sender<Result<isize, String>>,
+    ) {
+        let class_name = wide("AvioTray");
+        let instance = GetModuleHandleW(ptr::null());
+        let class = WndClass {
+            window_proc: Some(window_proc),
+            instance,
+            class_name: class_name.as_ptr(),
+            ..mem::zeroed()
+        };
+        // Fails harmlessly when an earlier tray icon already registered the class
+        RegisterClassW(&class);
+
+        let hwnd = CreateWindowExW(
+            0,
+            class_name.as_ptr(),
+            class_name.as_ptr(),
+            0,
+            0,
+            0,
+            0,
+            0,
+            HWND_MESSAGE,
+            0,
+            instance,
+            ptr::null_mut(),
+        );
+        if hwnd == 0 {
+            let _ = created.send(Err("CreateWindowExW failed".to_string()));
+            return;
+        }
+        STATE.with(|state| *state.borrow_mut() = Some((notify, main_window)));
+
+        let mut data = icon_data(hwnd);
+        data.flags = NIF_MESSAGE | NIF_ICON | NIF_TIP;
+        data.callback_message = CALLBACK_MESSAGE;
+        data.icon = LoadIconW(0, IDI_APPLICATION);
+        copy_wide(&mut data.tip, "Avio Player");
+        if Shell_NotifyIconW(NIM_ADD, &data) == 0 {
+            DestroyWindow(hwnd);
+            let _ = created.send(Err("Shell_NotifyIconW failed".to_string()));
+            return;
+        }
+        let _ = created.send(Ok(hwnd));
+
+        let mut message: Msg = mem::zeroed();
+        while GetMessageW(&mut message, 0, 0, 0) > 0 {
+            TranslateMessage(&message);
+            DispatchMessageW(&message);
+        }
+    }
+
+    unsafe extern "system" fn window_proc(
+        hwnd: isize,
+        message: u32,
+        wparam: usize,
+        lparam: isize,
+    ) -> isize {
+        match message {
+            CALLBACK_MESSAGE => {
+                match lparam as u32 {
+                    WM_LBUTTONUP => dispatch(PlayerCommand::ShowWindow),
+                    WM_RBUTTONUP => show_menu(hwnd),
+                    _ => {}
+                }
+                0
+            }
+            WM_DESTROY => {
+                Shell_NotifyIconW(NIM_DELETE, &icon_data(hwnd));
+                PostQuitMessage(0);
+                0
+            }
+            _ => DefWindowProcW(hwnd, message, wparam, lparam),
+        }
+    }
+
+    unsafe fn show_menu(hwnd: isize) {
+        let entries = menu_entries();
+        let menu = CreatePopupMenu();
+        for (index, entry) in entries.iter().enumerate() {
+            match entry {
+                Some((label, _)) => {
+                    let label = wide(label);
+                    AppendMenuW(menu, MF_STRING, index + 1, label.as_ptr());
+                }
+                None => {
+                    AppendMenuW(menu, MF_SEPARATOR, 0, ptr::null());
+                }
+            }
+        }
+
+        let mut cursor = Point { x: 0, y: 0 };
+        GetCursorPos(&mut cursor);
+        // Otherwise the menu doesn't close when clicking elsewhere
+        SetForegroundWindow(hwnd);
+        let chosen = TrackPopupMenu(
+            menu,
+            TPM_RETURNCMD | TPM_RIGHTBUTTON,
+            cursor.x,
+            cursor.y,
+            0,
+            hwnd,
+            ptr::null(),
+        );
+        DestroyMenu(menu);
+
+        if let Some(Some((_, command))) = (chosen as usize)
+            .checked_sub(1)
+            .and_then(|index| entries.get(index))
+        {
+            dispatch(*command);
+        }
+    }
+
+    fn dispatch(command: PlayerCommand) {
+        STATE.with(|state| {
+            if let Some((notify, main_window)) = &*state.borrow() {
+                // A hidden window may not run frames, so it is shown from here
+                if matches!(command, PlayerCommand::ShowWindow) {
+                    unsafe {
+                        ShowWindow(*main_window, SW_SHOW);
+                        ShowWindow(*main_window, SW_RESTORE);
+                        SetForegroundWindow(*main_window);
+                    }
+                }
+                notify.send(command);
+            }
+        });
+    }
+
+    fn icon_data(hwnd: isize) -> NotifyIconData {
+        NotifyIconData {
+            size: mem::size_of::<NotifyIconData>() as u32,
+            hwnd,
+            id: ICON_ID,
+            ..unsafe { mem::zeroed() }
+        }
+    }
+
+    fn wide(text: &str) -> Vec<u16> {
+        text.encode_utf16().chain(Some(0)).collect()
+    }
+
+    /// Copies as much of `text` as fits, leaving room for the terminating zero.
+    fn copy_wide(target: &mut [u16], text: &str) {
+        let mut length = 0;
+        for (slot, unit) in target
+            .iter_mut()
+            .zip(text.encode_utf16())
+            .take(target.len() - 1)
+        {
+            *slot = unit;
+            length += 1;
+        }
+        target[length] = 0;
+    }
+
+    mod win32 {
+        pub const WM_DESTROY: u32 = 0x0002;
+        pub const WM_CLOSE: u32 = 0x0010;
+        pub const WM_LBUTTONUP: u32 = 0x0202;
+        pub const WM_RBUTTONUP: u32 = 0x0205;
+        pub const WM_APP: u32 = 0x8000;
+        pub const HWND_MESSAGE: isize = -3;
+        pub const NIM_ADD: u32 = 0;
+        pub const NIM_MODIFY: u32 = 1;
+        pub const NIM_DELETE: u32 = 2;
+        pub const NIF_MESSAGE: u32 = 0x1;
+        pub const NIF_ICON: u32 = 0x2;
+        pub const NIF_TIP: u32 = 0x4;
+        pub const IDI_APPLICATION: *const u16 = 32512 as *const u16;
+        pub const MF_STRING: u32 = 0x0;
+        pub const MF_SEPARATOR: u32 = 0x800;
+        pub const TPM_RIGHTBUTTON: u32 = 0x2;
+        pub const TPM_RETURNCMD: u32 = 0x100;
+        pub const SW_SHOW: i32 = 5;
+        pub const SW_RESTORE: i32 = 9;
+
+        pub type WindowProc = unsafe extern "system" fn(isize, u32, usize, isize) -> isize;
+
+        #[repr(C)]
+        pub struct WndClass {
+            pub style: u32,
+            pub window_proc: Option<WindowProc>,
+            pub class_extra: i32,
+            pub window_extra: i32,
+            pub instance: isize,
+            pub icon: isize,
+            pub cursor: isize,
+            pub background: isize,
+            pub menu_name: *const u16,
+            pub class_name: *const u16,
+        }
+
+        #[repr(C)]
+        pub struct Point {
+            pub x: i32,
+            pub y: i32,
+        }
+
+        #[repr(C)]
+        pub struct Msg {
+            pub hwnd: isize,
+            pub message: u32,
+            pub wparam: usize,
+            pub lparam: isize,
+            pub time: u32,
+            pub point: Point,
+        }
+
+        /// NOTIFYICONDATAW
+        #[repr(C)]
+        pub struct NotifyIconData {
+            pub size: u32,
+            pub hwnd: isize,
+            pub id: u32,
+            pub flags: u32,
+            pub callback_message: u32,
+            pub icon: isize,
+            pub tip: [u16; 128],
+            pub state: u32,
+            pub state_mask: u32,
+            pub info: [u16; 256],
+            pub version: u32,
+            pub info_title: [u16; 64],
+            pub info_flags: u32,
+            pub guid: [u8; 16],
+            pub balloon_icon: isize,
+        }
+
+        #[link(name = "kernel32")]
+        extern "system" {
+            pub fn GetModuleHandleW(name: *const u16) -> isize;
+        }
+
+        #[link(name = "shell32")]
+        extern "system" {
+            pub fn Shell_NotifyIconW(message: u32, data: *const NotifyIconData) -> i32;
+        }
+
+        #[link(name = "user32")]
+        extern "system" {
+            pub fn RegisterClassW(class: *const WndClass) -> u16;
+            pub fn CreateWindowExW(
+                ex_style: u32,
+                class_name: *const u16,
+                window_name: *const u16,
+                style: u32,
+                x: i32,
+                y: i32,
+                width: i32,
+                height: i32,
+                parent: isize,
+                menu: isize,
+                instance: isize,
+                param: *mut std::ffi::c_void,
+            ) -> isize;
+            pub fn DestroyWindow(hwnd: isize) -> i32;
+            pub fn DefWindowProcW(hwnd: isize, message: u32, wparam: usize, lparam: isize)
+                -> isize;
+            pub fn GetMessageW(message: *mut Msg, hwnd: isize, min: u32, max: u32) -> i32;
+            pub fn TranslateMessage(message: *const Msg) -> i32;
+            pub fn DispatchMessageW(message: *const Msg) -> isize;
+            pub fn PostMessageW(hwnd: isize, message: u32, wparam: usize, lparam: isize) -> i32;
+            pub fn PostQuitMessage(exit_code: i32);
+            pub fn LoadIconW(instance: isize, name: *const u16) -> isize;
+            pub fn CreatePopupMenu() -> isize;
+            pub fn AppendMenuW(menu: isize, flags: u32, id: usize, text: *const u16) -> i32;
+            pub fn TrackPopupMenu(
+                menu: isize,
+                flags: u32,
+                x: i32,
+                y: i32,
+                reserved: i32,
+                hwnd: isize,
+                rect: *const std::ffi::c_void,
+            ) -> i32;
+            pub fn DestroyMenu(menu: isize) -> i32;
+            pub fn GetCursorPos(point: *mut Point) -> i32;
+            pub fn SetForegroundWindow(hwnd: isize) -> i32;
+            pub fn ShowWindow(hwnd: isize, command: i32) -> i32;
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+mod platform {
+    use super::CommandSender;
+    use std::error::Error;
+
+    pub struct TrayIcon;
+
+    impl TrayIcon {
+        pub fn new(_notify: CommandSender, _frame: &eframe::Frame) -> Result<Self, Box<dyn Error>> {
+            Err("The tray icon is not supported on this system".into())
+        }
+
+        pub fn set_title(&self, _title: &str) {}
+    }
+}
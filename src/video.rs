@@ -1,13 +1,20 @@
 extern crate ffmpeg_next as ffmpeg;
 
+use crate::bitmap_subtitles::{BitmapCue, BitmapSubtitleTrack};
+use crate::captions::Cea608Decoder;
+use crate::decode_ahead::{self, PacketReader};
+use crate::error::MediaError;
+use crate::http;
 use ffmpeg::ffi::AV_TIME_BASE;
 use ffmpeg::{
-    codec, format, frame, media, rescale,
+    codec, filter, format, frame, media, rescale,
     software::scaling::{context::Context as ScalingContext, flag::Flags},
     util::format::pixel::Pixel,
     Rational, Rescale,
 };
 use ffmpeg_next::threading::Type::Frame;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 
 const AV_TIME_BASE_RATIONAL: Rational = Rational(1, AV_TIME_BASE);
 const MS_TIME_BASE: Rational = Rational(1, 1000);
@@ -20,16 +27,99 @@ fn ms_to_timestamp(ms: i64, time_base: Rational) -> i64 {
     ms.rescale(MS_TIME_BASE, time_base)
 }
 
+/// swscale filter used when converting decoded frames to RGB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ScalingAlgorithm {
+    #[default]
+    Bilinear,
+    Bicubic,
+    Lanczos,
+    Spline,
+}
+
+impl ScalingAlgorithm {
+    pub const ALL: [ScalingAlgorithm; 4] = [
+        ScalingAlgorithm::Bilinear,
+        ScalingAlgorithm::Bicubic,
+        ScalingAlgorithm::Lanczos,
+        ScalingAlgorithm::Spline,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScalingAlgorithm::Bilinear => "Bilinear",
+            ScalingAlgorithm::Bicubic => "Bicubic",
+            ScalingAlgorithm::Lanczos => "Lanczos",
+            ScalingAlgorithm::Spline => "Spline",
+        }
+    }
+
+    fn flags(&self) -> Flags {
+        // Frames are converted at their own size, so the filter only matters for the
+        // chroma planes; swscale skips it for those unless full chroma interpolation is on
+        let full_chroma = Flags::FULL_CHR_H_INT | Flags::FULL_CHR_H_INP | Flags::ACCURATE_RND;
+        match self {
+            ScalingAlgorithm::Bilinear => Flags::BILINEAR,
+            ScalingAlgorithm::Bicubic => Flags::BICUBIC | full_chroma,
+            ScalingAlgorithm::Lanczos => Flags::LANCZOS | full_chroma,
+            ScalingAlgorithm::Spline => Flags::SPLINE | full_chroma,
+        }
+    }
+}
+
 pub struct VideoFrame {
     pub width: usize,
     pub height: usize,
     pub buffer: Vec<u8>,
+    pub timestamp_ms: i64,
+}
+
+/// Component values of one pixel in the frame as it was before RGB conversion.
+pub struct SourcePixel {
+    pub format: &'static str,
+    pub bit_depth: u8,
+    /// Component labels and values, e.g. `("Y", 235)`. Chroma is read from the
+    /// subsampled position that covers the pixel.
+    pub components: Vec<(&'static str, u32)>,
+}
+
+/// `minterpolate` graph that synthesizes in-between frames for slow motion.
+struct Interpolator {
+    graph: filter::Graph,
+    fps: f64,
+    time_base: Rational,
+}
+
+impl Interpolator {
+    /// Feeds one decoded frame and queues the frames the filter has ready. It needs a
+    /// few frames of lookahead, so the first calls after a reset queue nothing.
+    fn push(
+        &mut self,
+        decoded: &frame::Video,
+        fallback_ms: i64,
+        pending: &mut VecDeque<(frame::Video, i64)>,
+    ) -> Result<(), ffmpeg::Error> {
+        self.graph.get("in").unwrap().source().add(decoded)?;
+
+        let mut sink = self.graph.get("out").unwrap();
+        loop {
+            let mut interpolated = frame::Video::empty();
+            if sink.sink().frame(&mut interpolated).is_err() {
+                return Ok(());
+            }
+            let timestamp_ms = interpolated
+                .pts()
+                .map_or(fallback_ms, |pts| timestamp_to_ms(pts, self.time_base));
+            pending.push_back((interpolated, timestamp_ms));
+        }
+    }
 }
 
 pub struct Video {
-    input_context: format::context::Input,
+    reader: PacketReader,
     decoder: ffmpeg::decoder::Video,
     scaler: ScalingContext,
+    scaling: ScalingAlgorithm,
     stream_index: usize,
     duration_ms: i64,
     framerate: f64,
@@ -37,40 +127,66 @@ pub struct Video {
     time_base: Rational,
     video_width: usize,
     video_height: usize,
+    rgb_frame: frame::Video,
+    output_frame: VideoFrame,
+    /// The frame handed to the scaler for the current output frame.
+    source_frame: Option<frame::Video>,
+    filter_spec: Option<String>,
+    filter_graph: Option<filter::Graph>,
+    /// Playback speed that motion interpolation compensates for, if enabled.
+    slow_motion_speed: Option<f64>,
+    interpolator: Option<Interpolator>,
+    /// Frames due for display with their timestamps, waiting to be converted.
+    pending: VecDeque<(frame::Video, i64)>,
+    /// How many frames beyond the next one to decode ahead from packets already read.
+    frame_queue: usize,
     just_seeked: bool,
     seek_target_ms: i64,
     frames_decoded_since_seek: u32,
+    captions: Cea608Decoder,
+    bitmap_subtitles: Option<BitmapSubtitleTrack>,
+    /// A live stream: timestamps count from its first frame and the duration grows with it.
+    live: bool,
+    /// Timestamp of the first frame of a live stream.
+    live_start_ms: Option<i64>,
+    /// Set while the input is a file still being written, whose end is waited out.
+    following: bool,
 }
 
 impl Video {
-    pub fn new(filename: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let mut input_context = format::input(&filename)?;
+    pub fn new(filename: &str) -> Result<Self, MediaError> {
+        Self::open(filename, false, false)
+    }
+
+    /// Opens a live stream, which has no duration. `following` is for a file that is still
+    /// being written, such as the timeshift buffer.
+    pub fn new_live(filename: &str, following: bool) -> Result<Self, MediaError> {
+        Self::open(filename, true, following)
+    }
+
+    fn open(filename: &str, live: bool, following: bool) -> Result<Self, MediaError> {
+        let mut input_context = http::open_input(&filename).map_err(MediaError::Open)?;
         let video_stream = input_context
             .streams()
             .best(media::Type::Video)
-            .ok_or("Could not find video stream")?;
+            .ok_or(MediaError::NoVideoStream)?;
         let stream_index = video_stream.index();
         let time_base = video_stream.time_base();
-        let mut decoder_ctx = codec::context::Context::from_parameters(video_stream.parameters())?;
-
-        decoder_ctx.set_threading(ffmpeg::threading::Config {
-            count: num_cpus::get(),
-            kind: Frame,
-        });
-
-        let decoder = decoder_ctx.decoder().video()?;
+        let decoder = Video::create_decoder(video_stream.parameters())?;
 
         let reported_duration = timestamp_to_ms(input_context.duration(), AV_TIME_BASE_RATIONAL);
 
         let fps = Video::get_framerate(&input_context, stream_index);
         let min_reasonable_duration = (1000.0 / fps) as i64 * 10;
 
-        let duration_ms = if reported_duration < min_reasonable_duration {
+        let duration_ms = if live {
+            0
+        } else if reported_duration < min_reasonable_duration {
             println!(
                 "Reported duration too small ({}ms) → Calculating from packets...",
                 reported_duration
             );
-            Video::calculate_duration(&mut input_context, stream_index)
+            Video::calculate_duration(&mut input_context, stream_index)?
         } else {
             reported_duration
         };
@@ -78,20 +194,14 @@ impl Video {
         let video_width = decoder.width() as usize;
         let video_height = decoder.height() as usize;
 
-        let scaler = ScalingContext::get(
-            decoder.format(),
-            video_width as u32,
-            video_height as u32,
-            Pixel::RGB24,
-            video_width as u32,
-            video_height as u32,
-            Flags::BILINEAR,
-        )?;
+        let scaling = ScalingAlgorithm::default();
+        let scaler = Video::create_scaler(&decoder, scaling)?;
 
         Ok(Video {
-            input_context,
+            reader: PacketReader::start(input_context, following),
             decoder,
             scaler,
+            scaling,
             stream_index,
             duration_ms,
             framerate: fps,
@@ -99,12 +209,72 @@ impl Video {
             time_base,
             video_width,
             video_height,
+            rgb_frame: frame::Video::empty(),
+            output_frame: VideoFrame {
+                width: video_width,
+                height: video_height,
+                buffer: vec![0u8; video_width * video_height * 4],
+                timestamp_ms: 0,
+            },
+            source_frame: None,
+            filter_spec: None,
+            filter_graph: None,
+            slow_motion_speed: None,
+            interpolator: None,
+            pending: VecDeque::new(),
+            frame_queue: decode_ahead::frame_queue(),
             just_seeked: false,
             seek_target_ms: 0,
             frames_decoded_since_seek: 0,
+            captions: Cea608Decoder::new(),
+            bitmap_subtitles: None,
+            live,
+            live_start_ms: None,
+            following,
         })
     }
 
+    pub fn is_live(&self) -> bool {
+        self.live
+    }
+
+    /// Whether `next_frame` returning `None` only means the followed file has no more yet.
+    pub fn is_following(&self) -> bool {
+        self.following
+    }
+
+    /// Stops waiting for a followed file to grow, so that its end is the end of the stream.
+    pub fn stop_following(&mut self) {
+        self.following = false;
+        self.reader.stop_following();
+    }
+
+    /// Moves ahead to `target_ms` in a live stream. A network stream can't seek, so the
+    /// frames in between are decoded and dropped.
+    pub fn skip_to(&mut self, target_ms: i64) -> Result<(), MediaError> {
+        if self.following {
+            return self.seek_to_ms_accurate(target_ms);
+        }
+
+        // Frames decoded ahead are from before the target
+        self.pending.clear();
+        self.just_seeked = true;
+        self.seek_target_ms = target_ms;
+        self.frames_decoded_since_seek = 0;
+        Ok(())
+    }
+
+    fn pts_to_ms(&mut self, pts: i64) -> i64 {
+        let pts_ms = timestamp_to_ms(pts, self.time_base);
+        if !self.live {
+            return pts_ms;
+        }
+
+        let pts_ms = pts_ms - *self.live_start_ms.get_or_insert(pts_ms);
+        self.duration_ms = self.duration_ms.max(pts_ms);
+        pts_ms
+    }
+
     pub fn get_current_timestamp_ms(&self) -> i64 {
         self.current_timestamp_ms
     }
@@ -113,64 +283,370 @@ impl Video {
         self.duration_ms
     }
 
+    /// Frames per second of video time that `next_frame` returns, which is raised while
+    /// motion interpolation is active.
     pub fn get_frame_rate(&self) -> f64 {
+        self.interpolator
+            .as_ref()
+            .map_or(self.framerate, |interpolator| interpolator.fps)
+    }
+
+    /// Frames per second of the stream itself, also while interpolating.
+    pub fn stream_frame_rate(&self) -> f64 {
         self.framerate
     }
 
-    pub fn seek(&mut self, target_ms: i64) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn seek(&mut self, target_ms: i64) -> Result<(), MediaError> {
         self.seek_to_ms_accurate(target_ms)
     }
 
-    pub fn next_frame(&mut self) -> Option<Result<VideoFrame, Box<dyn std::error::Error>>> {
-        loop {
-            let mut decoded = frame::Video::empty();
-            match self.decoder.receive_frame(&mut decoded) {
-                Ok(_) => {
-                    if let Some(pts) = decoded.pts() {
-                        let pts_ms = timestamp_to_ms(pts, self.time_base);
-
-                        if self.just_seeked {
-                            self.frames_decoded_since_seek += 1;
-
-                            if self.frames_decoded_since_seek > 300 {
-                                self.current_timestamp_ms = pts_ms;
-                                self.just_seeked = false;
-                                return Some(self.convert_frame(decoded));
-                            }
-
-                            if pts_ms == 0 {
-                            } else if pts_ms >= self.seek_target_ms {
-                                self.current_timestamp_ms = pts_ms;
-                                self.just_seeked = false;
-                                return Some(self.convert_frame(decoded));
-                            } else {
-                                self.current_timestamp_ms = pts_ms;
-                            }
-                        } else {
-                            self.current_timestamp_ms = pts_ms;
-                            return Some(self.convert_frame(decoded));
-                        }
-                    } else {
-                        if !self.just_seeked {
-                            return Some(self.convert_frame(decoded));
-                        }
-                    }
+    /// Jumps to the keyframe at or before `target_ms` and shows it as it is, instead of
+    /// decoding on to the exact frame. Quick enough to follow a dragged seek bar.
+    pub fn seek_keyframe(&mut self, target_ms: i64) -> Result<(), MediaError> {
+        self.seek_to_ms_accurate(target_ms)?;
+        self.just_seeked = false;
+        Ok(())
+    }
+
+    /// Whether the video carries CEA-608 closed captions; only known once some were decoded.
+    pub fn has_captions(&self) -> bool {
+        self.captions.found()
+    }
+
+    pub fn caption_text(&self) -> Option<String> {
+        self.captions.text()
+    }
+
+    pub fn subtitle_stream_index(&self) -> Option<usize> {
+        self.bitmap_subtitles
+            .as_ref()
+            .map(|track| track.stream_index())
+    }
+
+    /// Starts decoding a PGS or VobSub stream alongside the video, or stops with `None`.
+    /// Cues that started before the current position show up from the next one on.
+    pub fn select_subtitle_stream(
+        &mut self,
+        stream_index: Option<usize>,
+    ) -> Result<(), MediaError> {
+        self.bitmap_subtitles = match stream_index {
+            Some(index) => {
+                let stream = self
+                    .reader
+                    .stream(index)
+                    .ok_or(MediaError::InvalidStream(index))?;
+                Some(BitmapSubtitleTrack::from_parameters(
+                    index,
+                    stream.parameters.clone(),
+                    stream.time_base,
+                    self.video_width,
+                    self.video_height,
+                )?)
+            }
+            None => None,
+        };
+        Ok(())
+    }
+
+    /// The bitmap subtitle to show at the given time and the canvas size it is positioned on.
+    pub fn bitmap_subtitle_at(&mut self, time_ms: i64) -> Option<(&BitmapCue, (usize, usize))> {
+        let track = self.bitmap_subtitles.as_mut()?;
+        let canvas_size = track.canvas_size();
+        track.cue_at(time_ms).map(|cue| (cue, canvas_size))
+    }
+
+    pub fn stream_index(&self) -> usize {
+        self.stream_index
+    }
+
+    /// Switches decoding to another video stream (e.g. a different angle) and
+    /// continues from the current position.
+    pub fn select_stream(&mut self, stream_index: usize) -> Result<(), MediaError> {
+        if stream_index == self.stream_index {
+            return Ok(());
+        }
+
+        let stream = self
+            .reader
+            .stream(stream_index)
+            .ok_or(MediaError::InvalidStream(stream_index))?;
+        if stream.parameters.medium() != media::Type::Video {
+            return Err(MediaError::InvalidStream(stream_index));
+        }
+        let time_base = stream.time_base;
+        let framerate = Video::framerate_of(stream.avg_frame_rate);
+        let decoder = Video::create_decoder(stream.parameters.clone())?;
+
+        self.scaler = Video::create_scaler(&decoder, self.scaling)?;
+        self.video_width = decoder.width() as usize;
+        self.video_height = decoder.height() as usize;
+        self.decoder = decoder;
+        self.stream_index = stream_index;
+        self.time_base = time_base;
+        self.framerate = framerate;
+
+        self.rgb_frame = frame::Video::empty();
+        self.source_frame = None;
+        self.output_frame = VideoFrame {
+            width: self.video_width,
+            height: self.video_height,
+            buffer: vec![0u8; self.video_width * self.video_height * 4],
+            timestamp_ms: self.current_timestamp_ms,
+        };
+
+        if let Some(spec) = &self.filter_spec {
+            self.filter_graph = Some(self.create_filter_graph(spec)?);
+        }
+
+        self.seek_to_ms_accurate(self.current_timestamp_ms)
+    }
+
+    /// Interpolates in-between frames with `minterpolate` while playing at `speed`
+    /// (below 1.0), so that slow motion keeps the original frame rate on screen.
+    /// `None` turns it off. This is expensive, especially at high resolutions.
+    pub fn set_slow_motion(&mut self, speed: Option<f64>) -> Result<(), MediaError> {
+        let speed = speed.filter(|speed| *speed < 1.0);
+        if speed == self.slow_motion_speed {
+            return Ok(());
+        }
+
+        self.slow_motion_speed = speed;
+        self.reset_interpolator()
+    }
+
+    fn reset_interpolator(&mut self) -> Result<(), MediaError> {
+        self.interpolator = None;
+        self.pending.clear();
+
+        if let Some(speed) = self.slow_motion_speed {
+            let fps = self.framerate / speed;
+            let mut graph = self.create_filter_graph(&format!(
+                "minterpolate=fps={:.3}:mi_mode=mci:mc_mode=aobmc:me_mode=bidir:vsbmc=1",
+                fps
+            ))?;
+            let time_base = graph.get("out").unwrap().sink().time_base();
+            self.interpolator = Some(Interpolator {
+                graph,
+                fps,
+                time_base,
+            });
+        }
+        Ok(())
+    }
+
+    /// Hands a frame that is due for display to the interpolator, or straight to the
+    /// queue of frames to show.
+    fn queue_frame(&mut self, decoded: frame::Video) {
+        let fallback_ms = self.current_timestamp_ms;
+        if let Some(interpolator) = &mut self.interpolator {
+            match interpolator.push(&decoded, fallback_ms, &mut self.pending) {
+                Ok(()) => return,
+                Err(e) => {
+                    eprintln!("Motion interpolation failed, turning it off: {}", e);
+                    self.interpolator = None;
+                    self.slow_motion_speed = None;
                 }
-                Err(_) => match self.input_context.packets().next() {
-                    Some((stream, packet)) => {
-                        if stream.index() == self.stream_index {
-                            if let Err(e) = self.decoder.send_packet(&packet) {
-                                return Some(Err(Box::new(e)));
-                            }
-                        }
-                    }
-                    None => return None,
-                },
             }
         }
+        self.pending.push_back((decoded, fallback_ms));
+    }
+
+    /// Runs decoded frames through the given FFmpeg filters (e.g. `hqdn3d`, `deband`)
+    /// before they are converted for display. An empty list disables filtering.
+    pub fn set_filters(&mut self, filters: &[&str]) -> Result<(), MediaError> {
+        if filters.is_empty() {
+            self.filter_spec = None;
+            self.filter_graph = None;
+            return Ok(());
+        }
+
+        let spec = filters.join(",");
+        self.filter_graph = Some(self.create_filter_graph(&spec)?);
+        self.filter_spec = Some(spec);
+        Ok(())
+    }
+
+    fn create_filter_graph(&self, spec: &str) -> Result<filter::Graph, ffmpeg::Error> {
+        let aspect_ratio = match self.decoder.aspect_ratio() {
+            ratio if ratio.numerator() > 0 => ratio,
+            _ => Rational(1, 1),
+        };
+        let args = format!(
+            "video_size={}x{}:pix_fmt={}:time_base={}:pixel_aspect={}",
+            self.decoder.width(),
+            self.decoder.height(),
+            ffmpeg::ffi::AVPixelFormat::from(self.decoder.format()) as i32,
+            self.time_base,
+            aspect_ratio
+        );
+
+        let mut graph = filter::Graph::new();
+        graph.add(
+            &filter::find("buffer").ok_or(ffmpeg::Error::FilterNotFound)?,
+            "in",
+            &args,
+        )?;
+        graph.add(
+            &filter::find("buffersink").ok_or(ffmpeg::Error::FilterNotFound)?,
+            "out",
+            "",
+        )?;
+        graph.output("in", 0)?.input("out", 0)?.parse(spec)?;
+        graph.validate()?;
+
+        Ok(graph)
+    }
+
+    fn apply_filters(&mut self, decoded: frame::Video) -> Result<frame::Video, ffmpeg::Error> {
+        let graph = match &mut self.filter_graph {
+            Some(graph) => graph,
+            None => return Ok(decoded),
+        };
+
+        graph.get("in").unwrap().source().add(&decoded)?;
+
+        let mut filtered = frame::Video::empty();
+        graph.get("out").unwrap().sink().frame(&mut filtered)?;
+        Ok(filtered)
+    }
+
+    fn create_decoder(parameters: codec::Parameters) -> Result<ffmpeg::decoder::Video, MediaError> {
+        let codec_id = parameters.id();
+        let mut decoder_ctx = codec::context::Context::from_parameters(parameters)?;
+
+        decoder_ctx.set_threading(ffmpeg::threading::Config {
+            count: num_cpus::get(),
+            kind: Frame,
+        });
+
+        decoder_ctx
+            .decoder()
+            .video()
+            .map_err(|e| MediaError::decoder(e, codec_id))
+    }
+
+    fn create_scaler(
+        decoder: &ffmpeg::decoder::Video,
+        scaling: ScalingAlgorithm,
+    ) -> Result<ScalingContext, ffmpeg::Error> {
+        ScalingContext::get(
+            decoder.format(),
+            decoder.width(),
+            decoder.height(),
+            Pixel::RGB24,
+            decoder.width(),
+            decoder.height(),
+            scaling.flags(),
+        )
+    }
+
+    /// Takes effect from the next frame on.
+    pub fn set_scaling(&mut self, scaling: ScalingAlgorithm) -> Result<(), MediaError> {
+        if scaling == self.scaling {
+            return Ok(());
+        }
+
+        self.scaler = Video::create_scaler(&self.decoder, scaling)?;
+        self.scaling = scaling;
+        Ok(())
+    }
+
+    pub fn next_frame(&mut self) -> Option<Result<&VideoFrame, MediaError>> {
+        while self.pending.is_empty() {
+            match self.decode(true) {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+
+        // Decode ahead from the packets already read, so that frames are ready to show
+        // when reading stalls
+        while self.pending.len() <= self.frame_queue {
+            match self.decode(false) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(e) => {
+                    eprintln!("Error decoding ahead: {}", e);
+                    break;
+                }
+            }
+        }
+
+        let (frame, timestamp_ms) = self.pending.pop_front()?;
+        self.current_timestamp_ms = timestamp_ms;
+        Some(self.convert_frame(frame))
     }
 
-    fn calculate_duration(input_context: &mut format::context::Input, stream_index: usize) -> i64 {
+    /// Takes one frame from the decoder, or else feeds it the next packet. Returns false
+    /// at the end of the stream, or without `wait`, when no packet has been read yet.
+    fn decode(&mut self, wait: bool) -> Result<bool, MediaError> {
+        let mut decoded = frame::Video::empty();
+        if self.decoder.receive_frame(&mut decoded).is_ok() {
+            self.handle_decoded(decoded);
+            return Ok(true);
+        }
+
+        let Some(packet) = self.reader.next(wait) else {
+            return Ok(false);
+        };
+        if packet.stream() == self.stream_index {
+            self.decoder.send_packet(&packet)?;
+        } else if let Some(track) = self
+            .bitmap_subtitles
+            .as_mut()
+            .filter(|track| track.stream_index() == packet.stream())
+        {
+            // A broken subtitle packet shouldn't stop the video
+            if let Err(e) = track.decode(&packet) {
+                eprintln!("Error decoding subtitle packet: {}", e);
+            }
+        }
+        Ok(true)
+    }
+
+    fn handle_decoded(&mut self, decoded: frame::Video) {
+        // Captions are embedded in the frames rather than in a stream of their own
+        if let Some(cc_data) = decoded.side_data(frame::side_data::Type::A53CC) {
+            self.captions.feed(cc_data.data());
+        }
+
+        if let Some(pts) = decoded.pts() {
+            let pts_ms = self.pts_to_ms(pts);
+
+            if self.just_seeked {
+                self.frames_decoded_since_seek += 1;
+
+                if self.frames_decoded_since_seek > 300 {
+                    self.current_timestamp_ms = pts_ms;
+                    self.just_seeked = false;
+                    self.queue_frame(decoded);
+                    return;
+                }
+
+                if pts_ms == 0 {
+                } else if pts_ms >= self.seek_target_ms {
+                    self.current_timestamp_ms = pts_ms;
+                    self.just_seeked = false;
+                    self.queue_frame(decoded);
+                } else {
+                    self.current_timestamp_ms = pts_ms;
+                }
+            } else {
+                self.current_timestamp_ms = pts_ms;
+                self.queue_frame(decoded);
+            }
+        } else {
+            if !self.just_seeked {
+                self.queue_frame(decoded);
+            }
+        }
+    }
+
+    fn calculate_duration(
+        input_context: &mut format::context::Input,
+        stream_index: usize,
+    ) -> Result<i64, ffmpeg::Error> {
         let mut last_pts = 0;
         let time_base = input_context
             .streams()
@@ -186,57 +662,148 @@ impl Video {
             }
         }
 
-        input_context.seek(0, ..0).unwrap();
+        input_context.seek(0, ..0)?;
 
-        last_pts
+        Ok(last_pts)
     }
 
     fn get_framerate(input_context: &format::context::Input, stream_index: usize) -> f64 {
         let stream = input_context.streams().nth(stream_index);
 
         match stream {
-            Some(s) => {
-                let avg_frame_rate = s.avg_frame_rate();
-
-                if avg_frame_rate.denominator() != 0 {
-                    return avg_frame_rate.numerator() as f64 / avg_frame_rate.denominator() as f64;
-                }
-
-                30.0
-            }
+            Some(s) => Video::framerate_of(s.avg_frame_rate()),
             None => 30.0,
         }
     }
 
+    fn framerate_of(avg_frame_rate: Rational) -> f64 {
+        if avg_frame_rate.denominator() != 0 {
+            return avg_frame_rate.numerator() as f64 / avg_frame_rate.denominator() as f64;
+        }
+
+        30.0
+    }
+
     #[inline]
-    fn convert_frame(
-        &mut self,
-        decoded: frame::Video,
-    ) -> Result<VideoFrame, Box<dyn std::error::Error>> {
-        let mut rgb_frame = frame::Video::empty();
-        self.scaler.run(&decoded, &mut rgb_frame)?;
+    fn convert_frame(&mut self, decoded: frame::Video) -> Result<&VideoFrame, MediaError> {
+        let decoded = self.apply_filters(decoded)?;
+
+        // Filters may hand back a different pixel format than the decoder produces
+        let input = self.scaler.input();
+        if decoded.format() != input.format
+            || decoded.width() != input.width
+            || decoded.height() != input.height
+        {
+            self.scaler.cached(
+                decoded.format(),
+                decoded.width(),
+                decoded.height(),
+                Pixel::RGB24,
+                self.video_width as u32,
+                self.video_height as u32,
+                self.scaling.flags(),
+            );
+        }
 
-        let mut buffer = vec![0u8; self.video_width * self.video_height * 4];
-        let data = rgb_frame.data(0);
-        let line_size = rgb_frame.stride(0);
+        self.scaler.run(&decoded, &mut self.rgb_frame)?;
 
-        self.convert_rgb_to_rgba_fast(data, line_size, &mut buffer);
+        let data = self.rgb_frame.data(0);
+        let line_size = self.rgb_frame.stride(0);
 
-        Ok(VideoFrame {
-            width: self.video_width,
-            height: self.video_height,
-            buffer,
+        Self::convert_rgb_to_rgba_fast(
+            data,
+            line_size,
+            &mut self.output_frame.buffer,
+            self.video_width,
+            self.video_height,
+        );
+        self.output_frame.timestamp_ms = self.current_timestamp_ms;
+        self.source_frame = Some(decoded);
+
+        Ok(&self.output_frame)
+    }
+
+    /// Reads the pixel at `x`, `y` (in output frame coordinates) from the frame the
+    /// current output frame was converted from, in its own pixel format.
+    pub fn source_pixel(&self, x: usize, y: usize) -> Option<SourcePixel> {
+        let frame = self.source_frame.as_ref()?;
+        let descriptor = frame.format().descriptor()?;
+        let flags = unsafe { (*descriptor.as_ptr()).flags };
+        if flags & ffmpeg::ffi::AV_PIX_FMT_FLAG_HWACCEL as u64 != 0
+            || x >= self.video_width
+            || y >= self.video_height
+        {
+            return None;
+        }
+
+        // Filters may have resized the frame
+        let frame_x = x * frame.width() as usize / self.video_width;
+        let frame_y = y * frame.height() as usize / self.video_height;
+
+        let component_count = descriptor.nb_components() as usize;
+        let labels: &[&'static str] = if flags & ffmpeg::ffi::AV_PIX_FMT_FLAG_PAL as u64 != 0 {
+            &["Index"]
+        } else if flags & ffmpeg::ffi::AV_PIX_FMT_FLAG_RGB as u64 != 0 {
+            &["R", "G", "B", "A"]
+        } else if component_count <= 2 {
+            &["Y", "A"]
+        } else {
+            &["Y", "U", "V", "A"]
+        };
+        let subsampled = labels[0] == "Y" && component_count >= 3;
+
+        let components = labels
+            .iter()
+            .take(component_count)
+            .enumerate()
+            .map(|(component, label)| {
+                let (mut cx, mut cy) = (frame_x, frame_y);
+                if subsampled && (component == 1 || component == 2) {
+                    cx >>= descriptor.log2_chroma_w();
+                    cy >>= descriptor.log2_chroma_h();
+                }
+
+                let mut value = 0u32;
+                unsafe {
+                    let frame = frame.as_ptr();
+                    ffmpeg::ffi::av_read_image_line2(
+                        &mut value as *mut u32 as *mut std::ffi::c_void,
+                        (*frame).data.as_ptr() as *mut *const u8,
+                        (*frame).linesize.as_ptr(),
+                        descriptor.as_ptr(),
+                        cx as i32,
+                        cy as i32,
+                        component as i32,
+                        1,
+                        0,
+                        4,
+                    );
+                }
+                (*label, value)
+            })
+            .collect();
+
+        Some(SourcePixel {
+            format: descriptor.name(),
+            bit_depth: unsafe { (*descriptor.as_ptr()).comp[0].depth } as u8,
+            components,
         })
     }
 
     #[inline]
-    fn convert_rgb_to_rgba_fast(&self, src: &[u8], line_size: usize, dst: &mut [u8]) {
-        for y in 0..self.video_height {
-            for x in (0..self.video_width).step_by(8) {
-                let chunk_size = std::cmp::min(8, self.video_width - x);
+    fn convert_rgb_to_rgba_fast(
+        src: &[u8],
+        line_size: usize,
+        dst: &mut [u8],
+        width: usize,
+        height: usize,
+    ) {
+        for y in 0..height {
+            for x in (0..width).step_by(8) {
+                let chunk_size = std::cmp::min(8, width - x);
                 for i in 0..chunk_size {
                     let src_idx = y * line_size + (x + i) * 3;
-                    let dst_idx = (y * self.video_width + x + i) * 4;
+                    let dst_idx = (y * width + x + i) * 4;
 
                     if src_idx + 2 < src.len() && dst_idx + 3 < dst.len() {
                         dst[dst_idx] = src[src_idx];
@@ -249,12 +816,20 @@ impl Video {
         }
     }
 
-    fn seek_to_ms_accurate(&mut self, target_ms: i64) -> Result<(), Box<dyn std::error::Error>> {
+    fn seek_to_ms_accurate(&mut self, target_ms: i64) -> Result<(), MediaError> {
         self.decoder.flush();
 
-        let target_ts = ms_to_timestamp(target_ms, rescale::TIME_BASE);
+        let file_ms = target_ms + self.live_start_ms.unwrap_or(0);
+        let target_ts = ms_to_timestamp(file_ms, rescale::TIME_BASE);
+
+        self.reader.seek(target_ts);
+        self.captions.reset();
+        if let Some(track) = &mut self.bitmap_subtitles {
+            track.flush();
+        }
 
-        self.input_context.seek(target_ts, ..target_ts)?;
+        // Interpolating across the jump would blend unrelated frames
+        self.reset_interpolator()?;
 
         self.just_seeked = true;
         self.seek_target_ms = target_ms;
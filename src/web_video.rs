@@ -0,0 +1,149 @@
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+/// A web page resolved by yt-dlp to a stream FFmpeg can open.
+pub struct ResolvedVideo {
+    pub stream_url: String,
+    pub title: Option<String>,
+}
+
+/// The video ID of a YouTube watch, short or youtu.be link.
+pub fn youtube_id(url: &str) -> Option<String> {
+    let (_, rest) = url.split_once("://")?;
+    let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+    let host = host.trim_start_matches("www.").trim_start_matches("m.");
+
+    let id = match host {
+        "youtu.be" => path.split(['?', '#']).next()?,
+        "youtube.com" | "music.youtube.com" => {
+            if let Some(query) = path.strip_prefix("watch?") {
+                query
+                    .split(['&', '#'])
+                    .find_map(|param| param.strip_prefix("v="))?
+            } else {
+                let id = path
+                    .strip_prefix("shorts/")
+                    .or_else(|| path.strip_prefix("live/"))
+                    .or_else(|| path.strip_prefix("embed/"))?;
+                id.split(['?', '#', '/']).next()?
+            }
+        }
+        _ => return None,
+    };
+
+    // IDs are 11 characters of base64url
+    let valid = id.len() == 11
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    valid.then(|| id.to_string())
+}
+
+/// A page being resolved by yt-dlp on a worker thread. Dropping it stops yt-dlp.
+pub struct Resolving {
+    receiver: mpsc::Receiver<Result<ResolvedVideo, String>>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl Resolving {
+    pub fn start(url: &str, extra_args: Vec<String>) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancel_flag = cancel.clone();
+        let url = url.to_string();
+
+        thread::spawn(move || {
+            let result = resolve(&url, &extra_args, &cancel_flag);
+            let _ = sender.send(result.map_err(|e| e.to_string()));
+        });
+
+        Self { receiver, cancel }
+    }
+
+    /// Returns the stream once yt-dlp has finished.
+    pub fn update(&self) -> Option<Result<ResolvedVideo, String>> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl Drop for Resolving {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Runs `yt-dlp` to get the best format that has both video and audio in one stream.
+/// Blocks until yt-dlp exits, which takes a few seconds, or kills it once `cancel` is set.
+fn resolve(
+    url: &str,
+    extra_args: &[String],
+    cancel: &AtomicBool,
+) -> Result<ResolvedVideo, Box<dyn std::error::Error>> {
+    let mut child = Command::new("yt-dlp")
+        .args(extra_args)
+        .args([
+            "--no-playlist",
+            "--format",
+            "b",
+            "--print",
+            "title",
+            "--print",
+            "urls",
+            url,
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Cannot run yt-dlp (is it installed?): {}", e))?;
+
+    // Read alongside, so that yt-dlp doesn't stall on a full pipe
+    let read = |pipe: Option<Box<dyn Read + Send>>| {
+        thread::spawn(move || {
+            let mut text = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut text);
+            }
+            text
+        })
+    };
+    let stdout = read(child.stdout.take().map(|pipe| Box::new(pipe) as _));
+    let stderr = read(child.stderr.take().map(|pipe| Box::new(pipe) as _));
+
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if cancel.load(Ordering::Relaxed) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err("Cancelled".into());
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+
+    if !status.success() {
+        let stderr = String::from_utf8_lossy(&stderr);
+        let message = stderr
+            .lines()
+            .rfind(|line| line.starts_with("ERROR"))
+            .unwrap_or("yt-dlp failed");
+        return Err(message.to_string().into());
+    }
+
+    let stdout = String::from_utf8_lossy(&stdout);
+    let mut lines = stdout.lines().filter(|line| !line.trim().is_empty());
+    let title = lines.next().map(|title| title.trim().to_string());
+    let stream_url = lines
+        .next()
+        .ok_or("yt-dlp returned no stream URL")?
+        .trim()
+        .to_string();
+
+    Ok(ResolvedVideo { stream_url, title })
+}